@@ -1,6 +1,37 @@
-
+use std::env;
+use std::path::PathBuf;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    tonic_build::compile_protos("proto/exam.proto")?;
+    let out_dir = PathBuf::from(env::var("OUT_DIR")?);
+
+    // The descriptor set lets the server answer reflection queries;
+    // serde lets the CLI and HTTP gateway speak JSON, with absent fields left at their defaults.
+    // Well-known types are generated rather than taken from prost-types, so they get serde too;
+    // their upstream comments hold examples in other languages, which rustdoc would run
+    let mut prost_config = tonic_build::Config::new();
+    prost_config.disable_comments([".google.protobuf"]);
+
+    tonic_build::configure()
+        .compile_well_known_types(true)
+        .type_attribute(".", "#[derive(serde::Serialize, serde::Deserialize)]")
+        .message_attribute(".", "#[serde(default)]")
+        .file_descriptor_set_path(out_dir.join("exam_descriptor.bin"))
+        .compile_protos_with_config(
+            prost_config,
+            &[
+                "proto/exam/v1/exam.proto",
+                "proto/exam/v2/exam.proto",
+                "proto/exam_admin.proto",
+                "proto/student.proto",
+                "proto/appeal.proto",
+                "proto/webhook.proto",
+                "proto/snapshot.proto",
+                "proto/admin.proto",
+                "proto/exam_session.proto",
+                "proto/grader.proto",
+                "proto/exam_sync.proto",
+            ],
+            &["proto"],
+        )?;
     Ok(())
-}
\ No newline at end of file
+}
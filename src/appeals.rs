@@ -0,0 +1,293 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tonic::{Request, Response, Status};
+use tracing::info;
+
+use crate::appeal::appeal_service_server::AppealService;
+use crate::appeal::{
+    Appeal, AppealState, FileAppealRequest, ListAppealsRequest, ListAppealsResponse, ResolveAppealRequest,
+    ReviewAppealRequest,
+};
+use crate::audit::WriteContext;
+use crate::auth::{Identity, Role};
+use crate::deadline::{self, Deadline, DEFAULT_MAX_PROCESSING};
+use crate::errors::{invalid_field, missing_dependency, not_found};
+use crate::exam_service::ResultStatus;
+use crate::key::{ExamId, ResultKey, StudentId};
+use crate::schedule::now_ms;
+use crate::server::{result_not_found, ExamServiceImpl};
+use crate::store::ExamStore;
+use crate::visibility::Visibility;
+
+// Implements AppealService. Approved appeals correct the result through
+// the exam service, so the change is graded, audited and sent to watchers
+// like any other correction.
+#[derive(Debug)]
+pub struct AppealServiceImpl<S> {
+    results: Arc<ExamServiceImpl<S>>,
+    max_processing: Duration,
+}
+
+// Clones share the exam service, so a resolution can run in a task of its own.
+impl<S> Clone for AppealServiceImpl<S> {
+    fn clone(&self) -> Self {
+        Self {
+            results: self.results.clone(),
+            max_processing: self.max_processing,
+        }
+    }
+}
+
+impl<S: ExamStore> AppealServiceImpl<S> {
+    pub fn new(results: Arc<ExamServiceImpl<S>>) -> Self {
+        Self {
+            results,
+            max_processing: DEFAULT_MAX_PROCESSING,
+        }
+    }
+
+    // Replaces the server-side processing limit.
+    pub fn with_max_processing_time(mut self, limit: Duration) -> Self {
+        self.max_processing = limit;
+        self
+    }
+
+    // The client's deadline for `request`, capped by the processing limit.
+    fn deadline<T>(&self, request: &Request<T>) -> Result<Deadline, Status> {
+        Deadline::from_request(request, Some(self.max_processing))
+    }
+
+    // The store of the caller's institution, where its appeals are filed.
+    fn store(&self, identity: &Identity) -> Result<&S, Status> {
+        self.results.tenants().for_caller(identity).map(|store| store.as_ref())
+    }
+
+    // Looks up an appeal, failing unless it is in state `expected`.
+    async fn appeal_in_state(&self, identity: &Identity, id: i64, expected: AppealState) -> Result<Appeal, Status> {
+        let appeal = self
+            .store(identity)?
+            .get_appeal(id)
+            .await?
+            .ok_or_else(|| appeal_not_found(id))?;
+
+        if appeal.state() != expected {
+            return Err(wrong_state(&appeal, expected));
+        }
+        Ok(appeal)
+    }
+}
+
+fn appeal_not_found(id: i64) -> Status {
+    not_found("appeal", id, format!("No appeal found with ID {}", id))
+}
+
+// FAILED_PRECONDITION for a transition out of the wrong state, e.g. resolving an OPEN appeal.
+fn wrong_state(appeal: &Appeal, expected: AppealState) -> Status {
+    missing_dependency(
+        expected.as_str_name(),
+        format!("appeal/{}", appeal.appeal_id),
+        format!(
+            "Appeal {} is {}, not {}",
+            appeal.appeal_id,
+            appeal.state().as_str_name(),
+            expected.as_str_name()
+        ),
+    )
+}
+
+// Reported when `update_appeal` finds the appeal moved on since it was read.
+fn state_changed(id: i64) -> Status {
+    Status::aborted(format!("Appeal {} changed while it was being updated; re-read it and retry", id))
+}
+
+#[tonic::async_trait]
+impl<S: ExamStore> AppealService for AppealServiceImpl<S> {
+    // Files an appeal against one published result. Students may appeal their
+    // own results once grades are released; staff may file for any student.
+    async fn file_appeal(&self, request: Request<FileAppealRequest>) -> Result<Response<Appeal>, Status> {
+        info!(request = ?request.get_ref(), "file appeal");
+
+        let deadline = self.deadline(&request)?;
+        deadline
+            .run(async move {
+                let identity = Identity::from_request(&request)?;
+                let req = request.into_inner();
+                let key = ResultKey::parse(&req.student_id, &req.exam_id)?;
+                identity.require_read(key.student_id.as_str())?;
+
+                if req.reason.trim().is_empty() {
+                    return Err(invalid_field("reason", "is required"));
+                }
+
+                // Students cannot tell an unreleased result from a missing one
+                let mut visibility = Visibility::new(self.store(&identity)?, identity.role);
+                let result = match self.store(&identity)?.get(&key).await? {
+                    Some(result) if visibility.shows(&result).await? => result,
+                    _ => return Err(result_not_found(&key)),
+                };
+
+                if result.status() != ResultStatus::Published {
+                    return Err(missing_dependency(
+                        "NOT_PUBLISHED",
+                        format!("exam_result/{}", key),
+                        format!("Result {} is a draft; only published results can be appealed", key),
+                    ));
+                }
+
+                let appeal = Appeal {
+                    student_id: key.student_id.to_string(),
+                    exam_id: key.exam_id.to_string(),
+                    reason: req.reason,
+                    state: AppealState::Open as i32,
+                    original_marks: result.marks_obtained,
+                    filed_by: identity.name.clone(),
+                    filed_at_ms: now_ms(),
+                    ..Default::default()
+                };
+
+                let Some(appeal) = self.store(&identity)?.create_appeal(appeal).await? else {
+                    return Err(Status::already_exists(format!(
+                        "Result {} already has an unresolved appeal",
+                        key
+                    )));
+                };
+
+                info!(%key, appeal_id = appeal.appeal_id, "appeal filed");
+                Ok(Response::new(appeal))
+            })
+            .await
+    }
+
+    // Lists appeals, oldest first. Students only see their own.
+    async fn list_appeals(
+        &self,
+        request: Request<ListAppealsRequest>,
+    ) -> Result<Response<ListAppealsResponse>, Status> {
+        info!(request = ?request.get_ref(), "list appeals");
+
+        let deadline = self.deadline(&request)?;
+        deadline
+            .run(async move {
+                let identity = Identity::from_request(&request)?;
+                let mut req = request.into_inner();
+
+                if identity.role == Role::Student {
+                    if req.student_id.is_empty() {
+                        req.student_id = identity.student_id.clone().unwrap_or_default();
+                    }
+                    identity.require_read(&req.student_id)?;
+                }
+
+                let student_id = (!req.student_id.is_empty())
+                    .then(|| StudentId::parse(&req.student_id))
+                    .transpose()?;
+                let exam_id = (!req.exam_id.is_empty()).then(|| ExamId::parse(&req.exam_id)).transpose()?;
+                let state = req.state();
+
+                let appeals = self
+                    .store(&identity)?
+                    .list_appeals(student_id.as_ref(), exam_id.as_ref())
+                    .await?
+                    .into_iter()
+                    .filter(|appeal| state == AppealState::Unspecified || appeal.state() == state)
+                    .collect();
+
+                Ok(Response::new(ListAppealsResponse { appeals }))
+            })
+            .await
+    }
+
+    // Takes an OPEN appeal under review, recording the caller as its reviewer.
+    async fn review_appeal(&self, request: Request<ReviewAppealRequest>) -> Result<Response<Appeal>, Status> {
+        info!(request = ?request.get_ref(), "review appeal");
+
+        let deadline = self.deadline(&request)?;
+        deadline
+            .run(async move {
+                let identity = Identity::from_request(&request)?;
+                identity.require_write()?;
+                let id = request.into_inner().appeal_id;
+
+                let mut appeal = self.appeal_in_state(&identity, id, AppealState::Open).await?;
+                appeal.state = AppealState::UnderReview as i32;
+                appeal.reviewer = identity.name.clone();
+
+                if !self.store(&identity)?.update_appeal(appeal.clone(), AppealState::Open).await? {
+                    return Err(state_changed(id));
+                }
+
+                info!(appeal_id = id, reviewer = %appeal.reviewer, "appeal under review");
+                Ok(Response::new(appeal))
+            })
+            .await
+    }
+
+    // Resolves an appeal under review. Approving it sets the result's marks
+    // to `revised_marks` and regrades it; rejecting it leaves the result alone.
+    async fn resolve_appeal(&self, request: Request<ResolveAppealRequest>) -> Result<Response<Appeal>, Status> {
+        info!(request = ?request.get_ref(), "resolve appeal");
+
+        let deadline = self.deadline(&request)?;
+        let service = self.clone();
+        deadline
+            .run_write(async move {
+                let identity = Identity::from_request(&request)?;
+                identity.require_write()?;
+                let req = request.into_inner();
+
+                if req.resolution.trim().is_empty() {
+                    return Err(invalid_field("resolution", "is required"));
+                }
+                if req.approved && req.revised_marks < 0 {
+                    return Err(invalid_field(
+                        "revised_marks",
+                        format!("must not be negative, got {}", req.revised_marks),
+                    ));
+                }
+
+                let under_review = service.appeal_in_state(&identity, req.appeal_id, AppealState::UnderReview).await?;
+                let mut appeal = under_review.clone();
+                appeal.state = AppealState::Resolved as i32;
+                appeal.approved = req.approved;
+                appeal.revised_marks = if req.approved { req.revised_marks } else { appeal.original_marks };
+                appeal.resolution = req.resolution;
+                appeal.resolved_at_ms = now_ms();
+                appeal.reviewer = identity.name.clone();
+
+                // Claimed before the result is touched, so two resolutions cannot both correct it.
+                // From here on the resolution runs to the end, so a claimed appeal is always
+                // either corrected or reopened
+                deadline::writing();
+                if !service.store(&identity)?.update_appeal(appeal.clone(), AppealState::UnderReview).await? {
+                    return Err(state_changed(appeal.appeal_id));
+                }
+
+                if appeal.approved {
+                    let key = ResultKey::parse(&appeal.student_id, &appeal.exam_id)?;
+                    let reason = format!("appeal {}: {}", appeal.appeal_id, appeal.resolution);
+
+                    let corrected = async {
+                        let current = service.store(&identity)?.get(&key).await?.ok_or_else(|| result_not_found(&key))?;
+                        let context = WriteContext {
+                            reason: &reason,
+                            request_id: "",
+                        };
+                        service.results
+                            .correct_marks(&identity, &key, current.version, appeal.revised_marks, context)
+                            .await
+                    }
+                    .await;
+
+                    // Reopen the review so the reviewer can fix the marks and try again
+                    if let Err(status) = corrected {
+                        service.store(&identity)?.update_appeal(under_review, AppealState::Resolved).await?;
+                        return Err(status);
+                    }
+                }
+
+                info!(appeal_id = appeal.appeal_id, approved = appeal.approved, "appeal resolved");
+                Ok(Response::new(appeal))
+            })
+            .await
+    }
+}
@@ -0,0 +1,42 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::auth::Identity;
+use crate::exam_service::{AuditRecord, ChangeKind, ExamResult};
+use crate::key::ResultKey;
+
+// Why and how a write was made, as given by the caller.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WriteContext<'a> {
+    pub reason: &'a str,
+    pub request_id: &'a str,
+}
+
+// Builds the audit record for one write to `key`; the store assigns its sequence.
+// Snapshots are kept unredacted, since only admins can read the trail.
+pub fn audit_record(
+    actor: &Identity,
+    key: &ResultKey,
+    action: ChangeKind,
+    before: Option<ExamResult>,
+    after: Option<ExamResult>,
+    context: WriteContext<'_>,
+) -> AuditRecord {
+    let recorded_at_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as i64)
+        .unwrap_or_default();
+
+    AuditRecord {
+        sequence: 0,
+        recorded_at_ms,
+        actor: actor.name.clone(),
+        role: actor.role.to_string(),
+        action: action as i32,
+        student_id: key.student_id.to_string(),
+        exam_id: key.exam_id.to_string(),
+        before: before.map(Into::into),
+        after: after.map(Into::into),
+        reason: context.reason.to_string(),
+        request_id: context.request_id.to_string(),
+    }
+}
@@ -0,0 +1,368 @@
+use std::collections::HashMap;
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use tonic::service::Interceptor;
+use tonic::{Request, Status};
+use tracing::warn;
+
+use crate::config::ServerConfig;
+use crate::key::InstitutionId;
+use crate::reload::Reloadable;
+
+mod jwt;
+
+pub use jwt::JwtAuth;
+
+// Environment variable holding the comma-separated `token=role` entries.
+// Students are bound to their own ID: `token=student:<student_id>`.
+// Either may end in `@name` to name the holder in the audit log, and start
+// with `institution/` to scope the token to an institution other than the
+// default one. `*/admin` tokens are valid in every institution.
+pub const TOKENS_ENV: &str = "EXAM_API_TOKENS";
+// Metadata naming the institution a request acts in. Optional for tokens
+// scoped to one institution, where it must match; required for `*/admin`.
+pub const INSTITUTION_HEADER: &str = "x-institution-id";
+// Token accepted when EXAM_API_TOKENS is unset and DEV_TOKEN_ENV is `1`,
+// for local development only.
+pub const DEV_TOKEN: &str = "dev-token";
+// Opts in to DEV_TOKEN. Without it, a server with no tokens configured refuses to start.
+pub const DEV_TOKEN_ENV: &str = "EXAM_DEV_TOKEN";
+
+// What a caller is allowed to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Role {
+    // Can read only their own results
+    Student,
+    // Can read any result and submit new ones
+    Teacher,
+    // Can do everything, including deletes
+    Admin,
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Role::Student => "student",
+            Role::Teacher => "teacher",
+            Role::Admin => "admin",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for Role {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "student" => Ok(Role::Student),
+            "teacher" => Ok(Role::Teacher),
+            "admin" => Ok(Role::Admin),
+            other => Err(format!("unknown role: {}", other)),
+        }
+    }
+}
+
+// The authenticated caller, attached to request extensions by `TokenAuth`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identity {
+    pub role: Role,
+    // Set for students; the only student_id they may read
+    pub student_id: Option<String>,
+    // Who holds the token, as recorded in the audit log
+    pub name: String,
+    // The institution the request acts in; every store access is scoped to it
+    pub institution: InstitutionId,
+    // Set for operators, whose `*/admin` tokens act in whichever institution
+    // the request names and may act on the server as a whole
+    pub every_institution: bool,
+}
+
+impl Identity {
+    // Returns the identity the auth interceptor attached to the request.
+    pub fn from_request<T>(request: &Request<T>) -> Result<Self, Status> {
+        request
+            .extensions()
+            .get::<Identity>()
+            .cloned()
+            .ok_or_else(|| Status::unauthenticated("Request was not authenticated"))
+    }
+
+    // Students may only read their own results; teachers and admins may read any.
+    pub fn require_read(&self, student_id: &str) -> Result<(), Status> {
+        match self.role {
+            Role::Student if self.student_id.as_deref() != Some(student_id) => Err(
+                Status::permission_denied("Students can only access their own results"),
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    // Only teachers and admins may submit results or manage the exam catalog.
+    pub fn require_write(&self) -> Result<(), Status> {
+        match self.role {
+            Role::Teacher | Role::Admin => Ok(()),
+            Role::Student => Err(Status::permission_denied(
+                "Only teachers and admins can modify results and exams",
+            )),
+        }
+    }
+
+    // Only teachers and admins may view class-wide data such as exam statistics.
+    pub fn require_staff(&self) -> Result<(), Status> {
+        match self.role {
+            Role::Teacher | Role::Admin => Ok(()),
+            Role::Student => Err(Status::permission_denied(
+                "Only teachers and admins can view class-wide data",
+            )),
+        }
+    }
+
+    // Only admins may delete results.
+    pub fn require_admin(&self) -> Result<(), Status> {
+        match self.role {
+            Role::Admin => Ok(()),
+            _ => Err(Status::permission_denied("Only admins can perform this operation")),
+        }
+    }
+
+    // Only operators may see or change what spans every institution, such as
+    // the server's settings and maintenance mode.
+    pub fn require_operator(&self) -> Result<(), Status> {
+        match (self.role, self.every_institution) {
+            (Role::Admin, true) => Ok(()),
+            _ => Err(Status::permission_denied(
+                "Only admins valid in every institution (`*/admin` tokens) can perform this operation",
+            )),
+        }
+    }
+}
+
+// Parses one `token=role` or `token=student:<student_id>` entry, optionally
+// prefixed by `institution/` and followed by `@name`. Unnamed tokens are
+// named after their grant. The token may contain `=`, as base64 padding does;
+// the grant may not.
+fn parse_token_entry(entry: &str) -> Result<(String, Identity), String> {
+    let (token, grant) = entry
+        .rsplit_once('=')
+        .ok_or_else(|| format!("expected token=role, got: {}", entry))?;
+
+    let (institution, grant) = match grant.split_once('/') {
+        Some((institution, grant)) => (Some(institution), grant),
+        None => (None, grant),
+    };
+
+    let (grant, name) = match grant.split_once('@') {
+        Some((grant, name)) if !name.is_empty() => (grant, name),
+        Some(_) => return Err(format!("empty name after @: {}", entry)),
+        None => (grant, grant),
+    };
+
+    let (role, student_id) = match grant.split_once(':') {
+        Some((role, student_id)) => (role.parse()?, Some(student_id.to_string())),
+        None => (grant.parse()?, None),
+    };
+
+    if (role == Role::Student) != student_id.is_some() {
+        return Err(format!("only student tokens carry a student_id: {}", entry));
+    }
+
+    let every_institution = institution == Some("*");
+    if every_institution && role != Role::Admin {
+        return Err(format!("only admin tokens can be valid in every institution: {}", entry));
+    }
+
+    let institution = match institution {
+        Some(institution) if !every_institution => InstitutionId::parse(institution)
+            .map_err(|status| format!("bad institution in {}: {}", entry, status.message()))?,
+        _ => InstitutionId::default(),
+    };
+
+    let identity = Identity {
+        role,
+        student_id,
+        name: name.to_string(),
+        institution,
+        every_institution,
+    };
+    Ok((token.to_string(), identity))
+}
+
+// Interceptor that validates the `authorization: Bearer <token>` metadata
+// against the configured tokens, or as a JWT, and attaches the caller's `Identity`.
+#[derive(Debug, Clone)]
+pub struct TokenAuth {
+    tokens: Reloadable<HashMap<String, Identity>>,
+    // Added in code by `with_operator`, so kept when the tokens are replaced
+    operators: Vec<(String, Identity)>,
+    // Validates bearer tokens that are not API tokens, if an issuer is configured
+    jwt: Option<JwtAuth>,
+}
+
+impl TokenAuth {
+    // Each token acts as its identity, in the identity's institution unless
+    // it is valid in every institution.
+    pub fn new(tokens: impl IntoIterator<Item = (String, Identity)>) -> Self {
+        Self {
+            tokens: Reloadable::new(tokens.into_iter().collect()),
+            operators: Vec::new(),
+            jwt: None,
+        }
+    }
+
+    // Also accepts JWTs `jwt` validates. API tokens are looked up first.
+    pub fn with_jwt(mut self, jwt: JwtAuth) -> Self {
+        self.jwt = Some(jwt);
+        self
+    }
+
+    // Adds an admin token valid in every institution, like `*/admin` in EXAM_API_TOKENS.
+    pub fn with_operator(mut self, token: impl Into<String>, name: &str) -> Self {
+        let identity = Identity {
+            role: Role::Admin,
+            student_id: None,
+            name: name.to_string(),
+            institution: InstitutionId::default(),
+            every_institution: true,
+        };
+        let token = token.into();
+        let mut tokens = HashMap::clone(&self.tokens.get());
+        tokens.insert(token.clone(), identity.clone());
+        self.tokens.set(tokens);
+        self.operators.push((token, identity));
+        self
+    }
+
+    // Loads tokens from `api_tokens_file` when configured, else from EXAM_API_TOKENS.
+    // With JWTs accepted, neither is needed.
+    pub fn load(config: &ServerConfig) -> Result<Self, Box<dyn Error>> {
+        match &config.api_tokens_file {
+            Some(path) => Ok(Self::new(Self::read_file(path)?)),
+            None if config.jwt.enabled() && env::var(TOKENS_ENV).is_err() => Ok(Self::new(Vec::new())),
+            None => Ok(Self::from_env()?),
+        }
+    }
+
+    // Reads a tokens file: one EXAM_API_TOKENS entry per line, with blank
+    // lines and lines starting with `#` ignored.
+    pub(crate) fn read_file(path: &Path) -> Result<Vec<(String, Identity)>, Box<dyn Error>> {
+        let contents =
+            fs::read_to_string(path).map_err(|e| format!("cannot read tokens file {}: {}", path.display(), e))?;
+        let tokens = contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(parse_token_entry)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(tokens)
+    }
+
+    // Swaps in `tokens` for every caller, including clones of this `TokenAuth`,
+    // from their next call on. Operator tokens stay valid.
+    pub(crate) fn replace(&self, tokens: Vec<(String, Identity)>) {
+        let tokens = tokens.into_iter().chain(self.operators.iter().cloned()).collect();
+        self.tokens.set(tokens);
+    }
+
+    // Loads tokens from EXAM_API_TOKENS. When it is unset, fails unless
+    // DEV_TOKEN_ENV opts in to the admin development token.
+    pub fn from_env() -> Result<Self, String> {
+        match env::var(TOKENS_ENV) {
+            Ok(value) => {
+                let tokens = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|entry| !entry.is_empty())
+                    .map(parse_token_entry)
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(Self::new(tokens))
+            }
+            Err(_) if env::var(DEV_TOKEN_ENV).is_ok_and(|value| value == "1") => {
+                warn!(
+                    "{} not set and {}=1: accepting the publicly known token {:?} as an admin; never do this in production",
+                    TOKENS_ENV, DEV_TOKEN_ENV, DEV_TOKEN
+                );
+                let identity = Identity {
+                    role: Role::Admin,
+                    student_id: None,
+                    name: "dev".to_string(),
+                    institution: InstitutionId::default(),
+                    every_institution: false,
+                };
+                Ok(Self::new([(DEV_TOKEN.to_string(), identity)]))
+            }
+            Err(_) => Err(format!(
+                "no API tokens configured: set {} or api_tokens_file, or {}=1 to accept the development token",
+                TOKENS_ENV, DEV_TOKEN_ENV
+            )),
+        }
+    }
+}
+
+impl TokenAuth {
+    // Resolves an `authorization` header value of the form `Bearer <token>`,
+    // in the institution named by the `x-institution-id` value if any.
+    // Shared by the gRPC interceptor and the HTTP gateway.
+    pub fn identify(&self, authorization: Option<&str>, institution: Option<&str>) -> Result<Identity, Status> {
+        let token = authorization
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| Status::unauthenticated("Missing bearer token"))?;
+
+        let mut identity = match (self.tokens.get().get(token), &self.jwt) {
+            (Some(identity), _) => identity.clone(),
+            // JWTs carry their institution, like tokens scoped to one
+            (None, Some(jwt)) if JwtAuth::accepts(token) => jwt.identify(token)?,
+            (None, _) => return Err(Status::unauthenticated("Invalid bearer token")),
+        };
+
+        match institution {
+            Some(institution) => {
+                let institution = InstitutionId::parse(institution)?;
+                if !identity.every_institution && institution != identity.institution {
+                    return Err(Status::permission_denied(format!(
+                        "Token is not valid for institution {}",
+                        institution
+                    )));
+                }
+                identity.institution = institution;
+            }
+            None if identity.every_institution => {
+                return Err(Status::invalid_argument(format!(
+                    "{} is required with a token valid in every institution",
+                    INSTITUTION_HEADER
+                )));
+            }
+            None => {}
+        }
+
+        Ok(identity)
+    }
+}
+
+impl Interceptor for TokenAuth {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        let authorization = request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok());
+        let institution = request
+            .metadata()
+            .get(INSTITUTION_HEADER)
+            .map(|value| {
+                value
+                    .to_str()
+                    .map_err(|_| Status::invalid_argument(format!("{} must be ASCII", INSTITUTION_HEADER)))
+            })
+            .transpose()?;
+
+        let identity = self.identify(authorization, institution)?;
+
+        request.extensions_mut().insert(identity);
+        Ok(request)
+    }
+}
@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use http::Uri;
+use jsonwebtoken::jwk::Jwk;
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use serde_json::{Map, Value};
+use tonic::Status;
+use tracing::{info, warn};
+
+use super::{Identity, Role};
+use crate::config::JwtConfig;
+use crate::key::InstitutionId;
+use crate::webhooks::HttpSender;
+
+type BoxError = Box<dyn Error + Send + Sync>;
+
+// Signed with the provider's private keys; shared-secret algorithms would let
+// anyone holding the published key sign tokens.
+const ALGORITHMS: [Algorithm; 9] = [
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::PS384,
+    Algorithm::PS512,
+    Algorithm::ES256,
+    Algorithm::ES384,
+    Algorithm::EdDSA,
+];
+
+// Fetches prompted by requests are at least this far apart, so neither tokens
+// naming made-up keys nor an unreachable provider cause a fetch per request.
+const MIN_REFETCH_INTERVAL: Duration = Duration::from_secs(10);
+
+// Validates JWTs from an OIDC provider and maps their claims to an `Identity`.
+// Cloning is cheap and clones share the cached keys.
+#[derive(Clone)]
+pub struct JwtAuth {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    config: JwtConfig,
+    roles: HashMap<String, Role>,
+    sender: HttpSender,
+    keys: RwLock<Keys>,
+    // Set while a fetch runs, so concurrent requests start only one
+    fetching: AtomicBool,
+}
+
+#[derive(Default)]
+struct Keys {
+    by_id: HashMap<String, DecodingKey>,
+    fetched_at: Option<Instant>,
+    attempted_at: Option<Instant>,
+}
+
+impl JwtAuth {
+    // Checks the role mapping and CA bundle; no keys are fetched until `refresh`.
+    pub fn new(config: &JwtConfig) -> Result<Self, Box<dyn Error>> {
+        let roles = config
+            .roles
+            .iter()
+            .map(|(value, role)| Ok((value.clone(), role.parse()?)))
+            .collect::<Result<_, String>>()
+            .map_err(|e| format!("jwt.roles: {}", e))?;
+
+        Ok(Self {
+            inner: Arc::new(Inner {
+                config: config.clone(),
+                roles,
+                sender: HttpSender::new(config.ca.as_deref())?,
+                keys: RwLock::default(),
+                fetching: AtomicBool::new(false),
+            }),
+        })
+    }
+
+    // Validates tokens for `config`, if it names an issuer, with its keys
+    // fetched. An unreachable provider is logged rather than fatal: tokens are
+    // refused until a later fetch succeeds.
+    pub async fn from_config(config: &JwtConfig) -> Result<Option<Self>, Box<dyn Error>> {
+        if !config.enabled() {
+            return Ok(None);
+        }
+
+        let jwt = Self::new(config)?;
+        if let Err(err) = jwt.refresh().await {
+            warn!(issuer = %config.issuer, %err, "could not fetch the JWT signing keys");
+        }
+        Ok(Some(jwt))
+    }
+
+    // Fetches the provider's signing keys, replacing those cached. Keys of
+    // types or algorithms this server cannot use are skipped.
+    pub async fn refresh(&self) -> Result<(), BoxError> {
+        self.inner.keys.write().unwrap().attempted_at = Some(Instant::now());
+
+        let url = match self.inner.config.jwks_url.as_str() {
+            "" => self.discover().await?,
+            url => url.parse()?,
+        };
+        let document = self.fetch(&url).await?;
+        let keys = document
+            .get("keys")
+            .and_then(Value::as_array)
+            .ok_or_else(|| format!("{} has no keys", url))?;
+
+        let mut by_id = HashMap::new();
+        for key in keys {
+            let parsed = serde_json::from_value::<Jwk>(key.clone())
+                .map_err(BoxError::from)
+                .and_then(|jwk| Ok((jwk.common.key_id.clone().unwrap_or_default(), DecodingKey::from_jwk(&jwk)?)));
+            match parsed {
+                Ok((id, key)) => {
+                    by_id.insert(id, key);
+                }
+                Err(err) => warn!(%url, %err, "skipping a JWT signing key"),
+            }
+        }
+
+        info!(%url, keys = by_id.len(), "fetched the JWT signing keys");
+        let mut keys = self.inner.keys.write().unwrap();
+        keys.by_id = by_id;
+        keys.fetched_at = Some(Instant::now());
+        Ok(())
+    }
+
+    // The JWKS URL named by the issuer's OIDC discovery document.
+    async fn discover(&self) -> Result<Uri, BoxError> {
+        let issuer = self.inner.config.issuer.trim_end_matches('/');
+        let document = self.fetch(&format!("{}/.well-known/openid-configuration", issuer).parse()?).await?;
+        let url = document
+            .get("jwks_uri")
+            .and_then(Value::as_str)
+            .ok_or("the OIDC discovery document has no jwks_uri")?;
+        Ok(url.parse()?)
+    }
+
+    async fn fetch(&self, url: &Uri) -> Result<Value, BoxError> {
+        let (status, body) = self.inner.sender.get(url).await?;
+        if !status.is_success() {
+            return Err(format!("{} answered {}", url, status).into());
+        }
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    // Starts a fetch in the background unless one is running. Returns whether
+    // one was started.
+    fn refresh_in_background(&self) -> bool {
+        if self.inner.fetching.swap(true, Ordering::AcqRel) {
+            return false;
+        }
+
+        let jwt = self.clone();
+        tokio::spawn(async move {
+            if let Err(err) = jwt.refresh().await {
+                warn!(issuer = %jwt.inner.config.issuer, %err, "could not fetch the JWT signing keys");
+            }
+            jwt.inner.fetching.store(false, Ordering::Release);
+        });
+        true
+    }
+
+    // Whether `token` is shaped like a JWT rather than a static API token.
+    pub(crate) fn accepts(token: &str) -> bool {
+        token.matches('.').count() == 2
+    }
+
+    // The identity `token` carries, once its signature, issuer, audience and
+    // lifetime check out.
+    pub(crate) fn identify(&self, token: &str) -> Result<Identity, Status> {
+        let invalid = |reason: &str| Status::unauthenticated(format!("Invalid bearer token: {}", reason));
+
+        let header = decode_header(token).map_err(|err| invalid(&err.to_string()))?;
+        if !ALGORITHMS.contains(&header.alg) {
+            return Err(invalid(&format!("{:?} signatures are not accepted", header.alg)));
+        }
+
+        let key = {
+            let keys = self.inner.keys.read().unwrap();
+            let recently = keys.attempted_at.is_some_and(|at| at.elapsed() < MIN_REFETCH_INTERVAL);
+            let stale = keys.fetched_at.is_none_or(|at| at.elapsed() >= self.inner.config.jwks_refresh());
+            if stale && !recently {
+                self.refresh_in_background();
+            }
+
+            let found = match header.kid.as_deref() {
+                Some(kid) => keys.by_id.get(kid).cloned(),
+                // Providers with a single key may leave it unnamed
+                None if keys.by_id.len() == 1 => keys.by_id.values().next().cloned(),
+                None => None,
+            };
+            found.ok_or_else(|| {
+                // Perhaps a key rotated in since the last fetch: fetch again, and have the caller retry
+                if self.inner.fetching.load(Ordering::Acquire) || (!recently && self.refresh_in_background()) {
+                    Status::unavailable("Token is signed with a key not fetched yet; retry shortly")
+                } else {
+                    invalid("unknown signing key")
+                }
+            })?
+        };
+
+        let config = &self.inner.config;
+        let mut validation = Validation::new(header.alg);
+        validation.leeway = config.leeway_secs;
+        validation.set_issuer(&[&config.issuer]);
+        if config.audience.is_empty() {
+            validation.validate_aud = false;
+        } else {
+            validation.set_audience(&[&config.audience]);
+        }
+
+        let claims = decode::<Map<String, Value>>(token, &key, &validation)
+            .map_err(|err| invalid(&err.to_string()))?
+            .claims;
+        self.identity(&claims)
+    }
+
+    // Maps validated claims to the caller's role, student ID, name and institution.
+    fn identity(&self, claims: &Map<String, Value>) -> Result<Identity, Status> {
+        let config = &self.inner.config;
+
+        let role = strings(claims.get(&config.roles_claim))
+            .filter_map(|value| self.inner.roles.get(value).copied())
+            .max_by_key(|role| match role {
+                Role::Student => 0,
+                Role::Teacher => 1,
+                Role::Admin => 2,
+            })
+            .ok_or_else(|| Status::permission_denied("Token grants no role in this service"))?;
+
+        let claim = |name: &str| claims.get(name).and_then(Value::as_str).filter(|value| !value.is_empty());
+
+        let student_id = match role {
+            Role::Student => Some(claim(&config.student_id_claim).ok_or_else(|| {
+                Status::permission_denied(format!("Student tokens must carry a {} claim", config.student_id_claim))
+            })?),
+            _ => None,
+        };
+
+        let institution = match config.institution_claim.as_str() {
+            "" => InstitutionId::default(),
+            name => {
+                let institution = claim(name)
+                    .ok_or_else(|| Status::permission_denied(format!("Token must carry a {} claim", name)))?;
+                InstitutionId::parse(institution)?
+            }
+        };
+
+        Ok(Identity {
+            role,
+            student_id: student_id.map(str::to_string),
+            name: claim(&config.name_claim).or_else(|| claim("sub")).unwrap_or("jwt").to_string(),
+            institution,
+            every_institution: false,
+        })
+    }
+}
+
+// A claim's string values, whether it holds one string or a list.
+fn strings(claim: Option<&Value>) -> impl Iterator<Item = &str> {
+    let values = match claim {
+        Some(Value::Array(values)) => values.as_slice(),
+        Some(value) => std::slice::from_ref(value),
+        None => &[],
+    };
+    values.iter().filter_map(Value::as_str)
+}
+
+impl fmt::Debug for JwtAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JwtAuth")
+            .field("issuer", &self.inner.config.issuer)
+            .field("keys", &self.inner.keys.read().unwrap().by_id.len())
+            .finish()
+    }
+}
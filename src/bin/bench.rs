@@ -0,0 +1,366 @@
+// Load generator: drives concurrent clients doing a mix of unary and streaming
+// calls against a running server, then reports throughput and latency
+// percentiles per call. Writes overwrite sample results, so point it at a
+// development server.
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::process::ExitCode;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use clap::Parser;
+use futures::StreamExt;
+use rand::Rng;
+use serde::Serialize;
+use tonic::transport::Endpoint;
+use tonic::Status;
+use exam_service::client::{BearerToken, CircuitBreaker, ExamClient, RetryPolicy};
+use exam_service::exam_service::{ExamResult, ListExamResultsRequest};
+
+// Server address used when neither --addr nor EXAM_ADDR is given.
+const DEFAULT_ADDR: &str = "[::1]:50051";
+
+// Results of the sample data, read and rewritten by the benchmark.
+const KEYS: [(&str, &str); 2] = [("123", "math101"), ("456", "phy101")];
+
+#[derive(Debug, Parser)]
+#[command(name = "bench", version, about = "Load-test a running exam service")]
+struct Args {
+    /// Server address, as host:port or a full http:// URL
+    #[arg(long, env = "EXAM_ADDR", default_value = DEFAULT_ADDR)]
+    addr: String,
+
+    /// API token sent with every call; writes need a teacher or admin token
+    #[arg(long, env = "EXAM_API_TOKEN", default_value = "dev-token", hide_env_values = true)]
+    token: String,
+
+    /// Institution to act in; required with a token valid in every institution
+    #[arg(long, env = "EXAM_INSTITUTION")]
+    institution: Option<String>,
+
+    /// Concurrent clients, each on a connection of its own
+    #[arg(long, default_value_t = 16, value_parser = clap::value_parser!(u32).range(1..))]
+    clients: u32,
+
+    /// Seconds to measure for
+    #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..))]
+    duration: u64,
+
+    /// Seconds of calls before measuring starts, so connections are warm
+    #[arg(long, default_value_t = 1)]
+    warmup: u64,
+
+    /// Relative weight of each call, as comma-separated call=weight pairs
+    #[arg(long, default_value = "get=60,submit=20,list=10,stream=10")]
+    mix: Mix,
+
+    /// Print the report as JSON, e.g. to compare runs
+    #[arg(long)]
+    json: bool,
+}
+
+// The calls the benchmark makes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Call {
+    // GetExamResult
+    Get,
+    // SubmitExamResult
+    Submit,
+    // ListExamResults, read to the end
+    List,
+    // GetExamResultStream, read to the end
+    Stream,
+}
+
+impl Call {
+    fn name(self) -> &'static str {
+        match self {
+            Call::Get => "get",
+            Call::Submit => "submit",
+            Call::List => "list",
+            Call::Stream => "stream",
+        }
+    }
+}
+
+impl fmt::Display for Call {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+// How often each call is made, relative to the others.
+#[derive(Debug, Clone)]
+struct Mix(Vec<(Call, u32)>);
+
+impl FromStr for Mix {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut weights = Vec::new();
+        for pair in value.split(',').map(str::trim).filter(|pair| !pair.is_empty()) {
+            let (call, weight) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("expected call=weight, got {}", pair))?;
+            let call = match call.trim() {
+                "get" => Call::Get,
+                "submit" => Call::Submit,
+                "list" => Call::List,
+                "stream" => Call::Stream,
+                other => return Err(format!("unknown call {} (expected get, submit, list or stream)", other)),
+            };
+            let weight = weight
+                .trim()
+                .parse()
+                .map_err(|_| format!("weight of {} is not a whole number: {}", call, weight))?;
+            weights.push((call, weight));
+        }
+
+        if weights.iter().all(|(_, weight)| *weight == 0) {
+            return Err("at least one call needs a weight above 0".to_string());
+        }
+        Ok(Mix(weights))
+    }
+}
+
+impl Mix {
+    fn pick(&self) -> Call {
+        let total: u32 = self.0.iter().map(|(_, weight)| weight).sum();
+        let mut roll = rand::thread_rng().gen_range(0..total);
+        for (call, weight) in &self.0 {
+            if roll < *weight {
+                return *call;
+            }
+            roll -= weight;
+        }
+        unreachable!("the roll is below the total weight")
+    }
+}
+
+// What one client saw of one call while measuring.
+#[derive(Default)]
+struct Samples {
+    latencies: Vec<Duration>,
+    errors: usize,
+    first_error: Option<Status>,
+}
+
+impl Samples {
+    fn record(&mut self, latency: Duration, outcome: Result<(), Status>) {
+        match outcome {
+            Ok(()) => self.latencies.push(latency),
+            Err(status) => {
+                self.errors += 1;
+                self.first_error.get_or_insert(status);
+            }
+        }
+    }
+
+    fn merge(&mut self, other: Samples) {
+        self.latencies.extend(other.latencies);
+        self.errors += other.errors;
+        if self.first_error.is_none() {
+            self.first_error = other.first_error;
+        }
+    }
+}
+
+async fn call(client: &ExamClient, call: Call) -> Result<(), Status> {
+    let (student_id, exam_id) = KEYS[rand::thread_rng().gen_range(0..KEYS.len())];
+
+    match call {
+        Call::Get => client.get_result(student_id, exam_id).await.map(drop),
+        Call::Submit => {
+            let result = ExamResult {
+                student_id: student_id.to_string(),
+                exam_id: exam_id.to_string(),
+                marks_obtained: rand::thread_rng().gen_range(0..=100),
+                ..Default::default()
+            };
+            client.submit_result(result).await.map(drop)
+        }
+        Call::List => {
+            let mut results = client.list_results(ListExamResultsRequest::default()).await?;
+            while let Some(result) = results.next().await {
+                result?;
+            }
+            Ok(())
+        }
+        Call::Stream => {
+            let mut results = client.get_result_stream(student_id, "").await?;
+            while let Some(result) = results.next().await {
+                result?;
+            }
+            Ok(())
+        }
+    }
+}
+
+// Makes calls back to back until `until`, recording those started at or after `measure_from`.
+async fn run_client(client: ExamClient, mix: Mix, measure_from: Instant, until: Instant) -> BTreeMap<Call, Samples> {
+    let mut samples: BTreeMap<Call, Samples> = BTreeMap::new();
+
+    loop {
+        let started = Instant::now();
+        if started >= until {
+            return samples;
+        }
+        let picked = mix.pick();
+        let outcome = call(&client, picked).await;
+
+        if started >= measure_from {
+            samples.entry(picked).or_default().record(started.elapsed(), outcome);
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct CallReport {
+    call: Call,
+    calls: usize,
+    errors: usize,
+    per_second: f64,
+    p50_ms: f64,
+    p90_ms: f64,
+    p99_ms: f64,
+    max_ms: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct Report {
+    clients: u32,
+    duration_secs: u64,
+    calls: Vec<CallReport>,
+    total_per_second: f64,
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+// The nearest-rank percentile of sorted latencies.
+fn percentile(sorted: &[Duration], fraction: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = (fraction * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn report(args: &Args, samples: BTreeMap<Call, Samples>) -> Report {
+    let seconds = args.duration as f64;
+    let calls: Vec<_> = samples
+        .into_iter()
+        .map(|(call, mut samples)| {
+            samples.latencies.sort();
+            let latencies = &samples.latencies;
+            CallReport {
+                call,
+                calls: latencies.len(),
+                errors: samples.errors,
+                per_second: latencies.len() as f64 / seconds,
+                p50_ms: millis(percentile(latencies, 0.50)),
+                p90_ms: millis(percentile(latencies, 0.90)),
+                p99_ms: millis(percentile(latencies, 0.99)),
+                max_ms: millis(latencies.last().copied().unwrap_or_default()),
+            }
+        })
+        .collect();
+
+    Report {
+        clients: args.clients,
+        duration_secs: args.duration,
+        total_per_second: calls.iter().map(|call| call.per_second).sum(),
+        calls,
+    }
+}
+
+fn print_table(report: &Report) {
+    println!(
+        "{} clients for {}s, {:.1} calls/s in total",
+        report.clients, report.duration_secs, report.total_per_second
+    );
+    println!(
+        "{:<8} {:>9} {:>7} {:>10} {:>9} {:>9} {:>9} {:>9}",
+        "call", "calls", "errors", "calls/s", "p50 ms", "p90 ms", "p99 ms", "max ms"
+    );
+    for call in &report.calls {
+        println!(
+            "{:<8} {:>9} {:>7} {:>10.1} {:>9.2} {:>9.2} {:>9.2} {:>9.2}",
+            call.call.name(),
+            call.calls,
+            call.errors,
+            call.per_second,
+            call.p50_ms,
+            call.p90_ms,
+            call.p99_ms,
+            call.max_ms
+        );
+    }
+}
+
+async fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    let url = match args.addr.contains("://") {
+        true => args.addr.clone(),
+        false => format!("http://{}", args.addr),
+    };
+    let endpoint = Endpoint::from_shared(url)?;
+
+    let mut token = BearerToken::new(&args.token)?;
+    if let Some(institution) = &args.institution {
+        token = token.with_institution(institution)?;
+    }
+
+    // Retries would hide the failures and latency the run is meant to show, and
+    // an open circuit breaker would count calls that never reached the server
+    let mut clients = Vec::new();
+    for _ in 0..args.clients {
+        let channel = endpoint.connect().await?;
+        let client = ExamClient::with_channel(channel, token.clone())
+            .with_retry_policy(RetryPolicy::none())
+            .with_circuit_breaker(CircuitBreaker::new(u32::MAX, Duration::ZERO));
+        clients.push(client);
+    }
+
+    let measure_from = Instant::now() + Duration::from_secs(args.warmup);
+    let until = measure_from + Duration::from_secs(args.duration);
+    let tasks: Vec<_> = clients
+        .into_iter()
+        .map(|client| tokio::spawn(run_client(client, args.mix.clone(), measure_from, until)))
+        .collect();
+
+    let mut samples: BTreeMap<Call, Samples> = BTreeMap::new();
+    for task in tasks {
+        for (call, seen) in task.await? {
+            samples.entry(call).or_default().merge(seen);
+        }
+    }
+
+    let failures: Vec<_> = samples
+        .iter()
+        .filter_map(|(call, samples)| samples.first_error.as_ref().map(|status| (*call, status.clone())))
+        .collect();
+    let report = report(&args, samples);
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_table(&report);
+    }
+    for (call, status) in failures {
+        eprintln!("{} failed, first with {:?}: {}", call, status.code(), status.message());
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    match run(Args::parse()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,494 @@
+use std::time::UNIX_EPOCH;
+use std::path::PathBuf;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use exam_service::exam_service::QuestionScore;
+
+// Server address used when neither --addr nor EXAM_ADDR is given.
+const DEFAULT_ADDR: &str = "[::1]:50051";
+
+// Command-line client for the exam service.
+#[derive(Debug, Parser)]
+#[command(name = "client", version, about = "Command-line client for the exam service")]
+pub struct Cli {
+    #[command(flatten)]
+    pub connection: ConnectionArgs,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Table, global = true)]
+    pub output: OutputFormat,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+// How to reach and authenticate with the server. Every flag can also be set
+// through the environment variables the client has always read.
+#[derive(Debug, Args)]
+pub struct ConnectionArgs {
+    /// Server address, as host:port or a full http(s):// URL. Repeat it or
+    /// separate addresses with commas to balance calls across replicas
+    #[arg(long, env = "EXAM_ADDR", default_value = DEFAULT_ADDR, value_delimiter = ',', global = true)]
+    pub addr: Vec<String>,
+
+    /// API token sent as a bearer token with every call
+    #[arg(long, env = "EXAM_API_TOKEN", default_value = "dev-token", hide_env_values = true, global = true)]
+    pub token: String,
+
+    /// Institution to act in; required with a token valid in every institution
+    #[arg(long, env = "EXAM_INSTITUTION", global = true)]
+    pub institution: Option<String>,
+
+    /// PEM CA bundle used to verify the server; setting it switches to TLS
+    #[arg(long, env = "EXAM_TLS_CA", global = true)]
+    pub ca: Option<PathBuf>,
+
+    /// Name the server certificate is issued for
+    #[arg(long, env = "EXAM_TLS_DOMAIN", default_value = "localhost", global = true)]
+    pub domain: String,
+
+    /// Client certificate presented when the server requires mutual TLS
+    #[arg(long, env = "EXAM_TLS_CLIENT_CERT", requires = "client_key", global = true)]
+    pub client_cert: Option<PathBuf>,
+
+    /// Private key for --client-cert
+    #[arg(long, env = "EXAM_TLS_CLIENT_KEY", requires = "client_cert", global = true)]
+    pub client_key: Option<PathBuf>,
+
+    /// Attempts per read or result write on transient failures, including the first
+    #[arg(long, env = "EXAM_MAX_ATTEMPTS", default_value_t = 3, value_parser = clap::value_parser!(u32).range(1..), global = true)]
+    pub max_attempts: u32,
+
+    /// Compress requests, e.g. for large imports; the server must accept the encoding
+    #[arg(long, env = "EXAM_COMPRESSION", value_enum, global = true)]
+    pub compress: Option<Compression>,
+
+    /// OTLP gRPC collector to export the command's trace to, e.g. http://localhost:4317
+    #[arg(long, env = "EXAM_OTLP_ENDPOINT", global = true)]
+    pub otlp_endpoint: Option<String>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum OperationCommand {
+    /// Print an operation's progress as it changes, until it finishes
+    Watch { operation_id: String },
+    /// Download the file of a finished background export
+    Download {
+        operation_id: String,
+        /// File to write; standard output by default
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Aligned columns for people
+    Table,
+    /// One JSON document per response (one per line for streams)
+    Json,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Fetch one result
+    Get {
+        #[command(flatten)]
+        key: ResultArgs,
+        #[command(flatten)]
+        fields: FieldArgs,
+    },
+    /// Fetch several results in one call, e.g. `batch-get 123/math101 456/phy101`
+    BatchGet {
+        /// Results as student_id/exam_id
+        #[arg(required = true, value_parser = parse_key)]
+        keys: Vec<(String, String)>,
+    },
+    /// Stream a student's results, optionally for one exam
+    GetStream {
+        student_id: String,
+        /// Only stream this exam's result
+        exam_id: Option<String>,
+    },
+    /// Submit or replace a result; the server computes the grade
+    Submit {
+        #[command(flatten)]
+        key: ResultArgs,
+        /// Marks obtained
+        marks: i32,
+        /// Total marks (defaults to the exam's)
+        #[arg(long)]
+        total: Option<i32>,
+        /// Student name (defaults to the registered name)
+        #[arg(long)]
+        name: Option<String>,
+        /// Subject (defaults to the exam's)
+        #[arg(long)]
+        subject: Option<String>,
+        /// Comment visible only to admins
+        #[arg(long)]
+        comment: Option<String>,
+    },
+    /// Record per-question marks, e.g. `scores 123 math101 q1=8/10 q2=5/5`
+    Scores {
+        #[command(flatten)]
+        key: ResultArgs,
+        /// Scores as question_id=marks/max_marks
+        #[arg(required = true, value_parser = parse_score)]
+        scores: Vec<QuestionScore>,
+    },
+    /// Amend the marks on an existing result
+    Correct {
+        #[command(flatten)]
+        key: ResultArgs,
+        /// Corrected marks
+        marks: i32,
+        /// Version the correction is based on; defaults to the currently stored version
+        #[arg(long = "version")]
+        expected_version: Option<i64>,
+        /// Why the result was corrected, recorded in the server log
+        #[arg(long, default_value = "")]
+        reason: String,
+    },
+    /// Delete a result (admin only)
+    Delete {
+        #[command(flatten)]
+        key: ResultArgs,
+        /// Why the result was deleted, recorded in the server log
+        #[arg(long, default_value = "")]
+        reason: String,
+    },
+    /// List deleted results not yet purged (admin only)
+    Deleted {
+        #[arg(long)]
+        student: Option<String>,
+        #[arg(long)]
+        exam: Option<String>,
+    },
+    /// Bring back a deleted result (admin only)
+    Undelete {
+        #[command(flatten)]
+        key: ResultArgs,
+        /// Why the result was undeleted, recorded in the audit log
+        #[arg(long, default_value = "")]
+        reason: String,
+    },
+    /// List results matching the given filters
+    List {
+        #[command(flatten)]
+        filter: FilterArgs,
+        #[command(flatten)]
+        fields: FieldArgs,
+    },
+    /// Find results by student name or subject, best matches first, e.g. `search "jane phys"`
+    Search {
+        query: String,
+        /// Most matches to return; the server's default when unset
+        #[arg(long)]
+        limit: Option<i32>,
+    },
+    /// Download matching results as CSV or JSON lines
+    Export {
+        #[command(flatten)]
+        filter: FilterArgs,
+        #[arg(long, value_enum, default_value_t = FileFormat::Csv)]
+        format: FileFormat,
+        /// File to write; standard output by default
+        #[arg(long, conflicts_with = "background")]
+        out: Option<PathBuf>,
+        /// Export on the server in the background; fetch the file with `operation download`
+        #[arg(long)]
+        background: bool,
+    },
+    /// Upload results from a CSV or JSON file, reporting rejected rows
+    Import {
+        file: PathBuf,
+        /// File format; detected from the extension (.csv, .json, .jsonl) by default
+        #[arg(long, value_enum)]
+        format: Option<FileFormat>,
+        /// Import in the background, printing the operation to follow with `operation watch`
+        #[arg(long)]
+        background: bool,
+    },
+    /// Publish the draft results of one or more exams to their students
+    Publish {
+        #[arg(required = true)]
+        exam_ids: Vec<String>,
+        /// Publish in the background, printing the operation to follow with `operation watch`
+        #[arg(long)]
+        background: bool,
+    },
+    /// Show aggregate statistics for an exam
+    Stats {
+        exam_id: String,
+    },
+    /// Show a student's transcript and GPA
+    Transcript {
+        student_id: String,
+    },
+    /// Show every recorded change to a student's results (admin only)
+    Audit {
+        student_id: String,
+        exam_id: Option<String>,
+    },
+    /// Print result changes as they happen, until interrupted
+    Watch {
+        #[arg(long, required_unless_present = "exam")]
+        student: Option<String>,
+        #[arg(long)]
+        exam: Option<String>,
+    },
+    /// Grade answers live, e.g. `grade math101 q1=4 q2=12`
+    Grade {
+        exam_id: String,
+        /// Answers as question_id=answer
+        #[arg(required = true, value_parser = parse_answer)]
+        answers: Vec<(String, String)>,
+    },
+    /// Manage the exam catalog
+    #[command(subcommand)]
+    Exam(ExamCommand),
+    /// Manage the student registry
+    #[command(subcommand)]
+    Student(StudentCommand),
+    /// File and resolve regrade requests
+    #[command(subcommand)]
+    Appeal(AppealCommand),
+    /// Manage HTTP callbacks for result events (admin only)
+    #[command(subcommand)]
+    Webhook(WebhookCommand),
+    /// Back up and restore the institution's store on the server (admin only)
+    #[command(subcommand)]
+    Snapshot(SnapshotCommand),
+    /// Inspect and control the running server (`*/admin` tokens only, with --institution)
+    #[command(subcommand)]
+    Server(ServerCommand),
+    /// Follow background imports, exports and publishes
+    #[command(subcommand)]
+    Operation(OperationCommand),
+}
+
+#[derive(Debug, Args)]
+pub struct ResultArgs {
+    pub student_id: String,
+    pub exam_id: String,
+}
+
+// Which results to list or export; every filter is optional.
+#[derive(Debug, Args)]
+pub struct FilterArgs {
+    #[arg(long)]
+    pub student: Option<String>,
+    #[arg(long)]
+    pub exam: Option<String>,
+    #[arg(long)]
+    pub subject: Option<String>,
+    /// Only results with this grade or better
+    #[arg(long)]
+    pub min_grade: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct FieldArgs {
+    /// Only fetch these result fields, e.g. `--fields grade,marks_obtained`
+    #[arg(long, value_delimiter = ',')]
+    pub fields: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum FileFormat {
+    /// A header row, then student_id, exam_id, marks_obtained and optional
+    /// total_marks, student_name, subject and internal_comment columns
+    Csv,
+    /// ExamResult objects, one per line; imports also accept a JSON array
+    Json,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ExamCommand {
+    /// Add an exam to the catalog
+    Create(ExamArgs),
+    /// Fetch one exam
+    Get { exam_id: String },
+    /// List exams, optionally for one subject
+    List {
+        #[arg(long)]
+        subject: Option<String>,
+    },
+    /// Replace an existing exam's subject, total marks and date
+    Update(ExamArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct ExamArgs {
+    pub exam_id: String,
+    pub subject: String,
+    pub total_marks: i32,
+    /// Exam date as YYYY-MM-DD
+    pub date: String,
+    /// When results may first be submitted, as an HTTP date
+    /// (e.g. "Tue, 03 Jun 2025 09:00:00 GMT")
+    #[arg(long, value_parser = parse_time)]
+    pub opens_at: Option<i64>,
+    /// When the exam sitting ends, as an HTTP date
+    #[arg(long, value_parser = parse_time)]
+    pub closes_at: Option<i64>,
+    /// When students may see their results, as an HTTP date
+    #[arg(long, value_parser = parse_time)]
+    pub grades_released_at: Option<i64>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum StudentCommand {
+    /// Register a student
+    Register {
+        student_id: String,
+        name: String,
+        #[arg(long, default_value = "")]
+        email: String,
+    },
+    /// Fetch one student
+    Get { student_id: String },
+    /// List registered students
+    List,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AppealCommand {
+    /// Ask for a published result to be regraded
+    File {
+        #[command(flatten)]
+        key: ResultArgs,
+        /// Why the result should be regraded
+        reason: String,
+    },
+    /// List appeals matching the given filters
+    List {
+        #[arg(long)]
+        student: Option<String>,
+        #[arg(long)]
+        exam: Option<String>,
+        #[arg(long, value_enum)]
+        state: Option<AppealStateArg>,
+    },
+    /// Take an open appeal under review
+    Review { appeal_id: i64 },
+    /// Resolve an appeal under review, approving it if --marks is given
+    Resolve {
+        appeal_id: i64,
+        /// The reviewer's explanation
+        resolution: String,
+        /// Marks the result is corrected to
+        #[arg(long)]
+        marks: Option<i32>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum AppealStateArg {
+    Open,
+    UnderReview,
+    Resolved,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum WebhookCommand {
+    /// Register a URL to receive signed result events
+    Register {
+        url: String,
+        /// Only send this event; repeat for several. Every event by default
+        #[arg(long = "event", value_enum)]
+        events: Vec<WebhookEventArg>,
+        /// Signing secret, at least 16 characters; generated and printed if omitted
+        #[arg(long, env = "EXAM_WEBHOOK_SECRET", hide_env_values = true)]
+        secret: Option<String>,
+    },
+    /// List registered webhooks
+    List,
+    /// Stop sending events to a webhook
+    Delete { webhook_id: i64 },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SnapshotCommand {
+    /// Write everything stored to a new snapshot file on the server
+    Create {
+        /// Named from the current time if omitted
+        name: Option<String>,
+    },
+    /// List snapshots, newest first
+    List,
+    /// Replace everything stored with a snapshot's contents
+    Restore { name: String },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ServerCommand {
+    /// Print the settings in effect
+    Config,
+    /// List open server streams and the connections they are on
+    Streams,
+    /// Turn maintenance mode on (reads only) or off
+    Maintenance {
+        #[arg(value_enum)]
+        state: Toggle,
+        /// Shown to callers whose writes are rejected
+        #[arg(long, default_value = "")]
+        reason: String,
+    },
+    /// Snapshot every institution now
+    Snapshot,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Toggle {
+    On,
+    Off,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum WebhookEventArg {
+    Published,
+    Corrected,
+}
+
+fn parse_score(value: &str) -> Result<QuestionScore, String> {
+    let invalid = || format!("expected question_id=marks/max_marks, got {:?}", value);
+
+    let (question_id, score) = value.split_once('=').ok_or_else(invalid)?;
+    let (marks, max_marks) = score.split_once('/').ok_or_else(invalid)?;
+
+    Ok(QuestionScore {
+        question_id: question_id.to_string(),
+        marks: marks.parse().map_err(|_| invalid())?,
+        max_marks: max_marks.parse().map_err(|_| invalid())?,
+        comment: String::new(),
+    })
+}
+
+fn parse_key(value: &str) -> Result<(String, String), String> {
+    value
+        .split_once('/')
+        .map(|(student_id, exam_id)| (student_id.to_string(), exam_id.to_string()))
+        .ok_or_else(|| format!("expected student_id/exam_id, got {:?}", value))
+}
+
+fn parse_answer(value: &str) -> Result<(String, String), String> {
+    value
+        .split_once('=')
+        .map(|(question, answer)| (question.to_string(), answer.to_string()))
+        .ok_or_else(|| format!("expected question_id=answer, got {:?}", value))
+}
+
+// An HTTP date as Unix milliseconds, the unit of exam timestamps.
+fn parse_time(value: &str) -> Result<i64, String> {
+    let time = httpdate::parse_http_date(value)
+        .map_err(|_| format!("expected an HTTP date like \"Tue, 03 Jun 2025 09:00:00 GMT\", got {:?}", value))?;
+    let elapsed = time.duration_since(UNIX_EPOCH).map_err(|_| format!("{:?} is before 1970", value))?;
+    Ok(elapsed.as_millis() as i64)
+}
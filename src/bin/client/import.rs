@@ -0,0 +1,192 @@
+use std::error::Error;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use exam_service::exam_service::{ExamResult, SubmitExamResultsResponse};
+
+use crate::cli::FileFormat;
+
+// One row of a CSV export. Only the IDs and marks are required; the server
+// fills in names, subjects and totals from its roster and catalog.
+#[derive(Debug, Deserialize)]
+struct CsvRow {
+    student_id: String,
+    exam_id: String,
+    marks_obtained: i32,
+    #[serde(default)]
+    total_marks: Option<i32>,
+    #[serde(default)]
+    student_name: String,
+    #[serde(default)]
+    subject: String,
+    #[serde(default)]
+    internal_comment: String,
+}
+
+impl From<CsvRow> for ExamResult {
+    fn from(row: CsvRow) -> Self {
+        Self {
+            student_id: row.student_id,
+            exam_id: row.exam_id,
+            student_name: row.student_name,
+            subject: row.subject,
+            marks_obtained: row.marks_obtained,
+            total_marks: row.total_marks.unwrap_or_default(),
+            internal_comment: row.internal_comment,
+            ..Default::default()
+        }
+    }
+}
+
+// A row that could not be imported, wherever it was rejected.
+#[derive(Debug, Serialize)]
+pub struct RowError {
+    // Line number for CSV and JSON lines, 1-based position in a JSON array
+    pub row: usize,
+    pub student_id: String,
+    pub exam_id: String,
+    pub message: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ImportSummary {
+    pub accepted: usize,
+    pub rejected: usize,
+    pub errors: Vec<RowError>,
+}
+
+// Results parsed from a file, each with the row it came from, plus the rows
+// that failed to parse and are never sent.
+#[derive(Debug, Default)]
+pub struct ParsedFile {
+    pub results: Vec<(usize, ExamResult)>,
+    pub errors: Vec<RowError>,
+}
+
+impl ParsedFile {
+    fn push(&mut self, row: usize, result: ExamResult) {
+        if result.student_id.is_empty() || result.exam_id.is_empty() {
+            self.reject(row, &result, "student_id and exam_id are required".to_string());
+        } else {
+            self.results.push((row, result));
+        }
+    }
+
+    fn reject(&mut self, row: usize, result: &ExamResult, message: String) {
+        self.errors.push(RowError {
+            row,
+            student_id: result.student_id.clone(),
+            exam_id: result.exam_id.clone(),
+            message,
+        });
+    }
+}
+
+// Picks the format from the file extension when none is given.
+pub fn detect_format(path: &Path, format: Option<FileFormat>) -> Result<FileFormat, Box<dyn Error>> {
+    if let Some(format) = format {
+        return Ok(format);
+    }
+
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("csv") => Ok(FileFormat::Csv),
+        Some(ext) if ext.eq_ignore_ascii_case("json") || ext.eq_ignore_ascii_case("jsonl") => Ok(FileFormat::Json),
+        _ => Err(format!("cannot tell the format of {}; pass --format", path.display()).into()),
+    }
+}
+
+pub fn parse(contents: &str, format: FileFormat) -> Result<ParsedFile, Box<dyn Error>> {
+    match format {
+        FileFormat::Csv => parse_csv(contents),
+        FileFormat::Json => parse_json(contents),
+    }
+}
+
+// CSV with a header row naming the columns, in any order.
+fn parse_csv(contents: &str) -> Result<ParsedFile, Box<dyn Error>> {
+    let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(contents.as_bytes());
+    let headers = reader.headers()?.clone();
+    let mut parsed = ParsedFile::default();
+
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(err) => {
+                let row = err.position().map_or(0, |position| position.line() as usize);
+                parsed.reject(row, &ExamResult::default(), err.to_string());
+                continue;
+            }
+        };
+
+        let row = record.position().map_or(0, |position| position.line() as usize);
+
+        match record.deserialize::<CsvRow>(Some(&headers)) {
+            Ok(csv_row) => parsed.push(row, csv_row.into()),
+            Err(err) => {
+                // Still name the result in the report when the IDs are readable
+                let partial = ExamResult {
+                    student_id: record.get(column(&headers, "student_id")).unwrap_or_default().to_string(),
+                    exam_id: record.get(column(&headers, "exam_id")).unwrap_or_default().to_string(),
+                    ..Default::default()
+                };
+                parsed.reject(row, &partial, err.to_string());
+            }
+        }
+    }
+
+    Ok(parsed)
+}
+
+fn column(headers: &csv::StringRecord, name: &str) -> usize {
+    headers.iter().position(|header| header == name).unwrap_or(usize::MAX)
+}
+
+// A JSON array of ExamResult objects, or one object per line (JSON lines).
+// Field names match the proto; omitted fields take their defaults.
+fn parse_json(contents: &str) -> Result<ParsedFile, Box<dyn Error>> {
+    let mut parsed = ParsedFile::default();
+
+    if contents.trim_start().starts_with('[') {
+        let values: Vec<serde_json::Value> = serde_json::from_str(contents)?;
+
+        for (index, value) in values.into_iter().enumerate() {
+            match serde_json::from_value(value) {
+                Ok(result) => parsed.push(index + 1, result),
+                Err(err) => parsed.reject(index + 1, &ExamResult::default(), err.to_string()),
+            }
+        }
+
+        return Ok(parsed);
+    }
+
+    for (index, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match serde_json::from_str(line) {
+            Ok(result) => parsed.push(index + 1, result),
+            Err(err) => parsed.reject(index + 1, &ExamResult::default(), err.to_string()),
+        }
+    }
+
+    Ok(parsed)
+}
+
+// Merges the server's per-record report into the local one. The server numbers
+// records by their position in the upload, which `rows` maps back to the file.
+pub fn summarize(rows: &[usize], parse_errors: Vec<RowError>, response: SubmitExamResultsResponse) -> ImportSummary {
+    let mut errors = parse_errors;
+    errors.extend(response.errors.into_iter().map(|error| RowError {
+        row: rows.get(error.index as usize).copied().unwrap_or_default(),
+        student_id: error.student_id,
+        exam_id: error.exam_id,
+        message: error.message,
+    }));
+    errors.sort_by_key(|error| error.row);
+
+    ImportSummary {
+        accepted: response.accepted_count as usize,
+        rejected: errors.len(),
+        errors,
+    }
+}
@@ -0,0 +1,469 @@
+use std::error::Error;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::ExitCode;
+use clap::Parser;
+use futures::StreamExt;
+use tonic::codec::CompressionEncoding;
+use tonic::transport::{Certificate, ClientTlsConfig, Endpoint, Identity};
+use tonic::{Status, Streaming};
+use tracing::{info_span, Instrument};
+use exam_service::appeal::AppealState;
+use exam_service::client::{read_mask, BearerToken, ExamClient, RetryPolicy};
+use exam_service::exam_admin::Exam;
+use exam_service::exam_service::{AnswerSubmission, ExamResult, ExportChunk, ExportFormat, ListExamResultsRequest, OperationState};
+use exam_service::student::Student;
+use exam_service::config::TelemetryConfig;
+use exam_service::telemetry::{init_trace_export, REQUEST_ID_HEADER};
+use exam_service::webhook::WebhookEvent;
+
+mod cli;
+mod import;
+mod output;
+
+use cli::{AppealCommand, AppealStateArg, Cli, Command, Compression, ConnectionArgs, ExamArgs, ExamCommand, FieldArgs, FileFormat, FilterArgs, OperationCommand, ResultArgs, ServerCommand, SnapshotCommand, StudentCommand, Toggle, WebhookCommand, WebhookEventArg};
+use output::Printer;
+
+// Builds one endpoint per server address, enabling TLS (and optionally mutual TLS)
+// when a CA is given. A bare host:port gets the scheme matching the TLS setting.
+fn endpoints(args: &ConnectionArgs) -> Result<Vec<Endpoint>, Box<dyn Error>> {
+    let tls = match &args.ca {
+        Some(ca_path) => {
+            let mut tls = ClientTlsConfig::new()
+                .ca_certificate(Certificate::from_pem(fs::read(ca_path)?))
+                .domain_name(args.domain.clone());
+
+            if let (Some(cert), Some(key)) = (&args.client_cert, &args.client_key) {
+                tls = tls.identity(Identity::from_pem(fs::read(cert)?, fs::read(key)?));
+            }
+            Some(tls)
+        }
+        None => None,
+    };
+
+    args.addr
+        .iter()
+        .map(|addr| {
+            let url = match (addr.contains("://"), tls.is_some()) {
+                (true, _) => addr.clone(),
+                (false, true) => format!("https://{}", addr),
+                (false, false) => format!("http://{}", addr),
+            };
+
+            let endpoint = Endpoint::from_shared(url)?;
+            match &tls {
+                Some(tls) => Ok(endpoint.tls_config(tls.clone())?),
+                None => Ok(endpoint),
+            }
+        })
+        .collect()
+}
+
+fn non_empty(value: Option<String>) -> String {
+    value.unwrap_or_default()
+}
+
+fn list_request(filter: FilterArgs) -> ListExamResultsRequest {
+    ListExamResultsRequest {
+        student_id: non_empty(filter.student),
+        exam_id: non_empty(filter.exam),
+        subject: non_empty(filter.subject),
+        min_grade: non_empty(filter.min_grade),
+        read_mask: None,
+    }
+}
+
+fn field_names(args: &FieldArgs) -> Vec<&str> {
+    args.fields.iter().map(String::as_str).collect()
+}
+
+fn exam(args: ExamArgs) -> Exam {
+    Exam {
+        exam_id: args.exam_id,
+        subject: args.subject,
+        total_marks: args.total_marks,
+        date: args.date,
+        opens_at_ms: args.opens_at.unwrap_or_default(),
+        closes_at_ms: args.closes_at.unwrap_or_default(),
+        grades_released_at_ms: args.grades_released_at.unwrap_or_default(),
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), Box<dyn Error>> {
+    let retry_policy = RetryPolicy {
+        max_attempts: cli.connection.max_attempts,
+        ..RetryPolicy::default()
+    };
+    let mut token = BearerToken::new(&cli.connection.token)?;
+    if let Some(institution) = &cli.connection.institution {
+        token = token.with_institution(institution)?;
+    }
+    let mut client = ExamClient::connect_balanced_with(endpoints(&cli.connection)?, token)
+        .await?
+        .with_retry_policy(retry_policy);
+
+    if let Some(compression) = cli.connection.compress {
+        client = client.with_compression(match compression {
+            Compression::Gzip => CompressionEncoding::Gzip,
+            Compression::Zstd => CompressionEncoding::Zstd,
+        });
+    }
+    let printer = Printer::new(cli.output);
+
+    match cli.command {
+        Command::Get {
+            key: ResultArgs { student_id, exam_id },
+            fields,
+        } => {
+            printer.result(&client.get_result_fields(&student_id, &exam_id, &field_names(&fields)).await?);
+        }
+
+        Command::BatchGet { keys } => {
+            let keys: Vec<_> = keys.iter().map(|(student_id, exam_id)| (student_id.as_str(), exam_id.as_str())).collect();
+            printer.many(&client.batch_get_results(&keys).await?);
+        }
+
+        Command::GetStream { student_id, exam_id } => {
+            let mut stream = client
+                .get_result_stream(&student_id, exam_id.as_deref().unwrap_or_default())
+                .await?;
+
+            let rows = printer.stream();
+            while let Some(response) = stream.next().await {
+                rows.print(&response?);
+            }
+        }
+
+        Command::Submit {
+            key,
+            marks,
+            total,
+            name,
+            subject,
+            comment,
+        } => {
+            let result = ExamResult {
+                student_id: key.student_id,
+                exam_id: key.exam_id,
+                student_name: non_empty(name),
+                subject: non_empty(subject),
+                marks_obtained: marks,
+                total_marks: total.unwrap_or_default(),
+                internal_comment: non_empty(comment),
+                // The grade is computed by the server from the marks
+                ..Default::default()
+            };
+
+            if let Some(result) = &client.submit_result(result).await?.result {
+                printer.result(result);
+            }
+        }
+
+        Command::Scores { key, scores } => {
+            let response = client.submit_question_scores(&key.student_id, &key.exam_id, scores).await?;
+
+            if let Some(result) = &response.result {
+                printer.result(result);
+            }
+        }
+
+        Command::Correct { key, marks, expected_version, reason } => {
+            let expected_version = match expected_version {
+                Some(version) => version,
+                None => client.get_result(&key.student_id, &key.exam_id).await?.version,
+            };
+            let response = client
+                .correct_result(&key.student_id, &key.exam_id, marks, expected_version, &reason)
+                .await?;
+            let results: Vec<_> = response.previous.into_iter().chain(response.current).collect();
+            printer.many(&results);
+        }
+
+        Command::Delete { key, reason } => {
+            if let Some(previous) = &client.delete_result(&key.student_id, &key.exam_id, &reason).await? {
+                printer.one(previous);
+            }
+        }
+
+        Command::Deleted { student, exam } => {
+            printer.many(&client.list_deleted_results(&non_empty(student), &non_empty(exam)).await?);
+        }
+
+        Command::Undelete { key, reason } => {
+            if let Some(result) = &client.undelete_result(&key.student_id, &key.exam_id, &reason).await? {
+                printer.one(result);
+            }
+        }
+
+        Command::List { filter, fields } => {
+            let request = ListExamResultsRequest {
+                read_mask: read_mask(&field_names(&fields)),
+                ..list_request(filter)
+            };
+            let mut stream = client.list_results(request).await?;
+            let mut results = Vec::new();
+            while let Some(result) = stream.next().await {
+                results.push(result?);
+            }
+            printer.many(&results);
+        }
+
+        Command::Search { query, limit } => {
+            let mut stream = client.search_results(&query, limit).await?;
+            let mut matches = Vec::new();
+            while let Some(found) = stream.next().await {
+                matches.push(found?);
+            }
+            printer.many(&matches);
+        }
+
+        Command::Export {
+            filter,
+            format,
+            out,
+            background,
+        } => {
+            let format = match format {
+                FileFormat::Csv => ExportFormat::Csv,
+                FileFormat::Json => ExportFormat::JsonLines,
+            };
+            if background {
+                printer.one(&client.start_export(list_request(filter), format).await?);
+            } else {
+                write_chunks(client.export_results(list_request(filter), format).await?, out.as_deref()).await?;
+            }
+        }
+
+        Command::Import {
+            file,
+            format,
+            background,
+        } => {
+            let format = import::detect_format(&file, format)?;
+            let parsed = import::parse(&fs::read_to_string(&file)?, format)?;
+
+            let (rows, results): (Vec<usize>, Vec<ExamResult>) = parsed.results.into_iter().unzip();
+            if background {
+                // The operation only reports rows the server rejects, so unreadable ones stop it here
+                if !parsed.errors.is_empty() {
+                    printer.many(&parsed.errors);
+                    return Err(format!("{} rows could not be read", parsed.errors.len()).into());
+                }
+                printer.one(&client.start_import(results).await?);
+                return Ok(());
+            }
+            let response = client.submit_results(futures::stream::iter(results)).await?;
+            let summary = import::summarize(&rows, parsed.errors, response);
+
+            printer.import(&summary);
+            if summary.rejected > 0 {
+                return Err(format!("{} rows were rejected", summary.rejected).into());
+            }
+        }
+
+        Command::Publish { exam_ids, background } => {
+            if background {
+                let exam_ids: Vec<&str> = exam_ids.iter().map(String::as_str).collect();
+                printer.one(&client.start_publish(&exam_ids).await?);
+            } else {
+                let mut published = Vec::new();
+                for exam_id in &exam_ids {
+                    published.push(client.publish_results(exam_id).await?);
+                }
+                printer.many(&published);
+            }
+        }
+
+        Command::Stats { exam_id } => printer.statistics(&client.statistics(&exam_id).await?),
+
+        Command::Transcript { student_id } => printer.transcript(&client.transcript(&student_id).await?),
+
+        Command::Audit { student_id, exam_id } => {
+            printer.many(&client.audit_trail(&student_id, &non_empty(exam_id)).await?)
+        }
+
+        Command::Watch { student, exam } => {
+            let mut changes = client.watch(&non_empty(student), &non_empty(exam)).await?;
+
+            let rows = printer.stream();
+            while let Some(change) = changes.next().await {
+                rows.print(&change?);
+            }
+        }
+
+        Command::Grade { exam_id, answers } => {
+            let answers = answers.into_iter().map(move |(question_id, answer)| AnswerSubmission {
+                exam_id: exam_id.clone(),
+                question_id,
+                answer,
+            });
+
+            let mut updates = client.grade_session(tokio_stream::iter(answers)).await?;
+
+            let rows = printer.stream();
+            while let Some(update) = updates.next().await {
+                rows.print(&update?);
+            }
+        }
+
+        Command::Exam(command) => match command {
+            ExamCommand::Create(args) => printer.one(&client.create_exam(exam(args)).await?),
+            ExamCommand::Get { exam_id } => printer.one(&client.get_exam(&exam_id).await?),
+            ExamCommand::List { subject } => printer.many(&client.list_exams(&non_empty(subject)).await?),
+            ExamCommand::Update(args) => printer.one(&client.update_exam(exam(args)).await?),
+        },
+
+        Command::Student(command) => match command {
+            StudentCommand::Register {
+                student_id,
+                name,
+                email,
+            } => {
+                let student = Student {
+                    student_id,
+                    name,
+                    email,
+                };
+                printer.one(&client.register_student(student).await?);
+            }
+            StudentCommand::Get { student_id } => printer.one(&client.get_student(&student_id).await?),
+            StudentCommand::List => printer.many(&client.list_students().await?),
+        },
+
+        Command::Appeal(command) => match command {
+            AppealCommand::File { key, reason } => {
+                printer.one(&client.file_appeal(&key.student_id, &key.exam_id, &reason).await?)
+            }
+            AppealCommand::List { student, exam, state } => {
+                let state = match state {
+                    None => AppealState::Unspecified,
+                    Some(AppealStateArg::Open) => AppealState::Open,
+                    Some(AppealStateArg::UnderReview) => AppealState::UnderReview,
+                    Some(AppealStateArg::Resolved) => AppealState::Resolved,
+                };
+                printer.many(&client.list_appeals(&non_empty(student), &non_empty(exam), state).await?);
+            }
+            AppealCommand::Review { appeal_id } => printer.one(&client.review_appeal(appeal_id).await?),
+            AppealCommand::Resolve {
+                appeal_id,
+                resolution,
+                marks,
+            } => {
+                let appeal = client
+                    .resolve_appeal(appeal_id, marks.is_some(), marks.unwrap_or_default(), &resolution)
+                    .await?;
+                printer.one(&appeal);
+            }
+        },
+
+        Command::Webhook(command) => match command {
+            WebhookCommand::Register { url, events, secret } => {
+                let events: Vec<_> = events
+                    .into_iter()
+                    .map(|event| match event {
+                        WebhookEventArg::Published => WebhookEvent::ResultPublished,
+                        WebhookEventArg::Corrected => WebhookEvent::ResultCorrected,
+                    })
+                    .collect();
+                printer.one(&client.register_webhook(&url, &events, &non_empty(secret)).await?);
+            }
+            WebhookCommand::List => printer.many(&client.list_webhooks().await?),
+            WebhookCommand::Delete { webhook_id } => client.delete_webhook(webhook_id).await?,
+        },
+
+        Command::Snapshot(command) => match command {
+            SnapshotCommand::Create { name } => printer.one(&client.create_snapshot(&non_empty(name)).await?),
+            SnapshotCommand::List => printer.many(&client.list_snapshots().await?),
+            SnapshotCommand::Restore { name } => printer.one(&client.restore_snapshot(&name).await?),
+        },
+
+        Command::Server(command) => match command {
+            ServerCommand::Config => printer.config(&client.get_config().await?),
+            ServerCommand::Streams => printer.many(&client.list_streams().await?.streams),
+            ServerCommand::Maintenance { state, reason } => {
+                printer.one(&client.set_maintenance_mode(state == Toggle::On, &reason).await?)
+            }
+            ServerCommand::Snapshot => printer.many(&client.trigger_snapshot().await?),
+        },
+
+        Command::Operation(command) => match command {
+            OperationCommand::Watch { operation_id } => {
+                let mut updates = client.watch_operation(&operation_id).await?;
+
+                let rows = printer.stream();
+                let mut last = None;
+                while let Some(operation) = updates.next().await {
+                    let operation = operation?;
+                    rows.print(&operation);
+                    last = Some(operation);
+                }
+
+                if let Some(operation) = last.filter(|operation| operation.state() == OperationState::Failed) {
+                    return Err(format!("operation failed: {}", operation.message).into());
+                }
+            }
+            OperationCommand::Download { operation_id, out } => {
+                write_chunks(client.download_export(&operation_id).await?, out.as_deref()).await?
+            }
+        },
+    }
+
+    Ok(())
+}
+
+// Writes a stream of export chunks to `out`, or standard output without one.
+async fn write_chunks(mut chunks: Streaming<ExportChunk>, out: Option<&Path>) -> Result<(), Box<dyn Error>> {
+    let mut writer: Box<dyn Write> = match out {
+        Some(path) => Box::new(fs::File::create(path)?),
+        None => Box::new(io::stdout().lock()),
+    };
+    while let Some(chunk) = chunks.next().await {
+        writer.write_all(&chunk?.data)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let telemetry = TelemetryConfig {
+        otlp_endpoint: cli.connection.otlp_endpoint.clone().unwrap_or_default(),
+        service_name: "exam-client".to_string(),
+        ..TelemetryConfig::default()
+    };
+    let tracing = match init_trace_export(&telemetry) {
+        Ok(tracing) => tracing,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // One trace per command, however many calls it makes
+    let outcome = run(cli).instrument(info_span!("command")).await;
+    tracing.shutdown().await;
+
+    match outcome {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            // Show gRPC failures as "CODE: message" rather than the full Status debug dump,
+            // with the request ID to find the call in the server's logs
+            match err.downcast_ref::<Status>() {
+                Some(status) => match status.metadata().get(REQUEST_ID_HEADER).and_then(|id| id.to_str().ok()) {
+                    Some(request_id) => eprintln!(
+                        "error: {:?}: {} (request id {})",
+                        status.code(),
+                        status.message(),
+                        request_id
+                    ),
+                    None => eprintln!("error: {:?}: {}", status.code(), status.message()),
+                },
+                None => eprintln!("error: {}", err),
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,521 @@
+use std::time::{Duration, UNIX_EPOCH};
+use serde::Serialize;
+use tonic::Code;
+
+use crate::cli::OutputFormat;
+use crate::import::{ImportSummary, RowError};
+use exam_service::admin::{GetConfigResponse, InstitutionSnapshot, MaintenanceMode, OpenStream};
+use exam_service::appeal::{Appeal, AppealState};
+use exam_service::exam_admin::Exam;
+use exam_service::exam_service::{
+    AuditRecord, BatchGetResult, DeletedResult, ExamStatistics, GetExamResultResponse, GradeUpdate, Operation, PublishExamResultsResponse, QuestionScore,
+    ResultChange, SearchMatch, Transcript,
+};
+use exam_service::google::protobuf::Timestamp;
+use exam_service::model::ms_from_timestamp;
+use exam_service::snapshot::SnapshotInfo;
+use exam_service::student::Student;
+use exam_service::webhook::{Webhook, WebhookEvent};
+
+// A message that can be printed as one row of a table.
+pub trait Row: Serialize {
+    const HEADERS: &'static [&'static str];
+
+    fn cells(&self) -> Vec<String>;
+}
+
+impl Row for GetExamResultResponse {
+    const HEADERS: &'static [&'static str] =
+        &["STUDENT", "EXAM", "NAME", "SUBJECT", "MARKS", "GRADE", "VERSION", "STATUS", "DATE", "GRADED", "COMMENT"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.student_id.clone(),
+            self.exam_id.clone(),
+            self.student_name.clone(),
+            self.subject.clone(),
+            format!("{}/{}", self.marks_obtained, self.total_marks),
+            self.grade.clone(),
+            self.version.to_string(),
+            self.status().as_str_name().to_string(),
+            timestamp_cell(self.exam_date.as_ref()),
+            timestamp_cell(self.graded_at.as_ref()),
+            self.internal_comment.clone(),
+        ]
+    }
+}
+
+impl Row for BatchGetResult {
+    const HEADERS: &'static [&'static str] = &["STUDENT", "EXAM", "NAME", "MARKS", "GRADE", "ERROR"];
+
+    fn cells(&self) -> Vec<String> {
+        let key = self.key.clone().unwrap_or_default();
+        let Some(result) = &self.result else {
+            let error = format!("{:?}: {}", Code::from_i32(self.code), self.message);
+            return vec![key.student_id, key.exam_id, "-".into(), "-".into(), "-".into(), error];
+        };
+
+        vec![
+            key.student_id,
+            key.exam_id,
+            result.student_name.clone(),
+            format!("{}/{}", result.marks_obtained, result.total_marks),
+            result.grade.clone(),
+            String::new(),
+        ]
+    }
+}
+
+impl Row for SearchMatch {
+    const HEADERS: &'static [&'static str] = &["SCORE", "STUDENT", "EXAM", "NAME", "SUBJECT", "GRADE"];
+
+    fn cells(&self) -> Vec<String> {
+        let result = self.result.clone().unwrap_or_default();
+        vec![
+            self.score.to_string(),
+            result.student_id,
+            result.exam_id,
+            result.student_name,
+            result.subject,
+            result.grade,
+        ]
+    }
+}
+
+impl Row for DeletedResult {
+    const HEADERS: &'static [&'static str] = &["STUDENT", "EXAM", "NAME", "MARKS", "GRADE", "VERSION", "DELETED"];
+
+    fn cells(&self) -> Vec<String> {
+        let result = self.result.clone().unwrap_or_default();
+        let deleted = UNIX_EPOCH + Duration::from_millis(self.deleted_at_ms.max(0) as u64);
+
+        vec![
+            result.student_id,
+            result.exam_id,
+            result.student_name,
+            format!("{}/{}", result.marks_obtained, result.total_marks),
+            result.grade,
+            result.version.to_string(),
+            httpdate::fmt_http_date(deleted),
+        ]
+    }
+}
+
+impl Row for QuestionScore {
+    const HEADERS: &'static [&'static str] = &["QUESTION", "MARKS", "COMMENT"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.question_id.clone(),
+            format!("{}/{}", self.marks, self.max_marks),
+            self.comment.clone(),
+        ]
+    }
+}
+
+impl Row for Exam {
+    const HEADERS: &'static [&'static str] = &["EXAM", "SUBJECT", "TOTAL", "DATE", "OPENS", "CLOSES", "RELEASED"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.exam_id.clone(),
+            self.subject.clone(),
+            self.total_marks.to_string(),
+            self.date.clone(),
+            time_cell(self.opens_at_ms),
+            time_cell(self.closes_at_ms),
+            time_cell(self.grades_released_at_ms),
+        ]
+    }
+}
+
+// Exam timestamps as HTTP dates; unset ones as "-".
+fn time_cell(ms: i64) -> String {
+    if ms <= 0 {
+        return "-".to_string();
+    }
+    httpdate::fmt_http_date(UNIX_EPOCH + Duration::from_millis(ms as u64))
+}
+
+fn timestamp_cell(timestamp: Option<&Timestamp>) -> String {
+    time_cell(timestamp.map_or(0, ms_from_timestamp))
+}
+
+impl Row for PublishExamResultsResponse {
+    const HEADERS: &'static [&'static str] = &["PUBLISHED"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![self.published_count.to_string()]
+    }
+}
+
+impl Row for Operation {
+    const HEADERS: &'static [&'static str] = &["OPERATION", "KIND", "STATE", "DONE", "PROCESSED", "RESULTS", "ERRORS", "MESSAGE"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.operation_id.clone(),
+            self.kind().as_str_name().to_string(),
+            self.state().as_str_name().to_string(),
+            format!("{}%", self.percent_done),
+            format!("{}/{}", self.processed_count, self.total_count),
+            self.result_count.to_string(),
+            self.errors.len().to_string(),
+            self.message.clone(),
+        ]
+    }
+}
+
+impl Row for Student {
+    const HEADERS: &'static [&'static str] = &["STUDENT", "NAME", "EMAIL"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![self.student_id.clone(), self.name.clone(), self.email.clone()]
+    }
+}
+
+impl Row for Appeal {
+    const HEADERS: &'static [&'static str] =
+        &["APPEAL", "STUDENT", "EXAM", "STATE", "FILED", "MARKS", "REVIEWER", "REASON", "RESOLUTION"];
+
+    fn cells(&self) -> Vec<String> {
+        let marks = match self.state() {
+            AppealState::Resolved => format!("{} -> {}", self.original_marks, self.revised_marks),
+            _ => self.original_marks.to_string(),
+        };
+
+        vec![
+            self.appeal_id.to_string(),
+            self.student_id.clone(),
+            self.exam_id.clone(),
+            self.state().as_str_name().to_string(),
+            time_cell(self.filed_at_ms),
+            marks,
+            self.reviewer.clone(),
+            self.reason.clone(),
+            self.resolution.clone(),
+        ]
+    }
+}
+
+impl Row for Webhook {
+    const HEADERS: &'static [&'static str] = &["WEBHOOK", "URL", "EVENTS", "CREATED BY", "CREATED", "SECRET"];
+
+    fn cells(&self) -> Vec<String> {
+        let events = match self.events.as_slice() {
+            [] => "*".to_string(),
+            events => events
+                .iter()
+                .map(|&event| WebhookEvent::try_from(event).map_or("?", |event| event.as_str_name()))
+                .collect::<Vec<_>>()
+                .join(","),
+        };
+
+        vec![
+            self.webhook_id.to_string(),
+            self.url.clone(),
+            events,
+            self.created_by.clone(),
+            time_cell(self.created_at_ms),
+            // Only set in the RegisterWebhook response
+            self.secret.clone(),
+        ]
+    }
+}
+
+impl Row for SnapshotInfo {
+    const HEADERS: &'static [&'static str] = &["SNAPSHOT", "CREATED", "BYTES", "AUTOMATIC"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            time_cell(self.created_at_ms),
+            self.size_bytes.to_string(),
+            self.automatic.to_string(),
+        ]
+    }
+}
+
+impl Row for InstitutionSnapshot {
+    const HEADERS: &'static [&'static str] = &["INSTITUTION", "SNAPSHOT", "CREATED", "BYTES"];
+
+    fn cells(&self) -> Vec<String> {
+        let snapshot = self.snapshot.clone().unwrap_or_default();
+        vec![
+            self.institution_id.clone(),
+            snapshot.name,
+            time_cell(snapshot.created_at_ms),
+            snapshot.size_bytes.to_string(),
+        ]
+    }
+}
+
+impl Row for OpenStream {
+    const HEADERS: &'static [&'static str] = &["METHOD", "PEER", "CALLER", "INSTITUTION", "OPENED"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.method.clone(),
+            // Calls through the HTTP gateway have no connection of their own
+            if self.peer.is_empty() { "-".to_string() } else { self.peer.clone() },
+            self.caller.clone(),
+            self.institution_id.clone(),
+            time_cell(self.opened_at_ms),
+        ]
+    }
+}
+
+impl Row for MaintenanceMode {
+    const HEADERS: &'static [&'static str] = &["MAINTENANCE", "SINCE", "BY", "REASON"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            if self.enabled { "on" } else { "off" }.to_string(),
+            time_cell(self.since_ms),
+            self.enabled_by.clone(),
+            self.reason.clone(),
+        ]
+    }
+}
+
+impl Row for ResultChange {
+    const HEADERS: &'static [&'static str] = &["CHANGE", "STUDENT", "EXAM", "MARKS", "GRADE"];
+
+    fn cells(&self) -> Vec<String> {
+        let result = self.result.clone().unwrap_or_default();
+        vec![
+            self.kind().as_str_name().to_string(),
+            result.student_id,
+            result.exam_id,
+            format!("{}/{}", result.marks_obtained, result.total_marks),
+            result.grade,
+        ]
+    }
+}
+
+impl Row for AuditRecord {
+    const HEADERS: &'static [&'static str] =
+        &["SEQ", "TIME", "ACTOR", "ROLE", "ACTION", "EXAM", "BEFORE", "AFTER", "REASON"];
+
+    fn cells(&self) -> Vec<String> {
+        let marks = |result: &Option<GetExamResultResponse>| match result {
+            Some(result) => format!("{}/{} {}", result.marks_obtained, result.total_marks, result.grade),
+            None => "-".to_string(),
+        };
+        let time = UNIX_EPOCH + Duration::from_millis(self.recorded_at_ms.max(0) as u64);
+
+        vec![
+            self.sequence.to_string(),
+            httpdate::fmt_http_date(time),
+            self.actor.clone(),
+            self.role.clone(),
+            self.action().as_str_name().to_string(),
+            self.exam_id.clone(),
+            marks(&self.before),
+            marks(&self.after),
+            self.reason.clone(),
+        ]
+    }
+}
+
+impl Row for RowError {
+    const HEADERS: &'static [&'static str] = &["ROW", "STUDENT", "EXAM", "ERROR"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.row.to_string(),
+            self.student_id.clone(),
+            self.exam_id.clone(),
+            self.message.clone(),
+        ]
+    }
+}
+
+impl Row for GradeUpdate {
+    const HEADERS: &'static [&'static str] = &["QUESTION", "CORRECT", "AWARDED", "TOTAL", "GRADE"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.question_id.clone(),
+            if self.is_final { String::new() } else { self.correct.to_string() },
+            self.marks_awarded.to_string(),
+            format!("{}/{}", self.running_total, self.max_total),
+            self.final_grade.clone(),
+        ]
+    }
+}
+
+// Prints complete responses in the chosen format.
+#[derive(Debug, Clone, Copy)]
+pub struct Printer {
+    format: OutputFormat,
+}
+
+impl Printer {
+    pub fn new(format: OutputFormat) -> Self {
+        Self { format }
+    }
+
+    pub fn one<T: Row>(&self, item: &T) {
+        match self.format {
+            OutputFormat::Json => print_json(item),
+            OutputFormat::Table => print_table(T::HEADERS, vec![item.cells()]),
+        }
+    }
+
+    // A JSON array, or a table sized to fit every row.
+    pub fn many<T: Row>(&self, items: &[T]) {
+        match self.format {
+            OutputFormat::Json => print_json(&items),
+            OutputFormat::Table => print_table(T::HEADERS, items.iter().map(Row::cells).collect()),
+        }
+    }
+
+    // Starts printing a stream whose length is unknown. JSON output is one
+    // document per line; table output uses column widths fixed from the headers.
+    pub fn stream<T: Row>(&self) -> StreamPrinter<T> {
+        let widths: Vec<usize> = T::HEADERS.iter().map(|header| header.len().max(STREAM_COLUMN_WIDTH)).collect();
+
+        if self.format == OutputFormat::Table {
+            println!("{}", format_row(T::HEADERS.iter().map(|h| h.to_string()).collect(), &widths));
+        }
+
+        StreamPrinter {
+            format: self.format,
+            widths,
+            _row: std::marker::PhantomData,
+        }
+    }
+
+    // One result, followed in table form by its per-question breakdown if it has one.
+    pub fn result(&self, result: &GetExamResultResponse) {
+        self.one(result);
+
+        if self.format == OutputFormat::Table && !result.questions.is_empty() {
+            println!();
+            self.many(&result.questions);
+        }
+    }
+
+    pub fn statistics(&self, stats: &ExamStatistics) {
+        if self.format == OutputFormat::Json {
+            return print_json(stats);
+        }
+
+        let distribution = stats
+            .grade_distribution
+            .iter()
+            .map(|entry| format!("{}:{}", entry.grade, entry.count))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let rows = [
+            ("Exam", stats.exam_id.clone()),
+            ("Results", stats.result_count.to_string()),
+            ("Total marks", stats.total_marks.to_string()),
+            ("Mean", format!("{:.2}", stats.mean)),
+            ("Median", format!("{:.2}", stats.median)),
+            ("Std dev", format!("{:.2}", stats.std_dev)),
+            ("Highest", stats.highest.to_string()),
+            ("Lowest", stats.lowest.to_string()),
+            ("25th percentile", format!("{:.2}", stats.percentile_25)),
+            ("75th percentile", format!("{:.2}", stats.percentile_75)),
+            ("Pass rate", format!("{:.1}%", stats.pass_rate * 100.0)),
+            ("Grades", distribution),
+        ];
+
+        print_table(
+            &["STATISTIC", "VALUE"],
+            rows.into_iter().map(|(name, value)| vec![name.to_string(), value]).collect(),
+        );
+    }
+
+    pub fn import(&self, summary: &ImportSummary) {
+        if self.format == OutputFormat::Json {
+            return print_json(summary);
+        }
+
+        println!("{} imported, {} rejected", summary.accepted, summary.rejected);
+        if !summary.errors.is_empty() {
+            println!();
+            self.many(&summary.errors);
+        }
+    }
+
+    // The config is JSON already, so tables show it as it is.
+    pub fn config(&self, config: &GetConfigResponse) {
+        if self.format == OutputFormat::Json {
+            return print_json(config);
+        }
+
+        println!("{}", config.config_json);
+        let maintenance = config.maintenance.clone().unwrap_or_default();
+        println!();
+        print_table(MaintenanceMode::HEADERS, vec![maintenance.cells()]);
+    }
+
+    pub fn transcript(&self, transcript: &Transcript) {
+        if self.format == OutputFormat::Json {
+            return print_json(transcript);
+        }
+
+        println!("{} ({})", transcript.student_name, transcript.student_id);
+        println!(
+            "GPA {:.2}, weighted average {:.1}%",
+            transcript.gpa, transcript.weighted_average
+        );
+        println!();
+        self.many(&transcript.results);
+    }
+}
+
+// Minimum column width for streamed tables, whose rows are not known up front.
+const STREAM_COLUMN_WIDTH: usize = 10;
+
+pub struct StreamPrinter<T> {
+    format: OutputFormat,
+    widths: Vec<usize>,
+    _row: std::marker::PhantomData<T>,
+}
+
+impl<T: Row> StreamPrinter<T> {
+    pub fn print(&self, item: &T) {
+        match self.format {
+            OutputFormat::Json => print_json(item),
+            OutputFormat::Table => println!("{}", format_row(item.cells(), &self.widths)),
+        }
+    }
+}
+
+fn print_json<T: Serialize + ?Sized>(value: &T) {
+    match serde_json::to_string(value) {
+        Ok(json) => println!("{}", json),
+        Err(err) => eprintln!("error: could not encode response as JSON: {}", err),
+    }
+}
+
+fn print_table(headers: &[&str], rows: Vec<Vec<String>>) {
+    let mut widths: Vec<usize> = headers.iter().map(|header| header.len()).collect();
+
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    println!("{}", format_row(headers.iter().map(|h| h.to_string()).collect(), &widths));
+    for row in rows {
+        println!("{}", format_row(row, &widths));
+    }
+}
+
+fn format_row(cells: Vec<String>, widths: &[usize]) -> String {
+    cells
+        .iter()
+        .zip(widths)
+        .map(|(cell, &width)| format!("{:<width$}", cell, width = width))
+        .collect::<Vec<_>>()
+        .join("  ")
+        .trim_end()
+        .to_string()
+}
@@ -0,0 +1,123 @@
+use std::sync::Arc;
+use tracing::info;
+
+use exam_service::config::{ServerConfig, StorageBackend};
+use exam_service::grading::GradingScheme;
+use exam_service::key::InstitutionId;
+use exam_service::server::{serve, ExamServiceImpl};
+use exam_service::store::{
+    CachedExamStore, ExamStore, InMemoryExamStore, ReplicatedExamStore, ReplicationLog, SqliteExamStore, WalExamStore,
+};
+use exam_service::telemetry::init_tracing;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let config = ServerConfig::load()?;
+    let tracing = init_tracing(&config.telemetry)?;
+
+    let served = open_stores(&config).await;
+    // Also after a failure, whose spans are the ones most worth exporting
+    tracing.shutdown().await;
+    served
+}
+
+// Opens each institution's store with the configured backend, then serves them.
+async fn open_stores(config: &ServerConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let grading = match &config.grading_config {
+        Some(path) => GradingScheme::load(path)?,
+        None => GradingScheme::default(),
+    };
+
+    let institutions = config.institutions()?;
+
+    match config.storage.backend {
+        StorageBackend::Sqlite => {
+            let mut stores = Vec::new();
+            for institution in institutions {
+                let path = config.storage.path_for(&institution);
+                info!(%institution, path = %path.display(), "using SQLite store");
+                stores.push((institution, SqliteExamStore::open(&path)?));
+            }
+            run(config, stores, grading).await
+        }
+        StorageBackend::Wal => {
+            let mut stores = Vec::new();
+            for institution in institutions {
+                let path = config.wal.path_for(&institution);
+                info!(%institution, path = %path.display(), "using in-memory store with a write-ahead log");
+                stores.push((institution, WalExamStore::open(&path, &config.wal).await?));
+            }
+            run(config, stores, grading).await
+        }
+        StorageBackend::Memory => {
+            info!("using in-memory stores (set storage.backend = \"sqlite\" or \"wal\" to persist results)");
+            let stores = institutions
+                .into_iter()
+                .map(|institution| (institution, InMemoryExamStore::with_sample_data()))
+                .collect();
+            run(config, stores, grading).await
+        }
+    }
+}
+
+async fn run<S: ExamStore>(
+    config: &ServerConfig,
+    stores: Vec<(InstitutionId, S)>,
+    grading: GradingScheme,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if config.cache.enabled {
+        info!(ttl_secs = config.cache.ttl_secs, "caching results in front of each store");
+        let stores = stores
+            .into_iter()
+            .map(|(institution, store)| (institution, CachedExamStore::new(store, &config.cache)))
+            .collect();
+        return replicate(config, stores, grading).await;
+    }
+
+    replicate(config, stores, grading).await
+}
+
+// Outermost, so writes applied from peers also pass through the cache.
+async fn replicate<S: ExamStore>(
+    config: &ServerConfig,
+    stores: Vec<(InstitutionId, S)>,
+    grading: GradingScheme,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let replication = &config.replication;
+    if !replication.enabled {
+        return start(config, stores, grading, None).await;
+    }
+    if replication.instance_id.is_empty() {
+        return Err("replication.instance_id must be set when replication is enabled".into());
+    }
+
+    let log = Arc::new(ReplicationLog::new(&replication.instance_id, replication.buffer));
+    let stores = stores
+        .into_iter()
+        .map(|(institution, store)| {
+            let store = ReplicatedExamStore::new(store, institution.clone(), log.clone());
+            (institution, store)
+        })
+        .collect();
+    start(config, stores, grading, Some(log)).await
+}
+
+async fn start<S: ExamStore>(
+    config: &ServerConfig,
+    stores: Vec<(InstitutionId, S)>,
+    grading: GradingScheme,
+    replication: Option<Arc<ReplicationLog>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut service = ExamServiceImpl::for_institutions(stores)
+        .with_grading(grading)
+        .with_max_processing_time(config.max_processing_time())
+        .with_streams(config.stream.clone())
+        .with_idempotency(&config.idempotency)
+        .with_operations(&config.operations)
+        .with_privacy(&config.privacy)?;
+    if let Some(log) = replication {
+        service = service.with_replication(log);
+    }
+
+    serve(config, service).await
+}
@@ -0,0 +1,58 @@
+use std::collections::HashSet;
+use tonic::Status;
+
+use crate::errors::invalid_field;
+use crate::exam_service::{ExamResult, QuestionScore};
+
+// Checks a per-question breakdown: unique IDs and marks within each question's maximum.
+fn validate_questions(questions: &[QuestionScore]) -> Result<(), Status> {
+    let mut seen = HashSet::new();
+
+    for (index, question) in questions.iter().enumerate() {
+        let field = |name: &str| format!("questions[{}].{}", index, name);
+
+        if question.question_id.trim().is_empty() {
+            return Err(invalid_field(&field("question_id"), "is required"));
+        }
+
+        if !seen.insert(question.question_id.as_str()) {
+            return Err(invalid_field(
+                &field("question_id"),
+                format!("{} appears more than once", question.question_id),
+            ));
+        }
+
+        if question.max_marks <= 0 {
+            return Err(invalid_field(
+                &field("max_marks"),
+                format!("must be positive, got {}", question.max_marks),
+            ));
+        }
+
+        if question.marks < 0 || question.marks > question.max_marks {
+            return Err(invalid_field(
+                &field("marks"),
+                format!("must be between 0 and {}, got {}", question.max_marks, question.marks),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+// When a result carries a breakdown, its marks and total are the sums of the
+// questions, replacing whatever the client sent. Results without one are unchanged.
+pub fn apply_breakdown(result: &mut ExamResult) -> Result<(), Status> {
+    if result.questions.is_empty() {
+        return Ok(());
+    }
+
+    validate_questions(&result.questions)?;
+
+    let marks: i64 = result.questions.iter().map(|question| question.marks as i64).sum();
+    let total: i64 = result.questions.iter().map(|question| question.max_marks as i64).sum();
+
+    result.marks_obtained = i32::try_from(marks).map_err(|_| invalid_field("questions", "marks overflow"))?;
+    result.total_marks = i32::try_from(total).map_err(|_| invalid_field("questions", "max_marks overflow"))?;
+    Ok(())
+}
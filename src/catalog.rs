@@ -0,0 +1,227 @@
+use std::time::Duration;
+use tonic::{Request, Response, Status};
+use tracing::info;
+
+use crate::auth::Identity;
+use crate::deadline::{Deadline, DEFAULT_MAX_PROCESSING};
+use crate::errors::{invalid_field, not_found};
+use crate::exam_admin::exam_admin_service_server::ExamAdminService;
+use crate::exam_admin::{
+    CreateExamRequest, Exam, GetExamRequest, ListExamsRequest, ListExamsResponse, UpdateExamRequest,
+};
+use crate::exam_service::ExamResult;
+use crate::key::ExamId;
+use crate::model::timestamp_from_date;
+use crate::schedule::validate_schedule;
+use crate::store::ExamStore;
+use crate::tenancy::Tenants;
+
+// Implements ExamAdminService on top of the same stores as ExamService,
+// so results are always checked against the current catalog.
+#[derive(Debug)]
+pub struct ExamAdminServiceImpl<S> {
+    tenants: Tenants<S>,
+    max_processing: Duration,
+}
+
+impl<S: ExamStore> ExamAdminServiceImpl<S> {
+    pub fn new(tenants: Tenants<S>) -> Self {
+        Self {
+            tenants,
+            max_processing: DEFAULT_MAX_PROCESSING,
+        }
+    }
+
+    // Replaces the server-side processing limit.
+    pub fn with_max_processing_time(mut self, limit: Duration) -> Self {
+        self.max_processing = limit;
+        self
+    }
+
+    // The client's deadline for `request`, capped by the processing limit.
+    fn deadline<T>(&self, request: &Request<T>) -> Result<Deadline, Status> {
+        Deadline::from_request(request, Some(self.max_processing))
+    }
+}
+
+// Rejects exam definitions that results could not be validated against.
+fn validate_exam(exam: &Exam) -> Result<ExamId, Status> {
+    let id = ExamId::parse(&exam.exam_id)?;
+
+    if exam.subject.trim().is_empty() {
+        return Err(invalid_field("subject", "is required"));
+    }
+
+    if exam.total_marks <= 0 {
+        return Err(invalid_field(
+            "total_marks",
+            format!("must be positive, got {}", exam.total_marks),
+        ));
+    }
+
+    if !is_valid_date(&exam.date) {
+        return Err(invalid_field(
+            "date",
+            format!("must be a calendar date in YYYY-MM-DD form, got {:?}", exam.date),
+        ));
+    }
+
+    validate_schedule(exam)?;
+    Ok(id)
+}
+
+// NOT_FOUND for an exam missing from the catalog.
+pub fn exam_not_found(id: &ExamId) -> Status {
+    not_found("exam", id, format!("No exam found for {}", id))
+}
+
+fn is_valid_date(date: &str) -> bool {
+    let mut parts = date.split('-');
+    let (Some(year), Some(month), Some(day), None) = (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return false;
+    };
+
+    if year.len() != 4 || month.len() != 2 || day.len() != 2 {
+        return false;
+    }
+
+    let (Ok(year), Ok(month), Ok(day)) = (year.parse::<u32>(), month.parse::<u32>(), day.parse::<u32>()) else {
+        return false;
+    };
+
+    let leap = year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+    let days_in_month = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return false,
+    };
+
+    (1..=days_in_month).contains(&day)
+}
+
+// Checks a submitted result against its catalog entry. Subject and total_marks
+// are filled in from the exam when left empty, and rejected when they disagree;
+// the exam date is always taken from the catalog.
+pub fn conform_to_exam(result: &mut ExamResult, exam: &Exam) -> Result<(), Status> {
+    if result.subject.is_empty() {
+        result.subject = exam.subject.clone();
+    } else if result.subject != exam.subject {
+        return Err(invalid_field(
+            "subject",
+            format!("{:?} does not match exam {} ({:?})", result.subject, exam.exam_id, exam.subject),
+        ));
+    }
+
+    if result.total_marks == 0 {
+        result.total_marks = exam.total_marks;
+    } else if result.total_marks != exam.total_marks {
+        return Err(invalid_field(
+            "total_marks",
+            format!("{} does not match exam {} ({})", result.total_marks, exam.exam_id, exam.total_marks),
+        ));
+    }
+
+    result.exam_date = timestamp_from_date(&exam.date);
+    Ok(())
+}
+
+#[tonic::async_trait]
+impl<S: ExamStore> ExamAdminService for ExamAdminServiceImpl<S> {
+    // Adds a new exam to the catalog; existing exams must be changed with UpdateExam.
+    async fn create_exam(&self, request: Request<CreateExamRequest>) -> Result<Response<Exam>, Status> {
+        info!(request = ?request.get_ref(), "create exam");
+
+        let deadline = self.deadline(&request)?;
+        deadline
+            .run(async move {
+                let identity = Identity::from_request(&request)?;
+                identity.require_write()?;
+
+                let exam = request
+                    .into_inner()
+                    .exam
+                    .ok_or_else(|| invalid_field("exam", "is required"))?;
+                let id = validate_exam(&exam)?;
+
+                if !self.tenants.for_caller(&identity)?.create_exam(id.clone(), exam.clone()).await? {
+                    return Err(Status::already_exists(format!("Exam {} already exists", id)));
+                }
+
+                info!(exam_id = %id, "exam created");
+                Ok(Response::new(exam))
+            })
+            .await
+    }
+
+    // Looks up a single exam. Any authenticated caller may read the catalog.
+    async fn get_exam(&self, request: Request<GetExamRequest>) -> Result<Response<Exam>, Status> {
+        info!(request = ?request.get_ref(), "get exam");
+
+        let deadline = self.deadline(&request)?;
+        deadline
+            .run(async move {
+                let identity = Identity::from_request(&request)?;
+                let id = ExamId::parse(&request.get_ref().exam_id)?;
+
+                match self.tenants.for_caller(&identity)?.get_exam(&id).await? {
+                    Some(exam) => Ok(Response::new(exam)),
+                    None => Err(exam_not_found(&id)),
+                }
+            })
+            .await
+    }
+
+    // Lists the catalog, optionally restricted to one subject.
+    async fn list_exams(&self, request: Request<ListExamsRequest>) -> Result<Response<ListExamsResponse>, Status> {
+        info!(request = ?request.get_ref(), "list exams");
+
+        let deadline = self.deadline(&request)?;
+        deadline
+            .run(async move {
+                let identity = Identity::from_request(&request)?;
+                let subject = request.into_inner().subject;
+
+                let exams = self
+                    .tenants
+                    .for_caller(&identity)?
+                    .list_exams()
+                    .await?
+                    .into_iter()
+                    .filter(|exam| subject.is_empty() || exam.subject == subject)
+                    .collect();
+
+                Ok(Response::new(ListExamsResponse { exams }))
+            })
+            .await
+    }
+
+    // Replaces an existing exam's subject, total_marks, date and schedule.
+    // Results already stored against the exam are left as they are.
+    async fn update_exam(&self, request: Request<UpdateExamRequest>) -> Result<Response<Exam>, Status> {
+        info!(request = ?request.get_ref(), "update exam");
+
+        let deadline = self.deadline(&request)?;
+        deadline
+            .run(async move {
+                let identity = Identity::from_request(&request)?;
+                identity.require_write()?;
+
+                let exam = request
+                    .into_inner()
+                    .exam
+                    .ok_or_else(|| invalid_field("exam", "is required"))?;
+                let id = validate_exam(&exam)?;
+
+                if self.tenants.for_caller(&identity)?.update_exam(id.clone(), exam.clone()).await?.is_none() {
+                    return Err(exam_not_found(&id));
+                }
+
+                info!(exam_id = %id, "exam updated");
+                Ok(Response::new(exam))
+            })
+            .await
+    }
+}
@@ -1,46 +1,1014 @@
-use tonic::{transport::Channel, Request};
-use exam_service::exam_service_client::ExamServiceClient;
-use exam_service::GetExamResultRequest;
-use futures::StreamExt;
-
-pub mod exam_service {
-    tonic::include_proto!("exam");
-}
-
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Create a gRPC client connection to the server
-    let mut client = ExamServiceClient::connect("http://[::1]:50051").await?;
-
-    // Prepare a request for the unary RPC
-    let request = Request::new(GetExamResultRequest {
-        student_id: "123".to_string(),
-        exam_id: "math101".to_string(),
-    });
-
-    // Call the unary RPC method
-    let response = client.get_exam_result(request).await?;
-    println!("Unary Response: {:?}", response.into_inner());
-
-    // Prepare a new request for the streaming RPC
-    let request = Request::new(GetExamResultRequest {
-        student_id: "456".to_string(),
-        exam_id: "physics101".to_string(),
-    });
-
-    // Call the streaming RPC method
-    let mut stream = client.get_exam_result_stream(request).await?.into_inner();
-
-    // Process the stream as responses arrive asynchronously
-    while let Some(response) = stream.next().await {
-        match response {
-            Ok(reply) => println!(
-                "Stream Response: {} - Grade: {}",
-                reply.student_name, reply.grade
-            ),
-            Err(e) => eprintln!("Stream Error: {}", e),
+use std::future::Future;
+use futures::{Stream, StreamExt};
+use tonic::codec::CompressionEncoding;
+use tonic::metadata::{Ascii, MetadataValue};
+use tonic::service::interceptor::InterceptedService;
+use tonic::service::Interceptor;
+use tonic::transport::{Channel, Endpoint};
+use tonic::{Code, Request, Status, Streaming};
+use tonic_types::StatusExt;
+
+use crate::appeal::appeal_service_client::AppealServiceClient;
+use crate::auth::INSTITUTION_HEADER;
+use crate::appeal::{
+    Appeal, AppealState, FileAppealRequest, ListAppealsRequest, ResolveAppealRequest, ReviewAppealRequest,
+};
+use crate::exam_admin::exam_admin_service_client::ExamAdminServiceClient;
+use crate::exam_admin::{CreateExamRequest, Exam, GetExamRequest, ListExamsRequest, UpdateExamRequest};
+use crate::exam_session::session_request::Request as SessionMessage;
+use crate::exam_session::session_service_client::SessionServiceClient;
+use crate::exam_session::{SessionEvent, SessionRequest, StartSession};
+use crate::grader::grader_service_client::GraderServiceClient;
+use crate::grader::{
+    AssignGradersRequest, DistributeSubmissionsRequest, DistributeSubmissionsResponse, DistributionStrategy,
+    ExamGraders, GetGradersRequest, GradingTask, ListMyGradingQueueRequest,
+};
+use crate::exam_service::exam_service_client::ExamServiceClient;
+use crate::exam_service::{
+    AnswerSubmission, AuditRecord, BatchGetExamResultsRequest, BatchGetResult, ExamResultKey, CorrectExamResultRequest, CorrectExamResultResponse, DeleteExamResultRequest, DeletedResult,
+    ExamResult, ExamStatistics, ExportChunk, ExportFormat, ExportResultsRequest, GetAuditTrailRequest, GetExamResultRequest, GetExamResultResponse, GetExamStatisticsRequest,
+    GetTranscriptRequest, GradeUpdate, ListExamResultsPageRequest, ListExamResultsPageResponse,
+    ListExamResultsRequest, PublishExamResultsRequest, PublishExamResultsResponse, QuestionScore, ResultChange, SearchExamResultsRequest, SearchMatch, SubmitExamResultRequest, SubmitExamResultResponse,
+    SubmitExamResultsResponse, SubmitQuestionScoresRequest, Transcript, WatchExamResultsRequest, ListDeletedResultsRequest,
+    UndeleteExamResultRequest, DownloadExportRequest, Operation, StartImportResultsRequest, StartPublishResultsRequest, WatchOperationRequest,
+};
+use crate::google::protobuf::FieldMask;
+use crate::key::InstitutionId;
+use crate::admin::admin_service_client::AdminServiceClient;
+use crate::admin::{
+    GetConfigRequest, GetConfigResponse, InstitutionSnapshot, ListStreamsRequest, ListStreamsResponse,
+    MaintenanceMode, SetMaintenanceModeRequest, TriggerSnapshotRequest,
+};
+use crate::snapshot::snapshot_service_client::SnapshotServiceClient;
+use crate::snapshot::{CreateSnapshotRequest, ListSnapshotsRequest, RestoreSnapshotRequest, SnapshotInfo};
+use crate::student::student_service_client::StudentServiceClient;
+use crate::telemetry::ClientTrace;
+use crate::student::{GetStudentRequest, ListStudentsRequest, RegisterStudentRequest, Student};
+use crate::webhook::webhook_service_client::WebhookServiceClient;
+use crate::webhook::{DeleteWebhookRequest, ListWebhooksRequest, RegisterWebhookRequest, Webhook, WebhookEvent};
+
+mod circuit_breaker;
+mod retry_budget;
+mod retry_policy;
+mod stream;
+
+pub use circuit_breaker::CircuitBreaker;
+pub use retry_budget::RetryBudget;
+pub use retry_policy::RetryPolicy;
+pub use stream::{ResultStream, StreamError, WatchEvent};
+
+// Only errors that indicate a transient server or network problem are retried.
+fn is_retryable(status: &Status) -> bool {
+    matches!(status.code(), Code::Unavailable | Code::ResourceExhausted)
+}
+
+// Retry and fail-fast settings shared by every clone of a client.
+#[derive(Debug, Clone, Default)]
+struct Retries {
+    budget: RetryBudget,
+    policy: RetryPolicy,
+    breaker: CircuitBreaker,
+}
+
+impl Retries {
+    // Admits one attempt through the circuit breaker.
+    fn acquire(&self) -> Result<(), Status> {
+        if self.breaker.try_acquire() {
+            Ok(())
+        } else {
+            Err(Status::unavailable("Server is failing; not calling it until the circuit breaker closes"))
+        }
+    }
+
+    fn record<T>(&self, result: &Result<T, Status>) {
+        match result {
+            Err(status) if is_retryable(status) => self.breaker.record_failure(),
+            _ => self.breaker.record_success(),
+        }
+    }
+}
+
+// Runs `call`, retrying transient failures with backoff while the policy and
+// the shared retry budget allow. A RetryInfo delay from the server replaces
+// a shorter backoff; one longer than `max_backoff` is not waited out.
+async fn with_retries<T, F, Fut>(retries: &Retries, mut call: F) -> Result<T, Status>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, Status>>,
+{
+    retries.budget.deposit();
+
+    let mut attempt = 1;
+    loop {
+        retries.acquire()?;
+        let result = call().await;
+        retries.record(&result);
+
+        match result {
+            Err(status) if is_retryable(&status) && attempt < retries.policy.max_attempts => {
+                if !retries.budget.try_withdraw() {
+                    tracing::warn!(%status, "retry budget exhausted, failing fast");
+                    return Err(status);
+                }
+                // A rate-limited server says when to come back; sooner would be rejected again
+                let backoff = match status.get_details_retry_info().and_then(|info| info.retry_delay) {
+                    Some(delay) if delay > retries.policy.max_backoff => return Err(status),
+                    Some(delay) => delay.max(retries.policy.backoff(attempt)),
+                    None => retries.policy.backoff(attempt),
+                };
+                tokio::time::sleep(backoff).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+// Runs `call` once through the circuit breaker. Streaming calls are not
+// retried, since part of the stream may already have been consumed.
+async fn without_retries<T, Fut>(retries: &Retries, call: Fut) -> Result<T, Status>
+where
+    Fut: Future<Output = Result<T, Status>>,
+{
+    retries.acquire()?;
+    let result = call.await;
+    retries.record(&result);
+    result
+}
+
+// A fresh idempotency key, shared by every attempt of one write.
+fn new_request_id() -> String {
+    format!("{:032x}", rand::random::<u128>())
+}
+
+// A read mask selecting `fields` of each result, for a request's `read_mask`.
+// No fields means no mask, so every field is returned.
+pub fn read_mask(fields: &[&str]) -> Option<FieldMask> {
+    (!fields.is_empty()).then(|| FieldMask {
+        paths: fields.iter().map(|field| field.to_string()).collect(),
+    })
+}
+
+// Attaches the API token to every call as `authorization: Bearer <token>`,
+// and the institution to act in as `x-institution-id` if one is set.
+#[derive(Debug, Clone)]
+pub struct BearerToken {
+    authorization: MetadataValue<Ascii>,
+    institution: Option<MetadataValue<Ascii>>,
+}
+
+impl BearerToken {
+    pub fn new(token: &str) -> Result<Self, Status> {
+        let authorization = format!("Bearer {}", token)
+            .parse()
+            .map_err(|_| Status::invalid_argument("API token contains characters not allowed in metadata"))?;
+        Ok(Self {
+            authorization,
+            institution: None,
+        })
+    }
+
+    // Names the institution every call acts in. Needed for tokens valid in
+    // every institution; scoped tokens act in their own without it.
+    pub fn with_institution(mut self, institution: &str) -> Result<Self, Status> {
+        let institution = InstitutionId::parse(institution)?;
+        self.institution = Some(
+            institution
+                .as_str()
+                .parse()
+                .map_err(|_| Status::invalid_argument("institution is not valid metadata"))?,
+        );
+        Ok(self)
+    }
+}
+
+impl Interceptor for BearerToken {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        request.metadata_mut().insert("authorization", self.authorization.clone());
+        if let Some(institution) = &self.institution {
+            request.metadata_mut().insert(INSTITUTION_HEADER, institution.clone());
+        }
+        Ok(request)
+    }
+}
+
+type Authorized = InterceptedService<ClientTrace<Channel>, BearerToken>;
+
+// Typed client for all three services over one shared channel.
+// Cloning is cheap and clones share the channel, retry budget and circuit breaker.
+// Reads and result writes are retried on transient failures with backoff, within
+// the budget; each write carries a request ID so the server applies a retried
+// write only once. The channel connects on first use and reconnects on its own
+// after the connection drops, so a retry after a restart reaches the new server.
+#[derive(Debug, Clone)]
+pub struct ExamClient {
+    exams: ExamServiceClient<Authorized>,
+    admin: ExamAdminServiceClient<Authorized>,
+    students: StudentServiceClient<Authorized>,
+    appeals: AppealServiceClient<Authorized>,
+    sessions: SessionServiceClient<Authorized>,
+    graders: GraderServiceClient<Authorized>,
+    webhooks: WebhookServiceClient<Authorized>,
+    snapshots: SnapshotServiceClient<Authorized>,
+    // AdminService, for the server as a whole
+    runtime: AdminServiceClient<Authorized>,
+    retries: Retries,
+}
+
+impl ExamClient {
+    // Creates a client for `endpoint`, which may already carry TLS settings.
+    // The connection is made lazily, so an unreachable server surfaces as an
+    // UNAVAILABLE error from the first call, after its retries.
+    pub async fn connect(endpoint: Endpoint, token: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let token = BearerToken::new(token)?;
+        Ok(Self::with_channel(endpoint.connect_lazy(), token))
+    }
+
+    // Creates a client that balances calls across replicas of the service, one
+    // endpoint per server. Each endpoint connects lazily and reconnects on its own;
+    // every call goes to the less loaded of two endpoints picked at random.
+    pub async fn connect_balanced(
+        endpoints: impl IntoIterator<Item = Endpoint>,
+        token: &str,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::connect_balanced_with(endpoints, BearerToken::new(token)?).await
+    }
+
+    // Like `connect_balanced`, with a token already built, e.g. one naming an institution.
+    pub async fn connect_balanced_with(
+        endpoints: impl IntoIterator<Item = Endpoint>,
+        token: BearerToken,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut endpoints: Vec<_> = endpoints.into_iter().collect();
+
+        match endpoints.len() {
+            0 => Err("at least one server endpoint is required".into()),
+            1 => Ok(Self::with_channel(endpoints.remove(0).connect_lazy(), token)),
+            _ => Ok(Self::with_channel(Channel::balance_list(endpoints.into_iter()), token)),
         }
     }
 
-    Ok(())
-}
\ No newline at end of file
+    // Builds a client on an existing channel, e.g. one shared with other clients.
+    // Compressed responses are accepted in either supported encoding, and every
+    // call is traced, carrying the caller's trace context to the server.
+    pub fn with_channel(channel: Channel, token: BearerToken) -> Self {
+        let channel = ClientTrace::new(channel);
+        Self {
+            exams: ExamServiceClient::with_interceptor(channel.clone(), token.clone())
+                .accept_compressed(CompressionEncoding::Gzip)
+                .accept_compressed(CompressionEncoding::Zstd),
+            admin: ExamAdminServiceClient::with_interceptor(channel.clone(), token.clone())
+                .accept_compressed(CompressionEncoding::Gzip)
+                .accept_compressed(CompressionEncoding::Zstd),
+            students: StudentServiceClient::with_interceptor(channel.clone(), token.clone())
+                .accept_compressed(CompressionEncoding::Gzip)
+                .accept_compressed(CompressionEncoding::Zstd),
+            appeals: AppealServiceClient::with_interceptor(channel.clone(), token.clone())
+                .accept_compressed(CompressionEncoding::Gzip)
+                .accept_compressed(CompressionEncoding::Zstd),
+            sessions: SessionServiceClient::with_interceptor(channel.clone(), token.clone())
+                .accept_compressed(CompressionEncoding::Gzip)
+                .accept_compressed(CompressionEncoding::Zstd),
+            graders: GraderServiceClient::with_interceptor(channel.clone(), token.clone())
+                .accept_compressed(CompressionEncoding::Gzip)
+                .accept_compressed(CompressionEncoding::Zstd),
+            webhooks: WebhookServiceClient::with_interceptor(channel.clone(), token.clone())
+                .accept_compressed(CompressionEncoding::Gzip)
+                .accept_compressed(CompressionEncoding::Zstd),
+            snapshots: SnapshotServiceClient::with_interceptor(channel.clone(), token.clone())
+                .accept_compressed(CompressionEncoding::Gzip)
+                .accept_compressed(CompressionEncoding::Zstd),
+            runtime: AdminServiceClient::with_interceptor(channel, token)
+                .accept_compressed(CompressionEncoding::Gzip)
+                .accept_compressed(CompressionEncoding::Zstd),
+            retries: Retries::default(),
+        }
+    }
+
+    // Compresses every request with `encoding`, e.g. for large bulk uploads.
+    // The server must accept it (`compression.accept`, on by default).
+    pub fn with_compression(mut self, encoding: CompressionEncoding) -> Self {
+        self.exams = self.exams.send_compressed(encoding);
+        self.admin = self.admin.send_compressed(encoding);
+        self.students = self.students.send_compressed(encoding);
+        self.appeals = self.appeals.send_compressed(encoding);
+        self.sessions = self.sessions.send_compressed(encoding);
+        self.graders = self.graders.send_compressed(encoding);
+        self.webhooks = self.webhooks.send_compressed(encoding);
+        self.snapshots = self.snapshots.send_compressed(encoding);
+        self.runtime = self.runtime.send_compressed(encoding);
+        self
+    }
+
+    // Replaces the default retry budget (10% of requests, up to 10 saved retries).
+    pub fn with_retry_budget(mut self, budget: RetryBudget) -> Self {
+        self.retries.budget = budget;
+        self
+    }
+
+    // Replaces the default retry policy (3 attempts, backoff from 50ms up to 2s).
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retries.policy = policy;
+        self
+    }
+
+    // Replaces the default circuit breaker (opens for 10s after 5 failures in a row).
+    pub fn with_circuit_breaker(mut self, breaker: CircuitBreaker) -> Self {
+        self.retries.breaker = breaker;
+        self
+    }
+
+    pub async fn get_result(&self, student_id: &str, exam_id: &str) -> Result<GetExamResultResponse, Status> {
+        self.get_result_fields(student_id, exam_id, &[]).await
+    }
+
+    // Fetches only the named fields of a result, e.g. `["grade"]`; the rest
+    // are left at their defaults. No fields fetches them all.
+    pub async fn get_result_fields(
+        &self,
+        student_id: &str,
+        exam_id: &str,
+        fields: &[&str],
+    ) -> Result<GetExamResultResponse, Status> {
+        with_retries(&self.retries, || {
+            let mut client = self.exams.clone();
+            let request = GetExamResultRequest {
+                student_id: student_id.to_string(),
+                exam_id: exam_id.to_string(),
+                read_mask: read_mask(fields),
+            };
+            async move { client.get_exam_result(request).await.map(|r| r.into_inner()) }
+        })
+        .await
+    }
+
+    // Fetches several results in one call, given as (student_id, exam_id)
+    // pairs. Each gets its result or its own error, in the order asked for.
+    pub async fn batch_get_results(&self, keys: &[(&str, &str)]) -> Result<Vec<BatchGetResult>, Status> {
+        let request = BatchGetExamResultsRequest {
+            keys: keys
+                .iter()
+                .map(|(student_id, exam_id)| ExamResultKey {
+                    student_id: student_id.to_string(),
+                    exam_id: exam_id.to_string(),
+                })
+                .collect(),
+            read_mask: None,
+        };
+        with_retries(&self.retries, || {
+            let mut client = self.exams.clone();
+            let request = request.clone();
+            async move { client.batch_get_exam_results(request).await.map(|r| r.into_inner().results) }
+        })
+        .await
+    }
+
+    // Streams a student's stored results; pass "" as `exam_id` for every exam.
+    pub async fn get_result_stream(
+        &self,
+        student_id: &str,
+        exam_id: &str,
+    ) -> Result<Streaming<GetExamResultResponse>, Status> {
+        let request = GetExamResultRequest {
+            student_id: student_id.to_string(),
+            exam_id: exam_id.to_string(),
+            read_mask: None,
+        };
+        without_retries(&self.retries, self.exams.clone().get_exam_result_stream(request))
+            .await
+            .map(|r| r.into_inner())
+    }
+
+    // Inserts or replaces a result. The server computes the grade.
+    pub async fn submit_result(&self, result: ExamResult) -> Result<SubmitExamResultResponse, Status> {
+        let request = SubmitExamResultRequest {
+            result: Some(result),
+            request_id: new_request_id(),
+        };
+        with_retries(&self.retries, || {
+            let mut client = self.exams.clone();
+            let request = request.clone();
+            async move { client.submit_exam_result(request).await.map(|r| r.into_inner()) }
+        })
+        .await
+    }
+
+    // Records the per-question breakdown of a result; the server derives marks and grade from it.
+    pub async fn submit_question_scores(
+        &self,
+        student_id: &str,
+        exam_id: &str,
+        questions: Vec<QuestionScore>,
+    ) -> Result<SubmitExamResultResponse, Status> {
+        let request = SubmitQuestionScoresRequest {
+            student_id: student_id.to_string(),
+            exam_id: exam_id.to_string(),
+            questions,
+            request_id: new_request_id(),
+        };
+        with_retries(&self.retries, || {
+            let mut client = self.exams.clone();
+            let request = request.clone();
+            async move { client.submit_question_scores(request).await.map(|r| r.into_inner()) }
+        })
+        .await
+    }
+
+    // Uploads many results in one call; invalid records are reported, not fatal.
+    pub async fn submit_results(
+        &self,
+        results: impl Stream<Item = ExamResult> + Send + 'static,
+    ) -> Result<SubmitExamResultsResponse, Status> {
+        without_retries(&self.retries, self.exams.clone().submit_exam_results(results))
+            .await
+            .map(|r| r.into_inner())
+    }
+
+    // Amends the marks of a result last read at `expected_version`. Fails with
+    // ABORTED if it has changed since; re-read and retry in that case.
+    pub async fn correct_result(
+        &self,
+        student_id: &str,
+        exam_id: &str,
+        marks_obtained: i32,
+        expected_version: i64,
+        reason: &str,
+    ) -> Result<CorrectExamResultResponse, Status> {
+        let request = CorrectExamResultRequest {
+            student_id: student_id.to_string(),
+            exam_id: exam_id.to_string(),
+            marks_obtained,
+            reason: reason.to_string(),
+            request_id: new_request_id(),
+            expected_version,
+            ..Default::default()
+        };
+        with_retries(&self.retries, || {
+            let mut client = self.exams.clone();
+            let request = request.clone();
+            async move { client.correct_exam_result(request).await.map(|r| r.into_inner()) }
+        })
+        .await
+    }
+
+    // Deletes a result (admin only), returning the removed result.
+    pub async fn delete_result(
+        &self,
+        student_id: &str,
+        exam_id: &str,
+        reason: &str,
+    ) -> Result<Option<GetExamResultResponse>, Status> {
+        let request = DeleteExamResultRequest {
+            student_id: student_id.to_string(),
+            exam_id: exam_id.to_string(),
+            reason: reason.to_string(),
+            request_id: new_request_id(),
+        };
+        with_retries(&self.retries, || {
+            let mut client = self.exams.clone();
+            let request = request.clone();
+            async move { client.delete_exam_result(request).await.map(|r| r.into_inner().previous) }
+        })
+        .await
+    }
+
+    // Lists the deleted results not yet purged; pass "" for either filter to match all.
+    pub async fn list_deleted_results(&self, student_id: &str, exam_id: &str) -> Result<Vec<DeletedResult>, Status> {
+        let request = ListDeletedResultsRequest {
+            student_id: student_id.to_string(),
+            exam_id: exam_id.to_string(),
+        };
+        with_retries(&self.retries, || {
+            let mut client = self.exams.clone();
+            let request = request.clone();
+            async move { client.list_deleted_results(request).await.map(|r| r.into_inner().results) }
+        })
+        .await
+    }
+
+    // Brings back a deleted result, returning it at its new version.
+    pub async fn undelete_result(
+        &self,
+        student_id: &str,
+        exam_id: &str,
+        reason: &str,
+    ) -> Result<Option<GetExamResultResponse>, Status> {
+        let request = UndeleteExamResultRequest {
+            student_id: student_id.to_string(),
+            exam_id: exam_id.to_string(),
+            reason: reason.to_string(),
+            request_id: new_request_id(),
+        };
+        with_retries(&self.retries, || {
+            let mut client = self.exams.clone();
+            let request = request.clone();
+            async move { client.undelete_exam_result(request).await.map(|r| r.into_inner().result) }
+        })
+        .await
+    }
+
+    // Makes every draft result of an exam visible to its students.
+    pub async fn publish_results(&self, exam_id: &str) -> Result<PublishExamResultsResponse, Status> {
+        let request = PublishExamResultsRequest {
+            exam_id: exam_id.to_string(),
+            request_id: new_request_id(),
+        };
+        with_retries(&self.retries, || {
+            let mut client = self.exams.clone();
+            let request = request.clone();
+            async move { client.publish_exam_results(request).await.map(|r| r.into_inner()) }
+        })
+        .await
+    }
+
+    pub async fn list_results(
+        &self,
+        filter: ListExamResultsRequest,
+    ) -> Result<Streaming<GetExamResultResponse>, Status> {
+        without_retries(&self.retries, self.exams.clone().list_exam_results(filter))
+            .await
+            .map(|r| r.into_inner())
+    }
+
+    // Streams the results whose student names and subjects match `query`, best
+    // first. No `limit` asks for the server's default.
+    pub async fn search_results(&self, query: &str, limit: Option<i32>) -> Result<Streaming<SearchMatch>, Status> {
+        let request = SearchExamResultsRequest {
+            query: query.to_string(),
+            limit,
+            read_mask: None,
+        };
+        without_retries(&self.retries, self.exams.clone().search_exam_results(request))
+            .await
+            .map(|r| r.into_inner())
+    }
+
+    // Downloads matching results in `format`; concatenate the chunks for the whole file.
+    pub async fn export_results(
+        &self,
+        filter: ListExamResultsRequest,
+        format: ExportFormat,
+    ) -> Result<Streaming<ExportChunk>, Status> {
+        let request = ExportResultsRequest {
+            filter: Some(filter),
+            format: format as i32,
+        };
+        without_retries(&self.retries, self.exams.clone().export_results(request))
+            .await
+            .map(|r| r.into_inner())
+    }
+
+    // Starts importing `results` in the background, returning the operation
+    // to follow with watch_operation.
+    pub async fn start_import(&self, results: Vec<ExamResult>) -> Result<Operation, Status> {
+        let request = StartImportResultsRequest {
+            results,
+            request_id: new_request_id(),
+        };
+        with_retries(&self.retries, || {
+            let mut client = self.exams.clone();
+            let request = request.clone();
+            async move { client.start_import_results(request).await.map(|r| r.into_inner()) }
+        })
+        .await
+    }
+
+    // Starts an export in the background; download_export fetches the file
+    // once the operation has succeeded.
+    pub async fn start_export(&self, filter: ListExamResultsRequest, format: ExportFormat) -> Result<Operation, Status> {
+        let request = ExportResultsRequest {
+            filter: Some(filter),
+            format: format as i32,
+        };
+        without_retries(&self.retries, self.exams.clone().start_export_results(request))
+            .await
+            .map(|r| r.into_inner())
+    }
+
+    // Starts publishing the drafts of each of `exam_ids` in the background.
+    pub async fn start_publish(&self, exam_ids: &[&str]) -> Result<Operation, Status> {
+        let request = StartPublishResultsRequest {
+            exam_ids: exam_ids.iter().map(|id| id.to_string()).collect(),
+            request_id: new_request_id(),
+        };
+        with_retries(&self.retries, || {
+            let mut client = self.exams.clone();
+            let request = request.clone();
+            async move { client.start_publish_results(request).await.map(|r| r.into_inner()) }
+        })
+        .await
+    }
+
+    // Streams an operation's state until it finishes.
+    pub async fn watch_operation(&self, operation_id: &str) -> Result<Streaming<Operation>, Status> {
+        let request = WatchOperationRequest {
+            operation_id: operation_id.to_string(),
+        };
+        without_retries(&self.retries, self.exams.clone().watch_operation(request))
+            .await
+            .map(|r| r.into_inner())
+    }
+
+    // Streams the file of an export operation that succeeded.
+    pub async fn download_export(&self, operation_id: &str) -> Result<Streaming<ExportChunk>, Status> {
+        let request = DownloadExportRequest {
+            operation_id: operation_id.to_string(),
+        };
+        without_retries(&self.retries, self.exams.clone().download_export(request))
+            .await
+            .map(|r| r.into_inner())
+    }
+
+    pub async fn list_results_page(
+        &self,
+        request: ListExamResultsPageRequest,
+    ) -> Result<ListExamResultsPageResponse, Status> {
+        with_retries(&self.retries, || {
+            let mut client = self.exams.clone();
+            let request = request.clone();
+            async move { client.list_exam_results_page(request).await.map(|r| r.into_inner()) }
+        })
+        .await
+    }
+
+    // Every result matching `filter` as one stream, fetched a page at a time.
+    // Unlike `list_results`, a connection lost partway costs nothing: the page
+    // is asked for again from the token the listing stopped at.
+    pub async fn stream_results(
+        &self,
+        filter: ListExamResultsRequest,
+    ) -> Result<ResultStream<GetExamResultResponse>, StreamError> {
+        let request = ListExamResultsPageRequest {
+            filter: Some(filter),
+            ..Default::default()
+        };
+        stream::listing(self.clone(), request).await
+    }
+
+    pub async fn statistics(&self, exam_id: &str) -> Result<ExamStatistics, Status> {
+        with_retries(&self.retries, || {
+            let mut client = self.exams.clone();
+            let request = GetExamStatisticsRequest {
+                exam_id: exam_id.to_string(),
+            };
+            async move { client.get_exam_statistics(request).await.map(|r| r.into_inner()) }
+        })
+        .await
+    }
+
+    pub async fn transcript(&self, student_id: &str) -> Result<Transcript, Status> {
+        with_retries(&self.retries, || {
+            let mut client = self.exams.clone();
+            let request = GetTranscriptRequest {
+                student_id: student_id.to_string(),
+            };
+            async move { client.get_transcript(request).await.map(|r| r.into_inner()) }
+        })
+        .await
+    }
+
+    // Every recorded change to a student's results, oldest first (admin only).
+    // Pass "" as `exam_id` for every exam.
+    pub async fn audit_trail(&self, student_id: &str, exam_id: &str) -> Result<Vec<AuditRecord>, Status> {
+        with_retries(&self.retries, || {
+            let mut client = self.exams.clone();
+            let request = GetAuditTrailRequest {
+                student_id: student_id.to_string(),
+                exam_id: exam_id.to_string(),
+            };
+            async move { client.get_audit_trail(request).await.map(|r| r.into_inner().records) }
+        })
+        .await
+    }
+
+    // Subscribes to changes for a student and/or exam; pass "" to leave one unset.
+    pub async fn watch(&self, student_id: &str, exam_id: &str) -> Result<Streaming<ResultChange>, Status> {
+        let request = WatchExamResultsRequest {
+            student_id: student_id.to_string(),
+            exam_id: exam_id.to_string(),
+        };
+        without_retries(&self.retries, self.exams.clone().watch_exam_results(request))
+            .await
+            .map(|r| r.into_inner())
+    }
+
+    // Like `watch`, with typed events, and opened again whenever the server
+    // ends it early; each time a `WatchEvent::Resubscribed` marks the gap.
+    pub async fn watch_events(&self, student_id: &str, exam_id: &str) -> Result<ResultStream<WatchEvent>, StreamError> {
+        let request = WatchExamResultsRequest {
+            student_id: student_id.to_string(),
+            exam_id: exam_id.to_string(),
+        };
+        stream::watch(self.clone(), request).await
+    }
+
+    // Opens a watch, retrying while the server is unreachable, e.g. restarting.
+    async fn subscribe(&self, request: &WatchExamResultsRequest) -> Result<Streaming<ResultChange>, Status> {
+        with_retries(&self.retries, || {
+            let mut client = self.exams.clone();
+            let request = request.clone();
+            async move { client.watch_exam_results(request).await.map(|r| r.into_inner()) }
+        })
+        .await
+    }
+
+    pub async fn grade_session(
+        &self,
+        answers: impl Stream<Item = AnswerSubmission> + Send + 'static,
+    ) -> Result<Streaming<GradeUpdate>, Status> {
+        without_retries(&self.retries, self.exams.clone().grade_session(answers))
+            .await
+            .map(|r| r.into_inner())
+    }
+
+    // Sits `exam_id` as the token's student, or resumes the sitting if one is
+    // under way: sends the start of the sitting, then `requests` (answers, and
+    // perhaps a hand-in) as they come.
+    pub async fn take_exam(
+        &self,
+        exam_id: &str,
+        requests: impl Stream<Item = SessionRequest> + Send + 'static,
+    ) -> Result<Streaming<SessionEvent>, Status> {
+        let start = SessionRequest {
+            request: Some(SessionMessage::Start(StartSession {
+                exam_id: exam_id.to_string(),
+            })),
+        };
+        let requests = futures::stream::once(async { start }).chain(requests);
+        without_retries(&self.retries, self.sessions.clone().take_exam(requests))
+            .await
+            .map(|r| r.into_inner())
+    }
+
+    pub async fn create_exam(&self, exam: Exam) -> Result<Exam, Status> {
+        let request = CreateExamRequest { exam: Some(exam) };
+        without_retries(&self.retries, self.admin.clone().create_exam(request))
+            .await
+            .map(|r| r.into_inner())
+    }
+
+    pub async fn get_exam(&self, exam_id: &str) -> Result<Exam, Status> {
+        let request = GetExamRequest {
+            exam_id: exam_id.to_string(),
+        };
+        with_retries(&self.retries, || {
+            let mut client = self.admin.clone();
+            let request = request.clone();
+            async move { client.get_exam(request).await.map(|r| r.into_inner()) }
+        })
+        .await
+    }
+
+    // Lists the catalog; pass "" for every subject.
+    pub async fn list_exams(&self, subject: &str) -> Result<Vec<Exam>, Status> {
+        let request = ListExamsRequest {
+            subject: subject.to_string(),
+        };
+        with_retries(&self.retries, || {
+            let mut client = self.admin.clone();
+            let request = request.clone();
+            async move { client.list_exams(request).await.map(|r| r.into_inner().exams) }
+        })
+        .await
+    }
+
+    pub async fn update_exam(&self, exam: Exam) -> Result<Exam, Status> {
+        let request = UpdateExamRequest { exam: Some(exam) };
+        without_retries(&self.retries, self.admin.clone().update_exam(request))
+            .await
+            .map(|r| r.into_inner())
+    }
+
+    pub async fn register_student(&self, student: Student) -> Result<Student, Status> {
+        let request = RegisterStudentRequest { student: Some(student) };
+        without_retries(&self.retries, self.students.clone().register_student(request))
+            .await
+            .map(|r| r.into_inner())
+    }
+
+    pub async fn get_student(&self, student_id: &str) -> Result<Student, Status> {
+        let request = GetStudentRequest {
+            student_id: student_id.to_string(),
+        };
+        with_retries(&self.retries, || {
+            let mut client = self.students.clone();
+            let request = request.clone();
+            async move { client.get_student(request).await.map(|r| r.into_inner()) }
+        })
+        .await
+    }
+
+    pub async fn list_students(&self) -> Result<Vec<Student>, Status> {
+        with_retries(&self.retries, || {
+            let mut client = self.students.clone();
+            let request = ListStudentsRequest {};
+            async move { client.list_students(request).await.map(|r| r.into_inner().students) }
+        })
+        .await
+    }
+
+    pub async fn file_appeal(&self, student_id: &str, exam_id: &str, reason: &str) -> Result<Appeal, Status> {
+        let request = FileAppealRequest {
+            student_id: student_id.to_string(),
+            exam_id: exam_id.to_string(),
+            reason: reason.to_string(),
+        };
+        without_retries(&self.retries, self.appeals.clone().file_appeal(request))
+            .await
+            .map(|r| r.into_inner())
+    }
+
+    // Lists appeals; pass "" and `AppealState::Unspecified` to leave a filter unset.
+    pub async fn list_appeals(
+        &self,
+        student_id: &str,
+        exam_id: &str,
+        state: AppealState,
+    ) -> Result<Vec<Appeal>, Status> {
+        let request = ListAppealsRequest {
+            student_id: student_id.to_string(),
+            exam_id: exam_id.to_string(),
+            state: state as i32,
+        };
+        with_retries(&self.retries, || {
+            let mut client = self.appeals.clone();
+            let request = request.clone();
+            async move { client.list_appeals(request).await.map(|r| r.into_inner().appeals) }
+        })
+        .await
+    }
+
+    pub async fn review_appeal(&self, appeal_id: i64) -> Result<Appeal, Status> {
+        let request = ReviewAppealRequest { appeal_id };
+        without_retries(&self.retries, self.appeals.clone().review_appeal(request))
+            .await
+            .map(|r| r.into_inner())
+    }
+
+    // Resolves an appeal under review; `revised_marks` is ignored unless `approved`.
+    pub async fn resolve_appeal(
+        &self,
+        appeal_id: i64,
+        approved: bool,
+        revised_marks: i32,
+        resolution: &str,
+    ) -> Result<Appeal, Status> {
+        let request = ResolveAppealRequest {
+            appeal_id,
+            approved,
+            revised_marks,
+            resolution: resolution.to_string(),
+        };
+        without_retries(&self.retries, self.appeals.clone().resolve_appeal(request))
+            .await
+            .map(|r| r.into_inner())
+    }
+
+    // Replaces the graders of `exam_id` (admin only); assigning none unassigns everyone.
+    // Retried like a read, since assigning the same graders again changes nothing.
+    pub async fn assign_graders(&self, exam_id: &str, graders: &[&str]) -> Result<ExamGraders, Status> {
+        let request = AssignGradersRequest {
+            exam_id: exam_id.to_string(),
+            graders: graders.iter().map(|grader| grader.to_string()).collect(),
+        };
+        with_retries(&self.retries, || {
+            let mut client = self.graders.clone();
+            let request = request.clone();
+            async move { client.assign_graders(request).await.map(|r| r.into_inner()) }
+        })
+        .await
+    }
+
+    pub async fn graders(&self, exam_id: &str) -> Result<Vec<String>, Status> {
+        with_retries(&self.retries, || {
+            let mut client = self.graders.clone();
+            let request = GetGradersRequest {
+                exam_id: exam_id.to_string(),
+            };
+            async move { client.get_graders(request).await.map(|r| r.into_inner().graders) }
+        })
+        .await
+    }
+
+    // Hands the drafts of `exam_id` no assigned grader holds to its graders (admin only).
+    pub async fn distribute_submissions(
+        &self,
+        exam_id: &str,
+        strategy: DistributionStrategy,
+    ) -> Result<DistributeSubmissionsResponse, Status> {
+        let request = DistributeSubmissionsRequest {
+            exam_id: exam_id.to_string(),
+            strategy: strategy as i32,
+        };
+        without_retries(&self.retries, self.graders.clone().distribute_submissions(request))
+            .await
+            .map(|r| r.into_inner())
+    }
+
+    // The drafts awaiting the caller, or `grader` if an admin names one; pass ""
+    // for either to leave it unset.
+    pub async fn grading_queue(&self, exam_id: &str, grader: &str) -> Result<Streaming<GradingTask>, Status> {
+        let request = ListMyGradingQueueRequest {
+            exam_id: exam_id.to_string(),
+            grader: grader.to_string(),
+        };
+        without_retries(&self.retries, self.graders.clone().list_my_grading_queue(request))
+            .await
+            .map(|r| r.into_inner())
+    }
+
+    // Registers a webhook for `events`, or every event if empty. An empty
+    // `secret` has the server generate one; either way it is in the response.
+    pub async fn register_webhook(
+        &self,
+        url: &str,
+        events: &[WebhookEvent],
+        secret: &str,
+    ) -> Result<Webhook, Status> {
+        let request = RegisterWebhookRequest {
+            url: url.to_string(),
+            events: events.iter().map(|&event| event as i32).collect(),
+            secret: secret.to_string(),
+        };
+        without_retries(&self.retries, self.webhooks.clone().register_webhook(request))
+            .await
+            .map(|r| r.into_inner())
+    }
+
+    pub async fn list_webhooks(&self) -> Result<Vec<Webhook>, Status> {
+        with_retries(&self.retries, || {
+            let mut client = self.webhooks.clone();
+            let request = ListWebhooksRequest {};
+            async move { client.list_webhooks(request).await.map(|r| r.into_inner().webhooks) }
+        })
+        .await
+    }
+
+    pub async fn delete_webhook(&self, webhook_id: i64) -> Result<(), Status> {
+        let request = DeleteWebhookRequest { webhook_id };
+        without_retries(&self.retries, self.webhooks.clone().delete_webhook(request))
+            .await
+            .map(|_| ())
+    }
+
+    // Snapshots the caller's institution; an empty `name` has the server pick one.
+    pub async fn create_snapshot(&self, name: &str) -> Result<SnapshotInfo, Status> {
+        let request = CreateSnapshotRequest { name: name.to_string() };
+        without_retries(&self.retries, self.snapshots.clone().create_snapshot(request))
+            .await
+            .map(|r| r.into_inner())
+    }
+
+    pub async fn list_snapshots(&self) -> Result<Vec<SnapshotInfo>, Status> {
+        with_retries(&self.retries, || {
+            let mut client = self.snapshots.clone();
+            let request = ListSnapshotsRequest {};
+            async move { client.list_snapshots(request).await.map(|r| r.into_inner().snapshots) }
+        })
+        .await
+    }
+
+    // Replaces everything stored for the caller's institution with the snapshot's contents.
+    pub async fn restore_snapshot(&self, name: &str) -> Result<SnapshotInfo, Status> {
+        let request = RestoreSnapshotRequest { name: name.to_string() };
+        without_retries(&self.retries, self.snapshots.clone().restore_snapshot(request))
+            .await
+            .map(|r| r.into_inner())
+    }
+
+    // The server's settings in effect, as JSON, and its maintenance mode.
+    pub async fn get_config(&self) -> Result<GetConfigResponse, Status> {
+        with_retries(&self.retries, || {
+            let mut client = self.runtime.clone();
+            async move { client.get_config(GetConfigRequest {}).await.map(|r| r.into_inner()) }
+        })
+        .await
+    }
+
+    // The server streams open on the server, across every institution.
+    pub async fn list_streams(&self) -> Result<ListStreamsResponse, Status> {
+        with_retries(&self.retries, || {
+            let mut client = self.runtime.clone();
+            async move { client.list_streams(ListStreamsRequest {}).await.map(|r| r.into_inner()) }
+        })
+        .await
+    }
+
+    // While enabled, the server serves reads and rejects writes with UNAVAILABLE.
+    pub async fn set_maintenance_mode(&self, enabled: bool, reason: &str) -> Result<MaintenanceMode, Status> {
+        let request = SetMaintenanceModeRequest {
+            enabled,
+            reason: reason.to_string(),
+        };
+        without_retries(&self.retries, self.runtime.clone().set_maintenance_mode(request))
+            .await
+            .map(|r| r.into_inner())
+    }
+
+    // Snapshots every institution now, as the periodic snapshot task does.
+    pub async fn trigger_snapshot(&self) -> Result<Vec<InstitutionSnapshot>, Status> {
+        without_retries(&self.retries, self.runtime.clone().trigger_snapshot(TriggerSnapshotRequest {}))
+            .await
+            .map(|r| r.into_inner().snapshots)
+    }
+}
@@ -0,0 +1,84 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy)]
+enum State {
+    // Calls flow; counts transient failures in a row
+    Closed { failures: u32 },
+    // Calls fail fast until `until`
+    Open { until: Instant },
+    // One trial call is in flight; its outcome closes or reopens the breaker.
+    // A trial that never reports back (e.g. cancelled) is replaced after `open_for`.
+    HalfOpen { since: Instant },
+}
+
+// Circuit breaker shared by every call made through a client. After
+// `failure_threshold` transient failures in a row it opens and calls fail
+// immediately for `open_for`; then a single trial call decides whether the
+// server is back.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    state: Arc<Mutex<State>>,
+    failure_threshold: u32,
+    open_for: Duration,
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, open_for: Duration) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(State::Closed { failures: 0 })),
+            failure_threshold: failure_threshold.max(1),
+            open_for,
+        }
+    }
+
+    // Whether a call may go ahead. Returns false while the breaker is open,
+    // or half-open with the trial call still outstanding.
+    pub fn try_acquire(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match *state {
+            State::Closed { .. } => true,
+            State::Open { until } if Instant::now() >= until => {
+                *state = State::HalfOpen { since: Instant::now() };
+                true
+            }
+            State::HalfOpen { since } if since.elapsed() >= self.open_for => {
+                *state = State::HalfOpen { since: Instant::now() };
+                true
+            }
+            State::Open { .. } | State::HalfOpen { .. } => false,
+        }
+    }
+
+    pub fn record_success(&self) {
+        *self.state.lock().unwrap() = State::Closed { failures: 0 };
+    }
+
+    // Records a transient failure, opening the breaker at the threshold or
+    // when the half-open trial fails.
+    pub fn record_failure(&self) {
+        let mut state = self.state.lock().unwrap();
+        let failures = match *state {
+            State::Closed { failures } => failures + 1,
+            State::HalfOpen { .. } => self.failure_threshold,
+            // A call admitted before the breaker opened; it stays open
+            State::Open { .. } => return,
+        };
+
+        *state = if failures >= self.failure_threshold {
+            tracing::warn!(open_for = ?self.open_for, "circuit breaker open, failing fast");
+            State::Open {
+                until: Instant::now() + self.open_for,
+            }
+        } else {
+            State::Closed { failures }
+        };
+    }
+}
+
+impl Default for CircuitBreaker {
+    // Opens after 5 transient failures in a row, for 10 seconds.
+    fn default() -> Self {
+        Self::new(5, Duration::from_secs(10))
+    }
+}
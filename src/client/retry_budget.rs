@@ -0,0 +1,49 @@
+use std::sync::{Arc, Mutex};
+
+// Token-bucket retry budget shared by every call made through a client.
+// Each request deposits `retry_ratio` tokens and each retry withdraws one,
+// so the aggregate retry rate is capped at roughly `retry_ratio` of request volume.
+#[derive(Debug, Clone)]
+pub struct RetryBudget {
+    tokens: Arc<Mutex<f64>>,
+    retry_ratio: f64,
+    max_tokens: f64,
+}
+
+impl RetryBudget {
+    // `retry_ratio` is the fraction of requests that may be retried (0.1 = 10%).
+    // `max_tokens` bounds how many retries can be saved up for a burst; the
+    // bucket starts full so a fresh client can still retry a few early failures.
+    pub fn new(retry_ratio: f64, max_tokens: f64) -> Self {
+        Self {
+            tokens: Arc::new(Mutex::new(max_tokens)),
+            retry_ratio,
+            max_tokens,
+        }
+    }
+
+    // Credits the budget for an original (non-retry) request.
+    pub fn deposit(&self) {
+        let mut tokens = self.tokens.lock().unwrap();
+        *tokens = (*tokens + self.retry_ratio).min(self.max_tokens);
+    }
+
+    // Spends one token for a retry. Returns false when the budget is exhausted
+    // and the caller should fail fast instead.
+    pub fn try_withdraw(&self) -> bool {
+        let mut tokens = self.tokens.lock().unwrap();
+        if *tokens >= 1.0 {
+            *tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl Default for RetryBudget {
+    // Retries limited to 10% of request volume with a burst of up to 10.
+    fn default() -> Self {
+        Self::new(0.1, 10.0)
+    }
+}
@@ -0,0 +1,46 @@
+use std::time::Duration;
+use rand::Rng;
+
+// How often and how patiently a failed call is retried. Waits grow
+// exponentially from `initial_backoff` up to `max_backoff`, and each wait is
+// drawn at random below that bound ("full jitter") so clients that failed
+// together do not retry together.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    // Attempts per call, including the original request; 1 disables retries.
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    // Growth of the backoff bound per retry.
+    pub multiplier: f64,
+}
+
+impl RetryPolicy {
+    // No retries: every call is attempted exactly once.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    // The wait before retry number `retry` (1 for the first retry).
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let growth = self.multiplier.max(1.0).powi(retry.saturating_sub(1).min(64) as i32);
+        // In seconds, so a long run of retries saturates at the cap instead of overflowing
+        let bound = (self.initial_backoff.as_secs_f64() * growth).min(self.max_backoff.as_secs_f64());
+        Duration::from_secs_f64(bound * rand::thread_rng().gen_range(0.0..=1.0))
+    }
+}
+
+impl Default for RetryPolicy {
+    // Three attempts, waiting up to 50ms and then 100ms, never more than 2s.
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(50),
+            max_backoff: Duration::from_secs(2),
+            multiplier: 2.0,
+        }
+    }
+}
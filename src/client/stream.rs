@@ -0,0 +1,226 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use futures::stream::{self, BoxStream};
+use futures::{Stream, StreamExt};
+use tonic::{Code, Status, Streaming};
+
+use crate::exam_service::{
+    ChangeKind, GetExamResultResponse, ListExamResultsPageRequest, ResultChange, WatchExamResultsRequest,
+};
+use super::ExamClient;
+
+// Why a stream from `ExamClient` failed, by what the caller can do about it.
+// Each variant keeps the status the server sent.
+#[derive(Debug, Clone)]
+pub enum StreamError {
+    // The token is missing or invalid, or its role may not make the call
+    Denied(Status),
+    // The request was refused as it stands; sending it again fails the same way
+    Invalid(Status),
+    NotFound(Status),
+    // The client's deadline, or the server's processing limit, passed first
+    DeadlineExceeded(Status),
+    // The server was unreachable or overloaded through every retry
+    Unavailable(Status),
+    Other(Status),
+}
+
+impl StreamError {
+    pub fn status(&self) -> &Status {
+        match self {
+            StreamError::Denied(status)
+            | StreamError::Invalid(status)
+            | StreamError::NotFound(status)
+            | StreamError::DeadlineExceeded(status)
+            | StreamError::Unavailable(status)
+            | StreamError::Other(status) => status,
+        }
+    }
+
+    pub fn code(&self) -> Code {
+        self.status().code()
+    }
+}
+
+impl From<Status> for StreamError {
+    fn from(status: Status) -> Self {
+        match status.code() {
+            Code::Unauthenticated | Code::PermissionDenied => StreamError::Denied(status),
+            Code::InvalidArgument | Code::OutOfRange | Code::FailedPrecondition => StreamError::Invalid(status),
+            Code::NotFound => StreamError::NotFound(status),
+            Code::DeadlineExceeded => StreamError::DeadlineExceeded(status),
+            Code::Unavailable | Code::ResourceExhausted => StreamError::Unavailable(status),
+            _ => StreamError::Other(status),
+        }
+    }
+}
+
+impl From<StreamError> for Status {
+    fn from(error: StreamError) -> Self {
+        match error {
+            StreamError::Denied(status)
+            | StreamError::Invalid(status)
+            | StreamError::NotFound(status)
+            | StreamError::DeadlineExceeded(status)
+            | StreamError::Unavailable(status)
+            | StreamError::Other(status) => status,
+        }
+    }
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}: {}", self.code(), self.status().message())
+    }
+}
+
+impl std::error::Error for StreamError {}
+
+// One change seen by `ExamClient::watch_events`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WatchEvent {
+    // A result was created, resubmitted, corrected, published or restored; the result as it now is
+    Changed { kind: ChangeKind, result: GetExamResultResponse },
+    // The result as it was when deleted
+    Deleted(GetExamResultResponse),
+    // The server dropped the watch, e.g. on restart or because it fell behind,
+    // and it was opened again. Changes made meanwhile were missed; re-read
+    // whatever matters.
+    Resubscribed,
+}
+
+impl From<ResultChange> for WatchEvent {
+    fn from(change: ResultChange) -> Self {
+        let kind = change.kind();
+        let result = change.result.unwrap_or_default();
+        match kind {
+            ChangeKind::Deleted => WatchEvent::Deleted(result),
+            kind => WatchEvent::Changed { kind, result },
+        }
+    }
+}
+
+// A typed stream of items from the server that recovers from dropped
+// connections where it can. Iterate it as a `Stream` of `Result<T, StreamError>`,
+// or consume it whole with `collect_all` and `try_for_each`. The stream ends
+// after the first error.
+pub struct ResultStream<T> {
+    items: BoxStream<'static, Result<T, StreamError>>,
+}
+
+impl<T: Send + 'static> ResultStream<T> {
+    fn new(items: impl Stream<Item = Result<T, StreamError>> + Send + 'static) -> Self {
+        Self { items: items.boxed() }
+    }
+
+    // Every item, or the error the stream stopped with.
+    pub async fn collect_all(self) -> Result<Vec<T>, StreamError> {
+        let mut items = self.items;
+        let mut collected = Vec::new();
+        while let Some(item) = items.next().await {
+            collected.push(item?);
+        }
+        Ok(collected)
+    }
+
+    // Runs `f` on each item in turn, stopping at the first error from either.
+    pub async fn try_for_each<E, F, Fut>(self, mut f: F) -> Result<(), E>
+    where
+        E: From<StreamError>,
+        F: FnMut(T) -> Fut,
+        Fut: Future<Output = Result<(), E>>,
+    {
+        let mut items = self.items;
+        while let Some(item) = items.next().await {
+            f(item?).await?;
+        }
+        Ok(())
+    }
+}
+
+impl<T> Stream for ResultStream<T> {
+    type Item = Result<T, StreamError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.items.poll_next_unpin(cx)
+    }
+}
+
+impl<T> fmt::Debug for ResultStream<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResultStream").finish_non_exhaustive()
+    }
+}
+
+// A listing fetched page by page. Each page's token is where the listing
+// resumes: a page that fails is retried from it, so a dropped connection or
+// restarted server costs at most that page's round trip.
+pub(super) async fn listing(
+    client: ExamClient,
+    mut request: ListExamResultsPageRequest,
+) -> Result<ResultStream<GetExamResultResponse>, StreamError> {
+    // The first page is fetched now, so a request that cannot succeed fails here
+    let first = client.list_results_page(request.clone()).await?;
+    request.page_token = first.next_page_token;
+
+    let pages = (client, request, VecDeque::from(first.results));
+    let items = stream::unfold(Some(pages), |state| async move {
+        let (client, mut request, mut buffered) = state?;
+        loop {
+            if let Some(result) = buffered.pop_front() {
+                return Some((Ok(result), Some((client, request, buffered))));
+            }
+            if request.page_token.is_empty() {
+                return None;
+            }
+            match client.list_results_page(request.clone()).await {
+                Ok(page) => {
+                    request.page_token = page.next_page_token;
+                    buffered.extend(page.results);
+                }
+                Err(status) => return Some((Err(status.into()), None)),
+            }
+        }
+    });
+
+    Ok(ResultStream::new(items))
+}
+
+// A watch opened again whenever the server drops it, with a `Resubscribed`
+// event each time. Watches carry no position to resume from, hence the event.
+pub(super) async fn watch(
+    client: ExamClient,
+    request: WatchExamResultsRequest,
+) -> Result<ResultStream<WatchEvent>, StreamError> {
+    let changes = client.subscribe(&request).await?;
+
+    let items = stream::unfold(Some((client, request, changes)), |state| async move {
+        let (client, request, mut changes) = state?;
+        match changes.next().await {
+            Some(Ok(change)) => Some((Ok(change.into()), Some((client, request, changes)))),
+            // Ended by a shutdown, a lost connection or falling behind: open it again
+            None => resubscribe(client, request).await,
+            Some(Err(status)) if matches!(status.code(), Code::Unavailable | Code::Aborted) => {
+                resubscribe(client, request).await
+            }
+            Some(Err(status)) => Some((Err(status.into()), None)),
+        }
+    });
+
+    Ok(ResultStream::new(items))
+}
+
+type WatchState = Option<(ExamClient, WatchExamResultsRequest, Streaming<ResultChange>)>;
+
+async fn resubscribe(
+    client: ExamClient,
+    request: WatchExamResultsRequest,
+) -> Option<(Result<WatchEvent, StreamError>, WatchState)> {
+    match client.subscribe(&request).await {
+        Ok(changes) => Some((Ok(WatchEvent::Resubscribed), Some((client, request, changes)))),
+        Err(status) => Some((Err(status.into()), None)),
+    }
+}
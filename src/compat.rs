@@ -0,0 +1,232 @@
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use http::uri::{PathAndQuery, Uri};
+use prost::Message;
+use tokio_stream::{Stream, StreamExt};
+use tonic::{Request, Response, Status, Streaming};
+use tower::{Layer, Service};
+
+use crate::exam_service::v1::exam_service_server::ExamService;
+use crate::exam_service::v2::exam_service_server::ExamService as _;
+use crate::exam_service::{v1, v2};
+use crate::server::ExamServiceImpl;
+use crate::store::ExamStore;
+
+// Clients from before the packages were versioned call this service name.
+const LEGACY_PREFIX: &str = "/exam.ExamService/";
+const V1_PREFIX: &str = "/exam.v1.ExamService/";
+
+// A stream of converted messages.
+type Converted<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>;
+
+// Re-encodes a message as its counterpart in another version. The versions
+// share field numbers and types, so this only fails on a diverged proto.
+fn convert<A: Message, B: Message + Default>(message: A) -> Result<B, Status> {
+    B::decode(message.encode_to_vec().as_slice())
+        .map_err(|err| Status::internal(format!("could not convert between ExamService versions: {}", err)))
+}
+
+// A v1 request as v2, keeping its metadata and extensions (identity, peer).
+fn upgrade<A: Message, B: Message + Default>(request: Request<A>) -> Result<Request<B>, Status> {
+    let (metadata, extensions, message) = request.into_parts();
+    Ok(Request::from_parts(metadata, extensions, convert(message)?))
+}
+
+// A v2 response as v1, keeping its metadata.
+fn downgrade<A: Message, B: Message + Default>(response: Result<Response<A>, Status>) -> Result<Response<B>, Status> {
+    let (metadata, message, extensions) = response?.into_parts();
+    Ok(Response::from_parts(metadata, convert(message)?, extensions))
+}
+
+// A v2 response stream as v1, converting each message as it is sent.
+fn downgrade_stream<A, B, St>(response: Result<Response<St>, Status>) -> Result<Response<Converted<B>>, Status>
+where
+    A: Message,
+    B: Message + Default + 'static,
+    St: Stream<Item = Result<A, Status>> + Send + 'static,
+{
+    Ok(response?.map(|stream| Box::pin(stream.map(|item| item.and_then(convert))) as Converted<B>))
+}
+
+// Implements the frozen v1 ExamService by translating every call to v2, so
+// both versions share one set of stores, streams and change feeds.
+#[derive(Debug)]
+pub(crate) struct ExamServiceV1<S> {
+    v2: Arc<ExamServiceImpl<S>>,
+}
+
+impl<S> ExamServiceV1<S> {
+    pub(crate) fn new(v2: Arc<ExamServiceImpl<S>>) -> Self {
+        Self { v2 }
+    }
+}
+
+#[tonic::async_trait]
+impl<S: ExamStore> ExamService for ExamServiceV1<S> {
+    async fn get_exam_result(
+        &self,
+        request: Request<v1::GetExamResultRequest>,
+    ) -> Result<Response<v1::GetExamResultResponse>, Status> {
+        downgrade(self.v2.get_exam_result(upgrade(request)?).await)
+    }
+
+    type GetExamResultStreamStream = Converted<v1::GetExamResultResponse>;
+
+    async fn get_exam_result_stream(
+        &self,
+        request: Request<v1::GetExamResultRequest>,
+    ) -> Result<Response<Self::GetExamResultStreamStream>, Status> {
+        downgrade_stream(self.v2.get_exam_result_stream(upgrade(request)?).await)
+    }
+
+    async fn submit_exam_result(
+        &self,
+        request: Request<v1::SubmitExamResultRequest>,
+    ) -> Result<Response<v1::SubmitExamResultResponse>, Status> {
+        downgrade(self.v2.submit_exam_result(upgrade(request)?).await)
+    }
+
+    async fn submit_question_scores(
+        &self,
+        request: Request<v1::SubmitQuestionScoresRequest>,
+    ) -> Result<Response<v1::SubmitExamResultResponse>, Status> {
+        downgrade(self.v2.submit_question_scores(upgrade(request)?).await)
+    }
+
+    async fn submit_exam_results(
+        &self,
+        request: Request<Streaming<v1::ExamResult>>,
+    ) -> Result<Response<v1::SubmitExamResultsResponse>, Status> {
+        let request = request.map(|stream| stream.map(|item| item.and_then(convert::<_, v2::ExamResult>)));
+        downgrade(self.v2.submit_results(request).await)
+    }
+
+    type GradeSessionStream = Converted<v1::GradeUpdate>;
+
+    async fn grade_session(
+        &self,
+        request: Request<Streaming<v1::AnswerSubmission>>,
+    ) -> Result<Response<Self::GradeSessionStream>, Status> {
+        let request = request.map(|stream| stream.map(|item| item.and_then(convert::<_, v2::AnswerSubmission>)));
+        downgrade_stream(self.v2.grade_answers(request).await)
+    }
+
+    type ListExamResultsStream = Converted<v1::GetExamResultResponse>;
+
+    async fn list_exam_results(
+        &self,
+        request: Request<v1::ListExamResultsRequest>,
+    ) -> Result<Response<Self::ListExamResultsStream>, Status> {
+        downgrade_stream(self.v2.list_exam_results(upgrade(request)?).await)
+    }
+
+    async fn list_exam_results_page(
+        &self,
+        request: Request<v1::ListExamResultsPageRequest>,
+    ) -> Result<Response<v1::ListExamResultsPageResponse>, Status> {
+        downgrade(self.v2.list_exam_results_page(upgrade(request)?).await)
+    }
+
+    type ExportResultsStream = Converted<v1::ExportChunk>;
+
+    async fn export_results(
+        &self,
+        request: Request<v1::ExportResultsRequest>,
+    ) -> Result<Response<Self::ExportResultsStream>, Status> {
+        downgrade_stream(self.v2.export_results(upgrade(request)?).await)
+    }
+
+    async fn delete_exam_result(
+        &self,
+        request: Request<v1::DeleteExamResultRequest>,
+    ) -> Result<Response<v1::DeleteExamResultResponse>, Status> {
+        downgrade(self.v2.delete_exam_result(upgrade(request)?).await)
+    }
+
+    async fn correct_exam_result(
+        &self,
+        request: Request<v1::CorrectExamResultRequest>,
+    ) -> Result<Response<v1::CorrectExamResultResponse>, Status> {
+        downgrade(self.v2.correct_exam_result(upgrade(request)?).await)
+    }
+
+    async fn get_exam_statistics(
+        &self,
+        request: Request<v1::GetExamStatisticsRequest>,
+    ) -> Result<Response<v1::ExamStatistics>, Status> {
+        downgrade(self.v2.get_exam_statistics(upgrade(request)?).await)
+    }
+
+    async fn get_transcript(
+        &self,
+        request: Request<v1::GetTranscriptRequest>,
+    ) -> Result<Response<v1::Transcript>, Status> {
+        downgrade(self.v2.get_transcript(upgrade(request)?).await)
+    }
+
+    type WatchExamResultsStream = Converted<v1::ResultChange>;
+
+    async fn watch_exam_results(
+        &self,
+        request: Request<v1::WatchExamResultsRequest>,
+    ) -> Result<Response<Self::WatchExamResultsStream>, Status> {
+        downgrade_stream(self.v2.watch_exam_results(upgrade(request)?).await)
+    }
+
+    async fn get_audit_trail(
+        &self,
+        request: Request<v1::GetAuditTrailRequest>,
+    ) -> Result<Response<v1::AuditTrail>, Status> {
+        downgrade(self.v2.get_audit_trail(upgrade(request)?).await)
+    }
+
+    async fn publish_exam_results(
+        &self,
+        request: Request<v1::PublishExamResultsRequest>,
+    ) -> Result<Response<v1::PublishExamResultsResponse>, Status> {
+        downgrade(self.v2.publish_exam_results(upgrade(request)?).await)
+    }
+}
+
+// Tower layer routing calls to the unversioned `exam.ExamService` to v1,
+// which is what those clients were built against.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LegacyPathLayer;
+
+impl<S> Layer<S> for LegacyPathLayer {
+    type Service = LegacyPaths<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        LegacyPaths { inner }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct LegacyPaths<S> {
+    inner: S,
+}
+
+impl<S, ReqBody> Service<http::Request<ReqBody>> for LegacyPaths<S>
+where
+    S: Service<http::Request<ReqBody>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: http::Request<ReqBody>) -> Self::Future {
+        if let Some(method) = request.uri().path().strip_prefix(LEGACY_PREFIX) {
+            let mut parts = request.uri().clone().into_parts();
+            parts.path_and_query = PathAndQuery::try_from(format!("{}{}", V1_PREFIX, method)).ok();
+            if let Ok(uri) = Uri::from_parts(parts) {
+                *request.uri_mut() = uri;
+            }
+        }
+        self.inner.call(request)
+    }
+}
@@ -0,0 +1,883 @@
+use std::collections::HashMap;
+use std::env;
+use std::error::Error;
+use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use figment::providers::{Env, Format, Serialized, Toml};
+use figment::Figment;
+use serde::{Deserialize, Serialize};
+use tonic::codec::CompressionEncoding;
+
+use crate::deadline::DEFAULT_MAX_PROCESSING;
+use crate::key::{InstitutionId, DEFAULT_INSTITUTION};
+
+// Path to the server's TOML config file. Without it, `exam-service.toml`
+// in the working directory is used if present.
+pub const CONFIG_PATH_ENV: &str = "EXAM_CONFIG";
+const DEFAULT_CONFIG_PATH: &str = "exam-service.toml";
+
+// Every setting can be overridden by `EXAM_<KEY>`; keys in a section are
+// prefixed with its name, e.g. `EXAM_TLS_CERT` or `EXAM_STREAM_CHANNEL_BUFFER`.
+const ENV_PREFIX: &str = "EXAM_";
+const SECTIONS: [&str; 19] = [
+    "cache",
+    "compression",
+    "connections",
+    "idempotency",
+    "jwt",
+    "limits",
+    "operations",
+    "privacy",
+    "rate_limit",
+    "replication",
+    "scheduler",
+    "sessions",
+    "snapshots",
+    "storage",
+    "stream",
+    "telemetry",
+    "tls",
+    "wal",
+    "webhooks",
+];
+
+// Selects SQLite at this path; kept from before the config file existed.
+const LEGACY_DB_PATH_ENV: &str = "EXAM_DB_PATH";
+
+// Shown in place of a secret that is set.
+const REDACTED: &str = "<redacted>";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    // Seeded with sample data and lost on restart
+    Memory,
+    Sqlite,
+    // In memory, made durable by the write-ahead log at `wal.path`
+    Wal,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct StorageConfig {
+    pub backend: StorageBackend,
+    // Database file for the SQLite backend, created if missing
+    pub path: PathBuf,
+}
+
+impl StorageConfig {
+    // The SQLite file of `institution`: `path` itself for the default
+    // institution, so single-school deployments keep their database, and
+    // `path` with the institution ID before the extension for the others.
+    pub fn path_for(&self, institution: &InstitutionId) -> PathBuf {
+        institution_path(&self.path, institution)
+    }
+}
+
+fn institution_path(path: &Path, institution: &InstitutionId) -> PathBuf {
+    if institution.as_str() == DEFAULT_INSTITUTION {
+        return path.to_path_buf();
+    }
+
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{}.{}.{}", stem, institution, extension.to_string_lossy()),
+        None => format!("{}.{}", stem, institution),
+    };
+    path.with_file_name(name)
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            backend: StorageBackend::Memory,
+            path: PathBuf::from("exam.db"),
+        }
+    }
+}
+
+// PEM files for TLS; no certificate and key means plaintext.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TlsConfig {
+    pub cert: Option<PathBuf>,
+    pub key: Option<PathBuf>,
+    // CA bundle used to verify client certificates; setting it enables mutual TLS
+    pub client_ca: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct StreamConfig {
+    // Messages buffered per server stream before the handler waits for the client; at least 1
+    pub channel_buffer: usize,
+    // Changes buffered per watcher before it falls behind and is dropped; at least 1
+    pub watch_buffer: usize,
+    // Server streams one client connection may have open at once; 0 sets no limit
+    pub max_per_connection: usize,
+}
+
+impl Default for StreamConfig {
+    fn default() -> Self {
+        Self {
+            channel_buffer: 4,
+            watch_buffer: 64,
+            max_per_connection: 32,
+        }
+    }
+}
+
+// Read-through cache of results in front of the storage backend, for GetExamResult.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    pub enabled: bool,
+    // Longest a cached result is served; writes through this server evict it sooner
+    pub ttl_secs: u64,
+    pub max_entries: u64,
+}
+
+impl CacheConfig {
+    pub fn ttl(&self) -> Duration {
+        Duration::from_secs(self.ttl_secs)
+    }
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl_secs: 30,
+            max_entries: 10_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    pub fn encoding(self) -> Option<CompressionEncoding> {
+        match self {
+            Compression::None => None,
+            Compression::Gzip => Some(CompressionEncoding::Gzip),
+            Compression::Zstd => Some(CompressionEncoding::Zstd),
+        }
+    }
+}
+
+// Message compression on the gRPC services. Responses are only compressed
+// for clients that advertise support for the encoding.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct CompressionConfig {
+    // Accept gzip- and zstd-compressed requests
+    pub accept: bool,
+    // Encoding used for responses
+    pub send: Compression,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            accept: true,
+            send: Compression::None,
+        }
+    }
+}
+
+// How long write responses are remembered for retries carrying the same `request_id`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct IdempotencyConfig {
+    pub ttl_secs: u64,
+    // Responses kept at most; the oldest are evicted first
+    pub max_entries: usize,
+}
+
+impl IdempotencyConfig {
+    pub fn ttl(&self) -> Duration {
+        Duration::from_secs(self.ttl_secs)
+    }
+}
+
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        Self {
+            ttl_secs: 10 * 60,
+            max_entries: 10_000,
+        }
+    }
+}
+
+// Background imports, exports and publishes started through the Start* RPCs.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct OperationsConfig {
+    // How long a finished operation can still be watched, and an export downloaded
+    pub retention_secs: u64,
+    // Operations running at once across every institution; more are refused
+    pub max_running: usize,
+}
+
+impl OperationsConfig {
+    pub fn retention(&self) -> Duration {
+        Duration::from_secs(self.retention_secs)
+    }
+}
+
+impl Default for OperationsConfig {
+    fn default() -> Self {
+        Self {
+            retention_secs: 60 * 60,
+            max_running: 4,
+        }
+    }
+}
+
+// Exams sat through SessionService.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SessionConfig {
+    // Time allowed per sitting; a sitting also ends when its exam closes
+    pub duration_secs: u64,
+    // Time between the countdown events sent to students; 0 sends none
+    pub countdown_interval_secs: u64,
+}
+
+impl SessionConfig {
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs(self.duration_secs)
+    }
+
+    pub fn countdown_interval(&self) -> Option<Duration> {
+        interval(self.countdown_interval_secs)
+    }
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            duration_secs: 60 * 60,
+            countdown_interval_secs: 60,
+        }
+    }
+}
+
+// Bearer tokens issued by an OIDC provider, accepted alongside the static API
+// tokens. Signatures are checked against the provider's published keys (JWKS),
+// which are cached and fetched again every `jwks_refresh_secs` or when a token
+// names a key not yet seen.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct JwtConfig {
+    // Required `iss` of every token; empty accepts no JWTs
+    pub issuer: String,
+    // Required `aud`; empty skips the check
+    pub audience: String,
+    // Where the keys are published; empty discovers it from the issuer's
+    // /.well-known/openid-configuration
+    pub jwks_url: String,
+    // CA bundle verifying an https:// issuer; without it only http:// can be used
+    pub ca: Option<PathBuf>,
+    pub jwks_refresh_secs: u64,
+    // Clock skew allowed on `exp` and `nbf`
+    pub leeway_secs: u64,
+    // Claim holding the caller's roles or groups, a string or a list of them
+    pub roles_claim: String,
+    // Claim values granting each role: "student", "teacher" or "admin". The
+    // highest granted applies; tokens granting none are refused
+    pub roles: HashMap<String, String>,
+    // Claim holding a student's ID, required of student tokens
+    pub student_id_claim: String,
+    // Claim naming the caller in the audit log; `sub` when missing
+    pub name_claim: String,
+    // Claim naming the caller's institution; empty puts every caller in the default one
+    pub institution_claim: String,
+}
+
+impl JwtConfig {
+    pub fn enabled(&self) -> bool {
+        !self.issuer.is_empty()
+    }
+
+    pub fn jwks_refresh(&self) -> Duration {
+        Duration::from_secs(self.jwks_refresh_secs)
+    }
+}
+
+impl Default for JwtConfig {
+    fn default() -> Self {
+        let roles = ["student", "teacher", "admin"].map(|role| (role.to_string(), role.to_string()));
+
+        Self {
+            issuer: String::new(),
+            audience: String::new(),
+            jwks_url: String::new(),
+            ca: None,
+            jwks_refresh_secs: 60 * 60,
+            leeway_secs: 60,
+            roles_claim: "roles".to_string(),
+            roles: HashMap::from(roles),
+            student_id_claim: "student_id".to_string(),
+            name_claim: "preferred_username".to_string(),
+            institution_claim: String::new(),
+        }
+    }
+}
+
+// How student names reach callers other than teachers and admins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NameDisclosure {
+    Show,
+    // Left empty
+    Redact,
+    // Replaced with a stable pseudonym derived from the student ID
+    Pseudonymize,
+}
+
+// Privacy mode (FERPA) for result responses. Off by default: names are shown
+// and statistics are released for any number of results.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PrivacyConfig {
+    pub student_names: NameDisclosure,
+    // File holding the secret pseudonyms are keyed with, so they match across
+    // restarts and replicas; unset picks a random one at startup
+    pub pseudonym_key_file: Option<PathBuf>,
+    // Fewest results an exam needs before GetExamStatistics releases more than
+    // the count; 0 releases any
+    pub min_cohort_size: u32,
+}
+
+impl Default for PrivacyConfig {
+    fn default() -> Self {
+        Self {
+            student_names: NameDisclosure::Show,
+            pseudonym_key_file: None,
+            min_cohort_size: 0,
+        }
+    }
+}
+
+// TCP and HTTP/2 settings of gRPC connections, applied when the server starts.
+// Keepalives let a server holding many long-lived WatchExamResults streams
+// notice dead peers and free their stream slots, rather than waiting on the OS.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ConnectionConfig {
+    // Idle time before TCP keepalive probes are sent; 0 sends none
+    pub tcp_keepalive_secs: u64,
+    pub tcp_nodelay: bool,
+    // Time between HTTP/2 PINGs on each connection; 0 sends none
+    pub http2_keepalive_interval_secs: u64,
+    // How long a PING may go unacknowledged before the connection is closed
+    pub http2_keepalive_timeout_secs: u64,
+    // Streams a client may open at once on one connection; 0 leaves hyper's default
+    pub max_concurrent_streams: u32,
+    // RPCs handled at once per connection, open streams included; 0 for no limit
+    pub concurrency_limit_per_connection: usize,
+    // Initial HTTP/2 flow-control windows, in bytes; 0 leaves hyper's defaults
+    pub initial_stream_window_size: u32,
+    pub initial_connection_window_size: u32,
+    // Grows the windows to fit the measured bandwidth, overriding the two above
+    pub http2_adaptive_window: bool,
+}
+
+impl ConnectionConfig {
+    pub fn tcp_keepalive(&self) -> Option<Duration> {
+        interval(self.tcp_keepalive_secs)
+    }
+
+    pub fn http2_keepalive_interval(&self) -> Option<Duration> {
+        interval(self.http2_keepalive_interval_secs)
+    }
+
+    pub fn http2_keepalive_timeout(&self) -> Duration {
+        Duration::from_secs(self.http2_keepalive_timeout_secs)
+    }
+}
+
+impl Default for ConnectionConfig {
+    fn default() -> Self {
+        Self {
+            tcp_keepalive_secs: 60,
+            tcp_nodelay: true,
+            http2_keepalive_interval_secs: 60,
+            http2_keepalive_timeout_secs: 20,
+            max_concurrent_streams: 0,
+            concurrency_limit_per_connection: 0,
+            initial_stream_window_size: 0,
+            initial_connection_window_size: 0,
+            http2_adaptive_window: false,
+        }
+    }
+}
+
+// Largest gRPC message, in bytes, the services decode from clients and encode
+// for them; either way an oversized message fails the call with OUT_OF_RANGE.
+// Page and export chunk sizes keep normal responses well below the default.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LimitsConfig {
+    pub max_decoding_message_bytes: usize,
+    pub max_encoding_message_bytes: usize,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_decoding_message_bytes: 4 * 1024 * 1024,
+            max_encoding_message_bytes: 4 * 1024 * 1024,
+        }
+    }
+}
+
+// Token-bucket limit: `requests_per_second` sustained, with bursts up to `burst`.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct MethodLimit {
+    pub requests_per_second: f64,
+    pub burst: u32,
+}
+
+// Per-client rate limits. Clients are identified by bearer token, or by IP
+// address when they send none; each client gets its own bucket per method.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    // Limit for methods without an override
+    pub requests_per_second: f64,
+    pub burst: u32,
+    // Overrides keyed by RPC method name, e.g. `SubmitExamResult`
+    pub methods: HashMap<String, MethodLimit>,
+}
+
+impl RateLimitConfig {
+    // The limit applying to `method` (the bare RPC name).
+    pub fn limit_for(&self, method: &str) -> MethodLimit {
+        self.methods.get(method).copied().unwrap_or(MethodLimit {
+            requests_per_second: self.requests_per_second,
+            burst: self.burst,
+        })
+    }
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            requests_per_second: 50.0,
+            burst: 100,
+            methods: HashMap::new(),
+        }
+    }
+}
+
+// Delivery of webhook events. A failed POST is retried with exponential
+// backoff and jitter; receivers should dedupe on the delivery ID.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct WebhookConfig {
+    // Attempts per event and webhook, including the first
+    pub max_attempts: u32,
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    // Longest one POST may take, from connecting to the response status
+    pub timeout_secs: u64,
+    // CA bundle used to verify https receivers; without it only http:// URLs can be registered
+    pub ca: Option<PathBuf>,
+}
+
+impl WebhookConfig {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 60_000,
+            timeout_secs: 10,
+            ca: None,
+        }
+    }
+}
+
+// The write-ahead log of the `wal` backend. Every write is appended before
+// it is acknowledged and replayed at startup; the log is compacted into one
+// record of the whole store once it grows past `compact_after` writes.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct WalConfig {
+    pub path: PathBuf,
+    // fsync after every write; off, a power loss can drop the last writes, though a crash cannot
+    pub sync: bool,
+    // Writes logged before compacting; 0 never compacts while running
+    pub compact_after: usize,
+}
+
+impl WalConfig {
+    // The log of `institution`, named from `path` as `StorageConfig::path_for` names databases.
+    pub fn path_for(&self, institution: &InstitutionId) -> PathBuf {
+        institution_path(&self.path, institution)
+    }
+}
+
+impl Default for WalConfig {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("exam.wal"),
+            sync: true,
+            compact_after: 10_000,
+        }
+    }
+}
+
+// Replication between the servers of one deployment. Each server logs the
+// writes made on it and serves them through SyncService; it subscribes to
+// every peer listed and applies what they send to its own stores. Every
+// server should list every other, since writes are not passed on.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ReplicationConfig {
+    pub enabled: bool,
+    // Names this server to its peers; required when enabled, and unique per server
+    pub instance_id: String,
+    // URLs of the other servers, e.g. `http://exam-2:50051`
+    pub peers: Vec<String>,
+    // API token presented to peers; it must be an operator (`*/admin`) token there
+    pub token: String,
+    // Writes kept for peers to catch up on; one further behind is sent a snapshot
+    pub buffer: usize,
+    // Time between attempts to reach a peer that is down or ended its stream
+    pub retry_interval_secs: u64,
+}
+
+impl ReplicationConfig {
+    pub fn retry_interval(&self) -> Duration {
+        Duration::from_secs(self.retry_interval_secs)
+    }
+}
+
+impl Default for ReplicationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            instance_id: String::new(),
+            peers: Vec::new(),
+            token: String::new(),
+            buffer: 10_000,
+            retry_interval_secs: 5,
+        }
+    }
+}
+
+// Snapshot files, written by CreateSnapshot and read by RestoreSnapshot,
+// one subdirectory per institution.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SnapshotConfig {
+    pub dir: PathBuf,
+    // Seconds between automatic snapshots of every institution; 0 takes none
+    pub interval_secs: u64,
+    // Automatic snapshots kept per institution; older ones are deleted. Named ones are never deleted
+    pub keep: usize,
+}
+
+impl SnapshotConfig {
+    // The automatic snapshot interval, if automatic snapshots are enabled.
+    pub fn interval(&self) -> Option<Duration> {
+        interval(self.interval_secs)
+    }
+}
+
+impl Default for SnapshotConfig {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("snapshots"),
+            interval_secs: 0,
+            keep: 24,
+        }
+    }
+}
+
+// Intervals of the background maintenance jobs; 0 disables a job.
+// Automatic snapshots are configured under `snapshots`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SchedulerConfig {
+    // Seconds between sweeps of expired cache and idempotency entries
+    pub cache_eviction_secs: u64,
+    // Seconds between refreshes of the stored-result gauges on `/metrics`
+    pub store_metrics_secs: u64,
+    // Seconds between deletions of stale drafts
+    pub stale_drafts_secs: u64,
+    // Days after an exam closes that its unpublished drafts are deleted; 0 keeps them
+    pub stale_draft_days: u64,
+    // Seconds between purges of deleted results past their retention
+    pub purge_deleted_secs: u64,
+    // Days deleted results can be undeleted before they are purged; 0 keeps them
+    pub deleted_retention_days: u64,
+}
+
+impl SchedulerConfig {
+    pub fn cache_eviction_interval(&self) -> Option<Duration> {
+        interval(self.cache_eviction_secs)
+    }
+
+    pub fn store_metrics_interval(&self) -> Option<Duration> {
+        interval(self.store_metrics_secs)
+    }
+
+    // How often to delete stale drafts, if drafts ever become stale.
+    pub fn stale_drafts_interval(&self) -> Option<Duration> {
+        interval(self.stale_drafts_secs).filter(|_| self.stale_draft_days > 0)
+    }
+
+    // How long after an exam closes its drafts are kept.
+    pub fn stale_draft_age(&self) -> Duration {
+        Duration::from_secs(self.stale_draft_days * 24 * 60 * 60)
+    }
+
+    // How often to purge deleted results, if they are ever purged.
+    pub fn purge_deleted_interval(&self) -> Option<Duration> {
+        interval(self.purge_deleted_secs).filter(|_| self.deleted_retention_days > 0)
+    }
+
+    // How long deleted results are kept.
+    pub fn deleted_retention(&self) -> Duration {
+        Duration::from_secs(self.deleted_retention_days * 24 * 60 * 60)
+    }
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            cache_eviction_secs: 60,
+            store_metrics_secs: 30,
+            stale_drafts_secs: 60 * 60,
+            stale_draft_days: 0,
+            purge_deleted_secs: 60 * 60,
+            deleted_retention_days: 30,
+        }
+    }
+}
+
+fn interval(secs: u64) -> Option<Duration> {
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+// OpenTelemetry export of the per-RPC spans over OTLP/gRPC, to a collector,
+// Jaeger or Tempo. Trace context arrives and leaves as W3C `traceparent` metadata.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TelemetryConfig {
+    // Collector endpoint, e.g. `http://localhost:4317`; empty exports nothing
+    pub otlp_endpoint: String,
+    // Fraction of new traces sampled; calls in a trace the caller sampled always are
+    pub sample_ratio: f64,
+    // The `service.name` spans are exported under
+    pub service_name: String,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            otlp_endpoint: String::new(),
+            sample_ratio: 1.0,
+            service_name: "exam-service".to_string(),
+        }
+    }
+}
+
+// Everything the server binary needs to start, loaded by `ServerConfig::load`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    pub host: IpAddr,
+    pub port: u16,
+    // Unix domain socket also served, alongside `host:port`; unset serves TCP only
+    pub unix_socket: Option<PathBuf>,
+    // HTTP endpoint serving `/metrics`
+    pub metrics_addr: SocketAddr,
+    // REST/JSON gateway
+    pub http_addr: SocketAddr,
+    // Comma-separated origins allowed to use gRPC-Web, or `*`; empty allows none
+    pub cors_origins: String,
+    // Seconds in-flight RPCs and streams get to finish after a shutdown signal
+    pub shutdown_timeout_secs: u64,
+    // Longest a unary RPC or finite stream may run, whatever deadline the client set
+    pub max_processing_secs: u64,
+    // TOML file overriding the default grade boundaries
+    pub grading_config: Option<PathBuf>,
+    // File of API tokens, one EXAM_API_TOKENS entry per line; replaces EXAM_API_TOKENS when set
+    pub api_tokens_file: Option<PathBuf>,
+    // Institutions served, each from its own store; empty serves only `default`
+    pub institutions: Vec<String>,
+    pub cache: CacheConfig,
+    pub compression: CompressionConfig,
+    pub connections: ConnectionConfig,
+    pub idempotency: IdempotencyConfig,
+    pub jwt: JwtConfig,
+    pub limits: LimitsConfig,
+    pub operations: OperationsConfig,
+    pub privacy: PrivacyConfig,
+    pub rate_limit: RateLimitConfig,
+    pub replication: ReplicationConfig,
+    pub scheduler: SchedulerConfig,
+    pub sessions: SessionConfig,
+    pub snapshots: SnapshotConfig,
+    pub storage: StorageConfig,
+    pub stream: StreamConfig,
+    pub telemetry: TelemetryConfig,
+    pub tls: TlsConfig,
+    pub wal: WalConfig,
+    pub webhooks: WebhookConfig,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        let localhost = IpAddr::V6(Ipv6Addr::LOCALHOST);
+
+        Self {
+            host: localhost,
+            port: 50051,
+            unix_socket: None,
+            metrics_addr: SocketAddr::new(localhost, 9090),
+            http_addr: SocketAddr::new(localhost, 8080),
+            cors_origins: String::new(),
+            shutdown_timeout_secs: 30,
+            max_processing_secs: DEFAULT_MAX_PROCESSING.as_secs(),
+            grading_config: None,
+            api_tokens_file: None,
+            institutions: Vec::new(),
+            cache: CacheConfig::default(),
+            compression: CompressionConfig::default(),
+            connections: ConnectionConfig::default(),
+            idempotency: IdempotencyConfig::default(),
+            jwt: JwtConfig::default(),
+            limits: LimitsConfig::default(),
+            operations: OperationsConfig::default(),
+            privacy: PrivacyConfig::default(),
+            rate_limit: RateLimitConfig::default(),
+            replication: ReplicationConfig::default(),
+            scheduler: SchedulerConfig::default(),
+            sessions: SessionConfig::default(),
+            snapshots: SnapshotConfig::default(),
+            storage: StorageConfig::default(),
+            stream: StreamConfig::default(),
+            telemetry: TelemetryConfig::default(),
+            tls: TlsConfig::default(),
+            wal: WalConfig::default(),
+            webhooks: WebhookConfig::default(),
+        }
+    }
+}
+
+impl ServerConfig {
+    // Loads defaults, then the config file, then `EXAM_*` environment overrides.
+    pub fn load() -> Result<Self, Box<dyn Error>> {
+        let path = match env::var(CONFIG_PATH_ENV) {
+            Ok(path) if !Path::new(&path).exists() => {
+                return Err(format!("{} points to {}, which does not exist", CONFIG_PATH_ENV, path).into());
+            }
+            Ok(path) => PathBuf::from(path),
+            Err(_) => PathBuf::from(DEFAULT_CONFIG_PATH),
+        };
+
+        Self::from_figment(Self::figment(&path))
+    }
+
+    // Merges the sources in priority order; a missing file is skipped.
+    pub fn figment(path: &Path) -> Figment {
+        let mut figment = Figment::from(Serialized::defaults(Self::default())).merge(Toml::file(path));
+
+        if let Ok(db_path) = env::var(LEGACY_DB_PATH_ENV) {
+            let storage = StorageConfig {
+                backend: StorageBackend::Sqlite,
+                path: PathBuf::from(db_path),
+            };
+            figment = figment.merge(Serialized::default("storage", storage));
+        }
+
+        figment.merge(Env::prefixed(ENV_PREFIX).map(|key| {
+            // Keys arrive as written, e.g. `STREAM_CHANNEL_BUFFER`
+            let key = key.as_str().to_ascii_lowercase();
+            SECTIONS
+                .iter()
+                .find_map(|section| {
+                    let field = key.strip_prefix(section)?.strip_prefix('_')?;
+                    Some(format!("{}.{}", section, field).into())
+                })
+                .unwrap_or_else(|| key.into())
+        }))
+    }
+
+    pub fn from_figment(figment: Figment) -> Result<Self, Box<dyn Error>> {
+        // figment's Display names the offending key and source; its Debug form is unreadable
+        let config: Self = figment.extract().map_err(|e| e.to_string())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    // Rejects values that would only fail, or panic, once the server used them.
+    fn validate(&self) -> Result<(), Box<dyn Error>> {
+        if self.stream.channel_buffer == 0 {
+            return Err("stream.channel_buffer must be at least 1".into());
+        }
+        if self.stream.watch_buffer == 0 {
+            return Err("stream.watch_buffer must be at least 1".into());
+        }
+        Ok(())
+    }
+
+    // A copy fit to show callers, as GetConfig does: secrets that are set
+    // are replaced by a marker.
+    pub fn redacted(&self) -> Self {
+        let mut config = self.clone();
+        if !config.replication.token.is_empty() {
+            config.replication.token = REDACTED.to_string();
+        }
+        config
+    }
+
+    // Address the gRPC server binds to.
+    pub fn addr(&self) -> SocketAddr {
+        SocketAddr::new(self.host, self.port)
+    }
+
+    pub fn shutdown_timeout(&self) -> Duration {
+        Duration::from_secs(self.shutdown_timeout_secs)
+    }
+
+    pub fn max_processing_time(&self) -> Duration {
+        Duration::from_secs(self.max_processing_secs)
+    }
+
+    // The institutions to open stores for, validated and deduplicated.
+    pub fn institutions(&self) -> Result<Vec<InstitutionId>, Box<dyn Error>> {
+        if self.institutions.is_empty() {
+            return Ok(vec![InstitutionId::default()]);
+        }
+
+        let mut institutions = self
+            .institutions
+            .iter()
+            .map(|id| InstitutionId::parse(id).map_err(|status| format!("institutions: {}", status.message())))
+            .collect::<Result<Vec<_>, _>>()?;
+        institutions.sort();
+        institutions.dedup();
+        Ok(institutions)
+    }
+}
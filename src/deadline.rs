@@ -0,0 +1,135 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+use tonic::{Request, Status};
+use tracing::Instrument;
+
+// Longest a unary RPC or finite stream may run unless configured otherwise.
+pub const DEFAULT_MAX_PROCESSING: Duration = Duration::from_secs(30);
+
+tokio::task_local! {
+    // Set once the call run by `Deadline::run_write` has started writing
+    static WRITING: Arc<AtomicBool>;
+}
+
+// The point in time a call must finish by, if any.
+#[derive(Debug, Clone, Copy)]
+pub struct Deadline {
+    at: Option<Instant>,
+}
+
+impl Deadline {
+    // Reads the client's `grpc-timeout`, capped by `limit` when one is given.
+    // Open-ended streams pass no limit so only the client's deadline applies.
+    pub fn from_request<T>(request: &Request<T>, limit: Option<Duration>) -> Result<Self, Status> {
+        let requested = match request.metadata().get("grpc-timeout") {
+            Some(value) => {
+                let timeout = value.to_str().ok().and_then(parse_grpc_timeout).ok_or_else(|| {
+                    Status::invalid_argument(format!("grpc-timeout {:?} is not a valid timeout", value))
+                })?;
+                Some(timeout)
+            }
+            None => None,
+        };
+
+        let timeout = match (requested, limit) {
+            (Some(requested), Some(limit)) => Some(requested.min(limit)),
+            (requested, limit) => requested.or(limit),
+        };
+
+        Ok(Self {
+            at: timeout.map(|timeout| Instant::now() + timeout),
+        })
+    }
+
+    // Fails once the deadline has passed.
+    pub fn check(&self) -> Result<(), Status> {
+        match self.at {
+            Some(at) if Instant::now() >= at => Err(exceeded()),
+            _ => Ok(()),
+        }
+    }
+
+    // Resolves when the deadline passes; never resolves without one.
+    pub async fn expired(&self) {
+        match self.at {
+            Some(at) => tokio::time::sleep_until(at).await,
+            None => std::future::pending().await,
+        }
+    }
+
+    // Runs `call`, abandoning it with DEADLINE_EXCEEDED if the deadline passes first.
+    pub async fn run<T>(self, call: impl Future<Output = Result<T, Status>>) -> Result<T, Status> {
+        self.check()?;
+
+        tokio::select! {
+            result = call => result,
+            _ = self.expired() => Err(exceeded()),
+        }
+    }
+
+    // Runs a write RPC's `call` in a task of its own, so that neither the
+    // deadline nor the client going away can cut a write short. The deadline
+    // abandons `call` only until it calls `writing`; from then on it runs to
+    // the end, so a stored write always gets its audit record, watch event
+    // and idempotency entry.
+    pub async fn run_write<T: Send + 'static>(
+        self,
+        call: impl Future<Output = Result<T, Status>> + Send + 'static,
+    ) -> Result<T, Status> {
+        self.check()?;
+
+        let started = Arc::new(AtomicBool::new(false));
+        let call = WRITING.scope(started.clone(), call);
+        let task = tokio::spawn(
+            async move {
+                tokio::pin!(call);
+                tokio::select! {
+                    result = &mut call => result,
+                    _ = self.expired() => match started.load(Ordering::Acquire) {
+                        true => call.await,
+                        false => Err(exceeded()),
+                    },
+                }
+            }
+            .in_current_span(),
+        );
+        task.await
+            .map_err(|err| Status::internal(format!("write task failed: {}", err)))?
+    }
+}
+
+// Marks the call being run by `Deadline::run_write` as writing, so its
+// deadline no longer abandons it. Call just before the first store write.
+pub fn writing() {
+    let _ = WRITING.try_with(|started| started.store(true, Ordering::Release));
+}
+
+pub fn exceeded() -> Status {
+    Status::deadline_exceeded("Deadline exceeded before the call completed")
+}
+
+// Parses a gRPC `grpc-timeout` value: up to 8 digits followed by a unit
+// (H hours, M minutes, S seconds, m millis, u micros, n nanos).
+fn parse_grpc_timeout(value: &str) -> Option<Duration> {
+    if value.len() < 2 || value.len() > 9 {
+        return None;
+    }
+
+    let (digits, unit) = value.split_at(value.len() - 1);
+    let amount: u64 = digits.parse().ok()?;
+
+    let timeout = match unit {
+        "H" => Duration::from_secs(amount * 60 * 60),
+        "M" => Duration::from_secs(amount * 60),
+        "S" => Duration::from_secs(amount),
+        "m" => Duration::from_millis(amount),
+        "u" => Duration::from_micros(amount),
+        "n" => Duration::from_nanos(amount),
+        _ => return None,
+    };
+
+    Some(timeout)
+}
@@ -0,0 +1,48 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::time::Duration;
+use tonic::{Code, Status};
+use tonic_types::{ErrorDetails, StatusExt};
+
+// Statuses carrying google.rpc error details, so clients can act on an error
+// without parsing its message. Read them with `tonic_types::StatusExt`.
+
+// The ErrorInfo domain of errors raised by this service.
+const ERROR_DOMAIN: &str = "exam-service";
+
+// INVALID_ARGUMENT for one field, with a BadRequest detail naming it.
+pub fn invalid_field(field: &str, description: impl Into<String>) -> Status {
+    let description = description.into();
+    Status::with_error_details(
+        Code::InvalidArgument,
+        format!("{} {}", field, description),
+        ErrorDetails::with_bad_request_violation(field, description),
+    )
+}
+
+// NOT_FOUND with a ResourceInfo detail, e.g. ("exam_result", "123/math101").
+pub fn not_found(resource_type: &str, resource_name: impl Display, message: String) -> Status {
+    let details = ErrorDetails::with_resource_info(resource_type, resource_name.to_string(), "", message.clone());
+    Status::with_error_details(Code::NotFound, message, details)
+}
+
+// FAILED_PRECONDITION because something the request depends on is missing,
+// with a PreconditionFailure detail such as ("NOT_REGISTERED", "student/123").
+pub fn missing_dependency(violation_type: &str, subject: impl Display, message: String) -> Status {
+    let details =
+        ErrorDetails::with_precondition_failure_violation(violation_type, subject.to_string(), message.clone());
+    Status::with_error_details(Code::FailedPrecondition, message, details)
+}
+
+// RESOURCE_EXHAUSTED with a RetryInfo detail saying when to try again.
+pub fn retry_after(message: String, delay: Duration) -> Status {
+    Status::with_error_details(Code::ResourceExhausted, message, ErrorDetails::with_retry_info(Some(delay)))
+}
+
+// FAILED_PRECONDITION with an ErrorInfo detail, e.g. ("MAINTENANCE_MODE", "exam-service"),
+// for a call the server's state refuses until an operator changes it. Unlike
+// UNAVAILABLE, clients neither retry it nor count it against the server's health.
+pub fn refused(reason: &str, message: String) -> Status {
+    let details = ErrorDetails::with_error_info(reason, ERROR_DOMAIN, HashMap::<String, String>::new());
+    Status::with_error_details(Code::FailedPrecondition, message, details)
+}
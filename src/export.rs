@@ -0,0 +1,93 @@
+use tonic::Status;
+
+use crate::exam_service::{ExportChunk, ExportFormat, GetExamResultResponse};
+
+// A chunk is sent once this much output has been buffered.
+pub(crate) const CHUNK_SIZE: usize = 64 * 1024;
+
+// CSV columns, matching what `client import` reads back.
+const CSV_COLUMNS: [&str; 9] = [
+    "student_id",
+    "exam_id",
+    "student_name",
+    "subject",
+    "marks_obtained",
+    "total_marks",
+    "grade",
+    "internal_comment",
+    "version",
+];
+
+// Serializes results in the requested format and cuts the output into chunks.
+#[derive(Debug)]
+pub struct ExportEncoder {
+    format: ExportFormat,
+    buffer: Vec<u8>,
+}
+
+impl ExportEncoder {
+    pub fn new(format: ExportFormat) -> Result<Self, Status> {
+        let format = match format {
+            ExportFormat::Unspecified => ExportFormat::Csv,
+            format => format,
+        };
+
+        let mut encoder = Self {
+            format,
+            buffer: Vec::with_capacity(CHUNK_SIZE),
+        };
+
+        if format == ExportFormat::Csv {
+            encoder.write_csv(CSV_COLUMNS)?;
+        }
+
+        Ok(encoder)
+    }
+
+    // Appends one result, returning a chunk once enough output is buffered.
+    pub fn push(&mut self, result: &GetExamResultResponse) -> Result<Option<ExportChunk>, Status> {
+        match self.format {
+            ExportFormat::JsonLines => {
+                serde_json::to_writer(&mut self.buffer, result).map_err(encoding_failed)?;
+                self.buffer.push(b'\n');
+            }
+            _ => self.write_csv([
+                result.student_id.clone(),
+                result.exam_id.clone(),
+                result.student_name.clone(),
+                result.subject.clone(),
+                result.marks_obtained.to_string(),
+                result.total_marks.to_string(),
+                result.grade.clone(),
+                result.internal_comment.clone(),
+                result.version.to_string(),
+            ])?,
+        }
+
+        if self.buffer.len() < CHUNK_SIZE {
+            return Ok(None);
+        }
+
+        let data = std::mem::replace(&mut self.buffer, Vec::with_capacity(CHUNK_SIZE));
+        Ok(Some(ExportChunk { data }))
+    }
+
+    // The remaining output, if any.
+    pub fn finish(self) -> Option<ExportChunk> {
+        (!self.buffer.is_empty()).then_some(ExportChunk { data: self.buffer })
+    }
+
+    fn write_csv<I>(&mut self, record: I) -> Result<(), Status>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+    {
+        let mut writer = csv::Writer::from_writer(&mut self.buffer);
+        writer.write_record(record).map_err(encoding_failed)?;
+        writer.flush().map_err(encoding_failed)
+    }
+}
+
+fn encoding_failed(err: impl std::fmt::Display) -> Status {
+    Status::internal(format!("could not encode export: {}", err))
+}
@@ -0,0 +1,216 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use axum::{
+    extract::{self, Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use tonic::{Code, Request, Status};
+use tonic_types::StatusExt;
+use tracing::{info, info_span, Instrument};
+
+use crate::auth::{TokenAuth, INSTITUTION_HEADER};
+use crate::exam_service::exam_service_server::ExamService;
+use crate::maintenance::{Maintenance, MAINTENANCE_MODE};
+use crate::exam_service::{ExamResult, GetExamResultRequest, SubmitExamResultRequest};
+use crate::google::protobuf::FieldMask;
+use crate::server::ExamServiceImpl;
+use crate::store::ExamStore;
+use crate::telemetry::{ensure_request_id, REQUEST_ID_HEADER};
+
+const IDEMPOTENCY_KEY: &str = "idempotency-key";
+
+// The gateway calls the gRPC handlers directly, so both front ends share
+// the same store, validation, redaction and change feed.
+struct Gateway<S> {
+    exams: Arc<ExamServiceImpl<S>>,
+    auth: TokenAuth,
+    maintenance: Maintenance,
+}
+
+impl<S> Gateway<S> {
+    // Builds a tonic request carrying the caller's identity, as the interceptor would.
+    fn authorize<T>(&self, headers: &HeaderMap, message: T) -> Result<Request<T>, Status> {
+        let authorization = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok());
+        let institution = headers
+            .get(INSTITUTION_HEADER)
+            .map(|value| {
+                value
+                    .to_str()
+                    .map_err(|_| Status::invalid_argument(format!("{} must be ASCII", INSTITUTION_HEADER)))
+            })
+            .transpose()?;
+        let identity = self.auth.identify(authorization, institution)?;
+
+        let mut request = Request::new(message);
+        request.extensions_mut().insert(identity);
+        Ok(request)
+    }
+}
+
+// JSON body returned for every failed call.
+#[derive(Debug, Serialize)]
+struct ErrorBody {
+    code: String,
+    message: String,
+}
+
+// Wraps a gRPC status so handlers can return it with `?`.
+struct GatewayError(Status);
+
+impl From<Status> for GatewayError {
+    fn from(status: Status) -> Self {
+        Self(status)
+    }
+}
+
+impl IntoResponse for GatewayError {
+    fn into_response(self) -> Response {
+        let body = ErrorBody {
+            code: format!("{:?}", self.0.code()),
+            message: self.0.message().to_string(),
+        };
+        // HTTP clients know 503 as "come back later", which maintenance is
+        let status = match self.0.get_details_error_info() {
+            Some(info) if info.reason == MAINTENANCE_MODE => StatusCode::SERVICE_UNAVAILABLE,
+            _ => http_status(self.0.code()),
+        };
+        (status, Json(body)).into_response()
+    }
+}
+
+// Maps gRPC status codes onto their conventional HTTP equivalents.
+fn http_status(code: Code) -> StatusCode {
+    match code {
+        Code::Ok => StatusCode::OK,
+        Code::InvalidArgument | Code::OutOfRange => StatusCode::BAD_REQUEST,
+        Code::FailedPrecondition => StatusCode::UNPROCESSABLE_ENTITY,
+        Code::Unauthenticated => StatusCode::UNAUTHORIZED,
+        Code::PermissionDenied => StatusCode::FORBIDDEN,
+        Code::NotFound => StatusCode::NOT_FOUND,
+        Code::AlreadyExists | Code::Aborted => StatusCode::CONFLICT,
+        Code::ResourceExhausted => StatusCode::TOO_MANY_REQUESTS,
+        Code::Cancelled => StatusCode::REQUEST_TIMEOUT,
+        Code::Unimplemented => StatusCode::NOT_IMPLEMENTED,
+        Code::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+        Code::DeadlineExceeded => StatusCode::GATEWAY_TIMEOUT,
+        Code::Unknown | Code::Internal | Code::DataLoss => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+// Query parameters of GET /students/{student_id}/exams/{exam_id}.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ResultQuery {
+    // Comma-separated result fields to return, e.g. `grade,marks_obtained`
+    fields: String,
+}
+
+impl ResultQuery {
+    fn read_mask(&self) -> Option<FieldMask> {
+        let paths: Vec<String> = self
+            .fields
+            .split(',')
+            .map(str::trim)
+            .filter(|field| !field.is_empty())
+            .map(str::to_string)
+            .collect();
+        (!paths.is_empty()).then_some(FieldMask { paths })
+    }
+}
+
+// GET /students/{student_id}/exams/{exam_id}[?fields=grade,marks_obtained]
+async fn get_result<S: ExamStore>(
+    State(gateway): State<Arc<Gateway<S>>>,
+    Path((student_id, exam_id)): Path<(String, String)>,
+    Query(query): Query<ResultQuery>,
+    headers: HeaderMap,
+) -> Result<Response, GatewayError> {
+    let read_mask = query.read_mask();
+    let message = GetExamResultRequest {
+        student_id,
+        exam_id,
+        read_mask: read_mask.clone(),
+    };
+    let request = gateway.authorize(&headers, message)?;
+    let result = gateway.exams.get_exam_result(request).await?.into_inner();
+
+    // JSON has no wire defaults to omit, so leave out the fields not asked for
+    let Some(mask) = read_mask else {
+        return Ok(Json(result).into_response());
+    };
+    let mut body = serde_json::to_value(result).map_err(|err| Status::internal(err.to_string()))?;
+    if let Some(fields) = body.as_object_mut() {
+        fields.retain(|field, _| mask.paths.contains(field));
+    }
+    Ok(Json(body).into_response())
+}
+
+// POST /results with an ExamResult body; 201 when created, 200 when replaced.
+// An `Idempotency-Key` header is used as the request_id, so retries are safe.
+async fn submit_result<S: ExamStore>(
+    State(gateway): State<Arc<Gateway<S>>>,
+    headers: HeaderMap,
+    Json(result): Json<ExamResult>,
+) -> Result<Response, GatewayError> {
+    let request_id = headers
+        .get(IDEMPOTENCY_KEY)
+        .map(|value| value.to_str().map(str::to_string))
+        .transpose()
+        .map_err(|_| Status::invalid_argument("Idempotency-Key must be ASCII"))?
+        .unwrap_or_default();
+    let message = SubmitExamResultRequest {
+        result: Some(result),
+        request_id,
+    };
+    let request = gateway.authorize(&headers, message)?;
+    gateway.maintenance.check_write()?;
+    let response = gateway.exams.submit_exam_result(request).await?.into_inner();
+
+    let status = if response.created {
+        StatusCode::CREATED
+    } else {
+        StatusCode::OK
+    };
+    Ok((status, Json(response)).into_response())
+}
+
+// Gives each HTTP call a request ID, as the gRPC server does: logged by
+// everything the call does, and echoed on the response.
+async fn with_request_id(mut request: extract::Request, next: Next) -> Response {
+    let request_id = ensure_request_id(request.headers_mut());
+    let span = info_span!(
+        "http",
+        method = %request.method(),
+        path = request.uri().path(),
+        request_id = request_id.to_str().unwrap_or_default(),
+    );
+
+    let mut response = next.run(request).instrument(span).await;
+    response.headers_mut().insert(REQUEST_ID_HEADER, request_id);
+    response
+}
+
+// Serves the REST/JSON gateway on `addr` until the returned future is dropped.
+pub async fn serve_gateway<S: ExamStore>(
+    addr: SocketAddr,
+    exams: Arc<ExamServiceImpl<S>>,
+    auth: TokenAuth,
+    maintenance: Maintenance,
+) -> std::io::Result<()> {
+    let app = Router::new()
+        .route("/students/:student_id/exams/:exam_id", get(get_result::<S>))
+        .route("/results", post(submit_result::<S>))
+        .layer(middleware::from_fn(with_request_id))
+        .with_state(Arc::new(Gateway { exams, auth, maintenance }));
+
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    info!(%addr, "HTTP gateway listening");
+    axum::serve(listener, app).await
+}
@@ -0,0 +1,339 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tonic::{Request, Response, Status};
+use tracing::info;
+
+use crate::auth::{Identity, Role};
+use crate::deadline::{self, Deadline, DEFAULT_MAX_PROCESSING};
+use crate::errors::{invalid_field, missing_dependency};
+use crate::exam_service::ResultStatus;
+use crate::grader::grader_service_server::GraderService;
+use crate::grader::{
+    Allocation, AssignGradersRequest, DistributeSubmissionsRequest, DistributeSubmissionsResponse,
+    DistributionStrategy, ExamGraders, GetGradersRequest, GradingTask, ListMyGradingQueueRequest,
+};
+use crate::key::{ExamId, ResultKey};
+use crate::query::{scan_page, ResultFilter, MAX_PAGE_SIZE};
+use crate::schedule::now_ms;
+use crate::server::ExamServiceImpl;
+use crate::store::ExamStore;
+use crate::streaming::ResponseStream;
+
+// Implements GraderService. Only draft results are handed out: once a
+// grader publishes one, it leaves their queue and no longer counts
+// towards their load.
+#[derive(Debug)]
+pub struct GraderServiceImpl<S> {
+    results: Arc<ExamServiceImpl<S>>,
+    max_processing: Duration,
+    // Held while distributing, so two distributions cannot both hand out the same draft
+    distributing: Mutex<()>,
+}
+
+impl<S: ExamStore> GraderServiceImpl<S> {
+    pub fn new(results: Arc<ExamServiceImpl<S>>) -> Self {
+        Self {
+            results,
+            max_processing: DEFAULT_MAX_PROCESSING,
+            distributing: Mutex::new(()),
+        }
+    }
+
+    // Replaces the server-side processing limit.
+    pub fn with_max_processing_time(mut self, limit: Duration) -> Self {
+        self.max_processing = limit;
+        self
+    }
+
+    // The client's deadline for `request`, capped by the processing limit.
+    fn deadline<T>(&self, request: &Request<T>) -> Result<Deadline, Status> {
+        Deadline::from_request(request, Some(self.max_processing))
+    }
+
+    // The store of the caller's institution, where its graders are assigned.
+    fn store(&self, identity: &Identity) -> Result<&S, Status> {
+        self.results.tenants().for_caller(identity).map(|store| store.as_ref())
+    }
+
+    // Fails unless `exam_id` is in the catalog.
+    async fn catalogued(&self, identity: &Identity, exam_id: &ExamId) -> Result<(), Status> {
+        match self.store(identity)?.get_exam(exam_id).await? {
+            Some(_) => Ok(()),
+            None => Err(missing_dependency(
+                "NOT_IN_CATALOG",
+                format!("exam/{}", exam_id),
+                format!("Exam {} is not in the catalog", exam_id),
+            )),
+        }
+    }
+
+    // The draft results of one exam, each with the grader holding it, if
+    // any grader still assigned to the exam does.
+    async fn drafts(
+        &self,
+        identity: &Identity,
+        exam_id: &ExamId,
+        graders: &[String],
+    ) -> Result<BTreeMap<ResultKey, Option<String>>, Status> {
+        let store = self.store(identity)?;
+        let held: HashMap<_, _> = store
+            .list_allocations(Some(exam_id), None)
+            .await?
+            .into_iter()
+            .filter(|allocation| graders.contains(&allocation.grader))
+            .map(|allocation| (ResultKey::from(&allocation), allocation.grader))
+            .collect();
+
+        let filter = ResultFilter {
+            exam_id: Some(exam_id.to_string()),
+            ..Default::default()
+        };
+        let mut drafts = BTreeMap::new();
+        let mut cursor = None;
+
+        loop {
+            let page = scan_page(store, &filter, cursor, MAX_PAGE_SIZE).await?;
+            for result in page.results.iter().filter(|result| result.status() == ResultStatus::Draft) {
+                let key = ResultKey::from(result);
+                let grader = held.get(&key).cloned();
+                drafts.insert(key, grader);
+            }
+
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+        Ok(drafts)
+    }
+}
+
+// The drafts queued per grader, counting only those held by `graders`.
+fn load_of<'a>(graders: &[String], held: impl Iterator<Item = &'a String>) -> BTreeMap<String, i32> {
+    let mut load: BTreeMap<_, _> = graders.iter().map(|grader| (grader.clone(), 0)).collect();
+    for grader in held {
+        if let Some(count) = load.get_mut(grader) {
+            *count += 1;
+        }
+    }
+    load
+}
+
+#[tonic::async_trait]
+impl<S: ExamStore> GraderService for GraderServiceImpl<S> {
+    // Replaces the graders of one catalogued exam. Names must be distinct;
+    // an empty list unassigns everyone.
+    async fn assign_graders(&self, request: Request<AssignGradersRequest>) -> Result<Response<ExamGraders>, Status> {
+        info!(request = ?request.get_ref(), "assign graders");
+
+        let deadline = self.deadline(&request)?;
+        deadline
+            .run(async move {
+                let identity = Identity::from_request(&request)?;
+                identity.require_admin()?;
+                let req = request.into_inner();
+                let exam_id = ExamId::parse(&req.exam_id)?;
+
+                let mut seen = HashSet::new();
+                for grader in &req.graders {
+                    if grader.trim().is_empty() {
+                        return Err(invalid_field("graders", "must not contain empty names"));
+                    }
+                    if !seen.insert(grader) {
+                        return Err(invalid_field("graders", format!("names {} more than once", grader)));
+                    }
+                }
+
+                self.catalogued(&identity, &exam_id).await?;
+
+                let graders = ExamGraders {
+                    exam_id: exam_id.to_string(),
+                    graders: req.graders,
+                };
+                self.store(&identity)?.set_graders(graders.clone()).await?;
+
+                info!(%exam_id, graders = graders.graders.len(), "graders assigned");
+                Ok(Response::new(graders))
+            })
+            .await
+    }
+
+    // Returns the graders of one exam, in assignment order.
+    async fn get_graders(&self, request: Request<GetGradersRequest>) -> Result<Response<ExamGraders>, Status> {
+        info!(request = ?request.get_ref(), "get graders");
+
+        let deadline = self.deadline(&request)?;
+        deadline
+            .run(async move {
+                let identity = Identity::from_request(&request)?;
+                identity.require_staff()?;
+                let exam_id = ExamId::parse(&request.get_ref().exam_id)?;
+
+                let graders = self.store(&identity)?.get_graders(&exam_id).await?;
+                Ok(Response::new(ExamGraders {
+                    exam_id: exam_id.to_string(),
+                    graders,
+                }))
+            })
+            .await
+    }
+
+    // Hands out the exam's drafts that no assigned grader holds: new ones,
+    // and those of graders since unassigned. Drafts already held stay put.
+    async fn distribute_submissions(
+        &self,
+        request: Request<DistributeSubmissionsRequest>,
+    ) -> Result<Response<DistributeSubmissionsResponse>, Status> {
+        info!(request = ?request.get_ref(), "distribute submissions");
+
+        let deadline = self.deadline(&request)?;
+        deadline
+            .run(async move {
+                let identity = Identity::from_request(&request)?;
+                identity.require_admin()?;
+                let req = request.into_inner();
+                let exam_id = ExamId::parse(&req.exam_id)?;
+                let strategy = req.strategy();
+
+                let _distributing = self.distributing.lock().await;
+                let store = self.store(&identity)?;
+                let graders = store.get_graders(&exam_id).await?;
+                if graders.is_empty() {
+                    return Err(missing_dependency(
+                        "NO_GRADERS",
+                        format!("exam/{}", exam_id),
+                        format!("Exam {} has no graders assigned; assign some first", exam_id),
+                    ));
+                }
+
+                let drafts = self.drafts(&identity, &exam_id, &graders).await?;
+                let mut load = load_of(&graders, drafts.values().flatten());
+
+                // Round robin carries on after the grader who was handed the last draft
+                let mut turn = store.list_allocations(Some(&exam_id), None).await?.len();
+                let allocated_at_ms = now_ms();
+                let mut allocated = Vec::new();
+
+                for key in drafts.iter().filter(|(_, grader)| grader.is_none()).map(|(key, _)| key) {
+                    let grader = match strategy {
+                        DistributionStrategy::LeastLoaded => graders
+                            .iter()
+                            .min_by_key(|grader| load[grader.as_str()])
+                            .expect("graders is not empty"),
+                        DistributionStrategy::Unspecified | DistributionStrategy::RoundRobin => {
+                            turn += 1;
+                            &graders[(turn - 1) % graders.len()]
+                        }
+                    };
+                    *load.get_mut(grader.as_str()).expect("every grader has a load") += 1;
+
+                    allocated.push(Allocation {
+                        student_id: key.student_id.to_string(),
+                        exam_id: key.exam_id.to_string(),
+                        grader: grader.clone(),
+                        allocated_at_ms,
+                    });
+                }
+
+                if !allocated.is_empty() {
+                    store.put_allocations(allocated.clone()).await?;
+                }
+
+                info!(%exam_id, allocated = allocated.len(), ?strategy, "submissions distributed");
+                Ok(Response::new(DistributeSubmissionsResponse {
+                    allocated,
+                    load: load.into_iter().collect(),
+                }))
+            })
+            .await
+    }
+
+    // Server-Streaming RPC: the drafts allocated to one grader, in key order.
+    // Drafts of exams the grader has since been unassigned from are left out;
+    // the next distribution hands them to someone else.
+    type ListMyGradingQueueStream = ResponseStream<GradingTask>;
+
+    async fn list_my_grading_queue(
+        &self,
+        request: Request<ListMyGradingQueueRequest>,
+    ) -> Result<Response<Self::ListMyGradingQueueStream>, Status> {
+        info!(request = ?request.get_ref(), "list my grading queue");
+
+        let deadline = self.deadline(&request)?;
+        let identity = Identity::from_request(&request)?;
+        identity.require_staff()?;
+        let slot = self
+            .results
+            .streams()
+            .reserve(&request, "grader.GraderService/ListMyGradingQueue")?;
+        let req = request.into_inner();
+
+        let grader = if req.grader.is_empty() { identity.name.clone() } else { req.grader };
+        if grader != identity.name && identity.role != Role::Admin {
+            return Err(Status::permission_denied("Only admins can list another grader's queue"));
+        }
+        let exam_id = (!req.exam_id.is_empty()).then(|| ExamId::parse(&req.exam_id)).transpose()?;
+
+        // Gathered up front, so the queue is as of one moment
+        let tasks = deadline
+            .run(async {
+                let store = self.store(&identity)?;
+                let mut assigned: HashMap<String, bool> = HashMap::new();
+                let mut tasks = Vec::new();
+
+                for allocation in store.list_allocations(exam_id.as_ref(), Some(grader.as_str())).await? {
+                    let key = ResultKey::from(&allocation);
+                    let still_assigned = match assigned.get(&allocation.exam_id) {
+                        Some(still_assigned) => *still_assigned,
+                        None => {
+                            let still_assigned = store.get_graders(&key.exam_id).await?.contains(&grader);
+                            assigned.insert(allocation.exam_id.clone(), still_assigned);
+                            still_assigned
+                        }
+                    };
+                    if !still_assigned {
+                        continue;
+                    }
+
+                    let Some(result) = store.get(&key).await? else { continue };
+                    if result.status() != ResultStatus::Draft {
+                        continue;
+                    }
+
+                    let result = self.results.redaction().redact(identity.role, result.into());
+                    tasks.push(GradingTask {
+                        allocation: Some(allocation),
+                        student_name: result.student_name,
+                        subject: result.subject,
+                        marks_obtained: result.marks_obtained,
+                        total_marks: result.total_marks,
+                        version: result.version,
+                    });
+                }
+                Ok(tasks)
+            })
+            .await?;
+
+        let stream = self.results.streams().spawn(slot, |tx| async move {
+            for task in tasks {
+                let sent = tokio::select! {
+                    sent = tx.send(Ok(task)) => sent.is_ok(),
+                    _ = deadline.expired() => {
+                        info!("deadline passed, ending grading queue");
+                        let _ = tx.send(Err(deadline::exceeded())).await;
+                        return;
+                    }
+                };
+
+                if !sent {
+                    info!("client disconnected before grading queue finished");
+                    return;
+                }
+            }
+        });
+
+        Ok(Response::new(stream))
+    }
+}
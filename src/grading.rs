@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use serde::Deserialize;
+use tracing::info;
+
+use crate::query::grade_rank;
+
+// The lowest percentage that earns `grade`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GradeBoundary {
+    pub grade: String,
+    pub min_percent: f64,
+}
+
+// On-disk form of the grading configuration.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GradingConfig {
+    #[serde(default = "default_boundaries")]
+    pub default: Vec<GradeBoundary>,
+    // Boundaries for specific subjects, keyed by subject name
+    #[serde(default)]
+    pub subjects: HashMap<String, Vec<GradeBoundary>>,
+}
+
+fn default_boundaries() -> Vec<GradeBoundary> {
+    [("A+", 95.0), ("A", 85.0), ("B", 75.0), ("C", 65.0), ("D", 50.0), ("F", 0.0)]
+        .into_iter()
+        .map(|(grade, min_percent)| GradeBoundary {
+            grade: grade.to_string(),
+            min_percent,
+        })
+        .collect()
+}
+
+// Computes letter grades from marks using validated boundaries.
+#[derive(Debug, Clone)]
+pub struct GradingScheme {
+    default: Vec<GradeBoundary>,
+    subjects: HashMap<String, Vec<GradeBoundary>>,
+}
+
+impl GradingScheme {
+    pub fn from_config(config: GradingConfig) -> Result<Self, String> {
+        let default = validate_boundaries("default", config.default)?;
+        let subjects = config
+            .subjects
+            .into_iter()
+            .map(|(subject, boundaries)| {
+                let boundaries = validate_boundaries(&subject, boundaries)?;
+                Ok((subject, boundaries))
+            })
+            .collect::<Result<_, String>>()?;
+
+        Ok(Self { default, subjects })
+    }
+
+    // Loads boundaries from a TOML file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        info!(path = %path.as_ref().display(), "loading grade boundaries");
+        let config: GradingConfig = toml::from_str(&fs::read_to_string(path)?)?;
+        Ok(Self::from_config(config)?)
+    }
+
+    // Grade for a score given as a percentage, using the subject's override if any.
+    pub fn grade_for_percent(&self, subject: &str, percent: f64) -> &str {
+        let boundaries = self.subjects.get(subject).unwrap_or(&self.default);
+
+        boundaries
+            .iter()
+            .find(|boundary| percent >= boundary.min_percent)
+            .or(boundaries.last())
+            .map(|boundary| boundary.grade.as_str())
+            .unwrap_or_default()
+    }
+
+    // Grade for `marks_obtained` out of `total_marks`.
+    pub fn grade(&self, subject: &str, marks_obtained: i32, total_marks: i32) -> String {
+        self.grade_for_percent(subject, percentage(marks_obtained, total_marks))
+            .to_string()
+    }
+
+    // A score passes when it earns more than the subject's lowest (0%) grade.
+    pub fn passes(&self, subject: &str, marks_obtained: i32, total_marks: i32) -> bool {
+        let boundaries = self.subjects.get(subject).unwrap_or(&self.default);
+        let percent = percentage(marks_obtained, total_marks);
+
+        boundaries
+            .iter()
+            .rev()
+            .nth(1)
+            .is_some_and(|lowest_pass| percent >= lowest_pass.min_percent)
+    }
+}
+
+fn percentage(marks_obtained: i32, total_marks: i32) -> f64 {
+    if total_marks > 0 {
+        marks_obtained as f64 * 100.0 / total_marks as f64
+    } else {
+        0.0
+    }
+}
+
+impl Default for GradingScheme {
+    fn default() -> Self {
+        Self {
+            default: default_boundaries(),
+            subjects: HashMap::new(),
+        }
+    }
+}
+
+// Sorts boundaries highest first and checks they form a usable scale: known
+// grades (so min_grade filters can rank them), percentages within 0..=100,
+// and a 0% floor so every score maps to a grade.
+fn validate_boundaries(
+    name: &str,
+    mut boundaries: Vec<GradeBoundary>,
+) -> Result<Vec<GradeBoundary>, String> {
+    for boundary in &boundaries {
+        if grade_rank(&boundary.grade).is_none() {
+            return Err(format!("{}: unknown grade {:?}", name, boundary.grade));
+        }
+
+        if !(0.0..=100.0).contains(&boundary.min_percent) {
+            return Err(format!(
+                "{}: min_percent for {} must be between 0 and 100",
+                name, boundary.grade
+            ));
+        }
+    }
+
+    boundaries.sort_by(|a, b| b.min_percent.total_cmp(&a.min_percent));
+
+    if boundaries.last().is_none_or(|lowest| lowest.min_percent > 0.0) {
+        return Err(format!("{}: boundaries must include a 0% grade", name));
+    }
+
+    Ok(boundaries)
+}
@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use prost::Message;
+use tonic::Status;
+use tracing::info;
+
+use crate::auth::{Identity, Role};
+use crate::config::IdempotencyConfig;
+use crate::errors::invalid_field;
+use crate::key::InstitutionId;
+
+// Longest accepted `request_id`; IDs are opaque to the server.
+pub(crate) const MAX_REQUEST_ID_LEN: usize = 128;
+
+// Responses are cached per method, institution and caller role: IDs are only
+// unique within an institution, and redaction depends on the role.
+type Key = (&'static str, InstitutionId, Role, String);
+
+#[derive(Debug)]
+enum State {
+    // The first call with this ID has not finished yet
+    InFlight,
+    // The encoded response of a successful call
+    Done(Vec<u8>),
+}
+
+#[derive(Debug)]
+struct Entry {
+    // The encoded request, so a reused ID with a different payload is caught
+    fingerprint: Vec<u8>,
+    state: State,
+    stored: Instant,
+}
+
+// Remembers the responses of write RPCs by client-chosen `request_id`, so a
+// retried call returns the original response instead of applying twice.
+// Failed calls are forgotten so they can be retried.
+#[derive(Debug, Clone)]
+pub struct IdempotencyCache {
+    entries: Arc<Mutex<HashMap<Key, Entry>>>,
+    ttl: Duration,
+    max_entries: usize,
+}
+
+impl IdempotencyCache {
+    pub fn new(config: &IdempotencyConfig) -> Self {
+        Self {
+            entries: Arc::new(Mutex::new(HashMap::new())),
+            ttl: config.ttl(),
+            max_entries: config.max_entries,
+        }
+    }
+
+    // Runs `call` once per `request_id`. Duplicates get the stored response, and
+    // duplicates of a call still in progress get ABORTED. Without an ID, `call` just runs.
+    pub async fn run<Req, Resp>(
+        &self,
+        method: &'static str,
+        caller: &Identity,
+        request_id: &str,
+        request: &Req,
+        call: impl Future<Output = Result<Resp, Status>>,
+    ) -> Result<Resp, Status>
+    where
+        Req: Message,
+        Resp: Message + Default,
+    {
+        if request_id.is_empty() {
+            return call.await;
+        }
+
+        if request_id.len() > MAX_REQUEST_ID_LEN {
+            return Err(invalid_field(
+                "request_id",
+                format!("must be at most {} characters", MAX_REQUEST_ID_LEN),
+            ));
+        }
+
+        let key = (method, caller.institution.clone(), caller.role, request_id.to_string());
+        let fingerprint = request.encode_to_vec();
+
+        if let Some(response) = self.begin(&key, &fingerprint)? {
+            info!(method, request_id, "returning stored response for duplicate request");
+            return Resp::decode(response.as_slice())
+                .map_err(|e| Status::internal(format!("stored response is corrupt: {}", e)));
+        }
+
+        // Clears the in-flight marker if `call` fails or is abandoned at a deadline
+        let mut pending = Pending { cache: self, key: Some(key) };
+        let response = call.await?;
+
+        if let Some(key) = pending.key.take() {
+            self.finish(key, fingerprint, response.encode_to_vec());
+        }
+
+        Ok(response)
+    }
+
+    // Returns the stored response for a duplicate, or marks `key` in flight.
+    fn begin(&self, key: &Key, fingerprint: &[u8]) -> Result<Option<Vec<u8>>, Status> {
+        let mut entries = self.entries.lock().unwrap();
+        let now = Instant::now();
+
+        if let Some(entry) = entries.get(key).filter(|entry| now.duration_since(entry.stored) < self.ttl) {
+            if entry.fingerprint != fingerprint {
+                return Err(invalid_field(
+                    "request_id",
+                    format!("{} was already used for a different request", key.3),
+                ));
+            }
+
+            return match &entry.state {
+                State::Done(response) => Ok(Some(response.clone())),
+                State::InFlight => Err(Status::aborted(format!(
+                    "Request {} is still being processed; retry later",
+                    key.3
+                ))),
+            };
+        }
+
+        if entries.len() >= self.max_entries {
+            entries.retain(|_, entry| now.duration_since(entry.stored) < self.ttl);
+        }
+
+        // Still full of live responses: make room by forgetting the oldest
+        if entries.len() >= self.max_entries {
+            let oldest = entries
+                .iter()
+                .filter(|(_, entry)| matches!(entry.state, State::Done(_)))
+                .min_by_key(|(_, entry)| entry.stored)
+                .map(|(key, _)| key.clone());
+
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+            }
+        }
+
+        entries.insert(
+            key.clone(),
+            Entry {
+                fingerprint: fingerprint.to_vec(),
+                state: State::InFlight,
+                stored: now,
+            },
+        );
+        Ok(None)
+    }
+
+    // Forgets every expired response. Expired entries are otherwise only
+    // dropped once the cache fills up.
+    pub fn evict_expired(&self) {
+        let now = Instant::now();
+        self.entries
+            .lock()
+            .unwrap()
+            .retain(|_, entry| matches!(entry.state, State::InFlight) || now.duration_since(entry.stored) < self.ttl);
+    }
+
+    fn finish(&self, key: Key, fingerprint: Vec<u8>, response: Vec<u8>) {
+        self.entries.lock().unwrap().insert(
+            key,
+            Entry {
+                fingerprint,
+                state: State::Done(response),
+                stored: Instant::now(),
+            },
+        );
+    }
+}
+
+struct Pending<'a> {
+    cache: &'a IdempotencyCache,
+    key: Option<Key>,
+}
+
+impl Drop for Pending<'_> {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            self.cache.entries.lock().unwrap().remove(&key);
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use std::fmt;
+use tonic::Status;
+
+use crate::errors::invalid_field;
+
+use crate::exam_admin::Exam;
+use crate::exam_service::ExamResult;
+use crate::grader::{Allocation, ExamGraders};
+use crate::student::Student;
+
+// Longest student or exam ID accepted.
+const MAX_ID_LEN: usize = 64;
+
+// IDs may only contain ASCII letters, digits, '-', '_' and '.'.
+// Anything else is rejected so IDs are safe in logs, URLs and page tokens.
+fn validate_id(field: &str, value: &str) -> Result<(), Status> {
+    match id_problem(value) {
+        Some(problem) => Err(invalid_field(field, problem)),
+        None => Ok(()),
+    }
+}
+
+// What is wrong with `value` as an ID, phrased to follow the field name.
+pub(crate) fn id_problem(value: &str) -> Option<String> {
+    if value.is_empty() {
+        return Some("is required".to_string());
+    }
+
+    if value.len() > MAX_ID_LEN {
+        return Some(format!("must be at most {} characters", MAX_ID_LEN));
+    }
+
+    value
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')))
+        .map(|c| format!("contains illegal character {:?}", c))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StudentId(String);
+
+impl StudentId {
+    pub fn parse(value: &str) -> Result<Self, Status> {
+        validate_id("student_id", value)?;
+        Ok(Self(value.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ExamId(String);
+
+impl ExamId {
+    pub fn parse(value: &str) -> Result<Self, Status> {
+        validate_id("exam_id", value)?;
+        Ok(Self(value.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+// Institution every deployment serves; unscoped tokens belong to it.
+pub const DEFAULT_INSTITUTION: &str = "default";
+
+// One school sharing the deployment. Every institution has its own store,
+// so its results, catalog, roster and audit log are invisible to the others.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct InstitutionId(String);
+
+impl InstitutionId {
+    pub fn parse(value: &str) -> Result<Self, Status> {
+        validate_id("institution_id", value)?;
+        Ok(Self(value.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for InstitutionId {
+    fn default() -> Self {
+        Self(DEFAULT_INSTITUTION.to_string())
+    }
+}
+
+impl fmt::Display for InstitutionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+// Identifies one stored result. Ordered by student, then exam.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ResultKey {
+    pub student_id: StudentId,
+    pub exam_id: ExamId,
+}
+
+impl ResultKey {
+    // Validates both IDs, rejecting illegal input with INVALID_ARGUMENT.
+    pub fn parse(student_id: &str, exam_id: &str) -> Result<Self, Status> {
+        Ok(Self {
+            student_id: StudentId::parse(student_id)?,
+            exam_id: ExamId::parse(exam_id)?,
+        })
+    }
+}
+
+impl ResultKey {
+    // Sorts before every key belonging to `student_id`, for range scans over one student.
+    pub fn first_for_student(student_id: StudentId) -> Self {
+        Self {
+            student_id,
+            exam_id: ExamId(String::new()),
+        }
+    }
+}
+
+// Keys of stored results were validated when they were written.
+impl From<&ExamResult> for ResultKey {
+    fn from(result: &ExamResult) -> Self {
+        Self {
+            student_id: StudentId(result.student_id.clone()),
+            exam_id: ExamId(result.exam_id.clone()),
+        }
+    }
+}
+
+// Allocations are only made of stored results.
+impl From<&Allocation> for ResultKey {
+    fn from(allocation: &Allocation) -> Self {
+        Self {
+            student_id: StudentId(allocation.student_id.clone()),
+            exam_id: ExamId(allocation.exam_id.clone()),
+        }
+    }
+}
+
+// Registered students were validated when they were registered.
+impl From<&Student> for StudentId {
+    fn from(student: &Student) -> Self {
+        Self(student.student_id.clone())
+    }
+}
+
+impl fmt::Display for StudentId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+// Exams in the catalog were validated when they were created.
+impl From<&Exam> for ExamId {
+    fn from(exam: &Exam) -> Self {
+        Self(exam.exam_id.clone())
+    }
+}
+
+// Graders are only assigned to catalogued exams.
+impl From<&ExamGraders> for ExamId {
+    fn from(graders: &ExamGraders) -> Self {
+        Self(graders.exam_id.clone())
+    }
+}
+
+impl fmt::Display for ExamId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl fmt::Display for ResultKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.student_id.as_str(), self.exam_id.as_str())
+    }
+}
@@ -0,0 +1,120 @@
+// Exam result service: generated protobuf types, the gRPC server, and a typed client.
+// The `server` and `client` binaries are thin wrappers around this library.
+
+// tonic::Status is the error type for every handler and call helper; boxing it would fight the API.
+#![allow(clippy::result_large_err)]
+
+// ExamService in every version served. The current version, v2, is also
+// re-exported here; v1 is kept for existing clients.
+pub mod exam_service {
+    pub mod v1 {
+        tonic::include_proto!("exam.v1");
+    }
+
+    pub mod v2 {
+        tonic::include_proto!("exam.v2");
+    }
+
+    pub use v2::*;
+
+    // Encoded descriptors for the exam protos, served through gRPC reflection.
+    pub const FILE_DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("exam_descriptor");
+}
+
+// The well-known protobuf types the protos use, such as FieldMask.
+pub mod google {
+    pub mod protobuf {
+        tonic::include_proto!("google.protobuf");
+    }
+}
+
+pub mod exam_admin {
+    tonic::include_proto!("exam_admin");
+}
+
+pub mod student {
+    tonic::include_proto!("student");
+}
+
+pub mod appeal {
+    tonic::include_proto!("appeal");
+}
+
+pub mod webhook {
+    tonic::include_proto!("webhook");
+}
+
+pub mod snapshot {
+    tonic::include_proto!("snapshot");
+}
+
+pub mod admin {
+    tonic::include_proto!("admin");
+}
+
+pub mod exam_session {
+    tonic::include_proto!("exam_session");
+}
+
+pub mod grader {
+    tonic::include_proto!("grader");
+}
+
+pub mod exam_sync {
+    tonic::include_proto!("exam_sync");
+}
+
+pub mod auth;
+pub mod client;
+pub mod config;
+pub mod grading;
+pub mod key;
+pub mod model;
+pub mod scheduler;
+pub mod server;
+pub mod store;
+pub mod telemetry;
+pub mod transport;
+
+#[cfg(feature = "test-util")]
+pub mod test_util;
+
+mod appeals;
+mod audit;
+mod breakdown;
+mod catalog;
+mod compat;
+mod deadline;
+mod errors;
+mod export;
+mod gateway;
+mod graders;
+mod idempotency;
+mod locks;
+mod maintenance;
+mod methods;
+mod metrics;
+mod ops;
+mod query;
+mod read_mask;
+mod rate_limit;
+mod redaction;
+mod reload;
+mod replication;
+mod roster;
+mod runtime;
+mod schedule;
+mod session;
+mod sessions;
+mod shutdown;
+mod snapshots;
+mod statistics;
+mod streaming;
+mod tenancy;
+mod tls;
+mod transcript;
+mod validation;
+mod visibility;
+mod watch;
+mod web;
+mod webhooks;
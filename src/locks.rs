@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+
+use crate::key::{InstitutionId, ResultKey};
+
+// One async mutex per result being written, so writes to the same result run
+// one at a time, from the read they start with to their audit record, while
+// writes to other results go ahead in parallel. Stores still check versions:
+// the locks only cover this server, not other instances sharing a database.
+pub(crate) type ResultLocks = KeyLocks<(InstitutionId, ResultKey)>;
+
+pub(crate) type ResultLock = KeyLock<(InstitutionId, ResultKey)>;
+
+// One async mutex per key in use, created on first use.
+#[derive(Debug)]
+pub(crate) struct KeyLocks<K> {
+    locks: Arc<Mutex<HashMap<K, Arc<AsyncMutex<()>>>>>,
+}
+
+impl<K> Clone for KeyLocks<K> {
+    fn clone(&self) -> Self {
+        Self {
+            locks: self.locks.clone(),
+        }
+    }
+}
+
+impl<K> Default for KeyLocks<K> {
+    fn default() -> Self {
+        Self {
+            locks: Arc::default(),
+        }
+    }
+}
+
+impl<K: Eq + Hash + Clone> KeyLocks<K> {
+    // Waits for whoever holds `key`, if anyone, then holds it until the
+    // returned guard is dropped.
+    pub(crate) async fn lock(&self, key: K) -> KeyLock<K> {
+        let mutex = self.locks.lock().unwrap().entry(key.clone()).or_default().clone();
+
+        KeyLock {
+            guard: Some(mutex.lock_owned().await),
+            key,
+            locks: self.clone(),
+        }
+    }
+}
+
+// Holds one key locked. The mutex is forgotten once released with nobody
+// waiting for it, so keys no longer in use take up no memory.
+#[derive(Debug)]
+pub(crate) struct KeyLock<K: Eq + Hash> {
+    guard: Option<OwnedMutexGuard<()>>,
+    key: K,
+    locks: KeyLocks<K>,
+}
+
+impl<K: Eq + Hash> Drop for KeyLock<K> {
+    fn drop(&mut self) {
+        let mut locks = self.locks.locks.lock().unwrap();
+        // Released under the map's lock, so no one can take the mutex out meanwhile
+        self.guard.take();
+        if locks.get(&self.key).is_some_and(|mutex| Arc::strong_count(mutex) == 1) {
+            locks.remove(&self.key);
+        }
+    }
+}
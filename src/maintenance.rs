@@ -0,0 +1,143 @@
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use futures::future::{self, Either, Ready};
+use http::{Request, Response};
+use tonic::body::BoxBody;
+use tonic::Status;
+use tower::{Layer, Service};
+use tracing::{info, warn};
+
+use crate::admin::MaintenanceMode;
+use crate::errors::refused;
+use crate::schedule::now_ms;
+
+// Methods whose names start with one of these only read, so they are served
+// in maintenance mode. Live grading sessions store nothing, and background
+// exports only read.
+const READ_PREFIXES: [&str; 9] = [
+    "Get", "BatchGet", "List", "Search", "Watch", "Export", "GradeSession", "StartExport", "Download",
+];
+
+// The ErrorInfo reason of writes refused in maintenance mode.
+pub(crate) const MAINTENANCE_MODE: &str = "MAINTENANCE_MODE";
+
+// Served whatever the mode: probes, tooling, the way back out of maintenance,
+// and peers following this server's writes, which carry on meanwhile.
+const EXEMPT_SERVICES: [&str; 5] = [
+    "/grpc.health.v1.Health/",
+    "/grpc.reflection.v1.ServerReflection/",
+    "/grpc.reflection.v1alpha.ServerReflection/",
+    "/admin.AdminService/",
+    "/exam_sync.SyncService/",
+];
+
+// Whether the server is in maintenance mode, shared by everything that
+// writes. Toggled through the admin service.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Maintenance {
+    mode: Arc<Mutex<MaintenanceMode>>,
+}
+
+impl Maintenance {
+    pub(crate) fn get(&self) -> MaintenanceMode {
+        self.mode.lock().expect("maintenance mode lock").clone()
+    }
+
+    // Enables or disables maintenance mode for every front end, returning the new mode.
+    pub(crate) fn set(&self, enabled: bool, reason: String, by: &str) -> MaintenanceMode {
+        let mode = match enabled {
+            true => MaintenanceMode {
+                enabled,
+                reason,
+                since_ms: now_ms(),
+                enabled_by: by.to_string(),
+            },
+            false => MaintenanceMode::default(),
+        };
+
+        match enabled {
+            true => warn!(reason = %mode.reason, by, "maintenance mode enabled, rejecting writes"),
+            false => info!(by, "maintenance mode disabled"),
+        }
+        *self.mode.lock().expect("maintenance mode lock") = mode.clone();
+        mode
+    }
+
+    // Fails with FAILED_PRECONDITION while in maintenance mode, for a call that writes.
+    pub(crate) fn check_write(&self) -> Result<(), Status> {
+        let mode = self.mode.lock().expect("maintenance mode lock");
+        if !mode.enabled {
+            return Ok(());
+        }
+
+        let message = match mode.reason.as_str() {
+            "" => "The server is in maintenance mode and only serves reads".to_string(),
+            reason => format!("The server is in maintenance mode and only serves reads: {}", reason),
+        };
+        Err(refused(MAINTENANCE_MODE, message))
+    }
+}
+
+// Whether the gRPC method at `path` only reads, or is exempt from maintenance mode.
+fn is_read(path: &str) -> bool {
+    if EXEMPT_SERVICES.iter().any(|service| path.starts_with(service)) {
+        return true;
+    }
+    let method = path.rsplit('/').next().unwrap_or(path);
+    READ_PREFIXES.iter().any(|prefix| method.starts_with(prefix))
+}
+
+// Tower layer rejecting every gRPC call that writes with FAILED_PRECONDITION
+// and a MAINTENANCE_MODE ErrorInfo while in maintenance mode. Unknown
+// methods count as writes.
+#[derive(Debug, Clone)]
+pub(crate) struct MaintenanceLayer {
+    maintenance: Maintenance,
+}
+
+impl MaintenanceLayer {
+    pub(crate) fn new(maintenance: Maintenance) -> Self {
+        Self { maintenance }
+    }
+}
+
+impl<S> Layer<S> for MaintenanceLayer {
+    type Service = MaintenanceGuard<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MaintenanceGuard {
+            inner,
+            maintenance: self.maintenance.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct MaintenanceGuard<S> {
+    inner: S,
+    maintenance: Maintenance,
+}
+
+impl<S, ReqBody> Service<Request<ReqBody>> for MaintenanceGuard<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<BoxBody>>,
+{
+    type Response = Response<BoxBody>;
+    type Error = S::Error;
+    type Future = Either<S::Future, Ready<Result<Self::Response, Self::Error>>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<ReqBody>) -> Self::Future {
+        if is_read(request.uri().path()) {
+            return Either::Left(self.inner.call(request));
+        }
+
+        match self.maintenance.check_write() {
+            Ok(()) => Either::Left(self.inner.call(request)),
+            Err(status) => Either::Right(future::ok(status.into_http())),
+        }
+    }
+}
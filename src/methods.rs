@@ -0,0 +1,42 @@
+use std::collections::HashSet;
+use std::sync::LazyLock;
+use prost::Message;
+use prost_types::FileDescriptorSet;
+
+use crate::exam_service;
+
+// Stands in for every request path that names no RPC served here.
+pub(crate) const UNKNOWN_METHOD: &str = "unknown";
+
+// The path of every RPC served, e.g. "/exam.v2.ExamService/SubmitExamResult",
+// read from the descriptors also served through reflection.
+static SERVED: LazyLock<HashSet<String>> = LazyLock::new(|| {
+    let sets = [
+        exam_service::FILE_DESCRIPTOR_SET,
+        tonic_health::pb::FILE_DESCRIPTOR_SET,
+        tonic_reflection::pb::v1::FILE_DESCRIPTOR_SET,
+        tonic_reflection::pb::v1alpha::FILE_DESCRIPTOR_SET,
+    ];
+
+    let mut served = HashSet::new();
+    for set in sets {
+        let set = FileDescriptorSet::decode(set).expect("valid descriptor set");
+        for file in set.file {
+            for service in &file.service {
+                for method in &service.method {
+                    served.insert(format!("/{}.{}/{}", file.package(), service.name(), method.name()));
+                }
+            }
+        }
+    }
+    served
+});
+
+// `path` if it names an RPC served here, else `UNKNOWN_METHOD`. Callers can
+// send any path, so whatever is kept per method must be keyed on this.
+pub(crate) fn served_method(path: &str) -> &str {
+    match SERVED.contains(path) {
+        true => path,
+        false => UNKNOWN_METHOD,
+    }
+}
@@ -0,0 +1,165 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+use axum::{extract::State, http::header, response::IntoResponse, routing::get, Router};
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use tonic::Status;
+use tracing::info;
+
+use crate::exam_service::ResultStatus;
+use crate::key::ResultKey;
+use crate::methods::served_method;
+use crate::query::MAX_PAGE_SIZE;
+use crate::store::ExamStore;
+use crate::tenancy::Tenants;
+
+// Prometheus metrics recorded for every RPC and maintenance job run.
+#[derive(Debug, Clone)]
+pub struct Metrics {
+    registry: Registry,
+    requests: IntCounterVec,
+    errors: IntCounterVec,
+    latency: HistogramVec,
+    job_runs: IntCounterVec,
+    job_duration: HistogramVec,
+    stored_results: IntGaugeVec,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let requests = IntCounterVec::new(
+            Opts::new("exam_rpc_requests_total", "Completed RPCs by method"),
+            &["method"],
+        )
+        .expect("valid metric");
+        let errors = IntCounterVec::new(
+            Opts::new("exam_rpc_errors_total", "Failed RPCs by method and gRPC status code"),
+            &["method", "code"],
+        )
+        .expect("valid metric");
+        let latency = HistogramVec::new(
+            HistogramOpts::new("exam_rpc_duration_seconds", "RPC latency by method"),
+            &["method"],
+        )
+        .expect("valid metric");
+        let job_runs = IntCounterVec::new(
+            Opts::new("exam_job_runs_total", "Maintenance job runs by job and outcome"),
+            &["job", "outcome"],
+        )
+        .expect("valid metric");
+        let job_duration = HistogramVec::new(
+            HistogramOpts::new("exam_job_duration_seconds", "Maintenance job run time by job"),
+            &["job"],
+        )
+        .expect("valid metric");
+        let stored_results = IntGaugeVec::new(
+            Opts::new("exam_stored_results", "Stored results by institution and status, as of the last count"),
+            &["institution", "status"],
+        )
+        .expect("valid metric");
+
+        let registry = Registry::new();
+        registry.register(Box::new(requests.clone())).expect("unique metric");
+        registry.register(Box::new(errors.clone())).expect("unique metric");
+        registry.register(Box::new(latency.clone())).expect("unique metric");
+        registry.register(Box::new(job_runs.clone())).expect("unique metric");
+        registry.register(Box::new(job_duration.clone())).expect("unique metric");
+        registry.register(Box::new(stored_results.clone())).expect("unique metric");
+
+        Self {
+            registry,
+            requests,
+            errors,
+            latency,
+            job_runs,
+            job_duration,
+            stored_results,
+        }
+    }
+
+    // Records one finished RPC. `code` is the tonic::Code name, e.g. "Ok" or "NotFound".
+    // Paths naming no RPC served share one `unknown` method label, so callers
+    // cannot add series by making paths up.
+    pub fn record(&self, method: &str, code: &str, latency: Duration) {
+        let method = served_method(method);
+        self.requests.with_label_values(&[method]).inc();
+        self.latency
+            .with_label_values(&[method])
+            .observe(latency.as_secs_f64());
+
+        if code != "Ok" {
+            self.errors.with_label_values(&[method, code]).inc();
+        }
+    }
+
+    // Records one finished run of a maintenance job.
+    pub fn record_job(&self, job: &str, succeeded: bool, duration: Duration) {
+        let outcome = if succeeded { "ok" } else { "failed" };
+        self.job_runs.with_label_values(&[job, outcome]).inc();
+        self.job_duration
+            .with_label_values(&[job])
+            .observe(duration.as_secs_f64());
+    }
+
+    // Recounts the results of every institution by status, a page at a time.
+    pub async fn count_stored_results<S: ExamStore>(&self, tenants: &Tenants<S>) -> Result<(), Status> {
+        for (institution, store) in tenants.iter() {
+            let (mut drafts, mut published) = (0, 0);
+            let mut after = None;
+
+            loop {
+                let page = store.list_page(after.as_ref(), MAX_PAGE_SIZE).await?;
+                for result in &page {
+                    match result.status() {
+                        ResultStatus::Published => published += 1,
+                        _ => drafts += 1,
+                    }
+                }
+
+                match page.last() {
+                    Some(last) if page.len() == MAX_PAGE_SIZE => after = Some(ResultKey::from(last)),
+                    _ => break,
+                }
+            }
+
+            let institution = institution.to_string();
+            self.stored_results.with_label_values(&[&institution, "draft"]).set(drafts);
+            self.stored_results.with_label_values(&[&institution, "published"]).set(published);
+        }
+        Ok(())
+    }
+
+    // Renders every metric in the Prometheus text exposition format.
+    fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("text encoding cannot fail");
+        String::from_utf8(buffer).expect("prometheus output is UTF-8")
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+async fn metrics_handler(State(metrics): State<Metrics>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, TextEncoder::new().format_type().to_string())],
+        metrics.render(),
+    )
+}
+
+// Serves `/metrics` on a secondary HTTP port until the process exits.
+pub async fn serve_metrics(addr: SocketAddr, metrics: Metrics) -> std::io::Result<()> {
+    let app = Router::new()
+        .route("/metrics", get(metrics_handler))
+        .with_state(metrics);
+
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    info!(%addr, "metrics endpoint listening");
+    axum::serve(listener, app).await
+}
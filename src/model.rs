@@ -0,0 +1,76 @@
+// Conversions between the protos' well-known and enum types and the plain
+// values stored and computed on: Unix milliseconds, catalog dates and grade letters.
+
+use crate::exam_service::Grade;
+use crate::google::protobuf::Timestamp;
+
+// Letter grades from lowest to highest, in the order Grade numbers them from 1.
+const LETTERS: [&str; 14] = [
+    "F", "E", "D-", "D", "D+", "C-", "C", "C+", "B-", "B", "B+", "A-", "A", "A+",
+];
+
+const MS_PER_DAY: i64 = 24 * 60 * 60 * 1000;
+
+impl Grade {
+    // The grade written as `letter`, ignoring case and surrounding whitespace,
+    // or Unspecified for anything off the scale.
+    pub fn from_letter(letter: &str) -> Self {
+        let letter = letter.trim();
+        LETTERS
+            .iter()
+            .position(|known| known.eq_ignore_ascii_case(letter))
+            .and_then(|index| Grade::try_from(index as i32 + 1).ok())
+            .unwrap_or(Grade::Unspecified)
+    }
+
+    // The grade as a letter, e.g. "B+"; empty for Unspecified.
+    pub fn letter(self) -> &'static str {
+        match self {
+            Grade::Unspecified => "",
+            grade => LETTERS[grade as usize - 1],
+        }
+    }
+}
+
+// A Unix time in milliseconds as a Timestamp.
+pub fn timestamp_from_ms(ms: i64) -> Timestamp {
+    Timestamp {
+        seconds: ms.div_euclid(1000),
+        nanos: (ms.rem_euclid(1000) * 1_000_000) as i32,
+    }
+}
+
+// A Timestamp as Unix milliseconds, dropping anything finer.
+pub fn ms_from_timestamp(timestamp: &Timestamp) -> i64 {
+    timestamp.seconds * 1000 + i64::from(timestamp.nanos) / 1_000_000
+}
+
+// A catalog date (YYYY-MM-DD) as midnight UTC on that day, or None when it is
+// empty or malformed.
+pub fn timestamp_from_date(date: &str) -> Option<Timestamp> {
+    let mut parts = date.split('-');
+    let (Some(year), Some(month), Some(day), None) = (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return None;
+    };
+
+    let (Ok(year), Ok(month), Ok(day)) = (year.parse::<i64>(), month.parse::<i64>(), day.parse::<i64>()) else {
+        return None;
+    };
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    Some(timestamp_from_ms(days_from_civil(year, month, day) * MS_PER_DAY))
+}
+
+// Days from 1970-01-01 to the given day of the proleptic Gregorian calendar,
+// counting years from March so the leap day falls at the end of one.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use bytes::Bytes;
+use tokio::sync::watch;
+use tonic::Status;
+
+use crate::auth::{Identity, Role};
+use crate::config::OperationsConfig;
+use crate::errors::not_found;
+use crate::exam_service::{Operation, OperationKind, OperationState, RecordError};
+use crate::key::InstitutionId;
+use crate::model::timestamp_from_ms;
+use crate::schedule::now_ms;
+
+#[derive(Debug)]
+struct Tracked {
+    institution: InstitutionId,
+    // The token holder who started it; only they and admins may follow it
+    owner: String,
+    updates: watch::Sender<Operation>,
+    // The file an export produced, once it has succeeded
+    output: Option<Bytes>,
+    finished: Option<Instant>,
+}
+
+// Long-running operations: a Start* call registers one and returns straight
+// away while the work runs in the background. Each operation's state is kept
+// in a watch channel, so WatchOperation streams follow it and a slow watcher
+// only ever skips ahead to the latest state. Finished operations are kept for
+// `retention`, so they can still be watched and exports downloaded.
+#[derive(Debug, Clone)]
+pub(crate) struct Operations {
+    tracked: Arc<Mutex<HashMap<String, Tracked>>>,
+    retention: Duration,
+    max_running: usize,
+}
+
+impl Operations {
+    pub(crate) fn new(config: &OperationsConfig) -> Self {
+        Self {
+            tracked: Arc::new(Mutex::new(HashMap::new())),
+            retention: config.retention(),
+            max_running: config.max_running,
+        }
+    }
+
+    // Registers a running operation started by `owner`, over `total` items
+    // (0 when not known yet). RESOURCE_EXHAUSTED when too many are running.
+    pub(crate) fn start(&self, owner: &Identity, kind: OperationKind, total: usize) -> Result<Progress, Status> {
+        let mut tracked = self.lock();
+        let running = tracked.values().filter(|operation| operation.finished.is_none()).count();
+        if running >= self.max_running {
+            return Err(Status::resource_exhausted(format!(
+                "{} operations are already running; wait for one to finish",
+                running
+            )));
+        }
+
+        let id = format!("{:032x}", rand::random::<u128>());
+        let (updates, _) = watch::channel(Operation {
+            operation_id: id.clone(),
+            kind: kind as i32,
+            state: OperationState::Running as i32,
+            total_count: total as i32,
+            started_at: Some(timestamp_from_ms(now_ms())),
+            ..Default::default()
+        });
+        tracked.insert(
+            id.clone(),
+            Tracked {
+                institution: owner.institution.clone(),
+                owner: owner.name.clone(),
+                updates: updates.clone(),
+                output: None,
+                finished: None,
+            },
+        );
+
+        Ok(Progress {
+            id,
+            updates,
+            operations: self.clone(),
+            finished: false,
+        })
+    }
+
+    // Follows an operation the caller may see: their own, or any in their
+    // institution for admins. Others are NOT_FOUND, as if they did not exist.
+    pub(crate) fn watch(&self, caller: &Identity, id: &str) -> Result<watch::Receiver<Operation>, Status> {
+        let tracked = self.lock();
+        Ok(visible(&tracked, caller, id)?.updates.subscribe())
+    }
+
+    // The file of an export that succeeded.
+    pub(crate) fn output(&self, caller: &Identity, id: &str) -> Result<Bytes, Status> {
+        let tracked = self.lock();
+        visible(&tracked, caller, id)?.output.clone().ok_or_else(|| {
+            Status::failed_precondition(format!("Operation {} has no file: only exports that succeeded do", id))
+        })
+    }
+
+    // Forgets the operations that finished more than `retention` ago.
+    pub(crate) fn evict_expired(&self) {
+        let now = Instant::now();
+        self.lock()
+            .retain(|_, operation| operation.finished.is_none_or(|finished| now - finished < self.retention));
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Tracked>> {
+        self.tracked.lock().unwrap()
+    }
+}
+
+fn visible<'a>(tracked: &'a HashMap<String, Tracked>, caller: &Identity, id: &str) -> Result<&'a Tracked, Status> {
+    tracked
+        .get(id)
+        .filter(|operation| operation.institution == caller.institution)
+        .filter(|operation| caller.role == Role::Admin || operation.owner == caller.name)
+        .ok_or_else(|| not_found("operation", id, format!("No operation found for {}", id)))
+}
+
+// Reports the progress of one running operation. Dropped unfinished, e.g.
+// when its task panics, it fails the operation so watchers are not left waiting.
+#[derive(Debug)]
+pub(crate) struct Progress {
+    id: String,
+    updates: watch::Sender<Operation>,
+    operations: Operations,
+    finished: bool,
+}
+
+impl Progress {
+    // The operation as it stands.
+    pub(crate) fn operation(&self) -> Operation {
+        self.updates.borrow().clone()
+    }
+
+    // Sets how many items there are, once known.
+    pub(crate) fn set_total(&self, total: usize) {
+        self.updates.send_modify(|operation| {
+            operation.total_count = total as i32;
+            operation.percent_done = percent_done(operation);
+        });
+    }
+
+    // Counts one more item handled, with the results it stored, exported or
+    // published, or the error it failed with.
+    pub(crate) fn advance(&self, results: usize, error: Option<RecordError>) {
+        self.updates.send_modify(|operation| {
+            operation.processed_count += 1;
+            operation.result_count += results as i32;
+            operation.errors.extend(error);
+            operation.percent_done = percent_done(operation);
+        });
+    }
+
+    // Ends the operation as SUCCEEDED, keeping an export's file for download.
+    pub(crate) fn succeed(mut self, output: Option<Bytes>) {
+        self.finish(OperationState::Succeeded, None, output);
+    }
+
+    // Ends the operation as FAILED with the status that stopped it.
+    pub(crate) fn fail(mut self, status: &Status) {
+        self.finish(OperationState::Failed, Some(status), None);
+    }
+
+    fn finish(&mut self, state: OperationState, status: Option<&Status>, output: Option<Bytes>) {
+        self.finished = true;
+
+        // Stored before the final state is sent, so whoever sees it can download the file
+        if let Some(tracked) = self.operations.lock().get_mut(&self.id) {
+            tracked.output = output;
+            tracked.finished = Some(Instant::now());
+        }
+
+        self.updates.send_modify(|operation| {
+            operation.state = state as i32;
+            if state == OperationState::Succeeded {
+                operation.percent_done = 100;
+            }
+            if let Some(status) = status {
+                operation.code = status.code() as i32;
+                operation.message = status.message().to_string();
+            }
+            operation.finished_at = Some(timestamp_from_ms(now_ms()));
+        });
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.finish(
+                OperationState::Failed,
+                Some(&Status::aborted("the operation stopped before it finished")),
+                None,
+            );
+        }
+    }
+}
+
+fn percent_done(operation: &Operation) -> i32 {
+    match operation.total_count {
+        0 => 0,
+        total => (i64::from(operation.processed_count) * 100 / i64::from(total)).min(100) as i32,
+    }
+}
@@ -0,0 +1,167 @@
+use tonic::Status;
+
+use crate::errors::invalid_field;
+use crate::exam_service::{ExamResult, Grade, ListExamResultsRequest};
+use crate::key::{ExamId, ResultKey, StudentId};
+use crate::store::{ExamStore, StoreError};
+
+// Page size used when the client does not ask for one.
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+// Upper bound on a single page, regardless of what the client asks for.
+pub const MAX_PAGE_SIZE: usize = 1000;
+// How many records are read from the store per scan while filling a page.
+const SCAN_BATCH: usize = 256;
+
+// Position of a grade on the scale, lowest first, or None for unrecognised grades.
+pub fn grade_rank(grade: &str) -> Option<usize> {
+    match Grade::from_letter(grade) {
+        Grade::Unspecified => None,
+        grade => Some(grade as usize - 1),
+    }
+}
+
+// Filters applied when listing results. `None` matches everything.
+#[derive(Debug, Clone, Default)]
+pub struct ResultFilter {
+    pub student_id: Option<String>,
+    pub exam_id: Option<String>,
+    pub subject: Option<String>,
+    pub min_grade_rank: Option<usize>,
+}
+
+impl ResultFilter {
+    // Builds a filter from a list request, rejecting malformed IDs and unknown minimum grades.
+    pub fn from_request(req: &ListExamResultsRequest) -> Result<Self, Status> {
+        let student_id = non_empty(&req.student_id);
+        if let Some(id) = &student_id {
+            StudentId::parse(id)?;
+        }
+
+        let exam_id = non_empty(&req.exam_id);
+        if let Some(id) = &exam_id {
+            ExamId::parse(id)?;
+        }
+
+        let min_grade_rank = match non_empty(&req.min_grade) {
+            Some(grade) => Some(grade_rank(&grade).ok_or_else(|| {
+                invalid_field("min_grade", format!("{:?} is not a grade", grade))
+            })?),
+            None => None,
+        };
+
+        Ok(Self {
+            student_id,
+            exam_id,
+            subject: non_empty(&req.subject),
+            min_grade_rank,
+        })
+    }
+
+    pub fn matches(&self, result: &ExamResult) -> bool {
+        if self.student_id.as_ref().is_some_and(|id| *id != result.student_id) {
+            return false;
+        }
+
+        if self.exam_id.as_ref().is_some_and(|id| *id != result.exam_id) {
+            return false;
+        }
+
+        if self
+            .subject
+            .as_ref()
+            .is_some_and(|subject| !subject.eq_ignore_ascii_case(&result.subject))
+        {
+            return false;
+        }
+
+        if let Some(min_rank) = self.min_grade_rank {
+            return grade_rank(&result.grade).is_some_and(|rank| rank >= min_rank);
+        }
+
+        true
+    }
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+// One page of filtered results plus the cursor to resume from, if more may follow.
+#[derive(Debug, Default)]
+pub struct Page {
+    pub results: Vec<ExamResult>,
+    pub next_cursor: Option<ResultKey>,
+}
+
+// Reads matching results in key order starting after `cursor`, scanning the store
+// in fixed-size batches so only one batch and one page are held in memory.
+pub async fn scan_page<S: ExamStore + ?Sized>(
+    store: &S,
+    filter: &ResultFilter,
+    cursor: Option<ResultKey>,
+    page_size: usize,
+) -> Result<Page, StoreError> {
+    let mut page = Page::default();
+    let mut cursor = cursor;
+
+    loop {
+        let batch = store.list_page(cursor.as_ref(), SCAN_BATCH).await?;
+        let exhausted = batch.len() < SCAN_BATCH;
+
+        for result in batch {
+            cursor = Some(ResultKey::from(&result));
+
+            if filter.matches(&result) {
+                page.results.push(result);
+
+                if page.results.len() == page_size {
+                    page.next_cursor = cursor;
+                    return Ok(page);
+                }
+            }
+        }
+
+        if exhausted {
+            return Ok(page);
+        }
+    }
+}
+
+// Clamps a requested page size into 1..=MAX_PAGE_SIZE, defaulting when unset.
+pub fn page_size(requested: Option<i32>) -> Result<usize, Status> {
+    match requested {
+        None => Ok(DEFAULT_PAGE_SIZE),
+        Some(n) if n <= 0 => Err(invalid_field("page_size", format!("must be positive when set, got {}", n))),
+        Some(n) => Ok((n as usize).min(MAX_PAGE_SIZE)),
+    }
+}
+
+// Page tokens are the hex-encoded "student_id/exam_id" cursor, so clients treat them
+// as opaque. '/' can never appear in a valid ID, which keeps the split unambiguous.
+pub fn encode_page_token(cursor: &ResultKey) -> String {
+    cursor.to_string().bytes().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn decode_page_token(token: &str) -> Result<Option<ResultKey>, Status> {
+    if token.is_empty() {
+        return Ok(None);
+    }
+
+    let invalid = || invalid_field("page_token", "is not a token returned by this service");
+
+    // Checked byte by byte first: slicing a multibyte character apart would panic
+    if !token.len().is_multiple_of(2) || !token.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+
+    let bytes = (0..token.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&token[i..i + 2], 16).map_err(|_| invalid()))
+        .collect::<Result<Vec<u8>, Status>>()?;
+
+    let cursor = String::from_utf8(bytes).map_err(|_| invalid())?;
+    let (student_id, exam_id) = cursor.split_once('/').ok_or_else(invalid)?;
+
+    ResultKey::parse(student_id, exam_id).map(Some).map_err(|_| invalid())
+}
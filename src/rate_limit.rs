@@ -0,0 +1,194 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use futures::future::{self, Either, Ready};
+use http::{Request, Response};
+use tonic::body::BoxBody;
+use tower::{Layer, Service};
+use tracing::warn;
+
+use crate::config::{MethodLimit, RateLimitConfig};
+use crate::errors::retry_after;
+use crate::methods::served_method;
+use crate::reload::Reloadable;
+use crate::telemetry::remote_addr;
+
+// Health probes must keep working however busy a client is.
+const EXEMPT_PREFIX: &str = "/grpc.health.v1.Health/";
+
+// Most buckets tracked; the longest-tracked is forgotten to make room.
+const MAX_TRACKED_BUCKETS: usize = 10_000;
+
+// Longest wait advertised in RetryInfo, for limits that never refill.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+// Tokens refill continuously at `requests_per_second`, capped at `burst`.
+#[derive(Debug)]
+struct Bucket {
+    limit: MethodLimit,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn full(limit: MethodLimit, now: Instant) -> Self {
+        Self {
+            limit,
+            tokens: limit.burst as f64,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.limit.requests_per_second).min(self.limit.burst as f64);
+        self.updated = now;
+    }
+
+    // Takes a token, or returns how long until one is available.
+    fn try_take(&mut self, now: Instant) -> Result<(), Duration> {
+        self.refill(now);
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return Ok(());
+        }
+
+        let wait = (1.0 - self.tokens) / self.limit.requests_per_second;
+        Err(Duration::try_from_secs_f64(wait).map_or(MAX_RETRY_DELAY, |wait| wait.min(MAX_RETRY_DELAY)))
+    }
+}
+
+// Buckets keyed by (client, method), shared by every connection. Clients
+// are named by headers nobody has checked yet, so there are at most
+// MAX_TRACKED_BUCKETS: a forgotten bucket starts again full.
+#[derive(Debug, Default)]
+struct Buckets {
+    buckets: HashMap<(String, String), Bucket>,
+    // Every key in `buckets`, oldest first
+    order: VecDeque<(String, String)>,
+}
+
+impl Buckets {
+    fn try_take(&mut self, client: String, method: &str, limit: MethodLimit) -> Result<(), Duration> {
+        let now = Instant::now();
+        let key = (client, method.to_string());
+
+        if !self.buckets.contains_key(&key) {
+            while self.buckets.len() >= MAX_TRACKED_BUCKETS {
+                let Some(oldest) = self.order.pop_front() else { break };
+                self.buckets.remove(&oldest);
+            }
+            self.order.push_back(key.clone());
+        }
+
+        let bucket = self.buckets.entry(key).or_insert_with(|| Bucket::full(limit, now));
+        // A reloaded limit applies to existing buckets from their next call
+        bucket.limit = limit;
+        bucket.try_take(now)
+    }
+}
+
+// Tower layer rejecting calls with RESOURCE_EXHAUSTED once a client exceeds
+// its token-bucket limit for a method, with a RetryInfo detail saying when
+// the next token is due. Limits come from `RateLimitConfig`, and can be
+// replaced while serving.
+#[derive(Debug, Clone)]
+pub struct RateLimitLayer {
+    config: Reloadable<RateLimitConfig>,
+    buckets: Arc<Mutex<Buckets>>,
+}
+
+impl RateLimitLayer {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config: Reloadable::new(config),
+            buckets: Arc::new(Mutex::new(Buckets::default())),
+        }
+    }
+
+    // The limits applied by every service of this layer.
+    pub(crate) fn config(&self) -> &Reloadable<RateLimitConfig> {
+        &self.config
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimit<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimit {
+            inner,
+            config: self.config.clone(),
+            buckets: self.buckets.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RateLimit<S> {
+    inner: S,
+    config: Reloadable<RateLimitConfig>,
+    buckets: Arc<Mutex<Buckets>>,
+}
+
+// Identifies the caller by bearer token when present, else by IP address.
+fn client_key<B>(request: &Request<B>) -> String {
+    match request.headers().get("authorization").and_then(|value| value.to_str().ok()) {
+        Some(authorization) => format!("token:{}", authorization),
+        None => match remote_addr(request) {
+            Some(addr) => format!("ip:{}", addr.ip()),
+            None => "unknown".to_string(),
+        },
+    }
+}
+
+impl<S, ReqBody> Service<Request<ReqBody>> for RateLimit<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<BoxBody>>,
+{
+    type Response = Response<BoxBody>;
+    type Error = S::Error;
+    type Future = Either<S::Future, Ready<Result<Self::Response, Self::Error>>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<ReqBody>) -> Self::Future {
+        let path = request.uri().path();
+        let config = self.config.get();
+
+        if !config.enabled || path.starts_with(EXEMPT_PREFIX) {
+            return Either::Left(self.inner.call(request));
+        }
+
+        // Limits are configured by bare method name, e.g. "SubmitExamResult".
+        // Paths naming no RPC served share one method, "unknown"
+        let method = served_method(path);
+        let method = method.rsplit('/').next().unwrap_or(method);
+        let limit = config.limit_for(method);
+        let taken = self
+            .buckets
+            .lock()
+            .unwrap()
+            .try_take(client_key(&request), method, limit);
+
+        let Err(wait) = taken else {
+            return Either::Left(self.inner.call(request));
+        };
+
+        let peer = remote_addr(&request).map(|addr| addr.ip().to_string()).unwrap_or_default();
+        warn!(method, %peer, ?wait, "rate limit exceeded");
+
+        let status = retry_after(
+            format!(
+                "Rate limit of {} requests per second exceeded for {}; retry in {:?}",
+                limit.requests_per_second, method, wait
+            ),
+            wait,
+        );
+        Either::Right(future::ok(status.into_http()))
+    }
+}
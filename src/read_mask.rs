@@ -0,0 +1,82 @@
+use std::collections::HashSet;
+
+use crate::exam_service::GetExamResultResponse;
+use crate::google::protobuf::FieldMask;
+
+// What a read mask may name: the fields of GetExamResultResponse. Masks
+// select whole fields, so `questions` has no subpaths.
+pub(crate) const PATHS: [&str; 14] = [
+    "student_name",
+    "subject",
+    "marks_obtained",
+    "total_marks",
+    "grade",
+    "internal_comment",
+    "student_id",
+    "exam_id",
+    "questions",
+    "version",
+    "status",
+    "grade_level",
+    "exam_date",
+    "graded_at",
+];
+
+// Why `path` cannot be used in a read mask, if it cannot.
+pub(crate) fn path_problem(path: &str) -> Option<String> {
+    match PATHS.contains(&path) {
+        true => None,
+        false => Some(format!("{:?} is not a field of a result; expected one of {}", path, PATHS.join(", "))),
+    }
+}
+
+// The fields of each result a caller asked for, applied to responses after
+// redaction. An unset or empty mask asks for every field.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ReadMask {
+    paths: Option<HashSet<String>>,
+}
+
+impl ReadMask {
+    // From a request's already validated mask.
+    pub(crate) fn new(mask: Option<&FieldMask>) -> Self {
+        let paths = mask
+            .filter(|mask| !mask.paths.is_empty())
+            .map(|mask| mask.paths.iter().cloned().collect());
+        Self { paths }
+    }
+
+    // Resets every field outside the mask to its protobuf default, so it is omitted on the wire.
+    pub(crate) fn apply(&self, mut response: GetExamResultResponse) -> GetExamResultResponse {
+        let Some(paths) = &self.paths else {
+            return response;
+        };
+
+        for path in PATHS {
+            if !paths.contains(path) {
+                clear(path, &mut response);
+            }
+        }
+        response
+    }
+}
+
+fn clear(path: &str, response: &mut GetExamResultResponse) {
+    match path {
+        "student_name" => response.student_name.clear(),
+        "subject" => response.subject.clear(),
+        "marks_obtained" => response.marks_obtained = 0,
+        "total_marks" => response.total_marks = 0,
+        "grade" => response.grade.clear(),
+        "internal_comment" => response.internal_comment.clear(),
+        "student_id" => response.student_id.clear(),
+        "exam_id" => response.exam_id.clear(),
+        "questions" => response.questions.clear(),
+        "version" => response.version = 0,
+        "status" => response.status = 0,
+        "grade_level" => response.grade_level = 0,
+        "exam_date" => response.exam_date = None,
+        "graded_at" => response.graded_at = None,
+        _ => {}
+    }
+}
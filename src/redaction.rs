@@ -0,0 +1,147 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use ring::hmac;
+
+use crate::auth::Role;
+use crate::config::{NameDisclosure, PrivacyConfig};
+use crate::exam_service::GetExamResultResponse;
+
+// Response fields that can be redacted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Field {
+    StudentName,
+    Subject,
+    MarksObtained,
+    TotalMarks,
+    Grade,
+    InternalComment,
+    Questions,
+}
+
+impl Field {
+    pub const ALL: [Field; 7] = [
+        Field::StudentName,
+        Field::Subject,
+        Field::MarksObtained,
+        Field::TotalMarks,
+        Field::Grade,
+        Field::InternalComment,
+        Field::Questions,
+    ];
+
+    // Resets the field to its protobuf default so it is omitted on the wire.
+    fn clear(self, response: &mut GetExamResultResponse) {
+        match self {
+            Field::StudentName => response.student_name.clear(),
+            Field::Subject => response.subject.clear(),
+            Field::MarksObtained => response.marks_obtained = 0,
+            Field::TotalMarks => response.total_marks = 0,
+            Field::Grade => response.grade.clear(),
+            Field::InternalComment => response.internal_comment.clear(),
+            Field::Questions => response.questions.clear(),
+        }
+    }
+}
+
+// Declarative role -> visible-fields map applied to responses before they are sent.
+#[derive(Debug, Clone)]
+pub struct RedactionPolicy {
+    visible: HashMap<Role, HashSet<Field>>,
+    // Applied on top of `visible` for students
+    names: StudentNames,
+}
+
+// How student names are shown to students, from `[privacy]`.
+#[derive(Debug, Clone)]
+enum StudentNames {
+    Shown,
+    Redacted,
+    Pseudonymized(hmac::Key),
+}
+
+impl RedactionPolicy {
+    pub fn new(visible: HashMap<Role, HashSet<Field>>) -> Self {
+        Self {
+            visible,
+            names: StudentNames::Shown,
+        }
+    }
+
+    // Hides student names from everyone but teachers and admins, as the
+    // privacy settings say. Fails if the pseudonym key file cannot be read.
+    pub fn with_privacy(mut self, config: &PrivacyConfig) -> Result<Self, Box<dyn Error>> {
+        self.names = match config.student_names {
+            NameDisclosure::Show => StudentNames::Shown,
+            NameDisclosure::Redact => StudentNames::Redacted,
+            NameDisclosure::Pseudonymize => {
+                let secret = match &config.pseudonym_key_file {
+                    Some(path) => fs::read(path)
+                        .map_err(|e| format!("cannot read pseudonym key file {}: {}", path.display(), e))?,
+                    None => rand::random::<[u8; 32]>().to_vec(),
+                };
+                StudentNames::Pseudonymized(hmac::Key::new(hmac::HMAC_SHA256, &secret))
+            }
+        };
+        Ok(self)
+    }
+
+    // The name of `student_id` as `role` may see it.
+    pub fn student_name(&self, role: Role, student_id: &str, name: String) -> String {
+        if matches!(role, Role::Teacher | Role::Admin) || name.is_empty() {
+            return name;
+        }
+
+        match &self.names {
+            StudentNames::Shown => name,
+            StudentNames::Redacted => String::new(),
+            StudentNames::Pseudonymized(key) => pseudonym(key, student_id),
+        }
+    }
+
+    // Clears every field the role is not allowed to see.
+    // Roles missing from the map see nothing.
+    pub fn redact(&self, role: Role, mut response: GetExamResultResponse) -> GetExamResultResponse {
+        let visible = self.visible.get(&role);
+
+        for field in Field::ALL {
+            if !visible.is_some_and(|fields| fields.contains(&field)) {
+                field.clear(&mut response);
+            }
+        }
+
+        let name = std::mem::take(&mut response.student_name);
+        response.student_name = self.student_name(role, &response.student_id, name);
+        response
+    }
+}
+
+impl Default for RedactionPolicy {
+    // Students and teachers see marks, grade and the per-question breakdown;
+    // only admins see internal comments.
+    fn default() -> Self {
+        let reader: HashSet<Field> = HashSet::from([
+            Field::StudentName,
+            Field::Subject,
+            Field::MarksObtained,
+            Field::TotalMarks,
+            Field::Grade,
+            Field::Questions,
+        ]);
+        let admin = Field::ALL.into_iter().collect();
+
+        Self::new(HashMap::from([
+            (Role::Student, reader.clone()),
+            (Role::Teacher, reader),
+            (Role::Admin, admin),
+        ]))
+    }
+}
+
+// The same student always gets the same pseudonym under one key, so results
+// can still be told apart and grouped without naming anyone.
+fn pseudonym(key: &hmac::Key, student_id: &str) -> String {
+    let tag = hmac::sign(key, student_id.as_bytes());
+    let digits: String = tag.as_ref()[..4].iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("Student {}", digits)
+}
@@ -0,0 +1,92 @@
+use std::error::Error;
+use std::sync::{Arc, RwLock};
+use tracing::{error, info};
+
+use crate::auth::TokenAuth;
+use crate::config::{RateLimitConfig, ServerConfig};
+use crate::grading::GradingScheme;
+
+// A setting the server reads on every use, so it can be replaced while
+// serving. Readers keep the value they got until they are done with it.
+#[derive(Debug)]
+pub(crate) struct Reloadable<T>(Arc<RwLock<Arc<T>>>);
+
+impl<T> Reloadable<T> {
+    pub(crate) fn new(value: T) -> Self {
+        Self(Arc::new(RwLock::new(Arc::new(value))))
+    }
+
+    pub(crate) fn get(&self) -> Arc<T> {
+        self.0.read().expect("reloadable setting lock").clone()
+    }
+
+    pub(crate) fn set(&self, value: T) {
+        *self.0.write().expect("reloadable setting lock") = Arc::new(value);
+    }
+}
+
+impl<T> Clone for Reloadable<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+// Re-reads the config file and applies what can change while serving: grade
+// boundaries, rate limits and the tokens of `api_tokens_file`. Everything
+// else is read once at startup.
+pub(crate) struct Reloader {
+    // The settings in effect, as the admin service reports them
+    pub(crate) config: Reloadable<ServerConfig>,
+    pub(crate) grading: Reloadable<GradingScheme>,
+    pub(crate) rate_limits: Reloadable<RateLimitConfig>,
+    pub(crate) auth: TokenAuth,
+}
+
+impl Reloader {
+    // Loads everything before applying any of it, so a reload that fails
+    // leaves the running settings as they were.
+    pub(crate) fn reload(&self) -> Result<(), Box<dyn Error>> {
+        let config = ServerConfig::load()?;
+        let grading = match &config.grading_config {
+            Some(path) => GradingScheme::load(path)?,
+            None => GradingScheme::default(),
+        };
+        let tokens = config.api_tokens_file.as_deref().map(TokenAuth::read_file).transpose()?;
+
+        let mut in_effect = ServerConfig::clone(&self.config.get());
+        in_effect.grading_config = config.grading_config;
+        in_effect.rate_limit = config.rate_limit.clone();
+        if let Some(tokens) = tokens {
+            self.auth.replace(tokens);
+            in_effect.api_tokens_file = config.api_tokens_file;
+        }
+        self.grading.set(grading);
+        self.rate_limits.set(config.rate_limit);
+        self.config.set(in_effect);
+        info!("reloaded grade boundaries, rate limits and tokens; other settings apply on restart");
+        Ok(())
+    }
+
+    // Reloads on every SIGHUP for as long as the server runs. The handler is
+    // installed before returning, so a SIGHUP no longer terminates the process.
+    #[cfg(unix)]
+    pub(crate) fn spawn_on_hangup(self) -> std::io::Result<()> {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut hangups = signal(SignalKind::hangup())?;
+        tokio::spawn(async move {
+            while hangups.recv().await.is_some() {
+                info!("received SIGHUP, reloading configuration");
+                if let Err(err) = self.reload() {
+                    error!(%err, "reload failed, keeping the current settings");
+                }
+            }
+        });
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    pub(crate) fn spawn_on_hangup(self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
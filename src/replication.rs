@@ -0,0 +1,186 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tonic::service::interceptor::InterceptedService;
+use tonic::transport::{Channel, Endpoint};
+use tonic::{Request, Response, Status};
+use tracing::{info, warn};
+
+use crate::auth::Identity;
+use crate::client::BearerToken;
+use crate::config::ReplicationConfig;
+use crate::exam_sync::sync_service_client::SyncServiceClient;
+use crate::exam_sync::sync_service_server::SyncService;
+use crate::exam_sync::{SubscribeRequest, SyncEvent};
+use crate::key::DEFAULT_INSTITUTION;
+use crate::store::ReplicationLog;
+use crate::streaming::{ResponseStream, Streams};
+use crate::watch::ChangeFeed;
+
+// Implements SyncService: the writes made on this server, streamed to its
+// peers. Like AdminService it spans every institution, so only operators
+// (`*/admin` tokens) may subscribe.
+#[derive(Debug)]
+pub(crate) struct SyncServiceImpl {
+    log: Arc<ReplicationLog>,
+    streams: Streams,
+    // Closed at shutdown, ending every subscription
+    changes: ChangeFeed,
+}
+
+impl SyncServiceImpl {
+    pub(crate) fn new(log: Arc<ReplicationLog>, streams: Streams, changes: ChangeFeed) -> Self {
+        Self { log, streams, changes }
+    }
+}
+
+// Sends `request`'s subscriber everything logged after its position, first
+// catching it up with a snapshot if its position cannot be resumed, until
+// it disconnects or falls further behind than the log buffers.
+async fn follow(log: &ReplicationLog, request: SubscribeRequest, tx: &mpsc::Sender<Result<SyncEvent, Status>>) {
+    let peer = request.instance_id;
+    let mut appended = log.appended();
+    let mut cursor = request.after_sequence;
+
+    if request.log_id != log.log_id() || log.since(cursor).is_none() {
+        let (sequence, snapshots) = match log.snapshot().await {
+            Ok(snapshot) => snapshot,
+            Err(err) => {
+                let _ = tx.send(Err(err.into())).await;
+                return;
+            }
+        };
+        info!(%peer, sequence, "sending snapshot to peer");
+        for snapshot in snapshots {
+            if tx.send(Ok(snapshot)).await.is_err() {
+                return;
+            }
+        }
+        cursor = sequence;
+    }
+
+    loop {
+        let Some(events) = log.since(cursor) else {
+            warn!(%peer, cursor, "peer fell behind the replication buffer");
+            let _ = tx
+                .send(Err(Status::out_of_range(
+                    "Fell further behind than this server buffers; subscribe again to be sent a snapshot",
+                )))
+                .await;
+            return;
+        };
+
+        for event in events {
+            cursor = event.sequence;
+            if tx.send(Ok(event)).await.is_err() {
+                info!(%peer, "peer disconnected");
+                return;
+            }
+        }
+
+        if appended.changed().await.is_err() {
+            return;
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl SyncService for SyncServiceImpl {
+    // Server-Streaming RPC, open until the subscriber disconnects or the server shuts down.
+    type SubscribeStream = ResponseStream<SyncEvent>;
+
+    async fn subscribe(&self, request: Request<SubscribeRequest>) -> Result<Response<Self::SubscribeStream>, Status> {
+        info!(request = ?request.get_ref(), "subscribe");
+
+        let identity = Identity::from_request(&request)?;
+        identity.require_operator()?;
+        let slot = self.streams.reserve(&request, "exam_sync.SyncService/Subscribe")?;
+        let request = request.into_inner();
+        let (log, changes) = (self.log.clone(), self.changes.clone());
+
+        let stream = self.streams.spawn(slot, |tx| async move {
+            tokio::select! {
+                _ = follow(&log, request, &tx) => {}
+                _ = changes.closed() => info!("server shutting down, ending sync stream"),
+            }
+        });
+
+        Ok(Response::new(stream))
+    }
+}
+
+// Follows one peer's SyncService, applying what it sends to the local stores.
+struct Peer {
+    url: String,
+    client: SyncServiceClient<InterceptedService<Channel, BearerToken>>,
+    log: Arc<ReplicationLog>,
+    retry_interval: Duration,
+    // Where to resume: the log and sequence of the last event applied
+    log_id: String,
+    cursor: u64,
+}
+
+impl Peer {
+    // Subscribes again after `retry_interval` whenever the peer cannot be
+    // reached or its stream ends, resuming after the last event applied.
+    async fn follow(mut self) {
+        loop {
+            match self.subscribe().await {
+                Ok(()) => info!(peer = %self.url, "peer ended the sync stream"),
+                Err(status) => warn!(peer = %self.url, %status, "sync with peer failed"),
+            }
+            tokio::time::sleep(self.retry_interval).await;
+        }
+    }
+
+    async fn subscribe(&mut self) -> Result<(), Status> {
+        let request = SubscribeRequest {
+            instance_id: self.log.instance_id().to_string(),
+            log_id: self.log_id.clone(),
+            after_sequence: self.cursor,
+        };
+        let mut events = self.client.subscribe(request).await?.into_inner();
+        info!(peer = %self.url, after = self.cursor, "following peer");
+
+        while let Some(event) = events.message().await? {
+            self.log.apply(&event).await?;
+            self.cursor = event.sequence;
+            self.log_id = event.log_id;
+        }
+        Ok(())
+    }
+}
+
+// Follows every peer in `config` in the background until `changes` closes.
+// Fails if a peer URL or the token is malformed; peers that are down are
+// simply tried again later.
+pub(crate) fn follow_peers(
+    log: &Arc<ReplicationLog>,
+    config: &ReplicationConfig,
+    changes: &ChangeFeed,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Operator tokens must name an institution; any will do, since the
+    // stream carries every institution's writes
+    let token = BearerToken::new(&config.token)?.with_institution(DEFAULT_INSTITUTION)?;
+
+    for url in &config.peers {
+        let channel = Endpoint::from_shared(url.clone())?.connect_lazy();
+        let peer = Peer {
+            url: url.clone(),
+            client: SyncServiceClient::with_interceptor(channel, token.clone()),
+            log: log.clone(),
+            retry_interval: config.retry_interval(),
+            log_id: String::new(),
+            cursor: 0,
+        };
+        let changes = changes.clone();
+
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = peer.follow() => {}
+                _ = changes.closed() => {}
+            }
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,162 @@
+use std::time::Duration;
+use tonic::{Request, Response, Status};
+use tracing::info;
+
+use crate::auth::{Identity, Role};
+use crate::deadline::{Deadline, DEFAULT_MAX_PROCESSING};
+use crate::errors::{invalid_field, missing_dependency, not_found};
+use crate::exam_service::ExamResult;
+use crate::key::StudentId;
+use crate::store::ExamStore;
+use crate::tenancy::Tenants;
+use crate::student::student_service_server::StudentService;
+use crate::student::{
+    GetStudentRequest, ListStudentsRequest, ListStudentsResponse, RegisterStudentRequest, Student,
+};
+
+// Implements StudentService on top of the same stores as ExamService,
+// so results are always checked against the current roster.
+#[derive(Debug)]
+pub struct StudentServiceImpl<S> {
+    tenants: Tenants<S>,
+    max_processing: Duration,
+}
+
+impl<S: ExamStore> StudentServiceImpl<S> {
+    pub fn new(tenants: Tenants<S>) -> Self {
+        Self {
+            tenants,
+            max_processing: DEFAULT_MAX_PROCESSING,
+        }
+    }
+
+    // Replaces the server-side processing limit.
+    pub fn with_max_processing_time(mut self, limit: Duration) -> Self {
+        self.max_processing = limit;
+        self
+    }
+
+    // The client's deadline for `request`, capped by the processing limit.
+    fn deadline<T>(&self, request: &Request<T>) -> Result<Deadline, Status> {
+        Deadline::from_request(request, Some(self.max_processing))
+    }
+}
+
+// Rejects registrations without a usable ID or name.
+fn validate_student(student: &Student) -> Result<StudentId, Status> {
+    let id = StudentId::parse(&student.student_id)?;
+
+    if student.name.trim().is_empty() {
+        return Err(invalid_field("name", "is required"));
+    }
+
+    if !student.email.is_empty() && !student.email.contains('@') {
+        return Err(invalid_field(
+            "email",
+            format!("{:?} is not a valid address", student.email),
+        ));
+    }
+
+    Ok(id)
+}
+
+// Error for results referring to a student that is not on the roster, kept
+// distinct from NOT_FOUND so callers can tell a bad ID from a missing result.
+pub fn unregistered_student(id: &StudentId) -> Status {
+    missing_dependency(
+        "NOT_REGISTERED",
+        format!("student/{}", id),
+        format!("Student {} is not registered", id),
+    )
+}
+
+// Checks a submitted result against the registered student. The name is
+// filled in from the roster when left empty, and rejected when it disagrees.
+pub fn conform_to_student(result: &mut ExamResult, student: &Student) -> Result<(), Status> {
+    if result.student_name.is_empty() {
+        result.student_name = student.name.clone();
+    } else if result.student_name != student.name {
+        return Err(invalid_field(
+            "student_name",
+            format!(
+                "{:?} does not match student {} ({:?})",
+                result.student_name, student.student_id, student.name
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+#[tonic::async_trait]
+impl<S: ExamStore> StudentService for StudentServiceImpl<S> {
+    // Adds a student to the roster; IDs cannot be registered twice.
+    async fn register_student(&self, request: Request<RegisterStudentRequest>) -> Result<Response<Student>, Status> {
+        info!(request = ?request.get_ref(), "register student");
+
+        let deadline = self.deadline(&request)?;
+        deadline
+            .run(async move {
+                let identity = Identity::from_request(&request)?;
+                identity.require_write()?;
+
+                let student = request
+                    .into_inner()
+                    .student
+                    .ok_or_else(|| invalid_field("student", "is required"))?;
+                let id = validate_student(&student)?;
+
+                if !self.tenants.for_caller(&identity)?.create_student(id.clone(), student.clone()).await? {
+                    return Err(Status::already_exists(format!("Student {} is already registered", id)));
+                }
+
+                info!(student_id = %id, "student registered");
+                Ok(Response::new(student))
+            })
+            .await
+    }
+
+    // Looks up one student. Students may only look up themselves.
+    async fn get_student(&self, request: Request<GetStudentRequest>) -> Result<Response<Student>, Status> {
+        info!(request = ?request.get_ref(), "get student");
+
+        let deadline = self.deadline(&request)?;
+        deadline
+            .run(async move {
+                let identity = Identity::from_request(&request)?;
+                let req = request.into_inner();
+                identity.require_read(&req.student_id)?;
+
+                let id = StudentId::parse(&req.student_id)?;
+
+                match self.tenants.for_caller(&identity)?.get_student(&id).await? {
+                    Some(student) => Ok(Response::new(student)),
+                    None => Err(not_found("student", &id, format!("No student found for {}", id))),
+                }
+            })
+            .await
+    }
+
+    // Lists the roster ordered by ID. Students only see their own entry.
+    async fn list_students(&self, request: Request<ListStudentsRequest>) -> Result<Response<ListStudentsResponse>, Status> {
+        info!(request = ?request.get_ref(), "list students");
+
+        let deadline = self.deadline(&request)?;
+        deadline
+            .run(async move {
+                let identity = Identity::from_request(&request)?;
+
+                let students = self
+                    .tenants
+                    .for_caller(&identity)?
+                    .list_students()
+                    .await?
+                    .into_iter()
+                    .filter(|student| identity.role != Role::Student || identity.student_id.as_deref() == Some(&student.student_id))
+                    .collect();
+
+                Ok(Response::new(ListStudentsResponse { students }))
+            })
+            .await
+    }
+}
@@ -0,0 +1,128 @@
+use std::time::Duration;
+use tonic::{Request, Response, Status};
+use tracing::info;
+
+use crate::admin::admin_service_server::AdminService;
+use crate::admin::{
+    GetConfigRequest, GetConfigResponse, InstitutionSnapshot, ListStreamsRequest, ListStreamsResponse,
+    MaintenanceMode, SetMaintenanceModeRequest, TriggerSnapshotRequest, TriggerSnapshotResponse,
+};
+use crate::auth::Identity;
+use crate::config::ServerConfig;
+use crate::deadline::{Deadline, DEFAULT_MAX_PROCESSING};
+use crate::maintenance::Maintenance;
+use crate::reload::Reloadable;
+use crate::snapshots::Snapshots;
+use crate::store::ExamStore;
+use crate::streaming::Streams;
+use crate::tenancy::Tenants;
+
+// Implements AdminService: what the running server is doing, and switches
+// that act on all of it. Unlike the other services these are not scoped to
+// one institution, so only operators, whose admin tokens are valid in every
+// institution, may call them.
+#[derive(Debug)]
+pub(crate) struct AdminServiceImpl<S> {
+    // The settings in effect, kept current by reloads
+    config: Reloadable<ServerConfig>,
+    streams: Streams,
+    maintenance: Maintenance,
+    tenants: Tenants<S>,
+    snapshots: Snapshots,
+    max_processing: Duration,
+}
+
+impl<S: ExamStore> AdminServiceImpl<S> {
+    pub(crate) fn new(
+        config: Reloadable<ServerConfig>,
+        streams: Streams,
+        maintenance: Maintenance,
+        tenants: Tenants<S>,
+        snapshots: Snapshots,
+    ) -> Self {
+        Self {
+            config,
+            streams,
+            maintenance,
+            tenants,
+            snapshots,
+            max_processing: DEFAULT_MAX_PROCESSING,
+        }
+    }
+
+    // Replaces the server-side processing limit.
+    pub(crate) fn with_max_processing_time(mut self, limit: Duration) -> Self {
+        self.max_processing = limit;
+        self
+    }
+
+    // The client's deadline for `request`, capped by the processing limit.
+    fn deadline<T>(&self, request: &Request<T>) -> Result<Deadline, Status> {
+        Deadline::from_request(request, Some(self.max_processing))
+    }
+}
+
+#[tonic::async_trait]
+impl<S: ExamStore> AdminService for AdminServiceImpl<S> {
+    async fn get_config(&self, request: Request<GetConfigRequest>) -> Result<Response<GetConfigResponse>, Status> {
+        info!("get config");
+        Identity::from_request(&request)?.require_operator()?;
+
+        let config_json = serde_json::to_string_pretty(&self.config.get().redacted())
+            .map_err(|err| Status::internal(format!("could not encode config: {}", err)))?;
+        Ok(Response::new(GetConfigResponse {
+            config_json,
+            maintenance: Some(self.maintenance.get()),
+        }))
+    }
+
+    async fn list_streams(
+        &self,
+        request: Request<ListStreamsRequest>,
+    ) -> Result<Response<ListStreamsResponse>, Status> {
+        info!("list streams");
+        Identity::from_request(&request)?.require_operator()?;
+
+        Ok(Response::new(self.streams.list()))
+    }
+
+    async fn set_maintenance_mode(
+        &self,
+        request: Request<SetMaintenanceModeRequest>,
+    ) -> Result<Response<MaintenanceMode>, Status> {
+        info!(req = ?request.get_ref(), "set maintenance mode");
+        let identity = Identity::from_request(&request)?;
+        identity.require_operator()?;
+        let req = request.into_inner();
+
+        Ok(Response::new(self.maintenance.set(req.enabled, req.reason, &identity.name)))
+    }
+
+    async fn trigger_snapshot(
+        &self,
+        request: Request<TriggerSnapshotRequest>,
+    ) -> Result<Response<TriggerSnapshotResponse>, Status> {
+        info!("trigger snapshot");
+
+        let deadline = self.deadline(&request)?;
+        deadline
+            .run(async move {
+                let identity = Identity::from_request(&request)?;
+                identity.require_operator()?;
+
+                let snapshots = self
+                    .snapshots
+                    .take_automatic(&self.tenants)
+                    .await?
+                    .into_iter()
+                    .map(|(institution, snapshot)| InstitutionSnapshot {
+                        institution_id: institution.to_string(),
+                        snapshot: Some(snapshot),
+                    })
+                    .collect();
+                info!(actor = %identity.name, "snapshots triggered");
+                Ok(Response::new(TriggerSnapshotResponse { snapshots }))
+            })
+            .await
+    }
+}
@@ -0,0 +1,75 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tonic::Status;
+
+use crate::errors::{invalid_field, missing_dependency};
+use crate::exam_admin::Exam;
+
+// Milliseconds since the Unix epoch, the unit of every exam timestamp.
+pub fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+// An exam timestamp as an HTTP date, e.g. "Tue, 03 Jun 2025 09:00:00 GMT".
+pub fn format_ms(ms: i64) -> String {
+    httpdate::fmt_http_date(UNIX_EPOCH + Duration::from_millis(ms.max(0) as u64))
+}
+
+// Checks the window timestamps of an exam definition; 0 leaves one unset.
+pub fn validate_schedule(exam: &Exam) -> Result<(), Status> {
+    for (field, value) in [
+        ("opens_at_ms", exam.opens_at_ms),
+        ("closes_at_ms", exam.closes_at_ms),
+        ("grades_released_at_ms", exam.grades_released_at_ms),
+    ] {
+        if value < 0 {
+            return Err(invalid_field(field, format!("must not be negative, got {}", value)));
+        }
+    }
+
+    if exam.opens_at_ms > 0 && exam.closes_at_ms > 0 && exam.closes_at_ms < exam.opens_at_ms {
+        return Err(invalid_field(
+            "closes_at_ms",
+            format!("must not be before opens_at_ms, got {}", exam.closes_at_ms),
+        ));
+    }
+
+    Ok(())
+}
+
+// Results may be submitted once the exam has opened: while it runs, for
+// live grading, and afterwards for marking.
+pub fn check_open(exam: &Exam, now_ms: i64) -> Result<(), Status> {
+    if now_ms >= exam.opens_at_ms {
+        return Ok(());
+    }
+
+    Err(missing_dependency(
+        "NOT_OPEN",
+        format!("exam/{}", exam.exam_id),
+        format!(
+            "Exam {} opens at {}; results cannot be submitted before then",
+            exam.exam_id,
+            format_ms(exam.opens_at_ms)
+        ),
+    ))
+}
+
+pub fn is_released(exam: &Exam, now_ms: i64) -> bool {
+    now_ms >= exam.grades_released_at_ms
+}
+
+// Error for a student reading a result whose grades are not out yet.
+pub fn not_released(exam: &Exam) -> Status {
+    missing_dependency(
+        "NOT_RELEASED",
+        format!("exam/{}", exam.exam_id),
+        format!(
+            "Grades for exam {} are released at {}",
+            exam.exam_id,
+            format_ms(exam.grades_released_at_ms)
+        ),
+    )
+}
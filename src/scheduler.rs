@@ -0,0 +1,109 @@
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tokio::task::JoinSet;
+use tokio::time::MissedTickBehavior;
+use tonic::Status;
+use tracing::{debug, error, info, warn};
+
+use crate::metrics::Metrics;
+
+// Runs maintenance jobs in the background, each every `interval`, until
+// `shutdown`. A job never overlaps itself: a run that overruns its interval
+// delays the next one. A failed run is logged and the job is scheduled again.
+pub struct Scheduler {
+    jobs: JoinSet<()>,
+    names: Vec<&'static str>,
+    stopped: watch::Sender<bool>,
+    metrics: Option<Metrics>,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        let (stopped, _) = watch::channel(false);
+        Self {
+            jobs: JoinSet::new(),
+            names: Vec::new(),
+            stopped,
+            metrics: None,
+        }
+    }
+
+    // Records the outcome and duration of every run on `/metrics`.
+    pub(crate) fn with_metrics(mut self, metrics: Metrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    // Registers `job` to run every `interval`, the first run one interval from now.
+    pub fn every<F, Fut>(&mut self, name: &'static str, interval: Duration, mut job: F)
+    where
+        F: FnMut() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(), Status>> + Send + 'static,
+    {
+        let mut stopped = self.stopped.subscribe();
+        let metrics = self.metrics.clone();
+        self.names.push(name);
+
+        self.jobs.spawn(async move {
+            let mut ticks = tokio::time::interval(interval);
+            ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            // The first tick is immediate
+            ticks.tick().await;
+
+            loop {
+                tokio::select! {
+                    _ = ticks.tick() => {}
+                    _ = stopped.wait_for(|stopped| *stopped) => return,
+                }
+
+                // A run is never interrupted; shutdown waits for it instead
+                let started = Instant::now();
+                let outcome = job().await;
+                let elapsed = started.elapsed();
+
+                if let Some(metrics) = &metrics {
+                    metrics.record_job(name, outcome.is_ok(), elapsed);
+                }
+                match outcome {
+                    Ok(()) => debug!(job = name, ?elapsed, "maintenance job finished"),
+                    Err(status) => error!(job = name, error = status.message(), "maintenance job failed"),
+                }
+            }
+        });
+    }
+
+    // The names of the registered jobs, in registration order.
+    pub fn jobs(&self) -> &[&'static str] {
+        &self.names
+    }
+
+    // Stops scheduling new runs and waits up to `timeout` for runs in progress
+    // to finish. Runs still going after that are cancelled.
+    pub async fn shutdown(mut self, timeout: Duration) {
+        self.stopped.send_replace(true);
+
+        let finished = tokio::time::timeout(timeout, async {
+            while let Some(joined) = self.jobs.join_next().await {
+                if let Err(err) = joined
+                    && err.is_panic()
+                {
+                    error!("maintenance job panicked");
+                }
+            }
+        })
+        .await;
+
+        match finished {
+            Ok(()) => info!("maintenance jobs stopped"),
+            // Dropping the set aborts the stragglers
+            Err(_) => warn!(?timeout, "maintenance jobs still running at shutdown were cancelled"),
+        }
+    }
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
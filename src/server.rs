@@ -1,136 +1,2411 @@
-use std::collections::HashMap;
+use std::future::Future;
+use std::mem;
 use std::sync::Arc;
-use tonic::{transport::Server, Request, Response, Status};
-use tokio::sync::{mpsc, RwLock};
-use tokio::time::{sleep, Duration};
-use tokio_stream::wrappers::ReceiverStream;
+use bytes::Bytes;
+use tonic::body::BoxBody;
+use tonic::codec::CompressionEncoding;
+use tonic::service::interceptor::InterceptedService;
+use tonic::transport::server::Router;
+use tonic::{transport::Server, Code, Request, Response, Status, Streaming};
+use tonic_health::ServingStatus;
+use tonic_web::GrpcWebLayer;
+use tokio::sync::{broadcast, oneshot};
+use tokio::time::Duration;
+use tokio::net::TcpListener;
+use tokio_stream::{Stream, StreamExt};
+use tower::util::MapResponseLayer;
+use tracing::{info, warn};
 
-pub mod exam_service {
-    tonic::include_proto!("exam");
-}
+use crate::exam_service::exam_service_server::{ExamService, ExamServiceServer as ExamServer};
+use crate::exam_service::{
+    AnswerSubmission, AuditTrail, BatchGetExamResultsRequest, BatchGetExamResultsResponse, BatchGetResult, CorrectExamResultRequest, CorrectExamResultResponse,
+    DeleteExamResultRequest, DeleteExamResultResponse, ExamResult, ExportChunk, Grade, ExportResultsRequest, ExamStatistics, GetAuditTrailRequest, GetExamStatisticsRequest,
+    GetTranscriptRequest, Transcript, ChangeKind, ResultChange, WatchExamResultsRequest, GetExamResultRequest, GetExamResultResponse, GradeUpdate,
+    ListExamResultsPageRequest, ListExamResultsPageResponse, ListExamResultsRequest, RecordError, SubmitExamResultRequest, SubmitExamResultResponse,
+    SubmitExamResultsResponse, SubmitQuestionScoresRequest, PublishExamResultsRequest, PublishExamResultsResponse, ResultStatus,
+    SearchExamResultsRequest, SearchMatch, ListDeletedResultsRequest, ListDeletedResultsResponse, UndeleteExamResultRequest,
+    UndeleteExamResultResponse, Operation, OperationKind, OperationState, StartImportResultsRequest, StartPublishResultsRequest,
+    WatchOperationRequest, DownloadExportRequest,
+};
+
+use crate::admin::admin_service_server::AdminServiceServer;
+use crate::appeal::appeal_service_server::AppealServiceServer;
+use crate::appeals::AppealServiceImpl;
+use crate::audit::{audit_record, WriteContext};
+use crate::auth::{Identity, JwtAuth, Role, TokenAuth};
+use crate::breakdown::apply_breakdown;
+use crate::catalog::{conform_to_exam, exam_not_found, ExamAdminServiceImpl};
+use crate::compat::{ExamServiceV1, LegacyPathLayer};
+use crate::exam_admin::exam_admin_service_server::ExamAdminServiceServer;
+use crate::exam_session::session_service_server::SessionServiceServer;
+use crate::exam_sync::sync_service_server::SyncServiceServer;
+use crate::grader::grader_service_server::GraderServiceServer;
+use crate::graders::GraderServiceImpl;
+use crate::config::{IdempotencyConfig, OperationsConfig, PrivacyConfig, ServerConfig, StreamConfig};
+use crate::deadline::{self, Deadline, DEFAULT_MAX_PROCESSING};
+use crate::errors::{invalid_field, missing_dependency, not_found};
+use crate::exam_service;
+use crate::exam_service::v1::exam_service_server::ExamServiceServer as ExamServerV1;
+use crate::export::{ExportEncoder, CHUNK_SIZE};
+use crate::gateway::serve_gateway;
+use crate::grading::GradingScheme;
+use crate::idempotency::IdempotencyCache;
+use crate::key::{ExamId, InstitutionId, ResultKey, StudentId};
+use crate::locks::{ResultLock, ResultLocks};
+use crate::maintenance::{Maintenance, MaintenanceLayer};
+use crate::model::timestamp_from_ms;
+use crate::metrics::{serve_metrics, Metrics};
+use crate::ops::{Operations, Progress};
+use crate::rate_limit::RateLimitLayer;
+use crate::scheduler::Scheduler;
+use crate::query::{
+    decode_page_token, encode_page_token, page_size, scan_page, ResultFilter, DEFAULT_PAGE_SIZE,
+    MAX_PAGE_SIZE,
+};
+use crate::read_mask::ReadMask;
+use crate::redaction::RedactionPolicy;
+use crate::reload::{Reloadable, Reloader};
+use crate::replication::{follow_peers, SyncServiceImpl};
+use crate::roster::{conform_to_student, unregistered_student, StudentServiceImpl};
+use crate::runtime::AdminServiceImpl;
+use crate::schedule::{check_open, is_released, not_released, now_ms};
+use crate::session::{run_grading_session, AnswerKey};
+use crate::sessions::SessionServiceImpl;
+use crate::shutdown::shutdown_signal;
+use crate::snapshot::snapshot_service_server::SnapshotServiceServer;
+use crate::snapshots::{SnapshotServiceImpl, Snapshots};
+use crate::statistics::{exam_statistics, withhold_small_cohort};
+use crate::streaming::{ResponseStream, Streams};
+use crate::store::{next_version, ExamStore, InMemoryExamStore, ReplicationLog, Undelete, VersionedPut};
+use crate::student::student_service_server::StudentServiceServer;
+use crate::telemetry::{RpcTraceLayer, TracedBody};
+use crate::transport::Listeners;
+#[cfg(unix)]
+use crate::transport::{bind_unix, remove_unix};
+use crate::tenancy::Tenants;
+use crate::validation::{validate, DEFAULT_SEARCH_LIMIT, MAX_SEARCH_LIMIT};
+use crate::visibility::Visibility;
+use crate::tls::server_tls;
+use crate::transcript::transcript;
+use crate::web::cors_layer;
+use crate::watch::ChangeFeed;
+use crate::webhook::webhook_service_server::WebhookServiceServer;
+use crate::webhooks::{HttpSender, WebhookDispatcher, WebhookServiceImpl};
 
-use exam_service::exam_service_server::{ExamService, ExamServiceServer as ExamServer};
-use exam_service::{GetExamResultRequest, GetExamResultResponse};
+// The audit log actor of writes made by maintenance jobs rather than a caller.
+const SCHEDULER_ACTOR: &str = "scheduler";
+
+// Health checks written before the packages were versioned ask about this name.
+const LEGACY_SERVICE_NAME: &str = "exam.ExamService";
 
 // The core server struct implementing the ExamService gRPC interface.
-// Holds a map of exam data keyed by a composite "student_id_exam_id" string.
-#[derive(Debug, Clone)]
-pub struct ExamServiceImpl {
-    // Exam results wrapped in Arc<RwLock<>> for thread-safe access
-    exam_data: Arc<RwLock<HashMap<String, GetExamResultResponse>>>,
-}
-
-impl ExamServiceImpl {
-    // Constructs a new instance of the service with pre-populated exam data.
-    pub fn new() -> Self {
-        let mut data = HashMap::new();
-
-        data.insert(
-            "123_math101".to_string(),
-            GetExamResultResponse {
-                student_name: "John Doe".to_string(),
-                subject: "Math 101".to_string(),
-                marks_obtained: 95,
-                total_marks: 100,
-                grade: "A+".to_string(),
-            },
-        );
+// Generic over the storage backend so the gRPC layer is independent of persistence.
+#[derive(Debug)]
+pub struct ExamServiceImpl<S = InMemoryExamStore> {
+    // One store per institution served, shared across all requests
+    tenants: Tenants<S>,
+    // Controls which response fields each caller role may see
+    redaction: RedactionPolicy,
+    // Expected answers used by live grading sessions
+    answer_key: Arc<AnswerKey>,
+    // Grade boundaries used to derive grades from marks
+    grading: Reloadable<GradingScheme>,
+    // Broadcasts result changes to WatchExamResults subscribers
+    changes: ChangeFeed,
+    // Safety net capping how long unary RPCs and finite streams may run
+    max_processing: Duration,
+    // Spawns server streams, within buffer sizes and per-connection limits
+    streams: Streams,
+    // Responses of recent writes by request_id, so retries are not applied twice
+    requests: IdempotencyCache,
+    // Background imports, exports and publishes, followed through WatchOperation
+    operations: Operations,
+    // Serializes writes to the same result, so racing ones cannot interleave
+    locks: ResultLocks,
+    // Fewest results GetExamStatistics describes beyond their count
+    min_cohort_size: u32,
+    // Writes made here, followed by peers; None unless the stores are replicated
+    replication: Option<Arc<ReplicationLog>>,
+}
+
+// Clones share every store, cache and feed, so a background operation can
+// write through its own clone. Stores themselves need not be Clone.
+impl<S> Clone for ExamServiceImpl<S> {
+    fn clone(&self) -> Self {
+        Self {
+            tenants: self.tenants.clone(),
+            redaction: self.redaction.clone(),
+            answer_key: self.answer_key.clone(),
+            grading: self.grading.clone(),
+            changes: self.changes.clone(),
+            max_processing: self.max_processing,
+            streams: self.streams.clone(),
+            requests: self.requests.clone(),
+            operations: self.operations.clone(),
+            locks: self.locks.clone(),
+            min_cohort_size: self.min_cohort_size,
+            replication: self.replication.clone(),
+        }
+    }
+}
+
+impl<S: ExamStore> ExamServiceImpl<S> {
+    // Constructs a new instance of the service on top of the given store,
+    // serving the default institution only.
+    pub fn new(store: S) -> Self {
+        Self::for_institutions([(InstitutionId::default(), store)])
+    }
+
+    // Constructs a service serving each institution from its own store.
+    // Callers only reach the store of the institution their token is scoped to.
+    pub fn for_institutions(stores: impl IntoIterator<Item = (InstitutionId, S)>) -> Self {
+        Self {
+            tenants: Tenants::new(stores),
+            redaction: RedactionPolicy::default(),
+            answer_key: Arc::new(AnswerKey::with_sample_data()),
+            grading: Reloadable::new(GradingScheme::default()),
+            changes: ChangeFeed::new(StreamConfig::default().watch_buffer),
+            max_processing: DEFAULT_MAX_PROCESSING,
+            streams: Streams::new(StreamConfig::default()),
+            requests: IdempotencyCache::new(&IdempotencyConfig::default()),
+            operations: Operations::new(&OperationsConfig::default()),
+            locks: ResultLocks::default(),
+            min_cohort_size: 0,
+            replication: None,
+        }
+    }
+
+    // Replaces the default grade boundaries.
+    pub fn with_grading(mut self, grading: GradingScheme) -> Self {
+        self.grading = Reloadable::new(grading);
+        self
+    }
+
+    // Replaces the server-side processing limit.
+    pub fn with_max_processing_time(mut self, limit: Duration) -> Self {
+        self.max_processing = limit;
+        self
+    }
+
+    // Replaces the stream buffer sizes and limits. Call before serving, since
+    // the change feed is recreated with the new watcher buffer.
+    pub fn with_streams(mut self, streams: StreamConfig) -> Self {
+        self.changes = ChangeFeed::new(streams.watch_buffer);
+        self.streams = Streams::new(streams);
+        self
+    }
+
+    // Replaces how long and how many write responses are kept for retries.
+    pub fn with_idempotency(mut self, config: &IdempotencyConfig) -> Self {
+        self.requests = IdempotencyCache::new(config);
+        self
+    }
+
+    // Replaces how long finished operations are kept and how many may run at once.
+    pub fn with_operations(mut self, config: &OperationsConfig) -> Self {
+        self.operations = Operations::new(config);
+        self
+    }
+
+    // Applies privacy mode: student names hidden from students, and statistics
+    // withheld for small exams. Fails if the pseudonym key file cannot be read.
+    pub fn with_privacy(mut self, config: &PrivacyConfig) -> Result<Self, Box<dyn std::error::Error>> {
+        self.redaction = self.redaction.with_privacy(config)?;
+        self.min_cohort_size = config.min_cohort_size;
+        Ok(self)
+    }
+
+    // Serves SyncService from `log`, and follows the configured peers, once
+    // serving. The stores must be `ReplicatedExamStore`s logging to it.
+    pub fn with_replication(mut self, log: Arc<ReplicationLog>) -> Self {
+        self.replication = Some(log);
+        self
+    }
+
+    // SubmitExamResults, over the records of any version's stream.
+    pub(crate) async fn submit_results<In>(
+        &self,
+        request: Request<In>,
+    ) -> Result<Response<SubmitExamResultsResponse>, Status>
+    where
+        In: Stream<Item = Result<ExamResult, Status>> + Unpin,
+    {
+        info!("bulk submit started");
+
+        // Uploads may legitimately be long, so only the client's own deadline applies
+        let deadline = Deadline::from_request(&request, None)?;
+        let identity = Identity::from_request(&request)?;
+        identity.require_write()?;
+
+        let mut stream = request.into_inner();
+        let mut summary = SubmitExamResultsResponse::default();
+        let mut index = 0;
+
+        while let Some(result) = deadline.run(async { stream.next().await.transpose() }).await? {
+            let (student_id, exam_id) = (result.student_id.clone(), result.exam_id.clone());
+
+            let stored = match validate(&result) {
+                Ok(()) => self.store_result(&identity, WriteContext::default(), result).await,
+                Err(status) => Err(status),
+            };
+
+            match stored {
+                Ok(_) => summary.accepted_count += 1,
+                Err(status) => {
+                    summary.rejected_count += 1;
+                    summary.errors.push(RecordError {
+                        index,
+                        student_id,
+                        exam_id,
+                        message: status.message().to_string(),
+                    });
+                }
+            }
 
-        data.insert(
-            "456_phy101".to_string(),
-            GetExamResultResponse {
-                student_name: "Jane Smith".to_string(),
-                subject: "Physics 101".to_string(),
-                marks_obtained: 88,
-                total_marks: 100,
-                grade: "A".to_string(),
-            },
+            index += 1;
+        }
+
+        info!(
+            accepted = summary.accepted_count,
+            rejected = summary.rejected_count,
+            "bulk submit finished"
         );
 
+        Ok(Response::new(summary))
+    }
+
+
+    // GradeSession, over the answers of any version's stream.
+    pub(crate) async fn grade_answers<In>(&self, request: Request<In>) -> Result<Response<ResponseStream<GradeUpdate>>, Status>
+    where
+        In: Stream<Item = Result<AnswerSubmission, Status>> + Unpin + Send + 'static,
+    {
+        info!("grading session started");
+
+        // Sessions are interactive, so only the client's own deadline applies
+        let deadline = Deadline::from_request(&request, None)?;
+        let slot = self.streams.reserve(&request, "exam.v2.ExamService/GradeSession")?;
+        let inbound = request.into_inner();
+        let answer_key = self.answer_key.clone();
+        let grading = self.grading.get();
+
+        let stream = self.streams.spawn(slot, |tx| async move {
+            let expired = tx.clone();
+
+            tokio::select! {
+                _ = run_grading_session(&answer_key, &grading, inbound, tx) => {}
+                _ = deadline.expired() => {
+                    info!("deadline passed, ending grading session");
+                    let _ = expired.send(Err(deadline::exceeded())).await;
+                }
+            }
+        });
+
+        Ok(Response::new(stream))
+    }
+
+    // The stores of every institution, shared with the handlers.
+    pub(crate) fn tenants(&self) -> &Tenants<S> {
+        &self.tenants
+    }
+
+    // Spawns and tracks the server streams of every handler.
+    pub(crate) fn streams(&self) -> &Streams {
+        &self.streams
+    }
+
+    // The expected answers of the exams that can be graded live or sat online.
+    pub(crate) fn answer_key(&self) -> &AnswerKey {
+        &self.answer_key
+    }
+
+    // Which response fields each role sees, and how student names are shown.
+    pub(crate) fn redaction(&self) -> &RedactionPolicy {
+        &self.redaction
+    }
+
+    // The grade boundaries in use, replaced when the configuration is reloaded.
+    pub(crate) fn grading(&self) -> &Reloadable<GradingScheme> {
+        &self.grading
+    }
+
+    // The log replicated stores record their writes in, if they are replicated.
+    pub(crate) fn replication(&self) -> Option<&Arc<ReplicationLog>> {
+        self.replication.as_ref()
+    }
+
+    // The feed the write handlers publish result changes to.
+    pub fn changes(&self) -> &ChangeFeed {
+        &self.changes
+    }
+
+    // The client's deadline for `request`, capped by the processing limit.
+    fn deadline<T>(&self, request: &Request<T>) -> Result<Deadline, Status> {
+        Deadline::from_request(request, Some(self.max_processing))
+    }
+
+    // One result as `identity` may see it, redacted, or why it cannot be read.
+    async fn read_result(&self, identity: &Identity, store: &S, key: &ResultKey) -> Result<GetExamResultResponse, Status> {
+        identity.require_read(key.student_id.as_str())?;
+        self.require_released(identity, &key.exam_id).await?;
+
+        // Students' drafts are reported as missing until published
+        let found = match store.get(key).await? {
+            Some(result) if Visibility::new(store, identity.role).shows(&result).await? => Some(result),
+            _ => None,
+        };
+
+        if let Some(result) = found {
+            // Redact after lookup so the stored record stays complete
+            return Ok(self.redaction.redact(identity.role, result.into()));
+        }
+
+        if store.get_student(&key.student_id).await?.is_none() {
+            return Err(unregistered_student(&key.student_id));
+        }
+
+        Err(result_not_found(key))
+    }
+
+    // Fails for students while the grades of `exam_id` are unreleased.
+    async fn require_released(&self, identity: &Identity, exam_id: &ExamId) -> Result<(), Status> {
+        if identity.role != Role::Student {
+            return Ok(());
+        }
+
+        match self.tenants.for_caller(identity)?.get_exam(exam_id).await? {
+            Some(exam) if !is_released(&exam, now_ms()) => Err(not_released(&exam)),
+            _ => Ok(()),
+        }
+    }
+
+    // Validates a result against the student roster and exam catalog, derives its marks
+    // from the per-question breakdown if any, computes its grade, and stores it as a
+    // draft. Returns the stored result and whether it was newly created.
+    pub(crate) async fn store_result(
+        &self,
+        actor: &Identity,
+        context: WriteContext<'_>,
+        result: ExamResult,
+    ) -> Result<(ExamResult, bool), Status> {
+        let key = ResultKey::parse(&result.student_id, &result.exam_id)?;
+        let lock = self.lock_result(actor, &key).await;
+        self.store_locked_result(actor, context, result, &lock).await
+    }
+
+    // Holds `key` in the caller's institution until the lock is dropped. Every
+    // write to a result goes through it, so a write never sees another half done.
+    async fn lock_result(&self, actor: &Identity, key: &ResultKey) -> ResultLock {
+        self.locks.lock((actor.institution.clone(), key.clone())).await
+    }
+
+    // store_result for a caller already holding the result's lock.
+    async fn store_locked_result(
+        &self,
+        actor: &Identity,
+        context: WriteContext<'_>,
+        mut result: ExamResult,
+        _lock: &ResultLock,
+    ) -> Result<(ExamResult, bool), Status> {
+        let key = ResultKey::parse(&result.student_id, &result.exam_id)?;
+        let store = self.tenants.for_caller(actor)?;
+
+        let student = store
+            .get_student(&key.student_id)
+            .await?
+            .ok_or_else(|| unregistered_student(&key.student_id))?;
+        conform_to_student(&mut result, &student)?;
+        apply_breakdown(&mut result)?;
+
+        let exam = store.get_exam(&key.exam_id).await?.ok_or_else(|| {
+            missing_dependency(
+                "NOT_IN_CATALOG",
+                format!("exam/{}", key.exam_id),
+                format!("Exam {} is not in the catalog", key.exam_id),
+            )
+        })?;
+        check_open(&exam, now_ms())?;
+        conform_to_exam(&mut result, &exam)?;
+        validate_exam_result(&result)?;
+
+        result.grade = self
+            .grading
+            .get()
+            .grade(&result.subject, result.marks_obtained, result.total_marks);
+        result.graded_at = Some(timestamp_from_ms(now_ms()));
+        // Resubmitting a published result withdraws it until it is published again
+        result.set_status(ResultStatus::Draft);
+
+        deadline::writing();
+        let previous = store.put(key.clone(), result.clone()).await?;
+        result.version = next_version(previous.as_ref());
+        let created = previous.is_none();
+
+        let kind = if created { ChangeKind::Created } else { ChangeKind::Updated };
+        self.record_change(actor, &key, kind, previous, Some(result.clone()), context)
+            .await?;
+
+        Ok((result, created))
+    }
+
+    // Sets the marks of the result at `key`, last read at `expected_version`, and
+    // regrades it. Returns the result before and after the correction.
+    pub(crate) async fn correct_marks(
+        &self,
+        actor: &Identity,
+        key: &ResultKey,
+        expected_version: i64,
+        marks_obtained: i32,
+        context: WriteContext<'_>,
+    ) -> Result<(ExamResult, ExamResult), Status> {
+        let store = self.tenants.for_caller(actor)?;
+        let _lock = self.lock_result(actor, key).await;
+        let previous = store.get(key).await?.ok_or_else(|| result_not_found(key))?;
+
+        if previous.version != expected_version {
+            return Err(version_conflict(key, expected_version, previous.version));
+        }
+
+        // The marks of a broken-down result are the sum of its questions
+        if !previous.questions.is_empty() {
+            return Err(Status::failed_precondition(format!(
+                "Result {} has a per-question breakdown; correct it with SubmitQuestionScores",
+                key
+            )));
+        }
+
+        let mut corrected = previous.clone();
+        corrected.marks_obtained = marks_obtained;
+
+        validate_exam_result(&corrected)?;
+        corrected.grade = self
+            .grading
+            .get()
+            .grade(&corrected.subject, corrected.marks_obtained, corrected.total_marks);
+        corrected.graded_at = Some(timestamp_from_ms(now_ms()));
+
+        // Another write may have landed since the read; the store checks again atomically
+        deadline::writing();
+        let previous = match store
+            .put_if_version(key.clone(), expected_version, corrected.clone())
+            .await?
+        {
+            VersionedPut::Written(previous) => previous,
+            VersionedPut::Conflict(Some(current)) => {
+                return Err(version_conflict(key, expected_version, current));
+            }
+            VersionedPut::Conflict(None) => {
+                return Err(result_not_found(key));
+            }
+        };
+        corrected.version = next_version(Some(&previous));
+
+        info!(%key, reason = %context.reason, "exam result corrected");
+        self.record_change(
+            actor,
+            key,
+            ChangeKind::Corrected,
+            Some(previous.clone()),
+            Some(corrected.clone()),
+            context,
+        )
+        .await?;
+
+        Ok((previous, corrected))
+    }
+
+    // Forgets expired idempotency responses, finished operations and cached results. Run
+    // periodically by the scheduler, so memory is freed even while nothing is written.
+    pub(crate) async fn evict_expired(&self) -> Result<(), Status> {
+        self.requests.evict_expired();
+        self.operations.evict_expired();
+        for (_, store) in self.tenants.iter() {
+            store.evict_expired().await?;
+        }
+        Ok(())
+    }
+
+    // Deletes the drafts of every exam that closed more than `age` ago: they
+    // were never published and, by then, never will be. Each deletion is
+    // audited and announced like an admin's, with the scheduler as the actor.
+    pub(crate) async fn delete_stale_drafts(&self, age: Duration) -> Result<(), Status> {
+        let cutoff = now_ms() - age.as_millis() as i64;
+        let reason = format!("stale draft: the exam closed more than {} days ago", age.as_secs() / 86_400);
+        let context = WriteContext {
+            reason: &reason,
+            ..Default::default()
+        };
+
+        for (institution, store) in self.tenants.iter() {
+            let scheduler = Identity {
+                role: Role::Admin,
+                student_id: None,
+                name: SCHEDULER_ACTOR.to_string(),
+                institution: institution.clone(),
+                every_institution: false,
+            };
+            let closed = store
+                .list_exams()
+                .await?
+                .into_iter()
+                .filter(|exam| exam.closes_at_ms > 0 && exam.closes_at_ms < cutoff);
+
+            for exam in closed {
+                let filter = ResultFilter {
+                    exam_id: Some(exam.exam_id.clone()),
+                    ..Default::default()
+                };
+                let mut drafts = Vec::new();
+                let mut cursor = None;
+
+                loop {
+                    let page = scan_page(store.as_ref(), &filter, cursor, MAX_PAGE_SIZE).await?;
+                    drafts.extend(page.results.into_iter().filter(|result| result.status() == ResultStatus::Draft));
+
+                    match page.next_cursor {
+                        Some(next) => cursor = Some(next),
+                        None => break,
+                    }
+                }
+
+                for draft in drafts {
+                    let key = ResultKey::from(&draft);
+                    let _lock = self.lock_result(&scheduler, &key).await;
+                    // A draft published or resubmitted since the scan is left for the next run
+                    match store.get(&key).await? {
+                        Some(current) if current.version == draft.version => {}
+                        _ => continue,
+                    }
+                    let Some(previous) = store.delete(&key, now_ms()).await? else {
+                        continue;
+                    };
+
+                    info!(%institution, %key, "stale draft deleted");
+                    self.record_change(&scheduler, &key, ChangeKind::Deleted, Some(previous), None, context)
+                        .await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Removes for good the results deleted more than `retention` ago. Their
+    // deletion is already audited, so purging them is not.
+    pub(crate) async fn purge_deleted(&self, retention: Duration) -> Result<(), Status> {
+        let cutoff = now_ms() - retention.as_millis() as i64;
+        for (institution, store) in self.tenants.iter() {
+            let purged = store.purge_deleted(cutoff).await?;
+            if !purged.is_empty() {
+                info!(%institution, count = purged.len(), "deleted results purged");
+            }
+        }
+        Ok(())
+    }
+
+    // Publishes every draft result of an exam, returning how many were published.
+    async fn publish_drafts(
+        &self,
+        identity: &Identity,
+        exam_id: &ExamId,
+        context: WriteContext<'_>,
+    ) -> Result<i32, Status> {
+        let store = self.tenants.for_caller(identity)?;
+        if store.get_exam(exam_id).await?.is_none() {
+            return Err(exam_not_found(exam_id));
+        }
+
+        let filter = ResultFilter {
+            exam_id: Some(exam_id.to_string()),
+            ..Default::default()
+        };
+        let mut drafts = Vec::new();
+        let mut cursor = None;
+
+        loop {
+            let page = scan_page(store.as_ref(), &filter, cursor, MAX_PAGE_SIZE).await?;
+            drafts.extend(page.results.into_iter().filter(|result| result.status() == ResultStatus::Draft));
+
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+
+        let mut published_count = 0;
+
+        for draft in drafts {
+            let key = ResultKey::from(&draft);
+            let _lock = self.lock_result(identity, &key).await;
+            let mut published = draft.clone();
+            published.set_status(ResultStatus::Published);
+
+            // A result resubmitted since the scan is a new draft; it waits for the next publish
+            deadline::writing();
+            let previous = match store
+                .put_if_version(key.clone(), draft.version, published.clone())
+                .await?
+            {
+                VersionedPut::Written(previous) => previous,
+                VersionedPut::Conflict(_) => continue,
+            };
+            published.version = next_version(Some(&previous));
+
+            self.record_change(
+                identity,
+                &key,
+                ChangeKind::Released,
+                Some(previous),
+                Some(published),
+                context,
+            )
+            .await?;
+            published_count += 1;
+        }
+
+        info!(%exam_id, published_count, "exam results published");
+        Ok(published_count)
+    }
+
+    // Stores each result of a background import as SubmitExamResults would,
+    // recording the ones rejected on the operation.
+    async fn import_results(&self, identity: &Identity, request_id: &str, results: Vec<ExamResult>, progress: &Progress) {
+        let context = WriteContext {
+            request_id,
+            ..Default::default()
+        };
+
+        for (index, result) in results.into_iter().enumerate() {
+            let (student_id, exam_id) = (result.student_id.clone(), result.exam_id.clone());
+
+            let stored = match validate(&result) {
+                Ok(()) => self.store_result(identity, context, result).await,
+                Err(status) => Err(status),
+            };
+
+            match stored {
+                Ok(_) => progress.advance(1, None),
+                Err(status) => progress.advance(
+                    0,
+                    Some(RecordError {
+                        index: index as i32,
+                        student_id,
+                        exam_id,
+                        message: status.message().to_string(),
+                    }),
+                ),
+            }
+        }
+    }
+
+    // Encodes the results a background export may see into one file. They are
+    // collected first so the operation knows its total before encoding starts.
+    async fn export_file(
+        &self,
+        identity: &Identity,
+        filter: ResultFilter,
+        mut encoder: ExportEncoder,
+        progress: &Progress,
+    ) -> Result<Bytes, Status> {
+        let store = self.tenants.for_caller(identity)?;
+        let mut visibility = Visibility::new(store.as_ref(), identity.role);
+        let mut visible = Vec::new();
+        let mut cursor = None;
+
+        loop {
+            let page = scan_page(store.as_ref(), &filter, cursor, MAX_PAGE_SIZE).await?;
+            for result in page.results {
+                if visibility.shows(&result).await? {
+                    visible.push(result);
+                }
+            }
+
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+        progress.set_total(visible.len());
+
+        let mut file = Vec::new();
+        for result in visible {
+            if let Some(chunk) = encoder.push(&self.redaction.redact(identity.role, result.into()))? {
+                file.extend(chunk.data);
+            }
+            progress.advance(1, None);
+        }
+        if let Some(chunk) = encoder.finish() {
+            file.extend(chunk.data);
+        }
+
+        Ok(Bytes::from(file))
+    }
+
+    // Publishes the drafts of each exam in turn for a background publish; an
+    // exam that fails is recorded on the operation and the rest still go ahead.
+    async fn publish_exams(&self, identity: &Identity, request_id: &str, exam_ids: Vec<ExamId>, progress: &Progress) {
+        let context = WriteContext {
+            request_id,
+            ..Default::default()
+        };
+
+        for (index, exam_id) in exam_ids.into_iter().enumerate() {
+            match self.publish_drafts(identity, &exam_id, context).await {
+                Ok(published_count) => progress.advance(published_count as usize, None),
+                Err(status) => progress.advance(
+                    0,
+                    Some(RecordError {
+                        index: index as i32,
+                        exam_id: exam_id.to_string(),
+                        message: status.message().to_string(),
+                        ..Default::default()
+                    }),
+                ),
+            }
+        }
+    }
+
+    // Appends a write to the audit log and notifies watchers of it. The write has
+    // already been applied, so a failure here is reported but not rolled back.
+    async fn record_change(
+        &self,
+        actor: &Identity,
+        key: &ResultKey,
+        kind: ChangeKind,
+        before: Option<ExamResult>,
+        after: Option<ExamResult>,
+        context: WriteContext<'_>,
+    ) -> Result<(), Status> {
+        // Watchers get the result after the change, or the removed result for deletes
+        let changed = after.clone().or_else(|| before.clone());
+
+        self.tenants
+            .for_caller(actor)?
+            .append_audit(audit_record(actor, key, kind, before, after, context))
+            .await?;
+
+        if let Some(changed) = changed {
+            self.changes.publish(actor.institution.clone(), kind, changed);
+        }
+
+        Ok(())
+    }
+}
+
+impl From<ExamResult> for GetExamResultResponse {
+    fn from(result: ExamResult) -> Self {
         Self {
-            exam_data: Arc::new(RwLock::new(data)),
+            student_name: result.student_name,
+            subject: result.subject,
+            marks_obtained: result.marks_obtained,
+            total_marks: result.total_marks,
+            grade_level: Grade::from_letter(&result.grade) as i32,
+            grade: result.grade,
+            internal_comment: result.internal_comment,
+            student_id: result.student_id,
+            exam_id: result.exam_id,
+            questions: result.questions,
+            version: result.version,
+            status: result.status,
+            exam_date: result.exam_date,
+            graded_at: result.graded_at,
+        }
+    }
+}
+
+// Rejects submissions that would store an inconsistent result.
+// IDs are validated separately when the result's key is parsed.
+fn validate_exam_result(result: &ExamResult) -> Result<(), Status> {
+    if result.total_marks <= 0 {
+        return Err(invalid_field(
+            "total_marks",
+            format!("must be positive, got {}", result.total_marks),
+        ));
+    }
+
+    if result.marks_obtained < 0 || result.marks_obtained > result.total_marks {
+        return Err(invalid_field(
+            "marks_obtained",
+            format!(
+                "must be between 0 and total_marks ({}), got {}",
+                result.total_marks, result.marks_obtained
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+// Whether a BatchGetExamResults key failing with `code` is reported for that
+// key alone: the caller's own errors are, the server's fail the whole call.
+fn is_key_error(code: Code) -> bool {
+    matches!(code, Code::NotFound | Code::PermissionDenied | Code::FailedPrecondition)
+}
+
+// NOT_FOUND for a result key with nothing stored under it.
+pub(crate) fn result_not_found(key: &ResultKey) -> Status {
+    not_found("exam_result", key, format!("No result found for {}", key))
+}
+
+fn not_deleted(key: &ResultKey) -> Status {
+    not_found("deleted_exam_result", key, format!("No deleted result found for {}", key))
+}
+
+// Returned when undeleting would overwrite a result submitted since the deletion.
+fn undelete_conflict(key: &ResultKey) -> Status {
+    Status::already_exists(format!(
+        "A result for {} was stored since it was deleted; delete that one first",
+        key
+    ))
+}
+
+// Returned when a conditional write was based on a stale read.
+fn version_conflict(key: &ResultKey, expected: i64, current: i64) -> Status {
+    Status::aborted(format!(
+        "Result {} is at version {}, not {}; re-read it and retry",
+        key, current, expected
+    ))
+}
+
+// Students may only list their own results, so their listings are pinned to their ID.
+fn scope_filter(identity: &Identity, filter: &mut ResultFilter) -> Result<(), Status> {
+    if identity.role != Role::Student {
+        return Ok(());
+    }
+
+    match &filter.student_id {
+        Some(student_id) => identity.require_read(student_id),
+        None => {
+            filter.student_id = identity.student_id.clone();
+            Ok(())
         }
     }
 }
 
 #[tonic::async_trait]
-impl ExamService for ExamServiceImpl {
+impl<S: ExamStore> ExamService for ExamServiceImpl<S> {
     // Handles a unary request to get exam result by student_id and exam_id.
     async fn get_exam_result(
         &self,
         request: Request<GetExamResultRequest>,
     ) -> Result<Response<GetExamResultResponse>, Status> {
-        println!("Got a Unary Request: {:?}", request);
+        info!(request = ?request.get_ref(), "get exam result");
+        validate(request.get_ref())?;
 
-        let req = request.into_inner();
-        let key = format!("{}_{}", req.student_id, req.exam_id);
+        let deadline = self.deadline(&request)?;
+        deadline
+            .run(async move {
+                let identity = Identity::from_request(&request)?;
+                let store = self.tenants.for_caller(&identity)?;
+                let req = request.into_inner();
 
-        let data = self.exam_data.read().await;
+                let key = ResultKey::parse(&req.student_id, &req.exam_id)?;
+                let result = self.read_result(&identity, store, &key).await?;
+                Ok(Response::new(ReadMask::new(req.read_mask.as_ref()).apply(result)))
+            })
+            .await
+    }
 
-        if let Some(result) = data.get(&key) {
-            return Ok(Response::new(result.clone()));
-        }
+    // Handles a unary request for several results at once. Each key gets its
+    // result or the error GetExamResult would return for it, so one missing
+    // result does not fail the rest; errors of the server itself still fail the call.
+    async fn batch_get_exam_results(
+        &self,
+        request: Request<BatchGetExamResultsRequest>,
+    ) -> Result<Response<BatchGetExamResultsResponse>, Status> {
+        info!(keys = request.get_ref().keys.len(), "batch get exam results");
+        validate(request.get_ref())?;
 
-        Err(Status::not_found(format!("No result found for key: {}", key)))
+        let deadline = self.deadline(&request)?;
+        deadline
+            .run(async move {
+                let identity = Identity::from_request(&request)?;
+                let store = self.tenants.for_caller(&identity)?;
+                let req = request.into_inner();
+                let mask = ReadMask::new(req.read_mask.as_ref());
+
+                let mut results = Vec::with_capacity(req.keys.len());
+                for requested in req.keys {
+                    let key = ResultKey::parse(&requested.student_id, &requested.exam_id)?;
+                    let outcome = match self.read_result(&identity, store, &key).await {
+                        Ok(result) => BatchGetResult {
+                            key: Some(requested),
+                            result: Some(mask.apply(result)),
+                            ..Default::default()
+                        },
+                        Err(status) if is_key_error(status.code()) => BatchGetResult {
+                            key: Some(requested),
+                            result: None,
+                            code: status.code() as i32,
+                            message: status.message().to_string(),
+                        },
+                        Err(status) => return Err(status),
+                    };
+                    results.push(outcome);
+                }
+
+                Ok(Response::new(BatchGetExamResultsResponse { results }))
+            })
+            .await
     }
 
     // Server-Streaming RPC
-    type GetExamResultStreamStream = ReceiverStream<Result<GetExamResultResponse, Status>>;
+    type GetExamResultStreamStream = ResponseStream<GetExamResultResponse>;
 
     async fn get_exam_result_stream(
         &self,
         request: Request<GetExamResultRequest>,
     ) -> Result<Response<Self::GetExamResultStreamStream>, Status> {
-        println!("Got a Streaming Request: {:?}", request);
+        info!(request = ?request.get_ref(), "get exam result stream");
+        validate(request.get_ref())?;
 
+        let deadline = self.deadline(&request)?;
+        let identity = Identity::from_request(&request)?;
+        let store = self.tenants.for_caller(&identity)?;
+        let slot = self.streams.reserve(&request, "exam.v2.ExamService/GetExamResultStream")?;
         let req = request.into_inner();
-        let key = format!("{}_{}", req.student_id, req.exam_id);
+        identity.require_read(&req.student_id)?;
+
+        let role = identity.role;
+        let student_id = StudentId::parse(&req.student_id)?;
+        let exam_id = match req.exam_id.as_str() {
+            "" => None,
+            exam_id => Some(ExamId::parse(exam_id)?),
+        };
+
+        let results = deadline
+            .run(async {
+                if store.get_student(&student_id).await?.is_none() {
+                    return Err(unregistered_student(&student_id));
+                }
+                if let Some(exam_id) = &exam_id {
+                    self.require_released(&identity, exam_id).await?;
+                }
+
+                // An exam ID narrows the history to that exam's result. Students
+                // get their published results of exams whose grades are out
+                let mut visibility = Visibility::new(store.as_ref(), role);
+                let mut results = Vec::new();
+                for result in store.list_for_student(&student_id).await? {
+                    if (req.exam_id.is_empty() || result.exam_id == req.exam_id)
+                        && visibility.shows(&result).await?
+                    {
+                        results.push(result);
+                    }
+                }
+                Ok(results)
+            })
+            .await?;
+
+        if results.is_empty() && !req.exam_id.is_empty() {
+            let key = ResultKey::parse(&req.student_id, &req.exam_id)?;
+            return Err(result_not_found(&key));
+        }
 
-        let (tx, rx) = mpsc::channel(4);
+        let redaction = self.redaction.clone();
+        let mask = ReadMask::new(req.read_mask.as_ref());
+
+        // Streams the student's stored results in exam order, as fast as the client reads them
+        let stream = self.streams.spawn(slot, |tx| async move {
+            for result in results {
+                let response = mask.apply(redaction.redact(role, result.into()));
+
+                let sent = tokio::select! {
+                    sent = tx.send(Ok(response)) => sent.is_ok(),
+                    _ = deadline.expired() => {
+                        info!("deadline passed, ending stream");
+                        let _ = tx.send(Err(deadline::exceeded())).await;
+                        return;
+                    }
+                };
+
+                if !sent {
+                    info!("client disconnected before stream finished");
+                    return;
+                }
+            }
+        });
+
+        Ok(Response::new(stream))
+    }
+
+    // Handles a unary request to insert or update an exam result.
+    async fn submit_exam_result(
+        &self,
+        request: Request<SubmitExamResultRequest>,
+    ) -> Result<Response<SubmitExamResultResponse>, Status> {
+        info!(request = ?request.get_ref(), "submit exam result");
+        validate(request.get_ref())?;
+
+        let deadline = self.deadline(&request)?;
+        let service = self.clone();
+        deadline
+            .run_write(async move {
+                let identity = Identity::from_request(&request)?;
+                identity.require_write()?;
+
+                let mut req = request.into_inner();
+                let request_id = mem::take(&mut req.request_id);
+
+                service.requests
+                    .run("SubmitExamResult", &identity, &request_id, &req, async {
+                        let result = req
+                            .result
+                            .clone()
+                            .ok_or_else(|| invalid_field("result", "is required"))?;
+
+                        let context = WriteContext {
+                            request_id: &request_id,
+                            ..Default::default()
+                        };
+                        let (result, created) = service.store_result(&identity, context, result).await?;
+
+                        Ok(SubmitExamResultResponse {
+                            result: Some(service.redaction.redact(identity.role, result.into())),
+                            created,
+                        })
+                    })
+                    .await
+                    .map(Response::new)
+            })
+            .await
+    }
+
+    // Handles a request to record the per-question breakdown of a result. The result is
+    // created if missing, and its marks, total and grade are recomputed from the questions.
+    async fn submit_question_scores(
+        &self,
+        request: Request<SubmitQuestionScoresRequest>,
+    ) -> Result<Response<SubmitExamResultResponse>, Status> {
+        info!(request = ?request.get_ref(), "submit question scores");
+        validate(request.get_ref())?;
+
+        let deadline = self.deadline(&request)?;
+        let service = self.clone();
+        deadline
+            .run_write(async move {
+                let identity = Identity::from_request(&request)?;
+                let store = service.tenants.for_caller(&identity)?;
+                identity.require_write()?;
+
+                let mut req = request.into_inner();
+                let request_id = mem::take(&mut req.request_id);
+                let key = ResultKey::parse(&req.student_id, &req.exam_id)?;
+
+                if req.questions.is_empty() {
+                    return Err(invalid_field("questions", "is required"));
+                }
+
+                service.requests
+                    .run("SubmitQuestionScores", &identity, &request_id, &req, async {
+                        // Held from the read, so a write meanwhile is not overwritten with stale fields
+                        let lock = service.lock_result(&identity, &key).await;
+                        // Keep the name, subject and comment of an existing result
+                        let mut result = store.get(&key).await?.unwrap_or_else(|| ExamResult {
+                            student_id: req.student_id.clone(),
+                            exam_id: req.exam_id.clone(),
+                            ..Default::default()
+                        });
+                        result.questions = req.questions.clone();
+
+                        let context = WriteContext {
+                            request_id: &request_id,
+                            ..Default::default()
+                        };
+                        let (result, created) = service.store_locked_result(&identity, context, result, &lock).await?;
+
+                        Ok(SubmitExamResultResponse {
+                            result: Some(service.redaction.redact(identity.role, result.into())),
+                            created,
+                        })
+                    })
+                    .await
+                    .map(Response::new)
+            })
+            .await
+    }
+
+    // Client-Streaming RPC: stores every valid record and reports the rejected ones.
+    async fn submit_exam_results(
+        &self,
+        request: Request<Streaming<ExamResult>>,
+    ) -> Result<Response<SubmitExamResultsResponse>, Status> {
+        self.submit_results(request).await
+    }
 
+    // Bidirectional-Streaming RPC
+    type GradeSessionStream = ResponseStream<GradeUpdate>;
+
+    async fn grade_session(
+        &self,
+        request: Request<Streaming<AnswerSubmission>>,
+    ) -> Result<Response<Self::GradeSessionStream>, Status> {
+        self.grade_answers(request).await
+    }
+
+    // Server-Streaming RPC: streams every stored result matching the request filters.
+    type ListExamResultsStream = ResponseStream<GetExamResultResponse>;
+
+    async fn list_exam_results(
+        &self,
+        request: Request<ListExamResultsRequest>,
+    ) -> Result<Response<Self::ListExamResultsStream>, Status> {
+        info!(request = ?request.get_ref(), "list exam results");
+        validate(request.get_ref())?;
+
+        let deadline = self.deadline(&request)?;
+        let identity = Identity::from_request(&request)?;
+        let slot = self.streams.reserve(&request, "exam.v2.ExamService/ListExamResults")?;
+        let mut filter = ResultFilter::from_request(request.get_ref())?;
+        scope_filter(&identity, &mut filter)?;
+
+        let role = identity.role;
+        let store = self.tenants.for_caller(&identity)?.clone();
+        let redaction = self.redaction.clone();
+        let mask = ReadMask::new(request.get_ref().read_mask.as_ref());
+
+        // Walk the store page by page so large result sets are never held in memory at once
+        let stream = self.streams.spawn(slot, |tx| async move {
+            let mut cursor = None;
+            let mut visibility = Visibility::new(store.as_ref(), role);
+
+            loop {
+                let scan = scan_page(store.as_ref(), &filter, cursor, DEFAULT_PAGE_SIZE);
+                let page = match deadline.run(async { Ok(scan.await?) }).await {
+                    Ok(page) => page,
+                    Err(status) => {
+                        let _ = tx.send(Err(status)).await;
+                        return;
+                    }
+                };
+
+                for result in page.results {
+                    match visibility.shows(&result).await {
+                        Ok(true) => {}
+                        Ok(false) => continue,
+                        Err(err) => {
+                            let _ = tx.send(Err(err.into())).await;
+                            return;
+                        }
+                    }
+
+                    let response = mask.apply(redaction.redact(role, result.into()));
+
+                    let sent = tokio::select! {
+                        sent = tx.send(Ok(response)) => sent.is_ok(),
+                        _ = deadline.expired() => {
+                            info!("deadline passed, ending listing");
+                            let _ = tx.send(Err(deadline::exceeded())).await;
+                            return;
+                        }
+                    };
+
+                    if !sent {
+                        info!("client disconnected before listing finished");
+                        return;
+                    }
+                }
+
+                match page.next_cursor {
+                    Some(next) => cursor = Some(next),
+                    None => return,
+                }
+            }
+        });
+
+        Ok(Response::new(stream))
+    }
+
+    // Handles a unary request for one page of filtered results.
+    async fn list_exam_results_page(
+        &self,
+        request: Request<ListExamResultsPageRequest>,
+    ) -> Result<Response<ListExamResultsPageResponse>, Status> {
+        info!(request = ?request.get_ref(), "list exam results page");
+        validate(request.get_ref())?;
+
+        let deadline = self.deadline(&request)?;
+        deadline
+            .run(async move {
+                let identity = Identity::from_request(&request)?;
+                let store = self.tenants.for_caller(&identity)?;
+                let req = request.into_inner();
+                let list = req.filter.unwrap_or_default();
+                let mut filter = ResultFilter::from_request(&list)?;
+                scope_filter(&identity, &mut filter)?;
+                let mask = ReadMask::new(list.read_mask.as_ref());
+
+                let page_size = page_size(req.page_size)?;
+                let cursor = decode_page_token(&req.page_token)?;
+
+                let page = scan_page(store.as_ref(), &filter, cursor, page_size).await?;
+
+                // Pages may come back short for students, with hidden results left out
+                let mut visibility = Visibility::new(store.as_ref(), identity.role);
+                let mut results = Vec::new();
+                for result in page.results {
+                    if visibility.shows(&result).await? {
+                        results.push(mask.apply(self.redaction.redact(identity.role, result.into())));
+                    }
+                }
+
+                Ok(Response::new(ListExamResultsPageResponse {
+                    results,
+                    next_page_token: page.next_cursor.as_ref().map(encode_page_token).unwrap_or_default(),
+                }))
+            })
+            .await
+    }
+
+    // Server-Streaming RPC: the results whose student name and subject match a
+    // query, best first. Students only find their own results they may see.
+    type SearchExamResultsStream = ResponseStream<SearchMatch>;
+
+    async fn search_exam_results(
+        &self,
+        request: Request<SearchExamResultsRequest>,
+    ) -> Result<Response<Self::SearchExamResultsStream>, Status> {
+        info!(request = ?request.get_ref(), "search exam results");
+        validate(request.get_ref())?;
+
+        let deadline = self.deadline(&request)?;
+        let identity = Identity::from_request(&request)?;
+        let store = self.tenants.for_caller(&identity)?;
+        let slot = self.streams.reserve(&request, "exam.v2.ExamService/SearchExamResults")?;
+        let req = request.into_inner();
+        let limit = req.limit.map_or(DEFAULT_SEARCH_LIMIT, |n| (n as usize).min(MAX_SEARCH_LIMIT));
+
+        // Matches are ranked by the store, so the limit is taken after scoping
+        let hits = deadline
+            .run(async {
+                let mut visibility = Visibility::new(store.as_ref(), identity.role);
+                let mut hits = Vec::new();
+                for hit in store.search(&req.query).await? {
+                    if hits.len() == limit {
+                        break;
+                    }
+                    if identity.require_read(&hit.result.student_id).is_ok() && visibility.shows(&hit.result).await? {
+                        hits.push(hit);
+                    }
+                }
+                Ok(hits)
+            })
+            .await?;
+
+        let role = identity.role;
+        let redaction = self.redaction.clone();
+        let mask = ReadMask::new(req.read_mask.as_ref());
+
+        let stream = self.streams.spawn(slot, |tx| async move {
+            for hit in hits {
+                let response = SearchMatch {
+                    result: Some(mask.apply(redaction.redact(role, hit.result.into()))),
+                    score: hit.score as i32,
+                };
+
+                let sent = tokio::select! {
+                    sent = tx.send(Ok(response)) => sent.is_ok(),
+                    _ = deadline.expired() => {
+                        info!("deadline passed, ending search");
+                        let _ = tx.send(Err(deadline::exceeded())).await;
+                        return;
+                    }
+                };
+
+                if !sent {
+                    info!("client disconnected before search finished");
+                    return;
+                }
+            }
+        });
+
+        Ok(Response::new(stream))
+    }
+
+    // Server-Streaming RPC: a download of every matching result as CSV or JSON lines.
+    type ExportResultsStream = ResponseStream<ExportChunk>;
+
+    async fn export_results(
+        &self,
+        request: Request<ExportResultsRequest>,
+    ) -> Result<Response<Self::ExportResultsStream>, Status> {
+        info!(request = ?request.get_ref(), "export results");
+        validate(request.get_ref())?;
+
+        // Full dumps can take a while, so only the client's own deadline applies
+        let deadline = Deadline::from_request(&request, None)?;
+        let identity = Identity::from_request(&request)?;
+        let slot = self.streams.reserve(&request, "exam.v2.ExamService/ExportResults")?;
+        let req = request.into_inner();
+        let mut encoder = ExportEncoder::new(req.format())?;
+        let mut filter = ResultFilter::from_request(&req.filter.unwrap_or_default())?;
+        scope_filter(&identity, &mut filter)?;
+
+        let role = identity.role;
+        let store = self.tenants.for_caller(&identity)?.clone();
+        let redaction = self.redaction.clone();
+
+        // Pages through the store like ListExamResults, sending a chunk whenever one fills
+        let stream = self.streams.spawn(slot, |tx| async move {
+            let mut cursor = None;
+            let mut exported = 0;
+            let mut visibility = Visibility::new(store.as_ref(), role);
+
+            loop {
+                let scan = scan_page(store.as_ref(), &filter, cursor, MAX_PAGE_SIZE);
+                let page = match deadline.run(async { Ok(scan.await?) }).await {
+                    Ok(page) => page,
+                    Err(status) => {
+                        let _ = tx.send(Err(status)).await;
+                        return;
+                    }
+                };
+
+                for result in page.results {
+                    match visibility.shows(&result).await {
+                        Ok(true) => {}
+                        Ok(false) => continue,
+                        Err(err) => {
+                            let _ = tx.send(Err(err.into())).await;
+                            return;
+                        }
+                    }
+
+                    let chunk = match encoder.push(&redaction.redact(role, result.into())) {
+                        Ok(chunk) => chunk,
+                        Err(status) => {
+                            let _ = tx.send(Err(status)).await;
+                            return;
+                        }
+                    };
+                    exported += 1;
+
+                    let Some(chunk) = chunk else { continue };
+
+                    let sent = tokio::select! {
+                        sent = tx.send(Ok(chunk)) => sent.is_ok(),
+                        _ = deadline.expired() => {
+                            info!("deadline passed, ending export");
+                            let _ = tx.send(Err(deadline::exceeded())).await;
+                            return;
+                        }
+                    };
+
+                    if !sent {
+                        info!("client disconnected before export finished");
+                        return;
+                    }
+                }
+
+                match page.next_cursor {
+                    Some(next) => cursor = Some(next),
+                    None => break,
+                }
+            }
+
+            if let Some(chunk) = encoder.finish() {
+                let _ = tx.send(Ok(chunk)).await;
+            }
+            info!(exported, "export finished");
+        });
+
+        Ok(Response::new(stream))
+    }
+
+    // Handles an admin request to remove a result, returning what was removed.
+    async fn delete_exam_result(
+        &self,
+        request: Request<DeleteExamResultRequest>,
+    ) -> Result<Response<DeleteExamResultResponse>, Status> {
+        info!(request = ?request.get_ref(), "delete exam result");
+        validate(request.get_ref())?;
+
+        let deadline = self.deadline(&request)?;
+        let service = self.clone();
+        deadline
+            .run_write(async move {
+                let identity = Identity::from_request(&request)?;
+                let store = service.tenants.for_caller(&identity)?;
+                identity.require_admin()?;
+
+                let mut req = request.into_inner();
+                let request_id = mem::take(&mut req.request_id);
+                let key = ResultKey::parse(&req.student_id, &req.exam_id)?;
+
+                // A retried delete gets the removed result again rather than NOT_FOUND
+                service.requests
+                    .run("DeleteExamResult", &identity, &request_id, &req, async {
+                        let _lock = service.lock_result(&identity, &key).await;
+                        deadline::writing();
+                        let previous = store
+                            .delete(&key, now_ms())
+                            .await?
+                            .ok_or_else(|| result_not_found(&key))?;
+
+                        info!(%key, reason = %req.reason, "exam result deleted");
+                        let context = WriteContext {
+                            reason: &req.reason,
+                            request_id: &request_id,
+                        };
+                        service.record_change(&identity, &key, ChangeKind::Deleted, Some(previous.clone()), None, context)
+                            .await?;
+
+                        Ok(DeleteExamResultResponse {
+                            previous: Some(service.redaction.redact(identity.role, previous.into())),
+                        })
+                    })
+                    .await
+                    .map(Response::new)
+            })
+            .await
+    }
+
+    // Handles a unary request for the deleted results not yet purged.
+    async fn list_deleted_results(
+        &self,
+        request: Request<ListDeletedResultsRequest>,
+    ) -> Result<Response<ListDeletedResultsResponse>, Status> {
+        info!(request = ?request.get_ref(), "list deleted results");
+        validate(request.get_ref())?;
+
+        let deadline = self.deadline(&request)?;
+        deadline
+            .run(async move {
+                let identity = Identity::from_request(&request)?;
+                let store = self.tenants.for_caller(&identity)?;
+                identity.require_admin()?;
+
+                let req = request.into_inner();
+                let student_id = match req.student_id.as_str() {
+                    "" => None,
+                    student_id => Some(StudentId::parse(student_id)?),
+                };
+                let exam_id = match req.exam_id.as_str() {
+                    "" => None,
+                    exam_id => Some(ExamId::parse(exam_id)?),
+                };
+
+                let results = store.list_deleted(student_id.as_ref(), exam_id.as_ref()).await?;
+                Ok(Response::new(ListDeletedResultsResponse { results }))
+            })
+            .await
+    }
+
+    // Handles a unary request to bring back a deleted result before it is purged.
+    async fn undelete_exam_result(
+        &self,
+        request: Request<UndeleteExamResultRequest>,
+    ) -> Result<Response<UndeleteExamResultResponse>, Status> {
+        info!(request = ?request.get_ref(), "undelete exam result");
+        validate(request.get_ref())?;
+
+        let deadline = self.deadline(&request)?;
+        let service = self.clone();
+        deadline
+            .run_write(async move {
+                let identity = Identity::from_request(&request)?;
+                let store = service.tenants.for_caller(&identity)?;
+                identity.require_admin()?;
+
+                let mut req = request.into_inner();
+                let request_id = mem::take(&mut req.request_id);
+                let key = ResultKey::parse(&req.student_id, &req.exam_id)?;
+
+                // A retried undelete gets the restored result again rather than NOT_FOUND
+                service.requests
+                    .run("UndeleteExamResult", &identity, &request_id, &req, async {
+                        let _lock = service.lock_result(&identity, &key).await;
+                        deadline::writing();
+                        let restored = match store.undelete(&key).await? {
+                            Undelete::Restored(restored) => restored,
+                            Undelete::NotDeleted => return Err(not_deleted(&key)),
+                            Undelete::Occupied => return Err(undelete_conflict(&key)),
+                        };
+
+                        info!(%key, reason = %req.reason, "exam result undeleted");
+                        let context = WriteContext {
+                            reason: &req.reason,
+                            request_id: &request_id,
+                        };
+                        service.record_change(&identity, &key, ChangeKind::Restored, None, Some(restored.clone()), context)
+                            .await?;
+
+                        Ok(UndeleteExamResultResponse {
+                            result: Some(service.redaction.redact(identity.role, restored.into())),
+                        })
+                    })
+                    .await
+                    .map(Response::new)
+            })
+            .await
+    }
+
+    // Handles a request to amend the marks of an existing result; the grade is recomputed.
+    async fn correct_exam_result(
+        &self,
+        request: Request<CorrectExamResultRequest>,
+    ) -> Result<Response<CorrectExamResultResponse>, Status> {
+        info!(request = ?request.get_ref(), "correct exam result");
+        validate(request.get_ref())?;
+
+        let deadline = self.deadline(&request)?;
+        let service = self.clone();
+        deadline
+            .run_write(async move {
+                let identity = Identity::from_request(&request)?;
+                identity.require_write()?;
+
+                let mut req = request.into_inner();
+                let request_id = mem::take(&mut req.request_id);
+                let key = ResultKey::parse(&req.student_id, &req.exam_id)?;
+
+                service.requests
+                    .run("CorrectExamResult", &identity, &request_id, &req, async {
+                        let context = WriteContext {
+                            reason: &req.reason,
+                            request_id: &request_id,
+                        };
+                        let (previous, corrected) = self
+                            .correct_marks(&identity, &key, req.expected_version, req.marks_obtained, context)
+                            .await?;
+
+                        Ok(CorrectExamResultResponse {
+                            previous: Some(service.redaction.redact(identity.role, previous.into())),
+                            current: Some(service.redaction.redact(identity.role, corrected.into())),
+                        })
+                    })
+                    .await
+                    .map(Response::new)
+            })
+            .await
+    }
+
+    // Handles a request for aggregate statistics over every result for one exam.
+    async fn get_exam_statistics(
+        &self,
+        request: Request<GetExamStatisticsRequest>,
+    ) -> Result<Response<ExamStatistics>, Status> {
+        info!(request = ?request.get_ref(), "get exam statistics");
+        validate(request.get_ref())?;
+
+        let deadline = self.deadline(&request)?;
+        deadline
+            .run(async move {
+                let identity = Identity::from_request(&request)?;
+                let store = self.tenants.for_caller(&identity)?;
+                identity.require_staff()?;
+
+                let exam_id = ExamId::parse(&request.get_ref().exam_id)?;
+                let exam = store
+                    .get_exam(&exam_id)
+                    .await?
+                    .ok_or_else(|| exam_not_found(&exam_id))?;
+
+                let filter = ResultFilter {
+                    exam_id: Some(exam.exam_id.clone()),
+                    ..Default::default()
+                };
+
+                // The median and percentiles need every mark, so collect the whole exam
+                let mut results = Vec::new();
+                let mut cursor = None;
+
+                loop {
+                    let page = scan_page(store.as_ref(), &filter, cursor, MAX_PAGE_SIZE).await?;
+                    results.extend(page.results);
+
+                    match page.next_cursor {
+                        Some(next) => cursor = Some(next),
+                        None => break,
+                    }
+                }
+
+                let stats = exam_statistics(&exam, &results, &self.grading.get());
+                Ok(Response::new(withhold_small_cohort(stats, self.min_cohort_size)))
+            })
+            .await
+    }
+
+    // Handles a request for every result of one student plus their GPA.
+    async fn get_transcript(
+        &self,
+        request: Request<GetTranscriptRequest>,
+    ) -> Result<Response<Transcript>, Status> {
+        info!(request = ?request.get_ref(), "get transcript");
+        validate(request.get_ref())?;
+
+        let deadline = self.deadline(&request)?;
+        deadline
+            .run(async move {
+                let identity = Identity::from_request(&request)?;
+                let store = self.tenants.for_caller(&identity)?;
+                let req = request.into_inner();
+                identity.require_read(&req.student_id)?;
+
+                let student_id = StudentId::parse(&req.student_id)?;
+                let student = store
+                    .get_student(&student_id)
+                    .await?
+                    .ok_or_else(|| unregistered_student(&student_id))?;
+
+                // Students' transcripts leave out drafts and unreleased exams
+                let mut visibility = Visibility::new(store.as_ref(), identity.role);
+                let mut results = Vec::new();
+                for result in store.list_for_student(&student_id).await? {
+                    if visibility.shows(&result).await? {
+                        results.push(result);
+                    }
+                }
+
+                let mut response = transcript(&student, results, |result| {
+                    self.redaction.redact(identity.role, result)
+                });
+                response.student_name =
+                    self.redaction.student_name(identity.role, &response.student_id, response.student_name);
+                Ok(Response::new(response))
+            })
+            .await
+    }
+
+    // Server-Streaming RPC: open-ended feed of changes to matching results
+    type WatchExamResultsStream = ResponseStream<ResultChange>;
+
+    async fn watch_exam_results(
+        &self,
+        request: Request<WatchExamResultsRequest>,
+    ) -> Result<Response<Self::WatchExamResultsStream>, Status> {
+        info!(request = ?request.get_ref(), "watch exam results");
+        validate(request.get_ref())?;
+
+        // The feed is open-ended, so only the client's own deadline applies
+        let deadline = Deadline::from_request(&request, None)?;
+        let identity = Identity::from_request(&request)?;
+        let slot = self.streams.reserve(&request, "exam.v2.ExamService/WatchExamResults")?;
+        let req = request.into_inner();
+
+        // Same matching rules as listing, restricted to the two IDs
+        let mut filter = ResultFilter::from_request(&ListExamResultsRequest {
+            student_id: req.student_id,
+            exam_id: req.exam_id,
+            ..Default::default()
+        })?;
+        scope_filter(&identity, &mut filter)?;
+
+        let role = identity.role;
+        let store = self.tenants.for_caller(&identity)?.clone();
+        let redaction = self.redaction.clone();
+        let changes = self.changes.clone();
+        let institution = identity.institution.clone();
+
+        // Subscribe before returning so no change made after this call is missed
+        let mut events = changes.subscribe();
+
+        let stream = self.streams.spawn(slot, |tx| async move {
+            // Open indefinitely, so a release postponed meanwhile must be seen
+            let mut visibility = Visibility::new(store.as_ref(), role).uncached();
+
+            loop {
+                let event = tokio::select! {
+                    event = events.recv() => event,
+                    _ = tx.closed() => {
+                        info!("client stopped watching");
+                        return;
+                    }
+                    _ = changes.closed() => {
+                        info!("server shutting down, ending watch");
+                        return;
+                    }
+                    _ = deadline.expired() => {
+                        info!("deadline passed, ending watch");
+                        let _ = tx.send(Err(deadline::exceeded())).await;
+                        return;
+                    }
+                };
+
+                let event = match event {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        warn!(missed, "watcher fell behind");
+                        let _ = tx
+                            .send(Err(Status::aborted(format!(
+                                "Watcher fell behind and missed {} changes; resubscribe",
+                                missed
+                            ))))
+                            .await;
+                        return;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return,
+                };
+
+                // The feed carries every institution's changes
+                if event.institution != institution || !filter.matches(&event.result) {
+                    continue;
+                }
+
+                match visibility.shows(&event.result).await {
+                    Ok(true) => {}
+                    Ok(false) => continue,
+                    Err(err) => {
+                        let _ = tx.send(Err(err.into())).await;
+                        return;
+                    }
+                }
+
+                let change = ResultChange {
+                    kind: event.kind.into(),
+                    result: Some(redaction.redact(role, event.result.into())),
+                };
+
+                if tx.send(Ok(change)).await.is_err() {
+                    info!("client stopped watching");
+                    return;
+                }
+            }
+        });
+
+        Ok(Response::new(stream))
+    }
+
+    // Handles an admin request for the audit trail of one student's results.
+    async fn get_audit_trail(
+        &self,
+        request: Request<GetAuditTrailRequest>,
+    ) -> Result<Response<AuditTrail>, Status> {
+        info!(request = ?request.get_ref(), "get audit trail");
+        validate(request.get_ref())?;
+
+        let deadline = self.deadline(&request)?;
+        deadline
+            .run(async move {
+                let identity = Identity::from_request(&request)?;
+                let store = self.tenants.for_caller(&identity)?;
+                identity.require_admin()?;
+
+                let req = request.into_inner();
+                let student_id = StudentId::parse(&req.student_id)?;
+                let exam_id = match req.exam_id.as_str() {
+                    "" => None,
+                    exam_id => Some(ExamId::parse(exam_id)?),
+                };
+
+                let records = store.audit_trail(&student_id, exam_id.as_ref()).await?;
+
+                Ok(Response::new(AuditTrail { records }))
+            })
+            .await
+    }
+
+    // Handles a request to publish every draft result of one exam to its students.
+    async fn publish_exam_results(
+        &self,
+        request: Request<PublishExamResultsRequest>,
+    ) -> Result<Response<PublishExamResultsResponse>, Status> {
+        info!(request = ?request.get_ref(), "publish exam results");
+        validate(request.get_ref())?;
+
+        let deadline = self.deadline(&request)?;
+        let service = self.clone();
+        deadline
+            .run_write(async move {
+                let identity = Identity::from_request(&request)?;
+                identity.require_write()?;
+
+                let mut req = request.into_inner();
+                let request_id = mem::take(&mut req.request_id);
+                let exam_id = ExamId::parse(&req.exam_id)?;
+
+                service.requests
+                    .run("PublishExamResults", &identity, &request_id, &req, async {
+                        let context = WriteContext {
+                            request_id: &request_id,
+                            ..Default::default()
+                        };
+                        let published_count = service.publish_drafts(&identity, &exam_id, context).await?;
+                        Ok(PublishExamResultsResponse { published_count })
+                    })
+                    .await
+                    .map(Response::new)
+            })
+            .await
+    }
+
+    type WatchOperationStream = ResponseStream<Operation>;
+    type DownloadExportStream = ResponseStream<ExportChunk>;
+
+    // Starts importing results in the background and returns the operation
+    // following it straight away.
+    async fn start_import_results(
+        &self,
+        request: Request<StartImportResultsRequest>,
+    ) -> Result<Response<Operation>, Status> {
+        info!(results = request.get_ref().results.len(), "start import results");
+        validate(request.get_ref())?;
+
+        let deadline = self.deadline(&request)?;
+        deadline
+            .run(async move {
+                let identity = Identity::from_request(&request)?;
+                self.tenants.for_caller(&identity)?;
+                identity.require_write()?;
+
+                let mut req = request.into_inner();
+                let request_id = mem::take(&mut req.request_id);
+
+                self.requests
+                    .run("StartImportResults", &identity, &request_id, &req, async {
+                        let progress = self.operations.start(&identity, OperationKind::Import, req.results.len())?;
+                        let operation = progress.operation();
+                        info!(operation_id = %operation.operation_id, "import started");
+
+                        let (service, actor, request_id, results) =
+                            (self.clone(), identity.clone(), request_id.clone(), req.results.clone());
+                        tokio::spawn(async move {
+                            service.import_results(&actor, &request_id, results, &progress).await;
+                            progress.succeed(None);
+                        });
+                        Ok(operation)
+                    })
+                    .await
+                    .map(Response::new)
+            })
+            .await
+    }
+
+    // Starts exporting results in the background. The file is kept with the
+    // operation once it succeeds, for DownloadExport.
+    async fn start_export_results(&self, request: Request<ExportResultsRequest>) -> Result<Response<Operation>, Status> {
+        info!(request = ?request.get_ref(), "start export results");
+        validate(request.get_ref())?;
+
+        let identity = Identity::from_request(&request)?;
+        self.tenants.for_caller(&identity)?;
+        let req = request.into_inner();
+        let encoder = ExportEncoder::new(req.format())?;
+        let mut filter = ResultFilter::from_request(&req.filter.unwrap_or_default())?;
+        scope_filter(&identity, &mut filter)?;
+
+        let progress = self.operations.start(&identity, OperationKind::Export, 0)?;
+        let operation = progress.operation();
+        info!(operation_id = %operation.operation_id, "export started");
+
+        let service = self.clone();
         tokio::spawn(async move {
-            let simulated_results = vec![
-                format!("Processing result for {} (1/3)", key),
-                format!("Still working on {} (2/3)", key),
-                format!("Completed result for {} (3/3)", key),
-            ];
-
-            for msg in simulated_results {
-                if tx
-                    .send(Ok(GetExamResultResponse {
-                        student_name: "Streamed".to_string(),
-                        subject: "Simulation".to_string(),
-                        marks_obtained: 90,
-                        total_marks: 100,
-                        grade: msg.clone(),
-                    }))
+            match service.export_file(&identity, filter, encoder, &progress).await {
+                Ok(file) => progress.succeed(Some(file)),
+                Err(status) => progress.fail(&status),
+            }
+        });
+
+        Ok(Response::new(operation))
+    }
+
+    // Starts publishing the drafts of several exams in the background.
+    async fn start_publish_results(
+        &self,
+        request: Request<StartPublishResultsRequest>,
+    ) -> Result<Response<Operation>, Status> {
+        info!(request = ?request.get_ref(), "start publish results");
+        validate(request.get_ref())?;
+
+        let deadline = self.deadline(&request)?;
+        deadline
+            .run(async move {
+                let identity = Identity::from_request(&request)?;
+                self.tenants.for_caller(&identity)?;
+                identity.require_write()?;
+
+                let mut req = request.into_inner();
+                let request_id = mem::take(&mut req.request_id);
+                let exam_ids = req.exam_ids.iter().map(|id| ExamId::parse(id)).collect::<Result<Vec<_>, _>>()?;
+
+                self.requests
+                    .run("StartPublishResults", &identity, &request_id, &req, async {
+                        let progress = self.operations.start(&identity, OperationKind::Publish, exam_ids.len())?;
+                        let operation = progress.operation();
+                        info!(operation_id = %operation.operation_id, "publish started");
+
+                        let (service, actor, request_id, exam_ids) =
+                            (self.clone(), identity.clone(), request_id.clone(), exam_ids.clone());
+                        tokio::spawn(async move {
+                            service.publish_exams(&actor, &request_id, exam_ids, &progress).await;
+                            progress.succeed(None);
+                        });
+                        Ok(operation)
+                    })
                     .await
-                    .is_err()
-                {
-                    println!("Client disconnected before stream finished");
-                    break;
+                    .map(Response::new)
+            })
+            .await
+    }
+
+    // Streams an operation's state: as it stands, then on every change until it
+    // finishes. A slow watcher skips intermediate states rather than lagging.
+    async fn watch_operation(
+        &self,
+        request: Request<WatchOperationRequest>,
+    ) -> Result<Response<Self::WatchOperationStream>, Status> {
+        info!(request = ?request.get_ref(), "watch operation");
+        validate(request.get_ref())?;
+
+        // Operations may run for a while, so only the client's own deadline applies
+        let deadline = Deadline::from_request(&request, None)?;
+        let identity = Identity::from_request(&request)?;
+        let mut updates = self.operations.watch(&identity, &request.get_ref().operation_id)?;
+        let slot = self.streams.reserve(&request, "exam.v2.ExamService/WatchOperation")?;
+
+        let stream = self.streams.spawn(slot, |tx| async move {
+            loop {
+                let operation = updates.borrow_and_update().clone();
+                let finished = operation.state() != OperationState::Running;
+                if tx.send(Ok(operation)).await.is_err() || finished {
+                    return;
                 }
 
-                sleep(Duration::from_secs(1)).await;
+                tokio::select! {
+                    changed = updates.changed() => {
+                        if changed.is_err() {
+                            return;
+                        }
+                    }
+                    _ = deadline.expired() => {
+                        info!("deadline passed, ending operation watch");
+                        let _ = tx.send(Err(deadline::exceeded())).await;
+                        return;
+                    }
+                }
             }
         });
 
-        Ok(Response::new(ReceiverStream::new(rx)))
+        Ok(Response::new(stream))
+    }
+
+    // Streams the file of an export operation that succeeded, in chunks the
+    // size ExportResults sends.
+    async fn download_export(
+        &self,
+        request: Request<DownloadExportRequest>,
+    ) -> Result<Response<Self::DownloadExportStream>, Status> {
+        info!(request = ?request.get_ref(), "download export");
+        validate(request.get_ref())?;
+
+        let identity = Identity::from_request(&request)?;
+        let file = self.operations.output(&identity, &request.get_ref().operation_id)?;
+        let slot = self.streams.reserve(&request, "exam.v2.ExamService/DownloadExport")?;
+
+        let stream = self.streams.spawn(slot, |tx| async move {
+            for data in file.chunks(CHUNK_SIZE) {
+                if tx.send(Ok(ExportChunk { data: data.to_vec() })).await.is_err() {
+                    info!("client disconnected before download finished");
+                    return;
+                }
+            }
+        });
+
+        Ok(Response::new(stream))
     }
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let addr = "[::1]:50051".parse()?;
 
-    let exam_service = ExamServiceImpl::new();
+// A connection setting, where 0 means hyper's default.
+fn nonzero(value: u32) -> Option<u32> {
+    (value > 0).then_some(value)
+}
+
+// Applies the compression and message size settings to a generated service
+// server. The servers share no trait for this, hence a macro.
+macro_rules! configured {
+    ($server:expr, $config:expr) => {{
+        let config: &ServerConfig = $config;
+        let mut server = $server
+            .max_decoding_message_size(config.limits.max_decoding_message_bytes)
+            .max_encoding_message_size(config.limits.max_encoding_message_bytes);
+        if config.compression.accept {
+            server = server
+                .accept_compressed(CompressionEncoding::Gzip)
+                .accept_compressed(CompressionEncoding::Zstd);
+        }
+        if let Some(encoding) = config.compression.send.encoding() {
+            server = server.send_compressed(encoding);
+        }
+        server
+    }};
+}
 
-    println!("ExamService listening on {}", addr);
+// Adds ExamService, v2 and v1, to a router built by a larger binary, to be served
+// next to its own services. Calls are authenticated with `auth` and limited and
+// compressed as `config` says. The rest of what `serve_on` runs, from the other
+// services to scheduled jobs, the gateway and maintenance mode, is not started.
+pub fn mount<S: ExamStore, L>(
+    router: Router<L>,
+    exam_service: ExamServiceImpl<S>,
+    auth: TokenAuth,
+    config: &ServerConfig,
+) -> Router<L> {
+    let exam_service = Arc::new(exam_service);
+    let exam_service_v1 = ExamServiceV1::new(exam_service.clone());
 
-    Server::builder()
-        .add_service(ExamServer::new(exam_service))
-        .serve(addr)
-        .await?;
+    router
+        .add_service(InterceptedService::new(
+            configured!(ExamServer::from_arc(exam_service), config),
+            auth.clone(),
+        ))
+        .add_service(InterceptedService::new(
+            configured!(ExamServerV1::new(exam_service_v1), config),
+            auth,
+        ))
+}
+
+// Binds the configured address, and Unix socket if one is set, and serves until
+// SIGINT/SIGTERM; see `serve_on`. The socket file is removed once serving stops.
+pub async fn serve<S: ExamStore>(
+    config: &ServerConfig,
+    exam_service: ExamServiceImpl<S>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let listeners = Listeners::from(TcpListener::bind(config.addr()).await?);
+    let mut auth = TokenAuth::load(config)?;
+    if let Some(jwt) = JwtAuth::from_config(&config.jwt).await? {
+        auth = auth.with_jwt(jwt);
+    }
+
+    #[cfg(unix)]
+    if let Some(path) = &config.unix_socket {
+        let listeners = listeners.with_unix(bind_unix(path)?);
+        let served = serve_on(config, exam_service, auth, listeners, shutdown_signal()).await;
+        remove_unix(path);
+        return served;
+    }
+    #[cfg(not(unix))]
+    if config.unix_socket.is_some() {
+        return Err("unix_socket is only supported on Unix".into());
+    }
+
+    serve_on(config, exam_service, auth, listeners, shutdown_signal()).await
+}
+
+// Serves ExamService, ExamAdminService, StudentService, AppealService,
+// SessionService, GraderService, WebhookService, SnapshotService and
+// AdminService on already-bound listeners, along with health checks,
+// reflection, the metrics endpoint and the HTTP gateway, until `signal`
+// resolves, then drains and flushes the store. With replication, SyncService
+// is served too and the configured peers followed. A SIGHUP meanwhile reloads
+// grade boundaries, rate limits and `api_tokens_file`.
+// Lets tests and embedders bind an ephemeral port and supply their own tokens.
+pub async fn serve_on<S: ExamStore>(
+    config: &ServerConfig,
+    exam_service: ExamServiceImpl<S>,
+    auth: TokenAuth,
+    listeners: impl Into<Listeners>,
+    signal: impl Future<Output = ()>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let listeners = listeners.into();
+    let addr = listeners.describe();
+
+    // HTTP/1.1 is accepted so browsers can reach the gRPC-Web layer
+    let connections = &config.connections;
+    let mut builder = Server::builder()
+        .accept_http1(true)
+        .http2_keepalive_interval(connections.http2_keepalive_interval())
+        .http2_keepalive_timeout(Some(connections.http2_keepalive_timeout()))
+        .http2_adaptive_window(Some(connections.http2_adaptive_window))
+        .max_concurrent_streams(nonzero(connections.max_concurrent_streams))
+        .initial_stream_window_size(nonzero(connections.initial_stream_window_size))
+        .initial_connection_window_size(nonzero(connections.initial_connection_window_size));
+    if connections.concurrency_limit_per_connection > 0 {
+        builder = builder.concurrency_limit_per_connection(connections.concurrency_limit_per_connection);
+    }
+
+    if let Some(tls) = server_tls(&config.tls)? {
+        builder = builder.tls_config(tls)?;
+    }
+
+    let metrics = Metrics::new();
+    let metrics_listener = serve_metrics(config.metrics_addr, metrics.clone());
+
+    // All services share each institution's store, so submissions see catalog and roster changes immediately
+    let exam_service = Arc::new(exam_service);
+    let exam_service_v1 = ExamServiceV1::new(exam_service.clone());
+    let tenants = exam_service.tenants().clone();
+    let changes = exam_service.changes().clone();
+    let exam_admin = ExamAdminServiceImpl::new(tenants.clone()).with_max_processing_time(config.max_processing_time());
+    let students = StudentServiceImpl::new(tenants.clone()).with_max_processing_time(config.max_processing_time());
+    let appeals = AppealServiceImpl::new(exam_service.clone()).with_max_processing_time(config.max_processing_time());
+    let sessions = SessionServiceImpl::new(exam_service.clone()).with_config(&config.sessions);
+    let graders = GraderServiceImpl::new(exam_service.clone()).with_max_processing_time(config.max_processing_time());
+    let sender = HttpSender::new(config.webhooks.ca.as_deref())?;
+    let webhooks = WebhookServiceImpl::new(tenants.clone(), &sender).with_max_processing_time(config.max_processing_time());
+
+    let snapshots = Snapshots::new(&config.snapshots);
+    let snapshot_service =
+        SnapshotServiceImpl::new(tenants.clone(), snapshots.clone()).with_max_processing_time(config.max_processing_time());
+
+    let settings = Reloadable::new(config.clone());
+    let maintenance = Maintenance::default();
+    let admin = AdminServiceImpl::new(
+        settings.clone(),
+        exam_service.streams().clone(),
+        maintenance.clone(),
+        tenants.clone(),
+        snapshots.clone(),
+    )
+    .with_max_processing_time(config.max_processing_time());
+    let sync = exam_service
+        .replication()
+        .map(|log| SyncServiceImpl::new(log.clone(), exam_service.streams().clone(), changes.clone()));
+    let replicating = sync.is_some();
+
+    // SIGHUP re-reads grade boundaries, rate limits and tokens; open streams are untouched
+    let rate_limit = RateLimitLayer::new(config.rate_limit.clone());
+    let reloader = Reloader {
+        config: settings,
+        grading: exam_service.grading().clone(),
+        rate_limits: rate_limit.config().clone(),
+        auth: auth.clone(),
+    };
+    reloader.spawn_on_hangup()?;
+
+    // Subscribed before serving, so no publication goes undelivered
+    WebhookDispatcher::new(tenants.clone(), sender, &config.webhooks).spawn(&changes);
+    if let Some(log) = exam_service.replication() {
+        follow_peers(log, &config.replication, &changes)?;
+        info!(instance_id = log.instance_id(), peers = ?config.replication.peers, "replicating writes");
+    }
+
+    let mut scheduler = Scheduler::new().with_metrics(metrics.clone());
+    if let Some(interval) = config.scheduler.cache_eviction_interval() {
+        let exam_service = exam_service.clone();
+        scheduler.every("cache_eviction", interval, move || {
+            let exam_service = exam_service.clone();
+            async move { exam_service.evict_expired().await }
+        });
+    }
+    if let Some(interval) = config.scheduler.store_metrics_interval() {
+        let (metrics, tenants) = (metrics.clone(), tenants.clone());
+        scheduler.every("store_metrics", interval, move || {
+            let (metrics, tenants) = (metrics.clone(), tenants.clone());
+            async move { metrics.count_stored_results(&tenants).await }
+        });
+    }
+    if let Some(interval) = config.scheduler.stale_drafts_interval() {
+        let (exam_service, age) = (exam_service.clone(), config.scheduler.stale_draft_age());
+        scheduler.every("stale_drafts", interval, move || {
+            let exam_service = exam_service.clone();
+            async move { exam_service.delete_stale_drafts(age).await }
+        });
+    }
+    if let Some(interval) = config.scheduler.purge_deleted_interval() {
+        let (exam_service, retention) = (exam_service.clone(), config.scheduler.deleted_retention());
+        scheduler.every("purge_deleted", interval, move || {
+            let exam_service = exam_service.clone();
+            async move { exam_service.purge_deleted(retention).await }
+        });
+    }
+    if let Some(interval) = config.snapshots.interval() {
+        let (snapshots, tenants) = (snapshots.clone(), tenants.clone());
+        scheduler.every("snapshots", interval, move || {
+            let (snapshots, tenants) = (snapshots.clone(), tenants.clone());
+            async move { snapshots.take_automatic(&tenants).await.map(drop) }
+        });
+    }
+    info!(jobs = ?scheduler.jobs(), "maintenance jobs scheduled");
+
+    // Standard grpc.health.v1.Health service, exempt from authentication so probes work
+    let (mut health_reporter, health_service) = tonic_health::server::health_reporter();
+    health_reporter
+        .set_serving::<ExamServer<ExamServiceImpl<S>>>()
+        .await;
+    health_reporter
+        .set_serving::<ExamServerV1<ExamServiceV1<S>>>()
+        .await;
+    health_reporter
+        .set_service_status(LEGACY_SERVICE_NAME, ServingStatus::Serving)
+        .await;
+    health_reporter
+        .set_serving::<ExamAdminServiceServer<ExamAdminServiceImpl<S>>>()
+        .await;
+    health_reporter
+        .set_serving::<StudentServiceServer<StudentServiceImpl<S>>>()
+        .await;
+    health_reporter
+        .set_serving::<AppealServiceServer<AppealServiceImpl<S>>>()
+        .await;
+    health_reporter
+        .set_serving::<SessionServiceServer<SessionServiceImpl<S>>>()
+        .await;
+    health_reporter
+        .set_serving::<GraderServiceServer<GraderServiceImpl<S>>>()
+        .await;
+    health_reporter
+        .set_serving::<WebhookServiceServer<WebhookServiceImpl<S>>>()
+        .await;
+    health_reporter
+        .set_serving::<SnapshotServiceServer<SnapshotServiceImpl<S>>>()
+        .await;
+    health_reporter
+        .set_serving::<AdminServiceServer<AdminServiceImpl<S>>>()
+        .await;
+    if replicating {
+        health_reporter
+            .set_serving::<SyncServiceServer<SyncServiceImpl>>()
+            .await;
+    }
+
+    // On SIGINT/SIGTERM, report NOT_SERVING so load balancers stop routing to us,
+    // then stop accepting connections while in-flight RPCs and streams drain
+    let (draining_tx, draining_rx) = oneshot::channel();
+    let shutdown = async move {
+        signal.await;
+        info!("shutdown requested, reporting NOT_SERVING");
+        health_reporter
+            .set_not_serving::<ExamServer<ExamServiceImpl<S>>>()
+            .await;
+        health_reporter
+            .set_not_serving::<ExamServerV1<ExamServiceV1<S>>>()
+            .await;
+        health_reporter
+            .set_service_status(LEGACY_SERVICE_NAME, ServingStatus::NotServing)
+            .await;
+        health_reporter
+            .set_not_serving::<ExamAdminServiceServer<ExamAdminServiceImpl<S>>>()
+            .await;
+        health_reporter
+            .set_not_serving::<StudentServiceServer<StudentServiceImpl<S>>>()
+            .await;
+        health_reporter
+            .set_not_serving::<AppealServiceServer<AppealServiceImpl<S>>>()
+            .await;
+        health_reporter
+            .set_not_serving::<SessionServiceServer<SessionServiceImpl<S>>>()
+            .await;
+        health_reporter
+            .set_not_serving::<GraderServiceServer<GraderServiceImpl<S>>>()
+            .await;
+        health_reporter
+            .set_not_serving::<WebhookServiceServer<WebhookServiceImpl<S>>>()
+            .await;
+        health_reporter
+            .set_not_serving::<SnapshotServiceServer<SnapshotServiceImpl<S>>>()
+            .await;
+        health_reporter
+            .set_not_serving::<AdminServiceServer<AdminServiceImpl<S>>>()
+            .await;
+        if replicating {
+            health_reporter
+                .set_not_serving::<SyncServiceServer<SyncServiceImpl>>()
+                .await;
+        }
+        health_reporter
+            .set_service_status("", ServingStatus::NotServing)
+            .await;
+        // Watch and sync streams never finish on their own, so end them before draining
+        changes.close();
+        let _ = draining_tx.send(());
+    };
+
+    // Bounds how long draining may take once shutdown has begun
+    let timeout = config.shutdown_timeout();
+    let drain_deadline = async move {
+        if draining_rx.await.is_err() {
+            std::future::pending::<()>().await;
+        }
+        tokio::time::sleep(timeout).await;
+    };
+
+
+    // Reflection lets grpcurl/grpcui discover services without the .proto files.
+    // v1alpha is still what many deployed tools speak, so serve both.
+    let reflection_v1 = tonic_reflection::server::Builder::configure()
+        .register_encoded_file_descriptor_set(exam_service::FILE_DESCRIPTOR_SET)
+        .register_encoded_file_descriptor_set(tonic_health::pb::FILE_DESCRIPTOR_SET)
+        .build_v1()?;
+    let reflection_v1alpha = tonic_reflection::server::Builder::configure()
+        .register_encoded_file_descriptor_set(exam_service::FILE_DESCRIPTOR_SET)
+        .register_encoded_file_descriptor_set(tonic_health::pb::FILE_DESCRIPTOR_SET)
+        .build_v1alpha()?;
+
+    let cors = cors_layer(&config.cors_origins)?;
+    let gateway = serve_gateway(config.http_addr, exam_service.clone(), auth.clone(), maintenance.clone());
+
+    info!(
+        %addr,
+        "ExamService, ExamAdminService, StudentService, AppealService, SessionService, GraderService, WebhookService, SnapshotService and AdminService listening"
+    );
+
+    // CORS answers browser preflights, then gRPC-Web calls are translated to
+    // plain gRPC and unversioned ExamService calls routed to v1 before
+    // tracing, so every front end is traced the same way
+    let grpc = builder
+        .layer(cors)
+        .layer(GrpcWebLayer::new())
+        .layer(LegacyPathLayer)
+        // gRPC-Web re-encodes tonic's boxed body, so box the traced body back up
+        .layer(MapResponseLayer::new(|response: http::Response<TracedBody<BoxBody>>| {
+            response.map(tonic::body::boxed)
+        }))
+        .layer(RpcTraceLayer::new(metrics))
+        .layer(rate_limit)
+        .layer(MaintenanceLayer::new(maintenance))
+        .add_service(health_service)
+        .add_service(reflection_v1)
+        .add_service(reflection_v1alpha)
+        .add_service(InterceptedService::new(
+            configured!(ExamServer::from_arc(exam_service), config),
+            auth.clone(),
+        ))
+        .add_service(InterceptedService::new(
+            configured!(ExamServerV1::new(exam_service_v1), config),
+            auth.clone(),
+        ))
+        .add_service(InterceptedService::new(
+            configured!(ExamAdminServiceServer::new(exam_admin), config),
+            auth.clone(),
+        ))
+        .add_service(InterceptedService::new(
+            configured!(StudentServiceServer::new(students), config),
+            auth.clone(),
+        ))
+        .add_service(InterceptedService::new(
+            configured!(AppealServiceServer::new(appeals), config),
+            auth.clone(),
+        ))
+        .add_service(InterceptedService::new(
+            configured!(SessionServiceServer::new(sessions), config),
+            auth.clone(),
+        ))
+        .add_service(InterceptedService::new(
+            configured!(GraderServiceServer::new(graders), config),
+            auth.clone(),
+        ))
+        .add_service(InterceptedService::new(
+            configured!(WebhookServiceServer::new(webhooks), config),
+            auth.clone(),
+        ))
+        .add_service(InterceptedService::new(
+            configured!(SnapshotServiceServer::new(snapshot_service), config),
+            auth.clone(),
+        ))
+        .add_service(InterceptedService::new(
+            configured!(AdminServiceServer::new(admin), config),
+            auth.clone(),
+        ))
+        .add_optional_service(
+            sync.map(|sync| InterceptedService::new(configured!(SyncServiceServer::new(sync), config), auth)),
+        )
+        .serve_with_incoming_shutdown(listeners.incoming(connections)?, shutdown);
+
+    // The metrics endpoint and HTTP gateway live exactly as long as the gRPC server
+    tokio::select! {
+        result = grpc => {
+            result?;
+            info!("all in-flight requests drained");
+        }
+        result = metrics_listener => result?,
+        result = gateway => result?,
+        _ = drain_deadline => warn!(?timeout, "drain timed out, dropping remaining connections"),
+    }
+
+    // Before flushing, so no job writes to a store after it has been flushed
+    scheduler.shutdown(timeout).await;
+    tenants.flush().await?;
+    info!("storage flushed, exiting");
 
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,184 @@
+use std::collections::{HashMap, HashSet};
+use tokio::sync::mpsc;
+use tokio_stream::{Stream, StreamExt};
+use tonic::Status;
+use tracing::{info, warn};
+
+use crate::errors::{invalid_field, not_found};
+use crate::exam_service::{AnswerSubmission, GradeUpdate};
+use crate::grading::GradingScheme;
+
+// A question as put to students, its expected answer and the marks it is worth.
+#[derive(Debug, Clone)]
+pub struct Question {
+    pub prompt: String,
+    pub answer: String,
+    pub marks: i32,
+}
+
+impl Question {
+    // The marks `answer` earns: all of them if it matches, ignoring case and
+    // surrounding whitespace, and none otherwise.
+    pub fn marks_for(&self, answer: &str) -> i32 {
+        if answer.trim().eq_ignore_ascii_case(&self.answer) { self.marks } else { 0 }
+    }
+}
+
+// The questions of one exam, keyed by question_id.
+#[derive(Debug, Clone)]
+pub struct ExamAnswers {
+    // Subject name, used to pick per-subject grade boundaries
+    pub subject: String,
+    pub questions: HashMap<String, Question>,
+}
+
+impl ExamAnswers {
+    pub fn max_total(&self) -> i32 {
+        self.questions.values().map(|question| question.marks).sum()
+    }
+
+    // The questions in the order they are put to students, by question_id.
+    pub fn in_order(&self) -> Vec<(&str, &Question)> {
+        let mut questions: Vec<_> = self.questions.iter().map(|(id, question)| (id.as_str(), question)).collect();
+        questions.sort_by_key(|(id, _)| *id);
+        questions
+    }
+}
+
+// Expected answers per exam, keyed by exam_id.
+#[derive(Debug, Clone, Default)]
+pub struct AnswerKey {
+    exams: HashMap<String, ExamAnswers>,
+}
+
+impl AnswerKey {
+    // Constructs an answer key for the sample exams.
+    pub fn with_sample_data() -> Self {
+        let math101 = ExamAnswers {
+            subject: "Math 101".to_string(),
+            questions: HashMap::from([
+                ("q1".to_string(), question("What is 2 + 2?", "4", 40)),
+                ("q2".to_string(), question("What is 3 x 4?", "12", 30)),
+                ("q3".to_string(), question("What is the ratio of a circle's circumference to its diameter?", "pi", 30)),
+            ]),
+        };
+
+        Self {
+            exams: HashMap::from([("math101".to_string(), math101)]),
+        }
+    }
+
+    pub fn exam(&self, exam_id: &str) -> Option<&ExamAnswers> {
+        self.exams.get(exam_id)
+    }
+}
+
+fn question(prompt: &str, answer: &str, marks: i32) -> Question {
+    Question {
+        prompt: prompt.to_string(),
+        answer: answer.to_string(),
+        marks,
+    }
+}
+
+// Reported when `exam_id` has no answer key, so can be neither graded nor sat.
+pub fn no_answer_key(exam_id: &str) -> Status {
+    not_found("answer_key", exam_id, format!("No answer key for exam: {}", exam_id))
+}
+
+// Grades answers as they arrive and streams back running scores.
+// All answers in a session must belong to the exam named by the first answer,
+// and each question may be answered once.
+// Once the client closes its side, a final update with the letter grade is sent.
+pub async fn run_grading_session(
+    answer_key: &AnswerKey,
+    grading: &GradingScheme,
+    mut inbound: impl Stream<Item = Result<AnswerSubmission, Status>> + Unpin,
+    tx: mpsc::Sender<Result<GradeUpdate, Status>>,
+) {
+    let mut exam: Option<(String, &ExamAnswers)> = None;
+    let mut running_total = 0;
+    let mut answered = HashSet::new();
+
+    loop {
+        let submission = match inbound.next().await.transpose() {
+            Ok(Some(submission)) => submission,
+            Ok(None) => break,
+            Err(status) => {
+                warn!(%status, "grading session aborted");
+                return;
+            }
+        };
+
+        if exam.is_none() {
+            match answer_key.exam(&submission.exam_id) {
+                Some(answers) => exam = Some((submission.exam_id.clone(), answers)),
+                None => {
+                    let _ = tx.send(Err(no_answer_key(&submission.exam_id))).await;
+                    return;
+                }
+            }
+        }
+
+        let (exam_id, answers) = exam.as_ref().expect("exam is set above");
+
+        if submission.exam_id != *exam_id {
+            let status = invalid_field(
+                "exam_id",
+                format!("{} is not this session's exam ({})", submission.exam_id, exam_id),
+            );
+            let _ = tx.send(Err(status)).await;
+            return;
+        }
+
+        let Some(question) = answers.questions.get(&submission.question_id) else {
+            let status = invalid_field(
+                "question_id",
+                format!("{} is not a question of exam {}", submission.question_id, exam_id),
+            );
+            let _ = tx.send(Err(status)).await;
+            return;
+        };
+
+        if !answered.insert(submission.question_id.clone()) {
+            let status = invalid_field(
+                "question_id",
+                format!("{} was already answered in this session", submission.question_id),
+            );
+            let _ = tx.send(Err(status)).await;
+            return;
+        }
+
+        let marks_awarded = question.marks_for(&submission.answer);
+        let correct = marks_awarded > 0;
+        running_total += marks_awarded;
+
+        let update = GradeUpdate {
+            question_id: submission.question_id,
+            correct,
+            marks_awarded,
+            running_total,
+            max_total: answers.max_total(),
+            ..Default::default()
+        };
+
+        if tx.send(Ok(update)).await.is_err() {
+            info!("client disconnected during grading session");
+            return;
+        }
+    }
+
+    let (subject, max_total) = exam
+        .map(|(_, answers)| (answers.subject.as_str(), answers.max_total()))
+        .unwrap_or_default();
+
+    let _ = tx
+        .send(Ok(GradeUpdate {
+            running_total,
+            max_total,
+            final_grade: grading.grade(subject, running_total, max_total),
+            is_final: true,
+            ..Default::default()
+        }))
+        .await;
+}
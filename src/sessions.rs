@@ -0,0 +1,440 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
+use tokio::time::{Instant, Interval, MissedTickBehavior};
+use tokio_stream::StreamExt;
+use tonic::{Request, Response, Status, Streaming};
+use tracing::{info, warn};
+
+use crate::audit::WriteContext;
+use crate::auth::{Identity, Role};
+use crate::config::SessionConfig;
+use crate::deadline::{self, Deadline};
+use crate::errors::{invalid_field, missing_dependency};
+use crate::exam_service::{ExamResult, QuestionScore};
+use crate::exam_session::session_event::Event;
+use crate::exam_session::session_request::Request as Message;
+use crate::exam_session::session_service_server::SessionService;
+use crate::exam_session::{
+    Answer, AnswerRecorded, Countdown, FinishReason, Question, SessionEvent, SessionFinished, SessionRequest,
+    SessionStarted,
+};
+use crate::key::{InstitutionId, ResultKey};
+use crate::roster::unregistered_student;
+use crate::schedule::{check_open, format_ms, now_ms};
+use crate::server::ExamServiceImpl;
+use crate::session::{no_answer_key, ExamAnswers};
+use crate::store::ExamStore;
+use crate::streaming::ResponseStream;
+
+// The audit log reason of results stored by a sitting.
+const SITTING_REASON: &str = "exam session";
+
+// Implements SessionService. Sittings are graded against the exam service's
+// answer key and their results stored through it, so they are validated,
+// audited and sent to watchers like any other submission.
+#[derive(Debug)]
+pub struct SessionServiceImpl<S> {
+    results: Arc<ExamServiceImpl<S>>,
+    config: SessionConfig,
+    // Sittings under way, by institution and result. A sitting outlives the
+    // streams serving it, and is held in memory only: a restart ends it
+    // without storing a result, so the student can start again.
+    sittings: Arc<Mutex<HashMap<(InstitutionId, ResultKey), Arc<Sitting>>>>,
+}
+
+// Clones share the sittings under way, so the tasks handing them in can
+// outlive the stream that started them.
+impl<S> Clone for SessionServiceImpl<S> {
+    fn clone(&self) -> Self {
+        Self {
+            results: self.results.clone(),
+            config: self.config.clone(),
+            sittings: self.sittings.clone(),
+        }
+    }
+}
+
+// One student sitting one exam.
+#[derive(Debug)]
+struct Sitting {
+    // The student, who is recorded as the author of the stored result
+    identity: Identity,
+    key: ResultKey,
+    subject: String,
+    exam: ExamAnswers,
+    ends_at_ms: i64,
+    ends_at: Instant,
+    // The answers so far by question_id; taken when the sitting is handed in
+    answers: Mutex<Option<HashMap<String, String>>>,
+    // Set once the sitting is handed in: what the student is told, or why
+    // the result could not be stored
+    finished: watch::Sender<Option<Result<SessionFinished, Status>>>,
+}
+
+impl Sitting {
+    // Records `answer`, returning how many questions are answered, or None if
+    // the sitting was handed in meanwhile.
+    fn record(&self, answer: Answer) -> Result<Option<i32>, Status> {
+        if !self.exam.questions.contains_key(&answer.question_id) {
+            return Err(invalid_field(
+                "question_id",
+                format!("{} is not a question of exam {}", answer.question_id, self.key.exam_id),
+            ));
+        }
+
+        let mut answers = self.answers.lock().expect("sitting answers lock");
+        Ok(answers.as_mut().map(|answers| {
+            answers.insert(answer.question_id, answer.answer);
+            answers.len() as i32
+        }))
+    }
+
+    fn remaining_ms(&self) -> i64 {
+        (self.ends_at_ms - now_ms()).max(0)
+    }
+}
+
+impl<S: ExamStore> SessionServiceImpl<S> {
+    pub fn new(results: Arc<ExamServiceImpl<S>>) -> Self {
+        Self {
+            results,
+            config: SessionConfig::default(),
+            sittings: Arc::default(),
+        }
+    }
+
+    // Replaces the time allowed per sitting and the countdown interval.
+    pub fn with_config(mut self, config: &SessionConfig) -> Self {
+        self.config = config.clone();
+        self
+    }
+
+    // The caller's sitting of `exam_id`: resumed if one is under way, or
+    // started if they may sit the exam. Returns whether it was resumed.
+    async fn open(&self, identity: &Identity, exam_id: &str) -> Result<(Arc<Sitting>, bool), Status> {
+        let key = ResultKey::parse(identity.student_id.as_deref().unwrap_or_default(), exam_id)?;
+        let id = (identity.institution.clone(), key.clone());
+        if let Some(sitting) = self.sittings.lock().expect("sittings lock").get(&id) {
+            return Ok((sitting.clone(), true));
+        }
+
+        let store = self.results.tenants().for_caller(identity)?;
+        store
+            .get_student(&key.student_id)
+            .await?
+            .ok_or_else(|| unregistered_student(&key.student_id))?;
+        let exam = store.get_exam(&key.exam_id).await?.ok_or_else(|| {
+            missing_dependency(
+                "NOT_IN_CATALOG",
+                format!("exam/{}", key.exam_id),
+                format!("Exam {} is not in the catalog", key.exam_id),
+            )
+        })?;
+
+        let now = now_ms();
+        check_open(&exam, now)?;
+        if exam.closes_at_ms > 0 && now >= exam.closes_at_ms {
+            return Err(missing_dependency(
+                "CLOSED",
+                format!("exam/{}", exam.exam_id),
+                format!("Exam {} closed at {}; it can no longer be sat", exam.exam_id, format_ms(exam.closes_at_ms)),
+            ));
+        }
+
+        let answers = self
+            .results
+            .answer_key()
+            .exam(exam_id)
+            .ok_or_else(|| no_answer_key(exam_id))?
+            .clone();
+
+        if store.get(&key).await?.is_some() {
+            return Err(Status::already_exists(format!(
+                "Result {} already exists; an exam can only be sat once",
+                key
+            )));
+        }
+
+        // Handed in when time is up, or when the exam closes if that is sooner
+        let mut ends_at_ms = now.saturating_add(self.config.duration().as_millis() as i64);
+        if exam.closes_at_ms > 0 {
+            ends_at_ms = ends_at_ms.min(exam.closes_at_ms);
+        }
+
+        let sitting = Arc::new(Sitting {
+            identity: identity.clone(),
+            key: key.clone(),
+            subject: exam.subject,
+            exam: answers,
+            ends_at_ms,
+            ends_at: Instant::now() + Duration::from_millis((ends_at_ms - now) as u64),
+            answers: Mutex::new(Some(HashMap::new())),
+            finished: watch::Sender::new(None),
+        });
+
+        // Another stream may have started the same sitting while this one looked things up
+        let sitting = match self.sittings.lock().expect("sittings lock").entry(id) {
+            Entry::Occupied(entry) => return Ok((entry.get().clone(), true)),
+            Entry::Vacant(entry) => entry.insert(sitting).clone(),
+        };
+
+        self.hand_in_when_time_is_up(sitting.clone());
+        info!(%key, ends_at = %format_ms(ends_at_ms), "exam sitting started");
+        Ok((sitting, false))
+    }
+
+    fn hand_in_when_time_is_up(&self, sitting: Arc<Sitting>) {
+        let service = self.clone();
+        let mut finished = sitting.finished.subscribe();
+
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = tokio::time::sleep_until(sitting.ends_at) => service.finish(&sitting, FinishReason::TimeUp).await,
+                // Handed in early
+                _ = finished.changed() => {}
+            }
+        });
+    }
+
+    // Hands in `sitting` from a task of its own, so a stream dropped
+    // meanwhile cannot abandon it half stored.
+    fn hand_in(&self, sitting: &Arc<Sitting>) {
+        let (service, sitting) = (self.clone(), sitting.clone());
+        tokio::spawn(async move { service.finish(&sitting, FinishReason::HandedIn).await });
+    }
+
+    // Grades the answers of `sitting` and stores its result as a draft, then
+    // tells every stream of it. Does nothing if it was already handed in.
+    async fn finish(&self, sitting: &Sitting, reason: FinishReason) {
+        let Some(answers) = sitting.answers.lock().expect("sitting answers lock").take() else {
+            return;
+        };
+
+        let questions = sitting
+            .exam
+            .in_order()
+            .into_iter()
+            .map(|(question_id, question)| QuestionScore {
+                question_id: question_id.to_string(),
+                marks: answers.get(question_id).map_or(0, |answer| question.marks_for(answer)),
+                max_marks: question.marks,
+                comment: String::new(),
+            })
+            .collect();
+
+        let result = ExamResult {
+            student_id: sitting.key.student_id.to_string(),
+            exam_id: sitting.key.exam_id.to_string(),
+            questions,
+            ..Default::default()
+        };
+        let context = WriteContext {
+            reason: SITTING_REASON,
+            request_id: "",
+        };
+
+        let outcome = match self.results.store_result(&sitting.identity, context, result).await {
+            Ok(_) => {
+                info!(key = %sitting.key, reason = reason.as_str_name(), answered = answers.len(), "exam sitting handed in");
+                Ok(SessionFinished {
+                    reason: reason as i32,
+                    answered_count: answers.len() as i32,
+                    question_count: sitting.exam.questions.len() as i32,
+                    finished_at_ms: now_ms(),
+                })
+            }
+            Err(status) => {
+                warn!(key = %sitting.key, %status, "could not store the result of an exam sitting");
+                Err(status)
+            }
+        };
+
+        // Only now, so no second sitting can start before the result is stored
+        self.sittings
+            .lock()
+            .expect("sittings lock")
+            .remove(&(sitting.identity.institution.clone(), sitting.key.clone()));
+        sitting.finished.send_replace(Some(outcome));
+    }
+
+    // Serves one stream of a sitting: its questions, the countdown, and the
+    // answers sent, until the sitting is handed in. If the stream drops first,
+    // the sitting carries on without it.
+    async fn run(
+        self,
+        identity: Identity,
+        mut inbound: Streaming<SessionRequest>,
+        deadline: Deadline,
+        tx: mpsc::Sender<Result<SessionEvent, Status>>,
+    ) {
+        let exam_id = match inbound.next().await {
+            Some(Ok(SessionRequest {
+                request: Some(Message::Start(start)),
+            })) => start.exam_id,
+            Some(Ok(_)) => {
+                let _ = tx.send(Err(invalid_field("start", "must be the first message"))).await;
+                return;
+            }
+            Some(Err(status)) => {
+                warn!(%status, "exam session aborted");
+                return;
+            }
+            None => return,
+        };
+
+        let (sitting, resumed) = match self.open(&identity, &exam_id).await {
+            Ok(opened) => opened,
+            Err(status) => {
+                let _ = tx.send(Err(status)).await;
+                return;
+            }
+        };
+
+        if send_questions(&sitting, resumed, &tx).await.is_err() {
+            return;
+        }
+
+        let mut finished = sitting.finished.subscribe();
+        let mut countdown = self.config.countdown_interval().map(|period| {
+            let mut ticks = tokio::time::interval_at(Instant::now() + period, period);
+            ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            ticks
+        });
+        let mut reading = true;
+
+        // Resuming a sitting being handed in skips straight to the outcome
+        while finished.borrow_and_update().is_none() {
+            let event = tokio::select! {
+                _ = finished.changed() => break,
+                message = inbound.next(), if reading => match message {
+                    Some(Ok(SessionRequest { request: Some(Message::Answer(answer)) })) => {
+                        let question_id = answer.question_id.clone();
+                        match sitting.record(answer) {
+                            Ok(Some(answered_count)) => Event::AnswerRecorded(AnswerRecorded { question_id, answered_count }),
+                            // Too late: the outcome follows
+                            Ok(None) => continue,
+                            Err(status) => {
+                                let _ = tx.send(Err(status)).await;
+                                return;
+                            }
+                        }
+                    }
+                    Some(Ok(SessionRequest { request: Some(Message::HandIn(_)) })) | None => {
+                        reading = false;
+                        self.hand_in(&sitting);
+                        continue;
+                    }
+                    Some(Ok(_)) => {
+                        let _ = tx.send(Err(invalid_field("start", "may only be the first message"))).await;
+                        return;
+                    }
+                    Some(Err(status)) => {
+                        info!(key = %sitting.key, %status, "exam session stream dropped; the sitting continues");
+                        return;
+                    }
+                },
+                _ = tick(&mut countdown) => Event::Countdown(Countdown { remaining_ms: sitting.remaining_ms() }),
+                _ = deadline.expired() => {
+                    let _ = tx.send(Err(deadline::exceeded())).await;
+                    return;
+                }
+            };
+
+            if send(&tx, event).await.is_err() {
+                return;
+            }
+        }
+
+        let outcome = sitting.finished.borrow().clone();
+        let _ = match outcome {
+            Some(Ok(finished)) => send(&tx, Event::Finished(finished)).await,
+            Some(Err(status)) => tx.send(Err(status)).await.map_err(drop),
+            None => Ok(()),
+        };
+    }
+}
+
+// Sends the start of a sitting: what it is, then every question, along with
+// the answer already recorded when resuming.
+async fn send_questions(
+    sitting: &Sitting,
+    resumed: bool,
+    tx: &mpsc::Sender<Result<SessionEvent, Status>>,
+) -> Result<(), ()> {
+    let answers = sitting.answers.lock().expect("sitting answers lock").clone().unwrap_or_default();
+    let questions = sitting.exam.in_order();
+
+    let started = SessionStarted {
+        student_id: sitting.key.student_id.to_string(),
+        exam_id: sitting.key.exam_id.to_string(),
+        subject: sitting.subject.clone(),
+        ends_at_ms: sitting.ends_at_ms,
+        question_count: questions.len() as i32,
+        resumed,
+    };
+    send(tx, Event::Started(started)).await?;
+
+    for (number, (question_id, question)) in questions.into_iter().enumerate() {
+        let question = Question {
+            question_id: question_id.to_string(),
+            number: number as i32 + 1,
+            prompt: question.prompt.clone(),
+            marks: question.marks,
+            answer: answers.get(question_id).cloned().unwrap_or_default(),
+        };
+        send(tx, Event::Question(question)).await?;
+    }
+    Ok(())
+}
+
+// Fails once the client is gone.
+async fn send(tx: &mpsc::Sender<Result<SessionEvent, Status>>, event: Event) -> Result<(), ()> {
+    tx.send(Ok(SessionEvent { event: Some(event) })).await.map_err(drop)
+}
+
+// The next countdown tick; never without a countdown.
+async fn tick(countdown: &mut Option<Interval>) {
+    match countdown {
+        Some(ticks) => {
+            ticks.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+#[tonic::async_trait]
+impl<S: ExamStore> SessionService for SessionServiceImpl<S> {
+    type TakeExamStream = ResponseStream<SessionEvent>;
+
+    // Bidirectional streaming RPC: one stream of a sitting, started or resumed
+    // by its first message.
+    async fn take_exam(
+        &self,
+        request: Request<Streaming<SessionRequest>>,
+    ) -> Result<Response<Self::TakeExamStream>, Status> {
+        info!("exam session opened");
+
+        let identity = Identity::from_request(&request)?;
+        if identity.role != Role::Student || identity.student_id.is_none() {
+            return Err(Status::permission_denied("Only students can take exams"));
+        }
+
+        // Sittings are interactive, so only the client's own deadline applies
+        let deadline = Deadline::from_request(&request, None)?;
+        let slot = self
+            .results
+            .streams()
+            .reserve(&request, "exam_session.SessionService/TakeExam")?;
+        let inbound = request.into_inner();
+        let service = self.clone();
+
+        let stream = self
+            .results
+            .streams()
+            .spawn(slot, |tx| service.run(identity, inbound, deadline, tx));
+        Ok(Response::new(stream))
+    }
+}
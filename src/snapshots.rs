@@ -0,0 +1,383 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use tokio::fs::File;
+use tokio::io::AsyncWriteExt;
+use tonic::{Request, Response, Status};
+use tracing::{error, info};
+
+use crate::auth::Identity;
+use crate::config::SnapshotConfig;
+use crate::deadline::{Deadline, DEFAULT_MAX_PROCESSING};
+use crate::errors::{invalid_field, missing_dependency, not_found};
+use crate::key::{id_problem, InstitutionId};
+use crate::schedule::now_ms;
+use crate::snapshot::snapshot_service_server::SnapshotService;
+use crate::snapshot::{
+    CreateSnapshotRequest, ListSnapshotsRequest, ListSnapshotsResponse, RestoreSnapshotRequest, SnapshotInfo,
+};
+use crate::store::{ExamStore, StoreContents};
+use crate::tenancy::Tenants;
+
+// Bumped whenever the file layout changes in a way older servers cannot read.
+const FORMAT_VERSION: u32 = 1;
+
+// Names of snapshots taken by the scheduler; only these are ever pruned.
+const AUTOMATIC_PREFIX: &str = "auto-";
+
+const EXTENSION: &str = ".json";
+
+// Read first, so a file from a newer server is rejected before its contents are parsed.
+#[derive(Debug, Deserialize)]
+struct Header {
+    format_version: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SnapshotFile {
+    format_version: u32,
+    created_at_ms: i64,
+    institution_id: String,
+    contents: StoreContents,
+}
+
+fn io_error(action: &str, path: &Path, err: io::Error) -> Status {
+    Status::internal(format!("could not {} {}: {}", action, path.display(), err))
+}
+
+// Makes the entries of `dir` durable, such as a file just linked into it.
+async fn sync_dir(dir: &Path) -> io::Result<()> {
+    File::open(dir).await?.sync_all().await
+}
+
+fn snapshot_not_found(name: &str) -> Status {
+    not_found("snapshot", name, format!("No snapshot named {:?}", name))
+}
+
+// A snapshot name sent by a client. Files starting with '.' are writes in progress.
+fn validate_name(name: &str) -> Result<(), Status> {
+    if let Some(problem) = id_problem(name) {
+        return Err(invalid_field("name", problem));
+    }
+    if name.starts_with('.') {
+        return Err(invalid_field("name", "must not start with '.'"));
+    }
+    Ok(())
+}
+
+// Snapshot files under `dir`, in one subdirectory per institution. Each is
+// written to a hidden temporary file, synced, and then linked into place, so
+// a crash or power loss never leaves a partial snapshot and an existing one
+// is never overwritten.
+#[derive(Debug, Clone)]
+pub struct Snapshots {
+    dir: PathBuf,
+    keep: usize,
+}
+
+impl Snapshots {
+    pub fn new(config: &SnapshotConfig) -> Self {
+        Self {
+            dir: config.dir.clone(),
+            keep: config.keep,
+        }
+    }
+
+    fn dir_for(&self, institution: &InstitutionId) -> PathBuf {
+        self.dir.join(institution.as_str())
+    }
+
+    fn path_for(&self, institution: &InstitutionId, name: &str) -> PathBuf {
+        self.dir_for(institution).join(format!("{}{}", name, EXTENSION))
+    }
+
+    // Writes everything in `store` to a new snapshot called `name`.
+    pub async fn create<S: ExamStore>(
+        &self,
+        institution: &InstitutionId,
+        store: &S,
+        name: &str,
+    ) -> Result<SnapshotInfo, Status> {
+        let file = SnapshotFile {
+            format_version: FORMAT_VERSION,
+            created_at_ms: now_ms(),
+            institution_id: institution.to_string(),
+            contents: store.dump().await?,
+        };
+        let json = serde_json::to_vec(&file).map_err(|err| Status::internal(format!("could not encode snapshot: {}", err)))?;
+
+        let dir = self.dir_for(institution);
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .map_err(|err| io_error("create", &dir, err))?;
+
+        let path = self.path_for(institution, name);
+        let temporary = dir.join(format!(".{}{}.tmp", name, EXTENSION));
+        // Synced before it is linked, so the name never points at data still in flight
+        let written = async {
+            let mut file = File::create(&temporary).await?;
+            file.write_all(&json).await?;
+            file.sync_all().await
+        };
+        written.await.map_err(|err| io_error("write", &temporary, err))?;
+
+        // Unlike a rename, linking fails if the name is taken
+        let linked = tokio::fs::hard_link(&temporary, &path).await;
+        let _ = tokio::fs::remove_file(&temporary).await;
+        match linked {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+                return Err(Status::already_exists(format!("A snapshot named {:?} already exists", name)));
+            }
+            Err(err) => return Err(io_error("write", &path, err)),
+        }
+        // The new name itself only survives a power loss once the directory is synced
+        sync_dir(&dir).await.map_err(|err| io_error("sync", &dir, err))?;
+
+        Ok(SnapshotInfo {
+            name: name.to_string(),
+            created_at_ms: file.created_at_ms,
+            size_bytes: json.len() as u64,
+            automatic: name.starts_with(AUTOMATIC_PREFIX),
+        })
+    }
+
+    // The institution's snapshots, newest first.
+    pub async fn list(&self, institution: &InstitutionId) -> Result<Vec<SnapshotInfo>, Status> {
+        let dir = self.dir_for(institution);
+        let mut entries = match tokio::fs::read_dir(&dir).await {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(io_error("read", &dir, err)),
+        };
+
+        let mut snapshots = Vec::new();
+        while let Some(entry) = entries.next_entry().await.map_err(|err| io_error("read", &dir, err))? {
+            let file_name = entry.file_name();
+            let Some(name) = file_name.to_str().and_then(|name| name.strip_suffix(EXTENSION)) else {
+                continue;
+            };
+            if name.starts_with('.') {
+                continue;
+            }
+
+            let metadata = entry.metadata().await.map_err(|err| io_error("read", &entry.path(), err))?;
+            let created_at_ms = metadata
+                .modified()
+                .ok()
+                .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |elapsed| elapsed.as_millis() as i64);
+
+            snapshots.push(SnapshotInfo {
+                name: name.to_string(),
+                created_at_ms,
+                size_bytes: metadata.len(),
+                automatic: name.starts_with(AUTOMATIC_PREFIX),
+            });
+        }
+
+        snapshots.sort_by(|a, b| (b.created_at_ms, &b.name).cmp(&(a.created_at_ms, &a.name)));
+        Ok(snapshots)
+    }
+
+    // Replaces everything in `store` with the contents of snapshot `name`.
+    pub async fn restore<S: ExamStore>(
+        &self,
+        institution: &InstitutionId,
+        store: &S,
+        name: &str,
+    ) -> Result<SnapshotInfo, Status> {
+        let path = self.path_for(institution, name);
+        let json = match tokio::fs::read(&path).await {
+            Ok(json) => json,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Err(snapshot_not_found(name)),
+            Err(err) => return Err(io_error("read", &path, err)),
+        };
+
+        let unreadable = |err: serde_json::Error| {
+            missing_dependency(
+                "SNAPSHOT_UNREADABLE",
+                format!("snapshot/{}", name),
+                format!("Snapshot {:?} is not a valid snapshot file: {}", name, err),
+            )
+        };
+        let header: Header = serde_json::from_slice(&json).map_err(unreadable)?;
+        if header.format_version != FORMAT_VERSION {
+            return Err(missing_dependency(
+                "SNAPSHOT_FORMAT",
+                format!("snapshot/{}", name),
+                format!(
+                    "Snapshot {:?} has format version {}; this server reads version {}",
+                    name, header.format_version, FORMAT_VERSION
+                ),
+            ));
+        }
+        let file: SnapshotFile = serde_json::from_slice(&json).map_err(unreadable)?;
+
+        store.restore(file.contents).await?;
+
+        Ok(SnapshotInfo {
+            name: name.to_string(),
+            created_at_ms: file.created_at_ms,
+            size_bytes: json.len() as u64,
+            automatic: name.starts_with(AUTOMATIC_PREFIX),
+        })
+    }
+
+    // Deletes all but the newest `keep` automatic snapshots; a `keep` of 0 keeps them all.
+    async fn prune(&self, institution: &InstitutionId) -> Result<(), Status> {
+        if self.keep == 0 {
+            return Ok(());
+        }
+
+        let automatic = self.list(institution).await?.into_iter().filter(|snapshot| snapshot.automatic);
+        for snapshot in automatic.skip(self.keep) {
+            let path = self.path_for(institution, &snapshot.name);
+            tokio::fs::remove_file(&path)
+                .await
+                .map_err(|err| io_error("delete", &path, err))?;
+        }
+        Ok(())
+    }
+
+    // Snapshots every institution, pruning old automatic snapshots. Run
+    // periodically by the scheduler, and on demand by the admin service; one
+    // institution failing does not stop the rest.
+    pub async fn take_automatic<S: ExamStore>(
+        &self,
+        tenants: &Tenants<S>,
+    ) -> Result<Vec<(InstitutionId, SnapshotInfo)>, Status> {
+        let mut taken_all = Vec::new();
+        let mut failed = 0;
+
+        for (institution, store) in tenants.iter() {
+            let name = format!("{}{}", AUTOMATIC_PREFIX, now_ms());
+            let taken = match self.create(institution, store.as_ref(), &name).await {
+                Ok(snapshot) => self.prune(institution).await.map(|()| snapshot),
+                Err(status) => Err(status),
+            };
+            match taken {
+                Ok(snapshot) => {
+                    info!(%institution, name = %snapshot.name, size_bytes = snapshot.size_bytes, "automatic snapshot taken");
+                    taken_all.push((institution.clone(), snapshot));
+                }
+                Err(status) => {
+                    error!(%institution, error = status.message(), "automatic snapshot failed");
+                    failed += 1;
+                }
+            }
+        }
+
+        match failed {
+            0 => Ok(taken_all),
+            n => Err(Status::internal(format!("{} automatic snapshots failed", n))),
+        }
+    }
+}
+
+// Implements SnapshotService over each institution's store.
+#[derive(Debug)]
+pub struct SnapshotServiceImpl<S> {
+    tenants: Tenants<S>,
+    snapshots: Snapshots,
+    max_processing: Duration,
+}
+
+impl<S: ExamStore> SnapshotServiceImpl<S> {
+    pub fn new(tenants: Tenants<S>, snapshots: Snapshots) -> Self {
+        Self {
+            tenants,
+            snapshots,
+            max_processing: DEFAULT_MAX_PROCESSING,
+        }
+    }
+
+    // Replaces the server-side processing limit.
+    pub fn with_max_processing_time(mut self, limit: Duration) -> Self {
+        self.max_processing = limit;
+        self
+    }
+
+    // The client's deadline for `request`, capped by the processing limit.
+    fn deadline<T>(&self, request: &Request<T>) -> Result<Deadline, Status> {
+        Deadline::from_request(request, Some(self.max_processing))
+    }
+}
+
+#[tonic::async_trait]
+impl<S: ExamStore> SnapshotService for SnapshotServiceImpl<S> {
+    async fn create_snapshot(
+        &self,
+        request: Request<CreateSnapshotRequest>,
+    ) -> Result<Response<SnapshotInfo>, Status> {
+        info!(req = ?request.get_ref(), "create snapshot");
+
+        let deadline = self.deadline(&request)?;
+        deadline
+            .run(async move {
+                let identity = Identity::from_request(&request)?;
+                identity.require_admin()?;
+                let req = request.into_inner();
+
+                let name = match req.name.as_str() {
+                    "" => format!("snapshot-{}", now_ms()),
+                    name if name.starts_with(AUTOMATIC_PREFIX) => {
+                        return Err(invalid_field("name", format!("must not start with {:?}", AUTOMATIC_PREFIX)));
+                    }
+                    name => {
+                        validate_name(name)?;
+                        name.to_string()
+                    }
+                };
+
+                let store = self.tenants.for_caller(&identity)?;
+                let snapshot = self.snapshots.create(&identity.institution, store.as_ref(), &name).await?;
+                info!(institution = %identity.institution, name = %snapshot.name, size_bytes = snapshot.size_bytes, "snapshot taken");
+                Ok(Response::new(snapshot))
+            })
+            .await
+    }
+
+    async fn list_snapshots(
+        &self,
+        request: Request<ListSnapshotsRequest>,
+    ) -> Result<Response<ListSnapshotsResponse>, Status> {
+        info!("list snapshots");
+
+        let deadline = self.deadline(&request)?;
+        deadline
+            .run(async move {
+                let identity = Identity::from_request(&request)?;
+                identity.require_admin()?;
+                self.tenants.for_caller(&identity)?;
+
+                let snapshots = self.snapshots.list(&identity.institution).await?;
+                Ok(Response::new(ListSnapshotsResponse { snapshots }))
+            })
+            .await
+    }
+
+    async fn restore_snapshot(
+        &self,
+        request: Request<RestoreSnapshotRequest>,
+    ) -> Result<Response<SnapshotInfo>, Status> {
+        info!(req = ?request.get_ref(), "restore snapshot");
+
+        let deadline = self.deadline(&request)?;
+        deadline
+            .run(async move {
+                let identity = Identity::from_request(&request)?;
+                identity.require_admin()?;
+                let req = request.into_inner();
+
+                validate_name(&req.name)?;
+
+                let store = self.tenants.for_caller(&identity)?;
+                let snapshot = self.snapshots.restore(&identity.institution, store.as_ref(), &req.name).await?;
+                info!(institution = %identity.institution, name = %snapshot.name, actor = %identity.name, "snapshot restored");
+                Ok(Response::new(snapshot))
+            })
+            .await
+    }
+}
@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+
+use crate::exam_admin::Exam;
+use crate::exam_service::{ExamResult, ExamStatistics, GradeCount};
+use crate::grading::GradingScheme;
+use crate::query::grade_rank;
+
+// Computes aggregate statistics for one exam from its stored results.
+pub fn exam_statistics(exam: &Exam, results: &[ExamResult], grading: &GradingScheme) -> ExamStatistics {
+    let mut stats = ExamStatistics {
+        exam_id: exam.exam_id.clone(),
+        total_marks: exam.total_marks,
+        ..Default::default()
+    };
+
+    if results.is_empty() {
+        return stats;
+    }
+
+    let mut marks: Vec<i32> = results.iter().map(|result| result.marks_obtained).collect();
+    marks.sort_unstable();
+
+    let count = marks.len() as f64;
+    let mean = marks.iter().map(|&m| m as f64).sum::<f64>() / count;
+    let variance = marks.iter().map(|&m| (m as f64 - mean).powi(2)).sum::<f64>() / count;
+
+    let passed = results
+        .iter()
+        .filter(|result| grading.passes(&exam.subject, result.marks_obtained, result.total_marks))
+        .count();
+
+    stats.result_count = marks.len() as i32;
+    stats.mean = mean;
+    stats.median = percentile(&marks, 50.0);
+    stats.std_dev = variance.sqrt();
+    stats.highest = marks[marks.len() - 1];
+    stats.lowest = marks[0];
+    stats.percentile_25 = percentile(&marks, 25.0);
+    stats.percentile_75 = percentile(&marks, 75.0);
+    stats.grade_distribution = grade_distribution(results);
+    stats.pass_rate = passed as f64 / count;
+    stats
+}
+
+// Only what identifies no one: the count, for an exam with fewer results
+// than `min_cohort_size`, where a mean or grade breakdown could single out
+// students. Empty exams have nothing to withhold.
+pub fn withhold_small_cohort(stats: ExamStatistics, min_cohort_size: u32) -> ExamStatistics {
+    let count = stats.result_count as u32;
+    if count == 0 || count >= min_cohort_size {
+        return stats;
+    }
+
+    ExamStatistics {
+        exam_id: stats.exam_id,
+        total_marks: stats.total_marks,
+        result_count: stats.result_count,
+        withheld: true,
+        ..Default::default()
+    }
+}
+
+// Percentile of sorted, non-empty `marks`, interpolating linearly between ranks.
+fn percentile(marks: &[i32], p: f64) -> f64 {
+    let rank = p / 100.0 * (marks.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    let weight = rank - lower as f64;
+
+    marks[lower] as f64 * (1.0 - weight) + marks[upper] as f64 * weight
+}
+
+// Number of results per stored grade, best grade first.
+fn grade_distribution(results: &[ExamResult]) -> Vec<GradeCount> {
+    let mut counts: HashMap<&str, i32> = HashMap::new();
+
+    for result in results {
+        *counts.entry(result.grade.as_str()).or_default() += 1;
+    }
+
+    let mut distribution: Vec<GradeCount> = counts
+        .into_iter()
+        .map(|(grade, count)| GradeCount {
+            grade: grade.to_string(),
+            count,
+        })
+        .collect();
+
+    distribution.sort_by_key(|entry| std::cmp::Reverse(grade_rank(&entry.grade)));
+    distribution
+}
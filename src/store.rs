@@ -0,0 +1,802 @@
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::mem;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use tonic::Status;
+
+use crate::appeal::{Appeal, AppealState};
+use crate::exam_admin::Exam;
+use crate::exam_service::{AuditRecord, DeletedResult, ExamResult, ResultStatus};
+use crate::grader::{Allocation, ExamGraders};
+use crate::key::{ExamId, ResultKey, StudentId};
+use crate::model::timestamp_from_date;
+use crate::student::Student;
+use crate::webhook::Webhook;
+
+mod cached;
+mod replicated;
+mod search;
+mod sharded;
+mod sqlite;
+mod wal;
+
+pub use cached::CachedExamStore;
+pub use replicated::{ReplicatedExamStore, ReplicationLog};
+pub use sqlite::SqliteExamStore;
+pub use wal::WalExamStore;
+
+pub(crate) use search::terms;
+
+use search::SearchIndex;
+use sharded::{in_key_order, replace_all, ShardedResults};
+
+// Failure reported by a storage backend.
+#[derive(Debug)]
+pub struct StoreError(pub String);
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "storage error: {}", self.0)
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+impl From<StoreError> for Status {
+    fn from(err: StoreError) -> Self {
+        Status::internal(err.to_string())
+    }
+}
+
+// Outcome of `ExamStore::put_if_version`. Matched on as soon as it is
+// returned, so the result is not boxed to make the conflict smaller.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug)]
+pub enum VersionedPut {
+    // Stored, replacing the returned previous result
+    Written(ExamResult),
+    // Not stored: the result is at another version, or None if it does not exist
+    Conflict(Option<i64>),
+}
+
+// Outcome of `ExamStore::undelete`, unboxed like VersionedPut.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug)]
+pub enum Undelete {
+    // Stored again at its next version, as returned
+    Restored(ExamResult),
+    // Not stored: no deleted result has the key
+    NotDeleted,
+    // Not stored: another result was stored under the key since the deletion
+    Occupied,
+}
+
+// One result matching a search, with how well it matched: higher is better.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    pub result: ExamResult,
+    pub score: u32,
+}
+
+// Everything one store holds, as written to and read from snapshot files.
+// Each list is in the order the store returns it: results and deleted results
+// by key, exams and students by ID, audit records by sequence, appeals and
+// webhooks by ID, graders by exam ID and allocations by key.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StoreContents {
+    pub results: Vec<ExamResult>,
+    pub deleted: Vec<DeletedResult>,
+    pub exams: Vec<Exam>,
+    pub students: Vec<Student>,
+    pub audit: Vec<AuditRecord>,
+    pub appeals: Vec<Appeal>,
+    pub webhooks: Vec<Webhook>,
+    pub graders: Vec<ExamGraders>,
+    pub allocations: Vec<Allocation>,
+}
+
+// The version a write stores: 1 for a new result, else one past the previous.
+pub fn next_version(previous: Option<&ExamResult>) -> i64 {
+    previous.map_or(1, |previous| previous.version + 1)
+}
+
+// What a write stored under `key`: keyed by it, whatever IDs the result carries, at `version`.
+fn stored_result(key: &ResultKey, result: ExamResult, version: i64) -> ExamResult {
+    ExamResult {
+        student_id: key.student_id.to_string(),
+        exam_id: key.exam_id.to_string(),
+        version,
+        ..result
+    }
+}
+
+// Storage backend for exam results, keyed by `ResultKey`, the exam catalog,
+// keyed by `ExamId`, and the student registry, keyed by `StudentId`.
+// The gRPC layer only talks to this trait, so backends can be swapped freely.
+#[tonic::async_trait]
+pub trait ExamStore: Send + Sync + 'static {
+    // Looks up a single result.
+    async fn get(&self, key: &ResultKey) -> Result<Option<ExamResult>, StoreError>;
+
+    // Inserts or replaces a result, returning the previous value if any.
+    // The stored version is set by `next_version`, whatever `result` carries.
+    async fn put(&self, key: ResultKey, result: ExamResult) -> Result<Option<ExamResult>, StoreError>;
+
+    // Replaces a result only if its stored version is `expected`, atomically
+    // with the check. Used for optimistic concurrency control.
+    async fn put_if_version(
+        &self,
+        key: ResultKey,
+        expected: i64,
+        result: ExamResult,
+    ) -> Result<VersionedPut, StoreError>;
+
+    // Inserts or replaces a result at the version it carries, returning the
+    // previous value if any. Used to apply writes replicated from another
+    // server, whose versions are kept as that server numbered them.
+    async fn put_at_version(&self, key: ResultKey, result: ExamResult) -> Result<Option<ExamResult>, StoreError>;
+
+    // Returns every stored result.
+    async fn list(&self) -> Result<Vec<ExamResult>, StoreError>;
+
+    // Returns up to `limit` results with keys strictly after `after`, in key order.
+    // Used for cursor-based pagination without loading the whole store.
+    async fn list_page(&self, after: Option<&ResultKey>, limit: usize) -> Result<Vec<ExamResult>, StoreError>;
+
+    // Returns every result for one student, ordered by exam ID.
+    async fn list_for_student(&self, student_id: &StudentId) -> Result<Vec<ExamResult>, StoreError>;
+
+    // Moves a result to the deleted results, stamped with `deleted_at_ms`,
+    // returning it if it existed. Deleted results are out of reach of every
+    // other read; one deleted before under the same key is replaced.
+    async fn delete(&self, key: &ResultKey, deleted_at_ms: i64) -> Result<Option<ExamResult>, StoreError>;
+
+    // Returns the deleted results, optionally narrowed to one student and/or exam, in key order.
+    async fn list_deleted(
+        &self,
+        student_id: Option<&StudentId>,
+        exam_id: Option<&ExamId>,
+    ) -> Result<Vec<DeletedResult>, StoreError>;
+
+    // Moves a deleted result back among the results, at its next version,
+    // unless another was stored under its key since.
+    async fn undelete(&self, key: &ResultKey) -> Result<Undelete, StoreError>;
+
+    // Removes for good the results deleted before `before_ms`, returning them.
+    async fn purge_deleted(&self, before_ms: i64) -> Result<Vec<DeletedResult>, StoreError>;
+
+    // Returns the results whose student name and subject match every word of
+    // `query`, case-insensitively, best match first. Stores without an index
+    // of their own build one from every result on each call.
+    async fn search(&self, query: &str) -> Result<Vec<SearchHit>, StoreError> {
+        let results: BTreeMap<_, _> = self.list().await?.into_iter().map(|result| (ResultKey::from(&result), result)).collect();
+        let index = SearchIndex::new(results.values());
+        Ok(index
+            .search(query)
+            .into_iter()
+            .filter_map(|(key, score)| results.get(&key).map(|result| SearchHit { result: result.clone(), score }))
+            .collect())
+    }
+
+    // Looks up an exam in the catalog.
+    async fn get_exam(&self, id: &ExamId) -> Result<Option<Exam>, StoreError>;
+
+    // Adds an exam to the catalog. Returns false, leaving the catalog unchanged,
+    // if an exam with the same ID already exists.
+    async fn create_exam(&self, id: ExamId, exam: Exam) -> Result<bool, StoreError>;
+
+    // Replaces an existing exam, returning the previous definition.
+    // Returns None, leaving the catalog unchanged, if the exam does not exist.
+    async fn update_exam(&self, id: ExamId, exam: Exam) -> Result<Option<Exam>, StoreError>;
+
+    // Returns every exam in the catalog, ordered by ID.
+    async fn list_exams(&self) -> Result<Vec<Exam>, StoreError>;
+
+    // Looks up a registered student.
+    async fn get_student(&self, id: &StudentId) -> Result<Option<Student>, StoreError>;
+
+    // Registers a student. Returns false, leaving the registry unchanged,
+    // if a student with the same ID is already registered.
+    async fn create_student(&self, id: StudentId, student: Student) -> Result<bool, StoreError>;
+
+    // Returns every registered student, ordered by ID.
+    async fn list_students(&self) -> Result<Vec<Student>, StoreError>;
+
+    // Appends a record to the audit log, assigning the next sequence number.
+    // Records are never modified or removed once appended.
+    async fn append_audit(&self, record: AuditRecord) -> Result<AuditRecord, StoreError>;
+
+    // Returns the audit records for one student, optionally narrowed to one exam, oldest first.
+    async fn audit_trail(
+        &self,
+        student_id: &StudentId,
+        exam_id: Option<&ExamId>,
+    ) -> Result<Vec<AuditRecord>, StoreError>;
+
+    // Files an appeal, assigning the next appeal ID. Returns None, leaving the
+    // appeals unchanged, if the same result already has an unresolved appeal.
+    async fn create_appeal(&self, appeal: Appeal) -> Result<Option<Appeal>, StoreError>;
+
+    // Looks up an appeal by ID.
+    async fn get_appeal(&self, id: i64) -> Result<Option<Appeal>, StoreError>;
+
+    // Returns the appeals, optionally narrowed to one student and/or exam, oldest first.
+    async fn list_appeals(
+        &self,
+        student_id: Option<&StudentId>,
+        exam_id: Option<&ExamId>,
+    ) -> Result<Vec<Appeal>, StoreError>;
+
+    // Replaces an appeal only if its stored state is `expected`, atomically
+    // with the check. Returns false, leaving it unchanged, otherwise.
+    async fn update_appeal(&self, appeal: Appeal, expected: AppealState) -> Result<bool, StoreError>;
+
+    // Registers a webhook, assigning the next webhook ID. IDs are never reused.
+    async fn create_webhook(&self, webhook: Webhook) -> Result<Webhook, StoreError>;
+
+    // Returns every registered webhook, ordered by ID.
+    async fn list_webhooks(&self) -> Result<Vec<Webhook>, StoreError>;
+
+    // Removes a webhook, returning false if it did not exist.
+    async fn delete_webhook(&self, id: i64) -> Result<bool, StoreError>;
+
+    // Replaces the graders assigned to an exam; an empty list removes them.
+    async fn set_graders(&self, graders: ExamGraders) -> Result<(), StoreError>;
+
+    // Returns the graders assigned to an exam, in assignment order.
+    async fn get_graders(&self, exam_id: &ExamId) -> Result<Vec<String>, StoreError>;
+
+    // Stores allocations, each replacing any earlier one of the same result.
+    async fn put_allocations(&self, allocations: Vec<Allocation>) -> Result<(), StoreError>;
+
+    // Returns the allocations, optionally narrowed to one exam and/or grader, in key order.
+    async fn list_allocations(
+        &self,
+        exam_id: Option<&ExamId>,
+        grader: Option<&str>,
+    ) -> Result<Vec<Allocation>, StoreError>;
+
+    // Returns everything stored, consistent as of a single point in time.
+    async fn dump(&self) -> Result<StoreContents, StoreError>;
+
+    // Replaces everything stored with `contents`, keeping their versions,
+    // sequence numbers and IDs. Readers see either the old contents or the new.
+    async fn restore(&self, contents: StoreContents) -> Result<(), StoreError>;
+
+    // Persists any buffered writes. Called once during graceful shutdown.
+    async fn flush(&self) -> Result<(), StoreError> {
+        Ok(())
+    }
+
+    // Drops cached entries that have expired. Called periodically by the
+    // cache eviction job; stores without a cache have nothing to do.
+    async fn evict_expired(&self) -> Result<(), StoreError> {
+        Ok(())
+    }
+}
+
+// In-memory store. Results are sharded by student, each shard a BTreeMap in
+// key order behind its own RwLock, so bulk writes to many students run side
+// by side and readers of one student only wait for writers of its shard.
+// The smaller tables each sit behind a single Arc<RwLock<>>. The search
+// index is only changed with the shard of the result written held, so it
+// always agrees with the shards. Deleted results are locked after the shard
+// they move from or to.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryExamStore {
+    data: ShardedResults,
+    index: Arc<Mutex<SearchIndex>>,
+    deleted: Arc<RwLock<BTreeMap<ResultKey, DeletedResult>>>,
+    exams: Arc<RwLock<BTreeMap<ExamId, Exam>>>,
+    students: Arc<RwLock<BTreeMap<StudentId, Student>>>,
+    // Append-only, in sequence order
+    audit: Arc<RwLock<Vec<AuditRecord>>>,
+    // In ID order; appeal N is at index N - 1
+    appeals: Arc<RwLock<Vec<Appeal>>>,
+    webhooks: Arc<RwLock<BTreeMap<i64, Webhook>>>,
+    last_webhook_id: Arc<AtomicI64>,
+    graders: Arc<RwLock<BTreeMap<ExamId, ExamGraders>>>,
+    allocations: Arc<RwLock<BTreeMap<ResultKey, Allocation>>>,
+}
+
+impl InMemoryExamStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn index(&self) -> MutexGuard<'_, SearchIndex> {
+        self.index.lock().expect("search index lock")
+    }
+
+    // Constructs a store pre-populated with sample exam data.
+    pub fn with_sample_data() -> Self {
+        let mut exams = BTreeMap::new();
+
+        for exam in sample_exams() {
+            exams.insert(ExamId::from(&exam), exam);
+        }
+
+        let mut students = BTreeMap::new();
+
+        for student in sample_students() {
+            students.insert(StudentId::from(&student), student);
+        }
+
+        let results = sample_results();
+
+        Self {
+            index: Arc::new(Mutex::new(SearchIndex::new(&results))),
+            data: ShardedResults::new(results),
+            deleted: Arc::default(),
+            exams: Arc::new(RwLock::new(exams)),
+            students: Arc::new(RwLock::new(students)),
+            audit: Arc::default(),
+            appeals: Arc::default(),
+            webhooks: Arc::default(),
+            last_webhook_id: Arc::default(),
+            graders: Arc::default(),
+            allocations: Arc::default(),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl ExamStore for InMemoryExamStore {
+    async fn get(&self, key: &ResultKey) -> Result<Option<ExamResult>, StoreError> {
+        Ok(self.data.shard(&key.student_id).read().await.get(key).cloned())
+    }
+
+    async fn put(&self, key: ResultKey, mut result: ExamResult) -> Result<Option<ExamResult>, StoreError> {
+        let mut data = self.data.shard(&key.student_id).write().await;
+        result.version = next_version(data.get(&key));
+        self.index().insert(key.clone(), &result);
+        Ok(data.insert(key, result))
+    }
+
+    async fn put_if_version(
+        &self,
+        key: ResultKey,
+        expected: i64,
+        mut result: ExamResult,
+    ) -> Result<VersionedPut, StoreError> {
+        let mut data = self.data.shard(&key.student_id).write().await;
+
+        match data.get_mut(&key) {
+            Some(current) if current.version == expected => {
+                result.version = next_version(Some(current));
+                self.index().insert(key, &result);
+                Ok(VersionedPut::Written(std::mem::replace(current, result)))
+            }
+            current => Ok(VersionedPut::Conflict(current.map(|current| current.version))),
+        }
+    }
+
+    async fn put_at_version(&self, key: ResultKey, result: ExamResult) -> Result<Option<ExamResult>, StoreError> {
+        let version = result.version;
+        let result = stored_result(&key, result, version);
+        let mut data = self.data.shard(&key.student_id).write().await;
+        self.index().insert(key.clone(), &result);
+        Ok(data.insert(key, result))
+    }
+
+    async fn list(&self) -> Result<Vec<ExamResult>, StoreError> {
+        Ok(in_key_order(&self.data.read_all().await))
+    }
+
+    async fn list_page(&self, after: Option<&ResultKey>, limit: usize) -> Result<Vec<ExamResult>, StoreError> {
+        Ok(self.data.page(after, limit).await)
+    }
+
+    // Keys order by student first, so one student's results are a contiguous range of one shard.
+    async fn list_for_student(&self, student_id: &StudentId) -> Result<Vec<ExamResult>, StoreError> {
+        let data = self.data.shard(student_id).read().await;
+        Ok(data
+            .range(ResultKey::first_for_student(student_id.clone())..)
+            .take_while(|(key, _)| &key.student_id == student_id)
+            .map(|(_, result)| result.clone())
+            .collect())
+    }
+
+    async fn delete(&self, key: &ResultKey, deleted_at_ms: i64) -> Result<Option<ExamResult>, StoreError> {
+        let mut data = self.data.shard(&key.student_id).write().await;
+        let Some(result) = data.remove(key) else {
+            return Ok(None);
+        };
+        self.index().remove(key);
+
+        let deleted = DeletedResult {
+            result: Some(result.clone()),
+            deleted_at_ms,
+        };
+        self.deleted.write().await.insert(key.clone(), deleted);
+        Ok(Some(result))
+    }
+
+    async fn list_deleted(
+        &self,
+        student_id: Option<&StudentId>,
+        exam_id: Option<&ExamId>,
+    ) -> Result<Vec<DeletedResult>, StoreError> {
+        Ok(self
+            .deleted
+            .read()
+            .await
+            .iter()
+            .filter(|(key, _)| student_id.is_none_or(|id| &key.student_id == id))
+            .filter(|(key, _)| exam_id.is_none_or(|id| &key.exam_id == id))
+            .map(|(_, deleted)| deleted.clone())
+            .collect())
+    }
+
+    async fn undelete(&self, key: &ResultKey) -> Result<Undelete, StoreError> {
+        let mut data = self.data.shard(&key.student_id).write().await;
+        let mut deleted = self.deleted.write().await;
+
+        if !deleted.contains_key(key) {
+            return Ok(Undelete::NotDeleted);
+        }
+        if data.contains_key(key) {
+            return Ok(Undelete::Occupied);
+        }
+
+        let mut result = deleted.remove(key).and_then(|deleted| deleted.result).unwrap_or_default();
+        result.version = next_version(Some(&result));
+        self.index().insert(key.clone(), &result);
+        data.insert(key.clone(), result.clone());
+        Ok(Undelete::Restored(result))
+    }
+
+    async fn purge_deleted(&self, before_ms: i64) -> Result<Vec<DeletedResult>, StoreError> {
+        let mut deleted = self.deleted.write().await;
+        let (purged, kept) = mem::take(&mut *deleted)
+            .into_iter()
+            .partition(|(_, deleted)| deleted.deleted_at_ms < before_ms);
+        *deleted = kept;
+        Ok(purged.into_values().collect())
+    }
+
+    async fn search(&self, query: &str) -> Result<Vec<SearchHit>, StoreError> {
+        let ranked = self.index().search(query);
+
+        // A result written since is read as it is now, or skipped if deleted
+        let mut hits = Vec::with_capacity(ranked.len());
+        for (key, score) in ranked {
+            if let Some(result) = self.data.shard(&key.student_id).read().await.get(&key) {
+                hits.push(SearchHit { result: result.clone(), score });
+            }
+        }
+        Ok(hits)
+    }
+
+    async fn get_exam(&self, id: &ExamId) -> Result<Option<Exam>, StoreError> {
+        Ok(self.exams.read().await.get(id).cloned())
+    }
+
+    async fn create_exam(&self, id: ExamId, exam: Exam) -> Result<bool, StoreError> {
+        match self.exams.write().await.entry(id) {
+            Entry::Occupied(_) => Ok(false),
+            Entry::Vacant(entry) => {
+                entry.insert(exam);
+                Ok(true)
+            }
+        }
+    }
+
+    async fn update_exam(&self, id: ExamId, exam: Exam) -> Result<Option<Exam>, StoreError> {
+        Ok(self
+            .exams
+            .write()
+            .await
+            .get_mut(&id)
+            .map(|current| std::mem::replace(current, exam)))
+    }
+
+    async fn list_exams(&self) -> Result<Vec<Exam>, StoreError> {
+        Ok(self.exams.read().await.values().cloned().collect())
+    }
+
+    async fn get_student(&self, id: &StudentId) -> Result<Option<Student>, StoreError> {
+        Ok(self.students.read().await.get(id).cloned())
+    }
+
+    async fn create_student(&self, id: StudentId, student: Student) -> Result<bool, StoreError> {
+        match self.students.write().await.entry(id) {
+            Entry::Occupied(_) => Ok(false),
+            Entry::Vacant(entry) => {
+                entry.insert(student);
+                Ok(true)
+            }
+        }
+    }
+
+    async fn list_students(&self) -> Result<Vec<Student>, StoreError> {
+        Ok(self.students.read().await.values().cloned().collect())
+    }
+
+    async fn append_audit(&self, mut record: AuditRecord) -> Result<AuditRecord, StoreError> {
+        let mut audit = self.audit.write().await;
+        record.sequence = audit.len() as i64 + 1;
+        audit.push(record.clone());
+        Ok(record)
+    }
+
+    async fn audit_trail(
+        &self,
+        student_id: &StudentId,
+        exam_id: Option<&ExamId>,
+    ) -> Result<Vec<AuditRecord>, StoreError> {
+        Ok(self
+            .audit
+            .read()
+            .await
+            .iter()
+            .filter(|record| record.student_id == student_id.as_str())
+            .filter(|record| exam_id.is_none_or(|exam_id| record.exam_id == exam_id.as_str()))
+            .cloned()
+            .collect())
+    }
+
+    async fn create_appeal(&self, mut appeal: Appeal) -> Result<Option<Appeal>, StoreError> {
+        let mut appeals = self.appeals.write().await;
+        let unresolved = appeals.iter().any(|existing| {
+            existing.student_id == appeal.student_id
+                && existing.exam_id == appeal.exam_id
+                && existing.state() != AppealState::Resolved
+        });
+        if unresolved {
+            return Ok(None);
+        }
+
+        appeal.appeal_id = appeals.len() as i64 + 1;
+        appeals.push(appeal.clone());
+        Ok(Some(appeal))
+    }
+
+    async fn get_appeal(&self, id: i64) -> Result<Option<Appeal>, StoreError> {
+        let appeals = self.appeals.read().await;
+        Ok(usize::try_from(id - 1).ok().and_then(|index| appeals.get(index).cloned()))
+    }
+
+    async fn list_appeals(
+        &self,
+        student_id: Option<&StudentId>,
+        exam_id: Option<&ExamId>,
+    ) -> Result<Vec<Appeal>, StoreError> {
+        Ok(self
+            .appeals
+            .read()
+            .await
+            .iter()
+            .filter(|appeal| student_id.is_none_or(|student_id| appeal.student_id == student_id.as_str()))
+            .filter(|appeal| exam_id.is_none_or(|exam_id| appeal.exam_id == exam_id.as_str()))
+            .cloned()
+            .collect())
+    }
+
+    async fn update_appeal(&self, appeal: Appeal, expected: AppealState) -> Result<bool, StoreError> {
+        let mut appeals = self.appeals.write().await;
+        let current = usize::try_from(appeal.appeal_id - 1)
+            .ok()
+            .and_then(|index| appeals.get_mut(index));
+
+        match current {
+            Some(current) if current.state() == expected => {
+                *current = appeal;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn create_webhook(&self, mut webhook: Webhook) -> Result<Webhook, StoreError> {
+        webhook.webhook_id = self.last_webhook_id.fetch_add(1, Ordering::Relaxed) + 1;
+        self.webhooks.write().await.insert(webhook.webhook_id, webhook.clone());
+        Ok(webhook)
+    }
+
+    async fn list_webhooks(&self) -> Result<Vec<Webhook>, StoreError> {
+        Ok(self.webhooks.read().await.values().cloned().collect())
+    }
+
+    async fn delete_webhook(&self, id: i64) -> Result<bool, StoreError> {
+        Ok(self.webhooks.write().await.remove(&id).is_some())
+    }
+
+    async fn set_graders(&self, graders: ExamGraders) -> Result<(), StoreError> {
+        let mut assigned = self.graders.write().await;
+        if graders.graders.is_empty() {
+            assigned.remove(&ExamId::from(&graders));
+        } else {
+            assigned.insert(ExamId::from(&graders), graders);
+        }
+        Ok(())
+    }
+
+    async fn get_graders(&self, exam_id: &ExamId) -> Result<Vec<String>, StoreError> {
+        Ok(self
+            .graders
+            .read()
+            .await
+            .get(exam_id)
+            .map(|graders| graders.graders.clone())
+            .unwrap_or_default())
+    }
+
+    async fn put_allocations(&self, allocations: Vec<Allocation>) -> Result<(), StoreError> {
+        let mut stored = self.allocations.write().await;
+        for allocation in allocations {
+            stored.insert(ResultKey::from(&allocation), allocation);
+        }
+        Ok(())
+    }
+
+    async fn list_allocations(
+        &self,
+        exam_id: Option<&ExamId>,
+        grader: Option<&str>,
+    ) -> Result<Vec<Allocation>, StoreError> {
+        Ok(self
+            .allocations
+            .read()
+            .await
+            .iter()
+            .filter(|(key, _)| exam_id.is_none_or(|id| &key.exam_id == id))
+            .filter(|(_, allocation)| grader.is_none_or(|grader| allocation.grader == grader))
+            .map(|(_, allocation)| allocation.clone())
+            .collect())
+    }
+
+    // Every lock is taken, always in field order and the shards first, so no
+    // write lands halfway through.
+    async fn dump(&self) -> Result<StoreContents, StoreError> {
+        let data = self.data.read_all().await;
+        let deleted = self.deleted.read().await;
+        let exams = self.exams.read().await;
+        let students = self.students.read().await;
+        let audit = self.audit.read().await;
+        let appeals = self.appeals.read().await;
+        let webhooks = self.webhooks.read().await;
+        let graders = self.graders.read().await;
+        let allocations = self.allocations.read().await;
+
+        Ok(StoreContents {
+            results: in_key_order(&data),
+            deleted: deleted.values().cloned().collect(),
+            exams: exams.values().cloned().collect(),
+            students: students.values().cloned().collect(),
+            audit: audit.clone(),
+            appeals: appeals.clone(),
+            webhooks: webhooks.values().cloned().collect(),
+            graders: graders.values().cloned().collect(),
+            allocations: allocations.values().cloned().collect(),
+        })
+    }
+
+    async fn restore(&self, contents: StoreContents) -> Result<(), StoreError> {
+        // Sequence numbers and appeal IDs double as positions here
+        if !numbered_from_one(contents.audit.iter().map(|record| record.sequence)) {
+            return Err(StoreError("audit sequence numbers must run from 1 without gaps".into()));
+        }
+        if !numbered_from_one(contents.appeals.iter().map(|appeal| appeal.appeal_id)) {
+            return Err(StoreError("appeal IDs must run from 1 without gaps".into()));
+        }
+        if contents.deleted.iter().any(|deleted| deleted.result.is_none()) {
+            return Err(StoreError("deleted results must carry the result deleted".into()));
+        }
+
+        let mut data = self.data.write_all().await;
+        let mut deleted = self.deleted.write().await;
+        let mut exams = self.exams.write().await;
+        let mut students = self.students.write().await;
+        let mut audit = self.audit.write().await;
+        let mut appeals = self.appeals.write().await;
+        let mut webhooks = self.webhooks.write().await;
+        let mut graders = self.graders.write().await;
+        let mut allocations = self.allocations.write().await;
+
+        *self.index() = SearchIndex::new(&contents.results);
+        replace_all(&mut data, contents.results);
+        *deleted = contents
+            .deleted
+            .into_iter()
+            .filter_map(|deleted| Some((ResultKey::from(deleted.result.as_ref()?), deleted)))
+            .collect();
+        *exams = contents.exams.into_iter().map(|exam| (ExamId::from(&exam), exam)).collect();
+        *students = contents.students.into_iter().map(|student| (StudentId::from(&student), student)).collect();
+        *audit = contents.audit;
+        *appeals = contents.appeals;
+        *webhooks = contents.webhooks.into_iter().map(|webhook| (webhook.webhook_id, webhook)).collect();
+        *graders = contents.graders.into_iter().map(|graders| (ExamId::from(&graders), graders)).collect();
+        *allocations = contents
+            .allocations
+            .into_iter()
+            .map(|allocation| (ResultKey::from(&allocation), allocation))
+            .collect();
+
+        // Never below what was already handed out, so IDs are not reused
+        let restored = webhooks.keys().next_back().copied().unwrap_or(0);
+        self.last_webhook_id.fetch_max(restored, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+fn numbered_from_one(numbers: impl Iterator<Item = i64>) -> bool {
+    (1..).zip(numbers).all(|(expected, number)| number == expected)
+}
+
+// Sample exam data the server starts with.
+pub fn sample_results() -> Vec<ExamResult> {
+    vec![
+        ExamResult {
+            student_id: "123".to_string(),
+            exam_id: "math101".to_string(),
+            student_name: "John Doe".to_string(),
+            subject: "Math 101".to_string(),
+            marks_obtained: 95,
+            total_marks: 100,
+            grade: "A+".to_string(),
+            internal_comment: "Moderated by second marker".to_string(),
+            questions: Vec::new(),
+            version: 1,
+            status: ResultStatus::Published as i32,
+            exam_date: timestamp_from_date("2024-06-03"),
+            graded_at: None,
+        },
+        ExamResult {
+            student_id: "456".to_string(),
+            exam_id: "phy101".to_string(),
+            student_name: "Jane Smith".to_string(),
+            subject: "Physics 101".to_string(),
+            marks_obtained: 88,
+            total_marks: 100,
+            grade: "A".to_string(),
+            internal_comment: "Late submission penalty waived".to_string(),
+            questions: Vec::new(),
+            version: 1,
+            status: ResultStatus::Published as i32,
+            exam_date: timestamp_from_date("2024-06-05"),
+            graded_at: None,
+        },
+    ]
+}
+
+// Sample exam catalog matching the sample results.
+pub fn sample_exams() -> Vec<Exam> {
+    vec![
+        Exam {
+            exam_id: "math101".to_string(),
+            subject: "Math 101".to_string(),
+            total_marks: 100,
+            date: "2024-06-03".to_string(),
+            ..Default::default()
+        },
+        Exam {
+            exam_id: "phy101".to_string(),
+            subject: "Physics 101".to_string(),
+            total_marks: 100,
+            date: "2024-06-05".to_string(),
+            ..Default::default()
+        },
+    ]
+}
+
+// Sample student registry matching the sample results.
+pub fn sample_students() -> Vec<Student> {
+    vec![
+        Student {
+            student_id: "123".to_string(),
+            name: "John Doe".to_string(),
+            email: "john.doe@example.edu".to_string(),
+        },
+        Student {
+            student_id: "456".to_string(),
+            name: "Jane Smith".to_string(),
+            email: "jane.smith@example.edu".to_string(),
+        },
+    ]
+}
@@ -0,0 +1,243 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use moka::future::Cache;
+
+use super::{ExamStore, SearchHit, StoreContents, StoreError, Undelete, VersionedPut};
+use crate::appeal::{Appeal, AppealState};
+use crate::config::CacheConfig;
+use crate::exam_admin::Exam;
+use crate::exam_service::{AuditRecord, DeletedResult, ExamResult};
+use crate::grader::{Allocation, ExamGraders};
+use crate::key::{ExamId, ResultKey, StudentId};
+use crate::student::Student;
+use crate::webhook::Webhook;
+
+// Read-through cache of single results in front of another store. Results
+// read by `get` are kept for the configured TTL; every result write made
+// through this store evicts the key. Writes made elsewhere (another replica,
+// or straight to the database) are only seen once the entry expires.
+pub struct CachedExamStore<S> {
+    inner: S,
+    results: Cache<ResultKey, ExamResult>,
+    // Bumped by every result write, so a read that raced a write does not
+    // cache what it read: it may predate the write
+    writes: AtomicU64,
+}
+
+impl<S: ExamStore> CachedExamStore<S> {
+    pub fn new(inner: S, config: &CacheConfig) -> Self {
+        Self {
+            inner,
+            results: Cache::builder()
+                .max_capacity(config.max_entries)
+                .time_to_live(config.ttl())
+                .build(),
+            writes: AtomicU64::new(0),
+        }
+    }
+
+    async fn invalidate(&self, key: &ResultKey) {
+        self.writes.fetch_add(1, Ordering::AcqRel);
+        self.results.invalidate(key).await;
+    }
+}
+
+#[tonic::async_trait]
+impl<S: ExamStore> ExamStore for CachedExamStore<S> {
+    async fn get(&self, key: &ResultKey) -> Result<Option<ExamResult>, StoreError> {
+        if let Some(result) = self.results.get(key).await {
+            return Ok(Some(result));
+        }
+
+        let writes = self.writes.load(Ordering::Acquire);
+        let result = self.inner.get(key).await?;
+
+        // Missing results are not cached, so a first submission is seen at once
+        if let Some(result) = &result
+            && self.writes.load(Ordering::Acquire) == writes
+        {
+            self.results.insert(key.clone(), result.clone()).await;
+        }
+
+        Ok(result)
+    }
+
+    async fn put(&self, key: ResultKey, result: ExamResult) -> Result<Option<ExamResult>, StoreError> {
+        let previous = self.inner.put(key.clone(), result).await;
+        self.invalidate(&key).await;
+        previous
+    }
+
+    async fn put_if_version(
+        &self,
+        key: ResultKey,
+        expected: i64,
+        result: ExamResult,
+    ) -> Result<VersionedPut, StoreError> {
+        let outcome = self.inner.put_if_version(key.clone(), expected, result).await;
+        // Also on a conflict: the cached copy may be what the caller saw as current
+        self.invalidate(&key).await;
+        outcome
+    }
+
+    async fn put_at_version(&self, key: ResultKey, result: ExamResult) -> Result<Option<ExamResult>, StoreError> {
+        let previous = self.inner.put_at_version(key.clone(), result).await;
+        self.invalidate(&key).await;
+        previous
+    }
+
+    async fn list(&self) -> Result<Vec<ExamResult>, StoreError> {
+        self.inner.list().await
+    }
+
+    // Not cached: matches change with every write to a name or subject
+    async fn search(&self, query: &str) -> Result<Vec<SearchHit>, StoreError> {
+        self.inner.search(query).await
+    }
+
+    async fn list_page(&self, after: Option<&ResultKey>, limit: usize) -> Result<Vec<ExamResult>, StoreError> {
+        self.inner.list_page(after, limit).await
+    }
+
+    async fn list_for_student(&self, student_id: &StudentId) -> Result<Vec<ExamResult>, StoreError> {
+        self.inner.list_for_student(student_id).await
+    }
+
+    async fn delete(&self, key: &ResultKey, deleted_at_ms: i64) -> Result<Option<ExamResult>, StoreError> {
+        let removed = self.inner.delete(key, deleted_at_ms).await;
+        self.invalidate(key).await;
+        removed
+    }
+
+    async fn list_deleted(
+        &self,
+        student_id: Option<&StudentId>,
+        exam_id: Option<&ExamId>,
+    ) -> Result<Vec<DeletedResult>, StoreError> {
+        self.inner.list_deleted(student_id, exam_id).await
+    }
+
+    async fn undelete(&self, key: &ResultKey) -> Result<Undelete, StoreError> {
+        let outcome = self.inner.undelete(key).await;
+        self.invalidate(key).await;
+        outcome
+    }
+
+    // Deleted results are never cached
+    async fn purge_deleted(&self, before_ms: i64) -> Result<Vec<DeletedResult>, StoreError> {
+        self.inner.purge_deleted(before_ms).await
+    }
+
+    async fn get_exam(&self, id: &ExamId) -> Result<Option<Exam>, StoreError> {
+        self.inner.get_exam(id).await
+    }
+
+    async fn create_exam(&self, id: ExamId, exam: Exam) -> Result<bool, StoreError> {
+        self.inner.create_exam(id, exam).await
+    }
+
+    async fn update_exam(&self, id: ExamId, exam: Exam) -> Result<Option<Exam>, StoreError> {
+        self.inner.update_exam(id, exam).await
+    }
+
+    async fn list_exams(&self) -> Result<Vec<Exam>, StoreError> {
+        self.inner.list_exams().await
+    }
+
+    async fn get_student(&self, id: &StudentId) -> Result<Option<Student>, StoreError> {
+        self.inner.get_student(id).await
+    }
+
+    async fn create_student(&self, id: StudentId, student: Student) -> Result<bool, StoreError> {
+        self.inner.create_student(id, student).await
+    }
+
+    async fn list_students(&self) -> Result<Vec<Student>, StoreError> {
+        self.inner.list_students().await
+    }
+
+    async fn append_audit(&self, record: AuditRecord) -> Result<AuditRecord, StoreError> {
+        self.inner.append_audit(record).await
+    }
+
+    async fn audit_trail(
+        &self,
+        student_id: &StudentId,
+        exam_id: Option<&ExamId>,
+    ) -> Result<Vec<AuditRecord>, StoreError> {
+        self.inner.audit_trail(student_id, exam_id).await
+    }
+
+    async fn create_appeal(&self, appeal: Appeal) -> Result<Option<Appeal>, StoreError> {
+        self.inner.create_appeal(appeal).await
+    }
+
+    async fn get_appeal(&self, id: i64) -> Result<Option<Appeal>, StoreError> {
+        self.inner.get_appeal(id).await
+    }
+
+    async fn list_appeals(
+        &self,
+        student_id: Option<&StudentId>,
+        exam_id: Option<&ExamId>,
+    ) -> Result<Vec<Appeal>, StoreError> {
+        self.inner.list_appeals(student_id, exam_id).await
+    }
+
+    async fn update_appeal(&self, appeal: Appeal, expected: AppealState) -> Result<bool, StoreError> {
+        self.inner.update_appeal(appeal, expected).await
+    }
+
+    async fn create_webhook(&self, webhook: Webhook) -> Result<Webhook, StoreError> {
+        self.inner.create_webhook(webhook).await
+    }
+
+    async fn list_webhooks(&self) -> Result<Vec<Webhook>, StoreError> {
+        self.inner.list_webhooks().await
+    }
+
+    async fn delete_webhook(&self, id: i64) -> Result<bool, StoreError> {
+        self.inner.delete_webhook(id).await
+    }
+
+    async fn set_graders(&self, graders: ExamGraders) -> Result<(), StoreError> {
+        self.inner.set_graders(graders).await
+    }
+
+    async fn get_graders(&self, exam_id: &ExamId) -> Result<Vec<String>, StoreError> {
+        self.inner.get_graders(exam_id).await
+    }
+
+    async fn put_allocations(&self, allocations: Vec<Allocation>) -> Result<(), StoreError> {
+        self.inner.put_allocations(allocations).await
+    }
+
+    async fn list_allocations(
+        &self,
+        exam_id: Option<&ExamId>,
+        grader: Option<&str>,
+    ) -> Result<Vec<Allocation>, StoreError> {
+        self.inner.list_allocations(exam_id, grader).await
+    }
+
+    async fn dump(&self) -> Result<StoreContents, StoreError> {
+        self.inner.dump().await
+    }
+
+    async fn restore(&self, contents: StoreContents) -> Result<(), StoreError> {
+        let restored = self.inner.restore(contents).await;
+        self.writes.fetch_add(1, Ordering::AcqRel);
+        self.results.invalidate_all();
+        restored
+    }
+
+    async fn flush(&self) -> Result<(), StoreError> {
+        self.inner.flush().await
+    }
+
+    // moka only evicts expired entries as other operations touch the cache;
+    // this frees their memory even when the cache sits idle
+    async fn evict_expired(&self) -> Result<(), StoreError> {
+        self.results.run_pending_tasks().await;
+        self.inner.evict_expired().await
+    }
+}
@@ -0,0 +1,798 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex as StdMutex, Weak};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{watch, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use tracing::debug;
+
+use super::{next_version, stored_result, ExamStore, SearchHit, StoreContents, StoreError, Undelete, VersionedPut};
+use crate::appeal::{Appeal, AppealState};
+use crate::exam_admin::Exam;
+use crate::exam_service::{AuditRecord, DeletedResult, ExamResult};
+use crate::exam_sync::SyncEvent;
+use crate::grader::{Allocation, ExamGraders};
+use crate::key::{ExamId, InstitutionId, ResultKey, StudentId};
+use crate::locks::{KeyLock, KeyLocks};
+use crate::schedule::now_ms;
+use crate::student::Student;
+use crate::webhook::Webhook;
+
+// One write as sent to peers: its effect, as stored, like a write-ahead log
+// entry. Appeals and webhooks are left out, since each server numbers its own.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Mutation {
+    PutResult(ExamResult),
+    DeleteResult(DeletedResult),
+    PurgeDeleted { before_ms: i64 },
+    PutExam(Exam),
+    PutStudent(Student),
+    // Boxed: records carry the result before and after
+    AppendAudit(Box<AuditRecord>),
+    SetGraders(ExamGraders),
+    PutAllocations(Vec<Allocation>),
+    // The store replaced with a backup
+    Restore(Box<StoreContents>),
+    // Everything stored, sent to a peer that cannot be sent the writes it missed
+    Snapshot(Box<StoreContents>),
+}
+
+// Something a write can conflict over.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Item {
+    Result(ResultKey),
+    Exam(ExamId),
+    Graders(ExamId),
+    Allocation(ResultKey),
+}
+
+// When and where an item was last written. Compared field by field, so the
+// later write wins, then the one at the higher version, then the one made
+// on the server whose instance ID sorts last.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Stamp {
+    at_ms: i64,
+    version: i64,
+    origin: String,
+}
+
+// The stamps of the latest writes to this store, as many as the log buffers.
+// Older ones are dropped: a peer write to an item no longer stamped is taken
+// as if the item had not been written here.
+#[derive(Debug, Default)]
+struct Stamps {
+    capacity: usize,
+    // Each stamp with the count it was set at
+    latest: HashMap<Item, (Stamp, u64)>,
+    // The items stamped, oldest first, with the count each was stamped at
+    order: VecDeque<(Item, u64)>,
+    count: u64,
+}
+
+impl Stamps {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            ..Self::default()
+        }
+    }
+
+    fn get(&self, item: &Item) -> Option<&Stamp> {
+        self.latest.get(item).map(|(stamp, _)| stamp)
+    }
+
+    fn insert(&mut self, item: Item, stamp: Stamp) {
+        self.count += 1;
+        self.latest.insert(item.clone(), (stamp, self.count));
+        self.order.push_back((item, self.count));
+        while self.order.len() > self.capacity {
+            let Some((item, count)) = self.order.pop_front() else { break };
+            // Left alone if stamped again since
+            if self.latest.get(&item).is_some_and(|(_, latest)| *latest == count) {
+                self.latest.remove(&item);
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.latest.clear();
+        self.order.clear();
+    }
+}
+
+impl Mutation {
+    // The items a mutation writes, each with the version it leaves them at.
+    // A deletion counts as one version past the result deleted, so it wins
+    // over a write of that result made in the same millisecond.
+    fn items(&self) -> Vec<(Item, i64)> {
+        match self {
+            Mutation::PutResult(result) => vec![(Item::Result(ResultKey::from(result)), result.version)],
+            Mutation::DeleteResult(deleted) => deleted
+                .result
+                .iter()
+                .map(|result| (Item::Result(ResultKey::from(result)), result.version + 1))
+                .collect(),
+            Mutation::PutExam(exam) => vec![(Item::Exam(ExamId::from(exam)), 0)],
+            Mutation::SetGraders(graders) => vec![(Item::Graders(ExamId::from(graders)), 0)],
+            Mutation::PutAllocations(allocations) => allocations
+                .iter()
+                .map(|allocation| (Item::Allocation(ResultKey::from(allocation)), 0))
+                .collect(),
+            Mutation::PurgeDeleted { .. }
+            | Mutation::PutStudent(_)
+            | Mutation::AppendAudit(_)
+            | Mutation::Restore(_)
+            | Mutation::Snapshot(_) => Vec::new(),
+        }
+    }
+}
+
+// A store whose writes the replication log can apply and dump, whatever its backend.
+#[tonic::async_trait]
+trait Replica: Send + Sync {
+    async fn apply(&self, event: &SyncEvent, mutation: Mutation) -> Result<(), StoreError>;
+
+    async fn dump(&self) -> Result<StoreContents, StoreError>;
+}
+
+// The writes made on this server, numbered in the order they were applied,
+// shared by the replicated stores of every institution. The last `capacity`
+// are kept for peers catching up; one further behind is sent a snapshot.
+pub struct ReplicationLog {
+    instance_id: String,
+    // Random per start, so a peer can tell its position is from an earlier run
+    log_id: String,
+    capacity: usize,
+    // Held for reading by each local write until it is logged, and for
+    // writing while a snapshot is taken, so the snapshot holds exactly the
+    // writes up to its sequence number
+    gate: RwLock<()>,
+    events: StdMutex<VecDeque<SyncEvent>>,
+    // The sequence number of the last write logged
+    last: watch::Sender<u64>,
+    replicas: StdMutex<BTreeMap<InstitutionId, Weak<dyn Replica>>>,
+}
+
+impl fmt::Debug for ReplicationLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReplicationLog")
+            .field("instance_id", &self.instance_id)
+            .field("log_id", &self.log_id)
+            .field("capacity", &self.capacity)
+            .field("last", &*self.last.borrow())
+            .finish_non_exhaustive()
+    }
+}
+
+impl ReplicationLog {
+    pub fn new(instance_id: impl Into<String>, capacity: usize) -> Self {
+        Self {
+            instance_id: instance_id.into(),
+            log_id: format!("{:016x}", rand::random::<u64>()),
+            capacity: capacity.max(1),
+            gate: RwLock::new(()),
+            events: StdMutex::new(VecDeque::new()),
+            last: watch::channel(0).0,
+            replicas: StdMutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn instance_id(&self) -> &str {
+        &self.instance_id
+    }
+
+    pub fn log_id(&self) -> &str {
+        &self.log_id
+    }
+
+    // The sequence number of the last write logged, or 0 before the first.
+    pub fn last_sequence(&self) -> u64 {
+        *self.last.borrow()
+    }
+
+    // Changes whenever a write is logged, to the write's sequence number.
+    pub(crate) fn appended(&self) -> watch::Receiver<u64> {
+        self.last.subscribe()
+    }
+
+    fn events(&self) -> std::sync::MutexGuard<'_, VecDeque<SyncEvent>> {
+        self.events.lock().expect("replication log lock")
+    }
+
+    fn register(&self, institution: InstitutionId, replica: Weak<dyn Replica>) {
+        self.replicas.lock().expect("replicas lock").insert(institution, replica);
+    }
+
+    fn replicas(&self) -> Vec<(InstitutionId, Arc<dyn Replica>)> {
+        let replicas = self.replicas.lock().expect("replicas lock");
+        replicas
+            .iter()
+            .filter_map(|(institution, replica)| Some((institution.clone(), replica.upgrade()?)))
+            .collect()
+    }
+
+    fn event(
+        &self,
+        sequence: u64,
+        institution: &InstitutionId,
+        at_ms: i64,
+        mutation: &Mutation,
+    ) -> Result<SyncEvent, StoreError> {
+        Ok(SyncEvent {
+            sequence,
+            log_id: self.log_id.clone(),
+            origin: self.instance_id.clone(),
+            institution_id: institution.to_string(),
+            recorded_at_ms: at_ms,
+            mutation: serde_json::to_vec(mutation)?,
+        })
+    }
+
+    // Logs a write just applied to `institution`'s store.
+    fn append(&self, institution: &InstitutionId, at_ms: i64, mutation: &Mutation) -> Result<(), StoreError> {
+        let mut events = self.events();
+        let sequence = self.last_sequence() + 1;
+        events.push_back(self.event(sequence, institution, at_ms, mutation)?);
+        while events.len() > self.capacity {
+            events.pop_front();
+        }
+        self.last.send_replace(sequence);
+        Ok(())
+    }
+
+    // The writes logged after `after`, oldest first, or None if some of
+    // them are no longer buffered.
+    pub fn since(&self, after: u64) -> Option<Vec<SyncEvent>> {
+        let events = self.events();
+        match events.front() {
+            Some(first) if first.sequence > after + 1 => None,
+            None if self.last_sequence() > after => None,
+            _ => Some(events.iter().filter(|event| event.sequence > after).cloned().collect()),
+        }
+    }
+
+    // A snapshot of every institution's store, all as of the returned sequence number.
+    pub async fn snapshot(&self) -> Result<(u64, Vec<SyncEvent>), StoreError> {
+        let _gate = self.gate.write().await;
+        let sequence = self.last_sequence();
+        let at_ms = now_ms();
+
+        let mut events = Vec::new();
+        for (institution, replica) in self.replicas() {
+            let contents = replica.dump().await?;
+            events.push(self.event(sequence, &institution, at_ms, &Mutation::Snapshot(Box::new(contents)))?);
+        }
+        Ok((sequence, events))
+    }
+
+    // Applies a write a peer sent, or merges a snapshot, into the store of
+    // its institution. Events for institutions not served here are skipped.
+    pub async fn apply(&self, event: &SyncEvent) -> Result<(), StoreError> {
+        let replica = self
+            .replicas()
+            .into_iter()
+            .find(|(institution, _)| institution.as_str() == event.institution_id);
+        let Some((_, replica)) = replica else {
+            debug!(institution = %event.institution_id, "skipping write to an institution not served here");
+            return Ok(());
+        };
+
+        let mutation = serde_json::from_slice(&event.mutation)?;
+        replica.apply(event, mutation).await
+    }
+}
+
+struct Replicated<S> {
+    inner: S,
+    institution: InstitutionId,
+    log: Arc<ReplicationLog>,
+    // Held by each write, local or a peer's, from applying it until it is
+    // stamped, so racing writes to one item cannot be applied in one order
+    // and stamped in the other. Writes to other items go ahead in parallel
+    items: KeyLocks<Item>,
+    // Held for reading by writes to particular items, and for writing by
+    // those replacing or merging in the whole store
+    whole: RwLock<()>,
+    // Only locked to read or set stamps, never across a write to the store
+    stamps: StdMutex<Stamps>,
+}
+
+// A local write in progress: the log gate, and the items written or the
+// whole store, held until it is logged.
+struct Writing<'a> {
+    _gate: RwLockReadGuard<'a, ()>,
+    _store: Store<'a>,
+}
+
+// What a write holds of one store: the items it writes, or all of it.
+enum Store<'a> {
+    Items {
+        _whole: RwLockReadGuard<'a, ()>,
+        _items: Vec<KeyLock<Item>>,
+    },
+    Whole {
+        _whole: RwLockWriteGuard<'a, ()>,
+    },
+}
+
+// Another store, whose writes are logged in a `ReplicationLog` for peers to
+// follow, and which applies the writes peers send. Conflicting writes to one
+// result, exam, grader list or allocation are settled by `Stamp`, the later
+// write winning, on every server alike. An item not written here since this
+// server started, or not among as many latest writes as the log buffers,
+// takes whatever a peer sends. Students are only ever added, and audit
+// records are appended wherever they arrive. Peer writes are not logged
+// again: every server follows every other directly.
+pub struct ReplicatedExamStore<S> {
+    shared: Arc<Replicated<S>>,
+}
+
+impl<S: ExamStore> ReplicatedExamStore<S> {
+    pub fn new(inner: S, institution: InstitutionId, log: Arc<ReplicationLog>) -> Self {
+        let shared = Arc::new(Replicated {
+            inner,
+            institution: institution.clone(),
+            log: log.clone(),
+            items: KeyLocks::default(),
+            whole: RwLock::new(()),
+            stamps: StdMutex::new(Stamps::new(log.capacity)),
+        });
+        let replica: Weak<dyn Replica> = Arc::downgrade(&shared);
+        log.register(institution, replica);
+        Self { shared }
+    }
+
+    // Holds the log gate and `items`, which must be those the write's mutation names.
+    async fn writing(&self, items: Vec<Item>) -> Writing<'_> {
+        let gate = self.shared.log.gate.read().await;
+        Writing {
+            _gate: gate,
+            _store: self.shared.lock_items(items).await,
+        }
+    }
+
+    // Holds the log gate and the whole store.
+    async fn writing_whole(&self) -> Writing<'_> {
+        let gate = self.shared.log.gate.read().await;
+        Writing {
+            _gate: gate,
+            _store: Store::Whole {
+                _whole: self.shared.whole.write().await,
+            },
+        }
+    }
+
+    // Stamps and logs a local write just applied, while its `Writing` is still held.
+    fn record(&self, _writing: &Writing<'_>, mutation: Mutation) -> Result<(), StoreError> {
+        let at_ms = now_ms();
+        {
+            let mut stamps = self.shared.stamps();
+            for (item, version) in mutation.items() {
+                let stamp = Stamp {
+                    at_ms,
+                    version,
+                    origin: self.shared.log.instance_id.clone(),
+                };
+                stamps.insert(item, stamp);
+            }
+        }
+        self.shared.log.append(&self.shared.institution, at_ms, &mutation)
+    }
+}
+
+impl<S> Replicated<S> {
+    fn stamps(&self) -> std::sync::MutexGuard<'_, Stamps> {
+        self.stamps.lock().expect("stamps lock")
+    }
+
+    // Holds `items`, taken in order so that two writes to several cannot
+    // each wait on the other.
+    async fn lock_items(&self, mut items: Vec<Item>) -> Store<'_> {
+        let whole = self.whole.read().await;
+        items.sort();
+        items.dedup();
+        let mut locks = Vec::with_capacity(items.len());
+        for item in items {
+            locks.push(self.items.lock(item).await);
+        }
+        Store::Items {
+            _whole: whole,
+            _items: locks,
+        }
+    }
+}
+
+impl<S: ExamStore> Replicated<S> {
+    // Merges a peer's snapshot: whatever is missing here, and results at a
+    // higher version there, unless their stamps are still kept here. Audit
+    // records are left out, since the peer's were numbered there. Called
+    // holding the whole store.
+    async fn merge(&self, contents: StoreContents) -> Result<(), StoreError> {
+        let written = |item: Item| self.stamps().get(&item).is_some();
+
+        for result in contents.results {
+            let key = ResultKey::from(&result);
+            if written(Item::Result(key.clone())) {
+                continue;
+            }
+            let local = self.inner.get(&key).await?;
+            if local.is_none_or(|local| result.version > local.version) {
+                self.inner.put_at_version(key, result).await?;
+            }
+        }
+        for deleted in contents.deleted {
+            let Some(result) = &deleted.result else { continue };
+            let key = ResultKey::from(result);
+            if written(Item::Result(key.clone())) {
+                continue;
+            }
+            let local = self.inner.get(&key).await?;
+            if local.is_some_and(|local| local.version <= result.version) {
+                self.inner.delete(&key, deleted.deleted_at_ms).await?;
+            }
+        }
+        for exam in contents.exams {
+            let id = ExamId::from(&exam);
+            if !written(Item::Exam(id.clone())) {
+                self.inner.create_exam(id, exam).await?;
+            }
+        }
+        for student in contents.students {
+            self.inner.create_student(StudentId::from(&student), student).await?;
+        }
+        for graders in contents.graders {
+            let id = ExamId::from(&graders);
+            if !written(Item::Graders(id.clone())) && self.inner.get_graders(&id).await?.is_empty() {
+                self.inner.set_graders(graders).await?;
+            }
+        }
+        let held: HashMap<_, _> = self
+            .inner
+            .list_allocations(None, None)
+            .await?
+            .into_iter()
+            .map(|allocation| (ResultKey::from(&allocation), allocation))
+            .collect();
+        let missing: Vec<_> = contents
+            .allocations
+            .into_iter()
+            .filter(|allocation| {
+                let key = ResultKey::from(allocation);
+                !held.contains_key(&key) && !written(Item::Allocation(key))
+            })
+            .collect();
+        if !missing.is_empty() {
+            self.inner.put_allocations(missing).await?;
+        }
+        Ok(())
+    }
+}
+
+#[tonic::async_trait]
+impl<S: ExamStore> Replica for Replicated<S> {
+    async fn apply(&self, event: &SyncEvent, mutation: Mutation) -> Result<(), StoreError> {
+        let items = mutation.items();
+        let _store = match &mutation {
+            Mutation::Restore(_) | Mutation::Snapshot(_) => Store::Whole {
+                _whole: self.whole.write().await,
+            },
+            _ => self.lock_items(items.iter().map(|(item, _)| item.clone()).collect()).await,
+        };
+
+        // Only the items whose stamp the peer's write beats are written
+        let mut newer = Vec::new();
+        {
+            let mut stamps = self.stamps();
+            for (item, version) in items {
+                let stamp = Stamp {
+                    at_ms: event.recorded_at_ms,
+                    version,
+                    origin: event.origin.clone(),
+                };
+                if stamps.get(&item).is_none_or(|local| stamp > *local) {
+                    stamps.insert(item.clone(), stamp);
+                    newer.push(item);
+                }
+            }
+        }
+
+        match mutation {
+            Mutation::PutResult(result) => {
+                if let Some(Item::Result(key)) = newer.pop() {
+                    self.inner.put_at_version(key, result).await?;
+                }
+            }
+            Mutation::DeleteResult(deleted) => {
+                if let Some(Item::Result(key)) = newer.pop() {
+                    self.inner.delete(&key, deleted.deleted_at_ms).await?;
+                }
+            }
+            Mutation::PurgeDeleted { before_ms } => {
+                self.inner.purge_deleted(before_ms).await?;
+            }
+            Mutation::PutExam(exam) => {
+                if let Some(Item::Exam(id)) = newer.pop()
+                    && !self.inner.create_exam(id.clone(), exam.clone()).await?
+                {
+                    self.inner.update_exam(id, exam).await?;
+                }
+            }
+            Mutation::PutStudent(student) => {
+                self.inner.create_student(StudentId::from(&student), student).await?;
+            }
+            Mutation::AppendAudit(record) => {
+                self.inner.append_audit(*record).await?;
+            }
+            Mutation::SetGraders(graders) => {
+                if !newer.is_empty() {
+                    self.inner.set_graders(graders).await?;
+                }
+            }
+            Mutation::PutAllocations(allocations) => {
+                let allocations: Vec<_> = allocations
+                    .into_iter()
+                    .filter(|allocation| newer.contains(&Item::Allocation(ResultKey::from(allocation))))
+                    .collect();
+                if !allocations.is_empty() {
+                    self.inner.put_allocations(allocations).await?;
+                }
+            }
+            // Appeals and webhooks stay as they are here
+            Mutation::Restore(mut contents) => {
+                let local = self.inner.dump().await?;
+                contents.appeals = local.appeals;
+                contents.webhooks = local.webhooks;
+                self.inner.restore(*contents).await?;
+                self.stamps().clear();
+            }
+            Mutation::Snapshot(contents) => {
+                self.merge(*contents).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn dump(&self) -> Result<StoreContents, StoreError> {
+        self.inner.dump().await
+    }
+}
+
+#[tonic::async_trait]
+impl<S: ExamStore> ExamStore for ReplicatedExamStore<S> {
+    async fn get(&self, key: &ResultKey) -> Result<Option<ExamResult>, StoreError> {
+        self.shared.inner.get(key).await
+    }
+
+    async fn put(&self, key: ResultKey, result: ExamResult) -> Result<Option<ExamResult>, StoreError> {
+        let writing = self.writing(vec![Item::Result(key.clone())]).await;
+        let previous = self.shared.inner.put(key.clone(), result.clone()).await?;
+        let stored = stored_result(&key, result, next_version(previous.as_ref()));
+        self.record(&writing, Mutation::PutResult(stored))?;
+        Ok(previous)
+    }
+
+    async fn put_if_version(
+        &self,
+        key: ResultKey,
+        expected: i64,
+        result: ExamResult,
+    ) -> Result<VersionedPut, StoreError> {
+        let writing = self.writing(vec![Item::Result(key.clone())]).await;
+        let outcome = self.shared.inner.put_if_version(key.clone(), expected, result.clone()).await?;
+        if let VersionedPut::Written(previous) = &outcome {
+            let stored = stored_result(&key, result, next_version(Some(previous)));
+            self.record(&writing, Mutation::PutResult(stored))?;
+        }
+        Ok(outcome)
+    }
+
+    async fn put_at_version(&self, key: ResultKey, result: ExamResult) -> Result<Option<ExamResult>, StoreError> {
+        let writing = self.writing(vec![Item::Result(key.clone())]).await;
+        let version = result.version;
+        let stored = stored_result(&key, result, version);
+        let previous = self.shared.inner.put_at_version(key, stored.clone()).await?;
+        self.record(&writing, Mutation::PutResult(stored))?;
+        Ok(previous)
+    }
+
+    async fn list(&self) -> Result<Vec<ExamResult>, StoreError> {
+        self.shared.inner.list().await
+    }
+
+    async fn list_page(&self, after: Option<&ResultKey>, limit: usize) -> Result<Vec<ExamResult>, StoreError> {
+        self.shared.inner.list_page(after, limit).await
+    }
+
+    async fn list_for_student(&self, student_id: &StudentId) -> Result<Vec<ExamResult>, StoreError> {
+        self.shared.inner.list_for_student(student_id).await
+    }
+
+    async fn delete(&self, key: &ResultKey, deleted_at_ms: i64) -> Result<Option<ExamResult>, StoreError> {
+        let writing = self.writing(vec![Item::Result(key.clone())]).await;
+        let removed = self.shared.inner.delete(key, deleted_at_ms).await?;
+        if let Some(result) = &removed {
+            let deleted = DeletedResult {
+                result: Some(result.clone()),
+                deleted_at_ms,
+            };
+            self.record(&writing, Mutation::DeleteResult(deleted))?;
+        }
+        Ok(removed)
+    }
+
+    async fn list_deleted(
+        &self,
+        student_id: Option<&StudentId>,
+        exam_id: Option<&ExamId>,
+    ) -> Result<Vec<DeletedResult>, StoreError> {
+        self.shared.inner.list_deleted(student_id, exam_id).await
+    }
+
+    async fn undelete(&self, key: &ResultKey) -> Result<Undelete, StoreError> {
+        let writing = self.writing(vec![Item::Result(key.clone())]).await;
+        let outcome = self.shared.inner.undelete(key).await?;
+        if let Undelete::Restored(result) = &outcome {
+            self.record(&writing, Mutation::PutResult(result.clone()))?;
+        }
+        Ok(outcome)
+    }
+
+    async fn purge_deleted(&self, before_ms: i64) -> Result<Vec<DeletedResult>, StoreError> {
+        let writing = self.writing(Vec::new()).await;
+        let purged = self.shared.inner.purge_deleted(before_ms).await?;
+        if !purged.is_empty() {
+            self.record(&writing, Mutation::PurgeDeleted { before_ms })?;
+        }
+        Ok(purged)
+    }
+
+    async fn search(&self, query: &str) -> Result<Vec<SearchHit>, StoreError> {
+        self.shared.inner.search(query).await
+    }
+
+    async fn get_exam(&self, id: &ExamId) -> Result<Option<Exam>, StoreError> {
+        self.shared.inner.get_exam(id).await
+    }
+
+    async fn create_exam(&self, id: ExamId, exam: Exam) -> Result<bool, StoreError> {
+        let writing = self.writing(vec![Item::Exam(id.clone())]).await;
+        let stored = Exam {
+            exam_id: id.to_string(),
+            ..exam
+        };
+        let created = self.shared.inner.create_exam(id, stored.clone()).await?;
+        if created {
+            self.record(&writing, Mutation::PutExam(stored))?;
+        }
+        Ok(created)
+    }
+
+    async fn update_exam(&self, id: ExamId, exam: Exam) -> Result<Option<Exam>, StoreError> {
+        let writing = self.writing(vec![Item::Exam(id.clone())]).await;
+        let stored = Exam {
+            exam_id: id.to_string(),
+            ..exam
+        };
+        let previous = self.shared.inner.update_exam(id, stored.clone()).await?;
+        if previous.is_some() {
+            self.record(&writing, Mutation::PutExam(stored))?;
+        }
+        Ok(previous)
+    }
+
+    async fn list_exams(&self) -> Result<Vec<Exam>, StoreError> {
+        self.shared.inner.list_exams().await
+    }
+
+    async fn get_student(&self, id: &StudentId) -> Result<Option<Student>, StoreError> {
+        self.shared.inner.get_student(id).await
+    }
+
+    async fn create_student(&self, id: StudentId, student: Student) -> Result<bool, StoreError> {
+        let writing = self.writing(Vec::new()).await;
+        let stored = Student {
+            student_id: id.to_string(),
+            ..student
+        };
+        let created = self.shared.inner.create_student(id, stored.clone()).await?;
+        if created {
+            self.record(&writing, Mutation::PutStudent(stored))?;
+        }
+        Ok(created)
+    }
+
+    async fn list_students(&self) -> Result<Vec<Student>, StoreError> {
+        self.shared.inner.list_students().await
+    }
+
+    async fn append_audit(&self, record: AuditRecord) -> Result<AuditRecord, StoreError> {
+        let writing = self.writing(Vec::new()).await;
+        let record = self.shared.inner.append_audit(record).await?;
+        self.record(&writing, Mutation::AppendAudit(Box::new(record.clone())))?;
+        Ok(record)
+    }
+
+    async fn audit_trail(
+        &self,
+        student_id: &StudentId,
+        exam_id: Option<&ExamId>,
+    ) -> Result<Vec<AuditRecord>, StoreError> {
+        self.shared.inner.audit_trail(student_id, exam_id).await
+    }
+
+    async fn create_appeal(&self, appeal: Appeal) -> Result<Option<Appeal>, StoreError> {
+        self.shared.inner.create_appeal(appeal).await
+    }
+
+    async fn get_appeal(&self, id: i64) -> Result<Option<Appeal>, StoreError> {
+        self.shared.inner.get_appeal(id).await
+    }
+
+    async fn list_appeals(
+        &self,
+        student_id: Option<&StudentId>,
+        exam_id: Option<&ExamId>,
+    ) -> Result<Vec<Appeal>, StoreError> {
+        self.shared.inner.list_appeals(student_id, exam_id).await
+    }
+
+    async fn update_appeal(&self, appeal: Appeal, expected: AppealState) -> Result<bool, StoreError> {
+        self.shared.inner.update_appeal(appeal, expected).await
+    }
+
+    async fn create_webhook(&self, webhook: Webhook) -> Result<Webhook, StoreError> {
+        self.shared.inner.create_webhook(webhook).await
+    }
+
+    async fn list_webhooks(&self) -> Result<Vec<Webhook>, StoreError> {
+        self.shared.inner.list_webhooks().await
+    }
+
+    async fn delete_webhook(&self, id: i64) -> Result<bool, StoreError> {
+        self.shared.inner.delete_webhook(id).await
+    }
+
+    async fn set_graders(&self, graders: ExamGraders) -> Result<(), StoreError> {
+        let writing = self.writing(vec![Item::Graders(ExamId::from(&graders))]).await;
+        self.shared.inner.set_graders(graders.clone()).await?;
+        self.record(&writing, Mutation::SetGraders(graders))
+    }
+
+    async fn get_graders(&self, exam_id: &ExamId) -> Result<Vec<String>, StoreError> {
+        self.shared.inner.get_graders(exam_id).await
+    }
+
+    async fn put_allocations(&self, allocations: Vec<Allocation>) -> Result<(), StoreError> {
+        let items = allocations.iter().map(|allocation| Item::Allocation(ResultKey::from(allocation)));
+        let writing = self.writing(items.collect()).await;
+        self.shared.inner.put_allocations(allocations.clone()).await?;
+        self.record(&writing, Mutation::PutAllocations(allocations))
+    }
+
+    async fn list_allocations(
+        &self,
+        exam_id: Option<&ExamId>,
+        grader: Option<&str>,
+    ) -> Result<Vec<Allocation>, StoreError> {
+        self.shared.inner.list_allocations(exam_id, grader).await
+    }
+
+    async fn dump(&self) -> Result<StoreContents, StoreError> {
+        self.shared.inner.dump().await
+    }
+
+    async fn restore(&self, contents: StoreContents) -> Result<(), StoreError> {
+        let writing = self.writing_whole().await;
+        self.shared.inner.restore(contents.clone()).await?;
+        self.shared.stamps().clear();
+        self.record(&writing, Mutation::Restore(Box::new(contents)))
+    }
+
+    async fn flush(&self) -> Result<(), StoreError> {
+        self.shared.inner.flush().await
+    }
+
+    async fn evict_expired(&self) -> Result<(), StoreError> {
+        self.shared.inner.evict_expired().await
+    }
+}
@@ -0,0 +1,139 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::exam_service::ExamResult;
+use crate::key::ResultKey;
+
+// How well one query term matched a word of a result. A result's score is
+// the sum over the query's terms of the best match of each.
+const EXACT: u32 = 3;
+const PREFIX: u32 = 2;
+const FUZZY: u32 = 1;
+
+// Shorter terms only match exactly or as a prefix: one edit away from a
+// three-letter word is most of the vocabulary.
+const FUZZY_MIN_CHARS: usize = 4;
+
+// The lowercased words of `text`, split at anything but letters and digits.
+pub(crate) fn terms(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+// Inverted index over the student names and subjects of results: each word
+// to the keys of the results containing it, in key order.
+#[derive(Debug, Default)]
+pub(super) struct SearchIndex {
+    postings: BTreeMap<String, BTreeSet<ResultKey>>,
+    // The words each result is indexed under, so it can be taken out again
+    words: HashMap<ResultKey, Vec<String>>,
+}
+
+impl SearchIndex {
+    pub(super) fn new<'a>(results: impl IntoIterator<Item = &'a ExamResult>) -> Self {
+        let mut index = Self::default();
+        for result in results {
+            index.insert(ResultKey::from(result), result);
+        }
+        index
+    }
+
+    // Indexes `result` under `key`, replacing whatever was indexed there before.
+    pub(super) fn insert(&mut self, key: ResultKey, result: &ExamResult) {
+        self.remove(&key);
+
+        let mut words = terms(&result.student_name);
+        words.extend(terms(&result.subject));
+        words.sort();
+        words.dedup();
+
+        for word in &words {
+            self.postings.entry(word.clone()).or_default().insert(key.clone());
+        }
+        self.words.insert(key, words);
+    }
+
+    pub(super) fn remove(&mut self, key: &ResultKey) {
+        for word in self.words.remove(key).unwrap_or_default() {
+            if let Some(keys) = self.postings.get_mut(&word) {
+                keys.remove(key);
+                if keys.is_empty() {
+                    self.postings.remove(&word);
+                }
+            }
+        }
+    }
+
+    // The keys of the results matching every term of `query`, best first and
+    // then in key order, with their scores. A term matches a word it equals,
+    // one it starts, or, from four letters, one a single edit away.
+    pub(super) fn search(&self, query: &str) -> Vec<(ResultKey, u32)> {
+        let mut scores: Option<HashMap<&ResultKey, u32>> = None;
+
+        for term in terms(query) {
+            let matched = self.matches(&term);
+            scores = Some(match scores {
+                None => matched,
+                Some(scores) => scores
+                    .into_iter()
+                    .filter_map(|(key, score)| matched.get(key).map(|best| (key, score + best)))
+                    .collect(),
+            });
+        }
+
+        let mut ranked: Vec<_> = scores
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(key, score)| (key.clone(), score))
+            .collect();
+        ranked.sort_by(|(a, a_score), (b, b_score)| b_score.cmp(a_score).then_with(|| a.cmp(b)));
+        ranked
+    }
+
+    // The best match of `term` in each result it matches at all.
+    fn matches(&self, term: &str) -> HashMap<&ResultKey, u32> {
+        let mut best = HashMap::new();
+
+        // Words starting with the term sort right after it
+        let prefixed = self.postings.range(term.to_string()..).take_while(|(word, _)| word.starts_with(term));
+        for (word, keys) in prefixed {
+            record(&mut best, keys, if word == term { EXACT } else { PREFIX });
+        }
+
+        if term.chars().count() >= FUZZY_MIN_CHARS {
+            for (word, keys) in &self.postings {
+                if within_one_edit(term, word) {
+                    record(&mut best, keys, FUZZY);
+                }
+            }
+        }
+        best
+    }
+}
+
+fn record<'a>(best: &mut HashMap<&'a ResultKey, u32>, keys: &'a BTreeSet<ResultKey>, score: u32) {
+    for key in keys {
+        let entry = best.entry(key).or_insert(score);
+        *entry = score.max(*entry);
+    }
+}
+
+// Whether `a` becomes `b` by inserting, deleting or replacing at most one character.
+fn within_one_edit(a: &str, b: &str) -> bool {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let (short, long) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    if long.len() - short.len() > 1 {
+        return false;
+    }
+
+    let common = short.iter().zip(&long).take_while(|(x, y)| x == y).count();
+    if common == short.len() {
+        return true;
+    }
+    match short.len() == long.len() {
+        true => short[common + 1..] == long[common + 1..],
+        false => short[common..] == long[common + 1..],
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::BTreeMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::ops::Bound;
+use std::sync::Arc;
+use tokio::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::exam_service::ExamResult;
+use crate::key::{ResultKey, StudentId};
+
+// Independent locks the results are spread over. Writes only contend with
+// reads and writes of students hashed to the same shard.
+const SHARDS: usize = 16;
+
+pub(super) type Shard = BTreeMap<ResultKey, ExamResult>;
+
+// Results split by student across `SHARDS` maps, each behind its own lock.
+// A student's results all live in one shard, still in key order, so every
+// per-student operation takes a single lock. Operations spanning students
+// take the shard locks one at a time, or all of them in shard order when
+// they need a consistent view.
+#[derive(Debug, Clone)]
+pub(super) struct ShardedResults {
+    shards: Arc<[RwLock<Shard>]>,
+}
+
+fn shard_index(student_id: &StudentId) -> usize {
+    let mut hasher = DefaultHasher::new();
+    student_id.hash(&mut hasher);
+    (hasher.finish() % SHARDS as u64) as usize
+}
+
+impl ShardedResults {
+    pub(super) fn new(results: impl IntoIterator<Item = ExamResult>) -> Self {
+        let mut shards: Vec<Shard> = (0..SHARDS).map(|_| Shard::new()).collect();
+        for result in results {
+            let key = ResultKey::from(&result);
+            shards[shard_index(&key.student_id)].insert(key, result);
+        }
+
+        Self {
+            shards: shards.into_iter().map(RwLock::new).collect(),
+        }
+    }
+
+    // The shard holding every result of `student_id`.
+    pub(super) fn shard(&self, student_id: &StudentId) -> &RwLock<Shard> {
+        &self.shards[shard_index(student_id)]
+    }
+
+    pub(super) async fn read_all(&self) -> Vec<RwLockReadGuard<'_, Shard>> {
+        let mut guards = Vec::with_capacity(SHARDS);
+        for shard in self.shards.iter() {
+            guards.push(shard.read().await);
+        }
+        guards
+    }
+
+    pub(super) async fn write_all(&self) -> Vec<RwLockWriteGuard<'_, Shard>> {
+        let mut guards = Vec::with_capacity(SHARDS);
+        for shard in self.shards.iter() {
+            guards.push(shard.write().await);
+        }
+        guards
+    }
+
+    // Up to `limit` results with keys after `after`, in key order. Shards are
+    // read one after another, so a page is not a point-in-time view; paging
+    // never was, since writes can land between pages.
+    pub(super) async fn page(&self, after: Option<&ResultKey>, limit: usize) -> Vec<ExamResult> {
+        let lower = match after {
+            Some(key) => Bound::Excluded(key),
+            None => Bound::Unbounded,
+        };
+
+        // The first `limit` of each shard include the first `limit` overall
+        let mut page = Vec::new();
+        for shard in self.shards.iter() {
+            let shard = shard.read().await;
+            page.extend(
+                shard
+                    .range((lower, Bound::Unbounded))
+                    .take(limit)
+                    .map(|(key, result)| (key.clone(), result.clone())),
+            );
+        }
+
+        page.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+        page.into_iter().take(limit).map(|(_, result)| result).collect()
+    }
+}
+
+impl Default for ShardedResults {
+    fn default() -> Self {
+        Self::new([])
+    }
+}
+
+// Every result held by `shards`, in key order.
+pub(super) fn in_key_order(shards: &[RwLockReadGuard<'_, Shard>]) -> Vec<ExamResult> {
+    let mut results: Vec<_> = shards.iter().flat_map(|shard| shard.iter()).collect();
+    results.sort_unstable_by_key(|(key, _)| *key);
+    results.into_iter().map(|(_, result)| result.clone()).collect()
+}
+
+// Replaces what `shards`, every shard in order, hold with `results`.
+pub(super) fn replace_all(shards: &mut [RwLockWriteGuard<'_, Shard>], results: Vec<ExamResult>) {
+    for shard in shards.iter_mut() {
+        shard.clear();
+    }
+    for result in results {
+        let key = ResultKey::from(&result);
+        shards[shard_index(&key.student_id)].insert(key, result);
+    }
+}
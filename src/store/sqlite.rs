@@ -0,0 +1,1297 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use rusqlite::types::Type;
+use rusqlite::{params, Connection, OptionalExtension, Row, Transaction};
+
+use super::{next_version, ExamStore, StoreContents, StoreError, Undelete, VersionedPut};
+use crate::appeal::{Appeal, AppealState};
+use crate::exam_admin::Exam;
+use crate::exam_service::{AuditRecord, DeletedResult, ExamResult, GetExamResultResponse, ResultStatus};
+use crate::grader::{Allocation, ExamGraders};
+use crate::key::{ExamId, ResultKey, StudentId};
+use crate::model::{ms_from_timestamp, timestamp_from_ms};
+use crate::student::Student;
+use crate::webhook::Webhook;
+
+// Result columns as of v1, the only ones a legacy table can be copied from.
+const V1_COLUMNS: &str =
+    "student_id, exam_id, student_name, subject, marks_obtained, total_marks, grade, internal_comment";
+
+const COLUMNS: &str = "student_id, exam_id, student_name, subject, marks_obtained, total_marks, grade, \
+     internal_comment, questions, version, status, exam_date_ms, graded_at_ms";
+
+const DELETED_COLUMNS: &str = "student_id, exam_id, student_name, subject, marks_obtained, total_marks, grade, \
+     internal_comment, questions, version, status, exam_date_ms, graded_at_ms, deleted_at_ms";
+
+const AUDIT_COLUMNS: &str =
+    "sequence, recorded_at_ms, actor, role, action, student_id, exam_id, before, after, reason, request_id";
+
+const APPEAL_COLUMNS: &str = "appeal_id, student_id, exam_id, reason, state, original_marks, filed_by, filed_at_ms, \
+     reviewer, approved, revised_marks, resolution, resolved_at_ms";
+
+const EXAM_COLUMNS: &str = "exam_id, subject, total_marks, date, opens_at_ms, closes_at_ms, grades_released_at_ms";
+
+const STUDENT_COLUMNS: &str = "student_id, name, email";
+
+const WEBHOOK_COLUMNS: &str = "webhook_id, url, events, secret, created_by, created_at_ms";
+
+const GRADER_COLUMNS: &str = "exam_id, graders";
+
+const ALLOCATION_COLUMNS: &str = "student_id, exam_id, grader, allocated_at_ms";
+
+// Keep the audit log append-only; only a snapshot restore lifts them, within its transaction.
+const AUDIT_TRIGGERS: &str = "CREATE TRIGGER audit_log_no_update BEFORE UPDATE ON audit_log
+        BEGIN SELECT RAISE(ABORT, 'audit_log is append-only'); END;
+    CREATE TRIGGER audit_log_no_delete BEFORE DELETE ON audit_log
+        BEGIN SELECT RAISE(ABORT, 'audit_log is append-only'); END;";
+
+// Schema upgrades, applied in order. The number applied so far is tracked in
+// `PRAGMA user_version`, so each migration runs exactly once per database.
+const MIGRATIONS: &[fn(&Transaction) -> rusqlite::Result<()>] =
+    &[create_results_table, create_exams_table, create_students_table, add_question_scores, add_versions, create_audit_log, add_exam_schedule, add_result_status, create_appeals_table, create_webhooks_table, create_deleted_results_table, add_result_dates, create_grading_tables];
+
+// v1: results keyed by (student_id, exam_id). Databases created before
+// structured keys used a "student_exam" string key column; their rows are copied over.
+fn create_results_table(tx: &Transaction) -> rusqlite::Result<()> {
+    let legacy: bool = tx.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('exam_results') WHERE name = 'key'",
+        [],
+        |row| row.get(0),
+    )?;
+
+    if legacy {
+        tx.execute_batch("ALTER TABLE exam_results RENAME TO exam_results_legacy;")?;
+    }
+
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS exam_results (
+            student_id TEXT NOT NULL,
+            exam_id TEXT NOT NULL,
+            student_name TEXT NOT NULL,
+            subject TEXT NOT NULL,
+            marks_obtained INTEGER NOT NULL,
+            total_marks INTEGER NOT NULL,
+            grade TEXT NOT NULL,
+            internal_comment TEXT NOT NULL DEFAULT '',
+            PRIMARY KEY (student_id, exam_id)
+        );",
+    )?;
+
+    if legacy {
+        tx.execute_batch(&format!(
+            "INSERT OR REPLACE INTO exam_results ({cols}) SELECT {cols} FROM exam_results_legacy;
+             DROP TABLE exam_results_legacy;",
+            cols = V1_COLUMNS
+        ))?;
+    }
+
+    Ok(())
+}
+
+// v2: the exam catalog. Exams that already have results are registered from
+// those results (with no date) so existing databases keep accepting submissions.
+fn create_exams_table(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE exams (
+            exam_id TEXT PRIMARY KEY,
+            subject TEXT NOT NULL,
+            total_marks INTEGER NOT NULL,
+            date TEXT NOT NULL DEFAULT ''
+        );
+        INSERT INTO exams (exam_id, subject, total_marks)
+            SELECT exam_id, MIN(subject), MAX(total_marks) FROM exam_results GROUP BY exam_id;",
+    )
+}
+
+// v3: the student registry, seeded from existing results like the exam catalog.
+fn create_students_table(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE students (
+            student_id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            email TEXT NOT NULL DEFAULT ''
+        );
+        INSERT INTO students (student_id, name)
+            SELECT student_id, MIN(student_name) FROM exam_results GROUP BY student_id;",
+    )
+}
+
+// v4: per-question breakdowns, stored as a JSON array of QuestionScore.
+fn add_question_scores(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch("ALTER TABLE exam_results ADD COLUMN questions TEXT NOT NULL DEFAULT '[]';")
+}
+
+// v5: result versions for optimistic concurrency; existing results start at 1.
+fn add_versions(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch("ALTER TABLE exam_results ADD COLUMN version INTEGER NOT NULL DEFAULT 1;")
+}
+
+// v6: the audit log. Triggers reject updates and deletes so it stays append-only.
+fn create_audit_log(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE audit_log (
+            sequence INTEGER PRIMARY KEY AUTOINCREMENT,
+            recorded_at_ms INTEGER NOT NULL,
+            actor TEXT NOT NULL,
+            role TEXT NOT NULL,
+            action INTEGER NOT NULL,
+            student_id TEXT NOT NULL,
+            exam_id TEXT NOT NULL,
+            before TEXT,
+            after TEXT,
+            reason TEXT NOT NULL,
+            request_id TEXT NOT NULL
+        );
+        CREATE INDEX audit_log_by_result ON audit_log (student_id, exam_id);",
+    )?;
+    tx.execute_batch(AUDIT_TRIGGERS)
+}
+
+// v7: exam availability windows and grade release times; existing exams have none.
+fn add_exam_schedule(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE exams ADD COLUMN opens_at_ms INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE exams ADD COLUMN closes_at_ms INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE exams ADD COLUMN grades_released_at_ms INTEGER NOT NULL DEFAULT 0;",
+    )
+}
+
+// v8: draft and published results. Existing results were visible to students, so they start published.
+fn add_result_status(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(&format!(
+        "ALTER TABLE exam_results ADD COLUMN status INTEGER NOT NULL DEFAULT {};",
+        ResultStatus::Published as i32
+    ))
+}
+
+// v9: regrade requests against results, in filing order.
+fn create_appeals_table(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE appeals (
+            appeal_id INTEGER PRIMARY KEY AUTOINCREMENT,
+            student_id TEXT NOT NULL,
+            exam_id TEXT NOT NULL,
+            reason TEXT NOT NULL,
+            state INTEGER NOT NULL,
+            original_marks INTEGER NOT NULL,
+            filed_by TEXT NOT NULL,
+            filed_at_ms INTEGER NOT NULL,
+            reviewer TEXT NOT NULL,
+            approved INTEGER NOT NULL,
+            revised_marks INTEGER NOT NULL,
+            resolution TEXT NOT NULL,
+            resolved_at_ms INTEGER NOT NULL
+        );
+        CREATE INDEX appeals_by_result ON appeals (student_id, exam_id);",
+    )
+}
+
+// v10: webhook registrations. AUTOINCREMENT keeps IDs of deleted webhooks from being reused.
+fn create_webhooks_table(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE webhooks (
+            webhook_id INTEGER PRIMARY KEY AUTOINCREMENT,
+            url TEXT NOT NULL,
+            events TEXT NOT NULL,
+            secret TEXT NOT NULL,
+            created_by TEXT NOT NULL,
+            created_at_ms INTEGER NOT NULL
+        );",
+    )
+}
+
+// v11: deleted results, kept with the time of deletion until purged.
+fn create_deleted_results_table(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE deleted_results (
+            student_id TEXT NOT NULL,
+            exam_id TEXT NOT NULL,
+            student_name TEXT NOT NULL,
+            subject TEXT NOT NULL,
+            marks_obtained INTEGER NOT NULL,
+            total_marks INTEGER NOT NULL,
+            grade TEXT NOT NULL,
+            internal_comment TEXT NOT NULL,
+            questions TEXT NOT NULL,
+            version INTEGER NOT NULL,
+            status INTEGER NOT NULL,
+            deleted_at_ms INTEGER NOT NULL,
+            PRIMARY KEY (student_id, exam_id)
+        );
+        CREATE INDEX deleted_results_by_time ON deleted_results (deleted_at_ms);",
+    )
+}
+
+// v12: exam dates and grading times of results, in Unix milliseconds; NULL when
+// unknown. Existing results take the date of their exam; when they were graded is lost.
+fn add_result_dates(tx: &Transaction) -> rusqlite::Result<()> {
+    for table in ["exam_results", "deleted_results"] {
+        tx.execute_batch(&format!(
+            "ALTER TABLE {table} ADD COLUMN exam_date_ms INTEGER;
+            ALTER TABLE {table} ADD COLUMN graded_at_ms INTEGER;
+            UPDATE {table} SET exam_date_ms = (
+                SELECT CAST(strftime('%s', date) AS INTEGER) * 1000 FROM exams
+                WHERE exams.exam_id = {table}.exam_id AND date != ''
+            );"
+        ))?;
+    }
+    Ok(())
+}
+
+// v13: graders assigned per exam, in assignment order, and which grader holds which result.
+fn create_grading_tables(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE exam_graders (
+            exam_id TEXT PRIMARY KEY,
+            graders TEXT NOT NULL
+        );
+        CREATE TABLE allocations (
+            student_id TEXT NOT NULL,
+            exam_id TEXT NOT NULL,
+            grader TEXT NOT NULL,
+            allocated_at_ms INTEGER NOT NULL,
+            PRIMARY KEY (student_id, exam_id)
+        );
+        CREATE INDEX allocations_by_grader ON allocations (grader);",
+    )
+}
+
+fn migrate(conn: &mut Connection) -> rusqlite::Result<()> {
+    let applied: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+
+    for (version, migration) in (1..).zip(MIGRATIONS).skip_while(|(version, _)| *version <= applied) {
+        let tx = conn.transaction()?;
+        migration(&tx)?;
+        tx.pragma_update(None, "user_version", version)?;
+        tx.commit()?;
+    }
+
+    Ok(())
+}
+
+impl From<rusqlite::Error> for StoreError {
+    fn from(err: rusqlite::Error) -> Self {
+        StoreError(err.to_string())
+    }
+}
+
+// SQLite-backed store so exam results survive server restarts.
+// rusqlite is blocking, so every query runs on tokio's blocking thread pool.
+#[derive(Debug, Clone)]
+pub struct SqliteExamStore {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteExamStore {
+    // Opens (or creates) the database at `path` and brings the schema up to date.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, StoreError> {
+        let mut conn = Connection::open(path)?;
+        migrate(&mut conn)?;
+
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    // Runs `f` against the connection without blocking the async runtime.
+    async fn call<T, F>(&self, f: F) -> Result<T, StoreError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> rusqlite::Result<T> + Send + 'static,
+    {
+        let conn = self.conn.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let mut conn = conn.lock().map_err(|_| StoreError("connection lock poisoned".into()))?;
+            f(&mut conn).map_err(StoreError::from)
+        })
+        .await
+        .map_err(|err| StoreError(err.to_string()))?
+    }
+}
+
+fn row_to_result(row: &Row<'_>) -> rusqlite::Result<ExamResult> {
+    let questions: String = row.get(8)?;
+    let questions = serde_json::from_str(&questions)
+        .map_err(|err| rusqlite::Error::FromSqlConversionFailure(8, Type::Text, Box::new(err)))?;
+
+    Ok(ExamResult {
+        student_id: row.get(0)?,
+        exam_id: row.get(1)?,
+        student_name: row.get(2)?,
+        subject: row.get(3)?,
+        marks_obtained: row.get(4)?,
+        total_marks: row.get(5)?,
+        grade: row.get(6)?,
+        internal_comment: row.get(7)?,
+        questions,
+        version: row.get(9)?,
+        status: row.get(10)?,
+        exam_date: row.get::<_, Option<i64>>(11)?.map(timestamp_from_ms),
+        graded_at: row.get::<_, Option<i64>>(12)?.map(timestamp_from_ms),
+    })
+}
+
+fn select_one(conn: &Connection, key: &ResultKey) -> rusqlite::Result<Option<ExamResult>> {
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM exam_results WHERE student_id = ?1 AND exam_id = ?2",
+            COLUMNS
+        ),
+        params![key.student_id.as_str(), key.exam_id.as_str()],
+        row_to_result,
+    )
+    .optional()
+}
+
+fn row_to_deleted(row: &Row<'_>) -> rusqlite::Result<DeletedResult> {
+    Ok(DeletedResult {
+        result: Some(row_to_result(row)?),
+        deleted_at_ms: row.get(13)?,
+    })
+}
+
+fn select_deleted(conn: &Connection, key: &ResultKey) -> rusqlite::Result<Option<DeletedResult>> {
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM deleted_results WHERE student_id = ?1 AND exam_id = ?2",
+            DELETED_COLUMNS
+        ),
+        params![key.student_id.as_str(), key.exam_id.as_str()],
+        row_to_deleted,
+    )
+    .optional()
+}
+
+fn row_to_exam(row: &Row<'_>) -> rusqlite::Result<Exam> {
+    Ok(Exam {
+        exam_id: row.get(0)?,
+        subject: row.get(1)?,
+        total_marks: row.get(2)?,
+        date: row.get(3)?,
+        opens_at_ms: row.get(4)?,
+        closes_at_ms: row.get(5)?,
+        grades_released_at_ms: row.get(6)?,
+    })
+}
+
+fn select_exam(conn: &Connection, id: &ExamId) -> rusqlite::Result<Option<Exam>> {
+    conn.query_row(
+        &format!("SELECT {} FROM exams WHERE exam_id = ?1", EXAM_COLUMNS),
+        params![id.as_str()],
+        row_to_exam,
+    )
+    .optional()
+}
+
+fn row_to_student(row: &Row<'_>) -> rusqlite::Result<Student> {
+    Ok(Student {
+        student_id: row.get(0)?,
+        name: row.get(1)?,
+        email: row.get(2)?,
+    })
+}
+
+// Inserts or replaces the row for `key`, stamped with `version`.
+fn write_result(tx: &Transaction, key: &ResultKey, result: &ExamResult, version: i64) -> rusqlite::Result<()> {
+    let questions = serde_json::to_string(&result.questions)
+        .map_err(|err| rusqlite::Error::ToSqlConversionFailure(Box::new(err)))?;
+
+    tx.execute(
+        &format!(
+            "INSERT OR REPLACE INTO exam_results ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            COLUMNS
+        ),
+        params![
+            key.student_id.as_str(),
+            key.exam_id.as_str(),
+            result.student_name,
+            result.subject,
+            result.marks_obtained,
+            result.total_marks,
+            result.grade,
+            result.internal_comment,
+            questions,
+            version,
+            result.status,
+            result.exam_date.as_ref().map(ms_from_timestamp),
+            result.graded_at.as_ref().map(ms_from_timestamp),
+        ],
+    )?;
+
+    Ok(())
+}
+
+// Inserts or replaces the deleted row for the key of `deleted`.
+fn write_deleted(tx: &Transaction, deleted: &DeletedResult) -> rusqlite::Result<()> {
+    let result = deleted.result.clone().unwrap_or_default();
+    let questions = serde_json::to_string(&result.questions)
+        .map_err(|err| rusqlite::Error::ToSqlConversionFailure(Box::new(err)))?;
+
+    tx.execute(
+        &format!(
+            "INSERT OR REPLACE INTO deleted_results ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            DELETED_COLUMNS
+        ),
+        params![
+            result.student_id,
+            result.exam_id,
+            result.student_name,
+            result.subject,
+            result.marks_obtained,
+            result.total_marks,
+            result.grade,
+            result.internal_comment,
+            questions,
+            result.version,
+            result.status,
+            result.exam_date.as_ref().map(ms_from_timestamp),
+            result.graded_at.as_ref().map(ms_from_timestamp),
+            deleted.deleted_at_ms,
+        ],
+    )?;
+
+    Ok(())
+}
+
+// Result snapshots in the audit log are stored as JSON, like question breakdowns.
+fn snapshot_to_json(snapshot: &Option<GetExamResultResponse>) -> rusqlite::Result<Option<String>> {
+    snapshot
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|err| rusqlite::Error::ToSqlConversionFailure(Box::new(err)))
+}
+
+fn snapshot_from_json(row: &Row<'_>, index: usize) -> rusqlite::Result<Option<GetExamResultResponse>> {
+    let json: Option<String> = row.get(index)?;
+    json.map(|json| serde_json::from_str(&json))
+        .transpose()
+        .map_err(|err| rusqlite::Error::FromSqlConversionFailure(index, Type::Text, Box::new(err)))
+}
+
+fn row_to_audit(row: &Row<'_>) -> rusqlite::Result<AuditRecord> {
+    Ok(AuditRecord {
+        sequence: row.get(0)?,
+        recorded_at_ms: row.get(1)?,
+        actor: row.get(2)?,
+        role: row.get(3)?,
+        action: row.get(4)?,
+        student_id: row.get(5)?,
+        exam_id: row.get(6)?,
+        before: snapshot_from_json(row, 7)?,
+        after: snapshot_from_json(row, 8)?,
+        reason: row.get(9)?,
+        request_id: row.get(10)?,
+    })
+}
+
+fn row_to_appeal(row: &Row<'_>) -> rusqlite::Result<Appeal> {
+    Ok(Appeal {
+        appeal_id: row.get(0)?,
+        student_id: row.get(1)?,
+        exam_id: row.get(2)?,
+        reason: row.get(3)?,
+        state: row.get(4)?,
+        original_marks: row.get(5)?,
+        filed_by: row.get(6)?,
+        filed_at_ms: row.get(7)?,
+        reviewer: row.get(8)?,
+        approved: row.get(9)?,
+        revised_marks: row.get(10)?,
+        resolution: row.get(11)?,
+        resolved_at_ms: row.get(12)?,
+    })
+}
+
+fn row_to_webhook(row: &Row<'_>) -> rusqlite::Result<Webhook> {
+    let events: String = row.get(2)?;
+    Ok(Webhook {
+        webhook_id: row.get(0)?,
+        url: row.get(1)?,
+        events: serde_json::from_str(&events)
+            .map_err(|err| rusqlite::Error::FromSqlConversionFailure(2, Type::Text, Box::new(err)))?,
+        secret: row.get(3)?,
+        created_by: row.get(4)?,
+        created_at_ms: row.get(5)?,
+    })
+}
+
+fn row_to_graders(row: &Row<'_>) -> rusqlite::Result<ExamGraders> {
+    let graders: String = row.get(1)?;
+    Ok(ExamGraders {
+        exam_id: row.get(0)?,
+        graders: serde_json::from_str(&graders)
+            .map_err(|err| rusqlite::Error::FromSqlConversionFailure(1, Type::Text, Box::new(err)))?,
+    })
+}
+
+fn row_to_allocation(row: &Row<'_>) -> rusqlite::Result<Allocation> {
+    Ok(Allocation {
+        student_id: row.get(0)?,
+        exam_id: row.get(1)?,
+        grader: row.get(2)?,
+        allocated_at_ms: row.get(3)?,
+    })
+}
+
+// Inserts or replaces the graders of one exam.
+fn write_graders(conn: &Connection, graders: &ExamGraders) -> rusqlite::Result<()> {
+    let names = serde_json::to_string(&graders.graders)
+        .map_err(|err| rusqlite::Error::ToSqlConversionFailure(Box::new(err)))?;
+    conn.execute(
+        &format!("INSERT OR REPLACE INTO exam_graders ({}) VALUES (?1, ?2)", GRADER_COLUMNS),
+        params![graders.exam_id, names],
+    )?;
+    Ok(())
+}
+
+// Inserts or replaces the allocation of one result.
+fn write_allocation(conn: &Connection, allocation: &Allocation) -> rusqlite::Result<()> {
+    conn.execute(
+        &format!("INSERT OR REPLACE INTO allocations ({}) VALUES (?1, ?2, ?3, ?4)", ALLOCATION_COLUMNS),
+        params![allocation.student_id, allocation.exam_id, allocation.grader, allocation.allocated_at_ms],
+    )?;
+    Ok(())
+}
+
+fn select_all<T>(conn: &Connection, sql: &str, f: fn(&Row<'_>) -> rusqlite::Result<T>) -> rusqlite::Result<Vec<T>> {
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt.query_map([], f)?;
+    rows.collect()
+}
+
+// Writes `contents` into emptied tables, keeping every stored ID.
+fn insert_contents(tx: &Transaction, contents: &StoreContents) -> rusqlite::Result<()> {
+    for result in &contents.results {
+        write_result(tx, &ResultKey::from(result), result, result.version)?;
+    }
+
+    for deleted in &contents.deleted {
+        write_deleted(tx, deleted)?;
+    }
+
+    for exam in &contents.exams {
+        tx.execute(
+            &format!("INSERT INTO exams ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)", EXAM_COLUMNS),
+            params![
+                exam.exam_id,
+                exam.subject,
+                exam.total_marks,
+                exam.date,
+                exam.opens_at_ms,
+                exam.closes_at_ms,
+                exam.grades_released_at_ms
+            ],
+        )?;
+    }
+
+    for student in &contents.students {
+        tx.execute(
+            &format!("INSERT INTO students ({}) VALUES (?1, ?2, ?3)", STUDENT_COLUMNS),
+            params![student.student_id, student.name, student.email],
+        )?;
+    }
+
+    for record in &contents.audit {
+        tx.execute(
+            &format!(
+                "INSERT INTO audit_log ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                AUDIT_COLUMNS
+            ),
+            params![
+                record.sequence,
+                record.recorded_at_ms,
+                record.actor,
+                record.role,
+                record.action,
+                record.student_id,
+                record.exam_id,
+                snapshot_to_json(&record.before)?,
+                snapshot_to_json(&record.after)?,
+                record.reason,
+                record.request_id,
+            ],
+        )?;
+    }
+
+    for appeal in &contents.appeals {
+        tx.execute(
+            &format!(
+                "INSERT INTO appeals ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+                APPEAL_COLUMNS
+            ),
+            params![
+                appeal.appeal_id,
+                appeal.student_id,
+                appeal.exam_id,
+                appeal.reason,
+                appeal.state,
+                appeal.original_marks,
+                appeal.filed_by,
+                appeal.filed_at_ms,
+                appeal.reviewer,
+                appeal.approved,
+                appeal.revised_marks,
+                appeal.resolution,
+                appeal.resolved_at_ms,
+            ],
+        )?;
+    }
+
+    for webhook in &contents.webhooks {
+        let events = serde_json::to_string(&webhook.events)
+            .map_err(|err| rusqlite::Error::ToSqlConversionFailure(Box::new(err)))?;
+        tx.execute(
+            &format!("INSERT INTO webhooks ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6)", WEBHOOK_COLUMNS),
+            params![
+                webhook.webhook_id,
+                webhook.url,
+                events,
+                webhook.secret,
+                webhook.created_by,
+                webhook.created_at_ms
+            ],
+        )?;
+    }
+
+    for graders in &contents.graders {
+        write_graders(tx, graders)?;
+    }
+
+    for allocation in &contents.allocations {
+        write_allocation(tx, allocation)?;
+    }
+
+    Ok(())
+}
+
+#[tonic::async_trait]
+impl ExamStore for SqliteExamStore {
+    async fn get(&self, key: &ResultKey) -> Result<Option<ExamResult>, StoreError> {
+        let key = key.clone();
+        self.call(move |conn| select_one(conn, &key)).await
+    }
+
+    async fn put(&self, key: ResultKey, result: ExamResult) -> Result<Option<ExamResult>, StoreError> {
+        self.call(move |conn| {
+            let tx = conn.transaction()?;
+            let previous = select_one(&tx, &key)?;
+
+            write_result(&tx, &key, &result, next_version(previous.as_ref()))?;
+
+            tx.commit()?;
+            Ok(previous)
+        })
+        .await
+    }
+
+    async fn put_if_version(
+        &self,
+        key: ResultKey,
+        expected: i64,
+        result: ExamResult,
+    ) -> Result<VersionedPut, StoreError> {
+        self.call(move |conn| {
+            let tx = conn.transaction()?;
+
+            let previous = match select_one(&tx, &key)? {
+                Some(previous) if previous.version == expected => previous,
+                current => return Ok(VersionedPut::Conflict(current.map(|current| current.version))),
+            };
+
+            write_result(&tx, &key, &result, next_version(Some(&previous)))?;
+
+            tx.commit()?;
+            Ok(VersionedPut::Written(previous))
+        })
+        .await
+    }
+
+    async fn put_at_version(&self, key: ResultKey, result: ExamResult) -> Result<Option<ExamResult>, StoreError> {
+        self.call(move |conn| {
+            let tx = conn.transaction()?;
+            let previous = select_one(&tx, &key)?;
+
+            write_result(&tx, &key, &result, result.version)?;
+
+            tx.commit()?;
+            Ok(previous)
+        })
+        .await
+    }
+
+    async fn list(&self) -> Result<Vec<ExamResult>, StoreError> {
+        self.call(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM exam_results ORDER BY student_id, exam_id",
+                COLUMNS
+            ))?;
+            let rows = stmt.query_map([], row_to_result)?;
+            rows.collect()
+        })
+        .await
+    }
+
+    async fn list_page(&self, after: Option<&ResultKey>, limit: usize) -> Result<Vec<ExamResult>, StoreError> {
+        let after = after.cloned();
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+
+        self.call(move |conn| {
+            let results = match after {
+                Some(key) => {
+                    let mut stmt = conn.prepare(&format!(
+                        "SELECT {} FROM exam_results WHERE (student_id, exam_id) > (?1, ?2)
+                         ORDER BY student_id, exam_id LIMIT ?3",
+                        COLUMNS
+                    ))?;
+                    let rows = stmt.query_map(
+                        params![key.student_id.as_str(), key.exam_id.as_str(), limit],
+                        row_to_result,
+                    )?;
+                    rows.collect::<rusqlite::Result<Vec<_>>>()?
+                }
+                None => {
+                    let mut stmt = conn.prepare(&format!(
+                        "SELECT {} FROM exam_results ORDER BY student_id, exam_id LIMIT ?1",
+                        COLUMNS
+                    ))?;
+                    let rows = stmt.query_map(params![limit], row_to_result)?;
+                    rows.collect::<rusqlite::Result<Vec<_>>>()?
+                }
+            };
+            Ok(results)
+        })
+        .await
+    }
+
+    // Served by the primary key index, whose leading column is student_id.
+    async fn list_for_student(&self, student_id: &StudentId) -> Result<Vec<ExamResult>, StoreError> {
+        let student_id = student_id.clone();
+        self.call(move |conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM exam_results WHERE student_id = ?1 ORDER BY exam_id",
+                COLUMNS
+            ))?;
+            let rows = stmt.query_map(params![student_id.as_str()], row_to_result)?;
+            rows.collect()
+        })
+        .await
+    }
+
+    async fn delete(&self, key: &ResultKey, deleted_at_ms: i64) -> Result<Option<ExamResult>, StoreError> {
+        let key = key.clone();
+        self.call(move |conn| {
+            let tx = conn.transaction()?;
+            let Some(previous) = select_one(&tx, &key)? else {
+                return Ok(None);
+            };
+
+            let deleted = DeletedResult {
+                result: Some(previous.clone()),
+                deleted_at_ms,
+            };
+            write_deleted(&tx, &deleted)?;
+            tx.execute(
+                "DELETE FROM exam_results WHERE student_id = ?1 AND exam_id = ?2",
+                params![key.student_id.as_str(), key.exam_id.as_str()],
+            )?;
+
+            tx.commit()?;
+            Ok(Some(previous))
+        })
+        .await
+    }
+
+    async fn list_deleted(
+        &self,
+        student_id: Option<&StudentId>,
+        exam_id: Option<&ExamId>,
+    ) -> Result<Vec<DeletedResult>, StoreError> {
+        let student_id = student_id.cloned();
+        let exam_id = exam_id.cloned();
+        self.call(move |conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM deleted_results WHERE (?1 IS NULL OR student_id = ?1) AND (?2 IS NULL OR exam_id = ?2)
+                 ORDER BY student_id, exam_id",
+                DELETED_COLUMNS
+            ))?;
+            let rows = stmt.query_map(
+                params![student_id.as_ref().map(StudentId::as_str), exam_id.as_ref().map(ExamId::as_str)],
+                row_to_deleted,
+            )?;
+            rows.collect()
+        })
+        .await
+    }
+
+    async fn undelete(&self, key: &ResultKey) -> Result<Undelete, StoreError> {
+        let key = key.clone();
+        self.call(move |conn| {
+            let tx = conn.transaction()?;
+
+            let Some(deleted) = select_deleted(&tx, &key)? else {
+                return Ok(Undelete::NotDeleted);
+            };
+            if select_one(&tx, &key)?.is_some() {
+                return Ok(Undelete::Occupied);
+            }
+
+            let mut result = deleted.result.unwrap_or_default();
+            result.version = next_version(Some(&result));
+            write_result(&tx, &key, &result, result.version)?;
+            tx.execute(
+                "DELETE FROM deleted_results WHERE student_id = ?1 AND exam_id = ?2",
+                params![key.student_id.as_str(), key.exam_id.as_str()],
+            )?;
+
+            tx.commit()?;
+            Ok(Undelete::Restored(result))
+        })
+        .await
+    }
+
+    async fn purge_deleted(&self, before_ms: i64) -> Result<Vec<DeletedResult>, StoreError> {
+        self.call(move |conn| {
+            let tx = conn.transaction()?;
+
+            let purged = {
+                let mut stmt = tx.prepare(&format!(
+                    "SELECT {} FROM deleted_results WHERE deleted_at_ms < ?1 ORDER BY student_id, exam_id",
+                    DELETED_COLUMNS
+                ))?;
+                let rows = stmt.query_map(params![before_ms], row_to_deleted)?;
+                rows.collect::<rusqlite::Result<Vec<_>>>()?
+            };
+            tx.execute("DELETE FROM deleted_results WHERE deleted_at_ms < ?1", params![before_ms])?;
+
+            tx.commit()?;
+            Ok(purged)
+        })
+        .await
+    }
+
+    async fn get_exam(&self, id: &ExamId) -> Result<Option<Exam>, StoreError> {
+        let id = id.clone();
+        self.call(move |conn| select_exam(conn, &id)).await
+    }
+
+    async fn create_exam(&self, id: ExamId, exam: Exam) -> Result<bool, StoreError> {
+        self.call(move |conn| {
+            let inserted = conn.execute(
+                &format!(
+                    "INSERT OR IGNORE INTO exams ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    EXAM_COLUMNS
+                ),
+                params![
+                    id.as_str(),
+                    exam.subject,
+                    exam.total_marks,
+                    exam.date,
+                    exam.opens_at_ms,
+                    exam.closes_at_ms,
+                    exam.grades_released_at_ms
+                ],
+            )?;
+            Ok(inserted > 0)
+        })
+        .await
+    }
+
+    async fn update_exam(&self, id: ExamId, exam: Exam) -> Result<Option<Exam>, StoreError> {
+        self.call(move |conn| {
+            let tx = conn.transaction()?;
+            let previous = select_exam(&tx, &id)?;
+
+            if previous.is_some() {
+                tx.execute(
+                    "UPDATE exams SET subject = ?2, total_marks = ?3, date = ?4,
+                        opens_at_ms = ?5, closes_at_ms = ?6, grades_released_at_ms = ?7
+                     WHERE exam_id = ?1",
+                    params![
+                        id.as_str(),
+                        exam.subject,
+                        exam.total_marks,
+                        exam.date,
+                        exam.opens_at_ms,
+                        exam.closes_at_ms,
+                        exam.grades_released_at_ms
+                    ],
+                )?;
+            }
+
+            tx.commit()?;
+            Ok(previous)
+        })
+        .await
+    }
+
+    async fn list_exams(&self) -> Result<Vec<Exam>, StoreError> {
+        self.call(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM exams ORDER BY exam_id",
+                EXAM_COLUMNS
+            ))?;
+            let rows = stmt.query_map([], row_to_exam)?;
+            rows.collect()
+        })
+        .await
+    }
+
+    async fn get_student(&self, id: &StudentId) -> Result<Option<Student>, StoreError> {
+        let id = id.clone();
+        self.call(move |conn| {
+            conn.query_row(
+                &format!("SELECT {} FROM students WHERE student_id = ?1", STUDENT_COLUMNS),
+                params![id.as_str()],
+                row_to_student,
+            )
+            .optional()
+        })
+        .await
+    }
+
+    async fn create_student(&self, id: StudentId, student: Student) -> Result<bool, StoreError> {
+        self.call(move |conn| {
+            let inserted = conn.execute(
+                &format!("INSERT OR IGNORE INTO students ({}) VALUES (?1, ?2, ?3)", STUDENT_COLUMNS),
+                params![id.as_str(), student.name, student.email],
+            )?;
+            Ok(inserted > 0)
+        })
+        .await
+    }
+
+    async fn list_students(&self) -> Result<Vec<Student>, StoreError> {
+        self.call(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM students ORDER BY student_id",
+                STUDENT_COLUMNS
+            ))?;
+            let rows = stmt.query_map([], row_to_student)?;
+            rows.collect()
+        })
+        .await
+    }
+
+    async fn append_audit(&self, mut record: AuditRecord) -> Result<AuditRecord, StoreError> {
+        self.call(move |conn| {
+            conn.execute(
+                "INSERT INTO audit_log (recorded_at_ms, actor, role, action, student_id, exam_id, before, after, reason, request_id)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                params![
+                    record.recorded_at_ms,
+                    record.actor,
+                    record.role,
+                    record.action,
+                    record.student_id,
+                    record.exam_id,
+                    snapshot_to_json(&record.before)?,
+                    snapshot_to_json(&record.after)?,
+                    record.reason,
+                    record.request_id,
+                ],
+            )?;
+
+            record.sequence = conn.last_insert_rowid();
+            Ok(record)
+        })
+        .await
+    }
+
+    async fn audit_trail(
+        &self,
+        student_id: &StudentId,
+        exam_id: Option<&ExamId>,
+    ) -> Result<Vec<AuditRecord>, StoreError> {
+        let student_id = student_id.clone();
+        let exam_id = exam_id.cloned();
+        self.call(move |conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM audit_log WHERE student_id = ?1 AND (?2 IS NULL OR exam_id = ?2) ORDER BY sequence",
+                AUDIT_COLUMNS
+            ))?;
+            let rows = stmt.query_map(
+                params![student_id.as_str(), exam_id.as_ref().map(ExamId::as_str)],
+                row_to_audit,
+            )?;
+            rows.collect()
+        })
+        .await
+    }
+
+    async fn create_appeal(&self, mut appeal: Appeal) -> Result<Option<Appeal>, StoreError> {
+        self.call(move |conn| {
+            let tx = conn.transaction()?;
+
+            let unresolved: bool = tx.query_row(
+                "SELECT EXISTS (SELECT 1 FROM appeals WHERE student_id = ?1 AND exam_id = ?2 AND state != ?3)",
+                params![appeal.student_id, appeal.exam_id, AppealState::Resolved as i32],
+                |row| row.get(0),
+            )?;
+            if unresolved {
+                return Ok(None);
+            }
+
+            tx.execute(
+                "INSERT INTO appeals (student_id, exam_id, reason, state, original_marks, filed_by, filed_at_ms,
+                     reviewer, approved, revised_marks, resolution, resolved_at_ms)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                params![
+                    appeal.student_id,
+                    appeal.exam_id,
+                    appeal.reason,
+                    appeal.state,
+                    appeal.original_marks,
+                    appeal.filed_by,
+                    appeal.filed_at_ms,
+                    appeal.reviewer,
+                    appeal.approved,
+                    appeal.revised_marks,
+                    appeal.resolution,
+                    appeal.resolved_at_ms,
+                ],
+            )?;
+            appeal.appeal_id = tx.last_insert_rowid();
+
+            tx.commit()?;
+            Ok(Some(appeal))
+        })
+        .await
+    }
+
+    async fn get_appeal(&self, id: i64) -> Result<Option<Appeal>, StoreError> {
+        self.call(move |conn| {
+            conn.query_row(
+                &format!("SELECT {} FROM appeals WHERE appeal_id = ?1", APPEAL_COLUMNS),
+                params![id],
+                row_to_appeal,
+            )
+            .optional()
+        })
+        .await
+    }
+
+    async fn list_appeals(
+        &self,
+        student_id: Option<&StudentId>,
+        exam_id: Option<&ExamId>,
+    ) -> Result<Vec<Appeal>, StoreError> {
+        let student_id = student_id.cloned();
+        let exam_id = exam_id.cloned();
+        self.call(move |conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM appeals WHERE (?1 IS NULL OR student_id = ?1) AND (?2 IS NULL OR exam_id = ?2)
+                 ORDER BY appeal_id",
+                APPEAL_COLUMNS
+            ))?;
+            let rows = stmt.query_map(
+                params![student_id.as_ref().map(StudentId::as_str), exam_id.as_ref().map(ExamId::as_str)],
+                row_to_appeal,
+            )?;
+            rows.collect()
+        })
+        .await
+    }
+
+    async fn update_appeal(&self, appeal: Appeal, expected: AppealState) -> Result<bool, StoreError> {
+        self.call(move |conn| {
+            let updated = conn.execute(
+                "UPDATE appeals SET reason = ?2, state = ?3, original_marks = ?4, filed_by = ?5, filed_at_ms = ?6,
+                     reviewer = ?7, approved = ?8, revised_marks = ?9, resolution = ?10, resolved_at_ms = ?11
+                 WHERE appeal_id = ?1 AND state = ?12",
+                params![
+                    appeal.appeal_id,
+                    appeal.reason,
+                    appeal.state,
+                    appeal.original_marks,
+                    appeal.filed_by,
+                    appeal.filed_at_ms,
+                    appeal.reviewer,
+                    appeal.approved,
+                    appeal.revised_marks,
+                    appeal.resolution,
+                    appeal.resolved_at_ms,
+                    expected as i32,
+                ],
+            )?;
+            Ok(updated == 1)
+        })
+        .await
+    }
+
+    async fn create_webhook(&self, mut webhook: Webhook) -> Result<Webhook, StoreError> {
+        self.call(move |conn| {
+            let events = serde_json::to_string(&webhook.events)
+                .map_err(|err| rusqlite::Error::ToSqlConversionFailure(Box::new(err)))?;
+            conn.execute(
+                "INSERT INTO webhooks (url, events, secret, created_by, created_at_ms) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![webhook.url, events, webhook.secret, webhook.created_by, webhook.created_at_ms],
+            )?;
+
+            webhook.webhook_id = conn.last_insert_rowid();
+            Ok(webhook)
+        })
+        .await
+    }
+
+    async fn list_webhooks(&self) -> Result<Vec<Webhook>, StoreError> {
+        self.call(|conn| {
+            let mut stmt = conn.prepare(&format!("SELECT {} FROM webhooks ORDER BY webhook_id", WEBHOOK_COLUMNS))?;
+            let rows = stmt.query_map([], row_to_webhook)?;
+            rows.collect()
+        })
+        .await
+    }
+
+    async fn delete_webhook(&self, id: i64) -> Result<bool, StoreError> {
+        self.call(move |conn| Ok(conn.execute("DELETE FROM webhooks WHERE webhook_id = ?1", params![id])? == 1))
+            .await
+    }
+
+    async fn set_graders(&self, graders: ExamGraders) -> Result<(), StoreError> {
+        self.call(move |conn| {
+            if graders.graders.is_empty() {
+                conn.execute("DELETE FROM exam_graders WHERE exam_id = ?1", params![graders.exam_id])?;
+                Ok(())
+            } else {
+                write_graders(conn, &graders)
+            }
+        })
+        .await
+    }
+
+    async fn get_graders(&self, exam_id: &ExamId) -> Result<Vec<String>, StoreError> {
+        let exam_id = exam_id.clone();
+        self.call(move |conn| {
+            let graders = conn
+                .query_row(
+                    &format!("SELECT {} FROM exam_graders WHERE exam_id = ?1", GRADER_COLUMNS),
+                    params![exam_id.as_str()],
+                    row_to_graders,
+                )
+                .optional()?;
+            Ok(graders.map(|graders| graders.graders).unwrap_or_default())
+        })
+        .await
+    }
+
+    // All or none, in one transaction.
+    async fn put_allocations(&self, allocations: Vec<Allocation>) -> Result<(), StoreError> {
+        self.call(move |conn| {
+            let tx = conn.transaction()?;
+            for allocation in &allocations {
+                write_allocation(&tx, allocation)?;
+            }
+            tx.commit()
+        })
+        .await
+    }
+
+    async fn list_allocations(
+        &self,
+        exam_id: Option<&ExamId>,
+        grader: Option<&str>,
+    ) -> Result<Vec<Allocation>, StoreError> {
+        let exam_id = exam_id.cloned();
+        let grader = grader.map(str::to_string);
+        self.call(move |conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM allocations WHERE (?1 IS NULL OR exam_id = ?1) AND (?2 IS NULL OR grader = ?2)
+                 ORDER BY student_id, exam_id",
+                ALLOCATION_COLUMNS
+            ))?;
+            let rows = stmt.query_map(params![exam_id.as_ref().map(ExamId::as_str), grader], row_to_allocation)?;
+            rows.collect()
+        })
+        .await
+    }
+
+    // Read in one transaction, so no write lands halfway through.
+    async fn dump(&self) -> Result<StoreContents, StoreError> {
+        self.call(|conn| {
+            let tx = conn.transaction()?;
+            let contents = StoreContents {
+                results: select_all(
+                    &tx,
+                    &format!("SELECT {} FROM exam_results ORDER BY student_id, exam_id", COLUMNS),
+                    row_to_result,
+                )?,
+                deleted: select_all(
+                    &tx,
+                    &format!("SELECT {} FROM deleted_results ORDER BY student_id, exam_id", DELETED_COLUMNS),
+                    row_to_deleted,
+                )?,
+                exams: select_all(&tx, &format!("SELECT {} FROM exams ORDER BY exam_id", EXAM_COLUMNS), row_to_exam)?,
+                students: select_all(
+                    &tx,
+                    &format!("SELECT {} FROM students ORDER BY student_id", STUDENT_COLUMNS),
+                    row_to_student,
+                )?,
+                audit: select_all(
+                    &tx,
+                    &format!("SELECT {} FROM audit_log ORDER BY sequence", AUDIT_COLUMNS),
+                    row_to_audit,
+                )?,
+                appeals: select_all(
+                    &tx,
+                    &format!("SELECT {} FROM appeals ORDER BY appeal_id", APPEAL_COLUMNS),
+                    row_to_appeal,
+                )?,
+                webhooks: select_all(
+                    &tx,
+                    &format!("SELECT {} FROM webhooks ORDER BY webhook_id", WEBHOOK_COLUMNS),
+                    row_to_webhook,
+                )?,
+                graders: select_all(
+                    &tx,
+                    &format!("SELECT {} FROM exam_graders ORDER BY exam_id", GRADER_COLUMNS),
+                    row_to_graders,
+                )?,
+                allocations: select_all(
+                    &tx,
+                    &format!("SELECT {} FROM allocations ORDER BY student_id, exam_id", ALLOCATION_COLUMNS),
+                    row_to_allocation,
+                )?,
+            };
+            tx.commit()?;
+            Ok(contents)
+        })
+        .await
+    }
+
+    // The audit log's triggers are dropped to empty it and recreated before
+    // committing, so it is append-only again whether or not the restore succeeds.
+    // AUTOINCREMENT counters are left alone: IDs handed out before the restore
+    // are still never reused.
+    async fn restore(&self, contents: StoreContents) -> Result<(), StoreError> {
+        self.call(move |conn| {
+            let tx = conn.transaction()?;
+            tx.execute_batch(
+                "DROP TRIGGER audit_log_no_update;
+                DROP TRIGGER audit_log_no_delete;
+                DELETE FROM exam_results;
+                DELETE FROM deleted_results;
+                DELETE FROM exams;
+                DELETE FROM students;
+                DELETE FROM audit_log;
+                DELETE FROM appeals;
+                DELETE FROM webhooks;
+                DELETE FROM exam_graders;
+                DELETE FROM allocations;",
+            )?;
+            tx.execute_batch(AUDIT_TRIGGERS)?;
+
+            insert_contents(&tx, &contents)?;
+
+            tx.commit()
+        })
+        .await
+    }
+
+    // Waits for in-flight queries (they hold the connection lock) and writes
+    // any dirty pages still held in SQLite's cache.
+    async fn flush(&self) -> Result<(), StoreError> {
+        self.call(|conn| conn.cache_flush()).await
+    }
+}
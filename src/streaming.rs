@@ -0,0 +1,175 @@
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+use tokio::task::AbortHandle;
+use tokio_stream::Stream;
+use tonic::{Request, Status};
+use tracing::{debug, warn, Instrument, Span};
+
+use crate::admin::{Connection, ListStreamsResponse, OpenStream};
+use crate::auth::Identity;
+use crate::config::StreamConfig;
+use crate::schedule::now_ms;
+
+// The messages of a server stream, produced by a task of their own. The
+// client sees them at its own pace: once `channel_buffer` are waiting, the
+// producer waits too. Dropping the stream, which tonic does as soon as the
+// client cancels or disconnects, aborts the producer wherever it is.
+pub struct ResponseStream<T> {
+    messages: mpsc::Receiver<Result<T, Status>>,
+    producer: AbortHandle,
+}
+
+impl<T> Stream for ResponseStream<T> {
+    type Item = Result<T, Status>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.messages.poll_recv(cx)
+    }
+}
+
+impl<T> Drop for ResponseStream<T> {
+    fn drop(&mut self) {
+        // A producer that already finished is unaffected
+        self.producer.abort();
+    }
+}
+
+// Spawns the producers of server streams, holding each connection to
+// `max_per_connection` streams open at once, and keeps track of the streams
+// open for the admin service.
+#[derive(Debug, Clone)]
+pub(crate) struct Streams {
+    config: StreamConfig,
+    open: Arc<Mutex<OpenStreams>>,
+}
+
+#[derive(Debug, Default)]
+struct OpenStreams {
+    next_id: u64,
+    // Oldest first, since IDs only grow
+    streams: BTreeMap<u64, OpenStream>,
+    // Counts by client connection; a connection is gone from here once its last stream ends
+    connections: HashMap<SocketAddr, usize>,
+}
+
+impl Streams {
+    pub(crate) fn new(config: StreamConfig) -> Self {
+        Self {
+            config,
+            open: Arc::default(),
+        }
+    }
+
+    // Reserves a stream of `method` for the caller's connection, failing with
+    // `RESOURCE_EXHAUSTED` while it has `max_per_connection` open. Calls that
+    // did not arrive over a connection, like the gateway's, are not limited.
+    pub(crate) fn reserve<B>(&self, request: &Request<B>, method: &str) -> Result<StreamSlot, Status> {
+        let limit = self.config.max_per_connection;
+        let peer = request.remote_addr();
+
+        let mut open = self.open.lock().expect("open streams lock");
+        if let Some(peer) = peer.filter(|_| limit > 0) {
+            let count = open.connections.get(&peer).copied().unwrap_or_default();
+            if count >= limit {
+                warn!(%peer, limit, "too many open streams on one connection");
+                return Err(Status::resource_exhausted(format!(
+                    "This connection already has {} streams open; close one first",
+                    limit
+                )));
+            }
+        }
+
+        if let Some(peer) = peer {
+            *open.connections.entry(peer).or_default() += 1;
+        }
+        let identity = request.extensions().get::<Identity>();
+        let stream = OpenStream {
+            method: method.to_string(),
+            peer: peer.map(|peer| peer.to_string()).unwrap_or_default(),
+            caller: identity.map(|identity| identity.name.clone()).unwrap_or_default(),
+            institution_id: identity.map(|identity| identity.institution.to_string()).unwrap_or_default(),
+            opened_at_ms: now_ms(),
+        };
+        let id = open.next_id;
+        open.next_id += 1;
+        open.streams.insert(id, stream);
+
+        Ok(StreamSlot {
+            open: self.open.clone(),
+            id,
+            peer,
+        })
+    }
+
+    // The streams open right now, oldest first, and the connections they are open on.
+    pub(crate) fn list(&self) -> ListStreamsResponse {
+        let open = self.open.lock().expect("open streams lock");
+        let mut connections: Vec<_> = open
+            .connections
+            .iter()
+            .map(|(peer, count)| Connection {
+                peer: peer.to_string(),
+                open_streams: *count as u32,
+            })
+            .collect();
+        connections.sort_by(|a, b| a.peer.cmp(&b.peer));
+
+        ListStreamsResponse {
+            streams: open.streams.values().cloned().collect(),
+            connections,
+        }
+    }
+
+    // Runs `produce` in its own task, in the current span, and streams what it
+    // sends. The slot is given back when the producer ends or is aborted.
+    pub(crate) fn spawn<T, F, Fut>(&self, slot: StreamSlot, produce: F) -> ResponseStream<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(mpsc::Sender<Result<T, Status>>) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let (tx, messages) = mpsc::channel(self.config.channel_buffer.max(1));
+        let producing = produce(tx);
+
+        let producer = tokio::spawn(
+            async move {
+                let _slot = slot;
+                producing.await;
+            }
+            .instrument(Span::current()),
+        );
+
+        ResponseStream {
+            messages,
+            producer: producer.abort_handle(),
+        }
+    }
+}
+
+// One open stream, counted against its connection's limit, until dropped.
+pub(crate) struct StreamSlot {
+    open: Arc<Mutex<OpenStreams>>,
+    id: u64,
+    peer: Option<SocketAddr>,
+}
+
+impl Drop for StreamSlot {
+    fn drop(&mut self) {
+        let mut open = self.open.lock().expect("open streams lock");
+        open.streams.remove(&self.id);
+
+        let Some(peer) = self.peer else { return };
+        if let Some(count) = open.connections.get_mut(&peer) {
+            *count -= 1;
+            if *count == 0 {
+                open.connections.remove(&peer);
+                debug!(%peer, "connection has no open streams");
+            }
+        }
+    }
+}
@@ -0,0 +1,482 @@
+use std::env;
+use std::error::Error;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Instant;
+use bytes::Bytes;
+use http::{HeaderMap, HeaderName, HeaderValue, Request, Response};
+use http_body::{Body, Frame, SizeHint};
+use opentelemetry::propagation::{Extractor, Injector};
+use opentelemetry::trace::TracerProvider;
+use opentelemetry::global;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::runtime;
+use opentelemetry_sdk::trace::span_processor_with_async_runtime::BatchSpanProcessor;
+use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider};
+use opentelemetry_sdk::Resource;
+use pin_project_lite::pin_project;
+use tonic::body::BoxBody;
+use tonic::transport::server::{TcpConnectInfo, TlsConnectInfo};
+use tower::{Layer, Service};
+use tracing::{error, field, info, info_span, Instrument, Span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer as _};
+
+use crate::config::TelemetryConfig;
+use crate::metrics::Metrics;
+
+// Set to "json" to emit newline-delimited JSON logs for a log collector.
+pub const LOG_FORMAT_ENV: &str = "EXAM_LOG_FORMAT";
+
+// Correlates one call across client and server logs: taken from the request
+// if the client sent one, generated otherwise, and echoed on the response.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+// Longest client-chosen request ID kept; longer ones are replaced.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+// Installs the global subscriber: logs to stdout, and spans to the OTLP
+// collector if `telemetry` names one. Verbosity follows RUST_LOG (default "info").
+pub fn init_tracing(telemetry: &TelemetryConfig) -> Result<Tracing, Box<dyn Error>> {
+    let logs = match env::var(LOG_FORMAT_ENV).as_deref() {
+        Ok("json") => tracing_subscriber::fmt::layer().json().with_current_span(true).boxed(),
+        _ => tracing_subscriber::fmt::layer().boxed(),
+    };
+    install(telemetry, Some(logs))
+}
+
+// Like `init_tracing`, without the logs: for the CLI, whose output is its results.
+pub fn init_trace_export(telemetry: &TelemetryConfig) -> Result<Tracing, Box<dyn Error>> {
+    install(telemetry, None)
+}
+
+type BoxedLayer = Box<dyn tracing_subscriber::Layer<tracing_subscriber::Registry> + Send + Sync>;
+
+fn install(telemetry: &TelemetryConfig, logs: Option<BoxedLayer>) -> Result<Tracing, Box<dyn Error>> {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let provider = match telemetry.otlp_endpoint.as_str() {
+        "" => None,
+        endpoint => Some(tracer_provider(endpoint, telemetry)?),
+    };
+    let export = provider
+        .as_ref()
+        .map(|provider| tracing_opentelemetry::layer().with_tracer(provider.tracer(env!("CARGO_PKG_NAME"))));
+
+    // Filtered last, so neither logs nor exported spans go below RUST_LOG
+    tracing_subscriber::registry()
+        .with(logs)
+        .with(export)
+        .with(filter)
+        .try_init()?;
+
+    if provider.is_some() {
+        info!(endpoint = %telemetry.otlp_endpoint, sample_ratio = telemetry.sample_ratio, "exporting traces over OTLP");
+    }
+    Ok(Tracing { provider })
+}
+
+fn tracer_provider(endpoint: &str, telemetry: &TelemetryConfig) -> Result<SdkTracerProvider, Box<dyn Error>> {
+    if !(0.0..=1.0).contains(&telemetry.sample_ratio) {
+        return Err(format!("telemetry.sample_ratio must be between 0 and 1, got {}", telemetry.sample_ratio).into());
+    }
+
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_tonic()
+        .with_endpoint(endpoint)
+        .build()?;
+
+    Ok(SdkTracerProvider::builder()
+        // Batched on the tokio runtime, since the tonic exporter needs one
+        .with_span_processor(BatchSpanProcessor::builder(exporter, runtime::Tokio).build())
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+            telemetry.sample_ratio,
+        ))))
+        .with_resource(Resource::builder().with_service_name(telemetry.service_name.clone()).build())
+        .build())
+}
+
+// Keeps the span exporter running; `shutdown` sends the spans still buffered.
+#[derive(Debug)]
+pub struct Tracing {
+    provider: Option<SdkTracerProvider>,
+}
+
+impl Tracing {
+    pub async fn shutdown(self) {
+        let Some(provider) = self.provider else {
+            return;
+        };
+        // Blocks until the exporter has been flushed
+        match tokio::task::spawn_blocking(move || provider.shutdown()).await {
+            Ok(Ok(())) => {}
+            Ok(Err(err)) => error!(%err, "exporting the last traces failed"),
+            Err(err) => error!(%err, "exporting the last traces panicked"),
+        }
+    }
+}
+
+// Reads and writes W3C trace context in HTTP headers, which is what gRPC metadata is.
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(HeaderName::as_str).collect()
+    }
+}
+
+struct HeaderInjector<'a>(&'a mut HeaderMap);
+
+impl Injector for HeaderInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(key), Ok(value)) = (HeaderName::try_from(key), HeaderValue::try_from(value)) {
+            self.0.insert(key, value);
+        }
+    }
+}
+
+// Tower layer that opens a span per RPC recording the method, peer address,
+// request ID, final gRPC status code, and latency (measured until the response
+// body ends, so streaming RPCs report their full duration). The same outcome
+// feeds `Metrics`. The request ID is echoed in the response headers and, for
+// streams, the trailers, so it reaches the client however the call ends.
+// A caller's `traceparent` makes the span part of the caller's trace.
+#[derive(Debug, Clone)]
+pub struct RpcTraceLayer {
+    metrics: Metrics,
+}
+
+impl RpcTraceLayer {
+    pub fn new(metrics: Metrics) -> Self {
+        Self { metrics }
+    }
+}
+
+impl<S> Layer<S> for RpcTraceLayer {
+    type Service = RpcTrace<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RpcTrace {
+            inner,
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RpcTrace<S> {
+    inner: S,
+    metrics: Metrics,
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for RpcTrace<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    ReqBody: Send + 'static,
+{
+    type Response = Response<TracedBody<ResBody>>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<ReqBody>) -> Self::Future {
+        let method = request.uri().path().to_string();
+        let metrics = self.metrics.clone();
+        let request_id = ensure_request_id(request.headers_mut());
+        let span = info_span!(
+            "rpc",
+            method = %method,
+            peer = %peer_addr(&request),
+            request_id = request_id.to_str().unwrap_or_default(),
+            status = field::Empty,
+            latency_ms = field::Empty,
+            otel.name = method.trim_start_matches('/'),
+            otel.kind = "server",
+            otel.status_code = field::Empty,
+            rpc.system = "grpc",
+        );
+        span.set_parent(global::get_text_map_propagator(|propagator| {
+            propagator.extract(&HeaderExtractor(request.headers()))
+        }));
+        let start = Instant::now();
+
+        // Take the service that was polled ready and leave a fresh clone behind
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        Box::pin(
+            async move {
+                let mut response = inner.call(request).await?;
+                response.headers_mut().insert(REQUEST_ID_HEADER, request_id.clone());
+                let mut tracker = CompletionTracker {
+                    span: Span::current(),
+                    method,
+                    metrics,
+                    start,
+                    finished: false,
+                };
+
+                // Errors returned by handlers arrive as trailers-only responses
+                if let Some(code) = grpc_status(response.headers()) {
+                    tracker.finish(&code);
+                }
+
+                Ok(response.map(|body| TracedBody {
+                    inner: body,
+                    tracker,
+                    request_id,
+                }))
+            }
+            .instrument(span),
+        )
+    }
+}
+
+// The request ID the client sent in `headers`, or a new one if it sent none
+// usable, left in `headers` for the handlers to read.
+pub(crate) fn ensure_request_id(headers: &mut HeaderMap) -> HeaderValue {
+    let sent = headers.get(REQUEST_ID_HEADER).filter(|id| {
+        // Visible ASCII only, so it can be quoted in logs verbatim
+        !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN && id.as_bytes().iter().all(u8::is_ascii_graphic)
+    });
+
+    let id = match sent {
+        Some(id) => id.clone(),
+        None => HeaderValue::from_str(&format!("{:032x}", rand::random::<u128>())).expect("hex is a valid header"),
+    };
+    headers.insert(REQUEST_ID_HEADER, id.clone());
+    id
+}
+
+// The client's address, whether it connected over plain TCP or TLS.
+pub(crate) fn remote_addr<B>(request: &Request<B>) -> Option<SocketAddr> {
+    let extensions = request.extensions();
+    extensions
+        .get::<TcpConnectInfo>()
+        .and_then(|info| info.remote_addr())
+        .or_else(|| {
+            extensions
+                .get::<TlsConnectInfo<TcpConnectInfo>>()
+                .and_then(|info| info.get_ref().remote_addr())
+        })
+}
+
+fn peer_addr<B>(request: &Request<B>) -> String {
+    remote_addr(request)
+        .map(|addr| addr.to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+fn grpc_status(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get("grpc-status")
+        .and_then(|value| value.to_str().ok())
+        .map(code_name)
+}
+
+// Names numeric gRPC status codes the way tonic::Code does ("NotFound", ...).
+fn code_name(code: &str) -> String {
+    match code.parse::<i32>() {
+        Ok(code) => format!("{:?}", tonic::Code::from(code)),
+        Err(_) => code.to_string(),
+    }
+}
+
+// Records the outcome of an RPC exactly once: on the status trailer,
+// at end of stream, or when the response is dropped early.
+struct CompletionTracker {
+    span: Span,
+    method: String,
+    metrics: Metrics,
+    start: Instant,
+    finished: bool,
+}
+
+impl CompletionTracker {
+    fn finish(&mut self, status: &str) {
+        if self.finished {
+            return;
+        }
+        self.finished = true;
+
+        let latency = self.start.elapsed();
+        self.metrics.record(&self.method, status, latency);
+
+        let latency_ms = latency.as_secs_f64() * 1000.0;
+        self.span.record("status", status);
+        self.span.record("latency_ms", latency_ms);
+        if status != "Ok" {
+            self.span.record("otel.status_code", "ERROR");
+        }
+        info!(parent: &self.span, status, latency_ms, "rpc finished");
+    }
+}
+
+impl Drop for CompletionTracker {
+    fn drop(&mut self) {
+        self.finish("Cancelled");
+    }
+}
+
+pin_project! {
+    // Response body wrapper that watches for the gRPC status trailer.
+    pub struct TracedBody<B> {
+        #[pin]
+        inner: B,
+        tracker: CompletionTracker,
+        request_id: HeaderValue,
+    }
+}
+
+impl<B: Body> Body for TracedBody<B> {
+    type Data = B::Data;
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+        let mut frame = std::task::ready!(this.inner.poll_frame(cx));
+
+        match &mut frame {
+            Some(Ok(frame)) => {
+                if let Some(trailers) = frame.trailers_mut() {
+                    trailers.insert(REQUEST_ID_HEADER, this.request_id.clone());
+                    if let Some(code) = grpc_status(trailers) {
+                        this.tracker.finish(&code);
+                    }
+                }
+            }
+            Some(Err(_)) => this.tracker.finish("TransportError"),
+            None => this.tracker.finish("Ok"),
+        }
+
+        Poll::Ready(frame)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+// Client-side counterpart of `RpcTrace`: opens an `rpc.client` span per
+// outbound call, child of whatever span the caller is in, and sends its trace
+// context as `traceparent` so the server's span joins the same trace. The span
+// lasts until the response body is dropped, so streams are timed in full.
+#[derive(Debug, Clone)]
+pub struct ClientTrace<S> {
+    inner: S,
+}
+
+impl<S> ClientTrace<S> {
+    pub fn new(inner: S) -> Self {
+        Self { inner }
+    }
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for ClientTrace<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    S::Future: Send + 'static,
+    ResBody: Body<Data = Bytes> + Send + 'static,
+    ResBody::Error: Into<Box<dyn Error + Send + Sync>>,
+{
+    // Boxed, as tonic's interceptors expect of the services they wrap
+    type Response = Response<BoxBody>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<ReqBody>) -> Self::Future {
+        let method = request.uri().path().trim_start_matches('/').to_string();
+        let span = info_span!(
+            "rpc.client",
+            method = %method,
+            otel.name = %method,
+            otel.kind = "client",
+            otel.status_code = field::Empty,
+            rpc.system = "grpc",
+        );
+        global::get_text_map_propagator(|propagator| {
+            propagator.inject_context(&span.context(), &mut HeaderInjector(request.headers_mut()))
+        });
+
+        let response = self.inner.call(request);
+        Box::pin(async move {
+            let response = match response.instrument(span.clone()).await {
+                Ok(response) => response,
+                Err(err) => {
+                    span.record("otel.status_code", "ERROR");
+                    return Err(err);
+                }
+            };
+            if grpc_status(response.headers()).is_some_and(|code| code != "Ok") {
+                span.record("otel.status_code", "ERROR");
+            }
+            Ok(response.map(|body| tonic::body::boxed(ClientTracedBody { inner: body, span })))
+        })
+    }
+}
+
+pin_project! {
+    // Response body wrapper that holds the client span open until the body is done with.
+    struct ClientTracedBody<B> {
+        #[pin]
+        inner: B,
+        span: Span,
+    }
+}
+
+impl<B: Body> Body for ClientTracedBody<B> {
+    type Data = B::Data;
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+        let frame = std::task::ready!(this.inner.poll_frame(cx));
+
+        let failed = match &frame {
+            Some(Ok(frame)) => frame.trailers_ref().and_then(grpc_status).is_some_and(|code| code != "Ok"),
+            Some(Err(_)) => true,
+            None => false,
+        };
+        if failed {
+            this.span.record("otel.status_code", "ERROR");
+        }
+
+        Poll::Ready(frame)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
@@ -0,0 +1,62 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tonic::Status;
+
+use crate::auth::Identity;
+use crate::errors::not_found;
+use crate::key::InstitutionId;
+use crate::store::{ExamStore, StoreError};
+
+// One store per institution served. Handlers only reach a store through the
+// caller's identity, so a request can never read or write another
+// institution's results, catalog, roster, audit log, appeals or webhooks.
+#[derive(Debug)]
+pub struct Tenants<S> {
+    stores: Arc<BTreeMap<InstitutionId, Arc<S>>>,
+}
+
+// Cloning shares the stores, whatever `S` is.
+impl<S> Clone for Tenants<S> {
+    fn clone(&self) -> Self {
+        Self {
+            stores: self.stores.clone(),
+        }
+    }
+}
+
+impl<S: ExamStore> Tenants<S> {
+    pub fn new(stores: impl IntoIterator<Item = (InstitutionId, S)>) -> Self {
+        Self {
+            stores: Arc::new(stores.into_iter().map(|(id, store)| (id, Arc::new(store))).collect()),
+        }
+    }
+
+    // The store of `institution`, or NOT_FOUND if this deployment does not serve it.
+    pub fn get(&self, institution: &InstitutionId) -> Result<&Arc<S>, Status> {
+        self.stores.get(institution).ok_or_else(|| {
+            not_found(
+                "institution",
+                institution,
+                format!("Institution {} is not served here", institution),
+            )
+        })
+    }
+
+    // The store of the caller's institution.
+    pub fn for_caller(&self, identity: &Identity) -> Result<&Arc<S>, Status> {
+        self.get(&identity.institution)
+    }
+
+    // Every institution served, with its store, in ID order.
+    pub fn iter(&self) -> impl Iterator<Item = (&InstitutionId, &Arc<S>)> {
+        self.stores.iter()
+    }
+
+    // Flushes every store, stopping at the first failure.
+    pub async fn flush(&self) -> Result<(), StoreError> {
+        for store in self.stores.values() {
+            store.flush().await?;
+        }
+        Ok(())
+    }
+}
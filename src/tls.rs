@@ -0,0 +1,28 @@
+use std::error::Error;
+use std::fs;
+use tonic::transport::{Certificate, Identity, ServerTlsConfig};
+use tracing::info;
+
+use crate::config::TlsConfig;
+
+// Builds the server TLS config from the configured PEM files.
+// Returns None (plaintext) when neither certificate nor key is configured.
+pub fn server_tls(tls: &TlsConfig) -> Result<Option<ServerTlsConfig>, Box<dyn Error>> {
+    let (cert_path, key_path) = match (&tls.cert, &tls.key) {
+        (Some(cert), Some(key)) => (cert, key),
+        (None, None) => return Ok(None),
+        _ => return Err("tls.cert and tls.key must be set together".into()),
+    };
+
+    let identity = Identity::from_pem(fs::read(cert_path)?, fs::read(key_path)?);
+    let mut config = ServerTlsConfig::new().identity(identity);
+
+    if let Some(ca_path) = &tls.client_ca {
+        // Only clients presenting a certificate signed by this CA can connect
+        config = config.client_ca_root(Certificate::from_pem(fs::read(ca_path)?));
+        info!(ca_path = %ca_path.display(), "mutual TLS enabled");
+    }
+
+    info!(cert_path = %cert_path.display(), "TLS enabled");
+    Ok(Some(config))
+}
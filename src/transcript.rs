@@ -0,0 +1,55 @@
+use crate::exam_service::{ExamResult, GetExamResultResponse, Transcript};
+use crate::student::Student;
+
+// Grade points on the common 4.0 scale, or None for unrecognised grades.
+fn grade_points(grade: &str) -> Option<f64> {
+    let points = match grade.trim().to_ascii_uppercase().as_str() {
+        "A+" | "A" => 4.0,
+        "A-" => 3.7,
+        "B+" => 3.3,
+        "B" => 3.0,
+        "B-" => 2.7,
+        "C+" => 2.3,
+        "C" => 2.0,
+        "C-" => 1.7,
+        "D+" => 1.3,
+        "D" => 1.0,
+        "D-" => 0.7,
+        "E" | "F" => 0.0,
+        _ => return None,
+    };
+
+    Some(points)
+}
+
+// Builds a transcript from a student's results. `redact` is applied to each
+// result so the transcript exposes no more than GetExamResult would.
+pub fn transcript(
+    student: &Student,
+    results: Vec<ExamResult>,
+    redact: impl Fn(GetExamResultResponse) -> GetExamResultResponse,
+) -> Transcript {
+    // Results with grades outside the scale still count towards the weighted average
+    let points: Vec<f64> = results.iter().filter_map(|result| grade_points(&result.grade)).collect();
+    let gpa = if points.is_empty() {
+        0.0
+    } else {
+        points.iter().sum::<f64>() / points.len() as f64
+    };
+
+    let obtained: i64 = results.iter().map(|result| result.marks_obtained as i64).sum();
+    let available: i64 = results.iter().map(|result| result.total_marks as i64).sum();
+    let weighted_average = if available > 0 {
+        obtained as f64 * 100.0 / available as f64
+    } else {
+        0.0
+    };
+
+    Transcript {
+        student_id: student.student_id.clone(),
+        student_name: student.name.clone(),
+        results: results.into_iter().map(|result| redact(result.into())).collect(),
+        gpa,
+        weighted_average,
+    }
+}
@@ -0,0 +1,154 @@
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::{TcpListener, TcpStream};
+use futures::stream::{self, BoxStream};
+use futures::{Stream, StreamExt};
+use tonic::transport::server::{Connected, TcpConnectInfo, TcpIncoming};
+
+use crate::config::ConnectionConfig;
+
+#[cfg(unix)]
+use std::path::Path;
+#[cfg(unix)]
+use tokio::net::{UnixListener, UnixStream};
+#[cfg(unix)]
+use tokio_stream::wrappers::UnixListenerStream;
+
+// Where the gRPC server accepts connections: a TCP listener, a Unix domain
+// socket, or both. A bare `TcpListener` converts into one, so callers that only
+// serve TCP pass their listener as before.
+#[derive(Debug, Default)]
+pub struct Listeners {
+    tcp: Option<TcpListener>,
+    #[cfg(unix)]
+    unix: Option<UnixListener>,
+}
+
+impl Listeners {
+    // Also accepts connections on `listener`, e.g. one bound by `bind_unix`.
+    #[cfg(unix)]
+    pub fn with_unix(mut self, listener: UnixListener) -> Self {
+        self.unix = Some(listener);
+        self
+    }
+
+    // What is listened on, for the startup log.
+    pub(crate) fn describe(&self) -> String {
+        let mut endpoints = Vec::new();
+        if let Some(addr) = self.tcp.as_ref().and_then(|listener| listener.local_addr().ok()) {
+            endpoints.push(addr.to_string());
+        }
+        #[cfg(unix)]
+        if let Some(path) = self.unix.as_ref().and_then(|listener| listener.local_addr().ok()) {
+            endpoints.push(format!("unix:{}", path.as_pathname().unwrap_or(Path::new("?")).display()));
+        }
+        endpoints.join(", ")
+    }
+
+    // Every accepted connection, whichever listener it came in on. TCP
+    // connections get the configured keepalive and nodelay settings.
+    pub(crate) fn incoming(self, config: &ConnectionConfig) -> io::Result<impl Stream<Item = io::Result<Connection>>> {
+        let mut incoming: Vec<BoxStream<'static, io::Result<Connection>>> = Vec::new();
+        if let Some(listener) = self.tcp {
+            let accepted = TcpIncoming::from_listener(listener, config.tcp_nodelay, config.tcp_keepalive())
+                .map_err(io::Error::other)?;
+            incoming.push(accepted.map(|accepted| accepted.map(Connection::Tcp)).boxed());
+        }
+        #[cfg(unix)]
+        if let Some(listener) = self.unix {
+            incoming.push(UnixListenerStream::new(listener).map(|accepted| accepted.map(Connection::Unix)).boxed());
+        }
+        Ok(stream::select_all(incoming))
+    }
+}
+
+impl From<TcpListener> for Listeners {
+    fn from(listener: TcpListener) -> Self {
+        Self {
+            tcp: Some(listener),
+            ..Default::default()
+        }
+    }
+}
+
+// Binds a Unix domain socket at `path`. A file left there by a server that did
+// not shut down cleanly is removed first; `remove_unix` cleans up after a clean one.
+#[cfg(unix)]
+pub fn bind_unix(path: &Path) -> io::Result<UnixListener> {
+    match std::fs::remove_file(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+        _ => {}
+    }
+    UnixListener::bind(path)
+}
+
+// Removes the socket file once the server has stopped listening on it.
+#[cfg(unix)]
+pub fn remove_unix(path: &Path) {
+    if let Err(err) = std::fs::remove_file(path) {
+        tracing::warn!(path = %path.display(), %err, "could not remove the Unix socket");
+    }
+}
+
+// A connection accepted on either kind of listener.
+#[derive(Debug)]
+pub(crate) enum Connection {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+// TCP peers keep their addresses, so rate limits and traces see them as
+// before; Unix socket peers have none, like a client with no known address.
+impl Connected for Connection {
+    type ConnectInfo = TcpConnectInfo;
+
+    fn connect_info(&self) -> Self::ConnectInfo {
+        match self {
+            Connection::Tcp(stream) => stream.connect_info(),
+            #[cfg(unix)]
+            Connection::Unix(_) => TcpConnectInfo {
+                local_addr: None,
+                remote_addr: None,
+            },
+        }
+    }
+}
+
+impl AsyncRead for Connection {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Connection::Tcp(stream) => Pin::new(stream).poll_read(cx, buf),
+            #[cfg(unix)]
+            Connection::Unix(stream) => Pin::new(stream).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for Connection {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Connection::Tcp(stream) => Pin::new(stream).poll_write(cx, buf),
+            #[cfg(unix)]
+            Connection::Unix(stream) => Pin::new(stream).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Connection::Tcp(stream) => Pin::new(stream).poll_flush(cx),
+            #[cfg(unix)]
+            Connection::Unix(stream) => Pin::new(stream).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Connection::Tcp(stream) => Pin::new(stream).poll_shutdown(cx),
+            #[cfg(unix)]
+            Connection::Unix(stream) => Pin::new(stream).poll_shutdown(cx),
+        }
+    }
+}
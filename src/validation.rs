@@ -0,0 +1,358 @@
+use tonic::{Code, Status};
+use tonic_types::{ErrorDetails, FieldViolation, StatusExt};
+
+use crate::exam_service::{
+    BatchGetExamResultsRequest, CorrectExamResultRequest, DeleteExamResultRequest, DownloadExportRequest, ExamResult, ExportResultsRequest, GetAuditTrailRequest,
+    GetExamResultRequest, GetExamStatisticsRequest, GetTranscriptRequest, ListExamResultsPageRequest,
+    ListExamResultsRequest, PublishExamResultsRequest, QuestionScore, SearchExamResultsRequest, SubmitExamResultRequest,
+    SubmitQuestionScoresRequest, UndeleteExamResultRequest, ListDeletedResultsRequest, WatchExamResultsRequest,
+    StartImportResultsRequest, StartPublishResultsRequest, WatchOperationRequest,
+};
+use crate::google::protobuf::FieldMask;
+use crate::idempotency::MAX_REQUEST_ID_LEN;
+use crate::key::id_problem;
+use crate::query::grade_rank;
+use crate::read_mask::path_problem;
+use crate::store::terms;
+
+// Most keys one BatchGetExamResults call may ask for.
+pub const MAX_BATCH_KEYS: usize = 100;
+
+// Matches SearchExamResults returns when no limit is given, and at most.
+pub const DEFAULT_SEARCH_LIMIT: usize = 20;
+pub const MAX_SEARCH_LIMIT: usize = 100;
+
+// Field-level checks on a request message, run by every handler before it
+// does anything else. Handlers still enforce the rules that need the store
+// (catalog totals, roster membership); these only look at the message.
+pub trait Validate {
+    fn validate(&self, violations: &mut Violations);
+}
+
+// Problems found in one request, each naming the offending field by its
+// path in the message, e.g. `result.questions[1].marks`.
+#[derive(Debug, Default)]
+pub struct Violations(Vec<FieldViolation>);
+
+impl Violations {
+    pub fn add(&mut self, field: impl Into<String>, description: impl Into<String>) {
+        self.0.push(FieldViolation::new(field, description));
+    }
+
+    fn id(&mut self, field: &str, value: &str) {
+        if let Some(problem) = id_problem(value) {
+            self.add(field, problem);
+        }
+    }
+
+    // Empty means unset, for the optional filters.
+    fn optional_id(&mut self, field: &str, value: &str) {
+        if !value.is_empty() {
+            self.id(field, value);
+        }
+    }
+
+    fn grade(&mut self, field: &str, value: &str) {
+        if !value.is_empty() && grade_rank(value).is_none() {
+            self.add(field, format!("{:?} is not a grade", value));
+        }
+    }
+
+    fn operation_id(&mut self, value: &str) {
+        if value.is_empty() {
+            self.add("operation_id", "must not be empty");
+        }
+    }
+
+    fn request_id(&mut self, value: &str) {
+        if value.len() > MAX_REQUEST_ID_LEN {
+            self.add("request_id", format!("must be at most {} characters", MAX_REQUEST_ID_LEN));
+        }
+    }
+
+    fn read_mask(&mut self, field: &str, mask: Option<&FieldMask>) {
+        for (index, path) in mask.map(|mask| mask.paths.as_slice()).unwrap_or_default().iter().enumerate() {
+            if let Some(problem) = path_problem(path) {
+                self.add(format!("{}.paths[{}]", field, index), problem);
+            }
+        }
+    }
+
+    fn questions(&mut self, prefix: &str, questions: &[QuestionScore]) {
+        for (index, question) in questions.iter().enumerate() {
+            let field = |name: &str| format!("{}questions[{}].{}", prefix, index, name);
+
+            if question.question_id.is_empty() {
+                self.add(field("question_id"), "is required");
+            }
+            if question.max_marks <= 0 {
+                self.add(field("max_marks"), format!("must be positive, got {}", question.max_marks));
+            }
+            if question.marks < 0 || question.marks > question.max_marks.max(0) {
+                self.add(
+                    field("marks"),
+                    format!("must be between 0 and max_marks ({}), got {}", question.max_marks, question.marks),
+                );
+            }
+        }
+    }
+
+    fn filter(&mut self, prefix: &str, filter: &ListExamResultsRequest) {
+        self.optional_id(&format!("{}student_id", prefix), &filter.student_id);
+        self.optional_id(&format!("{}exam_id", prefix), &filter.exam_id);
+        self.grade(&format!("{}min_grade", prefix), &filter.min_grade);
+        self.read_mask(&format!("{}read_mask", prefix), filter.read_mask.as_ref());
+    }
+
+    // INVALID_ARGUMENT listing every violation, with a google.rpc.BadRequest
+    // detail so clients can map them back to their form fields.
+    fn into_status(self) -> Result<(), Status> {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+
+        let message = self
+            .0
+            .iter()
+            .map(|violation| format!("{} {}", violation.field, violation.description))
+            .collect::<Vec<_>>()
+            .join("; ");
+
+        Err(Status::with_error_details(
+            Code::InvalidArgument,
+            message,
+            ErrorDetails::with_bad_request(self.0),
+        ))
+    }
+}
+
+// Checks `message`, failing with every violation found rather than the first.
+pub fn validate<T: Validate>(message: &T) -> Result<(), Status> {
+    let mut violations = Violations::default();
+    message.validate(&mut violations);
+    violations.into_status()
+}
+
+impl Validate for GetExamResultRequest {
+    // An empty exam_id asks GetExamResultStream for every exam
+    fn validate(&self, violations: &mut Violations) {
+        violations.id("student_id", &self.student_id);
+        violations.optional_id("exam_id", &self.exam_id);
+        violations.read_mask("read_mask", self.read_mask.as_ref());
+    }
+}
+
+impl Validate for BatchGetExamResultsRequest {
+    fn validate(&self, violations: &mut Violations) {
+        if self.keys.len() > MAX_BATCH_KEYS {
+            violations.add("keys", format!("must hold at most {} keys, got {}", MAX_BATCH_KEYS, self.keys.len()));
+        }
+        for (index, key) in self.keys.iter().enumerate() {
+            violations.id(&format!("keys[{}].student_id", index), &key.student_id);
+            violations.id(&format!("keys[{}].exam_id", index), &key.exam_id);
+        }
+        violations.read_mask("read_mask", self.read_mask.as_ref());
+    }
+}
+
+// Submitted results, alone or in a SubmitExamResult request.
+fn validate_result(prefix: &str, result: &ExamResult, violations: &mut Violations) {
+    let field = |name: &str| format!("{}{}", prefix, name);
+
+    violations.id(&field("student_id"), &result.student_id);
+    violations.id(&field("exam_id"), &result.exam_id);
+    violations.grade(&field("grade"), &result.grade);
+
+    // A zero total is filled in from the catalog
+    if result.total_marks < 0 {
+        violations.add(field("total_marks"), format!("must not be negative, got {}", result.total_marks));
+    }
+    if result.questions.is_empty()
+        && (result.marks_obtained < 0 || (result.total_marks > 0 && result.marks_obtained > result.total_marks))
+    {
+        violations.add(
+            field("marks_obtained"),
+            format!("must be between 0 and total_marks, got {}", result.marks_obtained),
+        );
+    }
+
+    violations.questions(prefix, &result.questions);
+}
+
+impl Validate for ExamResult {
+    fn validate(&self, violations: &mut Violations) {
+        validate_result("", self, violations);
+    }
+}
+
+impl Validate for SubmitExamResultRequest {
+    fn validate(&self, violations: &mut Violations) {
+        match &self.result {
+            Some(result) => validate_result("result.", result, violations),
+            None => violations.add("result", "is required"),
+        }
+        violations.request_id(&self.request_id);
+    }
+}
+
+impl Validate for SubmitQuestionScoresRequest {
+    fn validate(&self, violations: &mut Violations) {
+        violations.id("student_id", &self.student_id);
+        violations.id("exam_id", &self.exam_id);
+        if self.questions.is_empty() {
+            violations.add("questions", "is required");
+        }
+        violations.questions("", &self.questions);
+        violations.request_id(&self.request_id);
+    }
+}
+
+impl Validate for CorrectExamResultRequest {
+    fn validate(&self, violations: &mut Violations) {
+        violations.id("student_id", &self.student_id);
+        violations.id("exam_id", &self.exam_id);
+        violations.grade("grade", &self.grade);
+        if self.marks_obtained < 0 {
+            violations.add("marks_obtained", format!("must not be negative, got {}", self.marks_obtained));
+        }
+        if self.expected_version <= 0 {
+            violations.add("expected_version", "is required; read the result to get its current version");
+        }
+        violations.request_id(&self.request_id);
+    }
+}
+
+impl Validate for DeleteExamResultRequest {
+    fn validate(&self, violations: &mut Violations) {
+        violations.id("student_id", &self.student_id);
+        violations.id("exam_id", &self.exam_id);
+        violations.request_id(&self.request_id);
+    }
+}
+
+impl Validate for ListDeletedResultsRequest {
+    fn validate(&self, violations: &mut Violations) {
+        violations.optional_id("student_id", &self.student_id);
+        violations.optional_id("exam_id", &self.exam_id);
+    }
+}
+
+impl Validate for UndeleteExamResultRequest {
+    fn validate(&self, violations: &mut Violations) {
+        violations.id("student_id", &self.student_id);
+        violations.id("exam_id", &self.exam_id);
+        violations.request_id(&self.request_id);
+    }
+}
+
+impl Validate for ListExamResultsRequest {
+    fn validate(&self, violations: &mut Violations) {
+        violations.filter("", self);
+    }
+}
+
+impl Validate for ListExamResultsPageRequest {
+    fn validate(&self, violations: &mut Violations) {
+        if let Some(filter) = &self.filter {
+            violations.filter("filter.", filter);
+        }
+        if let Some(size) = self.page_size.filter(|size| *size <= 0) {
+            violations.add("page_size", format!("must be positive when set, got {}", size));
+        }
+    }
+}
+
+impl Validate for SearchExamResultsRequest {
+    fn validate(&self, violations: &mut Violations) {
+        if terms(&self.query).is_empty() {
+            violations.add("query", "must contain at least one letter or digit");
+        }
+        if let Some(limit) = self.limit.filter(|limit| *limit <= 0) {
+            violations.add("limit", format!("must be positive when set, got {}", limit));
+        }
+        violations.read_mask("read_mask", self.read_mask.as_ref());
+    }
+}
+
+impl Validate for ExportResultsRequest {
+    // Exports have fixed columns, so they cannot be masked
+    fn validate(&self, violations: &mut Violations) {
+        if let Some(filter) = &self.filter {
+            violations.filter("filter.", filter);
+            if filter.read_mask.is_some() {
+                violations.add("filter.read_mask", "is not supported by ExportResults");
+            }
+        }
+    }
+}
+
+impl Validate for GetExamStatisticsRequest {
+    fn validate(&self, violations: &mut Violations) {
+        violations.id("exam_id", &self.exam_id);
+    }
+}
+
+impl Validate for GetTranscriptRequest {
+    fn validate(&self, violations: &mut Violations) {
+        violations.id("student_id", &self.student_id);
+    }
+}
+
+impl Validate for WatchExamResultsRequest {
+    fn validate(&self, violations: &mut Violations) {
+        if self.student_id.is_empty() && self.exam_id.is_empty() {
+            violations.add("student_id", "or exam_id is required");
+        }
+        violations.optional_id("student_id", &self.student_id);
+        violations.optional_id("exam_id", &self.exam_id);
+    }
+}
+
+impl Validate for GetAuditTrailRequest {
+    fn validate(&self, violations: &mut Violations) {
+        violations.id("student_id", &self.student_id);
+        violations.optional_id("exam_id", &self.exam_id);
+    }
+}
+
+impl Validate for PublishExamResultsRequest {
+    fn validate(&self, violations: &mut Violations) {
+        violations.id("exam_id", &self.exam_id);
+        violations.request_id(&self.request_id);
+    }
+}
+
+// Records are checked one by one as the import runs, so a bad one is reported
+// among the operation's errors rather than failing the call.
+impl Validate for StartImportResultsRequest {
+    fn validate(&self, violations: &mut Violations) {
+        if self.results.is_empty() {
+            violations.add("results", "must hold at least one result");
+        }
+        violations.request_id(&self.request_id);
+    }
+}
+
+impl Validate for StartPublishResultsRequest {
+    fn validate(&self, violations: &mut Violations) {
+        if self.exam_ids.is_empty() {
+            violations.add("exam_ids", "must hold at least one exam ID");
+        }
+        for (index, exam_id) in self.exam_ids.iter().enumerate() {
+            violations.id(&format!("exam_ids[{}]", index), exam_id);
+        }
+        violations.request_id(&self.request_id);
+    }
+}
+
+impl Validate for WatchOperationRequest {
+    fn validate(&self, violations: &mut Violations) {
+        violations.operation_id(&self.operation_id);
+    }
+}
+
+impl Validate for DownloadExportRequest {
+    fn validate(&self, violations: &mut Violations) {
+        violations.operation_id(&self.operation_id);
+    }
+}
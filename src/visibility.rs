@@ -0,0 +1,62 @@
+use std::collections::HashMap;
+
+use crate::auth::Role;
+use crate::exam_service::{ExamResult, ResultStatus};
+use crate::key::ExamId;
+use crate::schedule::now_ms;
+use crate::store::{ExamStore, StoreError};
+
+// Which results a caller may see. Staff see every result, drafts included;
+// students only published results of exams whose grades are out. Each
+// exam's release time is looked up in the catalog once, unless `uncached`,
+// and exams missing from the catalog count as released.
+pub struct Visibility<'a, S> {
+    store: Option<&'a S>,
+    // None when every result looks the release time up afresh
+    released_at_ms: Option<HashMap<String, i64>>,
+}
+
+impl<'a, S: ExamStore> Visibility<'a, S> {
+    pub fn new(store: &'a S, role: Role) -> Self {
+        Self {
+            store: (role == Role::Student).then_some(store),
+            released_at_ms: Some(HashMap::new()),
+        }
+    }
+
+    // Looks each result's release time up when it is checked, for streams
+    // open long enough to see an exam's release postponed.
+    pub fn uncached(mut self) -> Self {
+        self.released_at_ms = None;
+        self
+    }
+
+    pub async fn shows(&mut self, result: &ExamResult) -> Result<bool, StoreError> {
+        let Some(store) = self.store else {
+            return Ok(true);
+        };
+
+        if result.status() != ResultStatus::Published {
+            return Ok(false);
+        }
+
+        let cached = self.released_at_ms.as_ref().and_then(|cache| cache.get(&result.exam_id));
+        let released_at_ms = match cached {
+            Some(&released_at_ms) => released_at_ms,
+            None => {
+                // Stored results always carry a valid exam ID
+                let released_at_ms = match ExamId::parse(&result.exam_id) {
+                    Ok(id) => store.get_exam(&id).await?.map_or(0, |exam| exam.grades_released_at_ms),
+                    Err(_) => 0,
+                };
+                if let Some(cache) = &mut self.released_at_ms {
+                    cache.insert(result.exam_id.clone(), released_at_ms);
+                }
+                released_at_ms
+            }
+        };
+
+        // Compared afresh each time, so a long-lived watch sees the release happen
+        Ok(now_ms() >= released_at_ms)
+    }
+}
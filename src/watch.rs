@@ -0,0 +1,55 @@
+use tokio::sync::{broadcast, watch};
+
+use crate::exam_service::{ChangeKind, ExamResult};
+use crate::key::InstitutionId;
+
+// One write to the results table, as seen by watchers.
+#[derive(Debug, Clone)]
+pub struct ResultEvent {
+    // Whose store was written; subscribers only act on their own institution's events
+    pub institution: InstitutionId,
+    pub kind: ChangeKind,
+    pub result: ExamResult,
+}
+
+// Fans result changes out from the write handlers to every open watch stream.
+// Publishing never blocks: watchers that fall more than `capacity` changes
+// behind are told so and dropped.
+#[derive(Debug, Clone)]
+pub struct ChangeFeed {
+    events: broadcast::Sender<ResultEvent>,
+    closed: watch::Sender<bool>,
+}
+
+impl ChangeFeed {
+    pub fn new(capacity: usize) -> Self {
+        let (events, _) = broadcast::channel(capacity);
+        let (closed, _) = watch::channel(false);
+        Self { events, closed }
+    }
+
+    // Sends a change to current subscribers; with none, the change is simply dropped.
+    pub fn publish(&self, institution: InstitutionId, kind: ChangeKind, result: ExamResult) {
+        let _ = self.events.send(ResultEvent {
+            institution,
+            kind,
+            result,
+        });
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<ResultEvent> {
+        self.events.subscribe()
+    }
+
+    // Resolves once `close` has been called, so watch streams can end during shutdown.
+    pub async fn closed(&self) {
+        let mut closed = self.closed.subscribe();
+        let _ = closed.wait_for(|closed| *closed).await;
+    }
+
+    // Ends every watch stream. Called when the server starts draining, since
+    // open-ended streams would otherwise hold shutdown until the drain timeout.
+    pub fn close(&self) {
+        self.closed.send_replace(true);
+    }
+}
@@ -0,0 +1,50 @@
+use std::error::Error;
+use std::time::Duration;
+use http::{header::HeaderName, HeaderValue, Method};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tracing::info;
+
+// How long browsers may cache a preflight response.
+const PREFLIGHT_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+// Request headers gRPC-Web clients send, including the bearer token.
+const ALLOWED_HEADERS: [&str; 6] = [
+    "authorization",
+    "content-type",
+    "grpc-timeout",
+    "x-grpc-web",
+    "x-request-id",
+    "x-user-agent",
+];
+// Trailers-as-headers the browser must be able to read to see the call status,
+// and the request ID to quote when reporting a failure.
+const EXPOSED_HEADERS: [&str; 4] = ["grpc-status", "grpc-message", "grpc-status-details-bin", "x-request-id"];
+
+// Builds the CORS policy for gRPC-Web from comma-separated `origins`,
+// or `*` for any origin. Empty means no cross-origin access.
+pub fn cors_layer(origins: &str) -> Result<CorsLayer, Box<dyn Error>> {
+    let allow_origin = match origins.trim() {
+        "*" => AllowOrigin::any(),
+        origins => {
+            let list = origins
+                .split(',')
+                .map(str::trim)
+                .filter(|origin| !origin.is_empty())
+                .map(HeaderValue::from_str)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("invalid CORS origin: {}", e))?;
+            AllowOrigin::list(list)
+        }
+    };
+
+    if !origins.trim().is_empty() {
+        info!(origins = %origins.trim(), "gRPC-Web CORS origins allowed");
+    }
+
+    Ok(CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods([Method::POST, Method::OPTIONS])
+        .allow_headers(ALLOWED_HEADERS.map(HeaderName::from_static))
+        .expose_headers(EXPOSED_HEADERS.map(HeaderName::from_static))
+        .max_age(PREFLIGHT_MAX_AGE))
+}
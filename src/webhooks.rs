@@ -0,0 +1,203 @@
+use std::time::Duration;
+use http::Uri;
+use rand::Rng;
+use tonic::{Request, Response, Status};
+use tracing::info;
+
+use crate::auth::Identity;
+use crate::deadline::{Deadline, DEFAULT_MAX_PROCESSING};
+use crate::errors::{invalid_field, missing_dependency, not_found};
+use crate::schedule::now_ms;
+use crate::store::ExamStore;
+use crate::tenancy::Tenants;
+use crate::webhook::webhook_service_server::WebhookService;
+use crate::webhook::{
+    DeleteWebhookRequest, DeleteWebhookResponse, ListWebhooksRequest, ListWebhooksResponse, RegisterWebhookRequest,
+    Webhook, WebhookEvent,
+};
+
+mod dispatch;
+mod sender;
+
+pub use self::dispatch::WebhookDispatcher;
+pub use self::sender::HttpSender;
+
+// Shortest secret an admin may choose; generated secrets are 32 random bytes.
+const MIN_SECRET_LEN: usize = 16;
+
+// Implements WebhookService. Registrations live in each institution's store,
+// so every replica sharing it delivers to the same webhooks, and a webhook
+// only hears of its own institution's results.
+#[derive(Debug)]
+pub struct WebhookServiceImpl<S> {
+    tenants: Tenants<S>,
+    // Whether a CA bundle is configured to verify https:// receivers
+    https: bool,
+    max_processing: Duration,
+}
+
+impl<S: ExamStore> WebhookServiceImpl<S> {
+    pub fn new(tenants: Tenants<S>, sender: &HttpSender) -> Self {
+        Self {
+            tenants,
+            https: sender.supports_https(),
+            max_processing: DEFAULT_MAX_PROCESSING,
+        }
+    }
+
+    // Replaces the server-side processing limit.
+    pub fn with_max_processing_time(mut self, limit: Duration) -> Self {
+        self.max_processing = limit;
+        self
+    }
+
+    // The client's deadline for `request`, capped by the processing limit.
+    fn deadline<T>(&self, request: &Request<T>) -> Result<Deadline, Status> {
+        Deadline::from_request(request, Some(self.max_processing))
+    }
+
+    fn validate_url(&self, url: &str) -> Result<(), Status> {
+        let uri = url
+            .parse::<Uri>()
+            .map_err(|err| invalid_field("url", format!("is not a URL: {}", err)))?;
+
+        if uri.host().is_none_or(str::is_empty) {
+            return Err(invalid_field("url", format!("must name a host, got {:?}", url)));
+        }
+
+        match uri.scheme_str() {
+            Some("http") => Ok(()),
+            Some("https") if self.https => Ok(()),
+            Some("https") => Err(missing_dependency(
+                "NO_WEBHOOK_CA",
+                "config/webhooks.ca",
+                "https:// webhooks need a CA bundle configured as webhooks.ca".to_string(),
+            )),
+            _ => Err(invalid_field("url", format!("must be an http:// or https:// URL, got {:?}", url))),
+        }
+    }
+}
+
+// Subscribed events, deduplicated; unknown and unspecified values are rejected.
+fn validate_events(events: &[i32]) -> Result<Vec<i32>, Status> {
+    let mut valid = Vec::new();
+
+    for &event in events {
+        match WebhookEvent::try_from(event) {
+            Ok(WebhookEvent::Unspecified) | Err(_) => {
+                return Err(invalid_field("events", format!("{} is not a webhook event", event)));
+            }
+            Ok(_) if valid.contains(&event) => {}
+            Ok(_) => valid.push(event),
+        }
+    }
+
+    Ok(valid)
+}
+
+fn generate_secret() -> String {
+    rand::thread_rng()
+        .r#gen::<[u8; 32]>()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[tonic::async_trait]
+impl<S: ExamStore> WebhookService for WebhookServiceImpl<S> {
+    // Registers a webhook. The response is the only place its secret is returned.
+    async fn register_webhook(&self, request: Request<RegisterWebhookRequest>) -> Result<Response<Webhook>, Status> {
+        // Not the whole request: it carries the secret
+        info!(url = %request.get_ref().url, "register webhook");
+
+        let deadline = self.deadline(&request)?;
+        deadline
+            .run(async move {
+                let identity = Identity::from_request(&request)?;
+                identity.require_admin()?;
+                let req = request.into_inner();
+
+                self.validate_url(&req.url)?;
+                let events = validate_events(&req.events)?;
+
+                let secret = match req.secret {
+                    secret if secret.is_empty() => generate_secret(),
+                    secret if secret.len() < MIN_SECRET_LEN => {
+                        return Err(invalid_field(
+                            "secret",
+                            format!("must be at least {} characters, or empty to generate one", MIN_SECRET_LEN),
+                        ));
+                    }
+                    secret => secret,
+                };
+
+                let webhook = Webhook {
+                    webhook_id: 0,
+                    url: req.url,
+                    events,
+                    secret,
+                    created_by: identity.name.clone(),
+                    created_at_ms: now_ms(),
+                };
+                let webhook = self.tenants.for_caller(&identity)?.create_webhook(webhook).await?;
+
+                info!(webhook_id = webhook.webhook_id, url = %webhook.url, "webhook registered");
+                Ok(Response::new(webhook))
+            })
+            .await
+    }
+
+    // Lists every webhook, without its secret.
+    async fn list_webhooks(
+        &self,
+        request: Request<ListWebhooksRequest>,
+    ) -> Result<Response<ListWebhooksResponse>, Status> {
+        info!("list webhooks");
+
+        let deadline = self.deadline(&request)?;
+        deadline
+            .run(async move {
+                let identity = Identity::from_request(&request)?;
+                identity.require_admin()?;
+
+                let webhooks = self
+                    .tenants
+                    .for_caller(&identity)?
+                    .list_webhooks()
+                    .await?
+                    .into_iter()
+                    .map(|webhook| Webhook {
+                        secret: String::new(),
+                        ..webhook
+                    })
+                    .collect();
+
+                Ok(Response::new(ListWebhooksResponse { webhooks }))
+            })
+            .await
+    }
+
+    // Removes a webhook. Deliveries already under way still finish their retries.
+    async fn delete_webhook(
+        &self,
+        request: Request<DeleteWebhookRequest>,
+    ) -> Result<Response<DeleteWebhookResponse>, Status> {
+        info!(request = ?request.get_ref(), "delete webhook");
+
+        let deadline = self.deadline(&request)?;
+        deadline
+            .run(async move {
+                let identity = Identity::from_request(&request)?;
+                identity.require_admin()?;
+                let id = request.into_inner().webhook_id;
+
+                if !self.tenants.for_caller(&identity)?.delete_webhook(id).await? {
+                    return Err(not_found("webhook", id, format!("No webhook found with ID {}", id)));
+                }
+
+                info!(webhook_id = id, "webhook deleted");
+                Ok(Response::new(DeleteWebhookResponse {}))
+            })
+            .await
+    }
+}
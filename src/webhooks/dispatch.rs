@@ -0,0 +1,236 @@
+use std::sync::Arc;
+use std::time::Duration;
+use bytes::Bytes;
+use http::header::{CONTENT_TYPE, USER_AGENT};
+use http::{HeaderMap, HeaderValue, StatusCode, Uri};
+use rand::Rng;
+use ring::hmac;
+use serde::Serialize;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{error, info, warn};
+
+use super::sender::HttpSender;
+use crate::client::RetryPolicy;
+use crate::config::WebhookConfig;
+use crate::exam_service::{ChangeKind, ExamResult, ResultStatus};
+use crate::schedule::now_ms;
+use crate::key::InstitutionId;
+use crate::store::ExamStore;
+use crate::tenancy::Tenants;
+use crate::watch::{ChangeFeed, ResultEvent};
+use crate::webhook::{Webhook, WebhookEvent};
+
+// The body POSTed to a webhook. `event_id` is the same on every attempt and
+// every webhook, so receivers can drop retried deliveries they already handled.
+#[derive(Debug, Serialize)]
+struct EventPayload<'a> {
+    event_id: &'a str,
+    event: &'a str,
+    institution_id: &'a str,
+    occurred_at_ms: i64,
+    result: ResultPayload<'a>,
+}
+
+// The published fields of a result; internal comments never leave the server.
+#[derive(Debug, Serialize)]
+struct ResultPayload<'a> {
+    student_id: &'a str,
+    exam_id: &'a str,
+    student_name: &'a str,
+    subject: &'a str,
+    marks_obtained: i32,
+    total_marks: i32,
+    grade: &'a str,
+    version: i64,
+}
+
+// Which webhook event a result change is, if any. Corrections of drafts are
+// skipped: students cannot see those results yet.
+fn webhook_event(change: &ResultEvent) -> Option<WebhookEvent> {
+    match change.kind {
+        ChangeKind::Released => Some(WebhookEvent::ResultPublished),
+        ChangeKind::Corrected if change.result.status() == ResultStatus::Published => {
+            Some(WebhookEvent::ResultCorrected)
+        }
+        _ => None,
+    }
+}
+
+fn subscribed(webhook: &Webhook, event: WebhookEvent) -> bool {
+    webhook.events.is_empty() || webhook.events.contains(&(event as i32))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// The `x-exam-signature` of a delivery: HMAC-SHA256 of "<timestamp>.<body>"
+// keyed with the webhook's secret, as lowercase hex after "sha256=".
+fn signature(secret: &str, timestamp_ms: i64, body: &[u8]) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    let mut context = hmac::Context::with_key(&key);
+    context.update(format!("{}.", timestamp_ms).as_bytes());
+    context.update(body);
+    format!("sha256={}", hex(context.sign().as_ref()))
+}
+
+// Receivers may be overloaded or restarting; anything else will fail again.
+fn is_retryable(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::REQUEST_TIMEOUT
+}
+
+// Delivers result events from the change feed to the webhooks registered
+// in the institution whose result changed.
+pub struct WebhookDispatcher<S> {
+    tenants: Tenants<S>,
+    sender: HttpSender,
+    retries: RetryPolicy,
+    timeout: Duration,
+}
+
+impl<S: ExamStore> WebhookDispatcher<S> {
+    pub fn new(tenants: Tenants<S>, sender: HttpSender, config: &WebhookConfig) -> Self {
+        Self {
+            tenants,
+            sender,
+            retries: RetryPolicy {
+                max_attempts: config.max_attempts.max(1),
+                initial_backoff: Duration::from_millis(config.initial_backoff_ms),
+                max_backoff: Duration::from_millis(config.max_backoff_ms),
+                ..RetryPolicy::default()
+            },
+            timeout: config.timeout(),
+        }
+    }
+
+    // Subscribes to `changes` and follows it in the background until it closes.
+    // Each event is delivered in its own task, so a slow receiver never holds up
+    // the feed; retries still pending at shutdown are abandoned.
+    pub fn spawn(self, changes: &ChangeFeed) {
+        let mut events = changes.subscribe();
+        let changes = changes.clone();
+        let dispatcher = Arc::new(self);
+
+        tokio::spawn(async move {
+            let closed = changes.closed();
+            tokio::pin!(closed);
+
+            loop {
+                let change = tokio::select! {
+                    change = events.recv() => change,
+                    _ = &mut closed => return,
+                };
+
+                match change {
+                    Ok(change) => {
+                        if let Some(event) = webhook_event(&change) {
+                            let dispatcher = dispatcher.clone();
+                            tokio::spawn(async move { dispatcher.deliver_all(change.institution, event, change.result).await });
+                        }
+                    }
+                    Err(RecvError::Lagged(missed)) => {
+                        error!(missed, "webhook dispatcher fell behind the change feed; events were dropped")
+                    }
+                    Err(RecvError::Closed) => return,
+                }
+            }
+        });
+    }
+
+    async fn deliver_all(self: Arc<Self>, institution: InstitutionId, event: WebhookEvent, result: ExamResult) {
+        let webhooks = match self.tenants.get(&institution) {
+            Ok(store) => store.list_webhooks().await,
+            // Events only come from stores this deployment serves
+            Err(_) => return,
+        };
+        let webhooks = match webhooks {
+            Ok(webhooks) => webhooks,
+            Err(err) => {
+                error!(%institution, %err, "could not load webhooks; event not delivered");
+                return;
+            }
+        };
+
+        let event_id = hex(&rand::thread_rng().r#gen::<[u8; 16]>());
+        let payload = EventPayload {
+            event_id: &event_id,
+            event: event.as_str_name(),
+            institution_id: institution.as_str(),
+            occurred_at_ms: now_ms(),
+            result: ResultPayload {
+                student_id: &result.student_id,
+                exam_id: &result.exam_id,
+                student_name: &result.student_name,
+                subject: &result.subject,
+                marks_obtained: result.marks_obtained,
+                total_marks: result.total_marks,
+                grade: &result.grade,
+                version: result.version,
+            },
+        };
+        let body = match serde_json::to_vec(&payload) {
+            Ok(body) => Bytes::from(body),
+            Err(err) => {
+                error!(%err, "could not encode webhook event");
+                return;
+            }
+        };
+
+        for webhook in webhooks.into_iter().filter(|webhook| subscribed(webhook, event)) {
+            let dispatcher = self.clone();
+            let event_id = event_id.clone();
+            let body = body.clone();
+            tokio::spawn(async move { dispatcher.deliver(&webhook, event, &event_id, body).await });
+        }
+    }
+
+    // POSTs one event to one webhook, retrying transient failures with backoff.
+    async fn deliver(&self, webhook: &Webhook, event: WebhookEvent, event_id: &str, body: Bytes) {
+        let Ok(url) = webhook.url.parse::<Uri>() else {
+            error!(webhook_id = webhook.webhook_id, url = %webhook.url, "webhook URL does not parse");
+            return;
+        };
+
+        for attempt in 1..=self.retries.max_attempts {
+            if attempt > 1 {
+                tokio::time::sleep(self.retries.backoff(attempt - 1)).await;
+            }
+
+            // Signed afresh each attempt, so receivers can reject stale replays by timestamp
+            let timestamp_ms = now_ms();
+            let mut headers = HeaderMap::new();
+            headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+            headers.insert(USER_AGENT, HeaderValue::from_static("exam-service-webhooks"));
+            headers.insert("x-exam-event", HeaderValue::from_static(event.as_str_name()));
+            headers.insert("x-exam-event-id", HeaderValue::from_str(event_id).expect("hex is a valid header"));
+            headers.insert("x-exam-timestamp", HeaderValue::from(timestamp_ms));
+            headers.insert(
+                "x-exam-signature",
+                HeaderValue::from_str(&signature(&webhook.secret, timestamp_ms, &body)).expect("hex is a valid header"),
+            );
+
+            let sent = tokio::time::timeout(self.timeout, self.sender.post(&url, headers, body.clone())).await;
+            let failure = match sent {
+                Ok(Ok(status)) if status.is_success() => {
+                    info!(webhook_id = webhook.webhook_id, event_id, attempt, "webhook event delivered");
+                    return;
+                }
+                Ok(Ok(status)) if !is_retryable(status) => {
+                    warn!(webhook_id = webhook.webhook_id, event_id, %status, "webhook rejected event; not retrying");
+                    return;
+                }
+                Ok(Ok(status)) => status.to_string(),
+                Ok(Err(err)) => err.to_string(),
+                Err(_) => format!("no response within {:?}", self.timeout),
+            };
+            warn!(webhook_id = webhook.webhook_id, event_id, attempt, %failure, "webhook delivery failed");
+        }
+
+        error!(
+            webhook_id = webhook.webhook_id,
+            event_id,
+            attempts = self.retries.max_attempts,
+            "webhook delivery abandoned"
+        );
+    }
+}
@@ -0,0 +1,98 @@
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use bytes::Bytes;
+use http::header::HOST;
+use http::{HeaderMap, HeaderValue, Request, Response, StatusCode, Uri};
+use http_body_util::{BodyExt, Full};
+use hyper::body::Incoming;
+use hyper_util::rt::TokioIo;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::pki_types::ServerName;
+use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+use tokio_rustls::TlsConnector;
+
+pub type BoxError = Box<dyn Error + Send + Sync>;
+
+// Minimal HTTP/1.1 client for webhook deliveries and JWKS fetches: one
+// connection per request, and TLS for https:// URLs, verified against the
+// configured CA bundle only.
+#[derive(Clone)]
+pub struct HttpSender {
+    tls: Option<TlsConnector>,
+}
+
+impl HttpSender {
+    pub fn new(ca: Option<&Path>) -> Result<Self, Box<dyn Error>> {
+        let Some(ca) = ca else {
+            return Ok(Self { tls: None });
+        };
+
+        let mut roots = RootCertStore::empty();
+        for cert in rustls_pemfile::certs(&mut fs::read(ca)?.as_slice()) {
+            roots.add(cert?)?;
+        }
+        let config = ClientConfig::builder()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+
+        Ok(Self {
+            tls: Some(TlsConnector::from(Arc::new(config))),
+        })
+    }
+
+    pub fn supports_https(&self) -> bool {
+        self.tls.is_some()
+    }
+
+    // POSTs `body` to `url`, returning the response status. The body of the
+    // response is not read: receivers only acknowledge with the status.
+    pub async fn post(&self, url: &Uri, headers: HeaderMap, body: Bytes) -> Result<StatusCode, BoxError> {
+        let mut request = Request::post(path(url)).body(Full::new(body))?;
+        request.headers_mut().extend(headers);
+        Ok(self.request(url, request).await?.status())
+    }
+
+    // GETs `url`, returning the response status and body.
+    pub async fn get(&self, url: &Uri) -> Result<(StatusCode, Bytes), BoxError> {
+        let request = Request::get(path(url)).body(Full::new(Bytes::new()))?;
+        let response = self.request(url, request).await?;
+        let status = response.status();
+        Ok((status, response.into_body().collect().await?.to_bytes()))
+    }
+
+    async fn request(&self, url: &Uri, mut request: Request<Full<Bytes>>) -> Result<Response<Incoming>, BoxError> {
+        let https = url.scheme_str() == Some("https");
+        let authority = url.authority().ok_or("URL has no host")?;
+        // IPv6 addresses keep their brackets in URLs
+        let host = authority.host().trim_start_matches('[').trim_end_matches(']');
+        let port = authority.port_u16().unwrap_or(if https { 443 } else { 80 });
+        request.headers_mut().insert(HOST, HeaderValue::from_str(authority.as_str())?);
+
+        let tcp = TcpStream::connect((host, port)).await?;
+        if !https {
+            return send(tcp, request).await;
+        }
+
+        let tls = self.tls.as_ref().ok_or("https:// URLs need a CA bundle to be configured")?;
+        let stream = tls.connect(ServerName::try_from(host.to_string())?, tcp).await?;
+        send(stream, request).await
+    }
+}
+
+fn path(url: &Uri) -> &str {
+    url.path_and_query().map_or("/", |path| path.as_str())
+}
+
+async fn send<T>(io: T, request: Request<Full<Bytes>>) -> Result<Response<Incoming>, BoxError>
+where
+    T: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let (mut sender, connection) = hyper::client::conn::http1::handshake(TokioIo::new(io)).await?;
+    // Ends once the response body is read or dropped, and `sender` with it
+    tokio::spawn(connection);
+
+    Ok(sender.send_request(request).await?)
+}
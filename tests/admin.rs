@@ -0,0 +1,136 @@
+mod common;
+
+use std::time::Duration;
+use tokio_stream::StreamExt;
+use tonic::Code;
+use tonic_types::StatusExt;
+
+use common::{test_config, TestServer, ADMIN, NORTH, OPERATOR, TEACHER};
+use exam_service::exam_service::ExamResult;
+use exam_service::key::InstitutionId;
+use exam_service::server::ExamServiceImpl;
+use exam_service::store::InMemoryExamStore;
+
+fn result(marks_obtained: i32) -> ExamResult {
+    ExamResult {
+        student_id: "123".to_string(),
+        exam_id: "math101".to_string(),
+        marks_obtained,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn only_operators_reach_the_admin_service() {
+    let server = TestServer::start().await;
+
+    // An admin of one institution cannot act on the others through the whole server
+    for token in [TEACHER, ADMIN] {
+        let client = server.client(token).await;
+        assert_eq!(client.get_config().await.unwrap_err().code(), Code::PermissionDenied);
+        assert_eq!(client.list_streams().await.unwrap_err().code(), Code::PermissionDenied);
+        let status = client.set_maintenance_mode(true, "").await.unwrap_err();
+        assert_eq!(status.code(), Code::PermissionDenied);
+        assert_eq!(client.trigger_snapshot().await.unwrap_err().code(), Code::PermissionDenied);
+    }
+
+    let operator = server.client_in(OPERATOR, "default").await;
+    assert!(!operator.get_config().await.unwrap().maintenance.unwrap().enabled);
+}
+
+#[tokio::test]
+async fn the_config_dump_shows_the_settings_in_effect() {
+    let mut config = test_config();
+    config.stream.max_per_connection = 7;
+    config.replication.token = "peer-secret".to_string();
+    let server =
+        TestServer::start_with_config(ExamServiceImpl::new(InMemoryExamStore::with_sample_data()), config).await;
+
+    let response = server.client_in(OPERATOR, "default").await.get_config().await.unwrap();
+    let dumped: serde_json::Value = serde_json::from_str(&response.config_json).unwrap();
+    assert_eq!(dumped["stream"]["max_per_connection"], 7);
+    assert_eq!(dumped["rate_limit"]["enabled"], false);
+    // Secrets are only said to be set
+    assert_eq!(dumped["replication"]["token"], "<redacted>");
+    assert!(!response.config_json.contains("peer-secret"));
+    assert!(!response.maintenance.unwrap().enabled);
+}
+
+#[tokio::test]
+async fn open_streams_are_listed_until_they_end() {
+    let server = TestServer::start().await;
+    let operator = server.client_in(OPERATOR, "default").await;
+    let watch = server.client(TEACHER).await.watch("123", "").await.unwrap();
+
+    let listed = operator.list_streams().await.unwrap();
+    assert_eq!(listed.streams.len(), 1);
+    let stream = &listed.streams[0];
+    assert_eq!(stream.method, "exam.v2.ExamService/WatchExamResults");
+    assert_eq!((stream.caller.as_str(), stream.institution_id.as_str()), ("teacher", "default"));
+    assert_eq!(listed.connections.len(), 1);
+    assert_eq!(listed.connections[0].peer, stream.peer);
+    assert_eq!(listed.connections[0].open_streams, 1);
+
+    drop(watch);
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while !operator.list_streams().await.unwrap().streams.is_empty() {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("the dropped stream is no longer listed");
+}
+
+#[tokio::test]
+async fn maintenance_mode_serves_reads_and_rejects_writes() {
+    let server = TestServer::start().await;
+    let operator = server.client_in(OPERATOR, "default").await;
+    let teacher = server.client(TEACHER).await;
+
+    let mode = operator.set_maintenance_mode(true, "storage migration").await.unwrap();
+    assert!(mode.enabled);
+    assert_eq!((mode.reason.as_str(), mode.enabled_by.as_str()), ("storage migration", "operator"));
+    assert!(operator.get_config().await.unwrap().maintenance.unwrap().enabled);
+
+    teacher.get_result("123", "math101").await.unwrap();
+    let mut watch = teacher.watch("123", "").await.unwrap();
+
+    // Refused outright: not retried, and not held against the server by the circuit breaker
+    let status = teacher.submit_result(result(70)).await.unwrap_err();
+    assert_eq!(status.code(), Code::FailedPrecondition);
+    assert!(status.message().contains("storage migration"), "{}", status.message());
+    assert_eq!(status.get_details_error_info().unwrap().reason, "MAINTENANCE_MODE");
+    for marks in 72..80 {
+        teacher.submit_result(result(marks)).await.unwrap_err();
+    }
+    teacher.get_result("123", "math101").await.unwrap();
+
+    operator.set_maintenance_mode(false, "").await.unwrap();
+    teacher.submit_result(result(71)).await.unwrap();
+
+    // Watch streams opened during maintenance see writes made after it
+    let change = watch.next().await.unwrap().unwrap();
+    assert_eq!(change.result.unwrap().marks_obtained, 71);
+}
+
+#[tokio::test]
+async fn triggered_snapshots_cover_every_institution() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = test_config();
+    config.snapshots.dir = dir.path().to_path_buf();
+    let service = ExamServiceImpl::for_institutions([
+        (InstitutionId::default(), InMemoryExamStore::with_sample_data()),
+        (InstitutionId::parse(NORTH).unwrap(), InMemoryExamStore::with_sample_data()),
+    ]);
+    let server = TestServer::start_with_config(service, config).await;
+
+    let snapshots = server.client_in(OPERATOR, NORTH).await.trigger_snapshot().await.unwrap();
+    let mut institutions: Vec<_> = snapshots.iter().map(|taken| taken.institution_id.as_str()).collect();
+    institutions.sort();
+    assert_eq!(institutions, ["default", NORTH]);
+    assert!(snapshots.iter().all(|taken| taken.snapshot.as_ref().unwrap().automatic));
+
+    // They are the institution's own snapshots, pruned like the periodic ones
+    let listed = server.client(ADMIN).await.list_snapshots().await.unwrap();
+    assert_eq!(listed.len(), 1);
+}
@@ -0,0 +1,155 @@
+mod common;
+
+use tonic::Code;
+
+use common::{TestServer, ADMIN, STUDENT, TEACHER};
+use exam_service::appeal::{Appeal, AppealState};
+use exam_service::exam_service::{ChangeKind, ExamResult};
+use exam_service::store::{ExamStore, SqliteExamStore};
+
+#[tokio::test]
+async fn an_approved_appeal_corrects_the_result() {
+    let server = TestServer::start().await;
+    let student = server.client(STUDENT).await;
+    let teacher = server.client(TEACHER).await;
+
+    let filed = student.file_appeal("123", "math101", "Question 4 was marked twice").await.unwrap();
+    assert_eq!(filed.state(), AppealState::Open);
+    assert_eq!(filed.original_marks, 95);
+
+    let reviewed = teacher.review_appeal(filed.appeal_id).await.unwrap();
+    assert_eq!(reviewed.state(), AppealState::UnderReview);
+    assert_eq!(reviewed.reviewer, "teacher");
+
+    let resolved = teacher
+        .resolve_appeal(filed.appeal_id, true, 85, "Question 4 counted once")
+        .await
+        .unwrap();
+    assert_eq!(resolved.state(), AppealState::Resolved);
+    assert!(resolved.approved);
+    assert_eq!(resolved.revised_marks, 85);
+
+    let result = student.get_result("123", "math101").await.unwrap();
+    assert_eq!(result.marks_obtained, 85);
+
+    // The correction is audited like any other, with the appeal as its reason
+    let trail = server.client(ADMIN).await.audit_trail("123", "math101").await.unwrap();
+    let last = trail.last().unwrap();
+    assert_eq!(last.action(), ChangeKind::Corrected);
+    assert_eq!(last.reason, format!("appeal {}: Question 4 counted once", filed.appeal_id));
+}
+
+#[tokio::test]
+async fn a_rejected_appeal_leaves_the_result_and_can_be_refiled() {
+    let server = TestServer::start().await;
+    let student = server.client(STUDENT).await;
+    let teacher = server.client(TEACHER).await;
+
+    let filed = student.file_appeal("123", "math101", "Recount please").await.unwrap();
+    let status = student.file_appeal("123", "math101", "Again").await.unwrap_err();
+    assert_eq!(status.code(), Code::AlreadyExists);
+
+    teacher.review_appeal(filed.appeal_id).await.unwrap();
+    let resolved = teacher
+        .resolve_appeal(filed.appeal_id, false, 0, "Marks are correct")
+        .await
+        .unwrap();
+    assert!(!resolved.approved);
+    assert_eq!(resolved.revised_marks, 95);
+    assert_eq!(student.get_result("123", "math101").await.unwrap().marks_obtained, 95);
+
+    let refiled = student.file_appeal("123", "math101", "New evidence").await.unwrap();
+    assert_ne!(refiled.appeal_id, filed.appeal_id);
+}
+
+#[tokio::test]
+async fn appeals_move_through_their_states_in_order() {
+    let server = TestServer::start().await;
+    let teacher = server.client(TEACHER).await;
+
+    let filed = server.client(STUDENT).await.file_appeal("123", "math101", "Recount").await.unwrap();
+
+    let status = server.client(STUDENT).await.review_appeal(filed.appeal_id).await.unwrap_err();
+    assert_eq!(status.code(), Code::PermissionDenied);
+
+    let status = teacher.resolve_appeal(filed.appeal_id, true, 90, "Early").await.unwrap_err();
+    assert_eq!(status.code(), Code::FailedPrecondition);
+
+    teacher.review_appeal(filed.appeal_id).await.unwrap();
+    let status = teacher.review_appeal(filed.appeal_id).await.unwrap_err();
+    assert_eq!(status.code(), Code::FailedPrecondition);
+
+    // Marks over the total are rejected and the appeal stays under review
+    let status = teacher.resolve_appeal(filed.appeal_id, true, 120, "Bonus").await.unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+    let appeals = teacher.list_appeals("123", "", AppealState::UnderReview).await.unwrap();
+    assert_eq!(appeals.len(), 1);
+
+    let status = teacher.review_appeal(999).await.unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+}
+
+#[tokio::test]
+async fn students_only_appeal_and_list_their_own_visible_results() {
+    let server = TestServer::start().await;
+    let student = server.client(STUDENT).await;
+    let teacher = server.client(TEACHER).await;
+
+    let status = student.file_appeal("456", "phy101", "Not mine").await.unwrap_err();
+    assert_eq!(status.code(), Code::PermissionDenied);
+
+    // Drafts cannot be appealed; to the student they do not exist yet
+    teacher
+        .submit_result(ExamResult {
+            student_id: "123".to_string(),
+            exam_id: "phy101".to_string(),
+            marks_obtained: 60,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    let status = student.file_appeal("123", "phy101", "Too low").await.unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+    let status = teacher.file_appeal("123", "phy101", "Too low").await.unwrap_err();
+    assert_eq!(status.code(), Code::FailedPrecondition);
+
+    teacher.file_appeal("456", "phy101", "Filed for the student").await.unwrap();
+    student.file_appeal("123", "math101", "Recount").await.unwrap();
+
+    let own = student.list_appeals("", "", AppealState::Unspecified).await.unwrap();
+    assert_eq!(own.len(), 1);
+    assert_eq!(own[0].student_id, "123");
+    assert_eq!(teacher.list_appeals("", "", AppealState::Open).await.unwrap().len(), 2);
+
+    let status = student.list_appeals("456", "", AppealState::Unspecified).await.unwrap_err();
+    assert_eq!(status.code(), Code::PermissionDenied);
+}
+
+#[tokio::test]
+async fn sqlite_appeals_only_change_from_the_expected_state() {
+    let store = SqliteExamStore::open(":memory:").unwrap();
+    let appeal = Appeal {
+        student_id: "123".to_string(),
+        exam_id: "math101".to_string(),
+        reason: "Recount".to_string(),
+        state: AppealState::Open as i32,
+        original_marks: 95,
+        ..Default::default()
+    };
+
+    let filed = store.create_appeal(appeal.clone()).await.unwrap().unwrap();
+    assert_eq!(filed.appeal_id, 1);
+    assert!(store.create_appeal(appeal).await.unwrap().is_none());
+
+    let reviewed = Appeal {
+        state: AppealState::UnderReview as i32,
+        reviewer: "teacher".to_string(),
+        ..filed.clone()
+    };
+    assert!(!store.update_appeal(reviewed.clone(), AppealState::UnderReview).await.unwrap());
+    assert!(store.update_appeal(reviewed.clone(), AppealState::Open).await.unwrap());
+
+    assert_eq!(store.get_appeal(1).await.unwrap(), Some(reviewed));
+    assert_eq!(store.list_appeals(None, None).await.unwrap().len(), 1);
+    assert!(store.get_appeal(2).await.unwrap().is_none());
+}
@@ -0,0 +1,96 @@
+mod common;
+
+use tonic::Code;
+use tonic_types::StatusExt;
+
+use common::{TestServer, STUDENT, TEACHER};
+use exam_service::client::read_mask;
+use exam_service::exam_service::{BatchGetExamResultsRequest, ExamResultKey};
+
+fn key(student_id: &str, exam_id: &str) -> ExamResultKey {
+    ExamResultKey {
+        student_id: student_id.to_string(),
+        exam_id: exam_id.to_string(),
+    }
+}
+
+#[tokio::test]
+async fn each_key_gets_its_result_or_its_error_in_order() {
+    let server = TestServer::start().await;
+    let client = server.client(TEACHER).await;
+
+    let results = client
+        .batch_get_results(&[("456", "phy101"), ("123", "phy101"), ("999", "math101"), ("123", "math101")])
+        .await
+        .unwrap();
+
+    let keys: Vec<_> = results.iter().map(|outcome| outcome.key.clone().unwrap()).collect();
+    assert_eq!(keys, [key("456", "phy101"), key("123", "phy101"), key("999", "math101"), key("123", "math101")]);
+
+    let codes: Vec<_> = results.iter().map(|outcome| Code::from_i32(outcome.code)).collect();
+    assert_eq!(codes, [Code::Ok, Code::NotFound, Code::FailedPrecondition, Code::Ok]);
+    assert_eq!(results[0].result.as_ref().unwrap().marks_obtained, 88);
+    assert!(results[1].result.is_none());
+    assert!(results[1].message.contains("123/phy101"), "{}", results[1].message);
+    assert_eq!(results[3].result.as_ref().unwrap().grade, "A+");
+}
+
+#[tokio::test]
+async fn students_get_permission_errors_for_other_students_keys() {
+    let server = TestServer::start().await;
+    let client = server.client(STUDENT).await;
+
+    let results = client.batch_get_results(&[("123", "math101"), ("456", "phy101")]).await.unwrap();
+    assert_eq!(Code::from_i32(results[0].code), Code::Ok);
+    // Redacted as for GetExamResult
+    assert_eq!(results[0].result.as_ref().unwrap().internal_comment, "");
+    assert_eq!(Code::from_i32(results[1].code), Code::PermissionDenied);
+    assert!(results[1].result.is_none());
+}
+
+#[tokio::test]
+async fn the_read_mask_applies_to_every_result() {
+    let server = TestServer::start().await;
+    let mut client = server.raw_client(TEACHER).await;
+
+    let response = client
+        .batch_get_exam_results(BatchGetExamResultsRequest {
+            keys: vec![key("123", "math101"), key("456", "phy101")],
+            read_mask: read_mask(&["grade"]),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+
+    let grades: Vec<_> = response.results.iter().map(|outcome| outcome.result.clone().unwrap()).collect();
+    assert_eq!(grades.iter().map(|result| result.grade.as_str()).collect::<Vec<_>>(), ["A+", "A"]);
+    assert!(grades.iter().all(|result| result.student_name.is_empty()));
+}
+
+#[tokio::test]
+async fn oversized_and_malformed_batches_are_rejected() {
+    let server = TestServer::start().await;
+    let mut client = server.raw_client(TEACHER).await;
+
+    let keys = (0..101).map(|index| key(&index.to_string(), "math101")).collect();
+    let status = client
+        .batch_get_exam_results(BatchGetExamResultsRequest { keys, read_mask: None })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+    assert_eq!(status.get_details_bad_request().unwrap().field_violations[0].field, "keys");
+
+    let keys = vec![key("123", "math101"), key("123", "")];
+    let status = client
+        .batch_get_exam_results(BatchGetExamResultsRequest { keys, read_mask: None })
+        .await
+        .unwrap_err();
+    let fields: Vec<_> = status
+        .get_details_bad_request()
+        .unwrap()
+        .field_violations
+        .into_iter()
+        .map(|violation| violation.field)
+        .collect();
+    assert_eq!(fields, ["keys[1].exam_id"]);
+}
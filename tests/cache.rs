@@ -0,0 +1,49 @@
+mod common;
+
+use common::{TestServer, ADMIN, TEACHER};
+use exam_service::config::CacheConfig;
+use exam_service::exam_service::ExamResult;
+use exam_service::key::ResultKey;
+use exam_service::server::ExamServiceImpl;
+use exam_service::store::{CachedExamStore, ExamStore, InMemoryExamStore};
+
+#[tokio::test]
+async fn reads_are_cached_until_a_write_evicts_them() {
+    // Clones share their data, so `backend` can change results behind the cache
+    let backend = InMemoryExamStore::with_sample_data();
+    let cached = CachedExamStore::new(backend.clone(), &CacheConfig::default());
+    let server = TestServer::start_with(ExamServiceImpl::new(cached)).await;
+    let client = server.client(TEACHER).await;
+
+    assert_eq!(client.get_result("123", "math101").await.unwrap().marks_obtained, 95);
+
+    let key = ResultKey::parse("123", "math101").unwrap();
+    let mut changed = backend.get(&key).await.unwrap().unwrap();
+    changed.marks_obtained = 10;
+    backend.put(key, changed).await.unwrap();
+    assert_eq!(client.get_result("123", "math101").await.unwrap().marks_obtained, 95);
+
+    let resubmitted = ExamResult {
+        student_id: "123".to_string(),
+        exam_id: "math101".to_string(),
+        marks_obtained: 70,
+        ..Default::default()
+    };
+    client.submit_result(resubmitted).await.unwrap();
+    let current = client.get_result("123", "math101").await.unwrap();
+    assert_eq!((current.marks_obtained, current.version), (70, 3));
+}
+
+#[tokio::test]
+async fn deletes_and_corrections_evict_cached_results() {
+    let cached = CachedExamStore::new(InMemoryExamStore::with_sample_data(), &CacheConfig::default());
+    let server = TestServer::start_with(ExamServiceImpl::new(cached)).await;
+    let admin = server.client(ADMIN).await;
+
+    assert_eq!(admin.get_result("123", "math101").await.unwrap().version, 1);
+    admin.correct_result("123", "math101", 80, 1, "").await.unwrap();
+    assert_eq!(admin.get_result("123", "math101").await.unwrap().marks_obtained, 80);
+
+    admin.delete_result("123", "math101", "").await.unwrap();
+    assert!(admin.get_result("123", "math101").await.is_err());
+}
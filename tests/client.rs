@@ -0,0 +1,254 @@
+mod common;
+
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+use std::time::{Duration, Instant};
+use tokio::net::TcpListener;
+use tokio_stream::StreamExt;
+use tonic::transport::Endpoint;
+use tonic::Code;
+use tonic_types::StatusExt;
+
+use common::{test_config, TestServer, ADMIN, TEACHER};
+use exam_service::client::{CircuitBreaker, ExamClient, RetryBudget, RetryPolicy, StreamError, WatchEvent};
+use exam_service::config::{RateLimitConfig, ServerConfig, StreamConfig};
+use exam_service::exam_service::{ChangeKind, ExamResult, ListExamResultsRequest};
+use exam_service::server::ExamServiceImpl;
+use exam_service::store::InMemoryExamStore;
+use exam_service::student::Student;
+
+fn result(student_id: &str, exam_id: &str, marks_obtained: i32) -> ExamResult {
+    ExamResult {
+        student_id: student_id.to_string(),
+        exam_id: exam_id.to_string(),
+        marks_obtained,
+        ..Default::default()
+    }
+}
+
+// An address nothing listens on: bound once to claim a port, then released.
+async fn unused_endpoint() -> Endpoint {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+    Endpoint::from_shared(format!("http://{}", addr)).unwrap()
+}
+
+#[tokio::test]
+async fn unreachable_servers_are_retried_then_reported_unavailable() {
+    let policy = RetryPolicy {
+        max_attempts: 3,
+        initial_backoff: Duration::from_millis(20),
+        ..RetryPolicy::default()
+    };
+    let client = ExamClient::connect(unused_endpoint().await, ADMIN)
+        .await
+        .unwrap()
+        .with_retry_policy(policy);
+
+    let status = client.get_result("123", "math101").await.unwrap_err();
+    assert_eq!(status.code(), Code::Unavailable);
+}
+
+#[tokio::test]
+async fn the_circuit_breaker_fails_fast_after_repeated_failures() {
+    let client = ExamClient::connect(unused_endpoint().await, ADMIN)
+        .await
+        .unwrap()
+        .with_retry_policy(RetryPolicy::none())
+        .with_circuit_breaker(CircuitBreaker::new(2, Duration::from_secs(60)));
+
+    for _ in 0..2 {
+        let status = client.get_result("123", "math101").await.unwrap_err();
+        assert_eq!(status.code(), Code::Unavailable);
+    }
+
+    let started = Instant::now();
+    let status = client.get_result("123", "math101").await.unwrap_err();
+    assert_eq!(status.code(), Code::Unavailable);
+    assert!(status.message().contains("circuit breaker"));
+    assert!(started.elapsed() < Duration::from_millis(100));
+}
+
+#[tokio::test]
+async fn the_circuit_breaker_closes_once_the_server_answers() {
+    let server = TestServer::start().await;
+    let breaker = CircuitBreaker::new(1, Duration::from_millis(50));
+    let client = server.client(ADMIN).await.with_circuit_breaker(breaker.clone());
+
+    // Trip it as a failed call would
+    assert!(breaker.try_acquire());
+    breaker.record_failure();
+    assert!(client.get_result("123", "math101").await.is_err());
+
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    assert!(client.get_result("123", "math101").await.is_ok());
+    assert!(client.get_result("123", "math101").await.is_ok());
+}
+
+#[tokio::test]
+async fn retries_stop_once_the_budget_is_spent() {
+    // Two calls a second, so a limited call is told to come back in half a second
+    let config = ServerConfig {
+        rate_limit: RateLimitConfig {
+            enabled: true,
+            requests_per_second: 2.0,
+            burst: 1,
+            methods: HashMap::new(),
+        },
+        ..test_config()
+    };
+    let service = ExamServiceImpl::new(InMemoryExamStore::with_sample_data());
+    let server = TestServer::start_with_config(service, config).await;
+    // One retry saved up, and none earned by later requests
+    let client = server
+        .client(ADMIN)
+        .await
+        .with_retry_policy(RetryPolicy {
+            max_attempts: 5,
+            ..RetryPolicy::default()
+        })
+        .with_retry_budget(RetryBudget::new(0.0, 1.0));
+
+    client.get_result("123", "math101").await.unwrap();
+
+    // Limited, then retried once the server's RetryInfo delay has passed
+    let started = Instant::now();
+    client.get_result("123", "math101").await.unwrap();
+    assert!(started.elapsed() >= Duration::from_millis(400));
+
+    // Limited again with the budget spent: the server's answer comes straight back
+    let started = Instant::now();
+    let status = client.get_result("123", "math101").await.unwrap_err();
+    assert!(started.elapsed() < Duration::from_millis(200));
+    assert_eq!(status.code(), Code::ResourceExhausted);
+    assert!(status.get_details_retry_info().is_some());
+}
+
+#[tokio::test]
+async fn balanced_clients_spread_calls_across_replicas() {
+    let replicas = [TestServer::start().await, TestServer::start().await];
+    let endpoints = replicas
+        .iter()
+        .map(|server| Endpoint::from_shared(format!("http://{}", server.addr)).unwrap());
+    let client = ExamClient::connect_balanced(endpoints, ADMIN).await.unwrap();
+
+    for marks in 0..20 {
+        let result = ExamResult {
+            student_id: "456".to_string(),
+            exam_id: "math101".to_string(),
+            marks_obtained: marks,
+            ..Default::default()
+        };
+        client.submit_result(result).await.unwrap();
+    }
+
+    // The replicas keep separate stores, so each version counts the writes it took
+    let mut versions = Vec::new();
+    for server in &replicas {
+        versions.push(server.client(ADMIN).await.get_result("456", "math101").await.unwrap().version);
+    }
+    assert_eq!(versions.iter().sum::<i64>(), 20);
+    assert!(versions.iter().all(|&version| version > 0));
+}
+
+#[tokio::test]
+async fn balanced_clients_need_an_endpoint() {
+    assert!(ExamClient::connect_balanced([], ADMIN).await.is_err());
+}
+
+#[tokio::test]
+async fn result_streams_read_every_page() {
+    let server = TestServer::start().await;
+    let admin = server.client(ADMIN).await;
+
+    // Well past the server's default page of 50
+    for n in 0..120 {
+        let student = Student {
+            student_id: format!("s{n}"),
+            name: format!("Student {n}"),
+            email: String::new(),
+        };
+        admin.register_student(student).await.unwrap();
+    }
+    let upload = (0..120).map(|n| result(&format!("s{n}"), "phy101", 50));
+    admin.submit_results(tokio_stream::iter(upload)).await.unwrap();
+
+    let filter = ListExamResultsRequest {
+        exam_id: "phy101".to_string(),
+        ..Default::default()
+    };
+    let results = admin.stream_results(filter.clone()).await.unwrap().collect_all().await.unwrap();
+    // With sample student 456's
+    assert_eq!(results.len(), 121);
+
+    let mut seen = 0;
+    admin
+        .stream_results(filter)
+        .await
+        .unwrap()
+        .try_for_each(|result| {
+            seen += 1;
+            async move {
+                assert_eq!(result.exam_id, "phy101");
+                Ok::<_, StreamError>(())
+            }
+        })
+        .await
+        .unwrap();
+    assert_eq!(seen, 121);
+}
+
+#[tokio::test]
+async fn stream_failures_are_typed() {
+    let server = TestServer::start().await;
+
+    let filter = ListExamResultsRequest {
+        min_grade: "Z".to_string(),
+        ..Default::default()
+    };
+    let err = server.client(ADMIN).await.stream_results(filter).await.unwrap_err();
+    assert!(matches!(err, StreamError::Invalid(_)));
+    assert_eq!(err.code(), Code::InvalidArgument);
+
+    let err = server.client("unknown-token").await.watch_events("123", "").await.unwrap_err();
+    assert!(matches!(err, StreamError::Denied(_)));
+}
+
+#[tokio::test]
+async fn watchers_that_fall_behind_are_resubscribed() {
+    let streams = StreamConfig {
+        channel_buffer: 1,
+        watch_buffer: 1,
+        ..StreamConfig::default()
+    };
+    let service = ExamServiceImpl::new(InMemoryExamStore::with_sample_data()).with_streams(streams);
+    let server = TestServer::start_with_config(service, test_config()).await;
+    let client = server.client(TEACHER).await;
+
+    let mut events = client.watch_events("456", "").await.unwrap();
+
+    // Far more changes than the watcher buffers while nobody reads them
+    let upload = (0..2000).map(|marks| result("456", "phy101", marks % 100));
+    client.submit_results(tokio_stream::iter(upload)).await.unwrap();
+
+    let resubscribed = tokio::time::timeout(Duration::from_secs(10), async {
+        while let Some(event) = events.next().await {
+            if event.unwrap() == WatchEvent::Resubscribed {
+                return;
+            }
+        }
+        panic!("the watch ended");
+    });
+    resubscribed.await.expect("never resubscribed");
+
+    client.submit_result(result("456", "math101", 64)).await.unwrap();
+    let event = tokio::time::timeout(Duration::from_secs(5), events.next()).await.unwrap().unwrap().unwrap();
+    match event {
+        WatchEvent::Changed { kind, result } => {
+            assert_eq!(kind, ChangeKind::Created);
+            assert_eq!((result.exam_id.as_str(), result.marks_obtained), ("math101", 64));
+        }
+        event => panic!("unexpected event {event:?}"),
+    }
+}
@@ -0,0 +1,148 @@
+// Shared harness: boots the full server in-process on an ephemeral port.
+#![allow(dead_code)]
+
+use std::net::{Ipv4Addr, SocketAddr};
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tonic::transport::{Channel, Endpoint};
+
+use exam_service::auth::{Identity, Role, TokenAuth};
+use exam_service::client::{BearerToken, ExamClient};
+use exam_service::config::ServerConfig;
+use exam_service::key::{InstitutionId, DEFAULT_INSTITUTION};
+use exam_service::exam_service::exam_service_client::ExamServiceClient;
+use exam_service::server::{serve_on, ExamServiceImpl};
+use exam_service::store::{ExamStore, InMemoryExamStore};
+
+pub const ADMIN: &str = "admin-token";
+pub const TEACHER: &str = "teacher-token";
+// Bound to sample student 123
+pub const STUDENT: &str = "student-token";
+// Bound to sample student 456, who has no math101 result yet
+pub const OTHER_STUDENT: &str = "other-student-token";
+// Scoped to the `north` institution, which only multi-institution servers serve
+pub const NORTH: &str = "north";
+pub const NORTH_TEACHER: &str = "north-teacher-token";
+pub const NORTH_STUDENT: &str = "north-student-token";
+// An admin valid in every institution, naming one with `x-institution-id`
+pub const OPERATOR: &str = "operator-token";
+
+pub fn tokens() -> TokenAuth {
+    let identity = |institution: &str, role, student_id: Option<&str>, name: &str| Identity {
+        role,
+        student_id: student_id.map(str::to_string),
+        name: name.to_string(),
+        institution: InstitutionId::parse(institution).unwrap(),
+        every_institution: false,
+    };
+
+    TokenAuth::new([
+        (ADMIN.to_string(), identity(DEFAULT_INSTITUTION, Role::Admin, None, "admin")),
+        (TEACHER.to_string(), identity(DEFAULT_INSTITUTION, Role::Teacher, None, "teacher")),
+        (STUDENT.to_string(), identity(DEFAULT_INSTITUTION, Role::Student, Some("123"), "student")),
+        (OTHER_STUDENT.to_string(), identity(DEFAULT_INSTITUTION, Role::Student, Some("456"), "other-student")),
+        (NORTH_TEACHER.to_string(), identity(NORTH, Role::Teacher, None, "north-teacher")),
+        (NORTH_STUDENT.to_string(), identity(NORTH, Role::Student, Some("123"), "north-student")),
+    ])
+    .with_operator(OPERATOR, "operator")
+}
+
+// Every listener on an ephemeral loopback port, and no rate limit to trip over.
+pub fn test_config() -> ServerConfig {
+    let any_port = SocketAddr::from((Ipv4Addr::LOCALHOST, 0));
+
+    let mut config = ServerConfig {
+        host: any_port.ip(),
+        port: 0,
+        metrics_addr: any_port,
+        http_addr: any_port,
+        ..ServerConfig::default()
+    };
+    config.rate_limit.enabled = false;
+    config
+}
+
+pub struct TestServer {
+    pub addr: SocketAddr,
+    shutdown: Option<oneshot::Sender<()>>,
+    task: JoinHandle<Result<(), String>>,
+}
+
+impl TestServer {
+    // A server seeded with the sample students, exams and results.
+    pub async fn start() -> Self {
+        Self::start_with(ExamServiceImpl::new(InMemoryExamStore::with_sample_data())).await
+    }
+
+    pub async fn start_with<S: ExamStore>(service: ExamServiceImpl<S>) -> Self {
+        Self::start_with_config(service, test_config()).await
+    }
+
+    // `config` should start from `test_config()` so nothing binds a fixed port.
+    pub async fn start_with_config<S: ExamStore>(service: ExamServiceImpl<S>, config: ServerConfig) -> Self {
+        Self::start_with_auth(service, config, tokens()).await
+    }
+
+    // Like `start_with_config`, authenticating callers with `auth` rather than the test tokens.
+    pub async fn start_with_auth<S: ExamStore>(service: ExamServiceImpl<S>, config: ServerConfig, auth: TokenAuth) -> Self {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.expect("bind test port");
+        let addr = listener.local_addr().expect("local addr");
+        let (shutdown, signal) = oneshot::channel::<()>();
+
+        let task = tokio::spawn(async move {
+            let signal = async {
+                let _ = signal.await;
+            };
+            serve_on(&config, service, auth, listener, signal)
+                .await
+                .map_err(|err| err.to_string())
+        });
+
+        Self {
+            addr,
+            shutdown: Some(shutdown),
+            task,
+        }
+    }
+
+    pub async fn channel(&self) -> Channel {
+        Endpoint::from_shared(format!("http://{}", self.addr))
+            .expect("valid endpoint")
+            .connect()
+            .await
+            .expect("connect to test server")
+    }
+
+    pub async fn client(&self, token: &str) -> ExamClient {
+        ExamClient::with_channel(self.channel().await, BearerToken::new(token).expect("valid token"))
+    }
+
+    // A client acting in `institution`, as operators must.
+    pub async fn client_in(&self, token: &str, institution: &str) -> ExamClient {
+        let token = BearerToken::new(token)
+            .expect("valid token")
+            .with_institution(institution)
+            .expect("valid institution");
+        ExamClient::with_channel(self.channel().await, token)
+    }
+
+    // The generated client, for requests the typed client never builds.
+    pub async fn raw_client(
+        &self,
+        token: &str,
+    ) -> ExamServiceClient<tonic::service::interceptor::InterceptedService<Channel, BearerToken>> {
+        ExamServiceClient::with_interceptor(self.channel().await, BearerToken::new(token).expect("valid token"))
+    }
+
+    // Triggers a graceful shutdown and waits for the server to drain.
+    pub async fn shutdown(mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        (&mut self.task)
+            .await
+            .expect("server task panicked")
+            .expect("server exited with an error");
+    }
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use tokio_stream::StreamExt;
+use tonic::codec::CompressionEncoding;
+use tonic::Code;
+
+use common::{test_config, TestServer, ADMIN, TEACHER};
+use exam_service::config::{Compression, CompressionConfig, ServerConfig};
+use exam_service::exam_service::{ExamResult, ExportFormat, GetExamResultRequest, ListExamResultsRequest};
+use exam_service::server::ExamServiceImpl;
+use exam_service::store::InMemoryExamStore;
+
+async fn start(compression: CompressionConfig) -> TestServer {
+    let config = ServerConfig {
+        compression,
+        ..test_config()
+    };
+    TestServer::start_with_config(ExamServiceImpl::new(InMemoryExamStore::with_sample_data()), config).await
+}
+
+fn upload() -> impl tokio_stream::Stream<Item = ExamResult> {
+    tokio_stream::iter(["123", "456"].map(|student_id| ExamResult {
+        student_id: student_id.to_string(),
+        exam_id: "math101".to_string(),
+        marks_obtained: 77,
+        ..Default::default()
+    }))
+}
+
+#[tokio::test]
+async fn compressed_uploads_and_exports_round_trip() {
+    for (send, encoding) in [
+        (Compression::Gzip, CompressionEncoding::Gzip),
+        (Compression::Zstd, CompressionEncoding::Zstd),
+    ] {
+        let server = start(CompressionConfig { accept: true, send }).await;
+        let client = server.client(ADMIN).await.with_compression(encoding);
+
+        let response = client.submit_results(upload()).await.unwrap();
+        assert_eq!(response.accepted_count, 2);
+
+        let chunks: Vec<_> = client
+            .export_results(ListExamResultsRequest::default(), ExportFormat::JsonLines)
+            .await
+            .unwrap()
+            .map(|chunk| chunk.expect("export chunk").data)
+            .collect()
+            .await;
+        let lines = String::from_utf8(chunks.concat()).unwrap();
+        assert_eq!(lines.lines().filter(|line| line.contains("\"marks_obtained\":77")).count(), 2);
+    }
+}
+
+#[tokio::test]
+async fn clients_without_compression_get_plain_responses() {
+    let server = start(CompressionConfig {
+        accept: true,
+        send: Compression::Gzip,
+    })
+    .await;
+
+    // The generated client does not advertise any encoding
+    let mut client = server.raw_client(TEACHER).await;
+    let request = GetExamResultRequest {
+        student_id: "123".to_string(),
+        exam_id: "math101".to_string(),
+        ..Default::default()
+    };
+    assert!(client.get_exam_result(request).await.is_ok());
+}
+
+#[tokio::test]
+async fn compressed_requests_can_be_refused() {
+    let server = start(CompressionConfig {
+        accept: false,
+        send: Compression::None,
+    })
+    .await;
+    let client = server.client(TEACHER).await.with_compression(CompressionEncoding::Gzip);
+
+    let status = client.get_result("123", "math101").await.unwrap_err();
+    assert_eq!(status.code(), Code::Unimplemented);
+}
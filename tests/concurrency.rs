@@ -0,0 +1,233 @@
+mod common;
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use futures::future::join_all;
+use tokio_stream::StreamExt;
+use tonic::Code;
+
+use common::{TestServer, ADMIN, TEACHER};
+use exam_service::exam_service::{AuditRecord, ExamResult, GetExamResultResponse, ListExamResultsRequest};
+use exam_service::key::ResultKey;
+use exam_service::store::{ExamStore, InMemoryExamStore};
+use exam_service::student::Student;
+
+const WRITERS: usize = 20;
+
+fn student_id(n: usize) -> String {
+    format!("s{:03}", n)
+}
+
+#[tokio::test]
+async fn concurrent_submissions_are_all_stored() {
+    let server = TestServer::start().await;
+    let admin = server.client(ADMIN).await;
+
+    for n in 0..WRITERS {
+        let student = Student {
+            student_id: student_id(n),
+            name: format!("Student {}", n),
+            email: String::new(),
+        };
+        admin.register_student(student).await.unwrap();
+    }
+
+    let teacher = server.client(TEACHER).await;
+    let submissions = (0..WRITERS).map(|n| {
+        let teacher = teacher.clone();
+        async move {
+            let result = ExamResult {
+                student_id: student_id(n),
+                exam_id: "math101".to_string(),
+                marks_obtained: n as i32,
+                ..Default::default()
+            };
+            teacher.submit_result(result).await
+        }
+    });
+    for response in join_all(submissions).await {
+        assert!(response.unwrap().created);
+    }
+
+    let filter = ListExamResultsRequest {
+        exam_id: "math101".to_string(),
+        ..Default::default()
+    };
+    let stored: Vec<_> = admin
+        .list_results(filter)
+        .await
+        .unwrap()
+        .map(|result| result.expect("stream item"))
+        .filter(|result| result.student_id.starts_with('s'))
+        .collect()
+        .await;
+    assert_eq!(stored.len(), WRITERS);
+    for result in stored {
+        assert_eq!(result.student_id, student_id(result.marks_obtained as usize));
+    }
+}
+
+#[tokio::test]
+async fn racing_corrections_apply_once() {
+    let server = TestServer::start().await;
+    let teacher = server.client(TEACHER).await;
+
+    // Every writer read version 1; only the first to land may apply
+    let corrections = (0..WRITERS).map(|n| {
+        let teacher = teacher.clone();
+        async move { teacher.correct_result("123", "math101", 50 + n as i32, 1, "race").await }
+    });
+    let outcomes = join_all(corrections).await;
+
+    let applied: Vec<_> = outcomes.iter().filter_map(|outcome| outcome.as_ref().ok()).collect();
+    assert_eq!(applied.len(), 1);
+    assert!(outcomes
+        .iter()
+        .filter_map(|outcome| outcome.as_ref().err())
+        .all(|status| status.code() == Code::Aborted));
+
+    let current = teacher.get_result("123", "math101").await.unwrap();
+    assert_eq!(current.version, 2);
+    assert_eq!(Some(current.marks_obtained), applied[0].current.as_ref().map(|c| c.marks_obtained));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn racing_writes_to_one_result_are_applied_one_at_a_time() {
+    let server = TestServer::start().await;
+    let teacher = server.client(TEACHER).await;
+
+    // Resubmissions and corrections of the same result, all at once
+    let writes = (0..WRITERS).map(|n| {
+        let teacher = teacher.clone();
+        async move {
+            let marks_obtained = 40 + n as i32;
+            if n % 2 == 0 {
+                let result = ExamResult {
+                    student_id: "123".to_string(),
+                    exam_id: "math101".to_string(),
+                    marks_obtained,
+                    ..Default::default()
+                };
+                teacher.submit_result(result).await.map(drop)
+            } else {
+                let current = teacher.get_result("123", "math101").await?;
+                teacher
+                    .correct_result("123", "math101", marks_obtained, current.version, "race")
+                    .await
+                    .map(drop)
+            }
+        }
+    });
+    for outcome in join_all(writes).await {
+        // A correction may lose to a write made after it read; it is refused, never applied over it
+        if let Err(status) = outcome {
+            assert_eq!(status.code(), Code::Aborted);
+        }
+    }
+
+    // Each write started from the one before it, and the trail records them in that order
+    let trail = server.client(ADMIN).await.audit_trail("123", "math101").await.unwrap();
+    let versions = |record: &AuditRecord| {
+        let version = |result: &Option<GetExamResultResponse>| result.as_ref().map(|result| result.version);
+        (version(&record.before), version(&record.after))
+    };
+    for pair in trail.windows(2) {
+        let (_, earlier_after) = versions(&pair[0]);
+        let (later_before, later_after) = versions(&pair[1]);
+        assert_eq!(later_before, earlier_after);
+        assert_eq!(later_after, later_before.map(|version| version + 1));
+    }
+
+    let current = teacher.get_result("123", "math101").await.unwrap();
+    let last = trail.last().unwrap().after.as_ref().unwrap();
+    assert_eq!((current.version, current.marks_obtained), (last.version, last.marks_obtained));
+}
+
+#[tokio::test]
+async fn shuts_down_gracefully() {
+    let server = TestServer::start().await;
+    let client = server.client(ADMIN).await;
+    client.get_result("123", "math101").await.unwrap();
+
+    server.shutdown().await;
+}
+
+fn stored(student_id: String, marks_obtained: i32) -> (ResultKey, ExamResult) {
+    let result = ExamResult {
+        student_id,
+        exam_id: "math101".to_string(),
+        marks_obtained,
+        ..Default::default()
+    };
+    (ResultKey::from(&result), result)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn readers_are_not_starved_by_bulk_writers() {
+    let store = Arc::new(InMemoryExamStore::with_sample_data());
+    let stopped = Arc::new(AtomicBool::new(false));
+    let written = Arc::new(AtomicUsize::new(0));
+
+    // Writers importing as fast as they can, across many students
+    let writers: Vec<_> = (0..8)
+        .map(|writer| {
+            let (store, stopped, written) = (store.clone(), stopped.clone(), written.clone());
+            tokio::spawn(async move {
+                let mut n = 0;
+                while !stopped.load(Ordering::Relaxed) {
+                    let (key, result) = stored(format!("w{}-{:03}", writer, n % 500), n % 100);
+                    store.put(key, result).await.unwrap();
+                    written.fetch_add(1, Ordering::Relaxed);
+                    n += 1;
+                }
+            })
+        })
+        .collect();
+
+    // Meanwhile single reads and listings keep completing
+    let key = ResultKey::parse("123", "math101").unwrap();
+    tokio::time::timeout(Duration::from_secs(10), async {
+        for _ in 0..2000 {
+            assert_eq!(store.get(&key).await.unwrap().unwrap().marks_obtained, 95);
+        }
+        for _ in 0..50 {
+            store.list_page(None, 100).await.unwrap();
+        }
+    })
+    .await
+    .expect("readers finish while the writers run");
+    let written_meanwhile = written.load(Ordering::Relaxed);
+
+    stopped.store(true, Ordering::Relaxed);
+    for writer in writers {
+        writer.await.unwrap();
+    }
+    assert!(written_meanwhile > 0, "the writers ran alongside the readers");
+}
+
+#[tokio::test]
+async fn pages_stay_in_key_order_across_shards() {
+    let store = InMemoryExamStore::new();
+    let writes = (0..200).map(|n| {
+        let (key, result) = stored(format!("s{:03}", (n * 37) % 200), n);
+        store.put(key, result)
+    });
+    for written in join_all(writes).await {
+        written.unwrap();
+    }
+
+    let mut paged = Vec::new();
+    let mut after = None;
+    loop {
+        let page = store.list_page(after.as_ref(), 7).await.unwrap();
+        let Some(last) = page.last() else { break };
+        after = Some(ResultKey::from(last));
+        paged.extend(page);
+    }
+
+    let ids: Vec<_> = paged.iter().map(|result| result.student_id.clone()).collect();
+    let expected: Vec<_> = (0..200).map(|n| format!("s{:03}", n)).collect();
+    assert_eq!(ids, expected);
+    assert_eq!(paged, store.list().await.unwrap());
+}
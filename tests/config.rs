@@ -0,0 +1,31 @@
+use std::path::Path;
+
+use exam_service::config::{NameDisclosure, ServerConfig, StorageBackend};
+
+// The only test in this binary: it sets process-wide environment variables.
+#[test]
+fn environment_overrides_reach_every_section() {
+    // SAFETY: no other thread of this test binary reads the environment
+    unsafe {
+        std::env::set_var("EXAM_PORT", "50099");
+        std::env::set_var("EXAM_CONNECTIONS_MAX_CONCURRENT_STREAMS", "500");
+        std::env::set_var("EXAM_PRIVACY_STUDENT_NAMES", "pseudonymize");
+        std::env::set_var("EXAM_RATE_LIMIT_ENABLED", "false");
+        std::env::set_var("EXAM_STORAGE_BACKEND", "sqlite");
+        std::env::set_var("EXAM_STREAM_CHANNEL_BUFFER", "9");
+    }
+
+    let config = ServerConfig::from_figment(ServerConfig::figment(Path::new("missing.toml"))).unwrap();
+
+    assert_eq!(config.port, 50099);
+    assert_eq!(config.connections.max_concurrent_streams, 500);
+    assert_eq!(config.privacy.student_names, NameDisclosure::Pseudonymize);
+    assert!(!config.rate_limit.enabled);
+    assert_eq!(config.storage.backend, StorageBackend::Sqlite);
+    assert_eq!(config.stream.channel_buffer, 9);
+
+    // An empty buffer is refused up front, not left to panic once a watch starts
+    unsafe { std::env::set_var("EXAM_STREAM_WATCH_BUFFER", "0") };
+    let err = ServerConfig::from_figment(ServerConfig::figment(Path::new("missing.toml"))).unwrap_err();
+    assert_eq!(err.to_string(), "stream.watch_buffer must be at least 1");
+}
@@ -0,0 +1,124 @@
+mod common;
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tonic::Code;
+use tonic_types::StatusExt;
+
+use common::{test_config, TestServer, ADMIN, TEACHER};
+use exam_service::config::{RateLimitConfig, ServerConfig};
+use exam_service::exam_service::{ExamResult, GetExamResultRequest, SubmitExamResultRequest};
+use exam_service::server::ExamServiceImpl;
+use exam_service::store::InMemoryExamStore;
+
+// A limit of `requests_per_second` with no burst beyond a single call.
+async fn rate_limited_server(requests_per_second: f64) -> TestServer {
+    let config = ServerConfig {
+        rate_limit: RateLimitConfig {
+            enabled: true,
+            requests_per_second,
+            burst: 1,
+            methods: HashMap::new(),
+        },
+        ..test_config()
+    };
+    let service = ExamServiceImpl::new(InMemoryExamStore::with_sample_data());
+    TestServer::start_with_config(service, config).await
+}
+
+fn submission(result: ExamResult) -> SubmitExamResultRequest {
+    SubmitExamResultRequest {
+        result: Some(result),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn missing_results_name_the_resource() {
+    let server = TestServer::start().await;
+    let mut client = server.raw_client(ADMIN).await;
+
+    let request = GetExamResultRequest {
+        student_id: "123".to_string(),
+        exam_id: "history101".to_string(),
+        ..Default::default()
+    };
+    let status = client.get_exam_result(request).await.unwrap_err();
+
+    assert_eq!(status.code(), Code::NotFound);
+    let info = status.get_details_resource_info().expect("a ResourceInfo detail");
+    assert_eq!(info.resource_type, "exam_result");
+    assert_eq!(info.resource_name, "123/history101");
+}
+
+#[tokio::test]
+async fn unregistered_students_are_a_failed_precondition() {
+    let server = TestServer::start().await;
+    let mut client = server.raw_client(TEACHER).await;
+
+    let result = ExamResult {
+        student_id: "999".to_string(),
+        exam_id: "math101".to_string(),
+        marks_obtained: 50,
+        ..Default::default()
+    };
+    let status = client.submit_exam_result(submission(result)).await.unwrap_err();
+
+    assert_eq!(status.code(), Code::FailedPrecondition);
+    let failure = status.get_details_precondition_failure().expect("a PreconditionFailure detail");
+    assert_eq!(failure.violations[0].r#type, "NOT_REGISTERED");
+    assert_eq!(failure.violations[0].subject, "student/999");
+}
+
+#[tokio::test]
+async fn catalog_mismatches_name_the_field() {
+    let server = TestServer::start().await;
+    let mut client = server.raw_client(TEACHER).await;
+
+    let result = ExamResult {
+        student_id: "456".to_string(),
+        exam_id: "math101".to_string(),
+        subject: "History".to_string(),
+        marks_obtained: 50,
+        ..Default::default()
+    };
+    let status = client.submit_exam_result(submission(result)).await.unwrap_err();
+
+    assert_eq!(status.code(), Code::InvalidArgument);
+    let bad_request = status.get_details_bad_request().expect("a BadRequest detail");
+    assert_eq!(bad_request.field_violations[0].field, "subject");
+}
+
+#[tokio::test]
+async fn rate_limited_calls_say_when_to_retry() {
+    let server = rate_limited_server(2.0).await;
+    let mut client = server.raw_client(ADMIN).await;
+
+    let request = || GetExamResultRequest {
+        student_id: "123".to_string(),
+        exam_id: "math101".to_string(),
+        ..Default::default()
+    };
+    client.get_exam_result(request()).await.unwrap();
+    let status = client.get_exam_result(request()).await.unwrap_err();
+
+    assert_eq!(status.code(), Code::ResourceExhausted);
+    let delay = status
+        .get_details_retry_info()
+        .and_then(|info| info.retry_delay)
+        .expect("a RetryInfo delay");
+    assert!(delay > Duration::ZERO && delay <= Duration::from_millis(500));
+}
+
+#[tokio::test]
+async fn the_client_waits_out_the_retry_delay() {
+    let server = rate_limited_server(5.0).await;
+    let client = server.client(ADMIN).await;
+
+    client.get_result("123", "math101").await.unwrap();
+    let started = Instant::now();
+    client.get_result("123", "math101").await.unwrap();
+
+    // The next token was about 200ms away; the default first backoff is 50ms at most
+    assert!(started.elapsed() >= Duration::from_millis(150));
+}
@@ -0,0 +1,182 @@
+mod common;
+
+use tonic::{Code, Streaming};
+
+use common::{TestServer, ADMIN, STUDENT, TEACHER};
+use exam_service::client::ExamClient;
+use exam_service::exam_service::ExamResult;
+use exam_service::grader::{Allocation, DistributionStrategy, ExamGraders, GradingTask};
+use exam_service::key::ExamId;
+use exam_service::store::{ExamStore, InMemoryExamStore, SqliteExamStore};
+use exam_service::student::Student;
+
+// Registers students s`from`..=s`to` and submits a math101 draft for each.
+async fn submit_drafts(admin: &ExamClient, from: u32, to: u32) {
+    for n in from..=to {
+        let student = Student {
+            student_id: format!("s{n}"),
+            name: format!("Student {n}"),
+            email: String::new(),
+        };
+        admin.register_student(student).await.unwrap();
+        let result = ExamResult {
+            student_id: format!("s{n}"),
+            exam_id: "math101".to_string(),
+            marks_obtained: 50 + n as i32,
+            ..Default::default()
+        };
+        admin.submit_result(result).await.unwrap();
+    }
+}
+
+async fn tasks(mut queue: Streaming<GradingTask>) -> Vec<GradingTask> {
+    let mut tasks = Vec::new();
+    while let Some(task) = queue.message().await.unwrap() {
+        tasks.push(task);
+    }
+    tasks
+}
+
+fn students(tasks: &[GradingTask]) -> Vec<&str> {
+    tasks
+        .iter()
+        .map(|task| task.allocation.as_ref().unwrap().student_id.as_str())
+        .collect()
+}
+
+#[tokio::test]
+async fn drafts_are_dealt_in_turn_and_queued_for_their_grader() {
+    let server = TestServer::start().await;
+    let admin = server.client(ADMIN).await;
+    let teacher = server.client(TEACHER).await;
+    submit_drafts(&admin, 1, 4).await;
+
+    let assigned = admin.assign_graders("math101", &["teacher", "admin"]).await.unwrap();
+    assert_eq!(assigned.graders, ["teacher", "admin"]);
+    assert_eq!(teacher.graders("math101").await.unwrap(), ["teacher", "admin"]);
+
+    let dealt = admin.distribute_submissions("math101", DistributionStrategy::RoundRobin).await.unwrap();
+    let graders: Vec<_> = dealt.allocated.iter().map(|allocation| allocation.grader.as_str()).collect();
+    assert_eq!(graders, ["teacher", "admin", "teacher", "admin"]);
+    assert_eq!(dealt.load["teacher"], 2);
+    assert_eq!(dealt.load["admin"], 2);
+
+    let queue = tasks(teacher.grading_queue("", "").await.unwrap()).await;
+    assert_eq!(students(&queue), ["s1", "s3"]);
+    assert_eq!(queue[0].subject, "Math 101");
+    assert_eq!(queue[0].marks_obtained, 51);
+    assert_eq!(queue[0].version, 1);
+
+    // Drafts already held stay put; new ones carry on from the next grader
+    let dealt = admin.distribute_submissions("math101", DistributionStrategy::Unspecified).await.unwrap();
+    assert!(dealt.allocated.is_empty());
+    submit_drafts(&admin, 5, 5).await;
+    let dealt = admin.distribute_submissions("math101", DistributionStrategy::RoundRobin).await.unwrap();
+    assert_eq!(dealt.allocated.len(), 1);
+    assert_eq!(dealt.allocated[0].grader, "teacher");
+
+    // Published results leave the queues
+    admin.publish_results("math101").await.unwrap();
+    assert!(tasks(teacher.grading_queue("", "").await.unwrap()).await.is_empty());
+    assert!(tasks(admin.grading_queue("math101", "teacher").await.unwrap()).await.is_empty());
+}
+
+#[tokio::test]
+async fn least_loaded_fills_the_emptiest_queue_and_takes_back_from_unassigned_graders() {
+    let server = TestServer::start().await;
+    let admin = server.client(ADMIN).await;
+    let teacher = server.client(TEACHER).await;
+    submit_drafts(&admin, 1, 3).await;
+
+    admin.assign_graders("math101", &["teacher"]).await.unwrap();
+    admin.distribute_submissions("math101", DistributionStrategy::LeastLoaded).await.unwrap();
+    assert_eq!(tasks(teacher.grading_queue("math101", "").await.unwrap()).await.len(), 3);
+
+    // The teacher's drafts go to whoever is assigned next
+    admin.assign_graders("math101", &["admin"]).await.unwrap();
+    assert!(tasks(teacher.grading_queue("math101", "").await.unwrap()).await.is_empty());
+    let dealt = admin.distribute_submissions("math101", DistributionStrategy::LeastLoaded).await.unwrap();
+    assert_eq!(dealt.allocated.len(), 3);
+    assert!(dealt.allocated.iter().all(|allocation| allocation.grader == "admin"));
+
+    admin.assign_graders("math101", &["admin", "teacher"]).await.unwrap();
+    submit_drafts(&admin, 4, 6).await;
+    let dealt = admin.distribute_submissions("math101", DistributionStrategy::LeastLoaded).await.unwrap();
+    let graders: Vec<_> = dealt.allocated.iter().map(|allocation| allocation.grader.as_str()).collect();
+    assert_eq!(graders, ["teacher", "teacher", "teacher"]);
+    assert_eq!((dealt.load["admin"], dealt.load["teacher"]), (3, 3));
+}
+
+#[tokio::test]
+async fn only_admins_assign_and_distribute() {
+    let server = TestServer::start().await;
+    let admin = server.client(ADMIN).await;
+    let teacher = server.client(TEACHER).await;
+
+    let status = teacher.assign_graders("math101", &["teacher"]).await.unwrap_err();
+    assert_eq!(status.code(), Code::PermissionDenied);
+    let status = server.client(STUDENT).await.graders("math101").await.unwrap_err();
+    assert_eq!(status.code(), Code::PermissionDenied);
+    let status = teacher.grading_queue("", "admin").await.unwrap_err();
+    assert_eq!(status.code(), Code::PermissionDenied);
+
+    let status = admin.assign_graders("chem999", &["teacher"]).await.unwrap_err();
+    assert_eq!(status.code(), Code::FailedPrecondition);
+    let status = admin.assign_graders("math101", &["teacher", "teacher"]).await.unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+
+    let status = admin
+        .distribute_submissions("math101", DistributionStrategy::RoundRobin)
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::FailedPrecondition);
+
+    // Assigning nobody unassigns everyone
+    admin.assign_graders("math101", &["teacher"]).await.unwrap();
+    admin.assign_graders("math101", &[]).await.unwrap();
+    assert!(admin.graders("math101").await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn sqlite_keeps_graders_and_allocations() {
+    let store = SqliteExamStore::open(":memory:").unwrap();
+    let math101 = ExamId::parse("math101").unwrap();
+    let allocation = |student_id: &str, grader: &str| Allocation {
+        student_id: student_id.to_string(),
+        exam_id: "math101".to_string(),
+        grader: grader.to_string(),
+        allocated_at_ms: 1_000,
+    };
+
+    store
+        .set_graders(ExamGraders {
+            exam_id: "math101".to_string(),
+            graders: vec!["teacher".to_string(), "admin".to_string()],
+        })
+        .await
+        .unwrap();
+    assert_eq!(store.get_graders(&math101).await.unwrap(), ["teacher", "admin"]);
+
+    store
+        .put_allocations(vec![allocation("456", "teacher"), allocation("123", "teacher")])
+        .await
+        .unwrap();
+    store.put_allocations(vec![allocation("456", "admin")]).await.unwrap();
+    let held = store.list_allocations(Some(&math101), None).await.unwrap();
+    assert_eq!(held, [allocation("123", "teacher"), allocation("456", "admin")]);
+    assert_eq!(store.list_allocations(None, Some("admin")).await.unwrap(), [allocation("456", "admin")]);
+
+    // Snapshots carry both over to any other backend
+    let memory = InMemoryExamStore::new();
+    memory.restore(store.dump().await.unwrap()).await.unwrap();
+    assert_eq!(memory.dump().await.unwrap(), store.dump().await.unwrap());
+
+    store
+        .set_graders(ExamGraders {
+            exam_id: "math101".to_string(),
+            graders: Vec::new(),
+        })
+        .await
+        .unwrap();
+    assert!(store.get_graders(&math101).await.unwrap().is_empty());
+}
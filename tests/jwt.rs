@@ -0,0 +1,177 @@
+mod common;
+
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::time::{SystemTime, UNIX_EPOCH};
+use axum::routing::get;
+use axum::{Json, Router};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use ring::rand::SystemRandom;
+use ring::signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING};
+use serde_json::{json, Value};
+use tokio::net::TcpListener;
+use tonic::Code;
+
+use common::{test_config, tokens, TestServer, ADMIN};
+use exam_service::auth::JwtAuth;
+use exam_service::config::JwtConfig;
+use exam_service::exam_service::ExamResult;
+use exam_service::server::ExamServiceImpl;
+use exam_service::store::InMemoryExamStore;
+
+const AUDIENCE: &str = "exam-service";
+
+// A stand-in OIDC provider publishing one ES256 key through discovery.
+struct Provider {
+    issuer: String,
+    key: EncodingKey,
+}
+
+impl Provider {
+    async fn start() -> Self {
+        let rng = SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng).unwrap();
+        let pair = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8.as_ref(), &rng).unwrap();
+        // An uncompressed point: 0x04, then x and y
+        let point = pair.public_key().as_ref();
+        let jwks = json!({
+            "keys": [{
+                "kty": "EC",
+                "crv": "P-256",
+                "kid": "key-1",
+                "alg": "ES256",
+                "use": "sig",
+                "x": URL_SAFE_NO_PAD.encode(&point[1..33]),
+                "y": URL_SAFE_NO_PAD.encode(&point[33..]),
+            }]
+        });
+
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let addr: SocketAddr = listener.local_addr().unwrap();
+        let issuer = format!("http://{}", addr);
+        let discovery = json!({
+            "issuer": issuer,
+            "jwks_uri": format!("{}/keys", issuer),
+        });
+
+        let app = Router::new()
+            .route("/.well-known/openid-configuration", get(move || async move { Json(discovery) }))
+            .route("/keys", get(move || async move { Json(jwks) }));
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        Self {
+            issuer,
+            key: EncodingKey::from_ec_der(pkcs8.as_ref()),
+        }
+    }
+
+    fn config(&self) -> JwtConfig {
+        JwtConfig {
+            issuer: self.issuer.clone(),
+            audience: AUDIENCE.to_string(),
+            roles: HashMap::from([
+                ("faculty".to_string(), "teacher".to_string()),
+                ("students".to_string(), "student".to_string()),
+            ]),
+            ..JwtConfig::default()
+        }
+    }
+
+    // A token for `claims` on top of a valid issuer, audience and expiry.
+    fn token(&self, claims: Value) -> String {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let mut all = json!({
+            "iss": self.issuer,
+            "aud": AUDIENCE,
+            "iat": now,
+            "exp": now + 600,
+            "sub": "user-1",
+        });
+        all.as_object_mut().unwrap().extend(claims.as_object().unwrap().clone());
+
+        let mut header = Header::new(Algorithm::ES256);
+        header.kid = Some("key-1".to_string());
+        encode(&header, &all, &self.key).unwrap()
+    }
+
+    async fn serve(&self) -> TestServer {
+        let jwt = JwtAuth::from_config(&self.config()).await.unwrap().expect("issuer configured");
+        let service = ExamServiceImpl::new(InMemoryExamStore::with_sample_data());
+        TestServer::start_with_auth(service, test_config(), tokens().with_jwt(jwt)).await
+    }
+}
+
+#[tokio::test]
+async fn provider_tokens_act_as_the_roles_their_claims_map_to() {
+    let provider = Provider::start().await;
+    let server = provider.serve().await;
+
+    let teacher = provider.token(json!({ "roles": ["staff", "faculty"], "preferred_username": "ada" }));
+    let teacher = server.client(&teacher).await;
+    let result = ExamResult {
+        student_id: "456".to_string(),
+        exam_id: "math101".to_string(),
+        marks_obtained: 71,
+        ..Default::default()
+    };
+    teacher.submit_result(result).await.unwrap();
+
+    let trail = server.client(ADMIN).await.audit_trail("456", "math101").await.unwrap();
+    assert_eq!(trail.last().unwrap().actor, "ada");
+    assert_eq!(trail.last().unwrap().role, "teacher");
+
+    let student = provider.token(json!({ "roles": "students", "student_id": "123" }));
+    let student = server.client(&student).await;
+    assert_eq!(student.get_result("123", "math101").await.unwrap().marks_obtained, 95);
+    let status = student.get_result("456", "phy101").await.unwrap_err();
+    assert_eq!(status.code(), Code::PermissionDenied);
+
+    // API tokens keep working alongside
+    assert!(server.client(ADMIN).await.get_result("456", "phy101").await.is_ok());
+}
+
+#[tokio::test]
+async fn provider_tokens_are_checked() {
+    let provider = Provider::start().await;
+    let server = provider.serve().await;
+    let code = |token: String| {
+        let server = &server;
+        async move { server.client(&token).await.get_result("123", "math101").await.unwrap_err().code() }
+    };
+
+    let other_audience = provider.token(json!({ "roles": "faculty", "aud": "another-service" }));
+    assert_eq!(code(other_audience).await, Code::Unauthenticated);
+    let other_issuer = provider.token(json!({ "roles": "faculty", "iss": "https://elsewhere" }));
+    assert_eq!(code(other_issuer).await, Code::Unauthenticated);
+    let expired = provider.token(json!({ "roles": "faculty", "exp": 1_000_000 }));
+    assert_eq!(code(expired).await, Code::Unauthenticated);
+
+    // Signed with a shared secret instead of the provider's key
+    let forged = encode(
+        &Header::new(Algorithm::HS256),
+        &json!({ "iss": provider.issuer, "aud": AUDIENCE, "exp": u32::MAX, "roles": "faculty" }),
+        &EncodingKey::from_secret(b"guessed"),
+    )
+    .unwrap();
+    assert_eq!(code(forged).await, Code::Unauthenticated);
+
+    let no_role = provider.token(json!({ "roles": ["alumni"] }));
+    assert_eq!(code(no_role).await, Code::PermissionDenied);
+    let no_student_id = provider.token(json!({ "roles": "students" }));
+    assert_eq!(code(no_student_id).await, Code::PermissionDenied);
+}
+
+#[tokio::test]
+async fn jwt_settings_are_checked_at_startup() {
+    assert!(JwtAuth::from_config(&JwtConfig::default()).await.unwrap().is_none());
+
+    let config = JwtConfig {
+        issuer: "http://127.0.0.1:1".to_string(),
+        roles: HashMap::from([("faculty".to_string(), "dean".to_string())]),
+        ..JwtConfig::default()
+    };
+    let err = JwtAuth::from_config(&config).await.unwrap_err();
+    assert!(err.to_string().contains("unknown role"));
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use common::{TestServer, ADMIN, TEACHER};
+use exam_service::exam_service::{ExamResult, Grade};
+use exam_service::google::protobuf::Timestamp;
+use exam_service::key::ResultKey;
+use exam_service::model::{ms_from_timestamp, timestamp_from_date, timestamp_from_ms};
+use exam_service::store::{ExamStore, SqliteExamStore};
+
+// 2024-06-03T00:00:00Z, the sample math101 exam's date.
+const MATH101_DATE_SECS: i64 = 1_717_372_800;
+
+fn now_ms() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64
+}
+
+#[tokio::test]
+async fn results_carry_their_grade_exam_date_and_grading_time() {
+    let server = TestServer::start().await;
+    let teacher = server.client(TEACHER).await;
+
+    let sample = teacher.get_result("123", "math101").await.unwrap();
+    assert_eq!(sample.grade_level(), Grade::APlus);
+    assert_eq!(sample.exam_date, Some(Timestamp { seconds: MATH101_DATE_SECS, nanos: 0 }));
+    // Sample results were never graded by this server
+    assert_eq!(sample.graded_at, None);
+
+    let before = now_ms();
+    let submitted = teacher
+        .submit_result(ExamResult {
+            student_id: "123".to_string(),
+            exam_id: "phy101".to_string(),
+            marks_obtained: 76,
+            // Both are the server's to set
+            exam_date: Some(timestamp_from_ms(0)),
+            graded_at: Some(timestamp_from_ms(0)),
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+        .result
+        .unwrap();
+    assert_eq!((submitted.grade.as_str(), submitted.grade_level()), ("B", Grade::B));
+    assert_eq!(submitted.exam_date, timestamp_from_date("2024-06-05"));
+    let graded_at = ms_from_timestamp(submitted.graded_at.as_ref().unwrap());
+    assert!(graded_at >= before);
+
+    let corrected = server
+        .client(ADMIN)
+        .await
+        .correct_result("123", "phy101", 45, submitted.version, "remarked")
+        .await
+        .unwrap()
+        .current
+        .unwrap();
+    assert_eq!(corrected.grade_level(), Grade::F);
+    assert!(ms_from_timestamp(corrected.graded_at.as_ref().unwrap()) >= graded_at);
+}
+
+#[test]
+fn grades_convert_to_and_from_letters() {
+    assert_eq!(Grade::from_letter(" b+ "), Grade::BPlus);
+    assert_eq!(Grade::from_letter("A-").letter(), "A-");
+    assert_eq!(Grade::from_letter("Distinction"), Grade::Unspecified);
+    assert_eq!(Grade::Unspecified.letter(), "");
+
+    // Numbered lowest first, so better grades compare higher
+    assert!(Grade::APlus > Grade::A && Grade::DMinus > Grade::E);
+}
+
+#[test]
+fn timestamps_convert_to_and_from_milliseconds_and_dates() {
+    assert_eq!(timestamp_from_ms(1_500), Timestamp { seconds: 1, nanos: 500_000_000 });
+    assert_eq!(timestamp_from_ms(-1), Timestamp { seconds: -1, nanos: 999_000_000 });
+    for ms in [0, -1, 1_717_372_800_123] {
+        assert_eq!(ms_from_timestamp(&timestamp_from_ms(ms)), ms);
+    }
+
+    assert_eq!(timestamp_from_date("1970-01-01"), Some(timestamp_from_ms(0)));
+    assert_eq!(timestamp_from_date("2024-03-01").unwrap().seconds, 1_709_251_200);
+    assert_eq!(timestamp_from_date("1969-12-31").unwrap().seconds, -86_400);
+    assert_eq!(timestamp_from_date(""), None);
+    assert_eq!(timestamp_from_date("2024-13-01"), None);
+}
+
+#[tokio::test]
+async fn sqlite_keeps_result_dates() {
+    let store = SqliteExamStore::open(":memory:").unwrap();
+    let result = ExamResult {
+        student_id: "123".to_string(),
+        exam_id: "math101".to_string(),
+        exam_date: timestamp_from_date("2024-06-03"),
+        graded_at: Some(timestamp_from_ms(1_717_400_000_250)),
+        ..Default::default()
+    };
+    let key = ResultKey::from(&result);
+    store.put(key.clone(), result.clone()).await.unwrap();
+
+    let stored = store.get(&key).await.unwrap().unwrap();
+    assert_eq!((stored.exam_date, stored.graded_at), (result.exam_date, result.graded_at));
+
+    store.delete(&key, 1_000).await.unwrap();
+    let deleted = store.list_deleted(None, None).await.unwrap();
+    assert_eq!(deleted[0].result.as_ref().unwrap().graded_at, result.graded_at);
+}
@@ -0,0 +1,153 @@
+mod common;
+
+use std::time::Duration;
+use tokio_stream::StreamExt;
+use tonic::Code;
+
+use common::{TestServer, ADMIN, STUDENT, TEACHER};
+use exam_service::client::ExamClient;
+use exam_service::config::OperationsConfig;
+use exam_service::exam_service::{
+    ExamResult, ExportFormat, ListExamResultsRequest, Operation, OperationKind, OperationState, ResultStatus,
+    WatchOperationRequest,
+};
+use exam_service::server::ExamServiceImpl;
+use exam_service::store::InMemoryExamStore;
+
+fn result(student_id: &str, exam_id: &str, marks_obtained: i32) -> ExamResult {
+    ExamResult {
+        student_id: student_id.to_string(),
+        exam_id: exam_id.to_string(),
+        marks_obtained,
+        ..Default::default()
+    }
+}
+
+// Every state the operation is watched through, ending with the finished one.
+async fn watch_until_finished(client: &ExamClient, operation: &Operation) -> Vec<Operation> {
+    let updates = client.watch_operation(&operation.operation_id).await.unwrap();
+    let states: Vec<_> = tokio::time::timeout(Duration::from_secs(10), updates.collect())
+        .await
+        .expect("the operation finishes");
+    states.into_iter().map(Result::unwrap).collect()
+}
+
+#[tokio::test]
+async fn imports_run_in_the_background_and_report_rejected_records() {
+    let server = TestServer::start().await;
+    let teacher = server.client(TEACHER).await;
+
+    let started = teacher
+        .start_import(vec![result("123", "phy101", 81), result("999", "phy101", 50), result("456", "math101", 64)])
+        .await
+        .unwrap();
+    assert_eq!(started.kind(), OperationKind::Import);
+    assert_eq!(started.total_count, 3);
+    assert!(started.started_at.is_some());
+
+    let states = watch_until_finished(&teacher, &started).await;
+    assert!(states.iter().all(|state| state.operation_id == started.operation_id));
+    assert!(states.windows(2).all(|pair| pair[0].percent_done <= pair[1].percent_done));
+
+    let finished = states.last().unwrap();
+    assert_eq!(finished.state(), OperationState::Succeeded);
+    assert_eq!((finished.percent_done, finished.processed_count, finished.result_count), (100, 3, 2));
+    assert_eq!(finished.errors.len(), 1);
+    assert_eq!((finished.errors[0].index, finished.errors[0].student_id.as_str()), (1, "999"));
+    assert!(finished.finished_at.is_some());
+
+    let imported = teacher.get_result("456", "math101").await.unwrap();
+    assert_eq!((imported.marks_obtained, imported.status()), (64, ResultStatus::Draft));
+
+    // Watching a finished operation sends its final state and ends
+    let again = watch_until_finished(&teacher, &started).await;
+    assert_eq!(&again, std::slice::from_ref(finished));
+}
+
+#[tokio::test]
+async fn background_exports_can_be_downloaded_once_finished() {
+    let server = TestServer::start().await;
+    let admin = server.client(ADMIN).await;
+
+    let started = admin.start_export(ListExamResultsRequest::default(), ExportFormat::Csv).await.unwrap();
+    assert_eq!(started.kind(), OperationKind::Export);
+
+    let finished = watch_until_finished(&admin, &started).await.pop().unwrap();
+    assert_eq!(finished.state(), OperationState::Succeeded);
+    assert_eq!((finished.total_count, finished.result_count), (2, 2));
+
+    let mut downloaded = Vec::new();
+    let mut chunks = admin.download_export(&started.operation_id).await.unwrap();
+    while let Some(chunk) = chunks.next().await {
+        downloaded.extend(chunk.unwrap().data);
+    }
+
+    let mut exported = Vec::new();
+    let mut chunks = admin.export_results(ListExamResultsRequest::default(), ExportFormat::Csv).await.unwrap();
+    while let Some(chunk) = chunks.next().await {
+        exported.extend(chunk.unwrap().data);
+    }
+    assert_eq!(downloaded, exported);
+}
+
+#[tokio::test]
+async fn publishes_cover_several_exams_and_record_the_ones_that_fail() {
+    let server = TestServer::start().await;
+    let teacher = server.client(TEACHER).await;
+    for draft in [result("123", "phy101", 81), result("456", "math101", 64)] {
+        teacher.submit_result(draft).await.unwrap();
+    }
+
+    let started = teacher.start_publish(&["math101", "chem999", "phy101"]).await.unwrap();
+    let finished = watch_until_finished(&teacher, &started).await.pop().unwrap();
+    assert_eq!(finished.state(), OperationState::Succeeded);
+    assert_eq!((finished.processed_count, finished.total_count, finished.result_count), (3, 3, 2));
+    assert_eq!(finished.errors.len(), 1);
+    assert_eq!((finished.errors[0].index, finished.errors[0].exam_id.as_str()), (1, "chem999"));
+
+    for (student_id, exam_id) in [("123", "phy101"), ("456", "math101")] {
+        let published = teacher.get_result(student_id, exam_id).await.unwrap();
+        assert_eq!(published.status(), ResultStatus::Published);
+    }
+}
+
+#[tokio::test]
+async fn operations_are_only_visible_to_their_owner_and_admins() {
+    let server = TestServer::start().await;
+    let teacher = server.client(TEACHER).await;
+    let started = teacher.start_publish(&["math101"]).await.unwrap();
+
+    let status = server.client(STUDENT).await.watch_operation(&started.operation_id).await.unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+    let admin = server.client(ADMIN).await;
+    assert_eq!(watch_until_finished(&admin, &started).await.pop().unwrap().state(), OperationState::Succeeded);
+
+    // Only exports leave a file behind
+    let status = teacher.download_export(&started.operation_id).await.unwrap_err();
+    assert_eq!(status.code(), Code::FailedPrecondition);
+
+    let status = server
+        .raw_client(TEACHER)
+        .await
+        .watch_operation(WatchOperationRequest::default())
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn operations_beyond_the_running_limit_are_refused() {
+    let service = ExamServiceImpl::new(InMemoryExamStore::with_sample_data()).with_operations(&OperationsConfig {
+        max_running: 0,
+        ..Default::default()
+    });
+    let server = TestServer::start_with(service).await;
+
+    let status = server
+        .client(ADMIN)
+        .await
+        .start_export(ListExamResultsRequest::default(), ExportFormat::JsonLines)
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::ResourceExhausted);
+}
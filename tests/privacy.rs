@@ -0,0 +1,116 @@
+mod common;
+
+use common::{test_config, TestServer, ADMIN, STUDENT, TEACHER};
+use exam_service::config::{NameDisclosure, PrivacyConfig};
+use exam_service::exam_service::ExamResult;
+use exam_service::server::ExamServiceImpl;
+use exam_service::store::InMemoryExamStore;
+use exam_service::student::Student;
+
+async fn start(privacy: PrivacyConfig) -> TestServer {
+    let service = ExamServiceImpl::new(InMemoryExamStore::with_sample_data())
+        .with_privacy(&privacy)
+        .unwrap();
+    TestServer::start_with_config(service, test_config()).await
+}
+
+fn names(student_names: NameDisclosure) -> PrivacyConfig {
+    PrivacyConfig {
+        student_names,
+        ..PrivacyConfig::default()
+    }
+}
+
+#[tokio::test]
+async fn redacted_names_are_hidden_from_students_only() {
+    let server = start(names(NameDisclosure::Redact)).await;
+
+    let result = server.client(STUDENT).await.get_result("123", "math101").await.unwrap();
+    assert!(result.student_name.is_empty());
+    assert_eq!(result.marks_obtained, 95);
+
+    let transcript = server.client(STUDENT).await.transcript("123").await.unwrap();
+    assert!(transcript.student_name.is_empty());
+    assert!(transcript.results.iter().all(|result| result.student_name.is_empty()));
+
+    for token in [TEACHER, ADMIN] {
+        let result = server.client(token).await.get_result("123", "math101").await.unwrap();
+        assert_eq!(result.student_name, "John Doe");
+    }
+}
+
+#[tokio::test]
+async fn pseudonyms_are_stable_under_one_key() {
+    let dir = tempfile::tempdir().unwrap();
+    let key = dir.path().join("pseudonym.key");
+    std::fs::write(&key, "a shared secret").unwrap();
+    let privacy = PrivacyConfig {
+        pseudonym_key_file: Some(key),
+        ..names(NameDisclosure::Pseudonymize)
+    };
+
+    let replicas = [start(privacy.clone()).await, start(privacy).await];
+    let mut pseudonyms = Vec::new();
+    for server in &replicas {
+        let result = server.client(STUDENT).await.get_result("123", "math101").await.unwrap();
+        pseudonyms.push(result.student_name);
+    }
+
+    assert_ne!(pseudonyms[0], "John Doe");
+    assert!(pseudonyms[0].starts_with("Student "));
+    assert_eq!(pseudonyms[0], pseudonyms[1]);
+
+    let transcript = replicas[0].client(STUDENT).await.transcript("123").await.unwrap();
+    assert_eq!(transcript.student_name, pseudonyms[0]);
+}
+
+#[tokio::test]
+async fn a_missing_pseudonym_key_file_is_an_error() {
+    let privacy = PrivacyConfig {
+        pseudonym_key_file: Some("/nonexistent/pseudonym.key".into()),
+        ..names(NameDisclosure::Pseudonymize)
+    };
+    let err = ExamServiceImpl::new(InMemoryExamStore::with_sample_data())
+        .with_privacy(&privacy)
+        .unwrap_err();
+    assert!(err.to_string().contains("pseudonym key file"));
+}
+
+#[tokio::test]
+async fn statistics_are_withheld_for_small_cohorts() {
+    let privacy = PrivacyConfig {
+        min_cohort_size: 3,
+        ..PrivacyConfig::default()
+    };
+    let server = start(privacy).await;
+    let admin = server.client(ADMIN).await;
+
+    // Only sample student 456 has taken it
+    let stats = admin.statistics("phy101").await.unwrap();
+    assert!(stats.withheld);
+    assert_eq!(stats.result_count, 1);
+    assert_eq!((stats.mean, stats.highest), (0.0, 0));
+    assert!(stats.grade_distribution.is_empty());
+
+    for n in 0..2 {
+        let student = Student {
+            student_id: format!("s{n}"),
+            name: format!("Student {n}"),
+            email: String::new(),
+        };
+        admin.register_student(student).await.unwrap();
+        let result = ExamResult {
+            student_id: format!("s{n}"),
+            exam_id: "phy101".to_string(),
+            marks_obtained: 60,
+            ..Default::default()
+        };
+        admin.submit_result(result).await.unwrap();
+    }
+
+    let stats = admin.statistics("phy101").await.unwrap();
+    assert!(!stats.withheld);
+    assert_eq!(stats.result_count, 3);
+    assert!(stats.mean > 0.0);
+    assert!(!stats.grade_distribution.is_empty());
+}
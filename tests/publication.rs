@@ -0,0 +1,106 @@
+mod common;
+
+use tokio_stream::StreamExt;
+use tonic::Code;
+
+use common::{TestServer, ADMIN, STUDENT, TEACHER};
+use exam_service::exam_service::{ChangeKind, ExamResult, ResultStatus};
+
+fn result(marks_obtained: i32) -> ExamResult {
+    ExamResult {
+        student_id: "123".to_string(),
+        exam_id: "phy101".to_string(),
+        marks_obtained,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn submitted_results_are_drafts_until_published() {
+    let server = TestServer::start().await;
+    let teacher = server.client(TEACHER).await;
+    let student = server.client(STUDENT).await;
+
+    let submitted = teacher.submit_result(result(70)).await.unwrap().result.unwrap();
+    assert_eq!(submitted.status(), ResultStatus::Draft);
+
+    // Teachers see the draft; to the student it does not exist yet
+    assert_eq!(teacher.get_result("123", "phy101").await.unwrap().status(), ResultStatus::Draft);
+    let status = student.get_result("123", "phy101").await.unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+    let transcript = student.transcript("123").await.unwrap();
+    assert!(transcript.results.iter().all(|result| result.exam_id != "phy101"));
+
+    let response = teacher.publish_results("phy101").await.unwrap();
+    assert_eq!(response.published_count, 1);
+
+    let published = student.get_result("123", "phy101").await.unwrap();
+    assert_eq!(published.status(), ResultStatus::Published);
+    assert_eq!(published.version, submitted.version + 1);
+}
+
+#[tokio::test]
+async fn publishing_only_touches_drafts_of_the_exam() {
+    let server = TestServer::start().await;
+    let teacher = server.client(TEACHER).await;
+
+    teacher.submit_result(result(70)).await.unwrap();
+    teacher
+        .submit_result(ExamResult {
+            exam_id: "math101".to_string(),
+            ..result(80)
+        })
+        .await
+        .unwrap();
+
+    // Sample result 456/phy101 is already published
+    assert_eq!(teacher.publish_results("phy101").await.unwrap().published_count, 1);
+    assert_eq!(teacher.publish_results("phy101").await.unwrap().published_count, 0);
+    assert_eq!(teacher.get_result("123", "math101").await.unwrap().status(), ResultStatus::Draft);
+}
+
+#[tokio::test]
+async fn resubmitting_withdraws_a_published_result() {
+    let server = TestServer::start().await;
+    let teacher = server.client(TEACHER).await;
+
+    let before = server.client(STUDENT).await.get_result("123", "math101").await.unwrap();
+    assert_eq!(before.status(), ResultStatus::Published);
+
+    teacher
+        .submit_result(ExamResult {
+            exam_id: "math101".to_string(),
+            ..result(90)
+        })
+        .await
+        .unwrap();
+
+    let status = server.client(STUDENT).await.get_result("123", "math101").await.unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+}
+
+#[tokio::test]
+async fn students_cannot_publish() {
+    let server = TestServer::start().await;
+
+    let status = server.client(STUDENT).await.publish_results("phy101").await.unwrap_err();
+    assert_eq!(status.code(), Code::PermissionDenied);
+
+    let status = server.client(ADMIN).await.publish_results("chem101").await.unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+}
+
+#[tokio::test]
+async fn students_watching_see_the_publication() {
+    let server = TestServer::start().await;
+    let teacher = server.client(TEACHER).await;
+    let mut changes = server.client(STUDENT).await.watch("123", "phy101").await.unwrap();
+
+    teacher.submit_result(result(70)).await.unwrap();
+    teacher.publish_results("phy101").await.unwrap();
+
+    // The draft's own change is withheld; the first the student hears is the release
+    let change = changes.next().await.unwrap().unwrap();
+    assert_eq!(change.kind(), ChangeKind::Released);
+    assert_eq!(change.result.unwrap().marks_obtained, 70);
+}
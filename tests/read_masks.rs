@@ -0,0 +1,151 @@
+mod common;
+
+use tokio_stream::StreamExt;
+use tonic::Code;
+use tonic_types::StatusExt;
+
+use common::{TestServer, ADMIN, STUDENT, TEACHER};
+use exam_service::client::read_mask;
+use exam_service::exam_service::{
+    ExportFormat, GetExamResultRequest, GetExamResultResponse, ListExamResultsPageRequest, ListExamResultsRequest,
+};
+
+fn violations(status: &tonic::Status) -> Vec<String> {
+    status
+        .get_details_bad_request()
+        .expect("a BadRequest detail")
+        .field_violations
+        .into_iter()
+        .map(|violation| violation.field)
+        .collect()
+}
+
+#[tokio::test]
+async fn a_masked_get_returns_only_the_fields_asked_for() {
+    let server = TestServer::start().await;
+    let client = server.client(TEACHER).await;
+
+    let result = client.get_result_fields("123", "math101", &["grade", "marks_obtained"]).await.unwrap();
+    assert_eq!(
+        result,
+        GetExamResultResponse {
+            grade: "A+".to_string(),
+            marks_obtained: 95,
+            ..Default::default()
+        }
+    );
+
+    // No fields is no mask
+    let result = client.get_result_fields("123", "math101", &[]).await.unwrap();
+    assert_eq!(result.student_name, "John Doe");
+}
+
+#[tokio::test]
+async fn masks_cannot_reveal_redacted_fields() {
+    let server = TestServer::start().await;
+
+    let student = server.client(STUDENT).await;
+    let result = student.get_result_fields("123", "math101", &["internal_comment", "grade"]).await.unwrap();
+    assert_eq!((result.internal_comment.as_str(), result.grade.as_str()), ("", "A+"));
+
+    let admin = server.client(ADMIN).await;
+    let result = admin.get_result_fields("123", "math101", &["internal_comment"]).await.unwrap();
+    assert_eq!(result.internal_comment, "Moderated by second marker");
+    assert_eq!(result.grade, "");
+}
+
+#[tokio::test]
+async fn listings_apply_the_mask_to_every_result() {
+    let server = TestServer::start().await;
+    let client = server.client(TEACHER).await;
+
+    let filter = ListExamResultsRequest {
+        read_mask: read_mask(&["student_id", "grade"]),
+        ..Default::default()
+    };
+    let streamed: Vec<_> = client.list_results(filter.clone()).await.unwrap().collect().await;
+    assert!(!streamed.is_empty());
+    for result in streamed {
+        let result = result.unwrap();
+        assert!(!result.student_id.is_empty() && !result.grade.is_empty());
+        let kept = GetExamResultResponse {
+            student_id: result.student_id.clone(),
+            grade: result.grade.clone(),
+            ..Default::default()
+        };
+        assert_eq!(result, kept);
+    }
+
+    let page = client
+        .list_results_page(ListExamResultsPageRequest {
+            filter: Some(filter),
+            page_size: Some(1),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(page.results.len(), 1);
+    assert_eq!(page.results[0].subject, "");
+    assert!(!page.results[0].grade.is_empty());
+
+    // Paging still works, though the cursor's IDs may be masked out
+    let next = client
+        .list_results_page(ListExamResultsPageRequest {
+            filter: Some(ListExamResultsRequest {
+                read_mask: read_mask(&["grade"]),
+                ..Default::default()
+            }),
+            page_size: Some(1),
+            page_token: page.next_page_token,
+        })
+        .await
+        .unwrap();
+    assert_eq!(next.results.len(), 1);
+
+    let stream: Vec<_> = server
+        .raw_client(TEACHER)
+        .await
+        .get_exam_result_stream(GetExamResultRequest {
+            student_id: "123".to_string(),
+            read_mask: read_mask(&["exam_id"]),
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .collect()
+        .await;
+    let math = stream.into_iter().map(Result::unwrap).find(|result| result.exam_id == "math101").unwrap();
+    assert_eq!(math, GetExamResultResponse { exam_id: "math101".to_string(), ..Default::default() });
+}
+
+#[tokio::test]
+async fn unknown_mask_paths_are_rejected() {
+    let server = TestServer::start().await;
+    let client = server.client(TEACHER).await;
+
+    let status = client.get_result_fields("123", "math101", &["grade", "marks", "questions.marks"]).await.unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+    assert_eq!(violations(&status), ["read_mask.paths[1]", "read_mask.paths[2]"]);
+
+    let filter = ListExamResultsRequest {
+        read_mask: read_mask(&["nope"]),
+        ..Default::default()
+    };
+    let status = client
+        .list_results_page(ListExamResultsPageRequest {
+            filter: Some(filter),
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(violations(&status), ["filter.read_mask.paths[0]"]);
+
+    // Exports have fixed columns
+    let filter = ListExamResultsRequest {
+        read_mask: read_mask(&["grade"]),
+        ..Default::default()
+    };
+    let status = client.export_results(filter, ExportFormat::Csv).await.unwrap_err();
+    assert_eq!(violations(&status), ["filter.read_mask"]);
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use common::{TestServer, ADMIN, STUDENT, TEACHER};
+use exam_service::exam_service::QuestionScore;
+
+#[tokio::test]
+async fn readers_see_everything_but_internal_comments() {
+    let server = TestServer::start().await;
+
+    let admin_view = server.client(ADMIN).await.get_result("123", "math101").await.unwrap();
+    assert_eq!(admin_view.student_name, "John Doe");
+    assert_eq!(admin_view.subject, "Math 101");
+    assert_eq!((admin_view.marks_obtained, admin_view.total_marks), (95, 100));
+    assert_eq!(admin_view.grade, "A+");
+    assert_eq!(admin_view.internal_comment, "Moderated by second marker");
+
+    // The reader view is the admin view, less the internal comment
+    let mut reader_view = admin_view.clone();
+    reader_view.internal_comment.clear();
+    for token in [STUDENT, TEACHER] {
+        let result = server.client(token).await.get_result("123", "math101").await.unwrap();
+        assert_eq!(result, reader_view);
+    }
+}
+
+#[tokio::test]
+async fn readers_see_the_question_breakdown() {
+    let server = TestServer::start().await;
+    let teacher = server.client(TEACHER).await;
+    let score = |question_id: &str, marks| QuestionScore {
+        question_id: question_id.to_string(),
+        marks,
+        max_marks: 50,
+        comment: "see rubric".to_string(),
+    };
+
+    teacher
+        .submit_question_scores("456", "math101", vec![score("q1", 40), score("q2", 45)])
+        .await
+        .unwrap();
+
+    let reader_view = teacher.get_result("456", "math101").await.unwrap();
+    assert_eq!(reader_view.questions, [score("q1", 40), score("q2", 45)]);
+    assert_eq!(reader_view.marks_obtained, 85);
+
+    let admin_view = server.client(ADMIN).await.get_result("456", "math101").await.unwrap();
+    assert_eq!(admin_view, reader_view);
+}
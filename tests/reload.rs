@@ -0,0 +1,85 @@
+mod common;
+
+use std::fs;
+use std::process::Command;
+use std::time::Duration;
+use tokio_stream::StreamExt;
+use tonic::Code;
+
+use common::{TestServer, ADMIN, TEACHER};
+use exam_service::client::RetryPolicy;
+use exam_service::exam_service::ExamResult;
+
+// The only test in this binary: it points EXAM_CONFIG at a file of its own
+// and sends SIGHUP to the whole process.
+#[tokio::test(flavor = "multi_thread")]
+async fn sighup_reloads_grades_limits_and_tokens_without_dropping_streams() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = dir.path().join("exam-service.toml");
+    let grading_path = dir.path().join("grading.toml");
+    let tokens_path = dir.path().join("tokens");
+
+    // SAFETY: set before the server starts, and never changed afterwards
+    unsafe { std::env::set_var("EXAM_CONFIG", &config_path) };
+    let server = TestServer::start().await;
+    let watcher = server.client(ADMIN).await;
+    let mut changes = watcher.watch("456", "").await.unwrap();
+
+    fs::write(
+        &grading_path,
+        "default = [{ grade = \"A\", min_percent = 50.0 }, { grade = \"F\", min_percent = 0.0 }]\n",
+    )
+    .unwrap();
+    fs::write(&tokens_path, "# rotated\nrotated-token=teacher@rotated\n").unwrap();
+    fs::write(
+        &config_path,
+        format!(
+            "grading_config = {:?}\napi_tokens_file = {:?}\n\n\
+             [rate_limit]\nenabled = true\n\n\
+             [rate_limit.methods.GetExamResult]\nrequests_per_second = 0.001\nburst = 1\n",
+            grading_path, tokens_path
+        ),
+    )
+    .unwrap();
+
+    let status = Command::new("kill")
+        .args(["-HUP", &std::process::id().to_string()])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    // The old tokens stop working once the reload has happened
+    let old = server.client(TEACHER).await.with_retry_policy(RetryPolicy::none());
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while old.get_result("123", "math101").await.map_err(|status| status.code()) != Err(Code::Unauthenticated) {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("the configuration is reloaded");
+
+    let rotated = server
+        .client("rotated-token")
+        .await
+        .with_retry_policy(RetryPolicy::none());
+    let submitted = rotated
+        .submit_result(ExamResult {
+            student_id: "456".to_string(),
+            exam_id: "math101".to_string(),
+            marks_obtained: 60,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(submitted.result.unwrap().grade, "A");
+
+    // The stream opened before the reload still sees writes
+    let change = changes.next().await.unwrap().unwrap();
+    assert_eq!(change.result.unwrap().marks_obtained, 60);
+
+    rotated.get_result("456", "math101").await.unwrap();
+    let limited = rotated.get_result("456", "math101").await.unwrap_err();
+    assert_eq!(limited.code(), Code::ResourceExhausted);
+
+    server.shutdown().await;
+}
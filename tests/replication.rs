@@ -0,0 +1,167 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+use tonic::Code;
+
+use common::{test_config, TestServer, ADMIN, OPERATOR, TEACHER};
+use exam_service::client::BearerToken;
+use exam_service::exam_service::ExamResult;
+use exam_service::exam_sync::sync_service_client::SyncServiceClient;
+use exam_service::exam_sync::SubscribeRequest;
+use exam_service::key::{InstitutionId, ResultKey};
+use exam_service::server::ExamServiceImpl;
+use exam_service::store::{ExamStore, InMemoryExamStore, ReplicatedExamStore, ReplicationLog};
+use exam_service::student::Student;
+
+// A sample store whose writes are logged as `instance_id`'s.
+fn replica(instance_id: &str) -> (Arc<ReplicationLog>, ReplicatedExamStore<InMemoryExamStore>) {
+    let log = Arc::new(ReplicationLog::new(instance_id, 100));
+    let store = ReplicatedExamStore::new(InMemoryExamStore::with_sample_data(), InstitutionId::default(), log.clone());
+    (log, store)
+}
+
+// A server replicating as `instance_id`, following the servers at `peers`.
+async fn start(instance_id: &str, peers: &[&TestServer]) -> TestServer {
+    let (log, store) = replica(instance_id);
+    let mut config = test_config();
+    config.replication.peers = peers.iter().map(|peer| format!("http://{}", peer.addr)).collect();
+    config.replication.token = OPERATOR.to_string();
+    config.replication.retry_interval_secs = 1;
+    TestServer::start_with_config(ExamServiceImpl::new(store).with_replication(log), config).await
+}
+
+fn result(marks_obtained: i32) -> ExamResult {
+    ExamResult {
+        student_id: "456".to_string(),
+        exam_id: "phy101".to_string(),
+        marks_obtained,
+        total_marks: 100,
+        ..Default::default()
+    }
+}
+
+// Applies everything `from` logged after `after` to `to`'s stores.
+async fn deliver(from: &ReplicationLog, after: u64, to: &ReplicationLog) {
+    for event in from.since(after).unwrap() {
+        to.apply(&event).await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn writes_on_one_server_reach_its_peers() {
+    let first = start("first", &[]).await;
+    let second = start("second", &[&first]).await;
+    let admin = first.client(ADMIN).await;
+    let peer = second.client(ADMIN).await;
+
+    let student = Student {
+        student_id: "789".to_string(),
+        name: "Ada Lovelace".to_string(),
+        email: String::new(),
+    };
+    admin.register_student(student).await.unwrap();
+    let submitted = ExamResult {
+        student_id: "789".to_string(),
+        exam_id: "math101".to_string(),
+        marks_obtained: 77,
+        ..Default::default()
+    };
+    admin.submit_result(submitted).await.unwrap();
+    admin.delete_result("456", "phy101", "entered twice").await.unwrap();
+
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while peer.get_result("456", "phy101").await.is_ok() {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("the deletion reaches the peer");
+
+    // Writes arrive in the order they were made, so the earlier ones are there too
+    assert_eq!(peer.get_student("789").await.unwrap().name, "Ada Lovelace");
+    let replicated = peer.get_result("789", "math101").await.unwrap();
+    assert_eq!(replicated.marks_obtained, 77);
+    assert_eq!(replicated.grade, admin.get_result("789", "math101").await.unwrap().grade);
+    let trail = peer.audit_trail("789", "math101").await.unwrap();
+    assert_eq!(trail.len(), 1);
+    assert_eq!(trail[0].actor, "admin");
+
+    second.shutdown().await;
+    first.shutdown().await;
+}
+
+#[tokio::test]
+async fn the_later_of_two_conflicting_writes_wins_everywhere() {
+    let (first_log, first) = replica("first");
+    let (second_log, second) = replica("second");
+    let key = ResultKey::from(&result(0));
+
+    first.put(key.clone(), result(70)).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(5)).await;
+    second.put(key.clone(), result(80)).await.unwrap();
+
+    deliver(&first_log, 0, &second_log).await;
+    deliver(&second_log, 0, &first_log).await;
+    for store in [&first, &second] {
+        let stored = store.get(&key).await.unwrap().unwrap();
+        assert_eq!((stored.marks_obtained, stored.version), (80, 2));
+    }
+
+    // A later deletion beats the earlier writes, however often they arrive
+    tokio::time::sleep(Duration::from_millis(5)).await;
+    let deleted_from = second_log.last_sequence();
+    second.delete(&key, 1_000).await.unwrap();
+    deliver(&second_log, deleted_from, &first_log).await;
+    deliver(&first_log, 0, &second_log).await;
+    deliver(&second_log, 0, &first_log).await;
+    assert_eq!(first.get(&key).await.unwrap(), None);
+    assert_eq!(second.get(&key).await.unwrap(), None);
+    assert_eq!(first.list_deleted(None, None).await.unwrap().len(), 1);
+
+    // Writes from peers are not logged again
+    assert_eq!(first_log.last_sequence(), 1);
+}
+
+#[tokio::test]
+async fn subscribers_that_cannot_resume_are_sent_a_snapshot_first() {
+    let server = start("first", &[]).await;
+    let admin = server.client(ADMIN).await;
+    admin.submit_result(result(70)).await.unwrap();
+
+    let subscriber = |token: &str| {
+        let token = BearerToken::new(token).unwrap().with_institution("default").unwrap();
+        let server = &server;
+        async move { SyncServiceClient::with_interceptor(server.channel().await, token) }
+    };
+    let mut sync = subscriber(OPERATOR).await;
+    let subscribe = |log_id: &str, after_sequence| SubscribeRequest {
+        instance_id: "second".to_string(),
+        log_id: log_id.to_string(),
+        after_sequence,
+    };
+
+    let mut events = sync.subscribe(subscribe("", 0)).await.unwrap().into_inner();
+    let snapshot = events.message().await.unwrap().unwrap();
+    let mutation: serde_json::Value = serde_json::from_slice(&snapshot.mutation).unwrap();
+    assert_eq!(mutation["snapshot"]["results"].as_array().unwrap().len(), 2);
+    assert_eq!(snapshot.institution_id, "default");
+    // The result and its audit record
+    assert_eq!(snapshot.sequence, 2);
+
+    admin.submit_result(result(75)).await.unwrap();
+    let written = events.message().await.unwrap().unwrap();
+    assert_eq!((written.sequence, written.origin.as_str()), (3, "first"));
+    assert_eq!(written.log_id, snapshot.log_id);
+
+    // Resuming picks up after the last event received, without a snapshot
+    drop(events);
+    let mut events = sync.subscribe(subscribe(&written.log_id, 3)).await.unwrap().into_inner();
+    assert_eq!(events.message().await.unwrap().unwrap().sequence, 4);
+
+    // The stream carries every institution's writes, so admins of one are refused too
+    for token in [ADMIN, TEACHER] {
+        let status = subscriber(token).await.subscribe(subscribe("", 0)).await.unwrap_err();
+        assert_eq!(status.code(), Code::PermissionDenied);
+    }
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use futures::StreamExt;
+use tonic::metadata::MetadataMap;
+use tonic::{Code, Request};
+
+use common::{TestServer, TEACHER};
+use exam_service::exam_service::{GetExamResultRequest, ListExamResultsRequest};
+use exam_service::telemetry::REQUEST_ID_HEADER;
+
+fn get(student_id: &str, request_id: Option<&str>) -> Request<GetExamResultRequest> {
+    let mut request = Request::new(GetExamResultRequest {
+        student_id: student_id.to_string(),
+        exam_id: "math101".to_string(),
+        ..Default::default()
+    });
+    if let Some(request_id) = request_id {
+        request.metadata_mut().insert(REQUEST_ID_HEADER, request_id.parse().unwrap());
+    }
+    request
+}
+
+fn request_id(metadata: &MetadataMap) -> &str {
+    metadata
+        .get(REQUEST_ID_HEADER)
+        .expect("a request ID")
+        .to_str()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn request_ids_are_echoed_or_generated() {
+    let server = TestServer::start().await;
+    let mut client = server.raw_client(TEACHER).await;
+
+    let response = client.get_exam_result(get("123", Some("trace-42"))).await.unwrap();
+    assert_eq!(request_id(response.metadata()), "trace-42");
+
+    let first = client.get_exam_result(get("123", None)).await.unwrap();
+    let second = client.get_exam_result(get("123", None)).await.unwrap();
+    assert_eq!(request_id(first.metadata()).len(), 32);
+    assert_ne!(request_id(first.metadata()), request_id(second.metadata()));
+
+    // An ID too long to log is replaced rather than trusted
+    let long = "x".repeat(200);
+    let response = client.get_exam_result(get("123", Some(&long))).await.unwrap();
+    assert_ne!(request_id(response.metadata()), long);
+}
+
+#[tokio::test]
+async fn failed_calls_carry_the_request_id() {
+    let server = TestServer::start().await;
+    let mut client = server.raw_client(TEACHER).await;
+
+    let status = client.get_exam_result(get("bad id", Some("trace-400"))).await.unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+    assert_eq!(request_id(status.metadata()), "trace-400");
+
+    // And so do the trailers that end a stream
+    let mut request = Request::new(ListExamResultsRequest::default());
+    request.metadata_mut().insert(REQUEST_ID_HEADER, "trace-stream".parse().unwrap());
+    let mut stream = client.list_exam_results(request).await.unwrap().into_inner();
+    while let Some(result) = stream.next().await {
+        result.unwrap();
+    }
+    let trailers = stream.trailers().await.unwrap().expect("trailers");
+    assert_eq!(request_id(&trailers), "trace-stream");
+}
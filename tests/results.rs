@@ -0,0 +1,220 @@
+mod common;
+
+use tonic::Code;
+
+use common::{TestServer, ADMIN, STUDENT, TEACHER};
+use exam_service::exam_service::{CorrectExamResultRequest, ExamResult, QuestionScore, SubmitExamResultRequest};
+
+fn result(student_id: &str, exam_id: &str, marks_obtained: i32) -> ExamResult {
+    ExamResult {
+        student_id: student_id.to_string(),
+        exam_id: exam_id.to_string(),
+        marks_obtained,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn gets_a_sample_result() {
+    let server = TestServer::start().await;
+    let client = server.client(ADMIN).await;
+
+    let result = client.get_result("123", "math101").await.unwrap();
+
+    assert_eq!(result.student_name, "John Doe");
+    assert_eq!(result.marks_obtained, 95);
+    assert_eq!(result.grade, "A+");
+    assert_eq!(result.version, 1);
+    assert_eq!(result.internal_comment, "Moderated by second marker");
+}
+
+#[tokio::test]
+async fn internal_comments_are_hidden_from_teachers() {
+    let server = TestServer::start().await;
+    let client = server.client(TEACHER).await;
+
+    let result = client.get_result("123", "math101").await.unwrap();
+
+    assert_eq!(result.marks_obtained, 95);
+    assert!(result.internal_comment.is_empty());
+}
+
+#[tokio::test]
+async fn missing_results_are_not_found() {
+    let server = TestServer::start().await;
+    let client = server.client(ADMIN).await;
+
+    let status = client.get_result("123", "phy101").await.unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+
+    let status = client.get_result("999", "math101").await.unwrap_err();
+    assert_eq!(status.code(), Code::FailedPrecondition);
+}
+
+#[tokio::test]
+async fn malformed_ids_are_invalid() {
+    let server = TestServer::start().await;
+    let client = server.client(ADMIN).await;
+
+    let status = client.get_result("", "math101").await.unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+
+    let status = client.get_result("123", "math 101").await.unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn callers_are_authenticated_and_scoped() {
+    let server = TestServer::start().await;
+
+    let status = server.client("not-a-token").await.get_result("123", "math101").await.unwrap_err();
+    assert_eq!(status.code(), Code::Unauthenticated);
+
+    let student = server.client(STUDENT).await;
+    assert!(student.get_result("123", "math101").await.is_ok());
+
+    let status = student.get_result("456", "phy101").await.unwrap_err();
+    assert_eq!(status.code(), Code::PermissionDenied);
+
+    let status = student.submit_result(result("123", "math101", 100)).await.unwrap_err();
+    assert_eq!(status.code(), Code::PermissionDenied);
+}
+
+#[tokio::test]
+async fn submissions_are_graded_and_filled_in_from_the_catalog() {
+    let server = TestServer::start().await;
+    let client = server.client(TEACHER).await;
+
+    let response = client.submit_result(result("456", "math101", 72)).await.unwrap();
+    let stored = response.result.unwrap();
+
+    assert!(response.created);
+    assert_eq!(stored.student_name, "Jane Smith");
+    assert_eq!(stored.subject, "Math 101");
+    assert_eq!(stored.total_marks, 100);
+    assert_eq!(stored.grade, "C");
+    assert_eq!(stored.version, 1);
+
+    let response = client.submit_result(result("456", "math101", 40)).await.unwrap();
+    assert!(!response.created);
+    assert_eq!(response.result.unwrap().version, 2);
+}
+
+#[tokio::test]
+async fn invalid_submissions_are_rejected() {
+    let server = TestServer::start().await;
+    let client = server.client(TEACHER).await;
+
+    let status = client.submit_result(result("456", "math101", 101)).await.unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+
+    let status = client.submit_result(result("456", "chem101", 50)).await.unwrap_err();
+    assert_eq!(status.code(), Code::FailedPrecondition);
+
+    let mut wrong_total = result("456", "math101", 50);
+    wrong_total.total_marks = 80;
+    let status = client.submit_result(wrong_total).await.unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn question_scores_set_the_total() {
+    let server = TestServer::start().await;
+    let client = server.client(TEACHER).await;
+
+    let score = |question_id: &str, marks, max_marks| QuestionScore {
+        question_id: question_id.to_string(),
+        marks,
+        max_marks,
+        comment: String::new(),
+    };
+
+    let response = client
+        .submit_question_scores("456", "math101", vec![score("q1", 40, 50), score("q2", 45, 50)])
+        .await
+        .unwrap();
+    let stored = response.result.unwrap();
+    assert_eq!(stored.marks_obtained, 85);
+    assert_eq!(stored.questions.len(), 2);
+
+    let status = client
+        .submit_question_scores("456", "math101", vec![score("q1", 60, 50), score("q2", 40, 50)])
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+
+    let status = client.correct_result("456", "math101", 90, 1, "").await.unwrap_err();
+    assert_eq!(status.code(), Code::FailedPrecondition);
+}
+
+#[tokio::test]
+async fn corrections_need_the_current_version() {
+    let server = TestServer::start().await;
+    let client = server.client(TEACHER).await;
+
+    let response = client.correct_result("123", "math101", 80, 1, "remarked").await.unwrap();
+    let current = response.current.unwrap();
+    assert_eq!(response.previous.unwrap().marks_obtained, 95);
+    assert_eq!(current.marks_obtained, 80);
+    assert_eq!(current.grade, "B");
+    assert_eq!(current.version, 2);
+
+    let status = client.correct_result("123", "math101", 70, 1, "").await.unwrap_err();
+    assert_eq!(status.code(), Code::Aborted);
+
+    let status = client.correct_result("123", "math101", 70, 0, "").await.unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn retried_writes_apply_once() {
+    let server = TestServer::start().await;
+    let mut client = server.raw_client(TEACHER).await;
+
+    let correct = |marks_obtained| CorrectExamResultRequest {
+        student_id: "123".to_string(),
+        exam_id: "math101".to_string(),
+        marks_obtained,
+        expected_version: 1,
+        request_id: "retry-1".to_string(),
+        ..Default::default()
+    };
+
+    let first = client.correct_exam_result(correct(80)).await.unwrap().into_inner();
+    // Without deduplication the retry would fail: version 1 is stale by now
+    let retry = client.correct_exam_result(correct(80)).await.unwrap().into_inner();
+    assert_eq!(first, retry);
+
+    let status = client.correct_exam_result(correct(70)).await.unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+
+    let submit = SubmitExamResultRequest {
+        result: Some(result("456", "math101", 60)),
+        request_id: "retry-2".to_string(),
+    };
+    let first = client.submit_exam_result(submit.clone()).await.unwrap().into_inner();
+    let retry = client.submit_exam_result(submit).await.unwrap().into_inner();
+    assert!(first.created && retry.created);
+    assert_eq!(retry.result.unwrap().version, 1);
+}
+
+#[tokio::test]
+async fn deletes_are_admin_only_and_audited() {
+    let server = TestServer::start().await;
+
+    let status = server.client(TEACHER).await.delete_result("123", "math101", "").await.unwrap_err();
+    assert_eq!(status.code(), Code::PermissionDenied);
+
+    let admin = server.client(ADMIN).await;
+    let removed = admin.delete_result("123", "math101", "duplicate").await.unwrap().unwrap();
+    assert_eq!(removed.marks_obtained, 95);
+
+    let status = admin.get_result("123", "math101").await.unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+
+    let trail = admin.audit_trail("123", "math101").await.unwrap();
+    assert_eq!(trail.len(), 1);
+    assert_eq!(trail[0].actor, "admin");
+    assert_eq!(trail[0].reason, "duplicate");
+    assert!(trail[0].after.is_none());
+}
@@ -0,0 +1,144 @@
+mod common;
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio_stream::StreamExt;
+use tonic::Code;
+use tonic_types::StatusExt;
+
+use common::{TestServer, ADMIN, STUDENT, TEACHER};
+use exam_service::client::ExamClient;
+use exam_service::exam_admin::Exam;
+use exam_service::exam_service::{ExamResult, ListExamResultsRequest};
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+fn ms(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH).unwrap().as_millis() as i64
+}
+
+fn exam(exam_id: &str, opens_at: SystemTime, grades_released_at: SystemTime) -> Exam {
+    Exam {
+        exam_id: exam_id.to_string(),
+        subject: "Chemistry 101".to_string(),
+        total_marks: 100,
+        date: "2024-06-10".to_string(),
+        opens_at_ms: ms(opens_at),
+        closes_at_ms: ms(opens_at + Duration::from_secs(3 * 60 * 60)),
+        grades_released_at_ms: ms(grades_released_at),
+    }
+}
+
+fn result(exam_id: &str) -> ExamResult {
+    ExamResult {
+        student_id: "123".to_string(),
+        exam_id: exam_id.to_string(),
+        marks_obtained: 64,
+        ..Default::default()
+    }
+}
+
+// An exam that has been sat but whose grades are out tomorrow, with a
+// published result for student 123.
+async fn unreleased_exam(client: &ExamClient) {
+    let now = SystemTime::now();
+    client.create_exam(exam("chem101", now - DAY, now + DAY)).await.unwrap();
+    client.submit_result(result("chem101")).await.unwrap();
+    client.publish_results("chem101").await.unwrap();
+}
+
+#[tokio::test]
+async fn results_are_refused_before_the_exam_opens() {
+    let server = TestServer::start().await;
+    let client = server.client(TEACHER).await;
+
+    let now = SystemTime::now();
+    client.create_exam(exam("chem101", now + DAY, now + 2 * DAY)).await.unwrap();
+    let status = client.submit_result(result("chem101")).await.unwrap_err();
+
+    assert_eq!(status.code(), Code::FailedPrecondition);
+    let failure = status.get_details_precondition_failure().expect("a PreconditionFailure detail");
+    assert_eq!(failure.violations[0].r#type, "NOT_OPEN");
+}
+
+#[tokio::test]
+async fn windows_must_close_after_they_open() {
+    let server = TestServer::start().await;
+    let client = server.client(ADMIN).await;
+
+    let now = SystemTime::now();
+    let mut backwards = exam("chem101", now, now);
+    backwards.closes_at_ms = backwards.opens_at_ms - 1;
+    let status = client.create_exam(backwards).await.unwrap_err();
+
+    assert_eq!(status.code(), Code::InvalidArgument);
+    assert!(status.message().starts_with("closes_at_ms"));
+}
+
+#[tokio::test]
+async fn students_wait_for_the_release_date() {
+    let server = TestServer::start().await;
+    unreleased_exam(&server.client(TEACHER).await).await;
+
+    let status = server.client(STUDENT).await.get_result("123", "chem101").await.unwrap_err();
+    assert_eq!(status.code(), Code::FailedPrecondition);
+    assert!(status.message().contains("released at"));
+
+    // Staff see the result to mark and moderate it
+    let result = server.client(TEACHER).await.get_result("123", "chem101").await.unwrap();
+    assert_eq!(result.marks_obtained, 64);
+}
+
+#[tokio::test]
+async fn unreleased_results_are_left_out_of_student_listings() {
+    let server = TestServer::start().await;
+    unreleased_exam(&server.client(TEACHER).await).await;
+    let student = server.client(STUDENT).await;
+
+    let transcript = student.transcript("123").await.unwrap();
+    assert!(transcript.results.iter().all(|result| result.exam_id != "chem101"));
+    assert!(!transcript.results.is_empty());
+
+    let mut listing = student.list_results(ListExamResultsRequest::default()).await.unwrap();
+    while let Some(result) = listing.next().await {
+        assert_ne!(result.unwrap().exam_id, "chem101");
+    }
+
+    let teacher = server.client(TEACHER).await.transcript("123").await.unwrap();
+    assert!(teacher.results.iter().any(|result| result.exam_id == "chem101"));
+}
+
+#[tokio::test]
+async fn released_results_are_visible() {
+    let server = TestServer::start().await;
+    let client = server.client(TEACHER).await;
+
+    let now = SystemTime::now();
+    client.create_exam(exam("chem101", now - 2 * DAY, now - DAY)).await.unwrap();
+    client.submit_result(result("chem101")).await.unwrap();
+    client.publish_results("chem101").await.unwrap();
+
+    let result = server.client(STUDENT).await.get_result("123", "chem101").await.unwrap();
+    assert_eq!(result.marks_obtained, 64);
+}
+
+#[tokio::test]
+async fn watches_honour_a_postponed_release() {
+    let server = TestServer::start().await;
+    let teacher = server.client(TEACHER).await;
+    let mut changes = server.client(STUDENT).await.watch("123", "").await.unwrap();
+
+    let now = SystemTime::now();
+    teacher.create_exam(exam("chem101", now - 2 * DAY, now - DAY)).await.unwrap();
+    teacher.submit_result(result("chem101")).await.unwrap();
+    teacher.publish_results("chem101").await.unwrap();
+    assert_eq!(changes.next().await.unwrap().unwrap().result.unwrap().exam_id, "chem101");
+
+    // Postponed while the watch is open: the republished result is withheld
+    teacher.update_exam(exam("chem101", now - 2 * DAY, now + DAY)).await.unwrap();
+    teacher.submit_result(result("chem101")).await.unwrap();
+    teacher.publish_results("chem101").await.unwrap();
+
+    teacher.submit_result(result("phy101")).await.unwrap();
+    teacher.publish_results("phy101").await.unwrap();
+    assert_eq!(changes.next().await.unwrap().unwrap().result.unwrap().exam_id, "phy101");
+}
@@ -0,0 +1,149 @@
+mod common;
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tonic::{Code, Status};
+
+use common::{test_config, TestServer, ADMIN, TEACHER};
+use exam_service::exam_admin::Exam;
+use exam_service::exam_service::{ChangeKind, ExamResult};
+use exam_service::scheduler::Scheduler;
+use exam_service::server::ExamServiceImpl;
+use exam_service::store::InMemoryExamStore;
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+fn now_ms() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64
+}
+
+#[tokio::test]
+async fn jobs_run_on_their_interval_until_shutdown() {
+    let mut scheduler = Scheduler::new();
+    let runs = Arc::new(AtomicUsize::new(0));
+    let failures = Arc::new(AtomicUsize::new(0));
+
+    let counted = runs.clone();
+    scheduler.every("counting", Duration::from_millis(20), move || {
+        let counted = counted.clone();
+        async move {
+            counted.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    });
+    // A failed run does not stop the job
+    let failed = failures.clone();
+    scheduler.every("failing", Duration::from_millis(20), move || {
+        let failed = failed.clone();
+        async move {
+            failed.fetch_add(1, Ordering::SeqCst);
+            Err(Status::internal("always fails"))
+        }
+    });
+    assert_eq!(scheduler.jobs(), ["counting", "failing"]);
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    scheduler.shutdown(Duration::from_secs(5)).await;
+
+    let after_shutdown = runs.load(Ordering::SeqCst);
+    assert!(after_shutdown >= 2, "ran {} times", after_shutdown);
+    assert!(failures.load(Ordering::SeqCst) >= 2);
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(runs.load(Ordering::SeqCst), after_shutdown);
+}
+
+#[tokio::test]
+async fn shutdown_waits_for_a_run_in_progress() {
+    let mut scheduler = Scheduler::new();
+    let started = Arc::new(AtomicBool::new(false));
+    let finished = Arc::new(AtomicBool::new(false));
+
+    let (was_started, was_finished) = (started.clone(), finished.clone());
+    scheduler.every("slow", Duration::from_millis(10), move || {
+        let (started, finished) = (was_started.clone(), was_finished.clone());
+        async move {
+            started.store(true, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            finished.store(true, Ordering::SeqCst);
+            Ok(())
+        }
+    });
+
+    while !started.load(Ordering::SeqCst) {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    scheduler.shutdown(Duration::from_secs(5)).await;
+    assert!(finished.load(Ordering::SeqCst));
+
+    // But only for so long
+    let mut scheduler = Scheduler::new();
+    scheduler.every("stuck", Duration::from_millis(10), std::future::pending);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    tokio::time::timeout(Duration::from_secs(5), scheduler.shutdown(Duration::from_millis(50)))
+        .await
+        .expect("shutdown gives up on the stuck run");
+}
+
+#[tokio::test]
+async fn drafts_of_long_closed_exams_are_deleted() {
+    let mut config = test_config();
+    config.scheduler.stale_drafts_secs = 1;
+    config.scheduler.stale_draft_days = 1;
+    let server =
+        TestServer::start_with_config(ExamServiceImpl::new(InMemoryExamStore::with_sample_data()), config).await;
+    let admin = server.client(ADMIN).await;
+    let teacher = server.client(TEACHER).await;
+
+    admin
+        .create_exam(Exam {
+            exam_id: "chem101".to_string(),
+            subject: "Chemistry 101".to_string(),
+            total_marks: 100,
+            date: "2024-06-07".to_string(),
+            closes_at_ms: now_ms() - 2 * DAY_MS,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    let result = |student_id: &str| ExamResult {
+        student_id: student_id.to_string(),
+        exam_id: "chem101".to_string(),
+        marks_obtained: 70,
+        ..Default::default()
+    };
+    teacher.submit_result(result("123")).await.unwrap();
+    teacher.publish_results("chem101").await.unwrap();
+    teacher.submit_result(result("456")).await.unwrap();
+    // A draft of an exam with no closing time is never stale
+    teacher
+        .submit_result(ExamResult {
+            exam_id: "math101".to_string(),
+            ..result("123")
+        })
+        .await
+        .unwrap();
+
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while teacher.get_result("456", "chem101").await.is_ok() {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await
+    .expect("the stale draft is deleted");
+
+    let status = teacher.get_result("456", "chem101").await.unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+    assert_eq!(teacher.get_result("123", "chem101").await.unwrap().marks_obtained, 70);
+    assert_eq!(teacher.get_result("123", "math101").await.unwrap().marks_obtained, 70);
+
+    let trail = admin.audit_trail("456", "chem101").await.unwrap();
+    let deleted = trail.last().unwrap();
+    assert_eq!(deleted.action(), ChangeKind::Deleted);
+    assert_eq!(deleted.actor, "scheduler");
+    assert!(deleted.reason.starts_with("stale draft"));
+}
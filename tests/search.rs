@@ -0,0 +1,120 @@
+mod common;
+
+use tokio_stream::StreamExt;
+use tonic::Code;
+use tonic_types::StatusExt;
+
+use common::{TestServer, ADMIN, STUDENT, TEACHER};
+use exam_service::client::{read_mask, ExamClient};
+use exam_service::exam_service::{ExamResult, SearchExamResultsRequest, SearchMatch};
+
+async fn search(client: &ExamClient, query: &str) -> Vec<SearchMatch> {
+    let stream = client.search_results(query, None).await.unwrap();
+    stream.map(Result::unwrap).collect().await
+}
+
+// Each match as "student_id/exam_id:score".
+fn ranked(matches: &[SearchMatch]) -> Vec<String> {
+    matches
+        .iter()
+        .map(|found| {
+            let result = found.result.as_ref().unwrap();
+            format!("{}/{}:{}", result.student_id, result.exam_id, found.score)
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn matches_are_ranked_by_how_well_every_word_matched() {
+    let server = TestServer::start().await;
+    let client = server.client(TEACHER).await;
+
+    // Exact beats prefix, and ties are in key order
+    assert_eq!(ranked(&search(&client, "JANE phys").await), ["456/phy101:5"]);
+    assert_eq!(ranked(&search(&client, "101").await), ["123/math101:3", "456/phy101:3"]);
+    assert_eq!(ranked(&search(&client, "Jo").await), ["123/math101:2"]);
+
+    // One letter off matches long enough words, but never short ones
+    assert_eq!(ranked(&search(&client, "smyth").await), ["456/phy101:1"]);
+    assert_eq!(ranked(&search(&client, "jon").await), Vec::<String>::new());
+
+    // Every word must match something
+    assert_eq!(ranked(&search(&client, "jane math").await), Vec::<String>::new());
+
+    let limited: Vec<_> = client.search_results("101", Some(1)).await.unwrap().collect().await;
+    assert_eq!(limited.len(), 1);
+}
+
+#[tokio::test]
+async fn the_index_follows_writes_and_deletes() {
+    let server = TestServer::start().await;
+    let teacher = server.client(TEACHER).await;
+
+    teacher
+        .submit_result(ExamResult {
+            student_id: "123".to_string(),
+            exam_id: "phy101".to_string(),
+            student_name: "John Doe".to_string(),
+            subject: "Physics 101".to_string(),
+            marks_obtained: 70,
+            total_marks: 100,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(ranked(&search(&teacher, "john physics").await), ["123/phy101:6"]);
+
+    server.client(ADMIN).await.delete_result("123", "phy101", "entered in error").await.unwrap();
+    assert_eq!(ranked(&search(&teacher, "john physics").await), Vec::<String>::new());
+}
+
+#[tokio::test]
+async fn students_only_find_their_own_results_redacted() {
+    let server = TestServer::start().await;
+    let student = server.client(STUDENT).await;
+
+    let matches = search(&student, "101").await;
+    assert_eq!(ranked(&matches), ["123/math101:3"]);
+    assert_eq!(matches[0].result.as_ref().unwrap().internal_comment, "");
+
+    let mut raw = server.raw_client(TEACHER).await;
+    let masked: Vec<_> = raw
+        .search_exam_results(SearchExamResultsRequest {
+            query: "doe".to_string(),
+            read_mask: read_mask(&["grade"]),
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .map(Result::unwrap)
+        .collect()
+        .await;
+    let result = masked[0].result.clone().unwrap();
+    assert_eq!((result.grade.as_str(), result.student_name.as_str()), ("A+", ""));
+}
+
+#[tokio::test]
+async fn queries_without_words_are_rejected() {
+    let server = TestServer::start().await;
+    let mut client = server.raw_client(TEACHER).await;
+
+    let status = client
+        .search_exam_results(SearchExamResultsRequest {
+            query: " - ".to_string(),
+            limit: Some(-1),
+            read_mask: None,
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+
+    let fields: Vec<_> = status
+        .get_details_bad_request()
+        .unwrap()
+        .field_violations
+        .into_iter()
+        .map(|violation| violation.field)
+        .collect();
+    assert_eq!(fields, ["query", "limit"]);
+}
@@ -0,0 +1,189 @@
+mod common;
+
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Code, Streaming};
+
+use common::{test_config, TestServer, ADMIN, OTHER_STUDENT, STUDENT, TEACHER};
+use exam_service::client::ExamClient;
+use exam_service::config::SessionConfig;
+use exam_service::exam_service::ResultStatus;
+use exam_service::exam_session::session_event::Event;
+use exam_service::exam_session::session_request::Request;
+use exam_service::exam_session::{Answer, FinishReason, HandIn, SessionEvent, SessionRequest};
+use exam_service::server::ExamServiceImpl;
+use exam_service::store::InMemoryExamStore;
+
+async fn start(sessions: SessionConfig) -> TestServer {
+    let mut config = test_config();
+    config.sessions = sessions;
+    TestServer::start_with_config(ExamServiceImpl::new(InMemoryExamStore::with_sample_data()), config).await
+}
+
+// One stream of a sitting, answered through `requests`.
+struct Sitting {
+    requests: mpsc::Sender<SessionRequest>,
+    events: Streaming<SessionEvent>,
+}
+
+impl Sitting {
+    async fn open(client: &ExamClient, exam_id: &str) -> Self {
+        let (requests, rx) = mpsc::channel(16);
+        let events = client.take_exam(exam_id, ReceiverStream::new(rx)).await.unwrap();
+        Self { requests, events }
+    }
+
+    async fn answer(&self, question_id: &str, answer: &str) {
+        let answer = Answer {
+            question_id: question_id.to_string(),
+            answer: answer.to_string(),
+        };
+        let request = SessionRequest {
+            request: Some(Request::Answer(answer)),
+        };
+        self.requests.send(request).await.unwrap();
+    }
+
+    async fn hand_in(&self) {
+        let request = SessionRequest {
+            request: Some(Request::HandIn(HandIn {})),
+        };
+        self.requests.send(request).await.unwrap();
+    }
+
+    async fn next(&mut self) -> Event {
+        self.events.message().await.unwrap().unwrap().event.unwrap()
+    }
+
+    // Every event until the stream ends.
+    async fn rest(&mut self) -> Vec<Event> {
+        let mut events = Vec::new();
+        while let Some(event) = self.events.message().await.unwrap() {
+            events.push(event.event.unwrap());
+        }
+        events
+    }
+}
+
+#[tokio::test]
+async fn a_handed_in_sitting_is_graded_and_stored_as_a_draft() {
+    let server = start(SessionConfig::default()).await;
+    let mut sitting = Sitting::open(&server.client(OTHER_STUDENT).await, "math101").await;
+
+    let Event::Started(started) = sitting.next().await else { panic!("sittings open with Started") };
+    assert_eq!((started.student_id.as_str(), started.exam_id.as_str()), ("456", "math101"));
+    assert_eq!(started.question_count, 3);
+    assert!(!started.resumed);
+
+    for expected in ["q1", "q2", "q3"] {
+        let Event::Question(question) = sitting.next().await else { panic!("expected a question") };
+        assert_eq!(question.question_id, expected);
+        assert!(!question.prompt.is_empty());
+    }
+
+    sitting.answer("q1", "4").await;
+    sitting.answer("q2", " 12 ").await;
+    sitting.answer("q3", "3.14").await;
+    sitting.hand_in().await;
+
+    let events = sitting.rest().await;
+    let recorded: Vec<_> = events
+        .iter()
+        .filter_map(|event| match event {
+            Event::AnswerRecorded(recorded) => Some(recorded.answered_count),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(recorded, [1, 2, 3]);
+    let Some(Event::Finished(finished)) = events.last() else { panic!("sittings end with Finished") };
+    assert_eq!(finished.reason(), FinishReason::HandedIn);
+    assert_eq!((finished.answered_count, finished.question_count), (3, 3));
+
+    let result = server.client(TEACHER).await.get_result("456", "math101").await.unwrap();
+    assert_eq!(result.marks_obtained, 70);
+    assert_eq!(result.total_marks, 100);
+    assert_eq!(result.status(), ResultStatus::Draft);
+    assert_eq!(result.questions.len(), 3);
+
+    let trail = server.client(ADMIN).await.audit_trail("456", "math101").await.unwrap();
+    assert_eq!(trail.last().unwrap().role, "student");
+    assert_eq!(trail.last().unwrap().reason, "exam session");
+}
+
+#[tokio::test]
+async fn a_sitting_is_handed_in_when_time_is_up() {
+    let server = start(SessionConfig {
+        duration_secs: 2,
+        countdown_interval_secs: 1,
+    })
+    .await;
+    let mut sitting = Sitting::open(&server.client(OTHER_STUDENT).await, "math101").await;
+    sitting.answer("q1", "4").await;
+
+    // The request stream stays open: the clock alone ends the sitting
+    let events = sitting.rest().await;
+    assert!(events.iter().any(|event| matches!(event, Event::Countdown(_))));
+    let Some(Event::Finished(finished)) = events.last() else { panic!("sittings end with Finished") };
+    assert_eq!(finished.reason(), FinishReason::TimeUp);
+    assert_eq!(finished.answered_count, 1);
+
+    let result = server.client(TEACHER).await.get_result("456", "math101").await.unwrap();
+    assert_eq!(result.marks_obtained, 40);
+}
+
+#[tokio::test]
+async fn a_dropped_sitting_resumes_with_its_answers() {
+    let server = start(SessionConfig::default()).await;
+    let student = server.client(OTHER_STUDENT).await;
+
+    let mut first = Sitting::open(&student, "math101").await;
+    first.answer("q2", "12").await;
+    while !matches!(first.next().await, Event::AnswerRecorded(_)) {}
+    // Cancelled mid-sitting; closing the requests cleanly would hand it in instead
+    let Sitting { requests: _open, events } = first;
+    drop(events);
+
+    let mut second = Sitting::open(&student, "math101").await;
+    let Event::Started(started) = second.next().await else { panic!("sittings open with Started") };
+    assert!(started.resumed);
+    let mut answers = Vec::new();
+    for _ in 0..3 {
+        let Event::Question(question) = second.next().await else { panic!("expected a question") };
+        answers.push(question.answer);
+    }
+    assert_eq!(answers, ["", "12", ""]);
+
+    second.hand_in().await;
+    let Some(Event::Finished(finished)) = second.rest().await.pop() else { panic!("sittings end with Finished") };
+    assert_eq!(finished.answered_count, 1);
+    let result = server.client(TEACHER).await.get_result("456", "math101").await.unwrap();
+    assert_eq!(result.marks_obtained, 30);
+}
+
+#[tokio::test]
+async fn only_students_sit_exams_and_only_once() {
+    let server = start(SessionConfig::default()).await;
+    let opened = |token: &'static str, exam_id: &'static str| {
+        let server = &server;
+        async move {
+            let client = server.client(token).await;
+            let (_requests, rx) = mpsc::channel(1);
+            match client.take_exam(exam_id, ReceiverStream::new(rx)).await {
+                Ok(mut events) => events.message().await.map(drop),
+                Err(status) => Err(status),
+            }
+        }
+    };
+
+    assert_eq!(opened(TEACHER, "math101").await.unwrap_err().code(), Code::PermissionDenied);
+    // Sample student 123 already has a math101 result
+    assert_eq!(opened(STUDENT, "math101").await.unwrap_err().code(), Code::AlreadyExists);
+    // No answer key
+    assert_eq!(opened(OTHER_STUDENT, "phy101").await.unwrap_err().code(), Code::NotFound);
+    assert_eq!(opened(OTHER_STUDENT, "chem999").await.unwrap_err().code(), Code::FailedPrecondition);
+
+    let mut sitting = Sitting::open(&server.client(OTHER_STUDENT).await, "math101").await;
+    sitting.hand_in().await;
+    sitting.rest().await;
+    assert_eq!(opened(OTHER_STUDENT, "math101").await.unwrap_err().code(), Code::AlreadyExists);
+}
@@ -0,0 +1,210 @@
+mod common;
+
+use std::time::Duration;
+use tonic::Code;
+
+use common::{test_config, TestServer, ADMIN, TEACHER};
+use exam_service::appeal::{Appeal, AppealState};
+use exam_service::config::ServerConfig;
+use exam_service::exam_service::{AuditRecord, ExamResult};
+use exam_service::key::ResultKey;
+use exam_service::server::ExamServiceImpl;
+use exam_service::store::{ExamStore, InMemoryExamStore, SqliteExamStore};
+use exam_service::webhook::Webhook;
+
+fn config(dir: &tempfile::TempDir) -> ServerConfig {
+    let mut config = test_config();
+    config.snapshots.dir = dir.path().to_path_buf();
+    config
+}
+
+async fn start_server(dir: &tempfile::TempDir) -> TestServer {
+    TestServer::start_with_config(ExamServiceImpl::new(InMemoryExamStore::with_sample_data()), config(dir)).await
+}
+
+fn result(marks_obtained: i32) -> ExamResult {
+    ExamResult {
+        student_id: "123".to_string(),
+        exam_id: "math101".to_string(),
+        marks_obtained,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn restoring_a_snapshot_undoes_later_writes() {
+    let dir = tempfile::tempdir().unwrap();
+    let server = start_server(&dir).await;
+    let admin = server.client(ADMIN).await;
+    let teacher = server.client(TEACHER).await;
+
+    let snapshot = admin.create_snapshot("before-regrade").await.unwrap();
+    assert_eq!(snapshot.name, "before-regrade");
+    assert!(!snapshot.automatic);
+    assert!(dir.path().join("default/before-regrade.json").exists());
+
+    teacher.submit_result(result(40)).await.unwrap();
+    assert_eq!(admin.audit_trail("123", "math101").await.unwrap().len(), 1);
+
+    let restored = admin.restore_snapshot("before-regrade").await.unwrap();
+    assert_eq!(restored.size_bytes, snapshot.size_bytes);
+
+    // Results, versions and the audit log are back as they were
+    let current = teacher.get_result("123", "math101").await.unwrap();
+    assert_eq!(current.marks_obtained, 95);
+    assert_eq!(current.version, 1);
+    assert!(admin.audit_trail("123", "math101").await.unwrap().is_empty());
+
+    let listed = admin.list_snapshots().await.unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].name, "before-regrade");
+}
+
+#[tokio::test]
+async fn only_admins_take_snapshots_with_valid_new_names() {
+    let dir = tempfile::tempdir().unwrap();
+    let server = start_server(&dir).await;
+    let admin = server.client(ADMIN).await;
+
+    let status = server.client(TEACHER).await.create_snapshot("").await.unwrap_err();
+    assert_eq!(status.code(), Code::PermissionDenied);
+    let status = server.client(TEACHER).await.restore_snapshot("nightly").await.unwrap_err();
+    assert_eq!(status.code(), Code::PermissionDenied);
+
+    for name in ["../escape", ".hidden", "auto-1", "has space"] {
+        let status = admin.create_snapshot(name).await.unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument, "{}", name);
+    }
+
+    let generated = admin.create_snapshot("").await.unwrap();
+    assert!(generated.name.starts_with("snapshot-"));
+
+    admin.create_snapshot("nightly").await.unwrap();
+    let status = admin.create_snapshot("nightly").await.unwrap_err();
+    assert_eq!(status.code(), Code::AlreadyExists);
+
+    let status = admin.restore_snapshot("missing").await.unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+}
+
+#[tokio::test]
+async fn snapshots_from_an_unknown_format_are_refused() {
+    let dir = tempfile::tempdir().unwrap();
+    let server = start_server(&dir).await;
+    let admin = server.client(ADMIN).await;
+
+    std::fs::create_dir_all(dir.path().join("default")).unwrap();
+    std::fs::write(dir.path().join("default/future.json"), r#"{"format_version": 99}"#).unwrap();
+    std::fs::write(dir.path().join("default/garbage.json"), "not json").unwrap();
+
+    for name in ["future", "garbage"] {
+        let status = admin.restore_snapshot(name).await.unwrap_err();
+        assert_eq!(status.code(), Code::FailedPrecondition, "{}", name);
+    }
+
+    // Nothing was replaced
+    assert_eq!(admin.get_result("123", "math101").await.unwrap().marks_obtained, 95);
+}
+
+#[tokio::test]
+async fn automatic_snapshots_are_taken_and_pruned() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = config(&dir);
+    config.snapshots.interval_secs = 1;
+    config.snapshots.keep = 1;
+    let server =
+        TestServer::start_with_config(ExamServiceImpl::new(InMemoryExamStore::with_sample_data()), config).await;
+    let admin = server.client(ADMIN).await;
+    admin.create_snapshot("named").await.unwrap();
+
+    // Wait for a second automatic snapshot to replace the first
+    let mut first = None;
+    let automatic = tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            let automatic: Vec<_> = admin
+                .list_snapshots()
+                .await
+                .unwrap()
+                .into_iter()
+                .filter(|snapshot| snapshot.automatic)
+                .collect();
+
+            match (&first, automatic.first()) {
+                (None, Some(snapshot)) => first = Some(snapshot.name.clone()),
+                (Some(first), Some(snapshot)) if &snapshot.name != first => return automatic,
+                _ => {}
+            }
+        }
+    })
+    .await
+    .expect("a second automatic snapshot");
+
+    assert_eq!(automatic.len(), 1);
+    assert!(automatic[0].name.starts_with("auto-"));
+    // Named snapshots are never pruned
+    assert!(admin.list_snapshots().await.unwrap().iter().any(|snapshot| snapshot.name == "named"));
+}
+
+#[tokio::test]
+async fn sqlite_restores_everything_a_dump_holds() {
+    let source = InMemoryExamStore::with_sample_data();
+    let result = source.get(&ResultKey::from(&result(0))).await.unwrap().unwrap();
+    source.put(ResultKey::from(&result), result).await.unwrap();
+    let deleted = ExamResult {
+        student_id: "456".to_string(),
+        exam_id: "phy101".to_string(),
+        ..Default::default()
+    };
+    source.delete(&ResultKey::from(&deleted), 1_000).await.unwrap().unwrap();
+    source
+        .append_audit(AuditRecord {
+            actor: "teacher".to_string(),
+            student_id: "123".to_string(),
+            exam_id: "math101".to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    source
+        .create_appeal(Appeal {
+            student_id: "123".to_string(),
+            exam_id: "math101".to_string(),
+            state: AppealState::Open as i32,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    source
+        .create_webhook(Webhook {
+            url: "http://example.com/hook".to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    let contents = source.dump().await.unwrap();
+
+    let store = SqliteExamStore::open(":memory:").unwrap();
+    let replaced = store
+        .create_webhook(Webhook {
+            url: "http://example.com/old".to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    store.restore(contents.clone()).await.unwrap();
+    assert_eq!(store.dump().await.unwrap(), contents);
+    assert_eq!(contents.results[0].version, 2);
+    assert_eq!(contents.deleted[0].deleted_at_ms, 1_000);
+
+    // Sequence numbers carry on from the restored log; IDs in use before the restore are not handed out again
+    let appended = store.append_audit(AuditRecord::default()).await.unwrap();
+    assert_eq!(appended.sequence, 2);
+    let webhook = store.create_webhook(Webhook::default()).await.unwrap();
+    assert!(webhook.webhook_id > replaced.webhook_id);
+
+    // And the in-memory store reads its own dump back
+    let copy = InMemoryExamStore::new();
+    copy.restore(contents.clone()).await.unwrap();
+    assert_eq!(copy.dump().await.unwrap(), contents);
+}
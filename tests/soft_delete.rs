@@ -0,0 +1,140 @@
+mod common;
+
+use std::time::Duration;
+use tokio_stream::StreamExt;
+use tonic::Code;
+
+use common::{test_config, TestServer, ADMIN, TEACHER};
+use exam_service::exam_service::{ChangeKind, ExamResult, ListExamResultsRequest};
+use exam_service::key::ResultKey;
+use exam_service::server::ExamServiceImpl;
+use exam_service::store::{ExamStore, InMemoryExamStore, SqliteExamStore, Undelete};
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+fn now_ms() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64
+}
+
+fn key(student_id: &str, exam_id: &str) -> ResultKey {
+    ResultKey::from(&ExamResult {
+        student_id: student_id.to_string(),
+        exam_id: exam_id.to_string(),
+        ..Default::default()
+    })
+}
+
+#[tokio::test]
+async fn deleted_results_are_hidden_until_undeleted() {
+    let server = TestServer::start().await;
+    let admin = server.client(ADMIN).await;
+    let teacher = server.client(TEACHER).await;
+
+    admin.delete_result("123", "math101", "duplicate").await.unwrap();
+    assert_eq!(teacher.get_result("123", "math101").await.unwrap_err().code(), Code::NotFound);
+    let listed: Vec<_> = teacher.list_results(ListExamResultsRequest::default()).await.unwrap().collect().await;
+    assert!(listed.into_iter().map(Result::unwrap).all(|result| result.exam_id != "math101"));
+
+    let deleted = admin.list_deleted_results("123", "").await.unwrap();
+    assert_eq!(deleted.len(), 1);
+    assert_eq!(deleted[0].result.as_ref().unwrap().marks_obtained, 95);
+    assert!(deleted[0].deleted_at_ms > now_ms() - 60_000);
+    assert!(admin.list_deleted_results("456", "").await.unwrap().is_empty());
+    assert_eq!(teacher.list_deleted_results("", "").await.unwrap_err().code(), Code::PermissionDenied);
+
+    let restored = admin.undelete_result("123", "math101", "deleted by mistake").await.unwrap().unwrap();
+    assert_eq!((restored.marks_obtained, restored.version), (95, 2));
+    assert_eq!(teacher.get_result("123", "math101").await.unwrap().grade, "A+");
+    assert!(admin.list_deleted_results("", "").await.unwrap().is_empty());
+
+    let trail = admin.audit_trail("123", "math101").await.unwrap();
+    let actions: Vec<_> = trail.iter().map(|record| record.action()).collect();
+    assert_eq!(actions, [ChangeKind::Deleted, ChangeKind::Restored]);
+    assert_eq!(trail[1].reason, "deleted by mistake");
+
+    let status = admin.undelete_result("123", "math101", "").await.unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+}
+
+#[tokio::test]
+async fn undeleting_never_overwrites_a_newer_result() {
+    let server = TestServer::start().await;
+    let admin = server.client(ADMIN).await;
+
+    admin.delete_result("456", "phy101", "").await.unwrap();
+    server
+        .client(TEACHER)
+        .await
+        .submit_result(ExamResult {
+            student_id: "456".to_string(),
+            exam_id: "phy101".to_string(),
+            marks_obtained: 60,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let status = admin.undelete_result("456", "phy101", "").await.unwrap_err();
+    assert_eq!(status.code(), Code::AlreadyExists);
+    assert_eq!(admin.get_result("456", "phy101").await.unwrap().marks_obtained, 60);
+    assert_eq!(admin.list_deleted_results("", "phy101").await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn results_deleted_before_the_retention_period_are_purged() {
+    let store = InMemoryExamStore::with_sample_data();
+    store.delete(&key("123", "math101"), now_ms() - 2 * DAY_MS).await.unwrap();
+    store.delete(&key("456", "phy101"), now_ms()).await.unwrap();
+
+    let mut config = test_config();
+    config.scheduler.purge_deleted_secs = 1;
+    config.scheduler.deleted_retention_days = 1;
+    let server = TestServer::start_with_config(ExamServiceImpl::new(store), config).await;
+    let admin = server.client(ADMIN).await;
+
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while admin.list_deleted_results("123", "").await.unwrap().len() == 1 {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await
+    .expect("the old deletion is purged");
+
+    // Purged results are gone for good; recent deletions are kept
+    assert_eq!(admin.undelete_result("123", "math101", "").await.unwrap_err().code(), Code::NotFound);
+    assert!(admin.undelete_result("456", "phy101", "").await.is_ok());
+}
+
+#[tokio::test]
+async fn sqlite_keeps_deleted_results_until_purged() {
+    let store = SqliteExamStore::open(":memory:").unwrap();
+    for student_id in ["123", "456"] {
+        let result = ExamResult {
+            student_id: student_id.to_string(),
+            exam_id: "math101".to_string(),
+            marks_obtained: 70,
+            ..Default::default()
+        };
+        store.put(ResultKey::from(&result), result).await.unwrap();
+    }
+
+    store.delete(&key("123", "math101"), 1_000).await.unwrap().unwrap();
+    store.delete(&key("456", "math101"), 3_000).await.unwrap().unwrap();
+    assert!(store.delete(&key("789", "math101"), 3_000).await.unwrap().is_none());
+    assert!(store.list().await.unwrap().is_empty());
+    assert_eq!(store.list_deleted(None, None).await.unwrap().len(), 2);
+
+    let purged = store.purge_deleted(2_000).await.unwrap();
+    assert_eq!(purged[0].result.as_ref().unwrap().student_id, "123");
+    assert!(matches!(store.undelete(&key("123", "math101")).await.unwrap(), Undelete::NotDeleted));
+
+    match store.undelete(&key("456", "math101")).await.unwrap() {
+        Undelete::Restored(result) => assert_eq!((result.marks_obtained, result.version), (70, 2)),
+        outcome => panic!("expected a restore, got {:?}", outcome),
+    }
+    assert_eq!(store.get(&key("456", "math101")).await.unwrap().unwrap().version, 2);
+    assert!(store.list_deleted(None, None).await.unwrap().is_empty());
+}
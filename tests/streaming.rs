@@ -0,0 +1,207 @@
+mod common;
+
+use std::time::Duration;
+use tokio_stream::StreamExt;
+use tonic::Code;
+
+use common::{test_config, TestServer, ADMIN, STUDENT, TEACHER};
+use exam_service::exam_service::{
+    AnswerSubmission, ChangeKind, ExamResult, ExportFormat, GetExamResultResponse, ListExamResultsRequest,
+};
+use exam_service::server::ExamServiceImpl;
+use exam_service::store::InMemoryExamStore;
+
+fn result(student_id: &str, exam_id: &str, marks_obtained: i32) -> ExamResult {
+    ExamResult {
+        student_id: student_id.to_string(),
+        exam_id: exam_id.to_string(),
+        marks_obtained,
+        ..Default::default()
+    }
+}
+
+async fn collect(stream: tonic::Streaming<GetExamResultResponse>) -> Vec<GetExamResultResponse> {
+    stream.map(|item| item.expect("stream item")).collect().await
+}
+
+#[tokio::test]
+async fn streams_a_students_results() {
+    let server = TestServer::start().await;
+    let client = server.client(STUDENT).await;
+
+    let results = collect(client.get_result_stream("123", "").await.unwrap()).await;
+
+    assert!(!results.is_empty());
+    assert!(results.iter().all(|result| result.student_id == "123"));
+}
+
+#[tokio::test]
+async fn lists_results_matching_a_filter() {
+    let server = TestServer::start().await;
+    let client = server.client(ADMIN).await;
+
+    let filter = ListExamResultsRequest {
+        exam_id: "phy101".to_string(),
+        ..Default::default()
+    };
+    let results = collect(client.list_results(filter).await.unwrap()).await;
+    assert!(!results.is_empty());
+    assert!(results.iter().all(|result| result.exam_id == "phy101"));
+
+    let filter = ListExamResultsRequest {
+        min_grade: "Z".to_string(),
+        ..Default::default()
+    };
+    let status = client.list_results(filter).await.unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn bulk_uploads_report_bad_records() {
+    let server = TestServer::start().await;
+    let client = server.client(TEACHER).await;
+
+    let upload = tokio_stream::iter(vec![
+        result("456", "math101", 70),
+        result("456", "math101", 500),
+        result("123", "phy101", 60),
+    ]);
+    let response = client.submit_results(upload).await.unwrap();
+
+    assert_eq!(response.accepted_count, 2);
+    assert_eq!(response.errors.len(), 1);
+    assert_eq!(response.errors[0].index, 1);
+
+    assert_eq!(client.get_result("123", "phy101").await.unwrap().marks_obtained, 60);
+}
+
+#[tokio::test]
+async fn grading_sessions_send_running_totals() {
+    let server = TestServer::start().await;
+    let client = server.client(STUDENT).await;
+
+    let answer = |question_id: &str, answer: &str| AnswerSubmission {
+        exam_id: "math101".to_string(),
+        question_id: question_id.to_string(),
+        answer: answer.to_string(),
+    };
+    let answers = tokio_stream::iter(vec![answer("q1", "4"), answer("q2", "11"), answer("q3", " PI ")]);
+
+    let updates: Vec<_> = client
+        .grade_session(answers)
+        .await
+        .unwrap()
+        .map(|update| update.expect("grade update"))
+        .collect()
+        .await;
+
+    let totals: Vec<_> = updates.iter().map(|update| update.running_total).collect();
+    assert_eq!(totals, [40, 40, 70, 70]);
+    assert!(!updates[1].correct);
+
+    let last = updates.last().unwrap();
+    assert!(last.is_final);
+    assert_eq!(last.max_total, 100);
+    assert_eq!(last.final_grade, "C");
+}
+
+#[tokio::test]
+async fn grading_sessions_reject_repeated_questions() {
+    let server = TestServer::start().await;
+    let client = server.client(STUDENT).await;
+
+    let answer = AnswerSubmission {
+        exam_id: "math101".to_string(),
+        question_id: "q1".to_string(),
+        answer: "4".to_string(),
+    };
+    let answers = tokio_stream::iter(vec![answer.clone(), answer]);
+    let mut updates = client.grade_session(answers).await.unwrap();
+
+    assert_eq!(updates.next().await.unwrap().unwrap().running_total, 40);
+    let status = updates.next().await.unwrap().unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn grading_sessions_reject_unknown_exams() {
+    let server = TestServer::start().await;
+    let client = server.client(STUDENT).await;
+
+    let answers = tokio_stream::iter(vec![AnswerSubmission {
+        exam_id: "chem101".to_string(),
+        question_id: "q1".to_string(),
+        answer: "4".to_string(),
+    }]);
+    let mut updates = client.grade_session(answers).await.unwrap();
+
+    let status = updates.next().await.unwrap().unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+}
+
+#[tokio::test]
+async fn watchers_see_later_writes() {
+    let server = TestServer::start().await;
+    let watcher = server.client(ADMIN).await;
+    let writer = server.client(TEACHER).await;
+
+    let mut changes = watcher.watch("456", "").await.unwrap();
+    writer.submit_result(result("456", "math101", 66)).await.unwrap();
+
+    let change = changes.next().await.unwrap().unwrap();
+    assert_eq!(change.kind(), ChangeKind::Created);
+    let changed = change.result.unwrap();
+    assert_eq!((changed.exam_id.as_str(), changed.marks_obtained), ("math101", 66));
+}
+
+#[tokio::test]
+async fn exports_are_scoped_to_the_caller() {
+    let server = TestServer::start().await;
+
+    let chunks: Vec<_> = server
+        .client(STUDENT)
+        .await
+        .export_results(ListExamResultsRequest::default(), ExportFormat::Csv)
+        .await
+        .unwrap()
+        .map(|chunk| chunk.expect("export chunk").data)
+        .collect()
+        .await;
+    let csv = String::from_utf8(chunks.concat()).unwrap();
+
+    let mut lines = csv.lines();
+    assert!(lines.next().unwrap().starts_with("student_id,exam_id,"));
+    assert!(lines.all(|line| line.starts_with("123,")));
+}
+
+async fn limited_to(max_per_connection: usize) -> TestServer {
+    let mut config = test_config();
+    config.stream.max_per_connection = max_per_connection;
+    let service = ExamServiceImpl::new(InMemoryExamStore::with_sample_data()).with_streams(config.stream.clone());
+    TestServer::start_with_config(service, config).await
+}
+
+#[tokio::test]
+async fn each_connection_has_a_stream_limit() {
+    let server = limited_to(2).await;
+    let client = server.client(ADMIN).await;
+
+    let first = client.watch("123", "").await.unwrap();
+    let _second = client.watch("456", "").await.unwrap();
+    let status = client.watch("123", "").await.unwrap_err();
+    assert_eq!(status.code(), Code::ResourceExhausted);
+
+    // Other connections have limits of their own
+    let other = server.client(ADMIN).await;
+    let _third = other.watch("123", "").await.unwrap();
+
+    // A stream the client drops gives its place back
+    drop(first);
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while client.watch("123", "").await.is_err() {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("the dropped watch is closed");
+}
@@ -0,0 +1,57 @@
+use tonic::Code;
+
+use exam_service::auth::{Identity, Role};
+use exam_service::exam_service::{ExamResult, GetExamResultRequest, SubmitExamResultRequest};
+use exam_service::key::InstitutionId;
+use exam_service::server::ExamServiceImpl;
+use exam_service::store::InMemoryExamStore;
+use exam_service::test_util::stub_client;
+
+fn identity(role: Role, student_id: Option<&str>) -> Identity {
+    Identity {
+        role,
+        student_id: student_id.map(str::to_string),
+        name: "stub".to_string(),
+        institution: InstitutionId::default(),
+        every_institution: false,
+    }
+}
+
+fn request(student_id: &str, exam_id: &str) -> GetExamResultRequest {
+    GetExamResultRequest {
+        student_id: student_id.to_string(),
+        exam_id: exam_id.to_string(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn stub_clients_reach_the_service_in_memory() {
+    let service = ExamServiceImpl::new(InMemoryExamStore::with_sample_data());
+    let mut client = stub_client(service, identity(Role::Teacher, None));
+
+    let result = client.get_exam_result(request("123", "math101")).await.unwrap().into_inner();
+    assert_eq!(result.marks_obtained, 95);
+
+    let submit = SubmitExamResultRequest {
+        result: Some(ExamResult {
+            student_id: "456".to_string(),
+            exam_id: "math101".to_string(),
+            marks_obtained: 70,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    assert!(client.submit_exam_result(submit).await.unwrap().into_inner().created);
+}
+
+#[tokio::test]
+async fn stub_clients_keep_role_checks() {
+    let service = ExamServiceImpl::new(InMemoryExamStore::with_sample_data());
+    let mut client = stub_client(service, identity(Role::Student, Some("123")));
+
+    assert!(client.get_exam_result(request("123", "math101")).await.is_ok());
+
+    let status = client.get_exam_result(request("456", "phy101")).await.unwrap_err();
+    assert_eq!(status.code(), Code::PermissionDenied);
+}
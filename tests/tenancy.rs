@@ -0,0 +1,123 @@
+mod common;
+
+use std::time::Duration;
+use tokio_stream::StreamExt;
+use tonic::Code;
+
+use common::{TestServer, ADMIN, NORTH, NORTH_STUDENT, NORTH_TEACHER, OPERATOR, STUDENT, TEACHER};
+use exam_service::config::StorageConfig;
+use exam_service::exam_service::{ExamResult, SubmitExamResultRequest};
+use exam_service::key::InstitutionId;
+use exam_service::server::ExamServiceImpl;
+use exam_service::store::InMemoryExamStore;
+
+// The default institution and `north`, both seeded with the same sample IDs.
+async fn start_server() -> TestServer {
+    let service = ExamServiceImpl::for_institutions([
+        (InstitutionId::default(), InMemoryExamStore::with_sample_data()),
+        (InstitutionId::parse(NORTH).unwrap(), InMemoryExamStore::with_sample_data()),
+    ]);
+    TestServer::start_with(service).await
+}
+
+fn result(marks_obtained: i32) -> ExamResult {
+    ExamResult {
+        student_id: "123".to_string(),
+        exam_id: "math101".to_string(),
+        marks_obtained,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn institutions_do_not_see_each_others_writes() {
+    let server = start_server().await;
+    let teacher = server.client(TEACHER).await;
+    let north = server.client(NORTH_TEACHER).await;
+
+    north.submit_result(result(40)).await.unwrap();
+    north.publish_results("math101").await.unwrap();
+
+    // Same IDs, separate stores
+    assert_eq!(north.get_result("123", "math101").await.unwrap().marks_obtained, 40);
+    assert_eq!(teacher.get_result("123", "math101").await.unwrap().marks_obtained, 95);
+    assert_eq!(server.client(NORTH_STUDENT).await.get_result("123", "math101").await.unwrap().marks_obtained, 40);
+    assert_eq!(server.client(STUDENT).await.get_result("123", "math101").await.unwrap().marks_obtained, 95);
+
+    // Nor each other's audit logs
+    let trail = server.client(ADMIN).await.audit_trail("123", "math101").await.unwrap();
+    assert!(trail.is_empty());
+    let trail = server.client_in(OPERATOR, NORTH).await.audit_trail("123", "math101").await.unwrap();
+    assert_eq!(trail.len(), 2);
+    assert!(trail.iter().all(|record| record.actor == "north-teacher"));
+}
+
+#[tokio::test]
+async fn scoped_tokens_cannot_name_another_institution() {
+    let server = start_server().await;
+
+    let status = server
+        .client_in(NORTH_TEACHER, "default")
+        .await
+        .get_result("123", "math101")
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::PermissionDenied);
+
+    // Naming its own institution is allowed
+    let north = server.client_in(NORTH_TEACHER, NORTH).await;
+    assert_eq!(north.get_result("123", "math101").await.unwrap().marks_obtained, 95);
+
+    // Operators must say where they act, and only where the server has a store
+    let status = server.client(OPERATOR).await.list_students().await.unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+    let status = server.client_in(OPERATOR, "south").await.list_students().await.unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+}
+
+#[tokio::test]
+async fn watchers_only_see_their_own_institutions_changes() {
+    let server = start_server().await;
+    let teacher = server.client(TEACHER).await;
+    let north = server.client(NORTH_TEACHER).await;
+
+    let mut changes = teacher.watch("123", "").await.unwrap();
+    north.submit_result(result(40)).await.unwrap();
+    teacher.submit_result(result(60)).await.unwrap();
+
+    let change = tokio::time::timeout(Duration::from_secs(5), changes.next())
+        .await
+        .expect("a change")
+        .unwrap()
+        .unwrap();
+    assert_eq!(change.result.unwrap().marks_obtained, 60);
+}
+
+#[tokio::test]
+async fn request_ids_are_scoped_to_their_institution() {
+    let server = start_server().await;
+
+    // The same client-chosen ID in two institutions is two different writes
+    let submit = |marks_obtained| SubmitExamResultRequest {
+        result: Some(result(marks_obtained)),
+        request_id: "shared-id".to_string(),
+    };
+    let stored = server.raw_client(TEACHER).await.submit_exam_result(submit(60)).await.unwrap();
+    let north = server.raw_client(NORTH_TEACHER).await.submit_exam_result(submit(40)).await.unwrap();
+    assert_eq!(stored.into_inner().result.unwrap().marks_obtained, 60);
+    assert_eq!(north.into_inner().result.unwrap().marks_obtained, 40);
+}
+
+#[test]
+fn non_default_institutions_get_their_own_sqlite_file() {
+    let storage = StorageConfig {
+        path: "data/exam.db".into(),
+        ..StorageConfig::default()
+    };
+
+    assert_eq!(storage.path_for(&InstitutionId::default()), storage.path);
+    assert_eq!(
+        storage.path_for(&InstitutionId::parse(NORTH).unwrap()),
+        std::path::Path::new("data/exam.north.db")
+    );
+}
@@ -0,0 +1,27 @@
+use exam_service::auth::{Role, TokenAuth, DEV_TOKEN, DEV_TOKEN_ENV, TOKENS_ENV};
+
+// The only test in this binary: it sets process-wide environment variables.
+#[test]
+fn the_development_token_needs_opting_in_to() {
+    // SAFETY: no other thread of this test binary reads the environment
+    unsafe {
+        std::env::remove_var(TOKENS_ENV);
+        std::env::remove_var(DEV_TOKEN_ENV);
+    }
+    let err = TokenAuth::from_env().unwrap_err();
+    assert!(err.contains(DEV_TOKEN_ENV), "{}", err);
+
+    unsafe { std::env::set_var(DEV_TOKEN_ENV, "1") };
+    let auth = TokenAuth::from_env().unwrap();
+    let identity = auth.identify(Some(&format!("Bearer {}", DEV_TOKEN)), None).unwrap();
+    assert_eq!(identity.role, Role::Admin);
+
+    // Configured tokens replace it, and may end in base64 padding
+    unsafe { std::env::set_var(TOKENS_ENV, "c2VjcmV0cw===admin@ops, dGVhY2hlcg==teacher") };
+    let auth = TokenAuth::from_env().unwrap();
+    let identity = auth.identify(Some("Bearer c2VjcmV0cw=="), None).unwrap();
+    assert_eq!((identity.role, identity.name.as_str()), (Role::Admin, "ops"));
+    let identity = auth.identify(Some("Bearer dGVhY2hlcg="), None).unwrap();
+    assert_eq!(identity.role, Role::Teacher);
+    assert!(auth.identify(Some(&format!("Bearer {}", DEV_TOKEN)), None).is_err());
+}
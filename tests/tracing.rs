@@ -0,0 +1,89 @@
+mod common;
+
+use std::time::Duration;
+use opentelemetry_proto::tonic::collector::trace::v1::trace_service_server::{TraceService, TraceServiceServer};
+use opentelemetry_proto::tonic::collector::trace::v1::{ExportTraceServiceRequest, ExportTraceServiceResponse};
+use opentelemetry_proto::tonic::trace::v1::span::SpanKind;
+use opentelemetry_proto::tonic::trace::v1::Span;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{Request, Response, Status};
+use tracing::Instrument;
+
+use common::{TestServer, TEACHER};
+use exam_service::config::TelemetryConfig;
+use exam_service::telemetry::init_tracing;
+
+// Stands in for an OpenTelemetry collector, handing over every span it receives.
+struct Collector(mpsc::UnboundedSender<Span>);
+
+#[tonic::async_trait]
+impl TraceService for Collector {
+    async fn export(
+        &self,
+        request: Request<ExportTraceServiceRequest>,
+    ) -> Result<Response<ExportTraceServiceResponse>, Status> {
+        let spans = request
+            .into_inner()
+            .resource_spans
+            .into_iter()
+            .flat_map(|resource| resource.scope_spans)
+            .flat_map(|scope| scope.spans);
+        for span in spans {
+            let _ = self.0.send(span);
+        }
+        Ok(Response::new(ExportTraceServiceResponse::default()))
+    }
+}
+
+// The only test in this binary: it installs the global subscriber.
+#[tokio::test(flavor = "multi_thread")]
+async fn client_and_server_spans_share_a_trace() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    let (spans, mut received) = mpsc::unbounded_channel();
+    tokio::spawn(
+        tonic::transport::Server::builder()
+            .add_service(TraceServiceServer::new(Collector(spans)))
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+
+    let tracing = init_tracing(&TelemetryConfig {
+        otlp_endpoint: endpoint,
+        ..TelemetryConfig::default()
+    })
+    .unwrap();
+
+    let server = TestServer::start().await;
+    let client = server.client(TEACHER).await;
+    client
+        .get_result("123", "math101")
+        .instrument(tracing::info_span!("test"))
+        .await
+        .unwrap();
+    server.shutdown().await;
+    tracing.shutdown().await;
+
+    let mut spans = Vec::new();
+    while let Ok(Some(span)) = tokio::time::timeout(Duration::from_secs(5), received.recv()).await {
+        spans.push(span);
+        let seen = |kind: SpanKind| spans.iter().any(|span| span.kind == kind as i32);
+        if seen(SpanKind::Client) && seen(SpanKind::Server) {
+            break;
+        }
+    }
+
+    let find = |kind: SpanKind| {
+        spans
+            .iter()
+            .find(|span| span.kind == kind as i32 && span.name == "exam.v2.ExamService/GetExamResult")
+            .unwrap_or_else(|| panic!("a {:?} span, got {:?}", kind, spans))
+    };
+    let (client, server) = (find(SpanKind::Client), find(SpanKind::Server));
+
+    // The server's span is a child of the client's, by way of `traceparent`
+    assert_eq!(server.trace_id, client.trace_id);
+    assert_eq!(server.parent_span_id, client.span_id);
+    assert!(!client.parent_span_id.is_empty(), "the client span is a child of the caller's");
+}
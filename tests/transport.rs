@@ -0,0 +1,122 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::io;
+use std::net::Ipv4Addr;
+use hyper_util::rt::TokioIo;
+use tokio::net::{TcpListener, UnixStream};
+use tokio::sync::oneshot;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::transport::{Channel, Endpoint, Server, Uri};
+use tonic::Code;
+use tower::service_fn;
+
+use common::{test_config, tokens, TestServer, TEACHER};
+use exam_service::client::{BearerToken, ExamClient};
+use exam_service::exam_service::WatchExamResultsRequest;
+use exam_service::server::{mount, serve_on, ExamServiceImpl};
+use exam_service::store::InMemoryExamStore;
+use exam_service::transport::{bind_unix, Listeners};
+
+fn client(channel: Channel, token: &str) -> ExamClient {
+    ExamClient::with_channel(channel, BearerToken::new(token).unwrap())
+}
+
+async fn tcp_channel(listener: &TcpListener) -> Channel {
+    Endpoint::from_shared(format!("http://{}", listener.local_addr().unwrap()))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn serves_a_unix_socket_alongside_tcp() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("grpc.sock");
+    // A file left behind by a crashed server does not stop the next one binding
+    std::fs::write(&path, b"stale").unwrap();
+
+    let tcp = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+    let tcp_channel = tcp_channel(&tcp).await;
+    let listeners = Listeners::from(tcp).with_unix(bind_unix(&path).unwrap());
+
+    let (shutdown, signal) = oneshot::channel::<()>();
+    let server = tokio::spawn(async move {
+        let service = ExamServiceImpl::new(InMemoryExamStore::with_sample_data());
+        let signal = async {
+            let _ = signal.await;
+        };
+        serve_on(&test_config(), service, tokens(), listeners, signal)
+            .await
+            .map_err(|err| err.to_string())
+    });
+
+    // The URI is never resolved; every connection goes to the socket
+    let socket = path.clone();
+    let unix_channel = Endpoint::from_static("http://unix.invalid")
+        .connect_with_connector(service_fn(move |_: Uri| {
+            let socket = socket.clone();
+            async move { Ok::<_, io::Error>(TokioIo::new(UnixStream::connect(socket).await?)) }
+        }))
+        .await
+        .unwrap();
+
+    for channel in [unix_channel, tcp_channel] {
+        let result = client(channel, TEACHER).get_result("123", "math101").await.unwrap();
+        assert_eq!(result.marks_obtained, 95);
+    }
+
+    shutdown.send(()).unwrap();
+    server.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn mounted_services_are_served_beside_the_embedders_own() {
+    let (_, health_service) = tonic_health::server::health_reporter();
+    let router = Server::builder().add_service(health_service);
+    let service = ExamServiceImpl::new(InMemoryExamStore::with_sample_data());
+    let router = mount(router, service, tokens(), &test_config());
+
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+    let channel = tcp_channel(&listener).await;
+    tokio::spawn(router.serve_with_incoming(TcpListenerStream::new(listener)));
+
+    let result = client(channel.clone(), TEACHER).get_result("456", "phy101").await.unwrap();
+    assert_eq!(result.marks_obtained, 88);
+
+    // Token checks come with the mounted services
+    let status = client(channel, "not-a-token").get_result("456", "phy101").await.unwrap_err();
+    assert_eq!(status.code(), Code::Unauthenticated);
+}
+
+#[tokio::test]
+async fn tuned_connections_carry_several_watch_streams() {
+    let mut config = test_config();
+    config.connections.tcp_keepalive_secs = 5;
+    config.connections.http2_keepalive_interval_secs = 1;
+    config.connections.http2_keepalive_timeout_secs = 1;
+    config.connections.max_concurrent_streams = 8;
+    config.connections.initial_stream_window_size = 1024 * 1024;
+    config.connections.http2_adaptive_window = true;
+    let service = ExamServiceImpl::new(InMemoryExamStore::with_sample_data());
+    let server = TestServer::start_with_config(service, config).await;
+
+    // Every watch shares the one connection; the keepalive PINGs keep it open while they idle
+    let mut raw = server.raw_client(TEACHER).await;
+    let mut watches = Vec::new();
+    for (student_id, exam_id) in [("123", ""), ("456", ""), ("", "phy101")] {
+        let request = WatchExamResultsRequest {
+            student_id: student_id.to_string(),
+            exam_id: exam_id.to_string(),
+        };
+        watches.push(raw.watch_exam_results(request).await.unwrap().into_inner());
+    }
+    tokio::time::sleep(std::time::Duration::from_millis(2500)).await;
+
+    let result = server.client(TEACHER).await.get_result("123", "math101").await.unwrap();
+    assert_eq!(result.marks_obtained, 95);
+    drop(watches);
+    server.shutdown().await;
+}
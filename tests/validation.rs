@@ -0,0 +1,143 @@
+mod common;
+
+use tonic::Code;
+use tonic_types::StatusExt;
+
+use common::{test_config, TestServer, ADMIN, TEACHER};
+use exam_service::config::{LimitsConfig, ServerConfig};
+use exam_service::exam_service::{
+    CorrectExamResultRequest, ExamResult, ListExamResultsPageRequest, QuestionScore, SubmitExamResultRequest,
+};
+use exam_service::server::ExamServiceImpl;
+use exam_service::store::InMemoryExamStore;
+
+fn violations(status: &tonic::Status) -> Vec<(String, String)> {
+    status
+        .get_details_bad_request()
+        .expect("a BadRequest detail")
+        .field_violations
+        .into_iter()
+        .map(|violation| (violation.field, violation.description))
+        .collect()
+}
+
+#[tokio::test]
+async fn every_invalid_field_is_reported() {
+    let server = TestServer::start().await;
+    let mut client = server.raw_client(TEACHER).await;
+
+    let request = CorrectExamResultRequest {
+        student_id: "12 3".to_string(),
+        exam_id: String::new(),
+        marks_obtained: -5,
+        grade: "Q".to_string(),
+        ..Default::default()
+    };
+    let status = client.correct_exam_result(request).await.unwrap_err();
+
+    assert_eq!(status.code(), Code::InvalidArgument);
+    let fields: Vec<_> = violations(&status).into_iter().map(|(field, _)| field).collect();
+    assert_eq!(fields, ["student_id", "exam_id", "grade", "marks_obtained", "expected_version"]);
+    assert!(status.message().contains("exam_id is required"));
+}
+
+#[tokio::test]
+async fn nested_fields_are_named_by_path() {
+    let server = TestServer::start().await;
+    let mut client = server.raw_client(TEACHER).await;
+
+    let question = |marks, max_marks| QuestionScore {
+        question_id: "q".to_string(),
+        marks,
+        max_marks,
+        comment: String::new(),
+    };
+    let request = SubmitExamResultRequest {
+        result: Some(ExamResult {
+            student_id: "456".to_string(),
+            exam_id: "math101".to_string(),
+            questions: vec![question(5, 10), question(12, 10)],
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let status = client.submit_exam_result(request).await.unwrap_err();
+
+    assert_eq!(
+        violations(&status),
+        [(
+            "result.questions[1].marks".to_string(),
+            "must be between 0 and max_marks (10), got 12".to_string()
+        )]
+    );
+}
+
+#[tokio::test]
+async fn bulk_uploads_reject_invalid_records_individually() {
+    let server = TestServer::start().await;
+    let client = server.client(TEACHER).await;
+
+    let upload = tokio_stream::iter(vec![
+        ExamResult {
+            student_id: "456".to_string(),
+            exam_id: "math101".to_string(),
+            marks_obtained: -1,
+            ..Default::default()
+        },
+        ExamResult {
+            student_id: "456".to_string(),
+            exam_id: "math101".to_string(),
+            marks_obtained: 60,
+            ..Default::default()
+        },
+    ]);
+    let response = client.submit_results(upload).await.unwrap();
+
+    assert_eq!(response.accepted_count, 1);
+    assert!(response.errors[0].message.starts_with("marks_obtained"));
+}
+
+#[tokio::test]
+async fn malformed_page_tokens_are_invalid() {
+    let server = TestServer::start().await;
+    let client = server.client(TEACHER).await;
+
+    // "aéb" is four bytes, but its second character spans two of them
+    for token in ["aéb", "abc", "+1+1", "zz"] {
+        let request = ListExamResultsPageRequest {
+            page_token: token.to_string(),
+            ..Default::default()
+        };
+        let status = client.list_results_page(request).await.unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument, "{}", token);
+        assert_eq!(violations(&status)[0].0, "page_token");
+    }
+}
+
+#[tokio::test]
+async fn oversized_messages_are_refused() {
+    let config = ServerConfig {
+        limits: LimitsConfig {
+            max_decoding_message_bytes: 1024,
+            max_encoding_message_bytes: 64,
+        },
+        ..test_config()
+    };
+    let service = ExamServiceImpl::new(InMemoryExamStore::with_sample_data());
+    let server = TestServer::start_with_config(service, config).await;
+    let client = server.client(ADMIN).await;
+
+    let result = ExamResult {
+        student_id: "456".to_string(),
+        exam_id: "math101".to_string(),
+        marks_obtained: 60,
+        internal_comment: "x".repeat(2048),
+        ..Default::default()
+    };
+    let status = client.submit_result(result).await.unwrap_err();
+    assert_eq!(status.code(), Code::OutOfRange);
+
+    // The sample result with its comment encodes to more than 64 bytes
+    let status = client.get_result("123", "math101").await.unwrap_err();
+    assert_eq!(status.code(), Code::OutOfRange);
+}
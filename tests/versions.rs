@@ -0,0 +1,117 @@
+mod common;
+
+use http::uri::PathAndQuery;
+use tokio_stream::StreamExt;
+use tonic::codec::ProstCodec;
+use tonic::service::interceptor::InterceptedService;
+use tonic::Code;
+use tonic_health::pb::health_check_response::ServingStatus;
+use tonic_health::pb::health_client::HealthClient;
+use tonic_health::pb::HealthCheckRequest;
+
+use common::{TestServer, STUDENT, TEACHER};
+use exam_service::client::BearerToken;
+use exam_service::exam_service::v1;
+use exam_service::exam_service::v1::exam_service_client::ExamServiceClient as ExamServiceClientV1;
+
+fn result(exam_id: &str, marks_obtained: i32) -> v1::ExamResult {
+    v1::ExamResult {
+        student_id: "123".to_string(),
+        exam_id: exam_id.to_string(),
+        marks_obtained,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn v1_clients_reach_the_same_results_as_v2() {
+    let server = TestServer::start().await;
+    let mut v1 = ExamServiceClientV1::with_interceptor(server.channel().await, BearerToken::new(TEACHER).unwrap());
+
+    let submitted = v1
+        .submit_exam_result(v1::SubmitExamResultRequest {
+            result: Some(result("math101", 88)),
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(submitted.result.unwrap().marks_obtained, 88);
+
+    // Written through v1, read through v2
+    let read = server.client(TEACHER).await.get_result("123", "math101").await.unwrap();
+    assert_eq!(read.marks_obtained, 88);
+
+    // Client streams are converted record by record
+    let uploads = tokio_stream::iter(vec![result("math101", 90), result("", 10)]);
+    let summary = v1.submit_exam_results(uploads).await.unwrap().into_inner();
+    assert_eq!((summary.accepted_count, summary.rejected_count), (1, 1));
+
+    // And so are server streams
+    let listed: Vec<_> = v1
+        .get_exam_result_stream(v1::GetExamResultRequest {
+            student_id: "123".to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .collect()
+        .await;
+    let math = listed.into_iter().map(Result::unwrap).find(|found| found.exam_id == "math101").unwrap();
+    assert_eq!(math.marks_obtained, 90);
+}
+
+#[tokio::test]
+async fn v1_applies_the_same_authorization() {
+    let server = TestServer::start().await;
+    let mut v1 = ExamServiceClientV1::with_interceptor(server.channel().await, BearerToken::new(STUDENT).unwrap());
+
+    let status = v1
+        .submit_exam_result(v1::SubmitExamResultRequest {
+            result: Some(result("math101", 100)),
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::PermissionDenied);
+}
+
+#[tokio::test]
+async fn unversioned_calls_are_served_by_v1() {
+    let server = TestServer::start().await;
+    let channel = InterceptedService::new(server.channel().await, BearerToken::new(TEACHER).unwrap());
+    let mut grpc = tonic::client::Grpc::new(channel);
+    grpc.ready().await.unwrap();
+
+    // The path clients built before the packages were versioned still call
+    let request = tonic::Request::new(v1::GetExamResultRequest {
+        student_id: "123".to_string(),
+        exam_id: "math101".to_string(),
+    });
+    let response: tonic::Response<v1::GetExamResultResponse> = grpc
+        .unary(
+            request,
+            PathAndQuery::from_static("/exam.ExamService/GetExamResult"),
+            ProstCodec::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.into_inner().exam_id, "math101");
+}
+
+#[tokio::test]
+async fn every_version_reports_its_health() {
+    let server = TestServer::start().await;
+    let mut health = HealthClient::new(server.channel().await);
+
+    for service in ["exam.ExamService", "exam.v1.ExamService", "exam.v2.ExamService"] {
+        let response = health
+            .check(HealthCheckRequest {
+                service: service.to_string(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(response.into_inner().status, ServingStatus::Serving as i32, "{}", service);
+    }
+}
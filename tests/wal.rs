@@ -0,0 +1,198 @@
+use std::io::Write;
+
+use exam_service::appeal::{Appeal, AppealState};
+use exam_service::config::WalConfig;
+use exam_service::exam_admin::Exam;
+use exam_service::exam_service::{AuditRecord, ExamResult};
+use exam_service::grader::{Allocation, ExamGraders};
+use exam_service::key::{ExamId, ResultKey};
+use exam_service::store::{ExamStore, InMemoryExamStore, Undelete, VersionedPut, WalExamStore};
+use exam_service::webhook::Webhook;
+
+fn config(dir: &tempfile::TempDir) -> WalConfig {
+    WalConfig {
+        path: dir.path().join("exam.wal"),
+        ..WalConfig::default()
+    }
+}
+
+fn result(marks_obtained: i32) -> ExamResult {
+    ExamResult {
+        student_id: "123".to_string(),
+        exam_id: "math101".to_string(),
+        marks_obtained,
+        ..Default::default()
+    }
+}
+
+fn lines(config: &WalConfig) -> usize {
+    std::fs::read_to_string(&config.path).unwrap().lines().count()
+}
+
+#[tokio::test]
+async fn every_kind_of_write_survives_a_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = config(&dir);
+    let store = WalExamStore::open(&config.path, &config).await.unwrap();
+    let key = ResultKey::from(&result(0));
+
+    store.put(key.clone(), result(60)).await.unwrap();
+    let outcome = store.put_if_version(key.clone(), 1, result(70)).await.unwrap();
+    assert!(matches!(outcome, VersionedPut::Written(_)));
+    // Conflicts and duplicates change nothing, so are not logged
+    store.put_if_version(key.clone(), 1, result(80)).await.unwrap();
+    let exam = Exam {
+        exam_id: "math101".to_string(),
+        total_marks: 100,
+        ..Default::default()
+    };
+    assert!(store.create_exam(ExamId::from(&exam), exam.clone()).await.unwrap());
+    assert!(!store.create_exam(ExamId::from(&exam), exam).await.unwrap());
+    store
+        .append_audit(AuditRecord {
+            student_id: "123".to_string(),
+            exam_id: "math101".to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    let appeal = store
+        .create_appeal(Appeal {
+            student_id: "123".to_string(),
+            exam_id: "math101".to_string(),
+            state: AppealState::Open as i32,
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    let reviewed = Appeal {
+        state: AppealState::UnderReview as i32,
+        ..appeal
+    };
+    assert!(store.update_appeal(reviewed, AppealState::Open).await.unwrap());
+    let webhook = store.create_webhook(Webhook::default()).await.unwrap();
+    assert!(store.delete_webhook(webhook.webhook_id).await.unwrap());
+    store
+        .set_graders(ExamGraders {
+            exam_id: "math101".to_string(),
+            graders: vec!["teacher".to_string()],
+        })
+        .await
+        .unwrap();
+    store
+        .put_allocations(vec![Allocation {
+            student_id: "123".to_string(),
+            exam_id: "math101".to_string(),
+            grader: "teacher".to_string(),
+            allocated_at_ms: 1_000,
+        }])
+        .await
+        .unwrap();
+    // Deletes are kept until purged, and replayed with their times
+    for (student_id, deleted_at_ms) in [("456", 1_000), ("789", 1_000), ("999", 3_000)] {
+        let other = ExamResult {
+            student_id: student_id.to_string(),
+            ..result(50)
+        };
+        let other_key = ResultKey::from(&other);
+        store.put(other_key.clone(), other).await.unwrap();
+        store.delete(&other_key, deleted_at_ms).await.unwrap();
+    }
+    let restored = ResultKey::from(&ExamResult {
+        student_id: "456".to_string(),
+        ..result(0)
+    });
+    assert!(matches!(store.undelete(&restored).await.unwrap(), Undelete::Restored(_)));
+    assert_eq!(store.purge_deleted(2_000).await.unwrap().len(), 1);
+    // Writes replicated from a peer keep the version the peer gave them
+    let replicated = ExamResult {
+        student_id: "321".to_string(),
+        version: 7,
+        ..result(65)
+    };
+    store.put_at_version(ResultKey::from(&replicated), replicated).await.unwrap();
+
+    let before = store.dump().await.unwrap();
+    store.flush().await.unwrap();
+    drop(store);
+
+    let reopened = WalExamStore::open(&config.path, &config).await.unwrap();
+    assert_eq!(reopened.dump().await.unwrap(), before);
+    assert_eq!(before.results[0].marks_obtained, 70);
+    assert_eq!(before.results[0].version, 2);
+    assert_eq!(before.appeals[0].state(), AppealState::UnderReview);
+    assert_eq!(before.results[1].version, 2);
+    assert_eq!(before.deleted.len(), 1);
+    assert_eq!(before.deleted[0].deleted_at_ms, 3_000);
+    assert_eq!(before.graders[0].graders, ["teacher"]);
+    assert_eq!(before.allocations[0].grader, "teacher");
+
+    // Deleted webhooks' IDs are remembered across restarts
+    let next = reopened.create_webhook(Webhook::default()).await.unwrap();
+    assert!(next.webhook_id > webhook.webhook_id);
+}
+
+#[tokio::test]
+async fn a_write_cut_short_by_a_crash_is_dropped() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = config(&dir);
+    let store = WalExamStore::open(&config.path, &config).await.unwrap();
+    store.put(ResultKey::from(&result(0)), result(60)).await.unwrap();
+    drop(store);
+
+    let mut log = std::fs::OpenOptions::new().append(true).open(&config.path).unwrap();
+    log.write_all(br#"{"put_result":{"student_id":"456","#).unwrap();
+    drop(log);
+
+    let reopened = WalExamStore::open(&config.path, &config).await.unwrap();
+    let results = reopened.list().await.unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].marks_obtained, 60);
+
+    // A damaged entry anywhere else is an error, not silently skipped
+    drop(reopened);
+    let mut log = std::fs::OpenOptions::new().append(true).open(&config.path).unwrap();
+    log.write_all(b"not json\n").unwrap();
+    drop(log);
+    assert!(WalExamStore::open(&config.path, &config).await.is_err());
+}
+
+#[tokio::test]
+async fn the_log_is_compacted_as_it_grows() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = WalConfig {
+        compact_after: 3,
+        ..config(&dir)
+    };
+    let store = WalExamStore::open(&config.path, &config).await.unwrap();
+
+    for marks in [10, 20, 30, 40] {
+        store.put(ResultKey::from(&result(0)), result(marks)).await.unwrap();
+    }
+    // The base, then the one write since it was compacted
+    assert_eq!(lines(&config), 2);
+    drop(store);
+
+    let reopened = WalExamStore::open(&config.path, &config).await.unwrap();
+    let results = reopened.list().await.unwrap();
+    assert_eq!(results[0].marks_obtained, 40);
+    assert_eq!(results[0].version, 4);
+    // Opening compacts too
+    assert_eq!(lines(&config), 1);
+}
+
+#[tokio::test]
+async fn restored_snapshots_are_logged() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = config(&dir);
+    let store = WalExamStore::open(&config.path, &config).await.unwrap();
+    store.put(ResultKey::from(&result(0)), result(60)).await.unwrap();
+
+    let contents = InMemoryExamStore::with_sample_data().dump().await.unwrap();
+    store.restore(contents.clone()).await.unwrap();
+    drop(store);
+
+    let reopened = WalExamStore::open(&config.path, &config).await.unwrap();
+    assert_eq!(reopened.dump().await.unwrap(), contents);
+}
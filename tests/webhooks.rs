@@ -0,0 +1,218 @@
+mod common;
+
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+use axum::Router;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tonic::Code;
+
+use common::{test_config, TestServer, ADMIN, TEACHER};
+use exam_service::exam_service::ExamResult;
+use exam_service::server::ExamServiceImpl;
+use exam_service::store::{ExamStore, InMemoryExamStore, SqliteExamStore};
+use exam_service::webhook::{Webhook, WebhookEvent};
+
+const SECRET: &str = "a-webhook-secret-for-tests";
+
+struct Delivery {
+    headers: HeaderMap,
+    body: serde_json::Value,
+    raw: Vec<u8>,
+}
+
+#[derive(Clone)]
+struct Receiver {
+    deliveries: mpsc::UnboundedSender<Delivery>,
+    // Requests answered with 503 before the receiver starts accepting
+    failures: Arc<AtomicU32>,
+}
+
+async fn receive(State(receiver): State<Receiver>, headers: HeaderMap, body: axum::body::Bytes) -> StatusCode {
+    let failed = receiver
+        .failures
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| left.checked_sub(1))
+        .is_ok();
+
+    let _ = receiver.deliveries.send(Delivery {
+        headers,
+        body: serde_json::from_slice(&body).unwrap(),
+        raw: body.to_vec(),
+    });
+
+    if failed { StatusCode::SERVICE_UNAVAILABLE } else { StatusCode::NO_CONTENT }
+}
+
+// An HTTP endpoint recording every POST, failing the first `failures` of them.
+async fn start_receiver(failures: u32) -> (String, mpsc::UnboundedReceiver<Delivery>) {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+    let addr: SocketAddr = listener.local_addr().unwrap();
+    let (deliveries, received) = mpsc::unbounded_channel();
+    let receiver = Receiver {
+        deliveries,
+        failures: Arc::new(AtomicU32::new(failures)),
+    };
+
+    let app = Router::new().route("/hooks/results", post(receive)).with_state(receiver);
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    (format!("http://{}/hooks/results", addr), received)
+}
+
+async fn start_server() -> TestServer {
+    let mut config = test_config();
+    config.webhooks.initial_backoff_ms = 10;
+    config.webhooks.max_backoff_ms = 20;
+    TestServer::start_with_config(ExamServiceImpl::new(InMemoryExamStore::with_sample_data()), config).await
+}
+
+async fn next(received: &mut mpsc::UnboundedReceiver<Delivery>) -> Delivery {
+    tokio::time::timeout(Duration::from_secs(5), received.recv())
+        .await
+        .expect("webhook delivery")
+        .unwrap()
+}
+
+fn header<'a>(delivery: &'a Delivery, name: &str) -> &'a str {
+    delivery.headers.get(name).unwrap().to_str().unwrap()
+}
+
+fn draft() -> ExamResult {
+    ExamResult {
+        student_id: "123".to_string(),
+        exam_id: "phy101".to_string(),
+        marks_obtained: 70,
+        internal_comment: "Borderline, second-marked".to_string(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn publishing_posts_a_signed_event() {
+    let server = start_server().await;
+    let (url, mut received) = start_receiver(0).await;
+    let admin = server.client(ADMIN).await;
+    let teacher = server.client(TEACHER).await;
+
+    admin.register_webhook(&url, &[], SECRET).await.unwrap();
+    teacher.submit_result(draft()).await.unwrap();
+    teacher.publish_results("phy101").await.unwrap();
+
+    let delivery = next(&mut received).await;
+    assert_eq!(header(&delivery, "x-exam-event"), "RESULT_PUBLISHED");
+    assert_eq!(delivery.body["event"], "RESULT_PUBLISHED");
+    assert_eq!(delivery.body["result"]["student_id"], "123");
+    assert_eq!(delivery.body["result"]["marks_obtained"], 70);
+    assert!(delivery.body["result"].get("internal_comment").is_none());
+
+    // HMAC-SHA256 over "<timestamp>.<body>", hex-encoded
+    let signed = [header(&delivery, "x-exam-timestamp").as_bytes(), b".", &delivery.raw].concat();
+    let key = ring::hmac::Key::new(ring::hmac::HMAC_SHA256, SECRET.as_bytes());
+    let expected: String = ring::hmac::sign(&key, &signed)
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    assert_eq!(header(&delivery, "x-exam-signature"), format!("sha256={}", expected));
+}
+
+#[tokio::test]
+async fn failed_deliveries_are_retried_with_the_same_event_id() {
+    let server = start_server().await;
+    let (url, mut received) = start_receiver(2).await;
+    let teacher = server.client(TEACHER).await;
+
+    server.client(ADMIN).await.register_webhook(&url, &[], SECRET).await.unwrap();
+    teacher.submit_result(draft()).await.unwrap();
+    teacher.publish_results("phy101").await.unwrap();
+
+    let attempts = [next(&mut received).await, next(&mut received).await, next(&mut received).await];
+    let event_id = header(&attempts[0], "x-exam-event-id");
+    assert!(attempts.iter().all(|attempt| header(attempt, "x-exam-event-id") == event_id));
+    assert_eq!(attempts[2].body["event_id"], event_id);
+
+    // Accepted on the third attempt, so nothing more is sent
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(received.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn webhooks_only_get_the_events_they_subscribe_to() {
+    let server = start_server().await;
+    let (url, mut received) = start_receiver(0).await;
+    let teacher = server.client(TEACHER).await;
+
+    server
+        .client(ADMIN)
+        .await
+        .register_webhook(&url, &[WebhookEvent::ResultCorrected], SECRET)
+        .await
+        .unwrap();
+
+    // Neither a publication nor a correction of a draft is sent
+    let submitted = teacher.submit_result(draft()).await.unwrap().result.unwrap();
+    teacher.correct_result("123", "phy101", 72, submitted.version, "Recount").await.unwrap();
+    teacher.publish_results("phy101").await.unwrap();
+
+    let published = teacher.get_result("123", "math101").await.unwrap();
+    teacher.correct_result("123", "math101", 90, published.version, "Recount").await.unwrap();
+
+    let delivery = next(&mut received).await;
+    assert_eq!(delivery.body["event"], "RESULT_CORRECTED");
+    assert_eq!(delivery.body["result"]["exam_id"], "math101");
+    assert_eq!(delivery.body["result"]["marks_obtained"], 90);
+}
+
+#[tokio::test]
+async fn only_admins_manage_webhooks_and_secrets_are_shown_once() {
+    let server = start_server().await;
+    let admin = server.client(ADMIN).await;
+
+    let status = server.client(TEACHER).await.list_webhooks().await.unwrap_err();
+    assert_eq!(status.code(), Code::PermissionDenied);
+
+    for (url, secret, code) in [
+        ("ftp://example.com/hook", "", Code::InvalidArgument),
+        ("not a url", "", Code::InvalidArgument),
+        ("http://example.com/hook", "short", Code::InvalidArgument),
+        ("https://example.com/hook", "", Code::FailedPrecondition),
+    ] {
+        let status = admin.register_webhook(url, &[], secret).await.unwrap_err();
+        assert_eq!(status.code(), code, "{}", url);
+    }
+
+    let webhook = admin.register_webhook("http://example.com/hook", &[], "").await.unwrap();
+    assert_eq!(webhook.secret.len(), 64);
+    assert_eq!(webhook.created_by, "admin");
+
+    let listed = admin.list_webhooks().await.unwrap();
+    assert_eq!(listed.len(), 1);
+    assert!(listed[0].secret.is_empty());
+
+    admin.delete_webhook(webhook.webhook_id).await.unwrap();
+    let status = admin.delete_webhook(webhook.webhook_id).await.unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+}
+
+#[tokio::test]
+async fn sqlite_webhook_ids_are_not_reused() {
+    let store = SqliteExamStore::open(":memory:").unwrap();
+    let webhook = Webhook {
+        url: "http://example.com/hook".to_string(),
+        events: vec![WebhookEvent::ResultPublished as i32],
+        secret: SECRET.to_string(),
+        ..Default::default()
+    };
+
+    let first = store.create_webhook(webhook.clone()).await.unwrap();
+    assert!(store.delete_webhook(first.webhook_id).await.unwrap());
+    let second = store.create_webhook(webhook).await.unwrap();
+    assert!(second.webhook_id > first.webhook_id);
+
+    assert_eq!(store.list_webhooks().await.unwrap(), vec![second]);
+}